@@ -0,0 +1,15 @@
+//! solana-amm 的链下模拟器
+//!
+//! 在调用 `update_config` 修改手续费之前，可以先用历史成交日志回放，
+//! 比较不同费率下 LP 的收益、协议收入、无常损失以及 TWAP 序列。
+//!
+//! 所有状态转移都直接调用链上程序的 `math` 模块和 `PoolState`，
+//! 因此模拟结果与链上 handler 逐个 lamport 对齐（见 `tests/parity.rs`）。
+
+pub mod log;
+pub mod pool;
+pub mod replay;
+
+pub use log::{Action, ActionKind, LogError, TradeRecord};
+pub use pool::{SimPool, SwapOutcome};
+pub use replay::{replay, ReplayConfig, ReplayFailure, ReplayReport, TwapPoint};
@@ -0,0 +1,170 @@
+//! 成交日志的读取
+//!
+//! CSV 和 JSON 共用同一套扁平的字段：
+//!
+//! ```text
+//! timestamp,action,amount_a,amount_b,amount_lp,share
+//! 1700000000,add_liquidity,1000000,2000000,,
+//! 1700000060,swap,5000,,,
+//! 1700000120,swap,,7000,,
+//! 1700000180,remove_liquidity,,,10000,
+//! 1700000240,set_protocol_fee_share,,,,5
+//! ```
+//!
+//! `swap` 只填 `amount_a` 表示 A -> B，只填 `amount_b` 表示 B -> A。
+//! JSON 日志是同样字段组成的数组，缺省字段可以省略。
+
+use std::fmt;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+/// 日志中的操作类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ActionKind {
+    Swap,
+    AddLiquidity,
+    RemoveLiquidity,
+    SetProtocolFeeShare,
+}
+
+/// 日志中的一行
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TradeRecord {
+    pub timestamp: u64,
+    pub action: ActionKind,
+    #[serde(default)]
+    pub amount_a: Option<u64>,
+    #[serde(default)]
+    pub amount_b: Option<u64>,
+    #[serde(default)]
+    pub amount_lp: Option<u64>,
+    #[serde(default)]
+    pub share: Option<u64>,
+}
+
+/// 校验过字段组合之后的操作
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Swap { amount_in: u64, is_a_to_b: bool },
+    AddLiquidity { amount_a: u64, amount_b: u64 },
+    RemoveLiquidity { amount_lp: u64 },
+    SetProtocolFeeShare { share: u64 },
+}
+
+#[derive(Debug)]
+pub enum LogError {
+    Io(std::io::Error),
+    Csv(csv::Error),
+    Json(serde_json::Error),
+    /// 第 `index` 条记录（从 0 开始）字段不合法
+    InvalidRecord { index: usize, reason: &'static str },
+    /// 无法从文件扩展名判断日志格式
+    UnknownFormat,
+}
+
+impl fmt::Display for LogError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LogError::Io(e) => write!(f, "io error: {e}"),
+            LogError::Csv(e) => write!(f, "csv error: {e}"),
+            LogError::Json(e) => write!(f, "json error: {e}"),
+            LogError::InvalidRecord { index, reason } => {
+                write!(f, "invalid record #{index}: {reason}")
+            }
+            LogError::UnknownFormat => write!(f, "unknown log format, expected .csv or .json"),
+        }
+    }
+}
+
+impl std::error::Error for LogError {}
+
+impl From<std::io::Error> for LogError {
+    fn from(e: std::io::Error) -> Self {
+        LogError::Io(e)
+    }
+}
+
+impl From<csv::Error> for LogError {
+    fn from(e: csv::Error) -> Self {
+        LogError::Csv(e)
+    }
+}
+
+impl From<serde_json::Error> for LogError {
+    fn from(e: serde_json::Error) -> Self {
+        LogError::Json(e)
+    }
+}
+
+impl TradeRecord {
+    /// 根据 `action` 检查字段组合，得到可执行的操作
+    pub fn to_action(&self, index: usize) -> Result<Action, LogError> {
+        let invalid = |reason| LogError::InvalidRecord { index, reason };
+        match self.action {
+            ActionKind::Swap => match (self.amount_a, self.amount_b) {
+                (Some(amount_in), None) => Ok(Action::Swap { amount_in, is_a_to_b: true }),
+                (None, Some(amount_in)) => Ok(Action::Swap { amount_in, is_a_to_b: false }),
+                _ => Err(invalid("swap needs exactly one of amount_a / amount_b")),
+            },
+            ActionKind::AddLiquidity => match (self.amount_a, self.amount_b) {
+                (Some(amount_a), Some(amount_b)) => Ok(Action::AddLiquidity { amount_a, amount_b }),
+                _ => Err(invalid("add_liquidity needs amount_a and amount_b")),
+            },
+            ActionKind::RemoveLiquidity => self
+                .amount_lp
+                .map(|amount_lp| Action::RemoveLiquidity { amount_lp })
+                .ok_or_else(|| invalid("remove_liquidity needs amount_lp")),
+            ActionKind::SetProtocolFeeShare => self
+                .share
+                .map(|share| Action::SetProtocolFeeShare { share })
+                .ok_or_else(|| invalid("set_protocol_fee_share needs share")),
+        }
+    }
+}
+
+/// 读取 CSV 日志（第一行为表头）
+pub fn read_csv<R: Read>(reader: R) -> Result<Vec<TradeRecord>, LogError> {
+    let mut reader = csv::ReaderBuilder::new().trim(csv::Trim::All).from_reader(reader);
+    let records = reader
+        .deserialize()
+        .collect::<Result<Vec<TradeRecord>, csv::Error>>()?;
+    validate(&records)?;
+    Ok(records)
+}
+
+/// 读取 JSON 日志（记录数组）
+pub fn read_json<R: Read>(reader: R) -> Result<Vec<TradeRecord>, LogError> {
+    let records: Vec<TradeRecord> = serde_json::from_reader(reader)?;
+    validate(&records)?;
+    Ok(records)
+}
+
+/// 按扩展名（`.csv` / `.json`）读取日志文件
+pub fn read_path<P: AsRef<Path>>(path: P) -> Result<Vec<TradeRecord>, LogError> {
+    let path = path.as_ref();
+    let file = File::open(path)?;
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("csv") => read_csv(file),
+        Some("json") => read_json(file),
+        _ => Err(LogError::UnknownFormat),
+    }
+}
+
+// 链上的 Clock 单调递增，回放时也要求时间戳不回退，
+// 否则 update_twap 会把 block_timestamp_last 往回拨
+fn validate(records: &[TradeRecord]) -> Result<(), LogError> {
+    for (index, record) in records.iter().enumerate() {
+        record.to_action(index)?;
+        if index > 0 && record.timestamp < records[index - 1].timestamp {
+            return Err(LogError::InvalidRecord {
+                index,
+                reason: "timestamps must be non-decreasing",
+            });
+        }
+    }
+    Ok(())
+}
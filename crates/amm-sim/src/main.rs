@@ -0,0 +1,73 @@
+// 命令行入口：回放成交日志并输出 JSON 报告
+//
+// 用法：
+//   amm-sim <trades.csv|trades.json> [--fee <numerator>/<denominator>] [--protocol-fee-share <share>] [--max-price-deviation-bps <bps>]
+
+use std::process::ExitCode;
+
+use amm_sim::{log, replay, ReplayConfig};
+
+const USAGE: &str = "usage: amm-sim <trades.csv|trades.json> [--fee <numerator>/<denominator>] [--protocol-fee-share <share>] [--max-price-deviation-bps <bps>]";
+
+fn parse_args(args: &[String]) -> Result<(String, ReplayConfig), String> {
+    let mut path = None;
+    let mut config = ReplayConfig::default();
+    let mut iter = args.iter();
+
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--fee" => {
+                let value = iter.next().ok_or("--fee needs a value")?;
+                let (numerator, denominator) =
+                    value.split_once('/').ok_or("--fee expects <numerator>/<denominator>")?;
+                config.fee_numerator = numerator.parse().map_err(|_| "invalid fee numerator")?;
+                config.fee_denominator =
+                    denominator.parse().map_err(|_| "invalid fee denominator")?;
+            }
+            "--protocol-fee-share" => {
+                let value = iter.next().ok_or("--protocol-fee-share needs a value")?;
+                config.protocol_fee_share =
+                    value.parse().map_err(|_| "invalid protocol fee share")?;
+            }
+            "--max-price-deviation-bps" => {
+                let value = iter.next().ok_or("--max-price-deviation-bps needs a value")?;
+                config.max_price_deviation_bps =
+                    value.parse().map_err(|_| "invalid max price deviation")?;
+            }
+            _ if path.is_none() && !arg.starts_with("--") => path = Some(arg.clone()),
+            _ => return Err(format!("unexpected argument: {arg}")),
+        }
+    }
+
+    Ok((path.ok_or("missing trade log path")?, config))
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let (path, config) = match parse_args(&args) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            eprintln!("{e}\n{USAGE}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let records = match log::read_path(&path) {
+        Ok(records) => records,
+        Err(e) => {
+            eprintln!("failed to read {path}: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    match replay(&records, config) {
+        Ok(report) => {
+            println!("{}", serde_json::to_string_pretty(&report).expect("report is serializable"));
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("invalid pool config: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,248 @@
+//! 池子模型：`PoolState` + 两个 vault 余额 + LP 供应量
+//!
+//! 每个方法对应一个链上指令，兑换和存取流动性直接调用 handler 使用的
+//! `pool_swap` / `pool_liquidity` 中的纯函数，CPI 转账则被替换成余额的加减。
+
+use anchor_lang::prelude::Pubkey;
+use solana_amm::math;
+use solana_amm::oracle;
+use solana_amm::pool_liquidity::{self, PoolDeposit, PoolWithdrawal};
+use solana_amm::pool_swap::{self, PoolSwap};
+use solana_amm::{
+    AmmError, OracleObservation, PoolState, CURRENT_POOL_VERSION, OBSERVATION_COUNT, POOL_RESERVED_BYTES,
+};
+
+/// 一次兑换的结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SwapOutcome {
+    /// 扣除手续费后的有效输入
+    pub amount_in_effective: u64,
+    pub amount_out: u64,
+}
+
+/// 链下模拟的池子
+///
+/// 指令失败时状态保持不变，和链上交易回滚的语义一致。
+#[derive(Clone)]
+pub struct SimPool {
+    pub state: PoolState,
+    /// token_a_vault 余额
+    pub reserve_a: u64,
+    /// token_b_vault 余额
+    pub reserve_b: u64,
+    /// lp_mint 的总供应量
+    pub lp_supply: u64,
+    /// 流动性提供者持有的 LP（不含黑洞地址和协议方）
+    pub provider_lp: u64,
+    /// 协议方累计收到的 LP
+    pub protocol_lp: u64,
+}
+
+impl SimPool {
+    /// 对应 `initialize`：创建一个空池子
+    pub fn new(fee_numerator: u64, fee_denominator: u64, timestamp: u64) -> Result<Self, AmmError> {
+        if fee_denominator == 0 || fee_numerator >= fee_denominator {
+            return Err(AmmError::InvalidFee);
+        }
+
+        let mut state = PoolState {
+            token_a: Pubkey::default(),
+            token_b: Pubkey::default(),
+            token_a_vault: Pubkey::default(),
+            token_b_vault: Pubkey::default(),
+            lp_mint: Pubkey::default(),
+            fee_numerator,
+            fee_denominator,
+            pool_bump: 0,
+            auth_bump: 0,
+            block_timestamp_last: timestamp,
+            price_a_cumulative_last: 0,
+            price_b_cumulative_last: 0,
+            admin: Pubkey::default(),
+            pending_admin: Pubkey::default(),
+            protocol_fee_recipient: Pubkey::default(),
+            protocol_fee_share: 0,
+            k_last: 0,
+            log_price_cumulative_last: 0,
+            observation_index: 0,
+            observation_count: 0,
+            observations: [OracleObservation::default(); OBSERVATION_COUNT],
+            max_price_deviation_bps: 0,
+            max_referral_fee_bps: 0,
+            version: CURRENT_POOL_VERSION,
+            batch_mode: 0,
+            registered: 0,
+            permissioned: 0,
+            padding: [0; 4],
+            twamm: Pubkey::default(),
+            max_total_reserve_a: 0,
+            max_total_reserve_b: 0,
+            max_deposit_a: 0,
+            max_deposit_b: 0,
+            reserved: [0; POOL_RESERVED_BYTES],
+        };
+        // 和链上 initialize 一样写入第一条观测
+        math::record_observation(&mut state, timestamp);
+
+        Ok(Self {
+            state,
+            reserve_a: 0,
+            reserve_b: 0,
+            lp_supply: 0,
+            provider_lp: 0,
+            protocol_lp: 0,
+        })
+    }
+
+    /// 对应 `update_config` 中的 `new_share`
+    pub fn set_protocol_fee_share(&mut self, share: u64) -> Result<(), AmmError> {
+        if share > 500 {
+            return Err(AmmError::InvalidFeeConfig);
+        }
+        self.state.protocol_fee_share = share;
+        Ok(())
+    }
+
+    /// 对应 `update_config` 中的 `new_max_price_deviation_bps`
+    pub fn set_max_price_deviation_bps(&mut self, bps: u16) -> Result<(), AmmError> {
+        if bps as u128 > oracle::BPS_DENOMINATOR {
+            return Err(AmmError::InvalidPriceDeviationConfig);
+        }
+        self.state.max_price_deviation_bps = bps;
+        Ok(())
+    }
+
+    /// 对应 `set_deposit_caps`，0 表示不限制
+    pub fn set_deposit_caps(
+        &mut self,
+        max_total_reserve_a: u64,
+        max_total_reserve_b: u64,
+        max_deposit_a: u64,
+        max_deposit_b: u64,
+    ) {
+        self.state.max_total_reserve_a = max_total_reserve_a;
+        self.state.max_total_reserve_b = max_total_reserve_b;
+        self.state.max_deposit_a = max_deposit_a;
+        self.state.max_deposit_b = max_deposit_b;
+    }
+
+    /// 对应 `swap`
+    pub fn swap(
+        &mut self,
+        amount_in: u64,
+        is_a_to_b: bool,
+        min_amount_out: u64,
+        timestamp: u64,
+    ) -> Result<SwapOutcome, AmmError> {
+        let mut next = self.clone();
+
+        // 和链上 swap 共用同一份成交和检查逻辑
+        let fee_numerator = next.state.fee_numerator;
+        let PoolSwap { amount_in_effective, amount_out, reserve_a_after, reserve_b_after } =
+            pool_swap::execute_pool_swap(
+                &mut next.state,
+                next.reserve_a,
+                next.reserve_b,
+                amount_in,
+                is_a_to_b,
+                fee_numerator,
+                min_amount_out,
+                timestamp,
+            )?;
+        next.reserve_a = reserve_a_after;
+        next.reserve_b = reserve_b_after;
+
+        *self = next;
+        Ok(SwapOutcome {
+            amount_in_effective,
+            amount_out,
+        })
+    }
+
+    /// 对应 `add_liquidity`，返回用户获得的 LP 数量
+    pub fn add_liquidity(
+        &mut self,
+        amount_a: u64,
+        amount_b: u64,
+        timestamp: u64,
+    ) -> Result<u64, AmmError> {
+        let mut next = self.clone();
+
+        // 和链上 add_liquidity 共用同一份计算
+        let PoolDeposit { protocol_fee_lp, locked_lp, liquidity, reserve_a_after, reserve_b_after } =
+            pool_liquidity::execute_add_liquidity(
+                &mut next.state,
+                next.reserve_a,
+                next.reserve_b,
+                next.lp_supply,
+                amount_a,
+                amount_b,
+                timestamp,
+            )?;
+        // 协议方和黑洞地址的 LP 也计入供应量
+        next.mint_protocol_fee(protocol_fee_lp)?;
+        next.lp_supply = next
+            .lp_supply
+            .checked_add(locked_lp)
+            .and_then(|supply| supply.checked_add(liquidity))
+            .ok_or(AmmError::MathOverflow)?;
+        next.provider_lp = next.provider_lp.checked_add(liquidity).ok_or(AmmError::MathOverflow)?;
+        next.reserve_a = reserve_a_after;
+        next.reserve_b = reserve_b_after;
+
+        *self = next;
+        Ok(liquidity)
+    }
+
+    /// 对应 `remove_liquidity`，返回用户取回的 (token_a, token_b)
+    pub fn remove_liquidity(
+        &mut self,
+        amount_lp: u64,
+        min_amount_a: u64,
+        min_amount_b: u64,
+        timestamp: u64,
+    ) -> Result<(u64, u64), AmmError> {
+        // 链上检查的是用户 LP 余额，这里用所有流动性提供者的 LP 之和近似
+        if amount_lp > self.provider_lp {
+            return Err(AmmError::InvalidLpMint);
+        }
+
+        let mut next = self.clone();
+
+        // 和链上 remove_liquidity 共用同一份计算
+        let PoolWithdrawal { protocol_fee_lp, amount_a, amount_b, reserve_a_after, reserve_b_after } =
+            pool_liquidity::execute_remove_liquidity(
+                &mut next.state,
+                next.reserve_a,
+                next.reserve_b,
+                next.lp_supply,
+                amount_lp,
+                min_amount_a,
+                min_amount_b,
+                timestamp,
+            )?;
+        next.mint_protocol_fee(protocol_fee_lp)?;
+        next.lp_supply = next.lp_supply.checked_sub(amount_lp).ok_or(AmmError::MathOverflow)?;
+        next.provider_lp -= amount_lp;
+        next.reserve_a = reserve_a_after;
+        next.reserve_b = reserve_b_after;
+
+        *self = next;
+        Ok((amount_a, amount_b))
+    }
+
+    /// B 计价的 A 现货价格
+    pub fn spot_price_a(&self) -> Option<f64> {
+        if self.reserve_a == 0 {
+            return None;
+        }
+        Some(self.reserve_b as f64 / self.reserve_a as f64)
+    }
+
+    // 给协议方增发的 LP 计入供应量
+    fn mint_protocol_fee(&mut self, amount: u64) -> Result<(), AmmError> {
+        self.lp_supply = self.lp_supply.checked_add(amount).ok_or(AmmError::MathOverflow)?;
+        self.protocol_lp = self.protocol_lp.checked_add(amount).ok_or(AmmError::MathOverflow)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,214 @@
+//! 回放成交日志并生成报告
+
+use serde::Serialize;
+use solana_amm::oracle::{self, Observation};
+use solana_amm::AmmError;
+
+use crate::log::{Action, TradeRecord};
+use crate::pool::SimPool;
+
+// Q64.64 定点数转浮点
+const Q64: f64 = 18_446_744_073_709_551_616.0;
+
+/// 回放使用的池子参数，也就是准备通过 `update_config` 设置的值
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReplayConfig {
+    pub fee_numerator: u64,
+    pub fee_denominator: u64,
+    /// 初始协议分成，日志中的 `set_protocol_fee_share` 会覆盖它
+    pub protocol_fee_share: u64,
+    /// swap 后价格相对 TWAP 的最大偏离（bps），0 表示不限制；超出的交易记为失败
+    pub max_price_deviation_bps: u16,
+}
+
+impl Default for ReplayConfig {
+    fn default() -> Self {
+        Self {
+            fee_numerator: 3,
+            fee_denominator: 1000,
+            protocol_fee_share: 0,
+            max_price_deviation_bps: 0,
+        }
+    }
+}
+
+/// 回放中失败的记录，对应链上会被回滚的交易
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ReplayFailure {
+    pub index: usize,
+    pub timestamp: u64,
+    pub error: String,
+}
+
+/// 两次池子操作之间的 TWAP
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TwapPoint {
+    pub start: u64,
+    pub end: u64,
+    /// B 计价的 A 的时间加权均价
+    pub price_a: f64,
+    /// A 计价的 B 的时间加权均价
+    pub price_b: f64,
+}
+
+/// 回放报告
+///
+/// 收益类指标都以"每单位 LP"计算，参照点为第一次成功添加流动性之后的池子状态：
+/// * `lp_return` - 持有 LP 相对于直接持有等量代币的收益（包含手续费和无常损失）
+/// * `fee_growth` - 每单位 LP 对应的 sqrt(k) 增长，即手续费带来的收益
+/// * `impermanent_loss` - 只由价格变化导致的无常损失 2*sqrt(r)/(1+r) - 1
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ReplayReport {
+    pub records: usize,
+    pub failures: Vec<ReplayFailure>,
+    pub swap_count: u64,
+    pub volume_a: u64,
+    pub volume_b: u64,
+    pub fees_a: u64,
+    pub fees_b: u64,
+    pub final_reserve_a: u64,
+    pub final_reserve_b: u64,
+    pub final_lp_supply: u64,
+    pub protocol_lp_minted: u64,
+    /// 协议方 LP 在回放结束时对应的 token A
+    pub protocol_revenue_a: u64,
+    /// 协议方 LP 在回放结束时对应的 token B
+    pub protocol_revenue_b: u64,
+    pub lp_return: Option<f64>,
+    pub fee_growth: Option<f64>,
+    pub impermanent_loss: Option<f64>,
+    pub twap: Vec<TwapPoint>,
+}
+
+// 第一次添加流动性后的参照点（每单位 LP 对应的储备）
+struct Baseline {
+    a_per_lp: f64,
+    b_per_lp: f64,
+    price_a: f64,
+}
+
+/// 在给定参数下回放日志
+///
+/// 池子在第一条记录的时间戳创建；失败的记录不改变池子状态，只记入 `failures`。
+pub fn replay(records: &[TradeRecord], config: ReplayConfig) -> Result<ReplayReport, AmmError> {
+    let start = records.first().map(|r| r.timestamp).unwrap_or(0);
+    let mut pool = SimPool::new(config.fee_numerator, config.fee_denominator, start)?;
+    pool.set_protocol_fee_share(config.protocol_fee_share)?;
+    pool.set_max_price_deviation_bps(config.max_price_deviation_bps)?;
+
+    let mut report = ReplayReport {
+        records: records.len(),
+        failures: Vec::new(),
+        swap_count: 0,
+        volume_a: 0,
+        volume_b: 0,
+        fees_a: 0,
+        fees_b: 0,
+        final_reserve_a: 0,
+        final_reserve_b: 0,
+        final_lp_supply: 0,
+        protocol_lp_minted: 0,
+        protocol_revenue_a: 0,
+        protocol_revenue_b: 0,
+        lp_return: None,
+        fee_growth: None,
+        impermanent_loss: None,
+        twap: Vec::new(),
+    };
+    let mut baseline: Option<Baseline> = None;
+
+    for (index, record) in records.iter().enumerate() {
+        let action = match record.to_action(index) {
+            Ok(action) => action,
+            Err(e) => {
+                report.failures.push(ReplayFailure {
+                    index,
+                    timestamp: record.timestamp,
+                    error: e.to_string(),
+                });
+                continue;
+            }
+        };
+
+        let before = Observation::from(&pool.state);
+
+        let result = match action {
+            Action::Swap { amount_in, is_a_to_b } => {
+                pool.swap(amount_in, is_a_to_b, 0, record.timestamp).map(|outcome| {
+                    let fee = amount_in - outcome.amount_in_effective;
+                    report.swap_count += 1;
+                    if is_a_to_b {
+                        report.volume_a = report.volume_a.saturating_add(amount_in);
+                        report.fees_a = report.fees_a.saturating_add(fee);
+                    } else {
+                        report.volume_b = report.volume_b.saturating_add(amount_in);
+                        report.fees_b = report.fees_b.saturating_add(fee);
+                    }
+                })
+            }
+            Action::AddLiquidity { amount_a, amount_b } => pool
+                .add_liquidity(amount_a, amount_b, record.timestamp)
+                .map(|_| {
+                    if baseline.is_none() {
+                        baseline = Some(Baseline {
+                            a_per_lp: pool.reserve_a as f64 / pool.lp_supply as f64,
+                            b_per_lp: pool.reserve_b as f64 / pool.lp_supply as f64,
+                            price_a: pool.reserve_b as f64 / pool.reserve_a as f64,
+                        });
+                    }
+                }),
+            Action::RemoveLiquidity { amount_lp } => pool
+                .remove_liquidity(amount_lp, 0, 0, record.timestamp)
+                .map(|_| ()),
+            Action::SetProtocolFeeShare { share } => pool.set_protocol_fee_share(share),
+        };
+
+        if let Err(e) = result {
+            report.failures.push(ReplayFailure {
+                index,
+                timestamp: record.timestamp,
+                error: e.to_string(),
+            });
+            continue;
+        }
+
+        // 只有累加器真正前进时才产生一个 TWAP 点
+        let after = Observation::from(&pool.state);
+        if after.price_a_cumulative != before.price_a_cumulative {
+            if let Some(twap) = oracle::twap(&before, &after) {
+                report.twap.push(TwapPoint {
+                    start: before.timestamp,
+                    end: after.timestamp,
+                    price_a: twap.price_a as f64 / Q64,
+                    price_b: twap.price_b as f64 / Q64,
+                });
+            }
+        }
+    }
+
+    report.final_reserve_a = pool.reserve_a;
+    report.final_reserve_b = pool.reserve_b;
+    report.final_lp_supply = pool.lp_supply;
+    report.protocol_lp_minted = pool.protocol_lp;
+    if pool.lp_supply > 0 {
+        report.protocol_revenue_a =
+            (pool.protocol_lp as u128 * pool.reserve_a as u128 / pool.lp_supply as u128) as u64;
+        report.protocol_revenue_b =
+            (pool.protocol_lp as u128 * pool.reserve_b as u128 / pool.lp_supply as u128) as u64;
+    }
+
+    if let (Some(baseline), Some(price_a)) = (baseline, pool.spot_price_a()) {
+        let a_per_lp = pool.reserve_a as f64 / pool.lp_supply as f64;
+        let b_per_lp = pool.reserve_b as f64 / pool.lp_supply as f64;
+        let lp_value = a_per_lp * price_a + b_per_lp;
+        let hold_value = baseline.a_per_lp * price_a + baseline.b_per_lp;
+        let ratio = price_a / baseline.price_a;
+
+        report.lp_return = Some(lp_value / hold_value - 1.0);
+        report.fee_growth =
+            Some((a_per_lp * b_per_lp).sqrt() / (baseline.a_per_lp * baseline.b_per_lp).sqrt() - 1.0);
+        report.impermanent_loss = Some(2.0 * ratio.sqrt() / (1.0 + ratio) - 1.0);
+    }
+
+    Ok(report)
+}
@@ -0,0 +1,82 @@
+//! 模拟器与链上 handler 的逐条对账
+//!
+//! 把 `tests/data/trades.csv` 同时在模拟器和 Mollusk 加载的链上程序中回放，
+//! 每条记录执行后比较储备金、LP 供应量、协议方 LP 和 `PoolState` 的 TWAP / k_last。
+//!
+//! 链上一侧直接复用 solana-amm 的 Mollusk 测试夹具，需要先执行 `anchor build`（或 `cargo build-sbf`）
+//! 生成 `target/deploy/solana_amm.so`，也可以通过 `SBF_OUT_DIR` 指定目录。测试标记为 `#[ignore]`，
+//! 构建之后用 `cargo test -p amm-sim -- --include-ignored` 运行；找不到程序文件时直接 panic。
+
+#[path = "../../../programs/solana-amm/tests/common/mod.rs"]
+mod common;
+
+use std::path::PathBuf;
+
+use amm_sim::{log, Action, SimPool};
+use common::*;
+use mollusk_svm::result::InstructionResult;
+use solana_amm::AmmError;
+
+fn chain_apply(chain: &TestPool, action: Action) -> InstructionResult {
+    match action {
+        Action::Swap { amount_in, is_a_to_b } => chain.swap(amount_in, is_a_to_b, 0),
+        Action::AddLiquidity { amount_a, amount_b } => chain.add_liquidity(amount_a, amount_b),
+        Action::RemoveLiquidity { amount_lp } => chain.remove_liquidity(amount_lp, 0, 0),
+        Action::SetProtocolFeeShare { share } => chain.update_config(&chain.admin, None, None, Some(share)),
+    }
+}
+
+fn sim_apply(pool: &mut SimPool, action: Action, timestamp: u64) -> Result<(), AmmError> {
+    match action {
+        Action::Swap { amount_in, is_a_to_b } => pool.swap(amount_in, is_a_to_b, 0, timestamp).map(|_| ()),
+        Action::AddLiquidity { amount_a, amount_b } => {
+            pool.add_liquidity(amount_a, amount_b, timestamp).map(|_| ())
+        }
+        Action::RemoveLiquidity { amount_lp } => {
+            pool.remove_liquidity(amount_lp, 0, 0, timestamp).map(|_| ())
+        }
+        Action::SetProtocolFeeShare { share } => pool.set_protocol_fee_share(share),
+    }
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn golden_log_matches_on_chain_handlers() {
+    let records = log::read_path(
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/data/trades.csv"),
+    )
+    .unwrap();
+    let start = records[0].timestamp;
+
+    let mut chain = TestPool::new();
+    chain.set_clock(start);
+    assert_success(&chain.initialize(FEE_NUMERATOR, FEE_DENOMINATOR));
+    let mut sim = SimPool::new(FEE_NUMERATOR, FEE_DENOMINATOR, start).unwrap();
+
+    for (index, record) in records.iter().enumerate() {
+        let action = record.to_action(index).unwrap();
+        chain.set_clock(record.timestamp);
+
+        let on_chain = chain_apply(&chain, action);
+        match sim_apply(&mut sim, action, record.timestamp) {
+            Ok(()) => assert!(
+                on_chain.program_result.is_ok(),
+                "record #{index}: chain failed with {:?}",
+                on_chain.program_result
+            ),
+            Err(e) => assert_error(&on_chain, e),
+        }
+
+        let state = chain.pool_state();
+        assert_eq!(chain.reserves(), (sim.reserve_a, sim.reserve_b), "record #{index}");
+        assert_eq!(chain.lp_supply(), sim.lp_supply, "record #{index}");
+        assert_eq!(chain.token_amount(&chain.protocol_fee_recipient), sim.protocol_lp, "record #{index}");
+        assert_eq!(state.block_timestamp_last, sim.state.block_timestamp_last, "record #{index}");
+        assert_eq!(state.price_a_cumulative_last, sim.state.price_a_cumulative_last, "record #{index}");
+        assert_eq!(state.price_b_cumulative_last, sim.state.price_b_cumulative_last, "record #{index}");
+        assert_eq!(state.log_price_cumulative_last, sim.state.log_price_cumulative_last, "record #{index}");
+        assert_eq!(state.observations, sim.state.observations, "record #{index}");
+        assert_eq!(state.k_last, sim.state.k_last, "record #{index}");
+        assert_eq!(state.protocol_fee_share, sim.state.protocol_fee_share, "record #{index}");
+    }
+}
@@ -0,0 +1,101 @@
+use std::path::PathBuf;
+
+use amm_sim::{log, replay, LogError, ReplayConfig};
+
+fn data(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/data").join(name)
+}
+
+#[test]
+fn csv_replay_matches_golden_report() {
+    let records = log::read_path(data("trades.csv")).unwrap();
+    let report = replay(&records, ReplayConfig::default()).unwrap();
+
+    let golden: serde_json::Value =
+        serde_json::from_reader(std::fs::File::open(data("trades.report.json")).unwrap()).unwrap();
+    assert_eq!(serde_json::to_value(&report).unwrap(), golden);
+}
+
+#[test]
+fn csv_and_json_logs_replay_identically() {
+    let from_csv = log::read_path(data("trades.csv")).unwrap();
+    let from_json = log::read_path(data("trades.json")).unwrap();
+    assert_eq!(from_csv, from_json);
+
+    let config = ReplayConfig::default();
+    assert_eq!(replay(&from_csv, config).unwrap(), replay(&from_json, config).unwrap());
+}
+
+#[test]
+fn higher_fee_grows_lp_value_faster() {
+    let records = log::read_path(data("trades.csv")).unwrap();
+    let low = replay(&records, ReplayConfig::default()).unwrap();
+    let high = replay(
+        &records,
+        ReplayConfig {
+            fee_numerator: 1,
+            fee_denominator: 100,
+            ..ReplayConfig::default()
+        },
+    )
+    .unwrap();
+
+    assert!(high.fee_growth.unwrap() > low.fee_growth.unwrap());
+    assert!(high.fees_a > low.fees_a);
+    assert!(high.protocol_lp_minted > low.protocol_lp_minted);
+}
+
+#[test]
+fn price_guard_rejects_swaps_that_move_price_too_far() {
+    let records = log::read_path(data("trades.csv")).unwrap();
+    let unguarded = replay(&records, ReplayConfig::default()).unwrap();
+    let guarded = replay(
+        &records,
+        ReplayConfig {
+            max_price_deviation_bps: 1,
+            ..ReplayConfig::default()
+        },
+    )
+    .unwrap();
+
+    assert!(guarded.swap_count < unguarded.swap_count);
+    assert_eq!(
+        guarded.failures.len() - unguarded.failures.len(),
+        (unguarded.swap_count - guarded.swap_count) as usize
+    );
+
+    let invalid = ReplayConfig {
+        max_price_deviation_bps: 10_001,
+        ..ReplayConfig::default()
+    };
+    assert!(replay(&records, invalid).is_err());
+}
+
+#[test]
+fn invalid_fee_config_is_rejected() {
+    let records = log::read_path(data("trades.csv")).unwrap();
+    let config = ReplayConfig {
+        fee_numerator: 10,
+        fee_denominator: 10,
+        ..ReplayConfig::default()
+    };
+    assert!(replay(&records, config).is_err());
+}
+
+#[test]
+fn malformed_records_are_rejected() {
+    let both_sides = "timestamp,action,amount_a,amount_b,amount_lp,share\n1,swap,10,10,,\n";
+    assert!(matches!(
+        log::read_csv(both_sides.as_bytes()),
+        Err(LogError::InvalidRecord { index: 0, .. })
+    ));
+
+    let backwards = r#"[
+        {"timestamp": 10, "action": "add_liquidity", "amount_a": 1, "amount_b": 1},
+        {"timestamp": 9, "action": "swap", "amount_a": 1}
+    ]"#;
+    assert!(matches!(
+        log::read_json(backwards.as_bytes()),
+        Err(LogError::InvalidRecord { index: 1, .. })
+    ));
+}
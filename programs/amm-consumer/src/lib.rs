@@ -0,0 +1,159 @@
+//! 通过 CPI 使用 solana-amm 的示例程序
+//!
+//! 依赖 `solana-amm` 的 `cpi` feature 后，每条指令都有 `solana_amm::cpi::<指令名>`，
+//! 账户结构体在 `solana_amm::cpi::accounts` 中，PDA 地址用 `solana_amm::pda` 派生。
+//! `swap_near_twap` 先用 CPI 读取池子的几何 TWAP，再按 TWAP 算出的最少换得数量 CPI 兑换，
+//! 现货价格被拉离 TWAP 太远时兑换以 `SlippageExceeded` 失败。
+
+use anchor_lang::prelude::*;
+use solana_amm::cpi::accounts::{GetGeometricTwap, Swap};
+use solana_amm::program::SolanaAmm;
+use solana_amm::PoolState;
+
+declare_id!("85tvuRFETLTeYogkVxNJYBVXKUNPdL2hY9hTj1T8pbu4");
+
+/// 滑点以万分比（bps）表示
+pub const BPS_DENOMINATOR: u128 = 10_000;
+
+#[program]
+pub mod amm_consumer {
+    use super::*;
+
+    /// 按最近 window 秒的 TWAP 兑换，成交价最多比 TWAP 扣除手续费后的价格差 max_slippage_bps
+    pub fn swap_near_twap(
+        ctx: Context<SwapNearTwap>,
+        amount_in: u64,
+        is_a_to_b: bool,
+        window: u64,
+        max_slippage_bps: u16,
+    ) -> Result<()> {
+        require!(max_slippage_bps as u128 <= BPS_DENOMINATOR, ConsumerError::InvalidSlippage);
+
+        // PoolState 是公开的零拷贝布局，直接读取费率；CPI 之前要释放借用
+        let (fee_numerator, fee_denominator) = {
+            let pool_state = ctx.accounts.pool_state.load()?;
+            (pool_state.fee_numerator, pool_state.fee_denominator)
+        };
+
+        let amm_program = ctx.accounts.amm_program.to_account_info();
+        let twap = solana_amm::cpi::get_geometric_twap(
+            CpiContext::new(
+                amm_program.clone(),
+                GetGeometricTwap {
+                    pool_state: ctx.accounts.pool_state.to_account_info(),
+                    token_a_vault: ctx.accounts.token_a_vault.to_account_info(),
+                    token_b_vault: ctx.accounts.token_b_vault.to_account_info(),
+                },
+            ),
+            window,
+        )?
+        .get();
+
+        let price_x64 = if is_a_to_b { twap.price_a } else { twap.price_b };
+        let min_amount_out = min_amount_out(amount_in, price_x64, fee_numerator, fee_denominator, max_slippage_bps)
+            .ok_or(ConsumerError::MathOverflow)?;
+
+        solana_amm::cpi::swap(
+            CpiContext::new(
+                amm_program,
+                Swap {
+                    pool_state: ctx.accounts.pool_state.to_account_info(),
+                    user_token_a: ctx.accounts.user_token_a.to_account_info(),
+                    user_token_b: ctx.accounts.user_token_b.to_account_info(),
+                    token_a_vault: ctx.accounts.token_a_vault.to_account_info(),
+                    token_b_vault: ctx.accounts.token_b_vault.to_account_info(),
+                    user: ctx.accounts.user.to_account_info(),
+                    pool_authority: ctx.accounts.pool_authority.to_account_info(),
+                    token_program: ctx.accounts.token_program.to_account_info(),
+                    referrer_token_account: None,
+                    twamm: ctx.accounts.twamm.as_ref().map(|account| account.to_account_info()),
+                    twamm_vault_a: ctx.accounts.twamm_vault_a.as_ref().map(|account| account.to_account_info()),
+                    twamm_vault_b: ctx.accounts.twamm_vault_b.as_ref().map(|account| account.to_account_info()),
+                    access_pass: ctx.accounts.access_pass.as_ref().map(|account| account.to_account_info()),
+                    pool_gatekeeper: ctx.accounts.pool_gatekeeper.as_ref().map(|account| account.to_account_info()),
+                },
+            ),
+            amount_in,
+            is_a_to_b,
+            min_amount_out,
+            None,
+            0,
+        )?;
+
+        msg!("TWAP 窗口 {}..{}，最少换得 {}", twap.start, twap.end, min_amount_out);
+        Ok(())
+    }
+}
+
+/// 按 TWAP 价格（Q64.64）换算输入，扣掉池子手续费，再留出 max_slippage_bps 的价格冲击
+pub fn min_amount_out(
+    amount_in: u64,
+    price_x64: u128,
+    fee_numerator: u64,
+    fee_denominator: u64,
+    max_slippage_bps: u16,
+) -> Option<u64> {
+    // 价格先右移 32 位，避免 u64 × Q64.64 溢出 u128
+    let fair = (amount_in as u128).checked_mul(price_x64 >> 32)? >> 32;
+    let after_fee = fair
+        .checked_mul(fee_denominator.checked_sub(fee_numerator)? as u128)?
+        .checked_div(fee_denominator as u128)?;
+    let min = after_fee.checked_mul(BPS_DENOMINATOR.checked_sub(max_slippage_bps as u128)?)? / BPS_DENOMINATOR;
+    u64::try_from(min).ok()
+}
+
+#[derive(Accounts)]
+pub struct SwapNearTwap<'info> {
+    pub amm_program: Program<'info, SolanaAmm>,
+
+    // 所有者必须是 solana-amm，其余约束由 solana-amm 在 CPI 中检查
+    #[account(mut)]
+    pub pool_state: AccountLoader<'info, PoolState>,
+
+    /// CHECK: 由 solana-amm 校验
+    #[account(mut)]
+    pub user_token_a: UncheckedAccount<'info>,
+    /// CHECK: 由 solana-amm 校验
+    #[account(mut)]
+    pub user_token_b: UncheckedAccount<'info>,
+    /// CHECK: 由 solana-amm 校验
+    #[account(mut)]
+    pub token_a_vault: UncheckedAccount<'info>,
+    /// CHECK: 由 solana-amm 校验
+    #[account(mut)]
+    pub token_b_vault: UncheckedAccount<'info>,
+
+    pub user: Signer<'info>,
+
+    /// CHECK: 由 solana-amm 校验，地址见 `solana_amm::pda::pool_authority_address`
+    pub pool_authority: UncheckedAccount<'info>,
+    /// CHECK: 由 solana-amm 校验
+    pub token_program: UncheckedAccount<'info>,
+
+    // 池子开启了 TWAMM 时必须传入
+    /// CHECK: 由 solana-amm 校验
+    #[account(mut)]
+    pub twamm: Option<UncheckedAccount<'info>>,
+    /// CHECK: 由 solana-amm 校验
+    #[account(mut)]
+    pub twamm_vault_a: Option<UncheckedAccount<'info>>,
+    /// CHECK: 由 solana-amm 校验
+    #[account(mut)]
+    pub twamm_vault_b: Option<UncheckedAccount<'info>>,
+
+    // 许可池子必须传入 user 的 AccessPass 和池子的 PoolGatekeeper，
+    // 地址见 `solana_amm::pda::access_pass_address` / `pool_gatekeeper_address`
+    /// CHECK: 由 solana-amm 校验
+    pub access_pass: Option<UncheckedAccount<'info>>,
+    /// CHECK: 由 solana-amm 校验
+    pub pool_gatekeeper: Option<UncheckedAccount<'info>>,
+}
+
+/// 示例程序的错误码
+#[error_code]
+pub enum ConsumerError {
+    #[msg("滑点不能超过 10000 bps")]
+    InvalidSlippage,
+    #[msg("数学运算溢出")]
+    MathOverflow,
+}
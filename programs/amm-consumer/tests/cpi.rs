@@ -0,0 +1,308 @@
+//! amm-consumer 通过 CPI 调用 solana-amm 的集成测试
+//!
+//! 需要先执行 `anchor build` 生成 `target/deploy/solana_amm.so` 和 `amm_consumer.so`，
+//! 也可以通过 `SBF_OUT_DIR` 指定目录。依赖程序文件的测试标记为 `#[ignore]`，构建之后用
+//! `cargo test -p amm-consumer -- --include-ignored` 运行；找不到任何一个程序文件时直接 panic。
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use anchor_lang::error::ERROR_CODE_OFFSET;
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::{InstructionData, ToAccountMetas};
+use anchor_spl::associated_token::get_associated_token_address;
+use mollusk_svm::program::loader_keys;
+use mollusk_svm::result::{InstructionResult, ProgramResult};
+use mollusk_svm::{Mollusk, MolluskContext};
+use solana_account::Account;
+use solana_amm::{pda, AmmError, PoolState, OBSERVATION_INTERVAL};
+use solana_instruction::{AccountMeta, Instruction};
+use solana_program_error::ProgramError;
+use solana_program_pack::Pack;
+use spl_token_interface::state::{Account as TokenAccount, AccountState, Mint};
+
+const FEE_NUMERATOR: u64 = 3;
+const FEE_DENOMINATOR: u64 = 1000;
+const START_TIMESTAMP: u64 = 1_700_000_000;
+const RESERVE_A: u64 = 1_000_000_000;
+const RESERVE_B: u64 = 4_000_000_000;
+
+fn address(key: &Pubkey) -> solana_pubkey::Pubkey {
+    solana_pubkey::Pubkey::new_from_array(key.to_bytes())
+}
+
+fn elf(name: &str) -> Vec<u8> {
+    let dir = std::env::var("SBF_OUT_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../target/deploy"));
+    let path = dir.join(name);
+    std::fs::read(&path).unwrap_or_else(|err| {
+        panic!("cannot read {}: {err}; run `anchor build` (or `cargo build-sbf`) first", path.display())
+    })
+}
+
+fn instruction(program_id: &Pubkey, data: impl InstructionData, accounts: impl ToAccountMetas) -> Instruction {
+    Instruction {
+        program_id: address(program_id),
+        accounts: accounts
+            .to_account_metas(None)
+            .into_iter()
+            .map(|meta| AccountMeta {
+                pubkey: address(&meta.pubkey),
+                is_signer: meta.is_signer,
+                is_writable: meta.is_writable,
+            })
+            .collect(),
+        data: data.data(),
+    }
+}
+
+fn token_account(mint: &Pubkey, owner: &Pubkey, amount: u64) -> Account {
+    mollusk_svm_programs_token::token::create_account_for_token_account(TokenAccount {
+        mint: address(mint),
+        owner: address(owner),
+        amount,
+        state: AccountState::Initialized,
+        ..TokenAccount::default()
+    })
+}
+
+fn assert_success(result: &InstructionResult) {
+    assert!(result.program_result.is_ok(), "instruction failed: {:?}", result.program_result);
+}
+
+// 加载了两个程序、已经注入流动性的池子
+struct Env {
+    context: MolluskContext<HashMap<solana_pubkey::Pubkey, Account>>,
+    user: Pubkey,
+    pool_state: Pubkey,
+    token_a_vault: Pubkey,
+    token_b_vault: Pubkey,
+    user_token_a: Pubkey,
+    user_token_b: Pubkey,
+}
+
+impl Env {
+    fn new() -> Self {
+        let (amm_elf, consumer_elf) = (elf("solana_amm.so"), elf("amm_consumer.so"));
+        let mut mollusk = Mollusk::default();
+        mollusk.add_program_with_loader_and_elf(&address(&solana_amm::ID), &loader_keys::LOADER_V3, &amm_elf);
+        mollusk.add_program_with_loader_and_elf(&address(&amm_consumer::ID), &loader_keys::LOADER_V3, &consumer_elf);
+        mollusk_svm_programs_token::token::add_program(&mut mollusk);
+        mollusk_svm_programs_token::associated_token::add_program(&mut mollusk);
+
+        let admin = Pubkey::new_unique();
+        let user = Pubkey::new_unique();
+        let (mint_a, mint_b) = {
+            let (x, y) = (Pubkey::new_unique(), Pubkey::new_unique());
+            if x < y { (x, y) } else { (y, x) }
+        };
+        let lp_mint = Pubkey::new_unique();
+        let pool_state = pda::pool_address(&mint_a, &mint_b);
+        let pool_authority = pda::pool_authority_address();
+
+        let mut env = Self {
+            context: mollusk.with_context(HashMap::new()),
+            user,
+            pool_state,
+            token_a_vault: pda::pool_vault_address(&mint_a),
+            token_b_vault: pda::pool_vault_address(&mint_b),
+            user_token_a: Pubkey::new_unique(),
+            user_token_b: Pubkey::new_unique(),
+        };
+        env.context.mollusk.sysvars.clock.unix_timestamp = START_TIMESTAMP as i64;
+        {
+            let mut store = env.context.account_store.borrow_mut();
+            let funded = Account::new(100_000_000_000, 0, &solana_pubkey::Pubkey::default());
+            store.insert(address(&admin), funded.clone());
+            store.insert(address(&user), funded);
+            for (mint, decimals) in [(mint_a, 6), (mint_b, 9)] {
+                let account = mollusk_svm_programs_token::token::create_account_for_mint(Mint {
+                    decimals,
+                    is_initialized: true,
+                    ..Mint::default()
+                });
+                store.insert(address(&mint), account);
+            }
+            store.insert(address(&env.user_token_a), token_account(&mint_a, &user, u64::MAX / 4));
+            store.insert(address(&env.user_token_b), token_account(&mint_b, &user, u64::MAX / 4));
+        }
+
+        assert_success(&env.process_amm(
+            solana_amm::instruction::Initialize {
+                mint_a,
+                mint_b,
+                fee_numerator: FEE_NUMERATOR,
+                fee_denominator: FEE_DENOMINATOR,
+            },
+            solana_amm::accounts::Initialize {
+                pool_state,
+                pool_authority,
+                token_a: mint_a,
+                token_b: mint_b,
+                token_a_vault: env.token_a_vault,
+                token_b_vault: env.token_b_vault,
+                lp_mint,
+                // 每个测试都是新的环境，池子登记在注册表的第 0 页
+                pool_registry: pda::pool_registry_address(),
+                registry_page: pda::registry_page_address(0),
+                system_program: anchor_lang::system_program::ID,
+                token_program: anchor_spl::token::ID,
+                rent: anchor_lang::solana_program::sysvar::rent::ID,
+                associated_token_program: anchor_spl::associated_token::ID,
+                admin,
+                token_metadata_program: None,
+                lp_metadata: None,
+                token_a_metadata: None,
+                token_b_metadata: None,
+            },
+        ));
+
+        // LP mint 创建之后才能放黑洞账户和协议费接收账户
+        let (black_hole_lp, protocol_fee_recipient) = (Pubkey::new_unique(), Pubkey::new_unique());
+        {
+            let mut store = env.context.account_store.borrow_mut();
+            store.insert(address(&black_hole_lp), token_account(&lp_mint, &Pubkey::default(), 0));
+            store.insert(address(&protocol_fee_recipient), token_account(&lp_mint, &admin, 0));
+        }
+        assert_success(&env.process_amm(
+            solana_amm::instruction::AddLiquidity { amount_a: RESERVE_A, amount_b: RESERVE_B, deadline: None },
+            solana_amm::accounts::AddLiquidity {
+                pool_state,
+                pool_authority,
+                user_token_a: env.user_token_a,
+                user_token_b: env.user_token_b,
+                token_a_vault: env.token_a_vault,
+                token_b_vault: env.token_b_vault,
+                user,
+                lp_mint,
+                user_lp_token_ATA: get_associated_token_address(&user, &lp_mint),
+                black_hole_lp_ATA: black_hole_lp,
+                protocol_fee_recipient,
+                associated_token_program: anchor_spl::associated_token::ID,
+                system_program: anchor_lang::system_program::ID,
+                token_program: anchor_spl::token::ID,
+                twamm: None,
+                twamm_vault_a: None,
+                twamm_vault_b: None,
+                access_pass: None,
+                pool_gatekeeper: None,
+            },
+        ));
+
+        // 让观测覆盖 TWAP 窗口
+        env.context.mollusk.sysvars.clock.unix_timestamp += 2 * OBSERVATION_INTERVAL as i64;
+        env
+    }
+
+    fn process_amm(&self, data: impl InstructionData, accounts: impl ToAccountMetas) -> InstructionResult {
+        self.context.process_instruction(&instruction(&solana_amm::ID, data, accounts))
+    }
+
+    fn token_amount(&self, key: &Pubkey) -> u64 {
+        let store = self.context.account_store.borrow();
+        TokenAccount::unpack(&store[&address(key)].data).unwrap().amount
+    }
+
+    fn pool_state(&self) -> PoolState {
+        let store = self.context.account_store.borrow();
+        bytemuck::pod_read_unaligned(&store[&address(&self.pool_state)].data[8..PoolState::LEN])
+    }
+
+    // 不经过示例程序，直接在池子里兑换
+    fn swap(&self, amount_in: u64, is_a_to_b: bool) -> InstructionResult {
+        self.process_amm(
+            solana_amm::instruction::Swap {
+                amount_in,
+                is_a_to_b,
+                min_amount_out: 0,
+                deadline: None,
+                referral_fee_bps: 0,
+            },
+            solana_amm::accounts::Swap {
+                pool_state: self.pool_state,
+                user_token_a: self.user_token_a,
+                user_token_b: self.user_token_b,
+                token_a_vault: self.token_a_vault,
+                token_b_vault: self.token_b_vault,
+                user: self.user,
+                pool_authority: pda::pool_authority_address(),
+                token_program: anchor_spl::token::ID,
+                referrer_token_account: None,
+                twamm: None,
+                twamm_vault_a: None,
+                twamm_vault_b: None,
+                access_pass: None,
+                pool_gatekeeper: None,
+            },
+        )
+    }
+
+    fn swap_near_twap(&self, amount_in: u64, is_a_to_b: bool, max_slippage_bps: u16) -> InstructionResult {
+        let ix = instruction(
+            &amm_consumer::ID,
+            amm_consumer::instruction::SwapNearTwap {
+                amount_in,
+                is_a_to_b,
+                window: OBSERVATION_INTERVAL,
+                max_slippage_bps,
+            },
+            amm_consumer::accounts::SwapNearTwap {
+                amm_program: solana_amm::ID,
+                pool_state: self.pool_state,
+                user_token_a: self.user_token_a,
+                user_token_b: self.user_token_b,
+                token_a_vault: self.token_a_vault,
+                token_b_vault: self.token_b_vault,
+                user: self.user,
+                pool_authority: pda::pool_authority_address(),
+                token_program: anchor_spl::token::ID,
+                twamm: None,
+                twamm_vault_a: None,
+                twamm_vault_b: None,
+                access_pass: None,
+                pool_gatekeeper: None,
+            },
+        );
+        self.context.process_instruction(&ix)
+    }
+}
+
+#[test]
+fn min_amount_out_applies_fee_and_slippage() {
+    // 价格 4 B/A：1000 A 值 4000 B，扣 0.3% 手续费后 3988，再留 1% 滑点后 3948
+    assert_eq!(amm_consumer::min_amount_out(1_000, 4 << 64, 3, 1000, 100), Some(3_948));
+    assert_eq!(amm_consumer::min_amount_out(1_000, 4 << 64, 3, 1000, 0), Some(3_988));
+    assert_eq!(amm_consumer::min_amount_out(1_000, 4 << 64, 3, 1000, 10_001), None);
+    assert_eq!(amm_consumer::min_amount_out(u64::MAX, u128::MAX, 3, 1000, 0), None);
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so and amm_consumer.so: run anchor build"]
+fn swap_through_cpi_at_twap() {
+    let env = Env::new();
+    let (before_a, before_b) = (env.token_amount(&env.user_token_a), env.token_amount(&env.user_token_b));
+
+    // 相对储备金很小的一笔，价格冲击远小于 1%
+    let amount_in = RESERVE_A / 10_000;
+    assert_success(&env.swap_near_twap(amount_in, true, 100));
+
+    let received = env.token_amount(&env.user_token_b) - before_b;
+    assert_eq!(env.token_amount(&env.user_token_a), before_a - amount_in);
+    assert!(received >= amm_consumer::min_amount_out(amount_in, 4 << 64, FEE_NUMERATOR, FEE_DENOMINATOR, 100).unwrap());
+    assert_eq!(env.token_amount(&env.token_a_vault), RESERVE_A + amount_in);
+    assert_eq!(env.pool_state().block_timestamp_last, START_TIMESTAMP + 2 * OBSERVATION_INTERVAL);
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so and amm_consumer.so: run anchor build"]
+fn swap_fails_when_spot_is_pushed_away_from_twap() {
+    let env = Env::new();
+
+    // 同一秒内把 A 的价格砸低约 20%，TWAP 还停留在原来的价格
+    assert_success(&env.swap(RESERVE_A / 10, true));
+    assert_eq!(
+        env.swap_near_twap(RESERVE_A / 10_000, true, 100).program_result,
+        ProgramResult::Failure(ProgramError::Custom(ERROR_CODE_OFFSET + AmmError::SlippageExceeded as u32)),
+    );
+}
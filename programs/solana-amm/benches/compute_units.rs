@@ -0,0 +1,26 @@
+// 各指令 CU 消耗基准，结果写入 benches/compute_units.md
+//
+// 需要先 `anchor build` 生成 target/deploy/solana_amm.so：
+//   cargo bench -p solana-amm --bench compute_units
+
+use mollusk_svm_bencher::MolluskComputeUnitBencher;
+
+#[path = "../tests/common/mod.rs"]
+mod common;
+
+fn main() {
+    let elf = common::program_elf();
+    let scenarios = common::compute_units::scenarios();
+
+    // 每个场景的账户快照已经取好，只需要让基准用的 Mollusk 时钟和场景一致
+    let mut mollusk = common::mollusk(&elf);
+    mollusk.sysvars.clock.unix_timestamp = scenarios[0].unix_timestamp();
+
+    let keyed: Vec<_> = scenarios.iter().map(|s| s.keyed_accounts()).collect();
+    // 默认输出目录就是 CARGO_MANIFEST_DIR/benches
+    let mut bencher = MolluskComputeUnitBencher::new(mollusk).must_pass(true);
+    for (scenario, accounts) in scenarios.iter().zip(&keyed) {
+        bencher = bencher.bench((scenario.name.as_str(), &scenario.instruction, accounts.as_slice()));
+    }
+    bencher.execute();
+}
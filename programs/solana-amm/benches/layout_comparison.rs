@@ -0,0 +1,44 @@
+// PoolState 的 Borsh 布局和零拷贝布局的 CU 对比，结果写入 benches/layout_comparison.md
+//
+// 除了 `anchor build` 生成的 target/deploy/solana_amm.so，还需要零拷贝之前的提交构建出的
+// target/deploy/solana_amm_borsh.so（构建方法见 README）：
+//   cargo bench -p solana-amm --bench layout_comparison
+
+use std::fmt::Write;
+use std::path::PathBuf;
+
+#[path = "../tests/common/mod.rs"]
+mod common;
+
+use common::compute_units::{borsh_baseline_elf, layout_scenarios, Scenario};
+
+fn compute_units(scenario: &Scenario) -> u64 {
+    let result = scenario.run();
+    assert!(
+        result.program_result.is_ok(),
+        "{} failed: {:?}",
+        scenario.name,
+        result.program_result
+    );
+    result.compute_units_consumed
+}
+
+fn main() {
+    let borsh_elf = borsh_baseline_elf();
+    let borsh = layout_scenarios(Some(&borsh_elf));
+    let zero_copy = layout_scenarios(None);
+
+    let mut report = String::from(
+        "# PoolState layout comparison\n\n\
+         | Scenario | Borsh CU | Zero-copy CU | Delta |\n\
+         | --- | ---: | ---: | ---: |\n",
+    );
+    for (borsh, zero_copy) in borsh.iter().zip(&zero_copy) {
+        let (before, after) = (compute_units(borsh), compute_units(zero_copy));
+        writeln!(report, "| {} | {before} | {after} | {:+} |", zero_copy.name, after as i64 - before as i64).unwrap();
+    }
+
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("benches/layout_comparison.md");
+    std::fs::write(&path, &report).expect("write layout_comparison.md");
+    print!("{report}");
+}
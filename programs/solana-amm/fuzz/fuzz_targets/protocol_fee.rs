@@ -0,0 +1,35 @@
+// 协议费增发不超过 fee_share 对应的份额，k 没有增长时不增发
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use solana_amm::math::{calculate_protocol_fee_mint, sqrt_product_u64, sqrt_u128};
+
+fuzz_target!(|input: (u64, u64, u128, u64, u64)| {
+    let (reserve_a, reserve_b, k_last, lp_supply, fee_share) = input;
+    // update_config 限制 fee_share <= 500
+    let fee_share = fee_share % 501;
+
+    let Some(minted) =
+        calculate_protocol_fee_mint(reserve_a, reserve_b, k_last, lp_supply, fee_share)
+    else {
+        return;
+    };
+    let minted = minted as u128;
+
+    let root_k = sqrt_product_u64(reserve_a, reserve_b).unwrap() as u128;
+    let root_k_last = sqrt_u128(k_last).unwrap() as u128;
+    if fee_share == 0 || k_last == 0 || root_k <= root_k_last {
+        assert_eq!(minted, 0);
+        return;
+    }
+
+    // 协议方最多拿走增长部分的 1 / (fee_share + 1)，不会超过现有 LP 总量
+    assert!(minted <= lp_supply as u128 / fee_share as u128);
+    let growth = root_k - root_k_last;
+    if let (Some(lhs), Some(rhs)) = (
+        minted.checked_mul(fee_share as u128 + 1).and_then(|v| v.checked_mul(root_k)),
+        (lp_supply as u128 + minted).checked_mul(growth),
+    ) {
+        assert!(lhs <= rhs);
+    }
+});
@@ -0,0 +1,15 @@
+// sqrt_u128 必须返回向下取整的平方根，且对任意输入都不 panic
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use solana_amm::math::{sqrt_product_u64, sqrt_u128};
+
+fuzz_target!(|input: (u128, u64, u64)| {
+    let (n, a, b) = input;
+
+    let r = sqrt_u128(n).expect("sqrt of u128 always fits in u64") as u128;
+    assert!(r * r <= n);
+    assert!((r + 1).checked_mul(r + 1).is_none_or(|sq| sq > n));
+
+    assert_eq!(sqrt_product_u64(a, b), sqrt_u128(a as u128 * b as u128));
+});
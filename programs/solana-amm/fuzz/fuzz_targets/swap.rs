@@ -0,0 +1,90 @@
+// 连续兑换 / 加减流动性时 k 不减少，存入再取出不会多拿
+#![no_main]
+
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+use solana_amm::math::{
+    calculate_deposit_liquidity, calculate_swap_amount_out, calculate_withdraw_amounts,
+};
+
+#[derive(Arbitrary, Debug)]
+enum Op {
+    Swap { amount_in: u64, is_a_to_b: bool },
+    Deposit { amount_a: u64, amount_b: u64 },
+    Withdraw { amount_lp: u64 },
+}
+
+#[derive(Arbitrary, Debug)]
+struct Input {
+    reserve_a: u64,
+    reserve_b: u64,
+    fee_numerator: u64,
+    fee_denominator: u64,
+    ops: Vec<Op>,
+}
+
+fn k(a: u64, b: u64) -> u128 {
+    a as u128 * b as u128
+}
+
+fuzz_target!(|input: Input| {
+    let Input { mut reserve_a, mut reserve_b, fee_numerator, fee_denominator, ops } = input;
+    if reserve_a == 0 || reserve_b == 0 || fee_denominator == 0 || fee_numerator >= fee_denominator {
+        return;
+    }
+    let mut lp_supply = solana_amm::math::sqrt_product_u64(reserve_a, reserve_b).unwrap();
+    if lp_supply == 0 {
+        return;
+    }
+
+    for op in ops {
+        match op {
+            Op::Swap { amount_in, is_a_to_b } => {
+                let (reserve_in, reserve_out) =
+                    if is_a_to_b { (reserve_a, reserve_b) } else { (reserve_b, reserve_a) };
+                let Some((_, out)) = calculate_swap_amount_out(
+                    amount_in, reserve_in, reserve_out, fee_numerator, fee_denominator,
+                ) else {
+                    continue;
+                };
+                let Some(new_in) = reserve_in.checked_add(amount_in) else { continue };
+                assert!(out < reserve_out);
+                let new_out = reserve_out - out;
+                assert!(k(new_in, new_out) >= k(reserve_in, reserve_out));
+                (reserve_a, reserve_b) =
+                    if is_a_to_b { (new_in, new_out) } else { (new_out, new_in) };
+            }
+            Op::Deposit { amount_a, amount_b } => {
+                let Some(minted) =
+                    calculate_deposit_liquidity(amount_a, amount_b, reserve_a, reserve_b, lp_supply)
+                else {
+                    continue;
+                };
+                let (Some(a), Some(b), Some(supply)) = (
+                    reserve_a.checked_add(amount_a),
+                    reserve_b.checked_add(amount_b),
+                    lp_supply.checked_add(minted),
+                ) else {
+                    continue;
+                };
+                // 立即取回刚铸造的 LP，拿到的不能比存入的多
+                let (back_a, back_b) = calculate_withdraw_amounts(minted, a, b, supply).unwrap();
+                assert!(back_a <= amount_a && back_b <= amount_b);
+                (reserve_a, reserve_b, lp_supply) = (a, b, supply);
+            }
+            Op::Withdraw { amount_lp } => {
+                // 至少留下 1 份 LP，避免池子被清空
+                let amount_lp = amount_lp % lp_supply;
+                let (out_a, out_b) =
+                    calculate_withdraw_amounts(amount_lp, reserve_a, reserve_b, lp_supply).unwrap();
+                assert!(out_a <= reserve_a && out_b <= reserve_b);
+                reserve_a -= out_a;
+                reserve_b -= out_b;
+                lp_supply -= amount_lp;
+                if reserve_a == 0 || reserve_b == 0 {
+                    return;
+                }
+            }
+        }
+    }
+});
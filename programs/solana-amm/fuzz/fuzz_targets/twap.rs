@@ -0,0 +1,50 @@
+// update_twap 对任意输入都不 panic，累加器的增量按 2^128 取模等于 price * dt；
+// oracle::twap 在累加器回绕后仍然能还原出窗口内的均价
+#![no_main]
+
+use anchor_lang::AccountDeserialize;
+use libfuzzer_sys::fuzz_target;
+use solana_amm::oracle::{self, Observation};
+use solana_amm::{math::update_twap, PoolState};
+
+fn price_fixed(numerator: u64, denominator: u64) -> u128 {
+    ((numerator as u128) << 64) / denominator as u128
+}
+
+fuzz_target!(|input: (u64, u128, u128, Vec<(u64, u64, u64)>)| {
+    let (start, cumulative_a, cumulative_b, steps) = input;
+    let data = vec![0u8; PoolState::LEN];
+    let mut pool = PoolState::try_deserialize_unchecked(&mut data.as_slice()).unwrap();
+    pool.block_timestamp_last = start;
+    pool.price_a_cumulative_last = cumulative_a;
+    pool.price_b_cumulative_last = cumulative_b;
+
+    for (timestamp, reserve_a, reserve_b) in steps {
+        let before = Observation::from(&pool);
+        update_twap(&mut pool, reserve_a, reserve_b, timestamp);
+        assert_eq!(pool.block_timestamp_last, timestamp);
+
+        let elapsed = timestamp.saturating_sub(before.timestamp);
+        if elapsed == 0 || reserve_a == 0 || reserve_b == 0 {
+            assert_eq!(pool.price_a_cumulative_last, before.price_a_cumulative);
+            assert_eq!(pool.price_b_cumulative_last, before.price_b_cumulative);
+            continue;
+        }
+
+        let price_a = price_fixed(reserve_b, reserve_a);
+        let price_b = price_fixed(reserve_a, reserve_b);
+        assert_eq!(
+            pool.price_a_cumulative_last.wrapping_sub(before.price_a_cumulative),
+            price_a.wrapping_mul(elapsed as u128)
+        );
+
+        // 窗口内的增量没有超过 2^128 时，均价就是这段时间的价格
+        let twap = oracle::twap(&before, &Observation::from(&pool)).unwrap();
+        if price_a.checked_mul(elapsed as u128).is_some() {
+            assert_eq!(twap.price_a, price_a);
+        }
+        if price_b.checked_mul(elapsed as u128).is_some() {
+            assert_eq!(twap.price_b, price_b);
+        }
+    }
+});
@@ -0,0 +1,103 @@
+//! 批量拍卖的统一价格结算
+//!
+//! 同一批的订单不分先后：两个方向的卖单先按现货价格相互抵消，多出的一方按恒定乘积曲线
+//! 和池子兑换并收取池子费率（和 TWAMM 的一段虚拟成交相同），同一方向的订单按卖出数量
+//! 平分换得的代币，成交价格完全相同，在批次内抢先提交没有任何好处。
+//!
+//! 统一价格下输出达不到 min_amount_out 的订单不成交，结算时退回输入。成交之后的储备不满足
+//! 池子的限制（价格偏离、储备上限）时，去掉多出一方中卖出最多的订单。去掉订单会改变价格，
+//! 所以重复计算直到剩下的订单都满足条件，每一轮至少去掉一笔，最多 MAX_BATCH_ORDERS 轮。
+//! 每笔订单的输出向下取整，舍入的零头留在 BatchAuction 的托管账户中。
+
+use crate::clmm::mul_div;
+use crate::twamm::{execute_virtual_trade, VirtualTrade};
+
+/// 参与结算的一笔订单
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BatchFill {
+    pub is_a_to_b: bool,
+    pub amount_in: u64,
+    pub min_amount_out: u64,
+}
+
+/// 一批订单的结算结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BatchClearing {
+    /// 和 orders 一一对应：成交的输出数量，None 表示不成交、退回输入
+    pub amounts_out: Vec<Option<u64>>,
+    /// 只统计成交订单的卖出总量和池子成交之后的储备
+    pub amount_a_in: u64,
+    pub amount_b_in: u64,
+    pub trade: VirtualTrade,
+}
+
+/// 按统一价格结算一批订单，储备为 0 时所有订单都不成交
+///
+/// `within_bounds` 检查成交之后的储备是否满足池子的限制，只在储备发生变化时调用
+pub fn clear_batch(
+    orders: &[BatchFill],
+    reserve_a: u64,
+    reserve_b: u64,
+    fee_numerator: u64,
+    fee_denominator: u64,
+    within_bounds: impl Fn(&VirtualTrade) -> bool,
+) -> Option<BatchClearing> {
+    let mut included = vec![reserve_a > 0 && reserve_b > 0; orders.len()];
+    loop {
+        let (mut amount_a_in, mut amount_b_in) = (0u64, 0u64);
+        for (order, _) in orders.iter().zip(&included).filter(|(_, included)| **included) {
+            if order.is_a_to_b {
+                amount_a_in = amount_a_in.checked_add(order.amount_in)?;
+            } else {
+                amount_b_in = amount_b_in.checked_add(order.amount_in)?;
+            }
+        }
+        let trade = execute_virtual_trade(
+            amount_a_in,
+            amount_b_in,
+            reserve_a,
+            reserve_b,
+            fee_numerator,
+            fee_denominator,
+        )?;
+
+        // 超出限制时去掉多出一方（卖进池子的一方）中卖出最多的订单
+        let moved = (trade.reserve_a, trade.reserve_b) != (reserve_a, reserve_b);
+        if moved && !within_bounds(&trade) {
+            let a_sold_to_pool = trade.reserve_a > reserve_a;
+            let (largest, _) = orders
+                .iter()
+                .zip(&included)
+                .enumerate()
+                .filter(|(_, (order, included))| **included && order.is_a_to_b == a_sold_to_pool)
+                .max_by_key(|(_, (order, _))| order.amount_in)?;
+            included[largest] = false;
+            continue;
+        }
+
+        let mut amounts_out = Vec::with_capacity(orders.len());
+        let mut excluded = false;
+        for (order, included) in orders.iter().zip(included.iter_mut()) {
+            if !*included {
+                amounts_out.push(None);
+                continue;
+            }
+            // 同一方向按卖出数量平分换得的代币
+            let (proceeds, total_in) = if order.is_a_to_b {
+                (trade.proceeds_a, amount_a_in)
+            } else {
+                (trade.proceeds_b, amount_b_in)
+            };
+            let amount_out =
+                u64::try_from(mul_div(proceeds as u128, order.amount_in as u128, total_in as u128, false)?).ok()?;
+            if amount_out < order.min_amount_out {
+                *included = false;
+                excluded = true;
+            }
+            amounts_out.push(Some(amount_out));
+        }
+        if !excluded {
+            return Some(BatchClearing { amounts_out, amount_a_in, amount_b_in, trade });
+        }
+    }
+}
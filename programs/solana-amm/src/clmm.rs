@@ -0,0 +1,426 @@
+//! 集中流动性（CLMM）的价格、流动性和手续费计算
+//!
+//! 价格用 Q64.64 的 sqrt(B/A) 表示，tick i 对应的价格是 1.0001^i。
+//! `sqrt_price_at_tick` 复用几何 TWAP 的 `exp2_q64`：sqrt(1.0001^i) = 2^(i * log2(1.0001) / 2)。
+//! 流动性 L 的定义和 Uniswap V3 相同，价格区间 [√pa, √pb] 内：
+//!
+//! * Δa = L · (1/√pa − 1/√pb)
+//! * Δb = L · (√pb − √pa)
+//!
+//! 纯函数返回 Option，溢出时为 None，handler 中转换为 `AmmError::MathOverflow`；
+//! 舍入方向总是对池子有利：用户存入的向上取整，取出的向下取整。
+
+use anchor_lang::prelude::*;
+use spl_math::uint::U256;
+
+use crate::errors::AmmError;
+use crate::math;
+use crate::state::{ClPool, Position, Tick, TickArray, TICK_ARRAY_SIZE};
+
+/// tick 的取值范围，对应价格约 [2^-64, 2^64]
+pub const MIN_TICK: i32 = -443_636;
+pub const MAX_TICK: i32 = 443_636;
+/// tick_spacing 的上限
+pub const MAX_TICK_SPACING: u16 = 1_000;
+/// log2(1.0001) / 2，Q64.64
+pub const LOG2_SQRT_TICK_Q64: i128 = 1_330_584_781_654_114;
+/// 一次 cl_swap 最多通过 remaining_accounts 传入的 TickArray 数量
+pub const MAX_SWAP_TICK_ARRAYS: usize = 3;
+
+const Q64_SHIFT: u32 = 64;
+
+/// tick 对应的 sqrt 价格（Q64.64）
+pub fn sqrt_price_at_tick(tick: i32) -> Option<u128> {
+    if !(MIN_TICK..=MAX_TICK).contains(&tick) {
+        return None;
+    }
+    math::exp2_q64((tick as i128).checked_mul(LOG2_SQRT_TICK_Q64)?)
+}
+
+/// 满足 `sqrt_price_at_tick(tick) <= sqrt_price` 的最大 tick
+///
+/// # Returns
+/// * `None` - 价格不在 [sqrt_price_at_tick(MIN_TICK), sqrt_price_at_tick(MAX_TICK)) 内
+pub fn tick_at_sqrt_price(sqrt_price: u128) -> Option<i32> {
+    if sqrt_price < sqrt_price_at_tick(MIN_TICK)? || sqrt_price >= sqrt_price_at_tick(MAX_TICK)? {
+        return None;
+    }
+
+    // log2_q64 只有 LOG2_FRACTION_BITS 位小数，先估算，再和 sqrt_price_at_tick 比较校正
+    let log2_sqrt_price = math::log2_q64(sqrt_price)?.checked_sub((Q64_SHIFT as i128) << 64)?;
+    let mut tick = log2_sqrt_price
+        .div_euclid(LOG2_SQRT_TICK_Q64)
+        .clamp(MIN_TICK as i128, MAX_TICK as i128 - 1) as i32;
+    while tick > MIN_TICK && sqrt_price_at_tick(tick)? > sqrt_price {
+        tick -= 1;
+    }
+    while tick < MAX_TICK - 1 && sqrt_price_at_tick(tick + 1)? <= sqrt_price {
+        tick += 1;
+    }
+    Some(tick)
+}
+
+// a * b / denominator，中间结果用 U256
+pub(crate) fn mul_div(a: u128, b: u128, denominator: u128, round_up: bool) -> Option<u128> {
+    if denominator == 0 {
+        return None;
+    }
+    let product = U256::from(a) * U256::from(b);
+    let denominator = U256::from(denominator);
+    let mut result = product / denominator;
+    if round_up && !(product % denominator).is_zero() {
+        result += U256::one();
+    }
+    u128_from(result)
+}
+
+// L · 2^64 / √p，L · 2^64 不会超出 U256
+fn liquidity_over_sqrt_price(liquidity: u128, sqrt_price: u128, round_up: bool) -> Option<U256> {
+    if sqrt_price == 0 {
+        return None;
+    }
+    let numerator = U256::from(liquidity) << Q64_SHIFT;
+    let sqrt_price = U256::from(sqrt_price);
+    let mut result = numerator / sqrt_price;
+    if round_up && !(numerator % sqrt_price).is_zero() {
+        result += U256::one();
+    }
+    Some(result)
+}
+
+fn u128_from(value: U256) -> Option<u128> {
+    (value <= U256::from(u128::MAX)).then(|| value.as_u128())
+}
+
+/// 价格在 [sqrt_price_lower, sqrt_price_upper] 之间移动时 token A 的变化量
+///
+/// 按 L/√pa − L/√pb 计算，两项分别取整，保证结果整体向指定方向舍入
+pub fn amount_a_delta(sqrt_price_lower: u128, sqrt_price_upper: u128, liquidity: u128, round_up: bool) -> Option<u128> {
+    if sqrt_price_lower > sqrt_price_upper {
+        return None;
+    }
+    let lower_term = liquidity_over_sqrt_price(liquidity, sqrt_price_lower, round_up)?;
+    let upper_term = liquidity_over_sqrt_price(liquidity, sqrt_price_upper, !round_up)?;
+    u128_from(lower_term.saturating_sub(upper_term))
+}
+
+/// 价格在 [sqrt_price_lower, sqrt_price_upper] 之间移动时 token B 的变化量
+pub fn amount_b_delta(sqrt_price_lower: u128, sqrt_price_upper: u128, liquidity: u128, round_up: bool) -> Option<u128> {
+    mul_div(liquidity, sqrt_price_upper.checked_sub(sqrt_price_lower)?, 1 << Q64_SHIFT, round_up)
+}
+
+/// 存入 `amount`（扣除手续费之后）后的新价格
+///
+/// * A -> B：√p' = L / (L/√p + Δa)，向上取整
+/// * B -> A：√p' = √p + Δb / L，向下取整
+pub fn next_sqrt_price_from_input(sqrt_price: u128, liquidity: u128, amount: u64, is_a_to_b: bool) -> Option<u128> {
+    if liquidity == 0 {
+        return None;
+    }
+    if amount == 0 {
+        return Some(sqrt_price);
+    }
+    if is_a_to_b {
+        let numerator = U256::from(liquidity) << Q64_SHIFT;
+        let denominator = liquidity_over_sqrt_price(liquidity, sqrt_price, false)?
+            .checked_add(U256::from(amount))?;
+        let mut result = numerator / denominator;
+        if !(numerator % denominator).is_zero() {
+            result += U256::one();
+        }
+        u128_from(result)
+    } else {
+        sqrt_price.checked_add(((amount as u128) << Q64_SHIFT) / liquidity)
+    }
+}
+
+/// 头寸在当前价格下对应的 token 数量
+///
+/// 价格低于区间时全部是 A，高于区间时全部是 B，在区间内两者都有
+pub fn amounts_for_liquidity(
+    sqrt_price: u128,
+    sqrt_price_lower: u128,
+    sqrt_price_upper: u128,
+    liquidity: u128,
+    round_up: bool,
+) -> Option<(u64, u64)> {
+    let (amount_a, amount_b) = if sqrt_price < sqrt_price_lower {
+        (amount_a_delta(sqrt_price_lower, sqrt_price_upper, liquidity, round_up)?, 0)
+    } else if sqrt_price < sqrt_price_upper {
+        (
+            amount_a_delta(sqrt_price, sqrt_price_upper, liquidity, round_up)?,
+            amount_b_delta(sqrt_price_lower, sqrt_price, liquidity, round_up)?,
+        )
+    } else {
+        (0, amount_b_delta(sqrt_price_lower, sqrt_price_upper, liquidity, round_up)?)
+    };
+    Some((u64::try_from(amount_a).ok()?, u64::try_from(amount_b).ok()?))
+}
+
+/// 一次兑换在单个 tick 区间内的结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SwapStep {
+    pub sqrt_price_next: u128,
+    pub amount_in: u64,         // 不含手续费
+    pub amount_out: u64,
+    pub fee_amount: u64,
+}
+
+/// 在流动性不变的区间内兑换，直到用完 `amount_remaining` 或者价格到达 `sqrt_price_target`
+///
+/// 方向由目标价格相对当前价格的位置决定；手续费按 fee_numerator / fee_denominator 从输入中扣除
+pub fn compute_swap_step(
+    sqrt_price_current: u128,
+    sqrt_price_target: u128,
+    liquidity: u128,
+    amount_remaining: u64,
+    fee_numerator: u64,
+    fee_denominator: u64,
+) -> Option<SwapStep> {
+    let is_a_to_b = sqrt_price_target <= sqrt_price_current;
+    let fee_complement = fee_denominator.checked_sub(fee_numerator)?;
+    let amount_remaining_less_fee = (amount_remaining as u128)
+        .checked_mul(fee_complement as u128)?
+        .checked_div(fee_denominator as u128)? as u64;
+
+    // 到达目标价格需要的输入，超出 u128 时肯定到达不了
+    let amount_to_target = if is_a_to_b {
+        amount_a_delta(sqrt_price_target, sqrt_price_current, liquidity, true)
+    } else {
+        amount_b_delta(sqrt_price_current, sqrt_price_target, liquidity, true)
+    }
+    .unwrap_or(u128::MAX);
+
+    let (sqrt_price_next, amount_in) = if amount_remaining_less_fee as u128 >= amount_to_target {
+        (sqrt_price_target, amount_to_target as u64)
+    } else {
+        let sqrt_price_next =
+            next_sqrt_price_from_input(sqrt_price_current, liquidity, amount_remaining_less_fee, is_a_to_b)?;
+        // 新价格朝对池子有利的方向取整，重新计算的输入不会超过 amount_remaining_less_fee
+        let amount_in = if is_a_to_b {
+            amount_a_delta(sqrt_price_next, sqrt_price_current, liquidity, true)?
+        } else {
+            amount_b_delta(sqrt_price_current, sqrt_price_next, liquidity, true)?
+        };
+        (sqrt_price_next, u64::try_from(amount_in).ok()?)
+    };
+
+    let amount_out = if is_a_to_b {
+        amount_b_delta(sqrt_price_next, sqrt_price_current, liquidity, false)?
+    } else {
+        amount_a_delta(sqrt_price_current, sqrt_price_next, liquidity, false)?
+    };
+
+    let fee_amount = if sqrt_price_next == sqrt_price_target {
+        // 到达目标价格时只对实际用掉的输入收费（向上取整），不超过剩余的输入
+        mul_div(amount_in as u128, fee_numerator as u128, fee_complement as u128, true)?
+            .min(amount_remaining.checked_sub(amount_in)? as u128) as u64
+    } else {
+        // 输入全部用完，剩下的都是手续费
+        amount_remaining.checked_sub(amount_in)?
+    };
+
+    Some(SwapStep {
+        sqrt_price_next,
+        amount_in,
+        amount_out: u64::try_from(amount_out).ok()?,
+        fee_amount,
+    })
+}
+
+/// 手续费摊到每单位流动性上的增长（Q64.64），没有流动性时不累计
+pub fn fee_growth_delta(fee_amount: u64, liquidity: u128) -> u128 {
+    if liquidity == 0 {
+        return 0;
+    }
+    ((fee_amount as u128) << Q64_SHIFT) / liquidity
+}
+
+/// 给流动性加上一个有符号的变化量
+pub fn add_liquidity_delta(liquidity: u128, delta: i128) -> Option<u128> {
+    if delta >= 0 {
+        liquidity.checked_add(delta as u128)
+    } else {
+        liquidity.checked_sub(delta.unsigned_abs())
+    }
+}
+
+/// 区间 [tick_lower, tick_upper) 内每单位流动性累计的手续费
+///
+/// 和 Uniswap V3 一样用 global − below − above，各项都按 2^128 回绕，只有差值有意义
+pub fn fee_growth_inside(pool: &ClPool, lower: &Tick, upper: &Tick, tick_lower: i32, tick_upper: i32) -> (u128, u128) {
+    let global_a = pool.fee_growth_global_a_x64;
+    let global_b = pool.fee_growth_global_b_x64;
+    let (below_a, below_b) = if pool.tick_current >= tick_lower {
+        (lower.fee_growth_outside_a_x64, lower.fee_growth_outside_b_x64)
+    } else {
+        (
+            global_a.wrapping_sub(lower.fee_growth_outside_a_x64),
+            global_b.wrapping_sub(lower.fee_growth_outside_b_x64),
+        )
+    };
+    let (above_a, above_b) = if pool.tick_current < tick_upper {
+        (upper.fee_growth_outside_a_x64, upper.fee_growth_outside_b_x64)
+    } else {
+        (
+            global_a.wrapping_sub(upper.fee_growth_outside_a_x64),
+            global_b.wrapping_sub(upper.fee_growth_outside_b_x64),
+        )
+    };
+    (
+        global_a.wrapping_sub(below_a).wrapping_sub(above_a),
+        global_b.wrapping_sub(below_b).wrapping_sub(above_b),
+    )
+}
+
+/// 头寸边界上的 tick 增减流动性
+pub fn update_tick(pool: &ClPool, tick: &mut Tick, tick_index: i32, liquidity_delta: i128, is_upper: bool) -> Option<()> {
+    let was_initialized = tick.is_initialized();
+    tick.liquidity_gross = add_liquidity_delta(tick.liquidity_gross, liquidity_delta)?;
+    // 约定 tick 初始化之前的手续费都发生在它下方
+    if !was_initialized && tick.is_initialized() && tick_index <= pool.tick_current {
+        tick.fee_growth_outside_a_x64 = pool.fee_growth_global_a_x64;
+        tick.fee_growth_outside_b_x64 = pool.fee_growth_global_b_x64;
+    }
+    // 价格从下往上穿过下边界时流动性增加，穿过上边界时减少
+    tick.liquidity_net = if is_upper {
+        tick.liquidity_net.checked_sub(liquidity_delta)?
+    } else {
+        tick.liquidity_net.checked_add(liquidity_delta)?
+    };
+    Some(())
+}
+
+/// 价格穿过 tick：outside 翻转到另一侧，返回 liquidity_net
+pub fn cross_tick(tick: &mut Tick, fee_growth_global_a: u128, fee_growth_global_b: u128) -> i128 {
+    tick.fee_growth_outside_a_x64 = fee_growth_global_a.wrapping_sub(tick.fee_growth_outside_a_x64);
+    tick.fee_growth_outside_b_x64 = fee_growth_global_b.wrapping_sub(tick.fee_growth_outside_b_x64);
+    tick.liquidity_net
+}
+
+// 按上次结算以来区间内的手续费增长计算头寸应得的手续费（向下取整）
+fn accrued_fees(liquidity: u128, fee_growth_delta: u128) -> Option<u64> {
+    u64::try_from(mul_div(liquidity, fee_growth_delta, 1 << Q64_SHIFT, false)?).ok()
+}
+
+/// 修改头寸的流动性，先把之前产生的手续费结算到 fees_owed
+///
+/// `liquidity_delta` 为 0 时只结算手续费；流动性减到 0 的 tick 会被清空
+pub fn modify_position(
+    pool: &mut ClPool,
+    position: &mut Position,
+    lower: &mut Tick,
+    upper: &mut Tick,
+    liquidity_delta: i128,
+) -> Option<()> {
+    if liquidity_delta != 0 {
+        update_tick(pool, lower, position.tick_lower, liquidity_delta, false)?;
+        update_tick(pool, upper, position.tick_upper, liquidity_delta, true)?;
+    }
+
+    let (inside_a, inside_b) = fee_growth_inside(pool, lower, upper, position.tick_lower, position.tick_upper);
+    let fees_a = accrued_fees(position.liquidity, inside_a.wrapping_sub(position.fee_growth_inside_a_last_x64))?;
+    let fees_b = accrued_fees(position.liquidity, inside_b.wrapping_sub(position.fee_growth_inside_b_last_x64))?;
+    position.fees_owed_a = position.fees_owed_a.checked_add(fees_a)?;
+    position.fees_owed_b = position.fees_owed_b.checked_add(fees_b)?;
+    position.fee_growth_inside_a_last_x64 = inside_a;
+    position.fee_growth_inside_b_last_x64 = inside_b;
+    position.liquidity = add_liquidity_delta(position.liquidity, liquidity_delta)?;
+
+    // 当前价格在区间内时，头寸的流动性立即生效
+    if (position.tick_lower..position.tick_upper).contains(&pool.tick_current) {
+        pool.liquidity = add_liquidity_delta(pool.liquidity, liquidity_delta)?;
+    }
+
+    if !lower.is_initialized() {
+        *lower = Tick::default();
+    }
+    if !upper.is_initialized() {
+        *upper = Tick::default();
+    }
+    Some(())
+}
+
+/// 取出头寸上下边界所在的 tick 并执行 `f`，两个边界可能落在同一个 TickArray 中
+pub fn with_position_ticks<R>(
+    lower_array: &AccountLoader<TickArray>,
+    upper_array: &AccountLoader<TickArray>,
+    tick_lower: i32,
+    tick_upper: i32,
+    tick_spacing: u16,
+    f: impl FnOnce(&mut Tick, &mut Tick) -> Result<R>,
+) -> Result<R> {
+    if lower_array.key() == upper_array.key() {
+        let mut array = lower_array.load_mut()?;
+        let lower = array.offset(tick_lower, tick_spacing).ok_or(AmmError::InvalidTickArray)?;
+        let upper = array.offset(tick_upper, tick_spacing).ok_or(AmmError::InvalidTickArray)?;
+        // tick_lower < tick_upper，所以 lower < upper
+        let (head, tail) = array.ticks.split_at_mut(upper);
+        f(&mut head[lower], &mut tail[0])
+    } else {
+        let mut lower_array = lower_array.load_mut()?;
+        let mut upper_array = upper_array.load_mut()?;
+        let lower = lower_array
+            .tick_mut(tick_lower, tick_spacing)
+            .ok_or(AmmError::InvalidTickArray)?;
+        let upper = upper_array
+            .tick_mut(tick_upper, tick_spacing)
+            .ok_or(AmmError::InvalidTickArray)?;
+        f(lower, upper)
+    }
+}
+
+/// 兑换方向上下一个需要停下来的 tick，只在包含起点的那个 TickArray 内查找
+///
+/// * A -> B：不大于 tick_current 的最近一个已初始化 tick，没有则停在数组的第一个 tick
+/// * B -> A：大于 tick_current 的最近一个已初始化 tick，没有则停在数组的最后一个 tick
+///
+/// # Returns
+/// * `(tick, initialized)` - tick 已经限制在 [MIN_TICK, MAX_TICK] 内
+/// * `None` - 需要的 TickArray 没有传入
+pub fn next_tick_in_array(
+    tick_arrays: &[AccountLoader<TickArray>],
+    tick_current: i32,
+    tick_spacing: u16,
+    is_a_to_b: bool,
+) -> Result<Option<(i32, bool)>> {
+    let spacing = tick_spacing as i32;
+    // 第一个候选 tick：A -> B 包括当前 tick 本身，B -> A 从下一个可用 tick 开始
+    let first = if is_a_to_b {
+        tick_current.div_euclid(spacing) * spacing
+    } else {
+        (tick_current.div_euclid(spacing) + 1) * spacing
+    };
+    let start = TickArray::start_index_for(first, tick_spacing);
+    let Some(loader) = find_tick_array(tick_arrays, start)? else {
+        return Ok(None);
+    };
+    let array = loader.load()?;
+
+    let first_offset = ((first - start) / spacing) as usize;
+    let found = if is_a_to_b {
+        (0..=first_offset).rev().find(|&offset| array.ticks[offset].is_initialized())
+    } else {
+        (first_offset..TICK_ARRAY_SIZE).find(|&offset| array.ticks[offset].is_initialized())
+    };
+    let (offset, initialized) = match found {
+        Some(offset) => (offset, true),
+        None if is_a_to_b => (0, false),
+        None => (TICK_ARRAY_SIZE - 1, false),
+    };
+    let tick = (start + offset as i32 * spacing).clamp(MIN_TICK, MAX_TICK);
+    Ok(Some((tick, initialized)))
+}
+
+/// 按起始 tick 在传入的 TickArray 中查找
+pub fn find_tick_array<'a, 'info>(
+    tick_arrays: &'a [AccountLoader<'info, TickArray>],
+    start_tick_index: i32,
+) -> Result<Option<&'a AccountLoader<'info, TickArray>>> {
+    for loader in tick_arrays {
+        if loader.load()?.start_tick_index == start_tick_index {
+            return Ok(Some(loader));
+        }
+    }
+    Ok(None)
+}
@@ -1,9 +1,14 @@
 use anchor_lang::prelude::*;
 use anchor_spl::token::{Mint, Token, TokenAccount};
 use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::metadata::Metadata;
 
-use crate::state::PoolState;
+use crate::state::{
+    AccessPass, BatchAuction, BatchOrder, ClPool, DcaVault, Farm, FarmStake, FeeOverride, LimitOrder, LongTermOrder,
+    LpLock, PoolGatekeeper, PoolRegistry, PoolRegistryPage, PoolState, Position, TickArray, Twamm,
+};
 use crate::errors::AmmError;
+use crate::math;
 
 /// 初始化池子的账户结构体
 #[derive(Accounts)]
@@ -18,7 +23,7 @@ pub struct Initialize<'info> {
         seeds = [b"pool", mint_a.as_ref(), mint_b.as_ref()],
         bump
     )]
-    pub pool_state: Account<'info, PoolState>,
+    pub pool_state: AccountLoader<'info, PoolState>,
     // 增加info的原因是生命周期与交易需要一致
     /// CHECK: 这个PDA只用作签名者，不存储数据。其地址由程序生成
     #[account(
@@ -50,11 +55,30 @@ pub struct Initialize<'info> {
     #[account(
         init,
         payer = admin,
-        mint::decimals = 9,
+        // 精度由两个代币的精度推出，见 math::lp_decimals
+        mint::decimals = math::lp_decimals(token_a.decimals, token_b.decimals),
         mint::authority = pool_authority,
     )]
     pub lp_mint: Account<'info, Mint>,
 
+    // 注册表：第一个池子创建时一起创建，之后每个池子追加到当前分页，写满一页时创建下一页
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = PoolRegistry::LEN,
+        seeds = [b"pool_registry"],
+        bump
+    )]
+    pub pool_registry: Box<Account<'info, PoolRegistry>>,
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = PoolRegistryPage::LEN,
+        seeds = [b"pool_registry_page".as_ref(), &pool_registry.next_page_index().to_le_bytes()],
+        bump
+    )]
+    pub registry_page: Box<Account<'info, PoolRegistryPage>>,
+
     pub system_program: Program<'info, System>,
     pub token_program: Program<'info, Token>,
 
@@ -64,7 +88,30 @@ pub struct Initialize<'info> {
     pub associated_token_program: Program<'info, AssociatedToken>,
 
     #[account(mut)] 
-    pub admin: Signer<'info>
+    pub admin: Signer<'info>,
+
+    // 可选：传入 Metaplex 程序和 lp_mint 的元数据 PDA 时为 LP 创建元数据，PDA 由 Metaplex 校验
+    pub token_metadata_program: Option<Program<'info, Metadata>>,
+    /// CHECK: lp_mint 的元数据 PDA，由 Metaplex 程序创建和校验
+    #[account(mut)]
+    pub lp_metadata: Option<UncheckedAccount<'info>>,
+    /// CHECK: 代币 A 的元数据 PDA，账户不存在或不匹配时改用 mint 地址作为符号
+    pub token_a_metadata: Option<UncheckedAccount<'info>>,
+    /// CHECK: 代币 B 的元数据 PDA，同上
+    pub token_b_metadata: Option<UncheckedAccount<'info>>,
+}
+
+/// 迁移旧版本池子的账户结构体
+#[derive(Accounts)]
+pub struct MigratePool<'info> {
+    /// CHECK: 旧版本的账户是 Borsh 布局，比 PoolState::LEN 短，不能用 AccountLoader<PoolState> 读取；discriminator 在指令中检查
+    #[account(mut, owner = crate::ID)]
+    pub pool_state: UncheckedAccount<'info>,
+
+    // 支付扩容需要补足的租金
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
 }
 
 /// 交换代币的账户结构体
@@ -75,16 +122,16 @@ pub struct Swap<'info> {
         has_one = token_a_vault @ AmmError::InvalidVault,
         has_one = token_b_vault @ AmmError::InvalidVault,
     )]
-    pub pool_state: Account<'info, PoolState>,
+    pub pool_state: AccountLoader<'info, PoolState>,
 
     #[account(
         mut,
-        constraint = user_token_a.mint == pool_state.token_a @ AmmError::InvalidUserToken
+        constraint = user_token_a.mint == pool_state.load()?.token_a @ AmmError::InvalidUserToken
     )]
     pub user_token_a: Account<'info, TokenAccount>,
     #[account(
         mut,
-        constraint = user_token_b.mint == pool_state.token_b @ AmmError::InvalidUserToken
+        constraint = user_token_b.mint == pool_state.load()?.token_b @ AmmError::InvalidUserToken
     )]
     pub user_token_b: Account<'info, TokenAccount>,
 
@@ -98,11 +145,35 @@ pub struct Swap<'info> {
     /// CHECK: 这个PDA只用作签名者，不存储数据
     #[account(
         seeds = [b"authority"],
-        bump = pool_state.auth_bump
+        bump = pool_state.load()?.auth_bump
     )]
     pub pool_authority: UncheckedAccount<'info>,
 
     pub token_program: Program<'info, Token>,
+
+    // 可选：推荐人接收推荐费的代币账户，Mint 必须是输入代币，在指令中按方向检查
+    #[account(mut)]
+    pub referrer_token_account: Option<Account<'info, TokenAccount>>,
+
+    // 可选：池子开启了 TWAMM 时必须传入，指令开始时先结算长期订单
+    #[account(mut)]
+    pub twamm: Option<AccountLoader<'info, Twamm>>,
+    #[account(mut)]
+    pub twamm_vault_a: Option<Box<Account<'info, TokenAccount>>>,
+    #[account(mut)]
+    pub twamm_vault_b: Option<Box<Account<'info, TokenAccount>>>,
+
+    // 可选：池子开启了许可模式时必须传入用户的 AccessPass 和池子的 PoolGatekeeper
+    #[account(
+        seeds = [b"access_pass", pool_state.key().as_ref(), user.key().as_ref()],
+        bump = access_pass.bump
+    )]
+    pub access_pass: Option<Box<Account<'info, AccessPass>>>,
+    #[account(
+        seeds = [b"pool_gatekeeper", pool_state.key().as_ref()],
+        bump = pool_gatekeeper.bump
+    )]
+    pub pool_gatekeeper: Option<Box<Account<'info, PoolGatekeeper>>>,
 }
 
 /// 添加流动性的账户结构体
@@ -115,12 +186,11 @@ pub struct AddLiquidity<'info> {
         has_one = token_b_vault @ AmmError::InvalidVault,
         has_one = lp_mint @ AmmError::InvalidLpMint,
     )]
-    // pub pool_state: Account<'info, PoolState>,
-    // 使用Box来优化内存使用
-    pub pool_state: Box<Account<'info, PoolState>>,
+    // 零拷贝加载，不会把整个 PoolState 复制到栈上
+    pub pool_state: AccountLoader<'info, PoolState>,
     #[account(
         seeds = [b"authority"],
-        bump = pool_state.auth_bump
+        bump = pool_state.load()?.auth_bump
     )]
     /// CHECK: 这个PDA只用作签名者，不存储数据。其地址由程序生成
     /// 不需要 mut，因为 PDA 只用作签名者，不存储数据
@@ -128,13 +198,13 @@ pub struct AddLiquidity<'info> {
     
     #[account(
         mut,
-        constraint = user_token_a.mint == pool_state.token_a @ AmmError::InvalidUserToken
+        constraint = user_token_a.mint == pool_state.load()?.token_a @ AmmError::InvalidUserToken
     )]
     // pub user_token_a: Account<'info, TokenAccount>,
     pub user_token_a: Box<Account<'info, TokenAccount>>,
     #[account(
         mut,
-        constraint = user_token_b.mint == pool_state.token_b @ AmmError::InvalidUserToken
+        constraint = user_token_b.mint == pool_state.load()?.token_b @ AmmError::InvalidUserToken
     )]
     pub user_token_b: Box<Account<'info, TokenAccount>>,
     #[account(mut)]
@@ -146,7 +216,7 @@ pub struct AddLiquidity<'info> {
     pub user: Signer<'info>,
     #[account(
         mut,
-        constraint = lp_mint.key() == pool_state.lp_mint @ AmmError::InvalidLpMint
+        constraint = lp_mint.key() == pool_state.load()?.lp_mint @ AmmError::InvalidLpMint
     )]
     pub lp_mint: Box<Account<'info, Mint>>,
 
@@ -170,7 +240,7 @@ pub struct AddLiquidity<'info> {
 
     #[account(
         mut,
-        constraint = protocol_fee_recipient.owner == pool_state.protocol_fee_recipient @ AmmError::InvalidUserToken,
+        constraint = protocol_fee_recipient.owner == pool_state.load()?.protocol_fee_recipient @ AmmError::InvalidUserToken,
         constraint = protocol_fee_recipient.mint == lp_mint.key() @ AmmError::InvalidLpMint
     )]
     pub protocol_fee_recipient: Account<'info, TokenAccount>,
@@ -178,6 +248,26 @@ pub struct AddLiquidity<'info> {
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
     pub token_program: Program<'info, Token>,
+
+    // 可选：池子开启了 TWAMM 时必须传入，指令开始时先结算长期订单
+    #[account(mut)]
+    pub twamm: Option<AccountLoader<'info, Twamm>>,
+    #[account(mut)]
+    pub twamm_vault_a: Option<Box<Account<'info, TokenAccount>>>,
+    #[account(mut)]
+    pub twamm_vault_b: Option<Box<Account<'info, TokenAccount>>>,
+
+    // 可选：池子开启了许可模式时必须传入用户的 AccessPass 和池子的 PoolGatekeeper
+    #[account(
+        seeds = [b"access_pass", pool_state.key().as_ref(), user.key().as_ref()],
+        bump = access_pass.bump
+    )]
+    pub access_pass: Option<Box<Account<'info, AccessPass>>>,
+    #[account(
+        seeds = [b"pool_gatekeeper", pool_state.key().as_ref()],
+        bump = pool_gatekeeper.bump
+    )]
+    pub pool_gatekeeper: Option<Box<Account<'info, PoolGatekeeper>>>,
 }
 
 /// 移除流动性的账户结构体
@@ -195,11 +285,11 @@ pub struct RemoveLiquidity<'info> {
         has_one = token_b_vault @ AmmError::InvalidVault,
         has_one = lp_mint @ AmmError::InvalidLpMint,
     )]
-    pub pool_state: Box<Account<'info, PoolState>>,
+    pub pool_state: AccountLoader<'info, PoolState>,
 
     #[account(
         seeds = [b"authority"],
-        bump = pool_state.auth_bump
+        bump = pool_state.load()?.auth_bump
     )]
     /// CHECK: 这个PDA只用作签名者，不存储数据。其地址由程序生成
     pub pool_authority: UncheckedAccount<'info>,
@@ -207,12 +297,12 @@ pub struct RemoveLiquidity<'info> {
     
     #[account(
         mut,
-        constraint = user_token_a.mint == pool_state.token_a @ AmmError::InvalidUserToken
+        constraint = user_token_a.mint == pool_state.load()?.token_a @ AmmError::InvalidUserToken
     )]
     pub user_token_a: Box<Account<'info, TokenAccount>>,
     #[account(
         mut,
-        constraint = user_token_b.mint == pool_state.token_b @ AmmError::InvalidUserToken
+        constraint = user_token_b.mint == pool_state.load()?.token_b @ AmmError::InvalidUserToken
     )]
     pub user_token_b: Box<Account<'info, TokenAccount>>,
     
@@ -225,7 +315,7 @@ pub struct RemoveLiquidity<'info> {
     pub user: Signer<'info>,
     #[account(
         mut,
-        constraint = lp_mint.key() == pool_state.lp_mint @ AmmError::InvalidLpMint
+        constraint = lp_mint.key() == pool_state.load()?.lp_mint @ AmmError::InvalidLpMint
     )]
     pub lp_mint: Box<Account<'info, Mint>>,
 
@@ -238,7 +328,7 @@ pub struct RemoveLiquidity<'info> {
 
     #[account(
         mut,
-        constraint = protocol_fee_recipient.owner == pool_state.protocol_fee_recipient @ AmmError::InvalidUserToken,
+        constraint = protocol_fee_recipient.owner == pool_state.load()?.protocol_fee_recipient @ AmmError::InvalidUserToken,
         constraint = protocol_fee_recipient.mint == lp_mint.key() @ AmmError::InvalidLpMint
     )]
     pub protocol_fee_recipient: Account<'info, TokenAccount>,
@@ -246,6 +336,26 @@ pub struct RemoveLiquidity<'info> {
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
     pub token_program: Program<'info, Token>,
+
+    // 可选：池子开启了 TWAMM 时必须传入，指令开始时先结算长期订单
+    #[account(mut)]
+    pub twamm: Option<AccountLoader<'info, Twamm>>,
+    #[account(mut)]
+    pub twamm_vault_a: Option<Box<Account<'info, TokenAccount>>>,
+    #[account(mut)]
+    pub twamm_vault_b: Option<Box<Account<'info, TokenAccount>>>,
+
+    // 可选：池子开启了许可模式时必须传入用户的 AccessPass 和池子的 PoolGatekeeper
+    #[account(
+        seeds = [b"access_pass", pool_state.key().as_ref(), user.key().as_ref()],
+        bump = access_pass.bump
+    )]
+    pub access_pass: Option<Box<Account<'info, AccessPass>>>,
+    #[account(
+        seeds = [b"pool_gatekeeper", pool_state.key().as_ref()],
+        bump = pool_gatekeeper.bump
+    )]
+    pub pool_gatekeeper: Option<Box<Account<'info, PoolGatekeeper>>>,
 }
 
 #[derive(Accounts)]
@@ -255,7 +365,7 @@ pub struct UpdateConfig<'info> {
         // 确保只有当前的 admin 能签收这笔交易
         has_one = admin @ AmmError::Unauthorized 
     )]
-    pub pool_state: Account<'info, PoolState>,
+    pub pool_state: AccountLoader<'info, PoolState>,
     
     pub admin: Signer<'info>,
 }
@@ -265,10 +375,1679 @@ pub struct ClaimAdmin<'info> {
     #[account(
         mut,
         // 簽名的必須是 pool_state 中記錄的 pending_admin
-        constraint = pool_state.pending_admin == Some(pending_admin.key()) @ AmmError::Unauthorized
+        constraint = pool_state.load()?.pending_admin() == Some(pending_admin.key()) @ AmmError::Unauthorized
     )]
-    pub pool_state: Account<'info, PoolState>,
+    pub pool_state: AccountLoader<'info, PoolState>,
     
     // 必須是新管理員簽名
     pub pending_admin: Signer<'info>, 
 }
+
+/// 读取几何平均 TWAP 的账户结构体，全部只读
+#[derive(Accounts)]
+pub struct GetGeometricTwap<'info> {
+    #[account(
+        has_one = token_a_vault @ AmmError::InvalidVault,
+        has_one = token_b_vault @ AmmError::InvalidVault,
+    )]
+    pub pool_state: AccountLoader<'info, PoolState>,
+
+    // 金库余额用于补算最近一次更新之后的累加器
+    pub token_a_vault: Account<'info, TokenAccount>,
+    pub token_b_vault: Account<'info, TokenAccount>,
+}
+
+/// 创建或修改交易者的折扣费率
+#[derive(Accounts)]
+pub struct SetFeeOverride<'info> {
+    #[account(has_one = admin @ AmmError::Unauthorized)]
+    pub pool_state: AccountLoader<'info, PoolState>,
+
+    /// CHECK: 只作为 PDA 种子和记录，不需要签名
+    pub trader: UncheckedAccount<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = FeeOverride::LEN,
+        seeds = [b"fee_override", pool_state.key().as_ref(), trader.key().as_ref()],
+        bump
+    )]
+    pub fee_override: Account<'info, FeeOverride>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+/// 删除交易者的折扣费率
+#[derive(Accounts)]
+pub struct RemoveFeeOverride<'info> {
+    #[account(has_one = admin @ AmmError::Unauthorized)]
+    pub pool_state: AccountLoader<'info, PoolState>,
+
+    #[account(
+        mut,
+        close = admin,
+        seeds = [b"fee_override", pool_state.key().as_ref(), fee_override.trader.as_ref()],
+        bump = fee_override.bump
+    )]
+    pub fee_override: Account<'info, FeeOverride>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+}
+
+/// 锁仓 LP 的账户结构体
+#[derive(Accounts)]
+pub struct LockLp<'info> {
+    #[account(
+        mut,
+        has_one = token_a_vault @ AmmError::InvalidVault,
+        has_one = token_b_vault @ AmmError::InvalidVault,
+        has_one = lp_mint @ AmmError::InvalidLpMint,
+    )]
+    pub pool_state: AccountLoader<'info, PoolState>,
+
+    /// CHECK: 这个PDA只用作托管账户的 authority，不存储数据
+    #[account(
+        seeds = [b"authority"],
+        bump = pool_state.load()?.auth_bump
+    )]
+    pub pool_authority: UncheckedAccount<'info>,
+
+    // 储备金用于记录锁仓本金，结算 TWAMM 时会有虚拟成交转入转出
+    #[account(mut)]
+    pub token_a_vault: Box<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub token_b_vault: Box<Account<'info, TokenAccount>>,
+    pub lp_mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = LpLock::LEN,
+        seeds = [b"lp_lock", pool_state.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub lp_lock: Box<Account<'info, LpLock>>,
+
+    // 托管 LP 的代币账户，authority 是 pool_authority，用户无法直接转出
+    #[account(
+        init_if_needed,
+        payer = user,
+        seeds = [b"lp_lock_vault", lp_lock.key().as_ref()],
+        bump,
+        token::mint = lp_mint,
+        token::authority = pool_authority,
+    )]
+    pub lock_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = user_lp_token.mint == lp_mint.key() @ AmmError::InvalidLpMint
+    )]
+    pub user_lp_token: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+
+    // 可选：池子开启了 TWAMM 时必须传入，记录本金前先把长期订单结算到现在
+    #[account(mut)]
+    pub twamm: Option<AccountLoader<'info, Twamm>>,
+    #[account(mut)]
+    pub twamm_vault_a: Option<Box<Account<'info, TokenAccount>>>,
+    #[account(mut)]
+    pub twamm_vault_b: Option<Box<Account<'info, TokenAccount>>>,
+}
+
+/// 到期取回 LP 的账户结构体
+#[derive(Accounts)]
+pub struct UnlockLp<'info> {
+    #[account(has_one = lp_mint @ AmmError::InvalidLpMint)]
+    pub pool_state: AccountLoader<'info, PoolState>,
+
+    /// CHECK: 这个PDA只用作签名者，不存储数据
+    #[account(
+        seeds = [b"authority"],
+        bump = pool_state.load()?.auth_bump
+    )]
+    pub pool_authority: UncheckedAccount<'info>,
+
+    pub lp_mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        mut,
+        close = user,
+        seeds = [b"lp_lock", pool_state.key().as_ref(), user.key().as_ref()],
+        bump = lp_lock.bump,
+        has_one = lock_vault,
+    )]
+    pub lp_lock: Box<Account<'info, LpLock>>,
+
+    #[account(mut)]
+    pub lock_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = user_lp_token.mint == lp_mint.key() @ AmmError::InvalidLpMint
+    )]
+    pub user_lp_token: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+/// 锁仓期间领取手续费的账户结构体，和移除流动性一样会结算协议费
+#[derive(Accounts)]
+pub struct ClaimLpLockFees<'info> {
+    #[account(
+        mut,
+        has_one = token_a_vault @ AmmError::InvalidVault,
+        has_one = token_b_vault @ AmmError::InvalidVault,
+        has_one = lp_mint @ AmmError::InvalidLpMint,
+    )]
+    pub pool_state: AccountLoader<'info, PoolState>,
+
+    /// CHECK: 这个PDA只用作签名者，不存储数据
+    #[account(
+        seeds = [b"authority"],
+        bump = pool_state.load()?.auth_bump
+    )]
+    pub pool_authority: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"lp_lock", pool_state.key().as_ref(), user.key().as_ref()],
+        bump = lp_lock.bump,
+        has_one = lock_vault,
+    )]
+    pub lp_lock: Box<Account<'info, LpLock>>,
+
+    #[account(mut)]
+    pub lock_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = user_token_a.mint == pool_state.load()?.token_a @ AmmError::InvalidUserToken
+    )]
+    pub user_token_a: Box<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        constraint = user_token_b.mint == pool_state.load()?.token_b @ AmmError::InvalidUserToken
+    )]
+    pub user_token_b: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub token_a_vault: Box<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub token_b_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub lp_mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        mut,
+        constraint = protocol_fee_recipient.owner == pool_state.load()?.protocol_fee_recipient @ AmmError::InvalidUserToken,
+        constraint = protocol_fee_recipient.mint == lp_mint.key() @ AmmError::InvalidLpMint
+    )]
+    pub protocol_fee_recipient: Account<'info, TokenAccount>,
+
+    pub user: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+
+    // 可选：池子开启了 TWAMM 时必须传入，领取前先把长期订单结算到现在
+    #[account(mut)]
+    pub twamm: Option<AccountLoader<'info, Twamm>>,
+    #[account(mut)]
+    pub twamm_vault_a: Option<Box<Account<'info, TokenAccount>>>,
+    #[account(mut)]
+    pub twamm_vault_b: Option<Box<Account<'info, TokenAccount>>>,
+
+    // 可选：池子开启了许可模式时必须传入用户的 AccessPass 和池子的 PoolGatekeeper
+    #[account(
+        seeds = [b"access_pass", pool_state.key().as_ref(), user.key().as_ref()],
+        bump = access_pass.bump
+    )]
+    pub access_pass: Option<Box<Account<'info, AccessPass>>>,
+    #[account(
+        seeds = [b"pool_gatekeeper", pool_state.key().as_ref()],
+        bump = pool_gatekeeper.bump
+    )]
+    pub pool_gatekeeper: Option<Box<Account<'info, PoolGatekeeper>>>,
+}
+
+/// 创建流动性挖矿的账户结构体，每个池子只有一个 Farm
+#[derive(Accounts)]
+pub struct CreateFarm<'info> {
+    #[account(
+        has_one = admin @ AmmError::Unauthorized,
+        has_one = lp_mint @ AmmError::InvalidLpMint,
+    )]
+    pub pool_state: AccountLoader<'info, PoolState>,
+
+    /// CHECK: 这个PDA只用作质押金库的 authority，不存储数据
+    #[account(
+        seeds = [b"authority"],
+        bump = pool_state.load()?.auth_bump
+    )]
+    pub pool_authority: UncheckedAccount<'info>,
+
+    pub lp_mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = Farm::LEN,
+        seeds = [b"farm", pool_state.key().as_ref()],
+        bump
+    )]
+    pub farm: Box<Account<'info, Farm>>,
+
+    // 托管质押 LP 的代币账户
+    #[account(
+        init,
+        payer = admin,
+        seeds = [b"farm_stake_vault", farm.key().as_ref()],
+        bump,
+        token::mint = lp_mint,
+        token::authority = pool_authority,
+    )]
+    pub stake_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+}
+
+/// 添加奖励代币的账户结构体，管理员从 admin_reward_token 存入全部奖励
+#[derive(Accounts)]
+pub struct AddFarmReward<'info> {
+    #[account(has_one = admin @ AmmError::Unauthorized)]
+    pub pool_state: AccountLoader<'info, PoolState>,
+
+    /// CHECK: 这个PDA只用作奖励金库的 authority，不存储数据
+    #[account(
+        seeds = [b"authority"],
+        bump = pool_state.load()?.auth_bump
+    )]
+    pub pool_authority: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"farm", pool_state.key().as_ref()],
+        bump = farm.bump,
+    )]
+    pub farm: Box<Account<'info, Farm>>,
+
+    pub reward_mint: Box<Account<'info, Mint>>,
+
+    // 同一种代币再次添加时沿用已有的奖励金库
+    #[account(
+        init_if_needed,
+        payer = admin,
+        seeds = [b"farm_reward_vault", farm.key().as_ref(), reward_mint.key().as_ref()],
+        bump,
+        token::mint = reward_mint,
+        token::authority = pool_authority,
+    )]
+    pub reward_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = admin_reward_token.mint == reward_mint.key() @ AmmError::InvalidUserToken
+    )]
+    pub admin_reward_token: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+}
+
+/// 质押 LP 的账户结构体
+#[derive(Accounts)]
+pub struct StakeLp<'info> {
+    #[account(has_one = lp_mint @ AmmError::InvalidLpMint)]
+    pub pool_state: AccountLoader<'info, PoolState>,
+
+    pub lp_mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        mut,
+        seeds = [b"farm", pool_state.key().as_ref()],
+        bump = farm.bump,
+        has_one = stake_vault,
+    )]
+    pub farm: Box<Account<'info, Farm>>,
+
+    #[account(mut)]
+    pub stake_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = FarmStake::LEN,
+        seeds = [b"farm_stake", farm.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub farm_stake: Box<Account<'info, FarmStake>>,
+
+    #[account(
+        mut,
+        constraint = user_lp_token.mint == lp_mint.key() @ AmmError::InvalidLpMint
+    )]
+    pub user_lp_token: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+}
+
+/// 取回质押 LP 的账户结构体
+#[derive(Accounts)]
+pub struct UnstakeLp<'info> {
+    #[account(has_one = lp_mint @ AmmError::InvalidLpMint)]
+    pub pool_state: AccountLoader<'info, PoolState>,
+
+    /// CHECK: 这个PDA只用作签名者，不存储数据
+    #[account(
+        seeds = [b"authority"],
+        bump = pool_state.load()?.auth_bump
+    )]
+    pub pool_authority: UncheckedAccount<'info>,
+
+    pub lp_mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        mut,
+        seeds = [b"farm", pool_state.key().as_ref()],
+        bump = farm.bump,
+        has_one = stake_vault,
+    )]
+    pub farm: Box<Account<'info, Farm>>,
+
+    #[account(mut)]
+    pub stake_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [b"farm_stake", farm.key().as_ref(), user.key().as_ref()],
+        bump = farm_stake.bump,
+    )]
+    pub farm_stake: Box<Account<'info, FarmStake>>,
+
+    #[account(
+        mut,
+        constraint = user_lp_token.mint == lp_mint.key() @ AmmError::InvalidLpMint
+    )]
+    pub user_lp_token: Box<Account<'info, TokenAccount>>,
+
+    pub user: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+/// 领取挖矿奖励的账户结构体，一次领取一种奖励代币
+#[derive(Accounts)]
+pub struct ClaimRewards<'info> {
+    pub pool_state: AccountLoader<'info, PoolState>,
+
+    /// CHECK: 这个PDA只用作签名者，不存储数据
+    #[account(
+        seeds = [b"authority"],
+        bump = pool_state.load()?.auth_bump
+    )]
+    pub pool_authority: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"farm", pool_state.key().as_ref()],
+        bump = farm.bump,
+    )]
+    pub farm: Box<Account<'info, Farm>>,
+
+    #[account(
+        mut,
+        seeds = [b"farm_stake", farm.key().as_ref(), user.key().as_ref()],
+        bump = farm_stake.bump,
+    )]
+    pub farm_stake: Box<Account<'info, FarmStake>>,
+
+    // 和 reward_index 对应的奖励金库，在指令中校验
+    #[account(mut)]
+    pub reward_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = user_reward_token.mint == reward_vault.mint @ AmmError::InvalidUserToken
+    )]
+    pub user_reward_token: Box<Account<'info, TokenAccount>>,
+
+    pub user: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+/// 修改 LP 元数据的账户结构体
+#[derive(Accounts)]
+pub struct UpdateLpMetadata<'info> {
+    #[account(has_one = admin @ AmmError::Unauthorized)]
+    pub pool_state: AccountLoader<'info, PoolState>,
+
+    /// CHECK: 这个PDA是所有 LP 元数据的 update authority，只用作签名者
+    #[account(
+        seeds = [b"authority"],
+        bump = pool_state.load()?.auth_bump
+    )]
+    pub pool_authority: UncheckedAccount<'info>,
+
+    // pool_authority 是所有池子共用的，必须限定为这个池子 lp_mint 的元数据
+    /// CHECK: lp_mint 的元数据 PDA，由 Metaplex 程序反序列化
+    #[account(
+        mut,
+        seeds = [b"metadata", token_metadata_program.key().as_ref(), pool_state.load()?.lp_mint.as_ref()],
+        seeds::program = token_metadata_program.key(),
+        bump
+    )]
+    pub lp_metadata: UncheckedAccount<'info>,
+
+    pub admin: Signer<'info>,
+    pub token_metadata_program: Program<'info, Metadata>,
+}
+
+/// 创建集中流动性池子的账户结构体
+#[derive(Accounts)]
+#[instruction(tick_spacing: u16)]
+pub struct CreateClPool<'info> {
+    // 同一对代币可以按不同的 tick_spacing 创建多个池子
+    #[account(
+        init,
+        payer = admin,
+        space = ClPool::LEN,
+        seeds = [b"cl_pool", token_a.key().as_ref(), token_b.key().as_ref(), &tick_spacing.to_le_bytes()],
+        bump
+    )]
+    pub cl_pool: AccountLoader<'info, ClPool>,
+
+    /// CHECK: 这个PDA只用作签名者，不存储数据。其地址由程序生成
+    #[account(
+        seeds = [b"authority"],
+        bump
+    )]
+    pub pool_authority: UncheckedAccount<'info>,
+
+    pub token_a: Box<Account<'info, Mint>>,
+    pub token_b: Box<Account<'info, Mint>>,
+
+    // 恒定乘积池子的金库是 pool_authority 的 ATA，这里用 PDA 金库，避免同一对代币的两种池子共用余额
+    #[account(
+        init,
+        payer = admin,
+        seeds = [b"cl_vault", cl_pool.key().as_ref(), token_a.key().as_ref()],
+        bump,
+        token::mint = token_a,
+        token::authority = pool_authority,
+    )]
+    pub token_a_vault: Box<Account<'info, TokenAccount>>,
+    #[account(
+        init,
+        payer = admin,
+        seeds = [b"cl_vault", cl_pool.key().as_ref(), token_b.key().as_ref()],
+        bump,
+        token::mint = token_b,
+        token::authority = pool_authority,
+    )]
+    pub token_b_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+/// 创建 TickArray 的账户结构体，任何人都可以付租金创建
+#[derive(Accounts)]
+#[instruction(start_tick_index: i32)]
+pub struct InitTickArray<'info> {
+    pub cl_pool: AccountLoader<'info, ClPool>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = TickArray::LEN,
+        seeds = [b"tick_array", cl_pool.key().as_ref(), &start_tick_index.to_le_bytes()],
+        bump
+    )]
+    pub tick_array: AccountLoader<'info, TickArray>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+/// 开立集中流动性头寸的账户结构体
+#[derive(Accounts)]
+#[instruction(tick_lower: i32, tick_upper: i32)]
+pub struct OpenPosition<'info> {
+    pub cl_pool: AccountLoader<'info, ClPool>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = Position::LEN,
+        seeds = [
+            b"position",
+            cl_pool.key().as_ref(),
+            owner.key().as_ref(),
+            &tick_lower.to_le_bytes(),
+            &tick_upper.to_le_bytes(),
+        ],
+        bump
+    )]
+    pub position: Box<Account<'info, Position>>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+/// 增加头寸流动性的账户结构体
+#[derive(Accounts)]
+pub struct IncreaseLiquidity<'info> {
+    #[account(
+        mut,
+        has_one = token_a_vault @ AmmError::InvalidVault,
+        has_one = token_b_vault @ AmmError::InvalidVault,
+    )]
+    pub cl_pool: AccountLoader<'info, ClPool>,
+
+    #[account(
+        mut,
+        has_one = owner @ AmmError::Unauthorized,
+        constraint = position.pool == cl_pool.key() @ AmmError::InvalidPosition,
+    )]
+    pub position: Box<Account<'info, Position>>,
+
+    // 包含 tick_lower / tick_upper 的 TickArray，两个边界在同一个数组中时传同一个账户
+    #[account(mut, constraint = tick_array_lower.load()?.pool == cl_pool.key() @ AmmError::InvalidTickArray)]
+    pub tick_array_lower: AccountLoader<'info, TickArray>,
+    #[account(mut, constraint = tick_array_upper.load()?.pool == cl_pool.key() @ AmmError::InvalidTickArray)]
+    pub tick_array_upper: AccountLoader<'info, TickArray>,
+
+    #[account(
+        mut,
+        constraint = user_token_a.mint == cl_pool.load()?.token_a @ AmmError::InvalidUserToken
+    )]
+    pub user_token_a: Box<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        constraint = user_token_b.mint == cl_pool.load()?.token_b @ AmmError::InvalidUserToken
+    )]
+    pub user_token_b: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub token_a_vault: Box<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub token_b_vault: Box<Account<'info, TokenAccount>>,
+
+    pub owner: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+/// 减少头寸流动性的账户结构体
+#[derive(Accounts)]
+pub struct DecreaseLiquidity<'info> {
+    #[account(
+        mut,
+        has_one = token_a_vault @ AmmError::InvalidVault,
+        has_one = token_b_vault @ AmmError::InvalidVault,
+    )]
+    pub cl_pool: AccountLoader<'info, ClPool>,
+
+    #[account(
+        mut,
+        has_one = owner @ AmmError::Unauthorized,
+        constraint = position.pool == cl_pool.key() @ AmmError::InvalidPosition,
+    )]
+    pub position: Box<Account<'info, Position>>,
+
+    #[account(mut, constraint = tick_array_lower.load()?.pool == cl_pool.key() @ AmmError::InvalidTickArray)]
+    pub tick_array_lower: AccountLoader<'info, TickArray>,
+    #[account(mut, constraint = tick_array_upper.load()?.pool == cl_pool.key() @ AmmError::InvalidTickArray)]
+    pub tick_array_upper: AccountLoader<'info, TickArray>,
+
+    #[account(
+        mut,
+        constraint = user_token_a.mint == cl_pool.load()?.token_a @ AmmError::InvalidUserToken
+    )]
+    pub user_token_a: Box<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        constraint = user_token_b.mint == cl_pool.load()?.token_b @ AmmError::InvalidUserToken
+    )]
+    pub user_token_b: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub token_a_vault: Box<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub token_b_vault: Box<Account<'info, TokenAccount>>,
+
+    /// CHECK: 这个PDA只用作签名者，不存储数据
+    #[account(
+        seeds = [b"authority"],
+        bump = cl_pool.load()?.auth_bump
+    )]
+    pub pool_authority: UncheckedAccount<'info>,
+
+    pub owner: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+/// 领取头寸手续费的账户结构体
+#[derive(Accounts)]
+pub struct CollectFees<'info> {
+    #[account(
+        mut,
+        has_one = token_a_vault @ AmmError::InvalidVault,
+        has_one = token_b_vault @ AmmError::InvalidVault,
+    )]
+    pub cl_pool: AccountLoader<'info, ClPool>,
+
+    #[account(
+        mut,
+        has_one = owner @ AmmError::Unauthorized,
+        constraint = position.pool == cl_pool.key() @ AmmError::InvalidPosition,
+    )]
+    pub position: Box<Account<'info, Position>>,
+
+    // 结算手续费需要读取边界 tick 的 fee_growth_outside
+    #[account(mut, constraint = tick_array_lower.load()?.pool == cl_pool.key() @ AmmError::InvalidTickArray)]
+    pub tick_array_lower: AccountLoader<'info, TickArray>,
+    #[account(mut, constraint = tick_array_upper.load()?.pool == cl_pool.key() @ AmmError::InvalidTickArray)]
+    pub tick_array_upper: AccountLoader<'info, TickArray>,
+
+    #[account(
+        mut,
+        constraint = user_token_a.mint == cl_pool.load()?.token_a @ AmmError::InvalidUserToken
+    )]
+    pub user_token_a: Box<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        constraint = user_token_b.mint == cl_pool.load()?.token_b @ AmmError::InvalidUserToken
+    )]
+    pub user_token_b: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub token_a_vault: Box<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub token_b_vault: Box<Account<'info, TokenAccount>>,
+
+    /// CHECK: 这个PDA只用作签名者，不存储数据
+    #[account(
+        seeds = [b"authority"],
+        bump = cl_pool.load()?.auth_bump
+    )]
+    pub pool_authority: UncheckedAccount<'info>,
+
+    pub owner: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+/// 集中流动性池子兑换的账户结构体
+/// 兑换方向上需要的 TickArray（最多 MAX_SWAP_TICK_ARRAYS 个）通过 remaining_accounts 传入
+#[derive(Accounts)]
+pub struct ClSwap<'info> {
+    #[account(
+        mut,
+        has_one = token_a_vault @ AmmError::InvalidVault,
+        has_one = token_b_vault @ AmmError::InvalidVault,
+    )]
+    pub cl_pool: AccountLoader<'info, ClPool>,
+
+    #[account(
+        mut,
+        constraint = user_token_a.mint == cl_pool.load()?.token_a @ AmmError::InvalidUserToken
+    )]
+    pub user_token_a: Box<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        constraint = user_token_b.mint == cl_pool.load()?.token_b @ AmmError::InvalidUserToken
+    )]
+    pub user_token_b: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub token_a_vault: Box<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub token_b_vault: Box<Account<'info, TokenAccount>>,
+
+    pub user: Signer<'info>,
+
+    /// CHECK: 这个PDA只用作签名者，不存储数据
+    #[account(
+        seeds = [b"authority"],
+        bump = cl_pool.load()?.auth_bump
+    )]
+    pub pool_authority: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+/// 挂限价单的账户结构体
+#[derive(Accounts)]
+#[instruction(order_id: u64, amount_in: u64, is_a_to_b: bool)]
+pub struct PlaceOrder<'info> {
+    pub pool_state: AccountLoader<'info, PoolState>,
+
+    /// CHECK: 这个PDA只用作托管账户的 authority，不存储数据
+    #[account(
+        seeds = [b"authority"],
+        bump = pool_state.load()?.auth_bump
+    )]
+    pub pool_authority: UncheckedAccount<'info>,
+
+    // 卖出的代币，A -> B 时是 token_a，否则是 token_b
+    #[account(
+        constraint = token_in_mint.key() == if is_a_to_b {
+            pool_state.load()?.token_a
+        } else {
+            pool_state.load()?.token_b
+        } @ AmmError::InvalidMint
+    )]
+    pub token_in_mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = LimitOrder::LEN,
+        seeds = [b"limit_order", pool_state.key().as_ref(), owner.key().as_ref(), &order_id.to_le_bytes()],
+        bump
+    )]
+    pub limit_order: Box<Account<'info, LimitOrder>>,
+
+    // 托管输入代币，authority 是 pool_authority，用户只能通过 cancel_order 取回
+    #[account(
+        init,
+        payer = owner,
+        seeds = [b"order_escrow", limit_order.key().as_ref()],
+        bump,
+        token::mint = token_in_mint,
+        token::authority = pool_authority,
+    )]
+    pub escrow: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = user_token_in.mint == token_in_mint.key() @ AmmError::InvalidUserToken
+    )]
+    pub user_token_in: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+}
+
+/// 撤销限价单的账户结构体，订单账户和托管账户的租金以及 keeper 赏金退还给用户
+#[derive(Accounts)]
+pub struct CancelOrder<'info> {
+    pub pool_state: AccountLoader<'info, PoolState>,
+
+    /// CHECK: 这个PDA只用作签名者，不存储数据
+    #[account(
+        seeds = [b"authority"],
+        bump = pool_state.load()?.auth_bump
+    )]
+    pub pool_authority: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        close = owner,
+        has_one = owner @ AmmError::Unauthorized,
+        has_one = escrow @ AmmError::InvalidOrder,
+        constraint = limit_order.pool == pool_state.key() @ AmmError::InvalidOrder,
+    )]
+    pub limit_order: Box<Account<'info, LimitOrder>>,
+
+    #[account(mut)]
+    pub escrow: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = user_token_in.mint == escrow.mint @ AmmError::InvalidUserToken
+    )]
+    pub user_token_in: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+/// 执行限价单的账户结构体，任何人都可以作为 keeper 调用
+/// 每个订单按顺序通过 remaining_accounts 传入 4 个账户：
+/// [limit_order, escrow, owner 接收输出的代币账户, owner]
+#[derive(Accounts)]
+pub struct FillOrders<'info> {
+    #[account(
+        mut,
+        has_one = token_a_vault @ AmmError::InvalidVault,
+        has_one = token_b_vault @ AmmError::InvalidVault,
+    )]
+    pub pool_state: AccountLoader<'info, PoolState>,
+
+    /// CHECK: 这个PDA只用作签名者，不存储数据
+    #[account(
+        seeds = [b"authority"],
+        bump = pool_state.load()?.auth_bump
+    )]
+    pub pool_authority: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub token_a_vault: Box<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub token_b_vault: Box<Account<'info, TokenAccount>>,
+
+    // 接收 keeper 赏金
+    #[account(mut)]
+    pub keeper: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+
+    // 可选：池子开启了 TWAMM 时必须传入，指令开始时先结算长期订单
+    #[account(mut)]
+    pub twamm: Option<AccountLoader<'info, Twamm>>,
+    #[account(mut)]
+    pub twamm_vault_a: Option<Box<Account<'info, TokenAccount>>>,
+    #[account(mut)]
+    pub twamm_vault_b: Option<Box<Account<'info, TokenAccount>>>,
+}
+
+/// 给池子开启 TWAMM 的账户结构体，只有池子管理员可以调用，每个池子只有一个 Twamm
+#[derive(Accounts)]
+pub struct InitTwamm<'info> {
+    #[account(
+        mut,
+        has_one = admin @ AmmError::Unauthorized,
+    )]
+    pub pool_state: AccountLoader<'info, PoolState>,
+
+    /// CHECK: 这个PDA只用作 Twamm 金库的 authority，不存储数据
+    #[account(
+        seeds = [b"authority"],
+        bump = pool_state.load()?.auth_bump
+    )]
+    pub pool_authority: UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = Twamm::LEN,
+        seeds = [b"twamm", pool_state.key().as_ref()],
+        bump
+    )]
+    pub twamm: AccountLoader<'info, Twamm>,
+
+    #[account(constraint = token_a_mint.key() == pool_state.load()?.token_a @ AmmError::InvalidMint)]
+    pub token_a_mint: Box<Account<'info, Mint>>,
+    #[account(constraint = token_b_mint.key() == pool_state.load()?.token_b @ AmmError::InvalidMint)]
+    pub token_b_mint: Box<Account<'info, Mint>>,
+
+    // 存放长期订单还没卖出的输入和已经换得的输出
+    #[account(
+        init,
+        payer = admin,
+        seeds = [b"twamm_vault", twamm.key().as_ref(), token_a_mint.key().as_ref()],
+        bump,
+        token::mint = token_a_mint,
+        token::authority = pool_authority,
+    )]
+    pub twamm_vault_a: Box<Account<'info, TokenAccount>>,
+    #[account(
+        init,
+        payer = admin,
+        seeds = [b"twamm_vault", twamm.key().as_ref(), token_b_mint.key().as_ref()],
+        bump,
+        token::mint = token_b_mint,
+        token::authority = pool_authority,
+    )]
+    pub twamm_vault_b: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+}
+
+/// 挂 TWAMM 长期订单的账户结构体，下单前先把已有的长期订单结算到当前时间
+#[derive(Accounts)]
+#[instruction(order_id: u64, amount_in: u64, is_a_to_b: bool)]
+pub struct PlaceLongTermOrder<'info> {
+    #[account(
+        mut,
+        has_one = token_a_vault @ AmmError::InvalidVault,
+        has_one = token_b_vault @ AmmError::InvalidVault,
+    )]
+    pub pool_state: AccountLoader<'info, PoolState>,
+
+    /// CHECK: 这个PDA只用作签名者，不存储数据
+    #[account(
+        seeds = [b"authority"],
+        bump = pool_state.load()?.auth_bump
+    )]
+    pub pool_authority: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub token_a_vault: Box<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub token_b_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [b"twamm", pool_state.key().as_ref()],
+        bump = twamm.load()?.bump,
+    )]
+    pub twamm: AccountLoader<'info, Twamm>,
+    #[account(mut, address = twamm.load()?.vault_a @ AmmError::InvalidTwamm)]
+    pub twamm_vault_a: Box<Account<'info, TokenAccount>>,
+    #[account(mut, address = twamm.load()?.vault_b @ AmmError::InvalidTwamm)]
+    pub twamm_vault_b: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = LongTermOrder::LEN,
+        seeds = [b"long_term_order", pool_state.key().as_ref(), owner.key().as_ref(), &order_id.to_le_bytes()],
+        bump
+    )]
+    pub long_term_order: Box<Account<'info, LongTermOrder>>,
+
+    // 卖出的代币，A -> B 时是 token_a，否则是 token_b
+    #[account(
+        mut,
+        constraint = user_token_in.mint == if is_a_to_b {
+            pool_state.load()?.token_a
+        } else {
+            pool_state.load()?.token_b
+        } @ AmmError::InvalidUserToken
+    )]
+    pub user_token_in: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+}
+
+/// 提取 TWAMM 长期订单的账户结构体：到期前调用相当于撤单，退回没卖出的输入
+/// 两种代币都可能有余额，所以两个用户代币账户都要传入；订单账户的租金退还给用户
+#[derive(Accounts)]
+pub struct WithdrawLongTermOrder<'info> {
+    #[account(
+        mut,
+        has_one = token_a_vault @ AmmError::InvalidVault,
+        has_one = token_b_vault @ AmmError::InvalidVault,
+    )]
+    pub pool_state: AccountLoader<'info, PoolState>,
+
+    /// CHECK: 这个PDA只用作签名者，不存储数据
+    #[account(
+        seeds = [b"authority"],
+        bump = pool_state.load()?.auth_bump
+    )]
+    pub pool_authority: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub token_a_vault: Box<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub token_b_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [b"twamm", pool_state.key().as_ref()],
+        bump = twamm.load()?.bump,
+    )]
+    pub twamm: AccountLoader<'info, Twamm>,
+    #[account(mut, address = twamm.load()?.vault_a @ AmmError::InvalidTwamm)]
+    pub twamm_vault_a: Box<Account<'info, TokenAccount>>,
+    #[account(mut, address = twamm.load()?.vault_b @ AmmError::InvalidTwamm)]
+    pub twamm_vault_b: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        close = owner,
+        has_one = owner @ AmmError::Unauthorized,
+        constraint = long_term_order.pool == pool_state.key() @ AmmError::InvalidLongTermOrder,
+    )]
+    pub long_term_order: Box<Account<'info, LongTermOrder>>,
+
+    #[account(
+        mut,
+        constraint = user_token_a.mint == pool_state.load()?.token_a @ AmmError::InvalidUserToken
+    )]
+    pub user_token_a: Box<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        constraint = user_token_b.mint == pool_state.load()?.token_b @ AmmError::InvalidUserToken
+    )]
+    pub user_token_b: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+/// 结算 TWAMM 长期订单的账户结构体，任何人都可以调用
+#[derive(Accounts)]
+pub struct ExecuteTwamm<'info> {
+    #[account(
+        mut,
+        has_one = token_a_vault @ AmmError::InvalidVault,
+        has_one = token_b_vault @ AmmError::InvalidVault,
+    )]
+    pub pool_state: AccountLoader<'info, PoolState>,
+
+    /// CHECK: 这个PDA只用作签名者，不存储数据
+    #[account(
+        seeds = [b"authority"],
+        bump = pool_state.load()?.auth_bump
+    )]
+    pub pool_authority: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub token_a_vault: Box<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub token_b_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [b"twamm", pool_state.key().as_ref()],
+        bump = twamm.load()?.bump,
+    )]
+    pub twamm: AccountLoader<'info, Twamm>,
+    #[account(mut, address = twamm.load()?.vault_a @ AmmError::InvalidTwamm)]
+    pub twamm_vault_a: Box<Account<'info, TokenAccount>>,
+    #[account(mut, address = twamm.load()?.vault_b @ AmmError::InvalidTwamm)]
+    pub twamm_vault_b: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+/// 创建定投金库的账户结构体，输入代币全部存入 input_vault
+#[derive(Accounts)]
+#[instruction(dca_id: u64, amount_in: u64, is_a_to_b: bool)]
+pub struct OpenDca<'info> {
+    pub pool_state: AccountLoader<'info, PoolState>,
+
+    /// CHECK: 这个PDA只用作定投代币账户的 authority，不存储数据
+    #[account(
+        seeds = [b"authority"],
+        bump = pool_state.load()?.auth_bump
+    )]
+    pub pool_authority: UncheckedAccount<'info>,
+
+    // 卖出的代币，A -> B 时是 token_a，否则是 token_b
+    #[account(
+        constraint = token_in_mint.key() == if is_a_to_b {
+            pool_state.load()?.token_a
+        } else {
+            pool_state.load()?.token_b
+        } @ AmmError::InvalidMint
+    )]
+    pub token_in_mint: Box<Account<'info, Mint>>,
+    #[account(
+        constraint = token_out_mint.key() == if is_a_to_b {
+            pool_state.load()?.token_b
+        } else {
+            pool_state.load()?.token_a
+        } @ AmmError::InvalidMint
+    )]
+    pub token_out_mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = DcaVault::LEN,
+        seeds = [b"dca_vault", pool_state.key().as_ref(), owner.key().as_ref(), &dca_id.to_le_bytes()],
+        bump
+    )]
+    pub dca_vault: Box<Account<'info, DcaVault>>,
+
+    // 还没有卖出的输入
+    #[account(
+        init,
+        payer = owner,
+        seeds = [b"dca_input", dca_vault.key().as_ref()],
+        bump,
+        token::mint = token_in_mint,
+        token::authority = pool_authority,
+    )]
+    pub input_vault: Box<Account<'info, TokenAccount>>,
+
+    // 每一期换得的输出，所有者通过 withdraw_dca 提取
+    #[account(
+        init,
+        payer = owner,
+        seeds = [b"dca_output", dca_vault.key().as_ref()],
+        bump,
+        token::mint = token_out_mint,
+        token::authority = pool_authority,
+    )]
+    pub output_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = user_token_in.mint == input_vault.mint @ AmmError::InvalidUserToken
+    )]
+    pub user_token_in: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+}
+
+/// 执行一期定投的账户结构体，任何人都可以作为 keeper 调用
+#[derive(Accounts)]
+pub struct ExecuteDca<'info> {
+    #[account(
+        mut,
+        has_one = token_a_vault @ AmmError::InvalidVault,
+        has_one = token_b_vault @ AmmError::InvalidVault,
+    )]
+    pub pool_state: AccountLoader<'info, PoolState>,
+
+    /// CHECK: 这个PDA只用作签名者，不存储数据
+    #[account(
+        seeds = [b"authority"],
+        bump = pool_state.load()?.auth_bump
+    )]
+    pub pool_authority: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub token_a_vault: Box<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub token_b_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        has_one = input_vault @ AmmError::InvalidDca,
+        has_one = output_vault @ AmmError::InvalidDca,
+        constraint = dca_vault.pool == pool_state.key() @ AmmError::InvalidDca,
+    )]
+    pub dca_vault: Box<Account<'info, DcaVault>>,
+    #[account(mut)]
+    pub input_vault: Box<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub output_vault: Box<Account<'info, TokenAccount>>,
+
+    pub keeper: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+
+    // 可选：池子开启了 TWAMM 时必须传入，指令开始时先结算长期订单
+    #[account(mut)]
+    pub twamm: Option<AccountLoader<'info, Twamm>>,
+    #[account(mut)]
+    pub twamm_vault_a: Option<Box<Account<'info, TokenAccount>>>,
+    #[account(mut)]
+    pub twamm_vault_b: Option<Box<Account<'info, TokenAccount>>>,
+}
+
+/// 提取定投已经换得的代币，定投继续执行
+#[derive(Accounts)]
+pub struct WithdrawDca<'info> {
+    pub pool_state: AccountLoader<'info, PoolState>,
+
+    /// CHECK: 这个PDA只用作签名者，不存储数据
+    #[account(
+        seeds = [b"authority"],
+        bump = pool_state.load()?.auth_bump
+    )]
+    pub pool_authority: UncheckedAccount<'info>,
+
+    #[account(
+        has_one = owner @ AmmError::Unauthorized,
+        has_one = output_vault @ AmmError::InvalidDca,
+        constraint = dca_vault.pool == pool_state.key() @ AmmError::InvalidDca,
+    )]
+    pub dca_vault: Box<Account<'info, DcaVault>>,
+    #[account(mut)]
+    pub output_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = user_token_out.mint == output_vault.mint @ AmmError::InvalidUserToken
+    )]
+    pub user_token_out: Box<Account<'info, TokenAccount>>,
+
+    pub owner: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+/// 取消定投的账户结构体：退回未卖出的输入和换得的输出，两个代币账户和定投金库的租金退还给用户
+#[derive(Accounts)]
+pub struct CloseDca<'info> {
+    pub pool_state: AccountLoader<'info, PoolState>,
+
+    /// CHECK: 这个PDA只用作签名者，不存储数据
+    #[account(
+        seeds = [b"authority"],
+        bump = pool_state.load()?.auth_bump
+    )]
+    pub pool_authority: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        close = owner,
+        has_one = owner @ AmmError::Unauthorized,
+        has_one = input_vault @ AmmError::InvalidDca,
+        has_one = output_vault @ AmmError::InvalidDca,
+        constraint = dca_vault.pool == pool_state.key() @ AmmError::InvalidDca,
+    )]
+    pub dca_vault: Box<Account<'info, DcaVault>>,
+    #[account(mut)]
+    pub input_vault: Box<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub output_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = user_token_in.mint == input_vault.mint @ AmmError::InvalidUserToken
+    )]
+    pub user_token_in: Box<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        constraint = user_token_out.mint == output_vault.mint @ AmmError::InvalidUserToken
+    )]
+    pub user_token_out: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+/// 给池子开启批量拍卖的账户结构体，只有池子管理员可以调用；开启之后 swap 只能通过批量拍卖进行
+#[derive(Accounts)]
+pub struct InitBatchAuction<'info> {
+    #[account(
+        mut,
+        has_one = admin @ AmmError::Unauthorized,
+    )]
+    pub pool_state: AccountLoader<'info, PoolState>,
+
+    /// CHECK: 这个PDA只用作托管账户的 authority，不存储数据
+    #[account(
+        seeds = [b"authority"],
+        bump = pool_state.load()?.auth_bump
+    )]
+    pub pool_authority: UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = BatchAuction::LEN,
+        seeds = [b"batch_auction", pool_state.key().as_ref()],
+        bump
+    )]
+    pub batch_auction: Box<Account<'info, BatchAuction>>,
+
+    #[account(constraint = token_a_mint.key() == pool_state.load()?.token_a @ AmmError::InvalidMint)]
+    pub token_a_mint: Box<Account<'info, Mint>>,
+    #[account(constraint = token_b_mint.key() == pool_state.load()?.token_b @ AmmError::InvalidMint)]
+    pub token_b_mint: Box<Account<'info, Mint>>,
+
+    // 托管当前批次订单的输入，结算时和池子金库交换净额，再付给订单所有者
+    #[account(
+        init,
+        payer = admin,
+        seeds = [b"batch_vault", batch_auction.key().as_ref(), token_a_mint.key().as_ref()],
+        bump,
+        token::mint = token_a_mint,
+        token::authority = pool_authority,
+    )]
+    pub batch_vault_a: Box<Account<'info, TokenAccount>>,
+    #[account(
+        init,
+        payer = admin,
+        seeds = [b"batch_vault", batch_auction.key().as_ref(), token_b_mint.key().as_ref()],
+        bump,
+        token::mint = token_b_mint,
+        token::authority = pool_authority,
+    )]
+    pub batch_vault_b: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+}
+
+/// 提交批量拍卖订单的账户结构体，输入代币存入 BatchAuction 的托管账户
+#[derive(Accounts)]
+#[instruction(amount_in: u64, is_a_to_b: bool)]
+pub struct SubmitBatchSwap<'info> {
+    pub pool_state: AccountLoader<'info, PoolState>,
+
+    #[account(
+        mut,
+        seeds = [b"batch_auction", pool_state.key().as_ref()],
+        bump = batch_auction.bump,
+    )]
+    pub batch_auction: Box<Account<'info, BatchAuction>>,
+
+    // A -> B 时是 vault_a，否则是 vault_b
+    #[account(
+        mut,
+        address = if is_a_to_b {
+            batch_auction.vault_a
+        } else {
+            batch_auction.vault_b
+        } @ AmmError::InvalidBatchOrder
+    )]
+    pub batch_vault_in: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = BatchOrder::LEN,
+        seeds = [
+            b"batch_order",
+            batch_auction.key().as_ref(),
+            owner.key().as_ref(),
+            &batch_auction.batch_id.to_le_bytes(),
+        ],
+        bump
+    )]
+    pub batch_order: Box<Account<'info, BatchOrder>>,
+
+    #[account(
+        mut,
+        constraint = user_token_in.mint == batch_vault_in.mint @ AmmError::InvalidUserToken
+    )]
+    pub user_token_in: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+}
+
+/// 结算批量拍卖的账户结构体，收集窗口结束后任何人都可以调用
+/// 当前批次的每笔订单通过 remaining_accounts 传入 batch_order 账户（可写），不需要所有者的代币账户
+#[derive(Accounts)]
+pub struct SettleBatch<'info> {
+    #[account(
+        mut,
+        has_one = token_a_vault @ AmmError::InvalidVault,
+        has_one = token_b_vault @ AmmError::InvalidVault,
+    )]
+    pub pool_state: AccountLoader<'info, PoolState>,
+
+    /// CHECK: 这个PDA只用作签名者，不存储数据
+    #[account(
+        seeds = [b"authority"],
+        bump = pool_state.load()?.auth_bump
+    )]
+    pub pool_authority: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub token_a_vault: Box<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub token_b_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [b"batch_auction", pool_state.key().as_ref()],
+        bump = batch_auction.bump,
+    )]
+    pub batch_auction: Box<Account<'info, BatchAuction>>,
+    #[account(mut, address = batch_auction.vault_a @ AmmError::InvalidBatchOrder)]
+    pub batch_vault_a: Box<Account<'info, TokenAccount>>,
+    #[account(mut, address = batch_auction.vault_b @ AmmError::InvalidBatchOrder)]
+    pub batch_vault_b: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+
+    // 可选：池子开启了 TWAMM 时必须传入，指令开始时先结算长期订单
+    #[account(mut)]
+    pub twamm: Option<AccountLoader<'info, Twamm>>,
+    #[account(mut)]
+    pub twamm_vault_a: Option<Box<Account<'info, TokenAccount>>>,
+    #[account(mut)]
+    pub twamm_vault_b: Option<Box<Account<'info, TokenAccount>>>,
+}
+
+/// 把注册表上线之前创建的池子补登记到注册表，任何人都可以调用
+#[derive(Accounts)]
+pub struct RegisterPool<'info> {
+    #[account(mut)]
+    pub pool_state: AccountLoader<'info, PoolState>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = PoolRegistry::LEN,
+        seeds = [b"pool_registry"],
+        bump
+    )]
+    pub pool_registry: Box<Account<'info, PoolRegistry>>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = PoolRegistryPage::LEN,
+        seeds = [b"pool_registry_page".as_ref(), &pool_registry.next_page_index().to_le_bytes()],
+        bump
+    )]
+    pub registry_page: Box<Account<'info, PoolRegistryPage>>,
+
+    // 支付新分页的租金
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+/// 读取注册表一页的账户结构体，全部只读
+#[derive(Accounts)]
+#[instruction(page_index: u64)]
+pub struct GetRegistryPage<'info> {
+    #[account(seeds = [b"pool_registry"], bump = pool_registry.bump)]
+    pub pool_registry: Box<Account<'info, PoolRegistry>>,
+    #[account(seeds = [b"pool_registry_page".as_ref(), &page_index.to_le_bytes()], bump)]
+    pub registry_page: Box<Account<'info, PoolRegistryPage>>,
+}
+
+/// 设置储备上限和单笔存入限额，只有池子管理员可以调用
+#[derive(Accounts)]
+pub struct SetDepositCaps<'info> {
+    #[account(mut, has_one = admin @ AmmError::Unauthorized)]
+    pub pool_state: AccountLoader<'info, PoolState>,
+
+    pub admin: Signer<'info>,
+}
+
+/// 开启许可模式并指定（或更换）守门人，只有池子管理员可以调用
+#[derive(Accounts)]
+pub struct SetGatekeeper<'info> {
+    #[account(mut, has_one = admin @ AmmError::Unauthorized)]
+    pub pool_state: AccountLoader<'info, PoolState>,
+
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = PoolGatekeeper::LEN,
+        seeds = [b"pool_gatekeeper", pool_state.key().as_ref()],
+        bump
+    )]
+    pub pool_gatekeeper: Account<'info, PoolGatekeeper>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+/// 守门人给用户发放（或续期）AccessPass
+#[derive(Accounts)]
+pub struct IssueAccessPass<'info> {
+    pub pool_state: AccountLoader<'info, PoolState>,
+
+    #[account(
+        seeds = [b"pool_gatekeeper", pool_state.key().as_ref()],
+        bump = pool_gatekeeper.bump,
+        constraint = pool_gatekeeper.authority == gatekeeper.key() @ AmmError::Unauthorized
+    )]
+    pub pool_gatekeeper: Account<'info, PoolGatekeeper>,
+
+    /// CHECK: 只作为 PDA 种子和记录，不需要签名
+    pub user: UncheckedAccount<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = gatekeeper,
+        space = AccessPass::LEN,
+        seeds = [b"access_pass", pool_state.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub access_pass: Account<'info, AccessPass>,
+
+    #[account(mut)]
+    pub gatekeeper: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+/// 守门人撤销 AccessPass，租金退还给守门人
+#[derive(Accounts)]
+pub struct RevokeAccessPass<'info> {
+    pub pool_state: AccountLoader<'info, PoolState>,
+
+    #[account(
+        seeds = [b"pool_gatekeeper", pool_state.key().as_ref()],
+        bump = pool_gatekeeper.bump,
+        constraint = pool_gatekeeper.authority == gatekeeper.key() @ AmmError::Unauthorized
+    )]
+    pub pool_gatekeeper: Account<'info, PoolGatekeeper>,
+
+    #[account(
+        mut,
+        close = gatekeeper,
+        seeds = [b"access_pass", pool_state.key().as_ref(), access_pass.user.as_ref()],
+        bump = access_pass.bump
+    )]
+    pub access_pass: Account<'info, AccessPass>,
+
+    #[account(mut)]
+    pub gatekeeper: Signer<'info>,
+}
+
+/// 开启或关闭已经初始化的批量拍卖，只有池子管理员可以调用
+#[derive(Accounts)]
+pub struct SetBatchMode<'info> {
+    #[account(mut, has_one = admin @ AmmError::Unauthorized)]
+    pub pool_state: AccountLoader<'info, PoolState>,
+
+    #[account(
+        seeds = [b"batch_auction", pool_state.key().as_ref()],
+        bump = batch_auction.bump,
+    )]
+    pub batch_auction: Box<Account<'info, BatchAuction>>,
+
+    pub admin: Signer<'info>,
+}
+
+/// 取回批量拍卖订单结算结果的账户结构体，订单账户关闭，租金退还给所有者
+#[derive(Accounts)]
+pub struct ClaimBatchOrder<'info> {
+    pub pool_state: AccountLoader<'info, PoolState>,
+
+    /// CHECK: 这个PDA只用作签名者，不存储数据
+    #[account(
+        seeds = [b"authority"],
+        bump = pool_state.load()?.auth_bump
+    )]
+    pub pool_authority: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [b"batch_auction", pool_state.key().as_ref()],
+        bump = batch_auction.bump,
+    )]
+    pub batch_auction: Box<Account<'info, BatchAuction>>,
+    #[account(mut, address = batch_auction.vault_a @ AmmError::InvalidBatchOrder)]
+    pub batch_vault_a: Box<Account<'info, TokenAccount>>,
+    #[account(mut, address = batch_auction.vault_b @ AmmError::InvalidBatchOrder)]
+    pub batch_vault_b: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        close = owner,
+        has_one = owner @ AmmError::Unauthorized,
+        constraint = batch_order.pool == pool_state.key() @ AmmError::InvalidBatchOrder,
+        constraint = batch_order.settled @ AmmError::BatchNotReady,
+    )]
+    pub batch_order: Box<Account<'info, BatchOrder>>,
+
+    #[account(
+        mut,
+        constraint = owner_token_a.mint == batch_vault_a.mint @ AmmError::InvalidUserToken
+    )]
+    pub owner_token_a: Box<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        constraint = owner_token_b.mint == batch_vault_b.mint @ AmmError::InvalidUserToken
+    )]
+    pub owner_token_b: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+/// 撤销还没有结算的批量拍卖订单的账户结构体，输入退还给所有者，订单账户关闭
+#[derive(Accounts)]
+pub struct CancelBatchOrder<'info> {
+    pub pool_state: AccountLoader<'info, PoolState>,
+
+    /// CHECK: 这个PDA只用作签名者，不存储数据
+    #[account(
+        seeds = [b"authority"],
+        bump = pool_state.load()?.auth_bump
+    )]
+    pub pool_authority: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"batch_auction", pool_state.key().as_ref()],
+        bump = batch_auction.bump,
+    )]
+    pub batch_auction: Box<Account<'info, BatchAuction>>,
+
+    #[account(
+        mut,
+        close = owner,
+        has_one = owner @ AmmError::Unauthorized,
+        constraint = batch_order.pool == pool_state.key() @ AmmError::InvalidBatchOrder,
+        constraint = batch_order.batch_id == batch_auction.batch_id && !batch_order.settled @ AmmError::InvalidBatchOrder,
+    )]
+    pub batch_order: Box<Account<'info, BatchOrder>>,
+
+    // 订单卖出 A 时是 vault_a，否则是 vault_b
+    #[account(
+        mut,
+        address = if batch_order.is_a_to_b {
+            batch_auction.vault_a
+        } else {
+            batch_auction.vault_b
+        } @ AmmError::InvalidBatchOrder
+    )]
+    pub batch_vault_in: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = user_token_in.mint == batch_vault_in.mint @ AmmError::InvalidUserToken
+    )]
+    pub user_token_in: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
@@ -23,4 +23,92 @@ pub enum AmmError {
     Unauthorized,
     #[msg("手续费配置不合法：抽成不能超过50%")]
     InvalidFeeConfig,
+    #[msg("预言机观测不足以覆盖请求的时间窗口")]
+    OracleWindowUnavailable,
+    #[msg("价格偏离配置不合法：不能超过 10000 bps")]
+    InvalidPriceDeviationConfig,
+    #[msg("交易后价格偏离 TWAP 过大")]
+    PriceDeviationTooLarge,
+    #[msg("交易已过期")]
+    Expired,
+    #[msg("推荐费配置不合法：超过全局上限")]
+    InvalidReferralFeeConfig,
+    #[msg("推荐费超过池子允许的上限")]
+    ReferralFeeTooHigh,
+    #[msg("推荐人代币账户缺失或 Mint 与输入代币不匹配")]
+    InvalidReferrer,
+    #[msg("折扣费率不合法：不能高于池子费率，或者账户不属于这个池子和交易者")]
+    InvalidFeeOverride,
+    #[msg("锁仓数量必须大于 0")]
+    InvalidLockAmount,
+    #[msg("解锁时间必须晚于当前时间，追加锁仓时不能早于原来的解锁时间")]
+    InvalidUnlockTime,
+    #[msg("LP 仍在锁仓期内")]
+    LpStillLocked,
+    #[msg("奖励配置不合法：释放速率必须大于 0、结束时间必须晚于当前时间，领取时奖励下标和金库必须匹配")]
+    InvalidFarmReward,
+    #[msg("奖励代币数量已达上限")]
+    FarmRewardsFull,
+    #[msg("质押数量必须大于 0 且不能超过已质押的数量")]
+    InvalidStakeAmount,
+    #[msg("LP 元数据不合法：名称最多 32 字节、符号最多 10 字节、URI 最多 200 字节")]
+    InvalidLpMetadata,
+    #[msg("池子已经是最新版本，不需要迁移")]
+    PoolAlreadyMigrated,
+    #[msg("tick_spacing 不合法：必须在 1 到 1000 之间")]
+    InvalidTickSpacing,
+    #[msg("价格区间不合法：tick 必须是 tick_spacing 的整数倍、在取值范围内，且下边界小于上边界")]
+    InvalidTickRange,
+    #[msg("TickArray 不合法：起始 tick 没有对齐、不属于这个池子或者不包含需要的 tick")]
+    InvalidTickArray,
+    #[msg("兑换需要的 TickArray 没有传入")]
+    TickArrayMissing,
+    #[msg("价格不合法：超出 tick 范围，或者价格限制和兑换方向不一致")]
+    InvalidSqrtPrice,
+    #[msg("流动性数量必须大于 0 且不能超过头寸的流动性")]
+    InvalidLiquidityAmount,
+    #[msg("头寸不属于这个池子")]
+    InvalidPosition,
+    #[msg("限价单不合法：数量和最少输出必须大于 0，订单必须属于这个池子，传入的账户必须和订单匹配")]
+    InvalidOrder,
+    #[msg("没有可以成交的限价单")]
+    OrderNotFillable,
+    #[msg("池子开启了 TWAMM，需要传入 Twamm 账户和它的两个金库")]
+    TwammAccountsMissing,
+    #[msg("Twamm 账户或金库与池子不匹配")]
+    InvalidTwamm,
+    #[msg("长期订单不合法：数量必须大于 0，持续的间隔数必须在 1 到 TWAMM_SLOTS - 1 之间，池子要有流动性，且到期槽位没有被占用")]
+    InvalidLongTermOrder,
+    #[msg("定投参数不合法：存入数量和每期数量必须大于 0，每期数量不能超过存入数量，间隔必须大于 0")]
+    InvalidDca,
+    #[msg("定投还没到下一期的执行时间，或者已经全部卖出")]
+    DcaNotDue,
+    #[msg("池子开启了批量拍卖，兑换只能通过 submit_batch_swap 提交，按统一价格结算")]
+    BatchAuctionOnly,
+    #[msg("批量拍卖参数不合法：数量必须大于 0，窗口长度必须大于 0，订单和账户必须属于当前批次")]
+    InvalidBatchOrder,
+    #[msg("当前批次已满，或者收集窗口已经结束、还没有结算")]
+    BatchClosed,
+    #[msg("当前批次的收集窗口还没有结束，或者没有订单")]
+    BatchNotReady,
+    #[msg("池子已经登记在注册表中")]
+    PoolAlreadyRegistered,
+    #[msg("注册表分页与池子数量不一致")]
+    InvalidRegistryPage,
+    #[msg("超过了池子的储备上限或单笔存入限额")]
+    CapExceeded,
+    #[msg("池子开启了许可模式，需要传入用户有效的 AccessPass")]
+    AccessPassRequired,
+    #[msg("AccessPass 已经过期")]
+    AccessPassExpired,
+    #[msg("许可池子只能通过 swap、add_liquidity、remove_liquidity 交易，不支持挂单类指令")]
+    PermissionedPool,
+    #[msg("AccessPass 不是当前守门人发放的，或者过期时间不晚于当前时间")]
+    InvalidAccessPass,
+    #[msg("TWAMM 结算因为价格偏离暂停，暂时不能下新的长期订单")]
+    TwammSettlementPaused,
+    #[msg("池子的批量拍卖已经关闭，兑换直接使用 swap")]
+    BatchAuctionDisabled,
+    #[msg("这种奖励代币还没有释放完，不能重新添加")]
+    FarmRewardActive,
 }
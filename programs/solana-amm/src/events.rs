@@ -0,0 +1,263 @@
+//! 程序事件
+//!
+//! 通过 `emit!` 写入交易日志，链下索引器可以按 IDL 解码。
+
+use anchor_lang::prelude::*;
+
+/// 每笔 swap 成交后发出
+#[event]
+pub struct SwapEvent {
+    pub pool: Pubkey,
+    pub user: Pubkey,
+    pub is_a_to_b: bool,
+    /// 用户支付的全部输入，包含推荐费
+    pub amount_in: u64,
+    pub amount_out: u64,
+    /// 收取推荐费的代币账户，没有推荐费时为 None
+    pub referrer: Option<Pubkey>,
+    /// 从 amount_in 中划给推荐人的数量
+    pub referral_amount: u64,
+}
+
+/// LP 锁仓（包括追加锁仓）后发出
+#[event]
+pub struct LpLocked {
+    pub pool: Pubkey,
+    pub owner: Pubkey,
+    /// 本次锁入的 LP 数量
+    pub amount: u64,
+    /// 锁仓后托管中的 LP 总量
+    pub total_locked: u64,
+    pub unlock_ts: i64,
+}
+
+/// 到期取回 LP 后发出
+#[event]
+pub struct LpUnlocked {
+    pub pool: Pubkey,
+    pub owner: Pubkey,
+    pub amount: u64,
+}
+
+/// 锁仓期间领取手续费后发出
+#[event]
+pub struct LpLockFeesClaimed {
+    pub pool: Pubkey,
+    pub owner: Pubkey,
+    /// 从托管中销毁的 LP 数量
+    pub amount_lp: u64,
+    pub amount_a: u64,
+    pub amount_b: u64,
+}
+
+/// 质押 LP 参与流动性挖矿后发出
+#[event]
+pub struct LpStaked {
+    pub farm: Pubkey,
+    pub owner: Pubkey,
+    pub amount: u64,
+    /// 质押后 Farm 中的 LP 总量
+    pub total_staked: u64,
+}
+
+/// 取回质押的 LP 后发出
+#[event]
+pub struct LpUnstaked {
+    pub farm: Pubkey,
+    pub owner: Pubkey,
+    pub amount: u64,
+    /// 取回后 Farm 中的 LP 总量
+    pub total_staked: u64,
+}
+
+/// 领取挖矿奖励后发出
+#[event]
+pub struct FarmRewardsClaimed {
+    pub farm: Pubkey,
+    pub owner: Pubkey,
+    pub reward_mint: Pubkey,
+    pub amount: u64,
+}
+
+/// 集中流动性池子兑换后发出
+#[event]
+pub struct ClSwapEvent {
+    pub pool: Pubkey,
+    pub user: Pubkey,
+    pub is_a_to_b: bool,
+    pub amount_in: u64,
+    pub amount_out: u64,
+    /// 兑换后的价格和 tick
+    pub sqrt_price_x64: u128,
+    pub tick_current: i32,
+}
+
+/// 集中流动性头寸增减流动性后发出
+#[event]
+pub struct ClLiquidityChanged {
+    pub pool: Pubkey,
+    pub position: Pubkey,
+    pub owner: Pubkey,
+    /// 正数为增加，负数为减少
+    pub liquidity_delta: i128,
+    pub amount_a: u64,
+    pub amount_b: u64,
+}
+
+/// 集中流动性头寸领取手续费后发出
+#[event]
+pub struct ClFeesCollected {
+    pub pool: Pubkey,
+    pub position: Pubkey,
+    pub owner: Pubkey,
+    pub amount_a: u64,
+    pub amount_b: u64,
+}
+
+/// 挂出限价单后发出
+#[event]
+pub struct OrderPlaced {
+    pub pool: Pubkey,
+    pub order: Pubkey,
+    pub owner: Pubkey,
+    pub is_a_to_b: bool,
+    pub amount_in: u64,
+    pub min_amount_out: u64,
+    pub keeper_bounty: u64,
+}
+
+/// 撤销限价单后发出
+#[event]
+pub struct OrderCancelled {
+    pub pool: Pubkey,
+    pub order: Pubkey,
+    pub owner: Pubkey,
+    /// 退回的输入数量
+    pub amount_in: u64,
+}
+
+/// 限价单被 keeper 执行后发出
+#[event]
+pub struct OrderFilled {
+    pub pool: Pubkey,
+    pub order: Pubkey,
+    pub owner: Pubkey,
+    pub keeper: Pubkey,
+    pub is_a_to_b: bool,
+    pub amount_in: u64,
+    pub amount_out: u64,
+    /// 付给 keeper 的 lamports
+    pub keeper_bounty: u64,
+}
+
+/// 挂出 TWAMM 长期订单后发出
+#[event]
+pub struct LongTermOrderPlaced {
+    pub pool: Pubkey,
+    pub order: Pubkey,
+    pub owner: Pubkey,
+    pub is_a_to_b: bool,
+    pub amount_in: u64,
+    /// 每秒卖出 2^-TWAMM_RATE_SHIFT 个输入代币
+    pub sell_rate: u128,
+    pub expiry_ts: u64,
+}
+
+/// 提取（到期前相当于撤销）TWAMM 长期订单后发出
+#[event]
+pub struct LongTermOrderWithdrawn {
+    pub pool: Pubkey,
+    pub order: Pubkey,
+    pub owner: Pubkey,
+    pub is_a_to_b: bool,
+    /// 退回的未卖出输入
+    pub amount_refunded: u64,
+    /// 换得的输出
+    pub amount_out: u64,
+}
+
+/// 创建定投金库后发出
+#[event]
+pub struct DcaOpened {
+    pub pool: Pubkey,
+    pub dca_vault: Pubkey,
+    pub owner: Pubkey,
+    pub is_a_to_b: bool,
+    pub amount_in: u64,
+    pub amount_per_cycle: u64,
+    pub cycle_interval: i64,
+}
+
+/// 执行一期定投后发出
+#[event]
+pub struct DcaExecuted {
+    pub pool: Pubkey,
+    pub dca_vault: Pubkey,
+    pub keeper: Pubkey,
+    pub is_a_to_b: bool,
+    pub amount_in: u64,
+    pub amount_out: u64,
+    /// 金库里还没有卖出的输入
+    pub amount_remaining: u64,
+}
+
+/// 提取定投换得的代币，或者关闭定投金库后发出
+#[event]
+pub struct DcaWithdrawn {
+    pub pool: Pubkey,
+    pub dca_vault: Pubkey,
+    pub owner: Pubkey,
+    /// 退回的未卖出输入，只有关闭时才不为 0
+    pub amount_refunded: u64,
+    pub amount_out: u64,
+    pub closed: bool,
+}
+
+/// 提交批量拍卖订单后发出
+#[event]
+pub struct BatchSwapSubmitted {
+    pub pool: Pubkey,
+    pub batch_id: u64,
+    pub order: Pubkey,
+    pub owner: Pubkey,
+    pub is_a_to_b: bool,
+    pub amount_in: u64,
+    pub min_amount_out: u64,
+}
+
+/// 一批订单按统一价格结算后发出
+#[event]
+pub struct BatchSettled {
+    pub pool: Pubkey,
+    pub batch_id: u64,
+    /// 成交订单卖出的总量
+    pub amount_a_in: u64,
+    pub amount_b_in: u64,
+    /// A 的卖家一共换得的 B，以及 B 的卖家一共换得的 A
+    pub proceeds_a: u64,
+    pub proceeds_b: u64,
+    pub orders_filled: u8,
+    /// 达不到限价、退回输入的订单数量
+    pub orders_refunded: u8,
+}
+
+/// 所有者取回已经结算的批量拍卖订单后发出
+#[event]
+pub struct BatchOrderClaimed {
+    pub pool: Pubkey,
+    pub batch_id: u64,
+    pub order: Pubkey,
+    pub owner: Pubkey,
+    pub amount_a: u64,
+    pub amount_b: u64,
+}
+
+/// 撤销还没有结算的批量拍卖订单后发出
+#[event]
+pub struct BatchOrderCancelled {
+    pub pool: Pubkey,
+    pub batch_id: u64,
+    pub order: Pubkey,
+    pub owner: Pubkey,
+    pub amount_in: u64,
+}
@@ -0,0 +1,79 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Transfer};
+
+use crate::contexts::AddFarmReward;
+use crate::errors::AmmError;
+use crate::math;
+use crate::state::{FarmReward, MAX_FARM_REWARDS};
+
+/// 添加一种奖励代币
+///
+/// 从现在到 `end_ts` 的全部奖励在这里一次性从管理员转入奖励金库，之后按秒释放给质押者，
+/// 金库余额总是足够支付所有人的奖励。
+///
+/// 已经添加过的代币在上一轮释放完之后可以再次添加，沿用原来的槽位和奖励金库重新开始释放。
+/// `reward_per_share` 继续累加，质押者在上一轮没有领取的奖励不受影响。
+///
+/// # Arguments
+/// * `emission_per_second` - 每秒释放的奖励数量
+/// * `end_ts` - 停止释放的时间（unix 时间戳）
+pub fn add_farm_reward(ctx: Context<AddFarmReward>, emission_per_second: u64, end_ts: i64) -> Result<()> {
+    let clock = Clock::get()?;
+    require!(
+        emission_per_second > 0 && end_ts > clock.unix_timestamp,
+        AmmError::InvalidFarmReward
+    );
+
+    // 先把已有奖励累加到现在，新奖励从现在开始计算
+    let farm = &mut ctx.accounts.farm;
+    math::update_farm_rewards(farm, clock.unix_timestamp).ok_or(AmmError::MathOverflow)?;
+
+    // 奖励金库的种子包含 reward_mint，同一种代币只能占用一个槽位
+    // 质押者未领取的奖励按槽位记录，槽位不能换给其他代币
+    let reward_mint = ctx.accounts.reward_mint.key();
+    let reward_count = farm.reward_count as usize;
+    let (index, reward_per_share) = match farm.rewards[..reward_count]
+        .iter()
+        .position(|reward| reward.mint == reward_mint)
+    {
+        Some(index) => {
+            // 质押量为 0 时释放会顺延，end_ts 不晚于现在说明上一轮已经全部释放
+            require!(farm.rewards[index].end_ts <= clock.unix_timestamp, AmmError::FarmRewardActive);
+            (index, farm.rewards[index].reward_per_share)
+        }
+        None => {
+            require!(reward_count < MAX_FARM_REWARDS, AmmError::FarmRewardsFull);
+            farm.reward_count += 1;
+            (reward_count, 0)
+        }
+    };
+
+    let total_reward = (emission_per_second as u128)
+        .checked_mul(end_ts.checked_sub(clock.unix_timestamp).ok_or(AmmError::MathOverflow)? as u128)
+        .and_then(|total| u64::try_from(total).ok())
+        .ok_or(AmmError::MathOverflow)?;
+
+    farm.rewards[index] = FarmReward {
+        mint: reward_mint,
+        vault: ctx.accounts.reward_vault.key(),
+        emission_per_second,
+        end_ts,
+        last_update_ts: clock.unix_timestamp,
+        reward_per_share,
+    };
+
+    // 管理员 -> 奖励金库：管理员签名
+    let cpi_accounts_admin_to_vault = Transfer {
+        from: ctx.accounts.admin_reward_token.to_account_info(),
+        to: ctx.accounts.reward_vault.to_account_info(),
+        authority: ctx.accounts.admin.to_account_info(),
+    };
+    let cpi_ctx_admin_to_vault = CpiContext::new(
+        ctx.accounts.token_program.to_account_info(),
+        cpi_accounts_admin_to_vault,
+    );
+    token::transfer(cpi_ctx_admin_to_vault, total_reward)?;
+
+    msg!("Farm reward {} added: {} per second until {}", reward_mint, emission_per_second, end_ts);
+    Ok(())
+}
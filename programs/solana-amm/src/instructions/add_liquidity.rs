@@ -3,8 +3,9 @@ use anchor_spl::token::{self, MintTo, Transfer};
 
 use crate::contexts::AddLiquidity;
 use crate::errors::AmmError;
-use crate::math;
-use crate::math::sqrt_product_u64;
+use crate::permission;
+use crate::pool_liquidity::{self, PoolDeposit};
+use crate::twamm::{self, TwammSettleAccounts};
 
 /// 添加流动性到池子
 /// 
@@ -12,19 +13,50 @@ use crate::math::sqrt_product_u64;
 /// * `ctx` - 添加流动性上下文
 /// * `amount_a` - 用户存入的tokenA的数量
 /// * `amount_b` - 用户存入的tokenB的数量
+/// * `deadline` - 可选的过期时间（unix 时间戳），晚于该时间执行会失败
 pub fn add_liquidity(
     ctx: Context<AddLiquidity>,
     amount_a: u64,
     amount_b: u64,
+    deadline: Option<i64>,
 ) -> Result<()> {
-    // 先检查现在的lp_mint的总量是不是为零，如果为零就表示是此账户是第一个提供流动性的账户
-    // 因此要计算初始的lp_mint的总量，然后计算出用户需要提供多少lp_mint的token
-    // 也就是根号的delta_a * delta_b
-    let lp_mint_supply = ctx.accounts.lp_mint.supply;
-    let liquidity: u64;
+    // TWAP 获取时间戳
+    let clock = Clock::get()?;
+    // 和 swap 一样，过了 deadline 的交易不再执行
+    if let Some(deadline) = deadline {
+        require!(clock.unix_timestamp <= deadline, AmmError::Expired);
+    }
+    // 许可池子要求用户持有有效的 AccessPass
+    permission::check_access(
+        &ctx.accounts.pool_state,
+        ctx.accounts.access_pass.as_deref(),
+        ctx.accounts.pool_gatekeeper.as_deref(),
+        clock.unix_timestamp,
+    )?;
+    // 先把 TWAMM 长期订单结算到现在，之后读到的储备已经包含虚拟成交
+    let settled = twamm::settle_pool(
+        TwammSettleAccounts {
+            pool_state: &ctx.accounts.pool_state,
+            twamm: ctx.accounts.twamm.as_ref(),
+            twamm_vault_a: ctx.accounts.twamm_vault_a.as_deref(),
+            twamm_vault_b: ctx.accounts.twamm_vault_b.as_deref(),
+            token_a_vault: &ctx.accounts.token_a_vault,
+            token_b_vault: &ctx.accounts.token_b_vault,
+            pool_authority: ctx.accounts.pool_authority.to_account_info(),
+            token_program: ctx.accounts.token_program.to_account_info(),
+        },
+        clock.unix_timestamp as u64,
+    )?;
+    if settled {
+        ctx.accounts.token_a_vault.reload()?;
+        ctx.accounts.token_b_vault.reload()?;
+    }
+
+    // 零拷贝加载，直接在账户数据上读写
+    let mut pool_state = ctx.accounts.pool_state.load_mut()?;
 
     // 构建 seeds 用于 PDA 签名
-    let auth_bump = ctx.accounts.pool_state.auth_bump;
+    let auth_bump = pool_state.auth_bump;
     let seeds: &[&[u8]] = &[
         b"authority",
         &[auth_bump],   
@@ -34,31 +66,23 @@ pub fn add_liquidity(
     let token_program = ctx.accounts.token_program.to_account_info();
 
 
-    // TWAP 获取时间戳
-    let clock = Clock::get()?;
     let current_timestamp = clock.unix_timestamp as u64;
 
-    // 调用math里面的函数来更新TWAP
-    math::update_twap(
-        &mut ctx.accounts.pool_state,
+    // 储备上限检查、TWAP 更新、协议费、首次存入的最小流动性和 k_last 都在 pool_liquidity 中计算，
+    // 链下模拟器调用同一个函数；这里只按结果铸造 LP 和划转代币
+    // 注意：协议费按增发前的 supply 计算，新用户的份额按包含协议费的总供应量计算，不会白嫖已积累的手续费
+    let PoolDeposit { protocol_fee_lp, locked_lp, liquidity, .. } = pool_liquidity::execute_add_liquidity(
+        &mut pool_state,
         ctx.accounts.token_a_vault.amount,
         ctx.accounts.token_b_vault.amount,
+        ctx.accounts.lp_mint.supply,
+        amount_a,
+        amount_b,
         current_timestamp,
-    );
+    )?;
 
-    // 计算应该给协议方增发多少LP
     // 调用 token::mint_to 给 protocol_fee_recipient 铸造 LP
-    // 更新 pool_state.k_last = reserve_a * reserve_b，作为下次结算的基准
-
-    let protocol_mint_amount = math::calculate_protocol_fee_mint(
-        ctx.accounts.token_a_vault.amount,
-        ctx.accounts.token_b_vault.amount,
-        ctx.accounts.pool_state.k_last,
-        ctx.accounts.lp_mint.supply,
-        ctx.accounts.pool_state.protocol_fee_share,
-    ).unwrap_or(0);
-
-    if protocol_mint_amount > 0 {
+    if protocol_fee_lp > 0 {
         let cpi_accounts_mint_to_protocol = MintTo {
             mint: ctx.accounts.lp_mint.to_account_info(),
             to: ctx.accounts.protocol_fee_recipient.to_account_info(),
@@ -69,42 +93,12 @@ pub fn add_liquidity(
             cpi_accounts_mint_to_protocol,
             signer_seeds,
         );
-        token::mint_to(cpi_ctx_mint_to_protocol, protocol_mint_amount)?;
-        msg!("Protocol mint amount: {}", protocol_mint_amount);
+        token::mint_to(cpi_ctx_mint_to_protocol, protocol_fee_lp)?;
+        msg!("Protocol mint amount: {}", protocol_fee_lp);
     }
 
-    // 计算包含协议费后的总 LP 供应量（用于后续流动性计算）
-    let total_lp_supply = lp_mint_supply.checked_add(protocol_mint_amount).ok_or(AmmError::MathOverflow)?;
-    msg!("Total LP supply (after protocol fee): {}", total_lp_supply);
-
-    // 注意：在协议费 mint 之前，需要先检查是否是首次添加流动性
-    // 使用保存的 lp_mint_supply 而不是 ctx.accounts.lp_mint.supply
-    // 因为后续的协议费 mint 会改变 supply
-    if lp_mint_supply == 0 {
-        // 注释掉 PreciseNumber 的原因：CU 溢出
-        // PreciseNumber 的计算开销过大，会导致程序消耗超过 200000 CU 的限制
-        // 改用自定义的轻量级 sqrt_product_u64 函数来计算 sqrt(amount_a * amount_b)
-        // 
-        // 原代码（已注释）：
-        // let p_a = PreciseNumber::new(amount_a as u128).ok_or(AmmError::MathOverflow)?;
-        // let p_b = PreciseNumber::new(amount_b as u128).ok_or(AmmError::MathOverflow)?;
-        // let product = p_a.checked_mul(&p_b).ok_or(AmmError::MathOverflow)?;
-        // let sqrt_product = product.sqrt().ok_or(AmmError::MathOverflow)?;
-        // let initial_liquidity = sqrt_product.to_imprecise().ok_or(AmmError::MathOverflow)?;
-        
-        // 使用自定义的 sqrt 函数计算初始流动性：sqrt(amount_a * amount_b)
-        let initial_liquidity = sqrt_product_u64(amount_a, amount_b)
-            .ok_or(AmmError::MathOverflow)? as u128;
-
-        // 这里增加最小流动性
-        // 防止流动性归零攻击，这里学习uniswap会转一小部分到0地址Pubkey::default()
-        const MINIMUM_LIQUIDITY: u64 = 1000;
-        if initial_liquidity <= MINIMUM_LIQUIDITY as u128 {
-            return Err(AmmError::InitialLiquidityTooLow.into());
-        }
-        liquidity = (initial_liquidity - MINIMUM_LIQUIDITY as u128) as u64; 
-        msg!("Initial liquidity: {}", liquidity);
-
+    // 首次添加流动性：防止流动性归零攻击，这里学习uniswap会转一小部分到0地址Pubkey::default()
+    if locked_lp > 0 {
         // 将铸造出来的MINIMUM_LIQUIDITY转到黑洞地址
         let cpi_accounts_mint_to_black_hole = MintTo {
             mint: ctx.accounts.lp_mint.to_account_info(),
@@ -116,25 +110,9 @@ pub fn add_liquidity(
             cpi_accounts_mint_to_black_hole,
             signer_seeds,
         );
-        token::mint_to(cpi_ctx_mint_to_black_hole, MINIMUM_LIQUIDITY)?;
-    } else {
-        // 这里是不为0的情况，也就是说已经存在了流动性
-        // 计算在现有的流动性池中，应该怎么计算用户应该提供多少lp_mint的token
-        // 根据两个资产的存入比例，分别计算出"如果按 A 算该给多少 LP"和"如果按 B 算该给多少 LP"，然后取其中的最小值
-        // 注意：使用 total_lp_supply（包含协议费后的总供应量）来计算，确保新用户不会白嫖已积累的手续费
-        let liquidity_a = (amount_a as u128)
-            .checked_mul(total_lp_supply as u128)
-            .ok_or(AmmError::MathOverflow)?
-            .checked_div(ctx.accounts.token_a_vault.amount as u128)
-            .ok_or(AmmError::MathOverflow)? as u64;
-        let liquidity_b = (amount_b as u128)
-            .checked_mul(total_lp_supply as u128)
-            .ok_or(AmmError::MathOverflow)?
-            .checked_div(ctx.accounts.token_b_vault.amount as u128)
-            .ok_or(AmmError::MathOverflow)? as u64;
-        liquidity = liquidity_a.min(liquidity_b);
-        msg!("Liquidity: {}", liquidity);
+        token::mint_to(cpi_ctx_mint_to_black_hole, locked_lp)?;
     }
+    msg!("Liquidity: {}", liquidity);
 
     // 现在就是用户将钱转进池子里面，所以目的地是池子的vault
     let cpi_accounts_user_to_pool = Transfer {
@@ -169,16 +147,6 @@ pub fn add_liquidity(
 
     msg!("Add liquidity completed: {} -> {}", amount_a, amount_b);
 
-    // 计算k_last
-    let new_reserve_a = ctx.accounts.token_a_vault.amount
-        .checked_add(amount_a)
-        .ok_or(AmmError::MathOverflow)?;
-    let new_reserve_b = ctx.accounts.token_b_vault.amount
-        .checked_add(amount_b)
-        .ok_or(AmmError::MathOverflow)?;
-    ctx.accounts.pool_state.k_last = (new_reserve_a as u128)
-        .checked_mul(new_reserve_b as u128)
-        .ok_or(AmmError::MathOverflow)?;
-    msg!("New k_last: {}", ctx.accounts.pool_state.k_last);
+    msg!("New k_last: {}", pool_state.k_last);
     Ok(())
 }
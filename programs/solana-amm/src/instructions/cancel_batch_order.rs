@@ -0,0 +1,59 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Transfer};
+
+use crate::contexts::CancelBatchOrder;
+use crate::errors::AmmError;
+use crate::events::BatchOrderCancelled;
+
+/// 撤销当前批次中还没有结算的订单，取回托管的输入，订单账户的租金一起退还
+///
+/// 收集窗口结束之后批次的订单已经确定，只能等待结算；池子关闭了批量拍卖时随时可以撤销
+pub fn cancel_batch_order(ctx: Context<CancelBatchOrder>) -> Result<()> {
+    let slot = Clock::get()?.slot;
+    let pool_state = ctx.accounts.pool_state.load()?;
+    let batch_auction = &mut ctx.accounts.batch_auction;
+    require!(
+        !batch_auction.window_closed(slot) || !pool_state.batch_mode(),
+        AmmError::BatchClosed
+    );
+
+    let batch_order = &ctx.accounts.batch_order;
+    let amount_in = batch_order.amount_in;
+    batch_auction.order_count -= 1;
+    if batch_order.is_a_to_b {
+        batch_auction.amount_a_in = batch_auction.amount_a_in.checked_sub(amount_in).ok_or(AmmError::MathOverflow)?;
+    } else {
+        batch_auction.amount_b_in = batch_auction.amount_b_in.checked_sub(amount_in).ok_or(AmmError::MathOverflow)?;
+    }
+    // 最后一笔订单撤销之后，下一笔订单重新开始计算收集窗口
+    if batch_auction.order_count == 0 {
+        batch_auction.batch_start_slot = 0;
+    }
+
+    // 构建 seeds 用于 PDA 签名
+    let seeds: &[&[u8]] = &[
+        b"authority",
+        &[pool_state.auth_bump],
+    ];
+    let signer_seeds = &[seeds];
+    let cpi_accounts_batch_to_user = Transfer {
+        from: ctx.accounts.batch_vault_in.to_account_info(),
+        to: ctx.accounts.user_token_in.to_account_info(),
+        authority: ctx.accounts.pool_authority.to_account_info(),
+    };
+    let cpi_ctx_batch_to_user = CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        cpi_accounts_batch_to_user,
+        signer_seeds,
+    );
+    token::transfer(cpi_ctx_batch_to_user, amount_in)?;
+
+    emit!(BatchOrderCancelled {
+        pool: batch_order.pool,
+        batch_id: batch_order.batch_id,
+        order: batch_order.key(),
+        owner: batch_order.owner,
+        amount_in,
+    });
+    Ok(())
+}
@@ -0,0 +1,50 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, CloseAccount, Transfer};
+
+use crate::contexts::CancelOrder;
+use crate::events::OrderCancelled;
+
+/// 撤销还没有成交的限价单，取回托管的代币，订单账户的租金和赏金一起退还
+pub fn cancel_order(ctx: Context<CancelOrder>) -> Result<()> {
+    // 构建 seeds 用于 PDA 签名
+    let auth_bump = ctx.accounts.pool_state.load()?.auth_bump;
+    let seeds: &[&[u8]] = &[
+        b"authority",
+        &[auth_bump],
+    ];
+    let signer_seeds = &[seeds];
+    let token_program = ctx.accounts.token_program.to_account_info();
+
+    let amount_in = ctx.accounts.escrow.amount;
+    let cpi_accounts_escrow_to_user = Transfer {
+        from: ctx.accounts.escrow.to_account_info(),
+        to: ctx.accounts.user_token_in.to_account_info(),
+        authority: ctx.accounts.pool_authority.to_account_info(),
+    };
+    let cpi_ctx_escrow_to_user = CpiContext::new_with_signer(
+        token_program.clone(),
+        cpi_accounts_escrow_to_user,
+        signer_seeds,
+    );
+    token::transfer(cpi_ctx_escrow_to_user, amount_in)?;
+
+    let cpi_accounts_close_escrow = CloseAccount {
+        account: ctx.accounts.escrow.to_account_info(),
+        destination: ctx.accounts.owner.to_account_info(),
+        authority: ctx.accounts.pool_authority.to_account_info(),
+    };
+    let cpi_ctx_close_escrow = CpiContext::new_with_signer(
+        token_program,
+        cpi_accounts_close_escrow,
+        signer_seeds,
+    );
+    token::close_account(cpi_ctx_close_escrow)?;
+
+    emit!(OrderCancelled {
+        pool: ctx.accounts.pool_state.key(),
+        order: ctx.accounts.limit_order.key(),
+        owner: ctx.accounts.owner.key(),
+        amount_in,
+    });
+    Ok(())
+}
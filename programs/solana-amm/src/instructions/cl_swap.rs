@@ -0,0 +1,179 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Transfer};
+
+use crate::clmm::{self, MAX_SWAP_TICK_ARRAYS, MAX_TICK, MIN_TICK};
+use crate::contexts::ClSwap;
+use crate::errors::AmmError;
+use crate::events::ClSwapEvent;
+use crate::state::TickArray;
+
+/// 在集中流动性池子中兑换
+///
+/// 按 tick 逐段兑换：每段流动性不变，价格到达下一个已初始化的 tick 时穿过它并更新流动性。
+/// 兑换方向上需要的 TickArray 按顺序通过 remaining_accounts 传入，缺少时交易失败。
+///
+/// # Arguments
+/// * `ctx` - 兑换上下文
+/// * `amount_in` - 输入数量（含手续费）
+/// * `is_a_to_b` - true 表示用 A 换 B，价格下降
+/// * `min_amount_out` - 滑点保护：用户能接受的最少输出
+/// * `sqrt_price_limit_x64` - 价格到达该值时停止，剩余的输入不会被使用；None 表示不限制
+pub fn cl_swap<'info>(
+    ctx: Context<'_, '_, 'info, 'info, ClSwap<'info>>,
+    amount_in: u64,
+    is_a_to_b: bool,
+    min_amount_out: u64,
+    sqrt_price_limit_x64: Option<u128>,
+) -> Result<()> {
+    require!(
+        (1..=MAX_SWAP_TICK_ARRAYS).contains(&ctx.remaining_accounts.len()),
+        AmmError::InvalidTickArray
+    );
+
+    let cl_pool_key = ctx.accounts.cl_pool.key();
+    let mut tick_arrays = Vec::with_capacity(ctx.remaining_accounts.len());
+    for account in ctx.remaining_accounts {
+        let loader = AccountLoader::<TickArray>::try_from(account)?;
+        require_keys_eq!(loader.load()?.pool, cl_pool_key, AmmError::InvalidTickArray);
+        tick_arrays.push(loader);
+    }
+
+    let mut cl_pool = ctx.accounts.cl_pool.load_mut()?;
+
+    // 价格限制必须在兑换方向上
+    let sqrt_price_limit = match sqrt_price_limit_x64 {
+        Some(limit) => limit,
+        None if is_a_to_b => clmm::sqrt_price_at_tick(MIN_TICK).ok_or(AmmError::MathOverflow)?,
+        None => clmm::sqrt_price_at_tick(MAX_TICK).ok_or(AmmError::MathOverflow)?,
+    };
+    if is_a_to_b {
+        require!(
+            sqrt_price_limit < cl_pool.sqrt_price_x64
+                && sqrt_price_limit >= clmm::sqrt_price_at_tick(MIN_TICK).ok_or(AmmError::MathOverflow)?,
+            AmmError::InvalidSqrtPrice
+        );
+    } else {
+        require!(
+            sqrt_price_limit > cl_pool.sqrt_price_x64
+                && sqrt_price_limit <= clmm::sqrt_price_at_tick(MAX_TICK).ok_or(AmmError::MathOverflow)?,
+            AmmError::InvalidSqrtPrice
+        );
+    }
+
+    let mut amount_remaining = amount_in;
+    let mut amount_out: u64 = 0;
+    while amount_remaining > 0 && cl_pool.sqrt_price_x64 != sqrt_price_limit {
+        let (tick_next, initialized) =
+            clmm::next_tick_in_array(&tick_arrays, cl_pool.tick_current, cl_pool.tick_spacing, is_a_to_b)?
+                .ok_or(AmmError::TickArrayMissing)?;
+        let sqrt_price_tick = clmm::sqrt_price_at_tick(tick_next).ok_or(AmmError::MathOverflow)?;
+        let sqrt_price_target = if is_a_to_b {
+            sqrt_price_tick.max(sqrt_price_limit)
+        } else {
+            sqrt_price_tick.min(sqrt_price_limit)
+        };
+
+        let step = clmm::compute_swap_step(
+            cl_pool.sqrt_price_x64,
+            sqrt_price_target,
+            cl_pool.liquidity,
+            amount_remaining,
+            cl_pool.fee_numerator,
+            cl_pool.fee_denominator,
+        )
+        .ok_or(AmmError::MathOverflow)?;
+
+        amount_remaining = amount_remaining
+            .checked_sub(step.amount_in)
+            .and_then(|x| x.checked_sub(step.fee_amount))
+            .ok_or(AmmError::MathOverflow)?;
+        amount_out = amount_out.checked_add(step.amount_out).ok_or(AmmError::MathOverflow)?;
+
+        // 手续费按输入方向累计，回绕是预期行为
+        let fee_growth = clmm::fee_growth_delta(step.fee_amount, cl_pool.liquidity);
+        if is_a_to_b {
+            cl_pool.fee_growth_global_a_x64 = cl_pool.fee_growth_global_a_x64.wrapping_add(fee_growth);
+        } else {
+            cl_pool.fee_growth_global_b_x64 = cl_pool.fee_growth_global_b_x64.wrapping_add(fee_growth);
+        }
+        cl_pool.sqrt_price_x64 = step.sqrt_price_next;
+
+        if step.sqrt_price_next == sqrt_price_tick {
+            if initialized {
+                let start = TickArray::start_index_for(tick_next, cl_pool.tick_spacing);
+                let loader = clmm::find_tick_array(&tick_arrays, start)?.ok_or(AmmError::TickArrayMissing)?;
+                let mut array = loader.load_mut()?;
+                let tick = array
+                    .tick_mut(tick_next, cl_pool.tick_spacing)
+                    .ok_or(AmmError::InvalidTickArray)?;
+                let liquidity_net =
+                    clmm::cross_tick(tick, cl_pool.fee_growth_global_a_x64, cl_pool.fee_growth_global_b_x64);
+                // 价格向下穿过 tick 时流动性变化方向相反
+                let liquidity_delta = if is_a_to_b {
+                    liquidity_net.checked_neg().ok_or(AmmError::MathOverflow)?
+                } else {
+                    liquidity_net
+                };
+                cl_pool.liquidity =
+                    clmm::add_liquidity_delta(cl_pool.liquidity, liquidity_delta).ok_or(AmmError::MathOverflow)?;
+            }
+            cl_pool.tick_current = if is_a_to_b { tick_next - 1 } else { tick_next };
+        } else {
+            cl_pool.tick_current =
+                clmm::tick_at_sqrt_price(cl_pool.sqrt_price_x64).ok_or(AmmError::InvalidSqrtPrice)?;
+        }
+    }
+
+    require!(amount_out >= min_amount_out, AmmError::SlippageExceeded);
+    // 到达价格限制时只收取实际用掉的输入
+    let amount_used = amount_in - amount_remaining;
+
+    let (user_source, vault_destination, vault_source, user_destination) = if is_a_to_b {
+        (
+            ctx.accounts.user_token_a.to_account_info(),
+            ctx.accounts.token_a_vault.to_account_info(),
+            ctx.accounts.token_b_vault.to_account_info(),
+            ctx.accounts.user_token_b.to_account_info(),
+        )
+    } else {
+        (
+            ctx.accounts.user_token_b.to_account_info(),
+            ctx.accounts.token_b_vault.to_account_info(),
+            ctx.accounts.token_a_vault.to_account_info(),
+            ctx.accounts.user_token_a.to_account_info(),
+        )
+    };
+
+    let token_program = ctx.accounts.token_program.to_account_info();
+    let cpi_accounts_user_to_vault = Transfer {
+        from: user_source,
+        to: vault_destination,
+        authority: ctx.accounts.user.to_account_info(),
+    };
+    token::transfer(CpiContext::new(token_program.clone(), cpi_accounts_user_to_vault), amount_used)?;
+
+    if amount_out > 0 {
+        let seeds: &[&[u8]] = &[b"authority", &[cl_pool.auth_bump]];
+        let signer_seeds = &[seeds];
+        let cpi_accounts_vault_to_user = Transfer {
+            from: vault_source,
+            to: user_destination,
+            authority: ctx.accounts.pool_authority.to_account_info(),
+        };
+        token::transfer(
+            CpiContext::new_with_signer(token_program, cpi_accounts_vault_to_user, signer_seeds),
+            amount_out,
+        )?;
+    }
+
+    emit!(ClSwapEvent {
+        pool: cl_pool_key,
+        user: ctx.accounts.user.key(),
+        is_a_to_b,
+        amount_in: amount_used,
+        amount_out,
+        sqrt_price_x64: cl_pool.sqrt_price_x64,
+        tick_current: cl_pool.tick_current,
+    });
+    Ok(())
+}
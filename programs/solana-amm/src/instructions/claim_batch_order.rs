@@ -0,0 +1,48 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Transfer};
+
+use crate::contexts::ClaimBatchOrder;
+use crate::events::BatchOrderClaimed;
+
+/// 取回已经结算的批量拍卖订单：成交的输出或者退回的输入，订单账户的租金一起退还
+///
+/// 只有订单所有者可以调用，池子的批量拍卖关闭之后也可以取回
+pub fn claim_batch_order(ctx: Context<ClaimBatchOrder>) -> Result<()> {
+    // 构建 seeds 用于 PDA 签名
+    let auth_bump = ctx.accounts.pool_state.load()?.auth_bump;
+    let seeds: &[&[u8]] = &[
+        b"authority",
+        &[auth_bump],
+    ];
+    let signer_seeds = &[seeds];
+    let token_program = ctx.accounts.token_program.to_account_info();
+
+    let batch_order = &ctx.accounts.batch_order;
+    for (batch_vault, owner_token, amount) in [
+        (&ctx.accounts.batch_vault_a, &ctx.accounts.owner_token_a, batch_order.claim_amount_a),
+        (&ctx.accounts.batch_vault_b, &ctx.accounts.owner_token_b, batch_order.claim_amount_b),
+    ] {
+        if amount == 0 {
+            continue;
+        }
+        let cpi_accounts_batch_to_owner = Transfer {
+            from: batch_vault.to_account_info(),
+            to: owner_token.to_account_info(),
+            authority: ctx.accounts.pool_authority.to_account_info(),
+        };
+        token::transfer(
+            CpiContext::new_with_signer(token_program.clone(), cpi_accounts_batch_to_owner, signer_seeds),
+            amount,
+        )?;
+    }
+
+    emit!(BatchOrderClaimed {
+        pool: batch_order.pool,
+        batch_id: batch_order.batch_id,
+        order: batch_order.key(),
+        owner: batch_order.owner,
+        amount_a: batch_order.claim_amount_a,
+        amount_b: batch_order.claim_amount_b,
+    });
+    Ok(())
+}
@@ -0,0 +1,167 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Burn, MintTo, Transfer};
+
+use crate::contexts::ClaimLpLockFees;
+use crate::errors::AmmError;
+use crate::events::LpLockFeesClaimed;
+use crate::math;
+use crate::permission;
+use crate::twamm::{self, TwammSettleAccounts};
+
+/// 领取锁仓期间累积的手续费
+///
+/// 锁仓时记录了本金对应的 sqrt(k) 份额，手续费让每个 LP 对应的份额增长，
+/// 这里把超出本金的那部分 LP 从托管账户中赎回成 token A / B 发给用户，本金继续锁定。
+pub fn claim_lp_lock_fees(ctx: Context<ClaimLpLockFees>) -> Result<()> {
+    let clock = Clock::get()?;
+    // 和 remove_liquidity 一样，许可池子要求用户持有有效的 AccessPass
+    permission::check_access(
+        &ctx.accounts.pool_state,
+        ctx.accounts.access_pass.as_deref(),
+        ctx.accounts.pool_gatekeeper.as_deref(),
+        clock.unix_timestamp,
+    )?;
+    // 先把 TWAMM 长期订单结算到现在，手续费份额按包含虚拟成交的储备计算
+    let settled = twamm::settle_pool(
+        TwammSettleAccounts {
+            pool_state: &ctx.accounts.pool_state,
+            twamm: ctx.accounts.twamm.as_ref(),
+            twamm_vault_a: ctx.accounts.twamm_vault_a.as_deref(),
+            twamm_vault_b: ctx.accounts.twamm_vault_b.as_deref(),
+            token_a_vault: &ctx.accounts.token_a_vault,
+            token_b_vault: &ctx.accounts.token_b_vault,
+            pool_authority: ctx.accounts.pool_authority.to_account_info(),
+            token_program: ctx.accounts.token_program.to_account_info(),
+        },
+        clock.unix_timestamp as u64,
+    )?;
+    if settled {
+        ctx.accounts.token_a_vault.reload()?;
+        ctx.accounts.token_b_vault.reload()?;
+    }
+
+    // 零拷贝加载，直接在账户数据上读写
+    let mut pool_state = ctx.accounts.pool_state.load_mut()?;
+
+    // 构建 seeds 用于 PDA 签名
+    let auth_bump = pool_state.auth_bump;
+    let seeds: &[&[u8]] = &[
+        b"authority",
+        &[auth_bump],
+    ];
+    let signer_seeds = &[seeds];
+    let token_program = ctx.accounts.token_program.to_account_info();
+
+    // 储备金会变化，先更新 TWAP
+    math::update_twap(
+        &mut pool_state,
+        ctx.accounts.token_a_vault.amount,
+        ctx.accounts.token_b_vault.amount,
+        clock.unix_timestamp as u64,
+    );
+
+    // 和 remove_liquidity 一样先结算协议费，锁仓的手续费按增发后的供应量计算
+    let protocol_mint_amount = math::calculate_protocol_fee_mint(
+        ctx.accounts.token_a_vault.amount,
+        ctx.accounts.token_b_vault.amount,
+        pool_state.k_last,
+        ctx.accounts.lp_mint.supply,
+        pool_state.protocol_fee_share,
+    ).unwrap_or(0);
+
+    if protocol_mint_amount > 0 {
+        let cpi_accounts_mint_to_protocol = MintTo {
+            mint: ctx.accounts.lp_mint.to_account_info(),
+            to: ctx.accounts.protocol_fee_recipient.to_account_info(),
+            authority: ctx.accounts.pool_authority.to_account_info(),
+        };
+        let cpi_ctx_mint_to_protocol = CpiContext::new_with_signer(
+            token_program.clone(),
+            cpi_accounts_mint_to_protocol,
+            signer_seeds,
+        );
+        token::mint_to(cpi_ctx_mint_to_protocol, protocol_mint_amount)?;
+        msg!("Protocol mint amount: {}", protocol_mint_amount);
+    }
+
+    let total_lp_supply = ctx.accounts.lp_mint.supply.checked_add(protocol_mint_amount).ok_or(AmmError::MathOverflow)?;
+
+    let fee_lp = math::calculate_locked_fee_lp(
+        ctx.accounts.lp_lock.amount,
+        ctx.accounts.lp_lock.principal,
+        ctx.accounts.token_a_vault.amount,
+        ctx.accounts.token_b_vault.amount,
+        total_lp_supply,
+    ).ok_or(AmmError::MathOverflow)?;
+    if fee_lp == 0 {
+        msg!("No fees to claim");
+        return Ok(());
+    }
+
+    let (amount_a, amount_b) = math::calculate_withdraw_amounts(
+        fee_lp,
+        ctx.accounts.token_a_vault.amount,
+        ctx.accounts.token_b_vault.amount,
+        total_lp_supply,
+    ).ok_or(AmmError::MathOverflow)?;
+
+    // 和 remove_liquidity 一样，按转账之后的余额更新 k_last
+    let new_reserve_a = ctx.accounts.token_a_vault.amount
+        .checked_sub(amount_a)
+        .ok_or(AmmError::MathOverflow)?;
+    let new_reserve_b = ctx.accounts.token_b_vault.amount
+        .checked_sub(amount_b)
+        .ok_or(AmmError::MathOverflow)?;
+    pool_state.k_last = (new_reserve_a as u128)
+        .checked_mul(new_reserve_b as u128)
+        .ok_or(AmmError::MathOverflow)?;
+
+    // 托管账户的 authority 是 pool_authority，由它签名销毁
+    let cpi_accounts_burn_fee_lp = Burn {
+        mint: ctx.accounts.lp_mint.to_account_info(),
+        from: ctx.accounts.lock_vault.to_account_info(),
+        authority: ctx.accounts.pool_authority.to_account_info(),
+    };
+    let cpi_ctx_burn_fee_lp = CpiContext::new_with_signer(
+        token_program.clone(),
+        cpi_accounts_burn_fee_lp,
+        signer_seeds,
+    );
+    token::burn(cpi_ctx_burn_fee_lp, fee_lp)?;
+
+    let cpi_accounts_vault_to_user_a = Transfer {
+        from: ctx.accounts.token_a_vault.to_account_info(),
+        to: ctx.accounts.user_token_a.to_account_info(),
+        authority: ctx.accounts.pool_authority.to_account_info(),
+    };
+    let cpi_ctx_vault_to_user_a = CpiContext::new_with_signer(
+        token_program.clone(),
+        cpi_accounts_vault_to_user_a,
+        signer_seeds,
+    );
+    token::transfer(cpi_ctx_vault_to_user_a, amount_a)?;
+
+    let cpi_accounts_vault_to_user_b = Transfer {
+        from: ctx.accounts.token_b_vault.to_account_info(),
+        to: ctx.accounts.user_token_b.to_account_info(),
+        authority: ctx.accounts.pool_authority.to_account_info(),
+    };
+    let cpi_ctx_vault_to_user_b = CpiContext::new_with_signer(
+        token_program,
+        cpi_accounts_vault_to_user_b,
+        signer_seeds,
+    );
+    token::transfer(cpi_ctx_vault_to_user_b, amount_b)?;
+
+    let lp_lock = &mut ctx.accounts.lp_lock;
+    lp_lock.amount = lp_lock.amount.checked_sub(fee_lp).ok_or(AmmError::MathOverflow)?;
+
+    emit!(LpLockFeesClaimed {
+        pool: lp_lock.pool,
+        owner: lp_lock.owner,
+        amount_lp: fee_lp,
+        amount_a,
+        amount_b,
+    });
+    Ok(())
+}
@@ -0,0 +1,61 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Transfer};
+
+use crate::contexts::ClaimRewards;
+use crate::errors::AmmError;
+use crate::events::FarmRewardsClaimed;
+use crate::math;
+
+/// 领取一种挖矿奖励
+///
+/// 先结算到当前时间，再把这种奖励的 `pending_rewards` 全部转给用户。
+///
+/// # Arguments
+/// * `reward_index` - 奖励在 `Farm::rewards` 中的下标，`reward_vault` 必须是它的金库
+pub fn claim_rewards(ctx: Context<ClaimRewards>, reward_index: u8) -> Result<()> {
+    let index = reward_index as usize;
+    let farm = &mut ctx.accounts.farm;
+    require!(index < farm.reward_count as usize, AmmError::InvalidFarmReward);
+    let reward = farm.rewards[index];
+    require_keys_eq!(ctx.accounts.reward_vault.key(), reward.vault, AmmError::InvalidFarmReward);
+
+    let clock = Clock::get()?;
+    let farm_stake = &mut ctx.accounts.farm_stake;
+    math::update_farm_rewards(farm, clock.unix_timestamp).ok_or(AmmError::MathOverflow)?;
+    math::settle_farm_stake(farm, farm_stake).ok_or(AmmError::MathOverflow)?;
+
+    let amount = farm_stake.pending_rewards[index];
+    if amount == 0 {
+        return Ok(());
+    }
+    farm_stake.pending_rewards[index] = 0;
+
+    // 构建 seeds 用于 PDA 签名
+    let auth_bump = ctx.accounts.pool_state.load()?.auth_bump;
+    let seeds: &[&[u8]] = &[
+        b"authority",
+        &[auth_bump],
+    ];
+    let signer_seeds = &[seeds];
+
+    // 奖励金库 -> 用户：pool_authority 签名
+    let cpi_accounts_vault_to_user = Transfer {
+        from: ctx.accounts.reward_vault.to_account_info(),
+        to: ctx.accounts.user_reward_token.to_account_info(),
+        authority: ctx.accounts.pool_authority.to_account_info(),
+    };
+    let cpi_ctx_vault_to_user = CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        cpi_accounts_vault_to_user,
+        signer_seeds,
+    );
+    token::transfer(cpi_ctx_vault_to_user, amount)?;
+
+    emit!(FarmRewardsClaimed {
+        farm: ctx.accounts.farm.key(),
+        owner: ctx.accounts.user.key(),
+        reward_mint: reward.mint,
+        amount,
+    });
+    Ok(())
+}
@@ -0,0 +1,73 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, CloseAccount, TokenAccount, Transfer};
+
+use crate::contexts::CloseDca;
+use crate::events::DcaWithdrawn;
+
+// 把定投代币账户的余额全部转给用户，再关闭账户，租金退给 owner；两步都由 pool_authority 签名
+fn drain_and_close<'info>(
+    vault: &Account<'info, TokenAccount>,
+    to: AccountInfo<'info>,
+    owner: AccountInfo<'info>,
+    pool_authority: AccountInfo<'info>,
+    token_program: AccountInfo<'info>,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<u64> {
+    let amount = vault.amount;
+    if amount > 0 {
+        let cpi_accounts_dca_to_user = Transfer {
+            from: vault.to_account_info(),
+            to,
+            authority: pool_authority.clone(),
+        };
+        token::transfer(
+            CpiContext::new_with_signer(token_program.clone(), cpi_accounts_dca_to_user, signer_seeds),
+            amount,
+        )?;
+    }
+    let cpi_accounts_close = CloseAccount {
+        account: vault.to_account_info(),
+        destination: owner,
+        authority: pool_authority,
+    };
+    token::close_account(CpiContext::new_with_signer(token_program, cpi_accounts_close, signer_seeds))?;
+    Ok(amount)
+}
+
+/// 取消定投：退回还没有卖出的输入和已经换得的输出，关闭两个代币账户和定投金库，租金退还给用户
+pub fn close_dca(ctx: Context<CloseDca>) -> Result<()> {
+    // 构建 seeds 用于 PDA 签名
+    let auth_bump = ctx.accounts.pool_state.load()?.auth_bump;
+    let seeds: &[&[u8]] = &[
+        b"authority",
+        &[auth_bump],
+    ];
+    let signer_seeds = &[seeds];
+
+    let amount_refunded = drain_and_close(
+        &ctx.accounts.input_vault,
+        ctx.accounts.user_token_in.to_account_info(),
+        ctx.accounts.owner.to_account_info(),
+        ctx.accounts.pool_authority.to_account_info(),
+        ctx.accounts.token_program.to_account_info(),
+        signer_seeds,
+    )?;
+    let amount_out = drain_and_close(
+        &ctx.accounts.output_vault,
+        ctx.accounts.user_token_out.to_account_info(),
+        ctx.accounts.owner.to_account_info(),
+        ctx.accounts.pool_authority.to_account_info(),
+        ctx.accounts.token_program.to_account_info(),
+        signer_seeds,
+    )?;
+
+    emit!(DcaWithdrawn {
+        pool: ctx.accounts.pool_state.key(),
+        dca_vault: ctx.accounts.dca_vault.key(),
+        owner: ctx.accounts.owner.key(),
+        amount_refunded,
+        amount_out,
+        closed: true,
+    });
+    Ok(())
+}
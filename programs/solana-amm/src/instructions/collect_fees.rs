@@ -0,0 +1,73 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Transfer};
+
+use crate::clmm;
+use crate::contexts::CollectFees;
+use crate::errors::AmmError;
+use crate::events::ClFeesCollected;
+
+/// 领取头寸累计的手续费
+///
+/// 头寸还有流动性时先把上次结算之后的手续费记到 fees_owed，再全部转给用户
+pub fn collect_fees(ctx: Context<CollectFees>) -> Result<()> {
+    let cl_pool_key = ctx.accounts.cl_pool.key();
+    let position_key = ctx.accounts.position.key();
+    let mut cl_pool = ctx.accounts.cl_pool.load_mut()?;
+    let position = &mut ctx.accounts.position;
+
+    // 没有流动性的头寸在最后一次 decrease_liquidity 时已经结算过
+    if position.liquidity > 0 {
+        let (tick_lower, tick_upper) = (position.tick_lower, position.tick_upper);
+        clmm::with_position_ticks(
+            &ctx.accounts.tick_array_lower,
+            &ctx.accounts.tick_array_upper,
+            tick_lower,
+            tick_upper,
+            cl_pool.tick_spacing,
+            |lower, upper| {
+                clmm::modify_position(&mut cl_pool, position, lower, upper, 0)
+                    .ok_or_else(|| AmmError::MathOverflow.into())
+            },
+        )?;
+    }
+
+    let amount_a = position.fees_owed_a;
+    let amount_b = position.fees_owed_b;
+    position.fees_owed_a = 0;
+    position.fees_owed_b = 0;
+
+    let seeds: &[&[u8]] = &[b"authority", &[cl_pool.auth_bump]];
+    let signer_seeds = &[seeds];
+    let token_program = ctx.accounts.token_program.to_account_info();
+    if amount_a > 0 {
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.token_a_vault.to_account_info(),
+            to: ctx.accounts.user_token_a.to_account_info(),
+            authority: ctx.accounts.pool_authority.to_account_info(),
+        };
+        token::transfer(
+            CpiContext::new_with_signer(token_program.clone(), cpi_accounts, signer_seeds),
+            amount_a,
+        )?;
+    }
+    if amount_b > 0 {
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.token_b_vault.to_account_info(),
+            to: ctx.accounts.user_token_b.to_account_info(),
+            authority: ctx.accounts.pool_authority.to_account_info(),
+        };
+        token::transfer(
+            CpiContext::new_with_signer(token_program, cpi_accounts, signer_seeds),
+            amount_b,
+        )?;
+    }
+
+    emit!(ClFeesCollected {
+        pool: cl_pool_key,
+        position: position_key,
+        owner: ctx.accounts.owner.key(),
+        amount_a,
+        amount_b,
+    });
+    Ok(())
+}
@@ -0,0 +1,43 @@
+use anchor_lang::prelude::*;
+
+use crate::clmm::{self, MAX_TICK_SPACING};
+use crate::contexts::CreateClPool;
+use crate::errors::AmmError;
+
+/// 创建集中流动性池子
+///
+/// # Arguments
+/// * `tick_spacing` - 可用 tick 的间隔，头寸的边界必须是它的整数倍
+/// * `initial_sqrt_price_x64` - 初始价格的平方根（Q64.64，sqrt(B/A)）
+/// * `fee_numerator` - 手续费分子
+/// * `fee_denominator` - 手续费分母
+pub fn create_cl_pool(
+    ctx: Context<CreateClPool>,
+    tick_spacing: u16,
+    initial_sqrt_price_x64: u128,
+    fee_numerator: u64,
+    fee_denominator: u64,
+) -> Result<()> {
+    // 和恒定乘积池子一样要求 mint_a < mint_b
+    require!(ctx.accounts.token_a.key() < ctx.accounts.token_b.key(), AmmError::InvalidMint);
+    require!(fee_denominator > 0 && fee_numerator < fee_denominator, AmmError::InvalidFee);
+    require!(tick_spacing > 0 && tick_spacing <= MAX_TICK_SPACING, AmmError::InvalidTickSpacing);
+    let tick_current = clmm::tick_at_sqrt_price(initial_sqrt_price_x64).ok_or(AmmError::InvalidSqrtPrice)?;
+
+    let mut cl_pool = ctx.accounts.cl_pool.load_init()?;
+    cl_pool.sqrt_price_x64 = initial_sqrt_price_x64;
+    cl_pool.tick_current = tick_current;
+    cl_pool.tick_spacing = tick_spacing;
+    cl_pool.token_a = ctx.accounts.token_a.key();
+    cl_pool.token_b = ctx.accounts.token_b.key();
+    cl_pool.token_a_vault = ctx.accounts.token_a_vault.key();
+    cl_pool.token_b_vault = ctx.accounts.token_b_vault.key();
+    cl_pool.admin = ctx.accounts.admin.key();
+    cl_pool.fee_numerator = fee_numerator;
+    cl_pool.fee_denominator = fee_denominator;
+    cl_pool.bump = ctx.bumps.cl_pool;
+    cl_pool.auth_bump = ctx.bumps.pool_authority;
+
+    msg!("CL pool initialized at tick {}", tick_current);
+    Ok(())
+}
@@ -0,0 +1,16 @@
+use anchor_lang::prelude::*;
+
+use crate::contexts::CreateFarm;
+
+/// 为池子创建流动性挖矿，创建后通过 `add_farm_reward` 添加奖励代币
+pub fn create_farm(ctx: Context<CreateFarm>) -> Result<()> {
+    let farm = &mut ctx.accounts.farm;
+    farm.pool = ctx.accounts.pool_state.key();
+    farm.stake_vault = ctx.accounts.stake_vault.key();
+    farm.total_staked = 0;
+    farm.reward_count = 0;
+    farm.bump = ctx.bumps.farm;
+
+    msg!("Farm created for pool {}", farm.pool);
+    Ok(())
+}
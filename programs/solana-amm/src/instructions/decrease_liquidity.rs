@@ -0,0 +1,94 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Transfer};
+
+use crate::clmm;
+use crate::contexts::DecreaseLiquidity;
+use crate::errors::AmmError;
+use crate::events::ClLiquidityChanged;
+
+/// 从头寸取出流动性，对应的 token 直接转给用户，手续费留在 fees_owed 中由 `collect_fees` 领取
+///
+/// # Arguments
+/// * `ctx` - 减少流动性上下文
+/// * `liquidity` - 要取出的流动性 L，不能超过头寸当前的流动性
+/// * `min_amount_a` - 滑点保护：用户能接受的最少 token A 数量
+/// * `min_amount_b` - 滑点保护：用户能接受的最少 token B 数量
+pub fn decrease_liquidity(
+    ctx: Context<DecreaseLiquidity>,
+    liquidity: u128,
+    min_amount_a: u64,
+    min_amount_b: u64,
+) -> Result<()> {
+    require!(
+        liquidity > 0 && liquidity <= ctx.accounts.position.liquidity,
+        AmmError::InvalidLiquidityAmount
+    );
+    // position.liquidity 只会通过 increase_liquidity 增加，不会超过 i128::MAX
+    let liquidity_delta = -(liquidity as i128);
+
+    let mut cl_pool = ctx.accounts.cl_pool.load_mut()?;
+    let position = &mut ctx.accounts.position;
+    let (tick_lower, tick_upper) = (position.tick_lower, position.tick_upper);
+
+    clmm::with_position_ticks(
+        &ctx.accounts.tick_array_lower,
+        &ctx.accounts.tick_array_upper,
+        tick_lower,
+        tick_upper,
+        cl_pool.tick_spacing,
+        |lower, upper| {
+            clmm::modify_position(&mut cl_pool, position, lower, upper, liquidity_delta)
+                .ok_or_else(|| AmmError::MathOverflow.into())
+        },
+    )?;
+
+    // 取出的数量向下取整
+    let (amount_a, amount_b) = clmm::amounts_for_liquidity(
+        cl_pool.sqrt_price_x64,
+        clmm::sqrt_price_at_tick(tick_lower).ok_or(AmmError::MathOverflow)?,
+        clmm::sqrt_price_at_tick(tick_upper).ok_or(AmmError::MathOverflow)?,
+        liquidity,
+        false,
+    )
+    .ok_or(AmmError::MathOverflow)?;
+    require!(
+        amount_a >= min_amount_a && amount_b >= min_amount_b,
+        AmmError::SlippageExceeded
+    );
+
+    let seeds: &[&[u8]] = &[b"authority", &[cl_pool.auth_bump]];
+    let signer_seeds = &[seeds];
+    let token_program = ctx.accounts.token_program.to_account_info();
+    if amount_a > 0 {
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.token_a_vault.to_account_info(),
+            to: ctx.accounts.user_token_a.to_account_info(),
+            authority: ctx.accounts.pool_authority.to_account_info(),
+        };
+        token::transfer(
+            CpiContext::new_with_signer(token_program.clone(), cpi_accounts, signer_seeds),
+            amount_a,
+        )?;
+    }
+    if amount_b > 0 {
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.token_b_vault.to_account_info(),
+            to: ctx.accounts.user_token_b.to_account_info(),
+            authority: ctx.accounts.pool_authority.to_account_info(),
+        };
+        token::transfer(
+            CpiContext::new_with_signer(token_program, cpi_accounts, signer_seeds),
+            amount_b,
+        )?;
+    }
+
+    emit!(ClLiquidityChanged {
+        pool: ctx.accounts.cl_pool.key(),
+        position: ctx.accounts.position.key(),
+        owner: ctx.accounts.owner.key(),
+        liquidity_delta,
+        amount_a,
+        amount_b,
+    });
+    Ok(())
+}
@@ -0,0 +1,112 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Transfer};
+
+use crate::contexts::ExecuteDca;
+use crate::errors::AmmError;
+use crate::events::DcaExecuted;
+use crate::pool_swap::{self, PoolSwap, Trader};
+use crate::twamm::TwammSettleAccounts;
+
+/// 执行一期定投：从定投金库卖出一期输入，换得的代币存入定投金库的输出账户，任何人都可以调用
+///
+/// 和普通 swap 一样按池子费率成交，更新 TWAP 并检查价格偏离；输出低于这一期的最少输出时失败，
+/// 这一期留给之后价格合适时再执行。执行之后下一期的时间按 `cycle_interval` 对齐，错过的期数不补。
+pub fn execute_dca(ctx: Context<ExecuteDca>) -> Result<()> {
+    let clock = Clock::get()?;
+    let dca_vault = &ctx.accounts.dca_vault;
+    let remaining = ctx.accounts.input_vault.amount;
+    require!(
+        remaining > 0 && clock.unix_timestamp >= dca_vault.next_cycle_ts,
+        AmmError::DcaNotDue
+    );
+    let (amount_in, min_amount_out) = dca_vault
+        .cycle_amounts(remaining)
+        .ok_or(AmmError::MathOverflow)?;
+
+    // 和 swap 一样检查批量拍卖和许可池子，再把 TWAMM 长期订单结算到现在，之后读到的储备已经包含虚拟成交
+    let settled = pool_swap::begin_pool_swap(
+        TwammSettleAccounts {
+            pool_state: &ctx.accounts.pool_state,
+            twamm: ctx.accounts.twamm.as_ref(),
+            twamm_vault_a: ctx.accounts.twamm_vault_a.as_deref(),
+            twamm_vault_b: ctx.accounts.twamm_vault_b.as_deref(),
+            token_a_vault: &ctx.accounts.token_a_vault,
+            token_b_vault: &ctx.accounts.token_b_vault,
+            pool_authority: ctx.accounts.pool_authority.to_account_info(),
+            token_program: ctx.accounts.token_program.to_account_info(),
+        },
+        Trader::Keeper,
+        clock.unix_timestamp,
+    )?;
+    if settled {
+        ctx.accounts.token_a_vault.reload()?;
+        ctx.accounts.token_b_vault.reload()?;
+    }
+
+    let is_a_to_b = dca_vault.is_a_to_b;
+    let (vault_in, vault_out) = if is_a_to_b {
+        (&ctx.accounts.token_a_vault, &ctx.accounts.token_b_vault)
+    } else {
+        (&ctx.accounts.token_b_vault, &ctx.accounts.token_a_vault)
+    };
+    require_keys_eq!(ctx.accounts.input_vault.mint, vault_in.mint, AmmError::InvalidDca);
+
+    let mut pool_state = ctx.accounts.pool_state.load_mut()?;
+    let fee_numerator = pool_state.fee_numerator;
+    let PoolSwap { amount_out, .. } = pool_swap::execute_pool_swap(
+        &mut pool_state,
+        ctx.accounts.token_a_vault.amount,
+        ctx.accounts.token_b_vault.amount,
+        amount_in,
+        is_a_to_b,
+        fee_numerator,
+        min_amount_out,
+        clock.unix_timestamp as u64,
+    )?;
+
+    // 定投金库 -> 池子金库，池子金库 -> 定投输出账户，都由 pool_authority 签名
+    let auth_bump = pool_state.auth_bump;
+    let seeds: &[&[u8]] = &[
+        b"authority",
+        &[auth_bump],
+    ];
+    let signer_seeds = &[seeds];
+    let token_program = ctx.accounts.token_program.to_account_info();
+
+    let cpi_accounts_dca_to_pool = Transfer {
+        from: ctx.accounts.input_vault.to_account_info(),
+        to: vault_in.to_account_info(),
+        authority: ctx.accounts.pool_authority.to_account_info(),
+    };
+    token::transfer(
+        CpiContext::new_with_signer(token_program.clone(), cpi_accounts_dca_to_pool, signer_seeds),
+        amount_in,
+    )?;
+    let cpi_accounts_pool_to_dca = Transfer {
+        from: vault_out.to_account_info(),
+        to: ctx.accounts.output_vault.to_account_info(),
+        authority: ctx.accounts.pool_authority.to_account_info(),
+    };
+    token::transfer(
+        CpiContext::new_with_signer(token_program, cpi_accounts_pool_to_dca, signer_seeds),
+        amount_out,
+    )?;
+
+    let dca_vault = &mut ctx.accounts.dca_vault;
+    dca_vault.next_cycle_ts = dca_vault
+        .next_cycle_after(clock.unix_timestamp)
+        .ok_or(AmmError::MathOverflow)?;
+    dca_vault.amount_sold = dca_vault.amount_sold.checked_add(amount_in).ok_or(AmmError::MathOverflow)?;
+    dca_vault.amount_bought = dca_vault.amount_bought.checked_add(amount_out).ok_or(AmmError::MathOverflow)?;
+
+    emit!(DcaExecuted {
+        pool: dca_vault.pool,
+        dca_vault: dca_vault.key(),
+        keeper: ctx.accounts.keeper.key(),
+        is_a_to_b,
+        amount_in,
+        amount_out,
+        amount_remaining: remaining - amount_in,
+    });
+    Ok(())
+}
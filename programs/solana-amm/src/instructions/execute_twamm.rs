@@ -0,0 +1,28 @@
+use anchor_lang::prelude::*;
+
+use crate::contexts::ExecuteTwamm;
+use crate::twamm::{self, TwammSettleAccounts};
+
+/// 把池子上的长期订单结算到当前时间，任何人都可以调用
+///
+/// 池子很久没有交易时，长期订单的虚拟成交会一直累积到下一次访问；
+/// 定期调用可以让 TWAP 和订单收益及时反映虚拟成交
+pub fn execute_twamm(ctx: Context<ExecuteTwamm>) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp as u64;
+    twamm::settle_pool(
+        TwammSettleAccounts {
+            pool_state: &ctx.accounts.pool_state,
+            twamm: Some(&ctx.accounts.twamm),
+            twamm_vault_a: Some(&ctx.accounts.twamm_vault_a),
+            twamm_vault_b: Some(&ctx.accounts.twamm_vault_b),
+            token_a_vault: &ctx.accounts.token_a_vault,
+            token_b_vault: &ctx.accounts.token_b_vault,
+            pool_authority: ctx.accounts.pool_authority.to_account_info(),
+            token_program: ctx.accounts.token_program.to_account_info(),
+        },
+        now,
+    )?;
+
+    msg!("Virtual orders executed up to {}", now);
+    Ok(())
+}
@@ -0,0 +1,161 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, CloseAccount, TokenAccount, Transfer};
+
+use crate::contexts::FillOrders;
+use crate::errors::AmmError;
+use crate::events::OrderFilled;
+use crate::pool_swap::{self, PoolSwap, Trader};
+use crate::state::LimitOrder;
+use crate::twamm::TwammSettleAccounts;
+
+// 每个订单在 remaining_accounts 中占用的账户数：[limit_order, escrow, owner_token_out, owner]
+const ACCOUNTS_PER_ORDER: usize = 4;
+
+/// 按池子当前的恒定乘积曲线执行已经到达限价的订单，任何人都可以作为 keeper 调用
+///
+/// 订单按传入顺序依次成交，每笔成交都会改变储备，后面的订单按更新后的储备计算。
+/// 还没有到达限价（或者成交后会触发价格偏离保护）的订单跳过，留待之后执行；
+/// 一笔都没有成交时返回 `OrderNotFillable`，keeper 不会白白支付交易费。
+///
+/// 成交的订单：托管的输入进入金库，输出直接转给订单所有者，赏金付给 keeper，
+/// 托管账户和订单账户关闭，租金退还给订单所有者。
+pub fn fill_orders<'info>(ctx: Context<'_, '_, 'info, 'info, FillOrders<'info>>) -> Result<()> {
+    let order_accounts = ctx.remaining_accounts;
+    require!(
+        !order_accounts.is_empty() && order_accounts.len().is_multiple_of(ACCOUNTS_PER_ORDER),
+        AmmError::InvalidOrder
+    );
+
+    let pool_key = ctx.accounts.pool_state.key();
+    let clock = Clock::get()?;
+    // 限价单成交也是立即兑换，和 swap 一样检查批量拍卖和许可池子，按结算长期订单之后的储备成交
+    let settled = pool_swap::begin_pool_swap(
+        TwammSettleAccounts {
+            pool_state: &ctx.accounts.pool_state,
+            twamm: ctx.accounts.twamm.as_ref(),
+            twamm_vault_a: ctx.accounts.twamm_vault_a.as_deref(),
+            twamm_vault_b: ctx.accounts.twamm_vault_b.as_deref(),
+            token_a_vault: &ctx.accounts.token_a_vault,
+            token_b_vault: &ctx.accounts.token_b_vault,
+            pool_authority: ctx.accounts.pool_authority.to_account_info(),
+            token_program: ctx.accounts.token_program.to_account_info(),
+        },
+        Trader::Keeper,
+        clock.unix_timestamp,
+    )?;
+    if settled {
+        ctx.accounts.token_a_vault.reload()?;
+        ctx.accounts.token_b_vault.reload()?;
+    }
+    let mut pool_state = ctx.accounts.pool_state.load_mut()?;
+    let mut reserve_a = ctx.accounts.token_a_vault.amount;
+    let mut reserve_b = ctx.accounts.token_b_vault.amount;
+
+    let auth_bump = pool_state.auth_bump;
+    let seeds: &[&[u8]] = &[
+        b"authority",
+        &[auth_bump],
+    ];
+    let signer_seeds = &[seeds];
+    let token_program = ctx.accounts.token_program.to_account_info();
+    let keeper = ctx.accounts.keeper.to_account_info();
+
+    let mut filled = 0usize;
+    for accounts in order_accounts.chunks_exact(ACCOUNTS_PER_ORDER) {
+        let [order_info, escrow_info, owner_token_out_info, owner_info] = accounts else {
+            unreachable!()
+        };
+        let order = Account::<LimitOrder>::try_from(order_info)?;
+        require!(
+            order.pool == pool_key && order.escrow == escrow_info.key() && order.owner == owner_info.key(),
+            AmmError::InvalidOrder
+        );
+
+        let (vault_in, vault_out, mint_out) = if order.is_a_to_b {
+            (&ctx.accounts.token_a_vault, &ctx.accounts.token_b_vault, pool_state.token_b)
+        } else {
+            (&ctx.accounts.token_b_vault, &ctx.accounts.token_a_vault, pool_state.token_a)
+        };
+        let owner_token_out = Account::<TokenAccount>::try_from(owner_token_out_info)?;
+        require!(
+            owner_token_out.mint == mint_out && owner_token_out.owner == order.owner,
+            AmmError::InvalidUserToken
+        );
+
+        // 还没有到达限价、或者成交后超出价格偏离和储备上限的订单跳过，留待之后执行
+        let fee_numerator = pool_state.fee_numerator;
+        let Ok(PoolSwap { amount_out, reserve_a_after, reserve_b_after, .. }) = pool_swap::execute_pool_swap(
+            &mut pool_state,
+            reserve_a,
+            reserve_b,
+            order.amount_in,
+            order.is_a_to_b,
+            fee_numerator,
+            order.min_amount_out,
+            clock.unix_timestamp as u64,
+        ) else {
+            continue;
+        };
+
+        // 托管 -> 金库，金库 -> 订单所有者，都由 pool_authority 签名
+        let cpi_accounts_escrow_to_vault = Transfer {
+            from: escrow_info.clone(),
+            to: vault_in.to_account_info(),
+            authority: ctx.accounts.pool_authority.to_account_info(),
+        };
+        token::transfer(
+            CpiContext::new_with_signer(token_program.clone(), cpi_accounts_escrow_to_vault, signer_seeds),
+            order.amount_in,
+        )?;
+        let cpi_accounts_vault_to_owner = Transfer {
+            from: vault_out.to_account_info(),
+            to: owner_token_out_info.clone(),
+            authority: ctx.accounts.pool_authority.to_account_info(),
+        };
+        token::transfer(
+            CpiContext::new_with_signer(token_program.clone(), cpi_accounts_vault_to_owner, signer_seeds),
+            amount_out,
+        )?;
+        let cpi_accounts_close_escrow = CloseAccount {
+            account: escrow_info.clone(),
+            destination: owner_info.clone(),
+            authority: ctx.accounts.pool_authority.to_account_info(),
+        };
+        token::close_account(CpiContext::new_with_signer(
+            token_program.clone(),
+            cpi_accounts_close_escrow,
+            signer_seeds,
+        ))?;
+
+        // 订单账户由本程序拥有，可以直接划出赏金，剩下的租金随关闭退还
+        let keeper_bounty = order.keeper_bounty;
+        **order_info.try_borrow_mut_lamports()? = order_info
+            .lamports()
+            .checked_sub(keeper_bounty)
+            .ok_or(AmmError::MathOverflow)?;
+        **keeper.try_borrow_mut_lamports()? = keeper
+            .lamports()
+            .checked_add(keeper_bounty)
+            .ok_or(AmmError::MathOverflow)?;
+        order.close(owner_info.clone())?;
+
+        reserve_a = reserve_a_after;
+        reserve_b = reserve_b_after;
+        filled += 1;
+
+        emit!(OrderFilled {
+            pool: pool_key,
+            order: order_info.key(),
+            owner: owner_info.key(),
+            keeper: keeper.key(),
+            is_a_to_b: order.is_a_to_b,
+            amount_in: order.amount_in,
+            amount_out,
+            keeper_bounty,
+        });
+    }
+
+    require!(filled > 0, AmmError::OrderNotFillable);
+    msg!("Filled {} limit orders", filled);
+    Ok(())
+}
@@ -0,0 +1,25 @@
+use anchor_lang::prelude::*;
+
+use crate::contexts::GetGeometricTwap;
+use crate::errors::AmmError;
+use crate::oracle::{self, GeometricTwap};
+
+/// 读取最近 `window` 秒的几何平均价格，结果通过 return data 返回
+/// 
+/// 实际窗口从不晚于 `now - window` 的最近一条观测开始，可能比 `window` 略长，
+/// 起止时间一并返回
+pub fn get_geometric_twap(ctx: Context<GetGeometricTwap>, window: u64) -> Result<GeometricTwap> {
+    let clock = Clock::get()?;
+    let pool_state = ctx.accounts.pool_state.load()?;
+    let twap = oracle::geometric_twap_for_window(
+        &pool_state,
+        ctx.accounts.token_a_vault.amount,
+        ctx.accounts.token_b_vault.amount,
+        clock.unix_timestamp as u64,
+        window,
+    )
+    .ok_or(AmmError::OracleWindowUnavailable)?;
+
+    msg!("Geometric TWAP {}..{}: price_a={} price_b={}", twap.start, twap.end, twap.price_a, twap.price_b);
+    Ok(twap)
+}
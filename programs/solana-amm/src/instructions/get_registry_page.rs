@@ -0,0 +1,17 @@
+use anchor_lang::prelude::*;
+
+use crate::contexts::GetRegistryPage;
+use crate::state::RegistryPage;
+
+/// 读取注册表的第 `page_index` 页，结果通过 return data 返回
+///
+/// 返回值带上池子总数，客户端据此知道一共有多少页
+pub fn get_registry_page(ctx: Context<GetRegistryPage>, page_index: u64) -> Result<RegistryPage> {
+    let registry_page = &ctx.accounts.registry_page;
+    msg!("Registry page {}: {} pools", page_index, registry_page.entries.len());
+    Ok(RegistryPage {
+        pool_count: ctx.accounts.pool_registry.pool_count,
+        page_index,
+        entries: registry_page.entries.clone(),
+    })
+}
@@ -0,0 +1,85 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Transfer};
+
+use crate::clmm;
+use crate::contexts::IncreaseLiquidity;
+use crate::errors::AmmError;
+use crate::events::ClLiquidityChanged;
+
+/// 向头寸存入流动性
+///
+/// # Arguments
+/// * `ctx` - 增加流动性上下文
+/// * `liquidity` - 要增加的流动性 L
+/// * `max_amount_a` - 滑点保护：用户最多愿意存入的 token A 数量
+/// * `max_amount_b` - 滑点保护：用户最多愿意存入的 token B 数量
+pub fn increase_liquidity(
+    ctx: Context<IncreaseLiquidity>,
+    liquidity: u128,
+    max_amount_a: u64,
+    max_amount_b: u64,
+) -> Result<()> {
+    require!(
+        liquidity > 0 && liquidity <= i128::MAX as u128,
+        AmmError::InvalidLiquidityAmount
+    );
+
+    let mut cl_pool = ctx.accounts.cl_pool.load_mut()?;
+    let position = &mut ctx.accounts.position;
+    let (tick_lower, tick_upper) = (position.tick_lower, position.tick_upper);
+
+    // 先结算手续费并更新边界 tick，再按当前价格计算需要存入的数量
+    clmm::with_position_ticks(
+        &ctx.accounts.tick_array_lower,
+        &ctx.accounts.tick_array_upper,
+        tick_lower,
+        tick_upper,
+        cl_pool.tick_spacing,
+        |lower, upper| {
+            clmm::modify_position(&mut cl_pool, position, lower, upper, liquidity as i128)
+                .ok_or_else(|| AmmError::MathOverflow.into())
+        },
+    )?;
+
+    // 存入的数量向上取整
+    let (amount_a, amount_b) = clmm::amounts_for_liquidity(
+        cl_pool.sqrt_price_x64,
+        clmm::sqrt_price_at_tick(tick_lower).ok_or(AmmError::MathOverflow)?,
+        clmm::sqrt_price_at_tick(tick_upper).ok_or(AmmError::MathOverflow)?,
+        liquidity,
+        true,
+    )
+    .ok_or(AmmError::MathOverflow)?;
+    require!(
+        amount_a <= max_amount_a && amount_b <= max_amount_b,
+        AmmError::SlippageExceeded
+    );
+
+    let token_program = ctx.accounts.token_program.to_account_info();
+    if amount_a > 0 {
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.user_token_a.to_account_info(),
+            to: ctx.accounts.token_a_vault.to_account_info(),
+            authority: ctx.accounts.owner.to_account_info(),
+        };
+        token::transfer(CpiContext::new(token_program.clone(), cpi_accounts), amount_a)?;
+    }
+    if amount_b > 0 {
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.user_token_b.to_account_info(),
+            to: ctx.accounts.token_b_vault.to_account_info(),
+            authority: ctx.accounts.owner.to_account_info(),
+        };
+        token::transfer(CpiContext::new(token_program, cpi_accounts), amount_b)?;
+    }
+
+    emit!(ClLiquidityChanged {
+        pool: ctx.accounts.cl_pool.key(),
+        position: ctx.accounts.position.key(),
+        owner: ctx.accounts.owner.key(),
+        liquidity_delta: liquidity as i128,
+        amount_a,
+        amount_b,
+    });
+    Ok(())
+}
@@ -0,0 +1,44 @@
+use anchor_lang::prelude::*;
+
+use crate::contexts::InitBatchAuction;
+use crate::errors::AmmError;
+
+/// 给池子开启批量拍卖，只有池子管理员可以调用
+///
+/// 开启之后 swap、fill_orders 和 execute_dca 这些立即成交的路径都会失败，兑换只能通过
+/// `submit_batch_swap` 提交，每 `batch_duration_slots` 个 slot 由 `settle_batch` 按统一价格结算一次
+///
+/// # Arguments
+/// * `batch_duration_slots` - 每批的收集窗口长度
+/// * `min_order_amount_a` / `min_order_amount_b` - 卖出 A / B 的订单最少数量，每批只有
+///   `MAX_BATCH_ORDERS` 个位置，不能让零头订单占满
+pub fn init_batch_auction(
+    ctx: Context<InitBatchAuction>,
+    batch_duration_slots: u64,
+    min_order_amount_a: u64,
+    min_order_amount_b: u64,
+) -> Result<()> {
+    require!(
+        batch_duration_slots > 0 && min_order_amount_a > 0 && min_order_amount_b > 0,
+        AmmError::InvalidBatchOrder
+    );
+
+    let batch_auction = &mut ctx.accounts.batch_auction;
+    batch_auction.pool = ctx.accounts.pool_state.key();
+    batch_auction.vault_a = ctx.accounts.batch_vault_a.key();
+    batch_auction.vault_b = ctx.accounts.batch_vault_b.key();
+    batch_auction.batch_duration_slots = batch_duration_slots;
+    batch_auction.batch_id = 0;
+    batch_auction.batch_start_slot = 0;
+    batch_auction.order_count = 0;
+    batch_auction.amount_a_in = 0;
+    batch_auction.amount_b_in = 0;
+    batch_auction.min_order_amount_a = min_order_amount_a;
+    batch_auction.min_order_amount_b = min_order_amount_b;
+    batch_auction.bump = ctx.bumps.batch_auction;
+
+    ctx.accounts.pool_state.load_mut()?.batch_mode = 1;
+
+    msg!("Batch auction enabled for pool {}", ctx.accounts.pool_state.key());
+    Ok(())
+}
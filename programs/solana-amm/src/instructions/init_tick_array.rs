@@ -0,0 +1,26 @@
+use anchor_lang::prelude::*;
+
+use crate::clmm::{MAX_TICK, MIN_TICK};
+use crate::contexts::InitTickArray;
+use crate::errors::AmmError;
+use crate::state::{TickArray, TICK_ARRAY_SIZE};
+
+/// 创建覆盖 [start_tick_index, start_tick_index + TICK_ARRAY_SIZE * tick_spacing) 的 TickArray
+///
+/// # Arguments
+/// * `start_tick_index` - 起始 tick，必须是 TICK_ARRAY_SIZE * tick_spacing 的整数倍
+pub fn init_tick_array(ctx: Context<InitTickArray>, start_tick_index: i32) -> Result<()> {
+    let tick_spacing = ctx.accounts.cl_pool.load()?.tick_spacing;
+    let span = TICK_ARRAY_SIZE as i32 * tick_spacing as i32;
+    require!(
+        TickArray::start_index_for(start_tick_index, tick_spacing) == start_tick_index
+            && start_tick_index <= MAX_TICK
+            && start_tick_index + span > MIN_TICK,
+        AmmError::InvalidTickArray
+    );
+
+    let mut tick_array = ctx.accounts.tick_array.load_init()?;
+    tick_array.pool = ctx.accounts.cl_pool.key();
+    tick_array.start_tick_index = start_tick_index;
+    Ok(())
+}
@@ -0,0 +1,23 @@
+use anchor_lang::prelude::*;
+
+use crate::contexts::InitTwamm;
+
+/// 给池子开启 TWAMM 长期订单，只有池子管理员可以调用
+///
+/// 开启之后 swap、add_liquidity、remove_liquidity 和 fill_orders 都必须传入 Twamm 和它的两个金库，
+/// 每次访问池子前先把长期订单结算到当前时间
+pub fn init_twamm(ctx: Context<InitTwamm>) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp as u64;
+
+    let mut twamm = ctx.accounts.twamm.load_init()?;
+    twamm.pool = ctx.accounts.pool_state.key();
+    twamm.vault_a = ctx.accounts.twamm_vault_a.key();
+    twamm.vault_b = ctx.accounts.twamm_vault_b.key();
+    twamm.last_virtual_order_ts = now;
+    twamm.bump = ctx.bumps.twamm;
+
+    ctx.accounts.pool_state.load_mut()?.twamm = ctx.accounts.twamm.key();
+
+    msg!("TWAMM enabled for pool {}", ctx.accounts.pool_state.key());
+    Ok(())
+}
@@ -1,7 +1,12 @@
 use anchor_lang::prelude::*;
+use anchor_spl::metadata::mpl_token_metadata::types::DataV2;
+use anchor_spl::metadata::{self, CreateMetadataAccountsV3};
 
 use crate::contexts::Initialize;
 use crate::errors::AmmError;
+use crate::lp_metadata;
+use crate::math;
+use crate::state::{RegistryEntry, CURRENT_POOL_VERSION};
 
 /// 初始化 AMM 池子
 /// 
@@ -23,7 +28,7 @@ pub fn initialize(
     // 验证手续费设置：分母必须大于0，分子必须小于分母
     require!(fee_denominator > 0 && fee_numerator < fee_denominator, AmmError::InvalidFee);
     
-    let pool_state = &mut ctx.accounts.pool_state;
+    let mut pool_state = ctx.accounts.pool_state.load_init()?;
     
     // 存储代币和 vault 地址
     pool_state.token_a = ctx.accounts.token_a.key();
@@ -46,16 +51,89 @@ pub fn initialize(
     pool_state.block_timestamp_last = clock.unix_timestamp as u64;
     pool_state.price_a_cumulative_last = 0;
     pool_state.price_b_cumulative_last = 0;
+    pool_state.log_price_cumulative_last = 0;
+    // 写入第一条观测，之后的窗口查询都以它为最早的起点
+    math::record_observation(&mut pool_state, clock.unix_timestamp as u64);
 
     // admin 作为创建者
     pool_state.admin = ctx.accounts.admin.key();
-    pool_state.pending_admin = None;
+    pool_state.set_pending_admin(None);
 
     // 初始设为创建者
     pool_state.protocol_fee_recipient = ctx.accounts.admin.key();
     // 协议分成比例，默认不开启动作，0表示关闭
     pool_state.protocol_fee_share = 0;
     pool_state.k_last = 0;
+    pool_state.version = CURRENT_POOL_VERSION;
+    pool_state.registered = 1;
+    drop(pool_state);
+
+    // 追加到注册表，客户端不需要扫描全部账户就能找到这个池子
+    let pool_registry = &mut ctx.accounts.pool_registry;
+    pool_registry.bump = ctx.bumps.pool_registry;
+    pool_registry
+        .append(
+            &mut ctx.accounts.registry_page,
+            RegistryEntry {
+                pool: ctx.accounts.pool_state.key(),
+                mint_a,
+                mint_b,
+                fee_numerator,
+                fee_denominator,
+            },
+        )
+        .ok_or(AmmError::InvalidRegistryPage)?;
+
+    // 传入了 Metaplex 程序时为 LP 创建元数据
+    if let (Some(token_metadata_program), Some(lp_metadata)) =
+        (&ctx.accounts.token_metadata_program, &ctx.accounts.lp_metadata)
+    {
+        let symbol_of = |metadata: &Option<UncheckedAccount>, mint: &Pubkey| {
+            metadata
+                .as_ref()
+                .and_then(|metadata| lp_metadata::read_symbol(metadata, mint))
+                .unwrap_or_else(|| lp_metadata::fallback_symbol(mint))
+        };
+        let symbol_a = symbol_of(&ctx.accounts.token_a_metadata, &mint_a);
+        let symbol_b = symbol_of(&ctx.accounts.token_b_metadata, &mint_b);
+        let (name, symbol) = lp_metadata::lp_name_and_symbol(&symbol_a, &symbol_b);
+
+        // lp_mint 的 mint authority 和元数据的 update authority 都是 pool_authority
+        let seeds: &[&[u8]] = &[
+            b"authority",
+            &[ctx.bumps.pool_authority],
+        ];
+        let signer_seeds = &[seeds];
+        let cpi_accounts_create_metadata = CreateMetadataAccountsV3 {
+            metadata: lp_metadata.to_account_info(),
+            mint: ctx.accounts.lp_mint.to_account_info(),
+            mint_authority: ctx.accounts.pool_authority.to_account_info(),
+            payer: ctx.accounts.admin.to_account_info(),
+            update_authority: ctx.accounts.pool_authority.to_account_info(),
+            system_program: ctx.accounts.system_program.to_account_info(),
+            rent: ctx.accounts.rent.to_account_info(),
+        };
+        let cpi_ctx_create_metadata = CpiContext::new_with_signer(
+            token_metadata_program.to_account_info(),
+            cpi_accounts_create_metadata,
+            signer_seeds,
+        );
+        metadata::create_metadata_accounts_v3(
+            cpi_ctx_create_metadata,
+            DataV2 {
+                name,
+                symbol,
+                uri: String::new(),
+                seller_fee_basis_points: 0,
+                creators: None,
+                collection: None,
+                uses: None,
+            },
+            true,
+            true,
+            None,
+        )?;
+    }
 
     msg!("Pool initialized successfully.");
     Ok(())
@@ -0,0 +1,24 @@
+use anchor_lang::prelude::*;
+
+use crate::contexts::IssueAccessPass;
+use crate::errors::AmmError;
+
+/// 守门人给用户发放 AccessPass，已有的凭证重新发放时更新过期时间
+///
+/// # Arguments
+/// * `expires_ts` - 过期时间（unix 时间戳），0 表示不过期
+pub fn issue_access_pass(ctx: Context<IssueAccessPass>, expires_ts: i64) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    require!(expires_ts == 0 || expires_ts > now, AmmError::InvalidAccessPass);
+
+    let access_pass = &mut ctx.accounts.access_pass;
+    access_pass.pool = ctx.accounts.pool_state.key();
+    access_pass.user = ctx.accounts.user.key();
+    access_pass.issuer = ctx.accounts.gatekeeper.key();
+    access_pass.issued_ts = now;
+    access_pass.expires_ts = expires_ts;
+    access_pass.bump = ctx.bumps.access_pass;
+
+    msg!("Access pass issued to {} until {}", access_pass.user, expires_ts);
+    Ok(())
+}
@@ -0,0 +1,93 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Transfer};
+
+use crate::contexts::LockLp;
+use crate::errors::AmmError;
+use crate::events::LpLocked;
+use crate::math;
+use crate::twamm::{self, TwammSettleAccounts};
+
+/// 把 LP 锁仓到 `unlock_ts`
+///
+/// LP 转入由 pool_authority 控制的托管账户，到期前只能通过 `claim_lp_lock_fees` 领取手续费。
+/// 已有锁仓时追加数量，解锁时间只能延后不能提前。
+/// 池子开启了 TWAMM 时先结算长期订单，本金不会因为未结算的虚拟成交而偏离。
+///
+/// # Arguments
+/// * `amount` - 锁入的 LP 数量
+/// * `unlock_ts` - 解锁时间（unix 时间戳）
+pub fn lock_lp(ctx: Context<LockLp>, amount: u64, unlock_ts: i64) -> Result<()> {
+    require!(amount > 0, AmmError::InvalidLockAmount);
+    let clock = Clock::get()?;
+    require!(
+        unlock_ts > clock.unix_timestamp && unlock_ts >= ctx.accounts.lp_lock.unlock_ts,
+        AmmError::InvalidUnlockTime
+    );
+
+    // 先把 TWAMM 长期订单结算到现在，本金按包含虚拟成交的储备计算
+    let settled = twamm::settle_pool(
+        TwammSettleAccounts {
+            pool_state: &ctx.accounts.pool_state,
+            twamm: ctx.accounts.twamm.as_ref(),
+            twamm_vault_a: ctx.accounts.twamm_vault_a.as_deref(),
+            twamm_vault_b: ctx.accounts.twamm_vault_b.as_deref(),
+            token_a_vault: &ctx.accounts.token_a_vault,
+            token_b_vault: &ctx.accounts.token_b_vault,
+            pool_authority: ctx.accounts.pool_authority.to_account_info(),
+            token_program: ctx.accounts.token_program.to_account_info(),
+        },
+        clock.unix_timestamp as u64,
+    )?;
+    if settled {
+        ctx.accounts.token_a_vault.reload()?;
+        ctx.accounts.token_b_vault.reload()?;
+    }
+
+    // 按当前储备金记录本金；还没结算的协议费之后会增发 LP，这里先计入供应量
+    let reserve_a = ctx.accounts.token_a_vault.amount;
+    let reserve_b = ctx.accounts.token_b_vault.amount;
+    let pool_state = ctx.accounts.pool_state.load()?;
+    let pending_protocol_fee = math::calculate_protocol_fee_mint(
+        reserve_a,
+        reserve_b,
+        pool_state.k_last,
+        ctx.accounts.lp_mint.supply,
+        pool_state.protocol_fee_share,
+    ).ok_or(AmmError::MathOverflow)?;
+    drop(pool_state);
+    let lp_supply = ctx.accounts.lp_mint.supply
+        .checked_add(pending_protocol_fee)
+        .ok_or(AmmError::MathOverflow)?;
+    let principal = math::calculate_lp_value(amount, reserve_a, reserve_b, lp_supply)
+        .ok_or(AmmError::MathOverflow)?;
+
+    // 用户 -> 托管账户：用户签名
+    let cpi_accounts_user_to_lock = Transfer {
+        from: ctx.accounts.user_lp_token.to_account_info(),
+        to: ctx.accounts.lock_vault.to_account_info(),
+        authority: ctx.accounts.user.to_account_info(),
+    };
+    let cpi_ctx_user_to_lock = CpiContext::new(
+        ctx.accounts.token_program.to_account_info(),
+        cpi_accounts_user_to_lock,
+    );
+    token::transfer(cpi_ctx_user_to_lock, amount)?;
+
+    let lp_lock = &mut ctx.accounts.lp_lock;
+    lp_lock.pool = ctx.accounts.pool_state.key();
+    lp_lock.owner = ctx.accounts.user.key();
+    lp_lock.lock_vault = ctx.accounts.lock_vault.key();
+    lp_lock.amount = lp_lock.amount.checked_add(amount).ok_or(AmmError::MathOverflow)?;
+    lp_lock.unlock_ts = unlock_ts;
+    lp_lock.principal = lp_lock.principal.checked_add(principal).ok_or(AmmError::MathOverflow)?;
+    lp_lock.bump = ctx.bumps.lp_lock;
+
+    emit!(LpLocked {
+        pool: lp_lock.pool,
+        owner: lp_lock.owner,
+        amount,
+        total_locked: lp_lock.amount,
+        unlock_ts,
+    });
+    Ok(())
+}
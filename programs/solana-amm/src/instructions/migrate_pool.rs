@@ -0,0 +1,83 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{self, Transfer};
+use anchor_lang::Discriminator;
+
+use crate::contexts::MigratePool;
+use crate::errors::AmmError;
+use crate::state::{LegacyPoolState, PoolState, CURRENT_POOL_VERSION, POOL_RESERVED_BYTES, POOL_V2_LEN};
+
+/// 把旧版本的 PoolState 升级到当前版本
+///
+/// 版本 0、1 是 Borsh 布局，比零拷贝布局短：先按 `LegacyPoolState` 解析（版本 0 的账户是它的前缀，补零即可），
+/// 再扩容到 `PoolState::LEN`，按零拷贝布局写回，最后为补零后没有意义的字段填上默认值。
+/// 版本 2 已经是零拷贝布局，只是缺少末尾的 `reserved`，扩容后原地补零即可。
+pub fn migrate_pool(ctx: Context<MigratePool>) -> Result<()> {
+    let pool_info = ctx.accounts.pool_state.to_account_info();
+    {
+        let data = pool_info.try_borrow_data()?;
+        require!(
+            data.len() >= PoolState::DISCRIMINATOR.len() && data.starts_with(PoolState::DISCRIMINATOR),
+            ErrorCode::AccountDiscriminatorMismatch
+        );
+    }
+
+    let old_len = pool_info.data_len();
+    let legacy = if old_len < POOL_V2_LEN {
+        let mut legacy_data = pool_info.try_borrow_data()?[PoolState::DISCRIMINATOR.len()..].to_vec();
+        legacy_data.resize(LegacyPoolState::LEN - PoolState::DISCRIMINATOR.len(), 0);
+        Some(Box::new(LegacyPoolState::deserialize(&mut legacy_data.as_slice())?))
+    } else {
+        None
+    };
+
+    if old_len < PoolState::LEN {
+        // 先补足新长度需要的租金，再扩容
+        let rent_needed = Rent::get()?
+            .minimum_balance(PoolState::LEN)
+            .saturating_sub(pool_info.lamports());
+        if rent_needed > 0 {
+            let cpi_accounts_payer_to_pool = Transfer {
+                from: ctx.accounts.payer.to_account_info(),
+                to: pool_info.clone(),
+            };
+            let cpi_ctx_payer_to_pool = CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                cpi_accounts_payer_to_pool,
+            );
+            system_program::transfer(cpi_ctx_payer_to_pool, rent_needed)?;
+        }
+        pool_info.resize(PoolState::LEN)?;
+    }
+
+    // 和 AccountLoader::load_mut 一样直接在账户数据上读写
+    let mut data = pool_info.try_borrow_mut_data()?;
+    let pool_state: &mut PoolState =
+        bytemuck::from_bytes_mut(&mut data[PoolState::DISCRIMINATOR.len()..PoolState::LEN]);
+    if let Some(legacy) = legacy {
+        *pool_state = legacy.to_pool_state();
+    }
+    require!(pool_state.version < CURRENT_POOL_VERSION, AmmError::PoolAlreadyMigrated);
+    // 扩容前的数据可能残留在新划出的区域里，预留空间必须是全零
+    pool_state.reserved = [0; POOL_RESERVED_BYTES];
+
+    // 没有 TWAP 字段的池子从现在开始累加，避免第一次更新时把 1970 年以来的时间都计入
+    if pool_state.block_timestamp_last == 0 {
+        pool_state.block_timestamp_last = Clock::get()?.unix_timestamp as u64;
+    }
+    // 和 initialize 一样，协议费默认发给管理员
+    if pool_state.protocol_fee_recipient == Pubkey::default() {
+        pool_state.protocol_fee_recipient = pool_state.admin;
+    }
+    // 其余新字段补零就是默认值：没有观测、不开启价格偏离保护和推荐费
+    let from_version = pool_state.version;
+    pool_state.version = CURRENT_POOL_VERSION;
+
+    msg!(
+        "Pool migrated from version {} ({} bytes) to {} ({} bytes)",
+        from_version,
+        old_len,
+        CURRENT_POOL_VERSION,
+        PoolState::LEN
+    );
+    Ok(())
+}
@@ -7,10 +7,96 @@ pub mod add_liquidity;
 pub mod remove_liquidity;
 pub mod update_config;
 pub mod claim_admin;
+pub mod get_geometric_twap;
+pub mod set_fee_override;
+pub mod remove_fee_override;
+pub mod lock_lp;
+pub mod unlock_lp;
+pub mod claim_lp_lock_fees;
+pub mod create_farm;
+pub mod add_farm_reward;
+pub mod stake_lp;
+pub mod unstake_lp;
+pub mod claim_rewards;
+pub mod update_lp_metadata;
+pub mod migrate_pool;
+pub mod create_cl_pool;
+pub mod init_tick_array;
+pub mod open_position;
+pub mod increase_liquidity;
+pub mod decrease_liquidity;
+pub mod collect_fees;
+pub mod cl_swap;
+pub mod place_order;
+pub mod cancel_order;
+pub mod fill_orders;
+pub mod init_twamm;
+pub mod place_long_term_order;
+pub mod withdraw_long_term_order;
+pub mod execute_twamm;
+pub mod open_dca;
+pub mod execute_dca;
+pub mod withdraw_dca;
+pub mod close_dca;
+pub mod init_batch_auction;
+pub mod submit_batch_swap;
+pub mod settle_batch;
+pub mod register_pool;
+pub mod get_registry_page;
+pub mod set_deposit_caps;
+pub mod set_gatekeeper;
+pub mod issue_access_pass;
+pub mod revoke_access_pass;
+pub mod set_batch_mode;
+pub mod claim_batch_order;
+pub mod cancel_batch_order;
 
 pub use initialize::*;
 pub use swap::*;
 pub use add_liquidity::*;
 pub use remove_liquidity::*;
 pub use update_config::*;
-pub use claim_admin::*;
\ No newline at end of file
+pub use claim_admin::*;
+pub use get_geometric_twap::*;
+pub use set_fee_override::*;
+pub use remove_fee_override::*;
+pub use lock_lp::*;
+pub use unlock_lp::*;
+pub use claim_lp_lock_fees::*;
+pub use create_farm::*;
+pub use add_farm_reward::*;
+pub use stake_lp::*;
+pub use unstake_lp::*;
+pub use claim_rewards::*;
+pub use update_lp_metadata::*;
+pub use migrate_pool::*;
+pub use create_cl_pool::*;
+pub use init_tick_array::*;
+pub use open_position::*;
+pub use increase_liquidity::*;
+pub use decrease_liquidity::*;
+pub use collect_fees::*;
+pub use cl_swap::*;
+pub use place_order::*;
+pub use cancel_order::*;
+pub use fill_orders::*;
+pub use init_twamm::*;
+pub use place_long_term_order::*;
+pub use withdraw_long_term_order::*;
+pub use execute_twamm::*;
+pub use open_dca::*;
+pub use execute_dca::*;
+pub use withdraw_dca::*;
+pub use close_dca::*;
+pub use init_batch_auction::*;
+pub use submit_batch_swap::*;
+pub use settle_batch::*;
+pub use register_pool::*;
+pub use get_registry_page::*;
+pub use set_deposit_caps::*;
+pub use set_gatekeeper::*;
+pub use issue_access_pass::*;
+pub use revoke_access_pass::*;
+pub use set_batch_mode::*;
+pub use claim_batch_order::*;
+pub use cancel_batch_order::*;
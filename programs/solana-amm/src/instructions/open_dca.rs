@@ -0,0 +1,71 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Transfer};
+
+use crate::contexts::OpenDca;
+use crate::errors::AmmError;
+use crate::events::DcaOpened;
+use crate::permission;
+
+/// 创建定投金库：输入代币全部存入，之后每隔 `cycle_interval` 秒由 keeper 调用 `execute_dca` 卖出一期
+///
+/// # Arguments
+/// * `ctx` - 创建定投的上下文
+/// * `dca_id` - 用户自选的编号，用于派生定投金库地址
+/// * `amount_in` - 存入的总数量
+/// * `is_a_to_b` - true 表示定期卖出 A 买入 B
+/// * `amount_per_cycle` - 每期卖出的数量
+/// * `cycle_interval` - 两期之间的秒数，第一期创建后立即可以执行
+/// * `min_amount_out_per_cycle` - 每期卖出 amount_per_cycle 时的最少输出，0 表示不限制
+pub fn open_dca(
+    ctx: Context<OpenDca>,
+    dca_id: u64,
+    amount_in: u64,
+    is_a_to_b: bool,
+    amount_per_cycle: u64,
+    cycle_interval: i64,
+    min_amount_out_per_cycle: u64,
+) -> Result<()> {
+    permission::require_permissionless(&ctx.accounts.pool_state)?;
+
+    require!(
+        amount_in > 0 && amount_per_cycle > 0 && amount_per_cycle <= amount_in && cycle_interval > 0,
+        AmmError::InvalidDca
+    );
+
+    let cpi_accounts_user_to_dca = Transfer {
+        from: ctx.accounts.user_token_in.to_account_info(),
+        to: ctx.accounts.input_vault.to_account_info(),
+        authority: ctx.accounts.owner.to_account_info(),
+    };
+    let cpi_ctx_user_to_dca = CpiContext::new(
+        ctx.accounts.token_program.to_account_info(),
+        cpi_accounts_user_to_dca,
+    );
+    token::transfer(cpi_ctx_user_to_dca, amount_in)?;
+
+    let dca_vault = &mut ctx.accounts.dca_vault;
+    dca_vault.pool = ctx.accounts.pool_state.key();
+    dca_vault.owner = ctx.accounts.owner.key();
+    dca_vault.input_vault = ctx.accounts.input_vault.key();
+    dca_vault.output_vault = ctx.accounts.output_vault.key();
+    dca_vault.dca_id = dca_id;
+    dca_vault.is_a_to_b = is_a_to_b;
+    dca_vault.amount_per_cycle = amount_per_cycle;
+    dca_vault.min_amount_out_per_cycle = min_amount_out_per_cycle;
+    dca_vault.cycle_interval = cycle_interval;
+    dca_vault.next_cycle_ts = Clock::get()?.unix_timestamp;
+    dca_vault.amount_sold = 0;
+    dca_vault.amount_bought = 0;
+    dca_vault.bump = ctx.bumps.dca_vault;
+
+    emit!(DcaOpened {
+        pool: dca_vault.pool,
+        dca_vault: dca_vault.key(),
+        owner: dca_vault.owner,
+        is_a_to_b,
+        amount_in,
+        amount_per_cycle,
+        cycle_interval,
+    });
+    Ok(())
+}
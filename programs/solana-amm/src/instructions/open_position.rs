@@ -0,0 +1,26 @@
+use anchor_lang::prelude::*;
+
+use crate::clmm::{MAX_TICK, MIN_TICK};
+use crate::contexts::OpenPosition;
+use crate::errors::AmmError;
+
+/// 开立一个 [tick_lower, tick_upper) 区间的空头寸，之后用 `increase_liquidity` 存入流动性
+pub fn open_position(ctx: Context<OpenPosition>, tick_lower: i32, tick_upper: i32) -> Result<()> {
+    let tick_spacing = ctx.accounts.cl_pool.load()?.tick_spacing as i32;
+    require!(
+        tick_lower < tick_upper
+            && tick_lower >= MIN_TICK
+            && tick_upper <= MAX_TICK
+            && tick_lower % tick_spacing == 0
+            && tick_upper % tick_spacing == 0,
+        AmmError::InvalidTickRange
+    );
+
+    let position = &mut ctx.accounts.position;
+    position.pool = ctx.accounts.cl_pool.key();
+    position.owner = ctx.accounts.owner.key();
+    position.tick_lower = tick_lower;
+    position.tick_upper = tick_upper;
+    position.bump = ctx.bumps.position;
+    Ok(())
+}
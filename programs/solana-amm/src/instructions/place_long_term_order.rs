@@ -0,0 +1,105 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Transfer};
+
+use crate::contexts::PlaceLongTermOrder;
+use crate::errors::AmmError;
+use crate::events::LongTermOrderPlaced;
+use crate::permission;
+use crate::state::{TWAMM_INTERVAL, TWAMM_SLOTS};
+use crate::twamm::{self, TwammSettleAccounts, TWAMM_RATE_SHIFT};
+
+/// 挂出 TWAMM 长期订单：输入代币转入 Twamm 金库，从现在起到第 `intervals` 个 TWAMM_INTERVAL
+/// 边界之间匀速卖出，卖出的过程在每次访问池子时惰性结算
+///
+/// # Arguments
+/// * `ctx` - 挂单上下文
+/// * `order_id` - 用户自选的订单编号，用于派生订单地址
+/// * `amount_in` - 卖出的总数量
+/// * `is_a_to_b` - true 表示卖出 A 买入 B
+/// * `intervals` - 持续的间隔数，到期时间是 (now / TWAMM_INTERVAL + intervals) * TWAMM_INTERVAL
+pub fn place_long_term_order(
+    ctx: Context<PlaceLongTermOrder>,
+    order_id: u64,
+    amount_in: u64,
+    is_a_to_b: bool,
+    intervals: u16,
+) -> Result<()> {
+    permission::require_permissionless(&ctx.accounts.pool_state)?;
+
+    require!(
+        amount_in > 0 && intervals > 0 && (intervals as usize) < TWAMM_SLOTS,
+        AmmError::InvalidLongTermOrder
+    );
+    // 池子没有流动性时虚拟订单无法成交
+    require!(
+        ctx.accounts.token_a_vault.amount > 0 && ctx.accounts.token_b_vault.amount > 0,
+        AmmError::InvalidLongTermOrder
+    );
+
+    // 先把已有的订单结算到现在，新订单从现在开始卖出
+    let now = Clock::get()?.unix_timestamp as u64;
+    twamm::settle_pool(
+        TwammSettleAccounts {
+            pool_state: &ctx.accounts.pool_state,
+            twamm: Some(&ctx.accounts.twamm),
+            twamm_vault_a: Some(&ctx.accounts.twamm_vault_a),
+            twamm_vault_b: Some(&ctx.accounts.twamm_vault_b),
+            token_a_vault: &ctx.accounts.token_a_vault,
+            token_b_vault: &ctx.accounts.token_b_vault,
+            pool_authority: ctx.accounts.pool_authority.to_account_info(),
+            token_program: ctx.accounts.token_program.to_account_info(),
+        },
+        now,
+    )?;
+    // 结算暂停时新订单会从 last_virtual_order_ts 开始被计入卖出，必须等结算恢复
+    require!(
+        ctx.accounts.twamm.load()?.last_virtual_order_ts == now,
+        AmmError::TwammSettlementPaused
+    );
+
+    let expiry_ts = (now / TWAMM_INTERVAL + intervals as u64) * TWAMM_INTERVAL;
+    let sell_rate = ((amount_in as u128) << TWAMM_RATE_SHIFT) / (expiry_ts - now) as u128;
+
+    let proceeds_per_rate_start_x64 =
+        twamm::open_long_term_order(&mut *ctx.accounts.twamm.load_mut()?, is_a_to_b, sell_rate, expiry_ts)
+            .ok_or(AmmError::InvalidLongTermOrder)?;
+
+    let twamm_vault_in = if is_a_to_b {
+        &ctx.accounts.twamm_vault_a
+    } else {
+        &ctx.accounts.twamm_vault_b
+    };
+    let cpi_accounts_user_to_twamm = Transfer {
+        from: ctx.accounts.user_token_in.to_account_info(),
+        to: twamm_vault_in.to_account_info(),
+        authority: ctx.accounts.owner.to_account_info(),
+    };
+    let cpi_ctx_user_to_twamm = CpiContext::new(
+        ctx.accounts.token_program.to_account_info(),
+        cpi_accounts_user_to_twamm,
+    );
+    token::transfer(cpi_ctx_user_to_twamm, amount_in)?;
+
+    let order = &mut ctx.accounts.long_term_order;
+    order.pool = ctx.accounts.pool_state.key();
+    order.owner = ctx.accounts.owner.key();
+    order.order_id = order_id;
+    order.is_a_to_b = is_a_to_b;
+    order.sell_rate = sell_rate;
+    order.amount_in = amount_in;
+    order.start_ts = now;
+    order.expiry_ts = expiry_ts;
+    order.proceeds_per_rate_start_x64 = proceeds_per_rate_start_x64;
+    order.bump = ctx.bumps.long_term_order;
+
+    emit!(LongTermOrderPlaced {
+        pool: order.pool,
+        order: order.key(),
+        owner: order.owner,
+        is_a_to_b,
+        amount_in,
+        sell_rate,
+        expiry_ts,
+    });
+    Ok(())
+}
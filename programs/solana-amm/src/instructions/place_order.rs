@@ -0,0 +1,77 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use anchor_spl::token::{self, Transfer};
+
+use crate::contexts::PlaceOrder;
+use crate::errors::AmmError;
+use crate::events::OrderPlaced;
+use crate::permission;
+
+/// 挂出限价单：输入代币转入托管账户，等价格到达后由 keeper 调用 `fill_orders` 执行
+///
+/// # Arguments
+/// * `ctx` - 挂单上下文
+/// * `order_id` - 用户自选的订单编号，用于派生订单地址
+/// * `amount_in` - 卖出的数量
+/// * `is_a_to_b` - true 表示卖出 A 买入 B
+/// * `min_amount_out` - 整笔订单的最少输出，即限价 min_amount_out / amount_in
+/// * `keeper_bounty` - 付给执行订单的 keeper 的 lamports，挂单时存入订单账户
+pub fn place_order(
+    ctx: Context<PlaceOrder>,
+    order_id: u64,
+    amount_in: u64,
+    is_a_to_b: bool,
+    min_amount_out: u64,
+    keeper_bounty: u64,
+) -> Result<()> {
+    permission::require_permissionless(&ctx.accounts.pool_state)?;
+
+    require!(amount_in > 0 && min_amount_out > 0, AmmError::InvalidOrder);
+
+    let cpi_accounts_user_to_escrow = Transfer {
+        from: ctx.accounts.user_token_in.to_account_info(),
+        to: ctx.accounts.escrow.to_account_info(),
+        authority: ctx.accounts.owner.to_account_info(),
+    };
+    let cpi_ctx_user_to_escrow = CpiContext::new(
+        ctx.accounts.token_program.to_account_info(),
+        cpi_accounts_user_to_escrow,
+    );
+    token::transfer(cpi_ctx_user_to_escrow, amount_in)?;
+
+    // 赏金和租金一起放在订单账户里，撤单时随账户关闭退回
+    if keeper_bounty > 0 {
+        let cpi_accounts_bounty = system_program::Transfer {
+            from: ctx.accounts.owner.to_account_info(),
+            to: ctx.accounts.limit_order.to_account_info(),
+        };
+        let cpi_ctx_bounty = CpiContext::new(
+            ctx.accounts.system_program.to_account_info(),
+            cpi_accounts_bounty,
+        );
+        system_program::transfer(cpi_ctx_bounty, keeper_bounty)?;
+    }
+
+    let limit_order = &mut ctx.accounts.limit_order;
+    limit_order.pool = ctx.accounts.pool_state.key();
+    limit_order.owner = ctx.accounts.owner.key();
+    limit_order.escrow = ctx.accounts.escrow.key();
+    limit_order.order_id = order_id;
+    limit_order.is_a_to_b = is_a_to_b;
+    limit_order.amount_in = amount_in;
+    limit_order.min_amount_out = min_amount_out;
+    limit_order.keeper_bounty = keeper_bounty;
+    limit_order.created_ts = Clock::get()?.unix_timestamp;
+    limit_order.bump = ctx.bumps.limit_order;
+
+    emit!(OrderPlaced {
+        pool: limit_order.pool,
+        order: limit_order.key(),
+        owner: limit_order.owner,
+        is_a_to_b,
+        amount_in,
+        min_amount_out,
+        keeper_bounty,
+    });
+    Ok(())
+}
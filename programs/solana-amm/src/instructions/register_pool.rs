@@ -0,0 +1,32 @@
+use anchor_lang::prelude::*;
+
+use crate::contexts::RegisterPool;
+use crate::errors::AmmError;
+use crate::state::RegistryEntry;
+
+/// 把注册表上线之前创建的池子补登记到注册表
+///
+/// 之后创建的池子在 `initialize` 中已经登记，`registered` 标记保证每个池子只登记一次
+pub fn register_pool(ctx: Context<RegisterPool>) -> Result<()> {
+    let mut pool_state = ctx.accounts.pool_state.load_mut()?;
+    require!(!pool_state.registered(), AmmError::PoolAlreadyRegistered);
+    pool_state.registered = 1;
+
+    let entry = RegistryEntry {
+        pool: ctx.accounts.pool_state.key(),
+        mint_a: pool_state.token_a,
+        mint_b: pool_state.token_b,
+        fee_numerator: pool_state.fee_numerator,
+        fee_denominator: pool_state.fee_denominator,
+    };
+    drop(pool_state);
+
+    let pool_registry = &mut ctx.accounts.pool_registry;
+    pool_registry.bump = ctx.bumps.pool_registry;
+    pool_registry
+        .append(&mut ctx.accounts.registry_page, entry)
+        .ok_or(AmmError::InvalidRegistryPage)?;
+
+    msg!("Pool {} registered as #{}", entry.pool, pool_registry.pool_count - 1);
+    Ok(())
+}
@@ -0,0 +1,9 @@
+use anchor_lang::prelude::*;
+
+use crate::contexts::RemoveFeeOverride;
+
+/// 删除交易者的折扣费率，账户由 `close = admin` 关闭，之后该交易者恢复池子的默认费率
+pub fn remove_fee_override(ctx: Context<RemoveFeeOverride>) -> Result<()> {
+    msg!("Fee override removed for {}", ctx.accounts.fee_override.trader);
+    Ok(())
+}
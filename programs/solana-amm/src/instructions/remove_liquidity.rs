@@ -3,7 +3,9 @@ use anchor_spl::token::{self, Burn, MintTo, Transfer};
 
 use crate::contexts::RemoveLiquidity;
 use crate::errors::AmmError;
-use crate::math;
+use crate::permission;
+use crate::pool_liquidity::{self, PoolWithdrawal};
+use crate::twamm::{self, TwammSettleAccounts};
 
 /// 从池子移除流动性
 /// 
@@ -12,11 +14,13 @@ use crate::math;
 /// * `amount_lp` - 要销毁的 LP token 数量
 /// * `min_amount_a` - 滑点保护：用户能接受的最少 token A 数量
 /// * `min_amount_b` - 滑点保护：用户能接受的最少 token B 数量
+/// * `deadline` - 可选的过期时间（unix 时间戳），晚于该时间执行会失败
 pub fn remove_liquidity(
     ctx: Context<RemoveLiquidity>,
     amount_lp: u64,
     min_amount_a: u64,
     min_amount_b: u64,
+    deadline: Option<i64>,
 ) -> Result<()> {
     // 这里检查用户给持有的lp_mint的token是否大于0
     // 如果小于0则revert
@@ -25,8 +29,43 @@ pub fn remove_liquidity(
         AmmError::InvalidLpMint
     );
 
+    // TWAP 获取时间戳
+    let clock = Clock::get()?;
+    // 和 swap 一样，过了 deadline 的交易不再执行
+    if let Some(deadline) = deadline {
+        require!(clock.unix_timestamp <= deadline, AmmError::Expired);
+    }
+    // 许可池子要求用户持有有效的 AccessPass
+    permission::check_access(
+        &ctx.accounts.pool_state,
+        ctx.accounts.access_pass.as_deref(),
+        ctx.accounts.pool_gatekeeper.as_deref(),
+        clock.unix_timestamp,
+    )?;
+    // 先把 TWAMM 长期订单结算到现在，之后读到的储备已经包含虚拟成交
+    let settled = twamm::settle_pool(
+        TwammSettleAccounts {
+            pool_state: &ctx.accounts.pool_state,
+            twamm: ctx.accounts.twamm.as_ref(),
+            twamm_vault_a: ctx.accounts.twamm_vault_a.as_deref(),
+            twamm_vault_b: ctx.accounts.twamm_vault_b.as_deref(),
+            token_a_vault: &ctx.accounts.token_a_vault,
+            token_b_vault: &ctx.accounts.token_b_vault,
+            pool_authority: ctx.accounts.pool_authority.to_account_info(),
+            token_program: ctx.accounts.token_program.to_account_info(),
+        },
+        clock.unix_timestamp as u64,
+    )?;
+    if settled {
+        ctx.accounts.token_a_vault.reload()?;
+        ctx.accounts.token_b_vault.reload()?;
+    }
+
+    // 零拷贝加载，直接在账户数据上读写
+    let mut pool_state = ctx.accounts.pool_state.load_mut()?;
+
     // 构建 seeds 用于 PDA 签名
-    let auth_bump = ctx.accounts.pool_state.auth_bump;
+    let auth_bump = pool_state.auth_bump;
     let seeds: &[&[u8]] = &[
         b"authority",
         &[auth_bump],   
@@ -35,28 +74,24 @@ pub fn remove_liquidity(
     // CPI 程序复用：后续需要多次构造 CpiContext，这里统一拿到 token_program
     let token_program = ctx.accounts.token_program.to_account_info();
 
-    // TWAP 获取时间戳
-    let clock = Clock::get()?;
     let current_timestamp = clock.unix_timestamp as u64;
 
-    // 调用math里面的函数来更新TWAP
-    math::update_twap(
-        &mut ctx.accounts.pool_state,
-        ctx.accounts.token_a_vault.amount,
-        ctx.accounts.token_b_vault.amount,
-        current_timestamp,
-    );
-
-    // 跟add_liquidity的思路一样，计算协议方应该销毁多少LP
-    let protocol_mint_amount = math::calculate_protocol_fee_mint(
-        ctx.accounts.token_a_vault.amount,
-        ctx.accounts.token_b_vault.amount,
-        ctx.accounts.pool_state.k_last,
-        ctx.accounts.lp_mint.supply,
-        ctx.accounts.pool_state.protocol_fee_share,
-    ).unwrap_or(0);
+    // TWAP 更新、协议费、用户取回的数量、滑点保护和 k_last 都在 pool_liquidity 中计算，链下模拟器调用同一个函数
+    // transfer 发生之前，token_a_vault.amount 拿到的还是旧余额，k_last 按预期的未来余额更新，保证了状态更新的原子性
+    let PoolWithdrawal { protocol_fee_lp, amount_a: user_get_amount_a, amount_b: user_get_amount_b, .. } =
+        pool_liquidity::execute_remove_liquidity(
+            &mut pool_state,
+            ctx.accounts.token_a_vault.amount,
+            ctx.accounts.token_b_vault.amount,
+            ctx.accounts.lp_mint.supply,
+            amount_lp,
+            min_amount_a,
+            min_amount_b,
+            current_timestamp,
+        )?;
 
-    if protocol_mint_amount > 0 {
+    // 跟add_liquidity的思路一样，先给协议方增发LP
+    if protocol_fee_lp > 0 {
         let cpi_accounts_mint_to_protocol = MintTo {
             mint: ctx.accounts.lp_mint.to_account_info(),
             to: ctx.accounts.protocol_fee_recipient.to_account_info(),
@@ -67,54 +102,10 @@ pub fn remove_liquidity(
             cpi_accounts_mint_to_protocol,
             signer_seeds,
         );
-        token::mint_to(cpi_ctx_mint_to_protocol, protocol_mint_amount)?;
-        msg!("Protocol mint amount: {}", protocol_mint_amount);
+        token::mint_to(cpi_ctx_mint_to_protocol, protocol_fee_lp)?;
+        msg!("Protocol mint amount: {}", protocol_fee_lp);
     }
-
-    let total_lp_supply = ctx.accounts.lp_mint.supply.checked_add(protocol_mint_amount).ok_or(AmmError::MathOverflow)?;
-    msg!("Total LP supply: {}", total_lp_supply);
-    
-    // 计算用户分别获得多少token a和b
-    // 根据用户输入的 amount_lp 计算比例
-    // 计算公式以a为例子，就是 user_get_amount_a = amount_lp * (token_a_vault.amount / lp_mint.supply)
-    // 使用 u128 进行中间计算以避免溢出
-    let user_get_amount_a = (amount_lp as u128)
-        .checked_mul(ctx.accounts.token_a_vault.amount as u128)
-        .ok_or(AmmError::MathOverflow)?
-        .checked_div(total_lp_supply as u128)
-        .ok_or(AmmError::MathOverflow)? as u64;
-    
-    let user_get_amount_b = (amount_lp as u128)
-        .checked_mul(ctx.accounts.token_b_vault.amount as u128)
-        .ok_or(AmmError::MathOverflow)?
-        .checked_div(total_lp_supply as u128)
-        .ok_or(AmmError::MathOverflow)? as u64;
-    
-    // 检查用户获得的token a和b是否大于最小值（滑点保护）
-    require!(
-        user_get_amount_a >= min_amount_a,
-        AmmError::SlippageExceeded
-    );
-    require!(
-        user_get_amount_b >= min_amount_b,
-        AmmError::SlippageExceeded
-    );
-
-    // 计算k_last
-    // 扣除掉发给用户的钱后的新储备金计算 K
-    // transfer 发生之前，token_a_vault.amount 拿到的还是旧余额
-    // 这里手动计算"预期的未来余额"来更新 k_last，保证了状态更新的原子性
-    let new_reserve_a = ctx.accounts.token_a_vault.amount
-        .checked_sub(user_get_amount_a)
-        .ok_or(AmmError::MathOverflow)?;
-    let new_reserve_b = ctx.accounts.token_b_vault.amount
-        .checked_sub(user_get_amount_b)
-        .ok_or(AmmError::MathOverflow)?;
-    ctx.accounts.pool_state.k_last = (new_reserve_a as u128)
-        .checked_mul(new_reserve_b as u128)
-        .ok_or(AmmError::MathOverflow)?;
-    msg!("New k_last: {}", ctx.accounts.pool_state.k_last);
-
+    msg!("New k_last: {}", pool_state.k_last);
 
     // 先将lp_mint的token从用户账户burn掉
     // Burn 指令只需要 mint、from 和 authority，不需要 to 账户
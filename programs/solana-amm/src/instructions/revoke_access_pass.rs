@@ -0,0 +1,9 @@
+use anchor_lang::prelude::*;
+
+use crate::contexts::RevokeAccessPass;
+
+/// 守门人撤销 AccessPass：账户由 `close` 约束关闭，之后用户在许可池子中的交易都会失败
+pub fn revoke_access_pass(ctx: Context<RevokeAccessPass>) -> Result<()> {
+    msg!("Access pass revoked for {}", ctx.accounts.access_pass.user);
+    Ok(())
+}
@@ -0,0 +1,14 @@
+use anchor_lang::prelude::*;
+
+use crate::contexts::SetBatchMode;
+
+/// 开启或关闭池子的批量拍卖，只有池子管理员可以调用，BatchAuction 必须已经由 `init_batch_auction` 创建
+///
+/// 关闭之后 swap、fill_orders、execute_dca 恢复立即成交，`submit_batch_swap` 以 `BatchAuctionDisabled` 失败；
+/// 当前批次已经提交的订单仍然可以通过 `settle_batch` 结算，也可以由所有者用 `cancel_batch_order` 撤销
+pub fn set_batch_mode(ctx: Context<SetBatchMode>, enabled: bool) -> Result<()> {
+    ctx.accounts.pool_state.load_mut()?.batch_mode = enabled as u8;
+
+    msg!("Pool {} batch mode: {}", ctx.accounts.pool_state.key(), enabled);
+    Ok(())
+}
@@ -0,0 +1,30 @@
+use anchor_lang::prelude::*;
+
+use crate::contexts::SetDepositCaps;
+
+/// 设置池子的储备上限和单笔存入限额，0 表示不限制
+///
+/// 新上线或风险较高的代币可以先限制池子的规模：`add_liquidity` 和会让输入一侧储备超过上限的兑换
+/// 都以 `CapExceeded` 失败。上限调低到当前储备以下时不会影响取出流动性和反方向的兑换
+pub fn set_deposit_caps(
+    ctx: Context<SetDepositCaps>,
+    max_total_reserve_a: u64,
+    max_total_reserve_b: u64,
+    max_deposit_a: u64,
+    max_deposit_b: u64,
+) -> Result<()> {
+    let mut pool_state = ctx.accounts.pool_state.load_mut()?;
+    pool_state.max_total_reserve_a = max_total_reserve_a;
+    pool_state.max_total_reserve_b = max_total_reserve_b;
+    pool_state.max_deposit_a = max_deposit_a;
+    pool_state.max_deposit_b = max_deposit_b;
+
+    msg!(
+        "Deposit caps: reserves {} / {}, per deposit {} / {}",
+        max_total_reserve_a,
+        max_total_reserve_b,
+        max_deposit_a,
+        max_deposit_b
+    );
+    Ok(())
+}
@@ -0,0 +1,26 @@
+use anchor_lang::prelude::*;
+
+use crate::contexts::SetFeeOverride;
+use crate::errors::AmmError;
+
+/// 为交易者设置折扣费率，已存在时直接覆盖
+///
+/// swap 时把这个 PDA 作为第一个 remaining account 传入即可使用折扣费率
+///
+/// # Arguments
+/// * `fee_numerator` - 折扣后的手续费分子，分母沿用池子的 fee_denominator，不能高于池子本身的费率
+pub fn set_fee_override(ctx: Context<SetFeeOverride>, fee_numerator: u64) -> Result<()> {
+    require!(
+        fee_numerator <= ctx.accounts.pool_state.load()?.fee_numerator,
+        AmmError::InvalidFeeOverride
+    );
+
+    let fee_override = &mut ctx.accounts.fee_override;
+    fee_override.pool = ctx.accounts.pool_state.key();
+    fee_override.trader = ctx.accounts.trader.key();
+    fee_override.fee_numerator = fee_numerator;
+    fee_override.bump = ctx.bumps.fee_override;
+
+    msg!("Fee override for {}: {}", fee_override.trader, fee_numerator);
+    Ok(())
+}
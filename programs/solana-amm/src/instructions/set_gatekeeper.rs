@@ -0,0 +1,21 @@
+use anchor_lang::prelude::*;
+
+use crate::contexts::SetGatekeeper;
+
+/// 指定池子的守门人，只有池子管理员可以调用
+///
+/// 第一次调用时开启许可模式：之后 swap、add_liquidity、remove_liquidity 要求用户持有守门人发放的
+/// AccessPass。再次调用可以更换守门人，旧守门人发放的 AccessPass 随之失效，需要新守门人重新发放；
+/// 传入 `Pubkey::default()` 关闭许可模式
+pub fn set_gatekeeper(ctx: Context<SetGatekeeper>, gatekeeper: Pubkey) -> Result<()> {
+    let pool_gatekeeper = &mut ctx.accounts.pool_gatekeeper;
+    pool_gatekeeper.pool = ctx.accounts.pool_state.key();
+    pool_gatekeeper.authority = gatekeeper;
+    pool_gatekeeper.bump = ctx.bumps.pool_gatekeeper;
+
+    let permissioned = gatekeeper != Pubkey::default();
+    ctx.accounts.pool_state.load_mut()?.permissioned = permissioned as u8;
+
+    msg!("Pool {} gatekeeper: {}, permissioned: {}", pool_gatekeeper.pool, gatekeeper, permissioned);
+    Ok(())
+}
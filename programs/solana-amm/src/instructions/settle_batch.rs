@@ -0,0 +1,161 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Transfer};
+
+use crate::batch_auction::{self, BatchFill};
+use crate::contexts::SettleBatch;
+use crate::errors::AmmError;
+use crate::events::BatchSettled;
+use crate::math;
+use crate::pool_swap;
+use crate::state::BatchOrder;
+use crate::twamm::{self, TwammSettleAccounts};
+
+/// 收集窗口结束后按统一价格结算当前批次，任何人都可以调用
+///
+/// 必须传入当前批次的全部订单（数量和 `order_count` 一致且不重复），结算者不能挑选或者漏掉订单。
+/// 成交订单的输出、未成交订单的输入都只记在订单上，代币留在托管账户中，由所有者调用 `claim_batch_order` 取回；
+/// 结算不触碰用户的代币账户，任何一个所有者关闭或者转让自己的账户都不会卡住整个批次。
+/// 托管账户和池子金库之间只转移净额，之后开始收集下一批。
+///
+/// 成交之后超出价格偏离或储备上限时，多出一方中卖出最多的订单不成交、退回输入，直到满足限制。
+pub fn settle_batch<'info>(ctx: Context<'_, '_, 'info, 'info, SettleBatch<'info>>) -> Result<()> {
+    let clock = Clock::get()?;
+    let batch_auction = &ctx.accounts.batch_auction;
+    require!(batch_auction.window_closed(clock.slot), AmmError::BatchNotReady);
+    let order_accounts = ctx.remaining_accounts;
+    require!(
+        order_accounts.len() == batch_auction.order_count as usize,
+        AmmError::InvalidBatchOrder
+    );
+
+    // 批次按结算长期订单之后的储备成交
+    let settled = twamm::settle_pool(
+        TwammSettleAccounts {
+            pool_state: &ctx.accounts.pool_state,
+            twamm: ctx.accounts.twamm.as_ref(),
+            twamm_vault_a: ctx.accounts.twamm_vault_a.as_deref(),
+            twamm_vault_b: ctx.accounts.twamm_vault_b.as_deref(),
+            token_a_vault: &ctx.accounts.token_a_vault,
+            token_b_vault: &ctx.accounts.token_b_vault,
+            pool_authority: ctx.accounts.pool_authority.to_account_info(),
+            token_program: ctx.accounts.token_program.to_account_info(),
+        },
+        clock.unix_timestamp as u64,
+    )?;
+    if settled {
+        ctx.accounts.token_a_vault.reload()?;
+        ctx.accounts.token_b_vault.reload()?;
+    }
+
+    let pool_key = ctx.accounts.pool_state.key();
+    let mut pool_state = ctx.accounts.pool_state.load_mut()?;
+    let batch_id = batch_auction.batch_id;
+    let mut orders: Vec<Account<BatchOrder>> = Vec::with_capacity(batch_auction.order_count as usize);
+    for order_info in order_accounts {
+        let order = Account::<BatchOrder>::try_from(order_info)?;
+        require!(
+            order.pool == pool_key
+                && order.batch_id == batch_id
+                && !order.settled
+                && orders.iter().all(|other| other.key() != order.key()),
+            AmmError::InvalidBatchOrder
+        );
+        orders.push(order);
+    }
+
+    let reserve_a = ctx.accounts.token_a_vault.amount;
+    let reserve_b = ctx.accounts.token_b_vault.amount;
+    let fills: Vec<BatchFill> = orders
+        .iter()
+        .map(|order| BatchFill {
+            is_a_to_b: order.is_a_to_b,
+            amount_in: order.amount_in,
+            min_amount_out: order.min_amount_out,
+        })
+        .collect();
+
+    // 和 swap 一样先用成交前的储备更新 TWAP，价格偏离以更新之后的 TWAP 为参考
+    math::update_twap(&mut pool_state, reserve_a, reserve_b, clock.unix_timestamp as u64);
+
+    let clearing = batch_auction::clear_batch(
+        &fills,
+        reserve_a,
+        reserve_b,
+        pool_state.fee_numerator,
+        pool_state.fee_denominator,
+        |trade| pool_swap::check_pool_bounds(&pool_state, reserve_a, reserve_b, trade.reserve_a, trade.reserve_b).is_ok(),
+    )
+    .ok_or(AmmError::MathOverflow)?;
+    // clear_batch 已经排除了超出限制的订单，这里和 swap 一样再检查一次
+    pool_swap::check_pool_bounds(&pool_state, reserve_a, reserve_b, clearing.trade.reserve_a, clearing.trade.reserve_b)?;
+
+    let auth_bump = pool_state.auth_bump;
+    let seeds: &[&[u8]] = &[
+        b"authority",
+        &[auth_bump],
+    ];
+    let signer_seeds = &[seeds];
+    let token_program = ctx.accounts.token_program.to_account_info();
+    let pool_authority = ctx.accounts.pool_authority.to_account_info();
+
+    // 托管账户和池子金库之间只转移储备的净变化
+    for (batch_vault, pool_vault, before, after) in [
+        (&ctx.accounts.batch_vault_a, &ctx.accounts.token_a_vault, reserve_a, clearing.trade.reserve_a),
+        (&ctx.accounts.batch_vault_b, &ctx.accounts.token_b_vault, reserve_b, clearing.trade.reserve_b),
+    ] {
+        let (from, to) = if after > before {
+            (batch_vault.to_account_info(), pool_vault.to_account_info())
+        } else {
+            (pool_vault.to_account_info(), batch_vault.to_account_info())
+        };
+        let amount = after.abs_diff(before);
+        if amount == 0 {
+            continue;
+        }
+        let cpi_accounts = Transfer {
+            from,
+            to,
+            authority: pool_authority.clone(),
+        };
+        token::transfer(
+            CpiContext::new_with_signer(token_program.clone(), cpi_accounts, signer_seeds),
+            amount,
+        )?;
+    }
+
+    // 成交的订单记下输出，没有成交的订单记下退回的输入，所有者之后自己取回
+    let mut orders_filled = 0u8;
+    for (mut order, amount_out) in orders.into_iter().zip(clearing.amounts_out) {
+        let (claim_a, claim_b) = match (amount_out, order.is_a_to_b) {
+            (Some(amount_out), true) => (0, amount_out),
+            (Some(amount_out), false) => (amount_out, 0),
+            (None, true) => (order.amount_in, 0),
+            (None, false) => (0, order.amount_in),
+        };
+        orders_filled += amount_out.is_some() as u8;
+        order.settled = true;
+        order.claim_amount_a = claim_a;
+        order.claim_amount_b = claim_b;
+        order.exit(&crate::ID)?;
+    }
+
+    let orders_refunded = ctx.accounts.batch_auction.order_count - orders_filled;
+    let batch_auction = &mut ctx.accounts.batch_auction;
+    batch_auction.batch_id = batch_id.checked_add(1).ok_or(AmmError::MathOverflow)?;
+    batch_auction.batch_start_slot = 0;
+    batch_auction.order_count = 0;
+    batch_auction.amount_a_in = 0;
+    batch_auction.amount_b_in = 0;
+
+    emit!(BatchSettled {
+        pool: pool_key,
+        batch_id,
+        amount_a_in: clearing.amount_a_in,
+        amount_b_in: clearing.amount_b_in,
+        proceeds_a: clearing.trade.proceeds_a,
+        proceeds_b: clearing.trade.proceeds_b,
+        orders_filled,
+        orders_refunded,
+    });
+    Ok(())
+}
@@ -0,0 +1,50 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Transfer};
+
+use crate::contexts::StakeLp;
+use crate::errors::AmmError;
+use crate::events::LpStaked;
+use crate::math;
+
+/// 质押 LP 参与流动性挖矿
+///
+/// 质押量变化前先把已产生的奖励结算到 `pending_rewards`，可以随时通过 `claim_rewards` 领取。
+///
+/// # Arguments
+/// * `amount` - 质押的 LP 数量
+pub fn stake_lp(ctx: Context<StakeLp>, amount: u64) -> Result<()> {
+    require!(amount > 0, AmmError::InvalidStakeAmount);
+    let clock = Clock::get()?;
+
+    let farm = &mut ctx.accounts.farm;
+    let farm_stake = &mut ctx.accounts.farm_stake;
+    math::update_farm_rewards(farm, clock.unix_timestamp).ok_or(AmmError::MathOverflow)?;
+    math::settle_farm_stake(farm, farm_stake).ok_or(AmmError::MathOverflow)?;
+
+    // 用户 -> 质押金库：用户签名
+    let cpi_accounts_user_to_stake = Transfer {
+        from: ctx.accounts.user_lp_token.to_account_info(),
+        to: ctx.accounts.stake_vault.to_account_info(),
+        authority: ctx.accounts.user.to_account_info(),
+    };
+    let cpi_ctx_user_to_stake = CpiContext::new(
+        ctx.accounts.token_program.to_account_info(),
+        cpi_accounts_user_to_stake,
+    );
+    token::transfer(cpi_ctx_user_to_stake, amount)?;
+
+    farm_stake.farm = farm.key();
+    farm_stake.owner = ctx.accounts.user.key();
+    farm_stake.amount = farm_stake.amount.checked_add(amount).ok_or(AmmError::MathOverflow)?;
+    farm_stake.bump = ctx.bumps.farm_stake;
+    farm.total_staked = farm.total_staked.checked_add(amount).ok_or(AmmError::MathOverflow)?;
+    math::reset_farm_reward_debts(farm, farm_stake).ok_or(AmmError::MathOverflow)?;
+
+    emit!(LpStaked {
+        farm: farm.key(),
+        owner: farm_stake.owner,
+        amount,
+        total_staked: farm.total_staked,
+    });
+    Ok(())
+}
@@ -0,0 +1,80 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Transfer};
+
+use crate::contexts::SubmitBatchSwap;
+use crate::errors::AmmError;
+use crate::events::BatchSwapSubmitted;
+use crate::permission;
+use crate::state::MAX_BATCH_ORDERS;
+
+/// 把一笔兑换提交到当前批次，输入代币存入托管账户，等收集窗口结束后按统一价格结算
+///
+/// # Arguments
+/// * `ctx` - 提交订单的上下文
+/// * `amount_in` - 卖出的数量，不能少于 BatchAuction 设置的最少数量
+/// * `is_a_to_b` - true 表示卖出 A 买入 B
+/// * `min_amount_out` - 统一价格下的最少输出，达不到时结算退回输入
+///
+/// 当前批次的第一笔订单开始计算收集窗口；窗口已经结束但还没有结算时先调用 `settle_batch`
+pub fn submit_batch_swap(
+    ctx: Context<SubmitBatchSwap>,
+    amount_in: u64,
+    is_a_to_b: bool,
+    min_amount_out: u64,
+) -> Result<()> {
+    permission::require_permissionless(&ctx.accounts.pool_state)?;
+    require!(ctx.accounts.pool_state.load()?.batch_mode(), AmmError::BatchAuctionDisabled);
+
+    let slot = Clock::get()?.slot;
+    let batch_auction = &mut ctx.accounts.batch_auction;
+    let min_order_amount = if is_a_to_b {
+        batch_auction.min_order_amount_a
+    } else {
+        batch_auction.min_order_amount_b
+    };
+    require!(amount_in > 0 && amount_in >= min_order_amount, AmmError::InvalidBatchOrder);
+    require!(
+        batch_auction.order_count < MAX_BATCH_ORDERS && !batch_auction.window_closed(slot),
+        AmmError::BatchClosed
+    );
+    if batch_auction.order_count == 0 {
+        batch_auction.batch_start_slot = slot;
+    }
+    batch_auction.order_count += 1;
+    if is_a_to_b {
+        batch_auction.amount_a_in = batch_auction.amount_a_in.checked_add(amount_in).ok_or(AmmError::MathOverflow)?;
+    } else {
+        batch_auction.amount_b_in = batch_auction.amount_b_in.checked_add(amount_in).ok_or(AmmError::MathOverflow)?;
+    }
+
+    let cpi_accounts_user_to_batch = Transfer {
+        from: ctx.accounts.user_token_in.to_account_info(),
+        to: ctx.accounts.batch_vault_in.to_account_info(),
+        authority: ctx.accounts.owner.to_account_info(),
+    };
+    let cpi_ctx_user_to_batch = CpiContext::new(
+        ctx.accounts.token_program.to_account_info(),
+        cpi_accounts_user_to_batch,
+    );
+    token::transfer(cpi_ctx_user_to_batch, amount_in)?;
+
+    let batch_order = &mut ctx.accounts.batch_order;
+    batch_order.pool = ctx.accounts.pool_state.key();
+    batch_order.owner = ctx.accounts.owner.key();
+    batch_order.batch_id = batch_auction.batch_id;
+    batch_order.is_a_to_b = is_a_to_b;
+    batch_order.amount_in = amount_in;
+    batch_order.min_amount_out = min_amount_out;
+    batch_order.bump = ctx.bumps.batch_order;
+
+    emit!(BatchSwapSubmitted {
+        pool: batch_order.pool,
+        batch_id: batch_order.batch_id,
+        order: batch_order.key(),
+        owner: batch_order.owner,
+        is_a_to_b,
+        amount_in,
+        min_amount_out,
+    });
+    Ok(())
+}
@@ -3,7 +3,22 @@ use anchor_spl::token::{self, Transfer};
 
 use crate::contexts::Swap;
 use crate::errors::AmmError;
+use crate::events::SwapEvent;
 use crate::math;
+use crate::pool_swap::{self, PoolSwap, Trader};
+use crate::state::FeeOverride;
+use crate::twamm::TwammSettleAccounts;
+
+// 读取并校验 remaining_accounts 中传入的 FeeOverride：必须由本程序拥有，并且属于这个池子和交易者
+fn load_fee_override(account: &AccountInfo, pool: &Pubkey, trader: &Pubkey) -> Result<FeeOverride> {
+    require_keys_eq!(*account.owner, crate::ID, AmmError::InvalidFeeOverride);
+    let fee_override = FeeOverride::try_deserialize(&mut &account.try_borrow_data()?[..])?;
+    require!(
+        fee_override.pool == *pool && fee_override.trader == *trader,
+        AmmError::InvalidFeeOverride
+    );
+    Ok(fee_override)
+}
 
 /// 执行代币交换
 /// 
@@ -12,18 +27,55 @@ use crate::math;
 /// * `amount_in` - 输入代币数量
 /// * `is_a_to_b` - 交换方向：true 表示 A->B，false 表示 B->A
 /// * `min_amount_out` - 滑点保护：用户能接受的最低到账金额
+/// * `deadline` - 可选的过期时间（unix 时间戳），晚于该时间执行会失败
+/// * `referral_fee_bps` - 推荐费比例（万分比），从 `amount_in` 中划出转给 `referrer_token_account`
+///
+/// 有折扣费率的交易者把自己的 `FeeOverride` PDA 作为第一个 remaining account 传入
 pub fn swap(
     ctx: Context<Swap>, 
     amount_in: u64, 
     is_a_to_b: bool, 
     min_amount_out: u64,
+    deadline: Option<i64>,
+    referral_fee_bps: u16,
 ) -> Result<()> {
+    // TWAP 获取时间戳
+    let clock = Clock::get()?;
+    // 交易在 deadline 之后才上链时直接失败，避免延迟的交易按过时的滑点设置成交
+    // 放在最前面，过期的交易不会再触发任何结算或转账
+    if let Some(deadline) = deadline {
+        require!(clock.unix_timestamp <= deadline, AmmError::Expired);
+    }
+
+    // 批量拍卖、许可池子的准入检查，再把 TWAMM 长期订单结算到现在，之后读到的储备已经包含虚拟成交
+    let settled = pool_swap::begin_pool_swap(
+        TwammSettleAccounts {
+            pool_state: &ctx.accounts.pool_state,
+            twamm: ctx.accounts.twamm.as_ref(),
+            twamm_vault_a: ctx.accounts.twamm_vault_a.as_deref(),
+            twamm_vault_b: ctx.accounts.twamm_vault_b.as_deref(),
+            token_a_vault: &ctx.accounts.token_a_vault,
+            token_b_vault: &ctx.accounts.token_b_vault,
+            pool_authority: ctx.accounts.pool_authority.to_account_info(),
+            token_program: ctx.accounts.token_program.to_account_info(),
+        },
+        Trader::User {
+            access_pass: ctx.accounts.access_pass.as_deref(),
+            pool_gatekeeper: ctx.accounts.pool_gatekeeper.as_deref(),
+        },
+        clock.unix_timestamp,
+    )?;
+    if settled {
+        ctx.accounts.token_a_vault.reload()?;
+        ctx.accounts.token_b_vault.reload()?;
+    }
+
     // 方向由调用者通过 is_a_to_b 参数传入
     // 注意：Swap 结构体中的约束确保 user_token_a 总是 Token A，user_token_b 总是 Token B
     // 但通过 is_a_to_b 参数，我们可以灵活决定哪个是输入、哪个是输出
     
     // 根据方向构建转账账户映射
-    let (user_token_in, user_token_out, vault_in, vault_out, reserve_in, reserve_out) = if is_a_to_b {
+    let (user_token_in, user_token_out, vault_in, vault_out) = if is_a_to_b {
         msg!("AtoB");
         // AtoB: 用户存入 A，池子支付 B
         (
@@ -31,8 +83,6 @@ pub fn swap(
             &ctx.accounts.user_token_b,
             &ctx.accounts.token_a_vault,
             &ctx.accounts.token_b_vault,
-            ctx.accounts.token_a_vault.amount,
-            ctx.accounts.token_b_vault.amount,
         )
     } else {
         msg!("BtoA");
@@ -42,52 +92,52 @@ pub fn swap(
             &ctx.accounts.user_token_a,
             &ctx.accounts.token_b_vault,
             &ctx.accounts.token_a_vault,
-            ctx.accounts.token_b_vault.amount,
-            ctx.accounts.token_a_vault.amount,
         )
     };
 
-    // TWAP 获取时间戳
-    let clock = Clock::get()?;
+    // 零拷贝加载，直接在账户数据上读写
+    let mut pool_state = ctx.accounts.pool_state.load_mut()?;
+
     let current_timestamp = clock.unix_timestamp as u64;
 
-    // 调用math里面的函数来更新TWAP
-    // 重要：无论交易方向如何，都必须使用 Token A 和 Token B 的原始余额
-    // 因为 update_twap 假设第一个参数是 Token A，第二个参数是 Token B
-    math::update_twap(
-        &mut ctx.accounts.pool_state,
+    // 推荐费：先从输入中划出，剩余部分才进入恒定乘积计算
+    require!(
+        referral_fee_bps <= pool_state.max_referral_fee_bps,
+        AmmError::ReferralFeeTooHigh
+    );
+    let (referral_amount, amount_in_to_pool) =
+        math::calculate_referral_fee(amount_in, referral_fee_bps).ok_or(AmmError::MathOverflow)?;
+    let referrer = ctx.accounts.referrer_token_account.as_ref();
+    if referral_fee_bps > 0 {
+        require!(
+            referrer.is_some_and(|account| account.mint == vault_in.mint),
+            AmmError::InvalidReferrer
+        );
+    }
+
+    // 折扣费率在设置时已经保证不高于池子费率，这里再取一次 min 兜底
+    let fee_numerator = match ctx.remaining_accounts.first() {
+        Some(account) => load_fee_override(account, &ctx.accounts.pool_state.key(), &ctx.accounts.user.key())?
+            .fee_numerator
+            .min(pool_state.fee_numerator),
+        None => pool_state.fee_numerator,
+    };
+    // 更新 TWAP、计算输出，检查滑点、价格偏离和储备上限，和其他成交路径共用同一组检查
+    // 重要：无论交易方向如何，都传入 Token A 和 Token B 的原始余额
+    let PoolSwap { amount_in_effective, amount_out, .. } = pool_swap::execute_pool_swap(
+        &mut pool_state,
         ctx.accounts.token_a_vault.amount,
         ctx.accounts.token_b_vault.amount,
+        amount_in_to_pool,
+        is_a_to_b,
+        fee_numerator,
+        min_amount_out,
         current_timestamp,
-    );
-
-    // 计算手续费和输出金额
-    let fee_denominator = ctx.accounts.pool_state.fee_denominator;
-    let fee_numerator = ctx.accounts.pool_state.fee_numerator;
-    // 计算在扣掉手续费之后有效的输入是多少
-    // 在solana中数学运算会溢出，所以这里使用checked_mul和checked_sub来防止溢出
-    let amount_in_effective = amount_in
-        .checked_mul(fee_denominator.checked_sub(fee_numerator).ok_or(AmmError::MathOverflow)?)
-        .ok_or(AmmError::MathOverflow)?
-        .checked_div(fee_denominator)
-        .ok_or(AmmError::MathOverflow)?;
+    )?;
 
     msg!("amount_in_effective: {}", amount_in_effective);
-    // 根据公式计算输出也就是amount_out
-    // 使用u128进行中间计算以避免溢出
-    let amount_out = (reserve_out as u128)
-        .checked_mul(amount_in_effective as u128)
-        .ok_or(AmmError::MathOverflow)?
-        .checked_div((reserve_in as u128).checked_add(amount_in_effective as u128).ok_or(AmmError::MathOverflow)?)
-        .ok_or(AmmError::MathOverflow)? as u64;
     msg!("amount_out: {}", amount_out);
 
-    // 如果滑点大于设定的滑点，则交易失败
-    require!(
-        amount_out >= min_amount_out, 
-        AmmError::SlippageExceeded 
-    );
-
     
     // CPI 转账
     // 用户 -> pool（存款）：用户签名
@@ -100,11 +150,26 @@ pub fn swap(
     // 这里在发起这笔交易的时候已经用私钥签过了，已经包含在ctx.accounts.user中，所以现在只要将签名权交给token_program即可
     let cpi_ctx_user_to_pool = CpiContext::new(cpi_program_user_to_pool, cpi_accounts_user_to_pool);
     // 完成转账
-    token::transfer(cpi_ctx_user_to_pool, amount_in)?;
+    token::transfer(cpi_ctx_user_to_pool, amount_in_to_pool)?;
+
+    // 用户 -> 推荐人：同样由用户签名
+    if referral_amount > 0 {
+        let referrer = referrer.ok_or(AmmError::InvalidReferrer)?;
+        let cpi_accounts_user_to_referrer = Transfer {
+            from: user_token_in.to_account_info(),
+            to: referrer.to_account_info(),
+            authority: ctx.accounts.user.to_account_info(),
+        };
+        let cpi_ctx_user_to_referrer = CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            cpi_accounts_user_to_referrer,
+        );
+        token::transfer(cpi_ctx_user_to_referrer, referral_amount)?;
+    }
 
     // pool -> 用户（取款）：使用 PDA 签名
     // 构建 seeds 用于 PDA 签名
-    let auth_bump = ctx.accounts.pool_state.auth_bump;
+    let auth_bump = pool_state.auth_bump;
     let seeds: &[&[u8]] = &[
         b"authority",
         &[auth_bump],   
@@ -125,6 +190,16 @@ pub fn swap(
     );
     token::transfer(cpi_ctx_pool_to_user, amount_out)?;
 
+    emit!(SwapEvent {
+        pool: ctx.accounts.pool_state.key(),
+        user: ctx.accounts.user.key(),
+        is_a_to_b,
+        amount_in,
+        amount_out,
+        referrer: referrer.filter(|_| referral_amount > 0).map(|account| account.key()),
+        referral_amount,
+    });
+
     msg!("Swap completed: {} -> {}", amount_in, amount_out);
     Ok(())
 }
@@ -0,0 +1,57 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, CloseAccount, Transfer};
+
+use crate::contexts::UnlockLp;
+use crate::errors::AmmError;
+use crate::events::LpUnlocked;
+
+/// 到期后取回托管的全部 LP，并关闭托管账户和锁仓记录，租金退还给用户
+pub fn unlock_lp(ctx: Context<UnlockLp>) -> Result<()> {
+    let clock = Clock::get()?;
+    require!(
+        clock.unix_timestamp >= ctx.accounts.lp_lock.unlock_ts,
+        AmmError::LpStillLocked
+    );
+
+    // 构建 seeds 用于 PDA 签名
+    let auth_bump = ctx.accounts.pool_state.load()?.auth_bump;
+    let seeds: &[&[u8]] = &[
+        b"authority",
+        &[auth_bump],
+    ];
+    let signer_seeds = &[seeds];
+    let token_program = ctx.accounts.token_program.to_account_info();
+
+    // 按托管账户的实际余额取回，未领取的手续费也包含在内
+    let amount = ctx.accounts.lock_vault.amount;
+    let cpi_accounts_lock_to_user = Transfer {
+        from: ctx.accounts.lock_vault.to_account_info(),
+        to: ctx.accounts.user_lp_token.to_account_info(),
+        authority: ctx.accounts.pool_authority.to_account_info(),
+    };
+    let cpi_ctx_lock_to_user = CpiContext::new_with_signer(
+        token_program.clone(),
+        cpi_accounts_lock_to_user,
+        signer_seeds,
+    );
+    token::transfer(cpi_ctx_lock_to_user, amount)?;
+
+    let cpi_accounts_close_lock_vault = CloseAccount {
+        account: ctx.accounts.lock_vault.to_account_info(),
+        destination: ctx.accounts.user.to_account_info(),
+        authority: ctx.accounts.pool_authority.to_account_info(),
+    };
+    let cpi_ctx_close_lock_vault = CpiContext::new_with_signer(
+        token_program,
+        cpi_accounts_close_lock_vault,
+        signer_seeds,
+    );
+    token::close_account(cpi_ctx_close_lock_vault)?;
+
+    emit!(LpUnlocked {
+        pool: ctx.accounts.pool_state.key(),
+        owner: ctx.accounts.user.key(),
+        amount,
+    });
+    Ok(())
+}
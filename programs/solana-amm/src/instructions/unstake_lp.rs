@@ -0,0 +1,59 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Transfer};
+
+use crate::contexts::UnstakeLp;
+use crate::errors::AmmError;
+use crate::events::LpUnstaked;
+use crate::math;
+
+/// 取回质押的 LP
+///
+/// 已产生的奖励结算到 `pending_rewards` 后保留在质押记录中，全部取回后仍然可以领取。
+///
+/// # Arguments
+/// * `amount` - 取回的 LP 数量
+pub fn unstake_lp(ctx: Context<UnstakeLp>, amount: u64) -> Result<()> {
+    require!(
+        amount > 0 && amount <= ctx.accounts.farm_stake.amount,
+        AmmError::InvalidStakeAmount
+    );
+    let clock = Clock::get()?;
+
+    let farm = &mut ctx.accounts.farm;
+    let farm_stake = &mut ctx.accounts.farm_stake;
+    math::update_farm_rewards(farm, clock.unix_timestamp).ok_or(AmmError::MathOverflow)?;
+    math::settle_farm_stake(farm, farm_stake).ok_or(AmmError::MathOverflow)?;
+
+    farm_stake.amount -= amount;
+    farm.total_staked = farm.total_staked.checked_sub(amount).ok_or(AmmError::MathOverflow)?;
+    math::reset_farm_reward_debts(farm, farm_stake).ok_or(AmmError::MathOverflow)?;
+
+    // 构建 seeds 用于 PDA 签名
+    let auth_bump = ctx.accounts.pool_state.load()?.auth_bump;
+    let seeds: &[&[u8]] = &[
+        b"authority",
+        &[auth_bump],
+    ];
+    let signer_seeds = &[seeds];
+
+    // 质押金库 -> 用户：pool_authority 签名
+    let cpi_accounts_stake_to_user = Transfer {
+        from: ctx.accounts.stake_vault.to_account_info(),
+        to: ctx.accounts.user_lp_token.to_account_info(),
+        authority: ctx.accounts.pool_authority.to_account_info(),
+    };
+    let cpi_ctx_stake_to_user = CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        cpi_accounts_stake_to_user,
+        signer_seeds,
+    );
+    token::transfer(cpi_ctx_stake_to_user, amount)?;
+
+    emit!(LpUnstaked {
+        farm: ctx.accounts.farm.key(),
+        owner: ctx.accounts.user.key(),
+        amount,
+        total_staked: ctx.accounts.farm.total_staked,
+    });
+    Ok(())
+}
@@ -3,11 +3,15 @@
 // 新的管理地址：用来转让权限的
 // 接收地址：用来接收协议收入的
 // 协议分成比例
+// 价格偏离上限：swap 后现货价格相对 TWAP 的最大偏离
+// 推荐费上限：swap 时推荐人最多能收取的比例
 
 use anchor_lang::prelude::*;
 
 use crate::contexts::UpdateConfig;
 use crate::errors::AmmError;
+use crate::oracle;
+use crate::state::MAX_REFERRAL_FEE_BPS;
 
 
 pub fn update_config(
@@ -15,13 +19,15 @@ pub fn update_config(
     new_admin: Option<Pubkey>,
     new_recipient: Option<Pubkey>,
     new_share: Option<u64>,
+    new_max_price_deviation_bps: Option<u16>,
+    new_max_referral_fee_bps: Option<u16>,
 ) -> Result<()> {
-    let pool_state = &mut ctx.accounts.pool_state;
+    let mut pool_state = ctx.accounts.pool_state.load_mut()?;
 
     // 修改管理者权限
     if let Some(admin) = new_admin {
         // pool_state.admin = admin;
-        pool_state.pending_admin = Some(admin);
+        pool_state.set_pending_admin(Some(admin));
     }
 
     // 修改协议的账户
@@ -35,6 +41,18 @@ pub fn update_config(
         require!(share <= 500, AmmError::InvalidFeeConfig);
         pool_state.protocol_fee_share = share;
     }
+
+    // 修改价格偏离上限，0 表示关闭
+    if let Some(bps) = new_max_price_deviation_bps {
+        require!(bps as u128 <= oracle::BPS_DENOMINATOR, AmmError::InvalidPriceDeviationConfig);
+        pool_state.max_price_deviation_bps = bps;
+    }
+
+    // 修改推荐费上限，0 表示不允许推荐费
+    if let Some(bps) = new_max_referral_fee_bps {
+        require!(bps <= MAX_REFERRAL_FEE_BPS, AmmError::InvalidReferralFeeConfig);
+        pool_state.max_referral_fee_bps = bps;
+    }
    
     Ok(())
 }
\ No newline at end of file
@@ -0,0 +1,60 @@
+use anchor_lang::prelude::*;
+use anchor_spl::metadata::mpl_token_metadata::types::DataV2;
+use anchor_spl::metadata::{self, UpdateMetadataAccountsV2};
+
+use crate::contexts::UpdateLpMetadata;
+use crate::errors::AmmError;
+use crate::lp_metadata;
+
+/// 修改 LP 代币的 Metaplex 元数据
+///
+/// 元数据在 `initialize` 时创建，URI 为空，管理员可以在这里补上图标和描述的链接。
+///
+/// # Arguments
+/// * `name` - 名称，最多 32 字节
+/// * `symbol` - 符号，最多 10 字节
+/// * `uri` - 链下 JSON 的地址，最多 200 字节
+pub fn update_lp_metadata(
+    ctx: Context<UpdateLpMetadata>,
+    name: String,
+    symbol: String,
+    uri: String,
+) -> Result<()> {
+    require!(lp_metadata::is_valid(&name, &symbol, &uri), AmmError::InvalidLpMetadata);
+
+    // 构建 seeds 用于 PDA 签名
+    let auth_bump = ctx.accounts.pool_state.load()?.auth_bump;
+    let seeds: &[&[u8]] = &[
+        b"authority",
+        &[auth_bump],
+    ];
+    let signer_seeds = &[seeds];
+
+    let cpi_accounts_update_metadata = UpdateMetadataAccountsV2 {
+        metadata: ctx.accounts.lp_metadata.to_account_info(),
+        update_authority: ctx.accounts.pool_authority.to_account_info(),
+    };
+    let cpi_ctx_update_metadata = CpiContext::new_with_signer(
+        ctx.accounts.token_metadata_program.to_account_info(),
+        cpi_accounts_update_metadata,
+        signer_seeds,
+    );
+    metadata::update_metadata_accounts_v2(
+        cpi_ctx_update_metadata,
+        None,
+        Some(DataV2 {
+            name,
+            symbol,
+            uri,
+            seller_fee_basis_points: 0,
+            creators: None,
+            collection: None,
+            uses: None,
+        }),
+        None,
+        None,
+    )?;
+
+    msg!("LP metadata updated for {}", ctx.accounts.pool_state.load()?.lp_mint);
+    Ok(())
+}
@@ -0,0 +1,41 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Transfer};
+
+use crate::contexts::WithdrawDca;
+use crate::events::DcaWithdrawn;
+
+/// 提取定投到目前为止换得的代币，剩下的输入继续按期卖出
+pub fn withdraw_dca(ctx: Context<WithdrawDca>) -> Result<()> {
+    // 构建 seeds 用于 PDA 签名
+    let auth_bump = ctx.accounts.pool_state.load()?.auth_bump;
+    let seeds: &[&[u8]] = &[
+        b"authority",
+        &[auth_bump],
+    ];
+    let signer_seeds = &[seeds];
+
+    let amount_out = ctx.accounts.output_vault.amount;
+    if amount_out > 0 {
+        let cpi_accounts_dca_to_user = Transfer {
+            from: ctx.accounts.output_vault.to_account_info(),
+            to: ctx.accounts.user_token_out.to_account_info(),
+            authority: ctx.accounts.pool_authority.to_account_info(),
+        };
+        let cpi_ctx_dca_to_user = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            cpi_accounts_dca_to_user,
+            signer_seeds,
+        );
+        token::transfer(cpi_ctx_dca_to_user, amount_out)?;
+    }
+
+    emit!(DcaWithdrawn {
+        pool: ctx.accounts.pool_state.key(),
+        dca_vault: ctx.accounts.dca_vault.key(),
+        owner: ctx.accounts.owner.key(),
+        amount_refunded: 0,
+        amount_out,
+        closed: false,
+    });
+    Ok(())
+}
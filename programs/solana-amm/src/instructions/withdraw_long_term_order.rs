@@ -0,0 +1,82 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Transfer};
+
+use crate::contexts::WithdrawLongTermOrder;
+use crate::errors::AmmError;
+use crate::events::LongTermOrderWithdrawn;
+use crate::twamm::{self, TwammSettleAccounts};
+
+/// 提取 TWAMM 长期订单：换得的输出和没有卖出的输入都转给用户，订单账户关闭
+///
+/// 到期前调用相当于撤单，订单的卖出速率立即从 Twamm 中扣除
+pub fn withdraw_long_term_order(ctx: Context<WithdrawLongTermOrder>) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp as u64;
+    twamm::settle_pool(
+        TwammSettleAccounts {
+            pool_state: &ctx.accounts.pool_state,
+            twamm: Some(&ctx.accounts.twamm),
+            twamm_vault_a: Some(&ctx.accounts.twamm_vault_a),
+            twamm_vault_b: Some(&ctx.accounts.twamm_vault_b),
+            token_a_vault: &ctx.accounts.token_a_vault,
+            token_b_vault: &ctx.accounts.token_b_vault,
+            pool_authority: ctx.accounts.pool_authority.to_account_info(),
+            token_program: ctx.accounts.token_program.to_account_info(),
+        },
+        now,
+    )?;
+
+    let order = &ctx.accounts.long_term_order;
+    let mut twamm = ctx.accounts.twamm.load_mut()?;
+    let (sold, amount_out) =
+        twamm::long_term_order_progress(order, &twamm, now).ok_or(AmmError::MathOverflow)?;
+    let amount_refunded = order.amount_in - sold;
+
+    // 还没到期时后面不再卖出，到期时也不用再扣除
+    twamm::close_long_term_order(&mut twamm, order, now).ok_or(AmmError::MathOverflow)?;
+    drop(twamm);
+
+    // 构建 seeds 用于 PDA 签名
+    let auth_bump = ctx.accounts.pool_state.load()?.auth_bump;
+    let seeds: &[&[u8]] = &[
+        b"authority",
+        &[auth_bump],
+    ];
+    let signer_seeds = &[seeds];
+
+    let (vault_a_amount, vault_b_amount) = if order.is_a_to_b {
+        (amount_refunded, amount_out)
+    } else {
+        (amount_out, amount_refunded)
+    };
+    for (twamm_vault, user_token, amount) in [
+        (&ctx.accounts.twamm_vault_a, &ctx.accounts.user_token_a, vault_a_amount),
+        (&ctx.accounts.twamm_vault_b, &ctx.accounts.user_token_b, vault_b_amount),
+    ] {
+        if amount == 0 {
+            continue;
+        }
+        let cpi_accounts_twamm_to_user = Transfer {
+            from: twamm_vault.to_account_info(),
+            to: user_token.to_account_info(),
+            authority: ctx.accounts.pool_authority.to_account_info(),
+        };
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                cpi_accounts_twamm_to_user,
+                signer_seeds,
+            ),
+            amount,
+        )?;
+    }
+
+    emit!(LongTermOrderWithdrawn {
+        pool: order.pool,
+        order: order.key(),
+        owner: order.owner,
+        is_a_to_b: order.is_a_to_b,
+        amount_refunded,
+        amount_out,
+    });
+    Ok(())
+}
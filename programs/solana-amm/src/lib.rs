@@ -1,18 +1,38 @@
 use anchor_lang::prelude::*;
 
 // 模块声明
-mod math;
+pub mod math;
+pub mod oracle;
+pub mod events;
+pub mod lp_metadata;
+pub mod clmm;
+pub mod twamm;
+pub mod batch_auction;
+pub mod pda;
+pub mod permission;
+pub mod pool_swap;
+pub mod pool_liquidity;
 mod state;
 mod errors;
+// 账户名 user_lp_token_ATA / black_hole_lp_ATA 已经写进 IDL 和客户端，保留原命名
+#[allow(non_snake_case)]
 mod contexts;
 mod instructions;
 
 // 重新导出状态和错误，供其他模块使用
-pub use state::PoolState;
+pub use state::{
+    AccessPass, BatchAuction, BatchOrder, ClPool, DcaVault, Farm, FarmReward, FarmStake, FeeOverride,
+    LegacyOracleObservation, LegacyPoolState, LimitOrder, LongTermOrder, LpLock, OracleObservation,
+    PoolGatekeeper, PoolRegistry, PoolRegistryPage, PoolState, Position, RegistryEntry, RegistryPage, Tick,
+    TickArray, Twamm, TwammSlot, CL_POOL_RESERVED_BYTES, CURRENT_POOL_VERSION, LEGACY_POOL_RESERVED_BYTES,
+    POOL_RESERVED_BYTES, POOL_V2_LEN, MAX_BATCH_ORDERS, MAX_FARM_REWARDS, MAX_REFERRAL_FEE_BPS, OBSERVATION_COUNT, OBSERVATION_INTERVAL,
+    REGISTRY_PAGE_SIZE, TICK_ARRAY_SIZE, TWAMM_INTERVAL, TWAMM_SLOTS,
+};
 pub use errors::AmmError;
 
 // 在程序模块内使用账户结构体
 use contexts::*;
+use oracle::GeometricTwap;
 
 declare_id!("3urPFjzfHCS8K37dh2yqvavsQPdmEa5H6pLuv8xWpQXP");
 
@@ -31,14 +51,26 @@ pub mod solana_amm {
         instructions::initialize(ctx, mint_a, mint_b, fee_numerator, fee_denominator)
     }
 
+    /// 修改 LP 代币的 Metaplex 元数据（仅管理员）
+    pub fn update_lp_metadata(
+        ctx: Context<UpdateLpMetadata>,
+        name: String,
+        symbol: String,
+        uri: String,
+    ) -> Result<()> {
+        instructions::update_lp_metadata(ctx, name, symbol, uri)
+    }
+
     /// 执行代币交换
     pub fn swap(
         ctx: Context<Swap>, 
         amount_in: u64, 
         is_a_to_b: bool, 
         min_amount_out: u64,
+        deadline: Option<i64>,
+        referral_fee_bps: u16,
     ) -> Result<()> {
-        instructions::swap(ctx, amount_in, is_a_to_b, min_amount_out)
+        instructions::swap(ctx, amount_in, is_a_to_b, min_amount_out, deadline, referral_fee_bps)
     }
 
     /// 添加liquidity到池子
@@ -46,8 +78,9 @@ pub mod solana_amm {
         ctx: Context<AddLiquidity>,
         amount_a: u64,
         amount_b: u64,
+        deadline: Option<i64>,
     ) -> Result<()> {
-        instructions::add_liquidity(ctx, amount_a, amount_b)
+        instructions::add_liquidity(ctx, amount_a, amount_b, deadline)
     }
 
     /// 从池子移除liquidity
@@ -56,8 +89,9 @@ pub mod solana_amm {
         amount_lp: u64,
         min_amount_a: u64,
         min_amount_b: u64,
+        deadline: Option<i64>,
     ) -> Result<()> {
-        instructions::remove_liquidity(ctx, amount_lp, min_amount_a, min_amount_b)
+        instructions::remove_liquidity(ctx, amount_lp, min_amount_a, min_amount_b, deadline)
     }
 
     /// 更新池子配置
@@ -66,20 +100,298 @@ pub mod solana_amm {
         new_admin: Option<Pubkey>,
         new_recipient: Option<Pubkey>,
         new_share: Option<u64>,
+        new_max_price_deviation_bps: Option<u16>,
+        new_max_referral_fee_bps: Option<u16>,
     ) -> Result<()> {
-        instructions::update_config(ctx, new_admin, new_recipient, new_share)
+        instructions::update_config(
+            ctx,
+            new_admin,
+            new_recipient,
+            new_share,
+            new_max_price_deviation_bps,
+            new_max_referral_fee_bps,
+        )
     }
 
+    /// 新管理员接收管理权限
     pub fn claim_admin(ctx: Context<ClaimAdmin>) -> Result<()> {
-    let pool_state = &mut ctx.accounts.pool_state;
-    
-    // 正式移交權限
-    pool_state.admin = ctx.accounts.pending_admin.key();
-    
-    // 清空暫存位
-    pool_state.pending_admin = None;
-    
-    msg!("管理權限已正式移交至: {:?}", pool_state.admin);
-    Ok(())
-}
+        instructions::claim_admin(ctx)
+    }
+
+    /// 把旧版本的 Borsh 布局 PoolState 转换成零拷贝布局并补上默认值，任何人都可以调用
+    pub fn migrate_pool(ctx: Context<MigratePool>) -> Result<()> {
+        instructions::migrate_pool(ctx)
+    }
+
+    /// 为交易者设置折扣费率（合作做市商等）
+    pub fn set_fee_override(ctx: Context<SetFeeOverride>, fee_numerator: u64) -> Result<()> {
+        instructions::set_fee_override(ctx, fee_numerator)
+    }
+
+    /// 删除交易者的折扣费率，租金退还给管理员
+    pub fn remove_fee_override(ctx: Context<RemoveFeeOverride>) -> Result<()> {
+        instructions::remove_fee_override(ctx)
+    }
+
+    /// 把 LP 锁仓到 unlock_ts，可以多次追加
+    pub fn lock_lp(ctx: Context<LockLp>, amount: u64, unlock_ts: i64) -> Result<()> {
+        instructions::lock_lp(ctx, amount, unlock_ts)
+    }
+
+    /// 到期后取回全部锁仓的 LP
+    pub fn unlock_lp(ctx: Context<UnlockLp>) -> Result<()> {
+        instructions::unlock_lp(ctx)
+    }
+
+    /// 锁仓期间领取累积的手续费，本金继续锁定
+    pub fn claim_lp_lock_fees(ctx: Context<ClaimLpLockFees>) -> Result<()> {
+        instructions::claim_lp_lock_fees(ctx)
+    }
+
+    /// 为池子创建流动性挖矿（仅管理员）
+    pub fn create_farm(ctx: Context<CreateFarm>) -> Result<()> {
+        instructions::create_farm(ctx)
+    }
+
+    /// 添加一种奖励代币，整个释放期的奖励在添加时一次性存入（仅管理员）
+    pub fn add_farm_reward(ctx: Context<AddFarmReward>, emission_per_second: u64, end_ts: i64) -> Result<()> {
+        instructions::add_farm_reward(ctx, emission_per_second, end_ts)
+    }
+
+    /// 质押 LP 参与流动性挖矿
+    pub fn stake_lp(ctx: Context<StakeLp>, amount: u64) -> Result<()> {
+        instructions::stake_lp(ctx, amount)
+    }
+
+    /// 取回质押的 LP，已产生的奖励保留到领取
+    pub fn unstake_lp(ctx: Context<UnstakeLp>, amount: u64) -> Result<()> {
+        instructions::unstake_lp(ctx, amount)
+    }
+
+    /// 领取第 reward_index 种奖励
+    pub fn claim_rewards(ctx: Context<ClaimRewards>, reward_index: u8) -> Result<()> {
+        instructions::claim_rewards(ctx, reward_index)
+    }
+
+    /// 创建集中流动性池子，初始价格为 sqrt(B/A) 的 Q64.64 表示
+    pub fn create_cl_pool(
+        ctx: Context<CreateClPool>,
+        tick_spacing: u16,
+        initial_sqrt_price_x64: u128,
+        fee_numerator: u64,
+        fee_denominator: u64,
+    ) -> Result<()> {
+        instructions::create_cl_pool(ctx, tick_spacing, initial_sqrt_price_x64, fee_numerator, fee_denominator)
+    }
+
+    /// 创建从 start_tick_index 开始的 TickArray，任何人都可以调用
+    pub fn init_tick_array(ctx: Context<InitTickArray>, start_tick_index: i32) -> Result<()> {
+        instructions::init_tick_array(ctx, start_tick_index)
+    }
+
+    /// 开立一个 [tick_lower, tick_upper) 区间的空头寸
+    pub fn open_position(ctx: Context<OpenPosition>, tick_lower: i32, tick_upper: i32) -> Result<()> {
+        instructions::open_position(ctx, tick_lower, tick_upper)
+    }
+
+    /// 向头寸存入流动性
+    pub fn increase_liquidity(
+        ctx: Context<IncreaseLiquidity>,
+        liquidity: u128,
+        max_amount_a: u64,
+        max_amount_b: u64,
+    ) -> Result<()> {
+        instructions::increase_liquidity(ctx, liquidity, max_amount_a, max_amount_b)
+    }
+
+    /// 从头寸取出流动性
+    pub fn decrease_liquidity(
+        ctx: Context<DecreaseLiquidity>,
+        liquidity: u128,
+        min_amount_a: u64,
+        min_amount_b: u64,
+    ) -> Result<()> {
+        instructions::decrease_liquidity(ctx, liquidity, min_amount_a, min_amount_b)
+    }
+
+    /// 领取头寸累计的手续费
+    pub fn collect_fees(ctx: Context<CollectFees>) -> Result<()> {
+        instructions::collect_fees(ctx)
+    }
+
+    /// 在集中流动性池子中兑换，TickArray 通过 remaining_accounts 传入
+    pub fn cl_swap<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ClSwap<'info>>,
+        amount_in: u64,
+        is_a_to_b: bool,
+        min_amount_out: u64,
+        sqrt_price_limit_x64: Option<u128>,
+    ) -> Result<()> {
+        instructions::cl_swap(ctx, amount_in, is_a_to_b, min_amount_out, sqrt_price_limit_x64)
+    }
+
+    /// 挂出限价单，输入代币托管在 PDA 中，等价格到达后由 keeper 执行
+    pub fn place_order(
+        ctx: Context<PlaceOrder>,
+        order_id: u64,
+        amount_in: u64,
+        is_a_to_b: bool,
+        min_amount_out: u64,
+        keeper_bounty: u64,
+    ) -> Result<()> {
+        instructions::place_order(ctx, order_id, amount_in, is_a_to_b, min_amount_out, keeper_bounty)
+    }
+
+    /// 撤销限价单，取回托管的代币和赏金
+    pub fn cancel_order(ctx: Context<CancelOrder>) -> Result<()> {
+        instructions::cancel_order(ctx)
+    }
+
+    /// 执行已经到达限价的订单，订单通过 remaining_accounts 传入，任何人都可以调用
+    pub fn fill_orders<'info>(ctx: Context<'_, '_, 'info, 'info, FillOrders<'info>>) -> Result<()> {
+        instructions::fill_orders(ctx)
+    }
+
+    /// 给池子开启 TWAMM 长期订单（仅管理员）
+    pub fn init_twamm(ctx: Context<InitTwamm>) -> Result<()> {
+        instructions::init_twamm(ctx)
+    }
+
+    /// 挂出 TWAMM 长期订单，在 intervals 个 TWAMM_INTERVAL 内匀速卖出
+    pub fn place_long_term_order(
+        ctx: Context<PlaceLongTermOrder>,
+        order_id: u64,
+        amount_in: u64,
+        is_a_to_b: bool,
+        intervals: u16,
+    ) -> Result<()> {
+        instructions::place_long_term_order(ctx, order_id, amount_in, is_a_to_b, intervals)
+    }
+
+    /// 提取长期订单换得的代币和没有卖出的输入，到期前调用相当于撤单
+    pub fn withdraw_long_term_order(ctx: Context<WithdrawLongTermOrder>) -> Result<()> {
+        instructions::withdraw_long_term_order(ctx)
+    }
+
+    /// 把长期订单结算到当前时间，任何人都可以调用
+    pub fn execute_twamm(ctx: Context<ExecuteTwamm>) -> Result<()> {
+        instructions::execute_twamm(ctx)
+    }
+
+    /// 创建定投金库，存入的输入每隔 cycle_interval 秒卖出 amount_per_cycle
+    pub fn open_dca(
+        ctx: Context<OpenDca>,
+        dca_id: u64,
+        amount_in: u64,
+        is_a_to_b: bool,
+        amount_per_cycle: u64,
+        cycle_interval: i64,
+        min_amount_out_per_cycle: u64,
+    ) -> Result<()> {
+        instructions::open_dca(
+            ctx,
+            dca_id,
+            amount_in,
+            is_a_to_b,
+            amount_per_cycle,
+            cycle_interval,
+            min_amount_out_per_cycle,
+        )
+    }
+
+    /// 执行到期的一期定投，任何人都可以调用
+    pub fn execute_dca(ctx: Context<ExecuteDca>) -> Result<()> {
+        instructions::execute_dca(ctx)
+    }
+
+    /// 提取定投已经换得的代币
+    pub fn withdraw_dca(ctx: Context<WithdrawDca>) -> Result<()> {
+        instructions::withdraw_dca(ctx)
+    }
+
+    /// 取消定投，退回剩余的输入和换得的输出
+    pub fn close_dca(ctx: Context<CloseDca>) -> Result<()> {
+        instructions::close_dca(ctx)
+    }
+
+    /// 给池子开启批量拍卖（仅管理员），之后兑换只能按批次统一价格结算
+    pub fn init_batch_auction(
+        ctx: Context<InitBatchAuction>,
+        batch_duration_slots: u64,
+        min_order_amount_a: u64,
+        min_order_amount_b: u64,
+    ) -> Result<()> {
+        instructions::init_batch_auction(ctx, batch_duration_slots, min_order_amount_a, min_order_amount_b)
+    }
+
+    /// 把一笔兑换提交到当前批次
+    pub fn submit_batch_swap(
+        ctx: Context<SubmitBatchSwap>,
+        amount_in: u64,
+        is_a_to_b: bool,
+        min_amount_out: u64,
+    ) -> Result<()> {
+        instructions::submit_batch_swap(ctx, amount_in, is_a_to_b, min_amount_out)
+    }
+
+    /// 按统一价格结算收集窗口已经结束的批次，订单通过 remaining_accounts 传入，任何人都可以调用
+    pub fn settle_batch<'info>(ctx: Context<'_, '_, 'info, 'info, SettleBatch<'info>>) -> Result<()> {
+        instructions::settle_batch(ctx)
+    }
+
+    /// 把注册表上线之前创建的池子补登记到注册表，任何人都可以调用
+    pub fn register_pool(ctx: Context<RegisterPool>) -> Result<()> {
+        instructions::register_pool(ctx)
+    }
+
+    /// 读取池子注册表的第 page_index 页（只读，通过 return data 返回）
+    pub fn get_registry_page(ctx: Context<GetRegistryPage>, page_index: u64) -> Result<RegistryPage> {
+        instructions::get_registry_page(ctx, page_index)
+    }
+
+    /// 设置池子的储备上限和单笔存入限额（仅管理员），0 表示不限制
+    pub fn set_deposit_caps(
+        ctx: Context<SetDepositCaps>,
+        max_total_reserve_a: u64,
+        max_total_reserve_b: u64,
+        max_deposit_a: u64,
+        max_deposit_b: u64,
+    ) -> Result<()> {
+        instructions::set_deposit_caps(ctx, max_total_reserve_a, max_total_reserve_b, max_deposit_a, max_deposit_b)
+    }
+
+    /// 指定守门人并开启许可模式（仅管理员），传入 Pubkey::default() 关闭
+    pub fn set_gatekeeper(ctx: Context<SetGatekeeper>, gatekeeper: Pubkey) -> Result<()> {
+        instructions::set_gatekeeper(ctx, gatekeeper)
+    }
+
+    /// 守门人给用户发放 AccessPass，expires_ts 为 0 表示不过期
+    pub fn issue_access_pass(ctx: Context<IssueAccessPass>, expires_ts: i64) -> Result<()> {
+        instructions::issue_access_pass(ctx, expires_ts)
+    }
+
+    /// 守门人撤销 AccessPass
+    pub fn revoke_access_pass(ctx: Context<RevokeAccessPass>) -> Result<()> {
+        instructions::revoke_access_pass(ctx)
+    }
+
+    /// 开启或关闭批量拍卖（仅管理员），关闭后恢复立即成交
+    pub fn set_batch_mode(ctx: Context<SetBatchMode>, enabled: bool) -> Result<()> {
+        instructions::set_batch_mode(ctx, enabled)
+    }
+
+    /// 取回已经结算的批量拍卖订单的输出或者退回的输入（仅订单所有者）
+    pub fn claim_batch_order(ctx: Context<ClaimBatchOrder>) -> Result<()> {
+        instructions::claim_batch_order(ctx)
+    }
+
+    /// 撤销当前批次中还没有结算的订单（仅订单所有者），收集窗口结束之后不能撤销
+    pub fn cancel_batch_order(ctx: Context<CancelBatchOrder>) -> Result<()> {
+        instructions::cancel_batch_order(ctx)
+    }
+
+    /// 读取最近 window 秒的几何平均 TWAP（只读，通过 return data 返回）
+    pub fn get_geometric_twap(ctx: Context<GetGeometricTwap>, window: u64) -> Result<GeometricTwap> {
+        instructions::get_geometric_twap(ctx, window)
+    }
 }
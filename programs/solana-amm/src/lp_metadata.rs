@@ -0,0 +1,65 @@
+//! LP 代币的 Metaplex 元数据
+//!
+//! `initialize` 传入 Metaplex 程序时为 lp_mint 创建元数据，钱包不再把 LP 显示成 "Unknown Token"。
+//! 名称形如 `AMM LP USDC-SOL`，符号取两个底层代币的符号；底层代币没有元数据时用 mint 地址的前几位代替。
+//! 元数据的 update authority 是 pool_authority，池子管理员可以通过 `update_lp_metadata` 修改。
+
+use anchor_lang::prelude::*;
+use anchor_spl::metadata::mpl_token_metadata::accounts::Metadata;
+
+/// Metaplex 对名称、符号、URI 的长度限制（字节）
+pub const MAX_NAME_LENGTH: usize = 32;
+pub const MAX_SYMBOL_LENGTH: usize = 10;
+pub const MAX_URI_LENGTH: usize = 200;
+
+/// LP 名称的前缀
+pub const LP_NAME_PREFIX: &str = "AMM LP";
+/// 底层代币没有元数据时，取 mint 地址 base58 的前几位作为符号
+pub const FALLBACK_SYMBOL_LENGTH: usize = 4;
+
+/// 读取底层代币元数据中的符号
+///
+/// 账户不是 `mint` 的元数据 PDA、不属于 Metaplex 程序或者无法解析时返回 None
+pub fn read_symbol(metadata: &AccountInfo, mint: &Pubkey) -> Option<String> {
+    if metadata.owner != &anchor_spl::metadata::ID || metadata.key() != Metadata::find_pda(mint).0 {
+        return None;
+    }
+    let data = metadata.try_borrow_data().ok()?;
+    let metadata = Metadata::safe_deserialize(&data).ok()?;
+    if metadata.mint != *mint {
+        return None;
+    }
+    // 旧版元数据按固定长度用 \0 填充
+    let symbol = metadata.symbol.trim_matches('\0').trim();
+    (!symbol.is_empty()).then(|| symbol.to_string())
+}
+
+/// 没有元数据时代替符号的 mint 地址前缀
+pub fn fallback_symbol(mint: &Pubkey) -> String {
+    mint.to_string().chars().take(FALLBACK_SYMBOL_LENGTH).collect()
+}
+
+/// LP 的名称和符号，按 Metaplex 的长度限制截断
+///
+/// # Returns
+/// * `(String, String)` - (`AMM LP <A>-<B>`, `<A>-<B>`)
+pub fn lp_name_and_symbol(symbol_a: &str, symbol_b: &str) -> (String, String) {
+    let pair = format!("{symbol_a}-{symbol_b}");
+    let name = truncate(&format!("{LP_NAME_PREFIX} {pair}"), MAX_NAME_LENGTH);
+    let symbol = truncate(&pair, MAX_SYMBOL_LENGTH);
+    (name, symbol)
+}
+
+/// 名称、符号、URI 是否都在 Metaplex 的长度限制内
+pub fn is_valid(name: &str, symbol: &str, uri: &str) -> bool {
+    name.len() <= MAX_NAME_LENGTH && symbol.len() <= MAX_SYMBOL_LENGTH && uri.len() <= MAX_URI_LENGTH
+}
+
+// 按字节数截断，不切开多字节字符
+fn truncate(s: &str, max_len: usize) -> String {
+    let mut end = s.len().min(max_len);
+    while !s.is_char_boundary(end) {
+        end -= 1;
+    }
+    s[..end].to_string()
+}
@@ -1,7 +1,22 @@
 // 自定义数学函数模块
 // 不使用 PreciseNumber 的原因：PreciseNumber 的计算开销过大，会导致 CU (Compute Units) 溢出
 // 使用轻量级的整数运算来替代，减少计算单元消耗
-use crate::state::PoolState;
+use crate::state::{Farm, FarmStake, OracleObservation, PoolState, OBSERVATION_COUNT, OBSERVATION_INTERVAL};
+
+// 最小流动性：首次添加流动性时铸造到黑洞地址，防止流动性归零攻击
+// 按 LP 的最小单位计算：首次铸造的 LP 数量是 sqrt(amount_a * amount_b)（都是最小单位），和 LP 的精度无关，
+// 所以无论 lp_decimals 是多少，锁定的都是同样多的底层代币，不需要随精度调整
+pub const MINIMUM_LIQUIDITY: u64 = 1000;
+
+// LP 的精度，取两个代币精度的平均值（向下取整）
+// LP 数量的单位是 sqrt(A 的最小单位 * B 的最小单位)，即 10^-((decimals_a + decimals_b) / 2)，
+// 这样 1 个 LP 大致对应 sqrt(1 A * 1 B)，例如 USDC(6) / SOL(9) 的 LP 精度为 7
+pub const fn lp_decimals(decimals_a: u8, decimals_b: u8) -> u8 {
+    ((decimals_a as u16 + decimals_b as u16) / 2) as u8
+}
+
+// 流动性挖矿中 reward_per_share 的放大倍数
+pub const REWARD_PER_SHARE_PRECISION: u128 = 1_000_000_000_000;
 
 // 计算 u128 的整数平方根
 // 使用牛顿法（Newton's method）进行迭代计算
@@ -23,9 +38,9 @@ pub fn sqrt_u128(n: u128) -> Option<u64> {
     }
     
     // 牛顿法：x_{n+1} = (x_n + n/x_n) / 2
-    // 初始值设为 n/2
+    // 初始值设为 ceil(n/2)，不能写成 (n + 1) / 2，否则 n = u128::MAX 时会溢出
     let mut x = n;
-    let mut y = x.checked_add(1)?.checked_div(2)?;
+    let mut y = n / 2 + n % 2;
     
     // 迭代到收敛为止（最多迭代 64 次，因为 u128 最多 128 位）
     while y < x {
@@ -58,50 +73,353 @@ pub fn sqrt_product_u64(a: u64, b: u64) -> Option<u64> {
 }
 
 
+// log2 / exp2 计算的小数位数
+// 每多一位就多一次 u128 乘法，32 位时 log2 的误差约 2^-32，换算成价格的相对误差约 1.6e-10
+pub const LOG2_FRACTION_BITS: u32 = 32;
 
+// Q1.63 定点数的 1
+const ONE_Q63: u128 = 1 << 63;
 
+// EXP2_TABLE[i] = 2^(2^-(i+1))，Q1.63 定点数
+const EXP2_TABLE: [u64; LOG2_FRACTION_BITS as usize] = [
+    0xb504f333f9de6484, // 2^(2^-1)
+    0x9837f0518db8a96f, // 2^(2^-2)
+    0x8b95c1e3ea8bd6e7, // 2^(2^-3)
+    0x85aac367cc487b15, // 2^(2^-4)
+    0x82cd8698ac2ba1d7, // 2^(2^-5)
+    0x8164d1f3bc030773, // 2^(2^-6)
+    0x80b1ed4fd999ab6c, // 2^(2^-7)
+    0x8058d7d2d5e5f6b1, // 2^(2^-8)
+    0x802c6436d0e04f51, // 2^(2^-9)
+    0x8016302f17467628, // 2^(2^-10)
+    0x800b179c82028fd1, // 2^(2^-11)
+    0x80058baf7fee3b5d, // 2^(2^-12)
+    0x8002c5d00fdcfcb7, // 2^(2^-13)
+    0x800162e61bed4a49, // 2^(2^-14)
+    0x8000b17292f702a4, // 2^(2^-15)
+    0x800058b92abbae02, // 2^(2^-16)
+    0x80002c5c8dade4d7, // 2^(2^-17)
+    0x8000162e44eaf636, // 2^(2^-18)
+    0x80000b1721fa7c19, // 2^(2^-19)
+    0x8000058b90de7e4d, // 2^(2^-20)
+    0x800002c5c8678f37, // 2^(2^-21)
+    0x80000162e431dba0, // 2^(2^-22)
+    0x800000b1721872d1, // 2^(2^-23)
+    0x80000058b90c1aa9, // 2^(2^-24)
+    0x8000002c5c8605a4, // 2^(2^-25)
+    0x800000162e4300e6, // 2^(2^-26)
+    0x8000000b17217ff8, // 2^(2^-27)
+    0x800000058b90bfdd, // 2^(2^-28)
+    0x80000002c5c85fe7, // 2^(2^-29)
+    0x8000000162e42ff2, // 2^(2^-30)
+    0x80000000b17217f8, // 2^(2^-31)
+    0x8000000058b90bfc, // 2^(2^-32)
+];
 
-// 更新 TWAP 价格累积
+// 计算整数 x 的 log2，结果为 Q64.64 定点数（只计算 LOG2_FRACTION_BITS 位小数，向下取整）
+// 整数部分就是最高位的位置；小数部分逐位求出：
+// 把 x 归一化到 m ∈ [1, 2)，每次平方，m^2 >= 2 时这一位为 1，再把 m 除以 2
+// 
+// # Returns
+// * `None` - x 为 0
+pub fn log2_q64(x: u128) -> Option<i128> {
+    if x == 0 {
+        return None;
+    }
+
+    let msb = 127 - x.leading_zeros();
+    // 归一化为 Q1.63，m ∈ [2^63, 2^64)，平方后不会超出 u128
+    let mut m = if msb >= 63 { x >> (msb - 63) } else { x << (63 - msb) };
+    let mut result = (msb as i128) << 64;
+
+    for bit in 1..=LOG2_FRACTION_BITS {
+        m = (m * m) >> 63;
+        if m >= ONE_Q63 << 1 {
+            m >>= 1;
+            result |= 1 << (64 - bit);
+        }
+    }
+
+    Some(result)
+}
+
+// 计算 2^x，x 和结果都是 Q64.64 定点数，是 log2_q64 的反函数
+// 小数部分只使用最高的 LOG2_FRACTION_BITS 位，按位查表相乘
+// 
+// # Returns
+// * `None` - 结果超出 Q64.64 的表示范围（x >= 64）
+pub fn exp2_q64(x: i128) -> Option<u128> {
+    // 算术右移是向下取整，负数也成立；低 64 位就是 [0, 1) 之间的小数部分
+    let integer = x >> 64;
+    let fraction = x as u128 & u64::MAX as u128;
+
+    let mut result = ONE_Q63;
+    for (i, factor) in EXP2_TABLE.iter().enumerate() {
+        if (fraction >> (63 - i)) & 1 == 1 {
+            result = (result * *factor as u128) >> 63;
+        }
+    }
+
+    // result 是 Q1.63，乘以 2^integer 再转换为 Q64.64，一共左移 integer + 1 位
+    let shift = integer + 1;
+    if shift > 64 {
+        return None;
+    }
+    if shift >= 0 {
+        return Some(result << shift);
+    }
+    // 太小的结果向下取整为 0
+    if shift <= -128 {
+        return Some(0);
+    }
+    Some(result >> -shift)
+}
+
+
+// 从输入中划出推荐费（向下取整），剩余部分进入池子
+//
+// # Returns
+// * `Option<(u64, u64)>` - (推荐费, 进入池子的输入)
+pub fn calculate_referral_fee(amount_in: u64, referral_fee_bps: u16) -> Option<(u64, u64)> {
+    // 推荐费不超过 amount_in，转换回 u64 不会截断
+    let referral_amount = (amount_in as u128)
+        .checked_mul(referral_fee_bps as u128)?
+        .checked_div(crate::oracle::BPS_DENOMINATOR)? as u64;
+    Some((referral_amount, amount_in.checked_sub(referral_amount)?))
+}
+
+// 计算一次兑换的结果（恒定乘积 x * y = k）
+// 
+// # Arguments
+// * `amount_in` - 用户输入的代币数量
+// * `reserve_in` - 输入方向的储备金
+// * `reserve_out` - 输出方向的储备金
+// * `fee_numerator` - 手续费分子
+// * `fee_denominator` - 手续费分母
+// 
+// # Returns
+// * `Option<(u64, u64)>` - (扣除手续费后的有效输入, 输出数量)，溢出则返回 None
+pub fn calculate_swap_amount_out(
+    amount_in: u64,
+    reserve_in: u64,
+    reserve_out: u64,
+    fee_numerator: u64,
+    fee_denominator: u64,
+) -> Option<(u64, u64)> {
+    // 扣除手续费之后的有效输入
+    // 乘法放在 u128 中做，u64 下大额兑换会溢出；结果不超过 amount_in，转换回 u64 不会截断
+    let amount_in_effective = (amount_in as u128)
+        .checked_mul(fee_denominator.checked_sub(fee_numerator)? as u128)?
+        .checked_div(fee_denominator as u128)? as u64;
+
+    // amount_out = reserve_out * amount_in_effective / (reserve_in + amount_in_effective)
+    // 使用u128进行中间计算以避免溢出
+    // 结果一定小于 reserve_out，转换回 u64 不会截断
+    let amount_out = (reserve_out as u128)
+        .checked_mul(amount_in_effective as u128)?
+        .checked_div((reserve_in as u128).checked_add(amount_in_effective as u128)?)? as u64;
+
+    Some((amount_in_effective, amount_out))
+}
+
+// amount_lp 个 LP 对应的 sqrt(reserve_a * reserve_b) 份额，用来衡量 LP 锁仓的本金
+// 手续费留在池子里会让每个 LP 对应的份额增长，而单纯的价格变化不会
+// 向上取整，保证领取手续费时不会动到本金
+pub fn calculate_lp_value(
+    amount_lp: u64,
+    reserve_a: u64,
+    reserve_b: u64,
+    lp_supply: u64,
+) -> Option<u128> {
+    if lp_supply == 0 {
+        return None;
+    }
+    let sqrt_k = sqrt_product_u64(reserve_a, reserve_b)? as u128;
+    Some((amount_lp as u128).checked_mul(sqrt_k)?.div_ceil(lp_supply as u128))
+}
+
+// 锁仓的 amount_lp 中超出本金 principal 的部分，即锁仓期间累积的手续费对应的 LP 数量
+// 
+// # Arguments
+// * `principal` - 锁仓时由 calculate_lp_value 记录的本金
+// * `lp_supply` - 包含协议费增发后的 LP 总供应量
+pub fn calculate_locked_fee_lp(
+    amount_lp: u64,
+    principal: u128,
+    reserve_a: u64,
+    reserve_b: u64,
+    lp_supply: u64,
+) -> Option<u64> {
+    let sqrt_k = sqrt_product_u64(reserve_a, reserve_b)? as u128;
+    if sqrt_k == 0 {
+        return None;
+    }
+    // 保住本金需要的 LP，向上取整；结果不超过 amount_lp，转换回 u64 不会截断
+    let principal_lp = principal.checked_mul(lp_supply as u128)?.div_ceil(sqrt_k);
+    Some((amount_lp as u128).saturating_sub(principal_lp) as u64)
+}
+
+// 计算已有流动性时存入 (amount_a, amount_b) 能获得多少 LP
+// 分别按 A、B 的存入比例计算，取两者的最小值
+// 
+// # Arguments
+// * `total_lp_supply` - 包含协议费增发后的 LP 总供应量
+pub fn calculate_deposit_liquidity(
+    amount_a: u64,
+    amount_b: u64,
+    reserve_a: u64,
+    reserve_b: u64,
+    total_lp_supply: u64,
+) -> Option<u64> {
+    let liquidity_a = (amount_a as u128)
+        .checked_mul(total_lp_supply as u128)?
+        .checked_div(reserve_a as u128)?;
+    let liquidity_b = (amount_b as u128)
+        .checked_mul(total_lp_supply as u128)?
+        .checked_div(reserve_b as u128)?;
+    // 结果超出 u64 时返回 None，不能用 as 静默截断
+    u64::try_from(liquidity_a.min(liquidity_b)).ok()
+}
+
+// 计算销毁 amount_lp 后用户按比例取回的 (token_a, token_b) 数量
+// 
+// # Arguments
+// * `total_lp_supply` - 包含协议费增发后的 LP 总供应量
+pub fn calculate_withdraw_amounts(
+    amount_lp: u64,
+    reserve_a: u64,
+    reserve_b: u64,
+    total_lp_supply: u64,
+) -> Option<(u64, u64)> {
+    let amount_a = (amount_lp as u128)
+        .checked_mul(reserve_a as u128)?
+        .checked_div(total_lp_supply as u128)?;
+    let amount_b = (amount_lp as u128)
+        .checked_mul(reserve_b as u128)?
+        .checked_div(total_lp_supply as u128)?;
+    // amount_lp 大于 total_lp_supply 时结果可能超出 u64
+    Some((u64::try_from(amount_a).ok()?, u64::try_from(amount_b).ok()?))
+}
+
+// 计算截至 current_timestamp 的价格累加器，不修改 pool_state
+// 上次更新之后储备金没有变化，所以中间这段时间的价格就是当前价格
+// 
+// 累加器按 2^128 取模回绕（和 Uniswap V2 一样），只有两次观测的差值有意义，
+// 读取方式见 oracle 模块
 // 
 // # Arguments
 // * `reserve_a` - 池子里的Token A 的数量
 // * `reserve_b` - 池子里的Token B 的数量
-// * `current_timestamp` - 当前时间戳
+// * `current_timestamp` - 当前时间戳，小于上次更新时间戳时不累加
 // 
 // # Returns
-// * `None` - 如果时间戳小于上次更新时间戳
+// * `(u128, u128)` - (price_a_cumulative, price_b_cumulative)
+pub fn current_cumulative_prices(
+    pool_state: &PoolState,
+    reserve_a: u64,
+    reserve_b: u64,
+    current_timestamp: u64,
+) -> (u128, u128) {
+    let mut price_a_cumulative = pool_state.price_a_cumulative_last;
+    let mut price_b_cumulative = pool_state.price_b_cumulative_last;
 
-pub fn update_twap(
-    pool_state: &mut PoolState, 
-    reserve_a: u64, 
-    reserve_b: u64, 
-    current_timestamp: u64
-) {
     // 1. 计算时间差
-    let time_elapsed = current_timestamp.checked_sub(pool_state.block_timestamp_last).unwrap_or(0);
+    let time_elapsed = current_timestamp.saturating_sub(pool_state.block_timestamp_last);
 
     // 2. 只有时间有变化、池子有流动性时才更新累加器
     if time_elapsed > 0 && reserve_a != 0 && reserve_b != 0 {
         // 计算当前价格：P = (ReserveB << 64) / ReserveA
         // 这里的 Q64.64 定点数能提供极高的精度
-        let price_a_fixed = (reserve_b as u128)
-            .checked_shl(64).unwrap()
-            .checked_div(reserve_a as u128).unwrap();
-        
-        let price_b_fixed = (reserve_a as u128)
-            .checked_shl(64).unwrap()
-            .checked_div(reserve_b as u128).unwrap();
+        // u64 左移 64 位不会超出 u128，储备金非零所以除法也不会失败
+        let price_a_fixed = ((reserve_b as u128) << 64) / reserve_a as u128;
+        let price_b_fixed = ((reserve_a as u128) << 64) / reserve_b as u128;
+
+        // 核心公式：cumulative += price * delta_time，溢出时有意回绕
+        price_a_cumulative =
+            price_a_cumulative.wrapping_add(price_a_fixed.wrapping_mul(time_elapsed as u128));
+        price_b_cumulative =
+            price_b_cumulative.wrapping_add(price_b_fixed.wrapping_mul(time_elapsed as u128));
+    }
+
+    (price_a_cumulative, price_b_cumulative)
+}
+
+// 计算截至 current_timestamp 的 log2 价格累加器，不修改 pool_state
+// 累加的是 log2(reserve_b / reserve_a) * delta_time，同样按 2^128 回绕
+// 取平均再做 exp2 得到的是几何平均价格，A 的均价和 B 的均价互为倒数
+pub fn current_log_price_cumulative(
+    pool_state: &PoolState,
+    reserve_a: u64,
+    reserve_b: u64,
+    current_timestamp: u64,
+) -> i128 {
+    let time_elapsed = current_timestamp.saturating_sub(pool_state.block_timestamp_last);
+    if time_elapsed == 0 || reserve_a == 0 || reserve_b == 0 {
+        return pool_state.log_price_cumulative_last;
+    }
+
+    // 两边分别取 log2 再相减，不需要先算 Q64.64 价格，极端价格下也不会丢精度
+    let log_price = match (log2_q64(reserve_b as u128), log2_q64(reserve_a as u128)) {
+        (Some(log_b), Some(log_a)) => log_b - log_a,
+        _ => return pool_state.log_price_cumulative_last,
+    };
+
+    pool_state
+        .log_price_cumulative_last
+        .wrapping_add(log_price.wrapping_mul(time_elapsed as i128))
+}
+
+// 把当前的 log2 价格累加器写入观测环形缓冲区
+// 距离上一条观测不足 OBSERVATION_INTERVAL 秒时不写入，保证缓冲区覆盖足够长的时间
+pub fn record_observation(pool_state: &mut PoolState, current_timestamp: u64) {
+    let observation = OracleObservation::new(current_timestamp, pool_state.log_price_cumulative_last);
 
-        // 核心公式：cumulative += price * delta_time
-        pool_state.price_a_cumulative_last = pool_state.price_a_cumulative_last
-            .checked_add(price_a_fixed.checked_mul(time_elapsed as u128).unwrap()).unwrap();
-            
-        pool_state.price_b_cumulative_last = pool_state.price_b_cumulative_last
-            .checked_add(price_b_fixed.checked_mul(time_elapsed as u128).unwrap()).unwrap();
+    if pool_state.observation_count == 0 {
+        pool_state.observation_index = 0;
+        pool_state.observation_count = 1;
+        pool_state.observations[0] = observation;
+        return;
     }
 
+    let last = pool_state.observations[pool_state.observation_index as usize];
+    if current_timestamp < last.timestamp.saturating_add(OBSERVATION_INTERVAL) {
+        return;
+    }
+
+    let index = (pool_state.observation_index as usize + 1) % OBSERVATION_COUNT;
+    pool_state.observation_index = index as u8;
+    pool_state.observation_count = pool_state
+        .observation_count
+        .saturating_add(1)
+        .min(OBSERVATION_COUNT as u8);
+    pool_state.observations[index] = observation;
+}
+
+// 更新 TWAP 价格累积
+// 每次储备金变化之前调用，累加器回绕而不是报错，长期运行的池子不会因此卡死
+// 
+// # Arguments
+// * `reserve_a` - 池子里的Token A 的数量
+// * `reserve_b` - 池子里的Token B 的数量
+// * `current_timestamp` - 当前时间戳
+pub fn update_twap(
+    pool_state: &mut PoolState, 
+    reserve_a: u64, 
+    reserve_b: u64, 
+    current_timestamp: u64
+) {
+    let (price_a_cumulative, price_b_cumulative) =
+        current_cumulative_prices(pool_state, reserve_a, reserve_b, current_timestamp);
+    pool_state.price_a_cumulative_last = price_a_cumulative;
+    pool_state.price_b_cumulative_last = price_b_cumulative;
+    pool_state.log_price_cumulative_last =
+        current_log_price_cumulative(pool_state, reserve_a, reserve_b, current_timestamp);
+
     // 3. 无论是否更新累加器，都要更新最后的时间戳
     pool_state.block_timestamp_last = current_timestamp;
+
+    // 4. 按间隔记录观测，供几何平均 TWAP 的时间窗口查询
+    record_observation(pool_state, current_timestamp);
 }
 
 // 计算协议抽成
@@ -137,4 +455,57 @@ pub fn calculate_protocol_fee_mint(
     }
 
     Some(0)
-}
\ No newline at end of file
+}
+
+// 把每种奖励的 reward_per_share 累加到 now（不超过各自的 end_ts）
+// 质押量为 0 时暂停释放：剩余的释放时间整体顺延，奖励不会留在金库里无人领取
+pub fn update_farm_rewards(farm: &mut Farm, now: i64) -> Option<()> {
+    let total_staked = farm.total_staked as u128;
+    for reward in farm.rewards.iter_mut().take(farm.reward_count as usize) {
+        let until = now.min(reward.end_ts);
+        if until <= reward.last_update_ts {
+            continue;
+        }
+        if total_staked == 0 {
+            reward.end_ts = now.checked_add(reward.end_ts.checked_sub(reward.last_update_ts)?)?;
+            reward.last_update_ts = now;
+            continue;
+        }
+        let elapsed = until.checked_sub(reward.last_update_ts)? as u128;
+        let delta = (reward.emission_per_second as u128)
+            .checked_mul(elapsed)?
+            .checked_mul(REWARD_PER_SHARE_PRECISION)?
+            .checked_div(total_staked)?;
+        reward.reward_per_share = reward.reward_per_share.checked_add(delta)?;
+        reward.last_update_ts = until;
+    }
+    Some(())
+}
+
+// amount 个质押 LP 按 reward_per_share 累计可得的奖励数量
+// 向下取整，所有人领取的总和不会超过实际释放的奖励
+pub fn farm_reward_debt(amount: u64, reward_per_share: u128) -> Option<u128> {
+    (amount as u128)
+        .checked_mul(reward_per_share)?
+        .checked_div(REWARD_PER_SHARE_PRECISION)
+}
+
+// 把上次结算以来产生的奖励记入 pending_rewards，并按当前质押量重置 reward_debts
+// 质押量变化之前调用一次，变化之后调用 reset_farm_reward_debts
+pub fn settle_farm_stake(farm: &Farm, stake: &mut FarmStake) -> Option<()> {
+    for i in 0..farm.reward_count as usize {
+        let accrued = farm_reward_debt(stake.amount, farm.rewards[i].reward_per_share)?;
+        let earned = u64::try_from(accrued.checked_sub(stake.reward_debts[i])?).ok()?;
+        stake.pending_rewards[i] = stake.pending_rewards[i].checked_add(earned)?;
+        stake.reward_debts[i] = accrued;
+    }
+    Some(())
+}
+
+// 质押量变化之后，按新的质押量记录已计入的奖励
+pub fn reset_farm_reward_debts(farm: &Farm, stake: &mut FarmStake) -> Option<()> {
+    for i in 0..farm.reward_count as usize {
+        stake.reward_debts[i] = farm_reward_debt(stake.amount, farm.rewards[i].reward_per_share)?;
+    }
+    Some(())
+}
@@ -0,0 +1,229 @@
+//! TWAP 预言机读取接口
+//!
+//! `PoolState` 中的 `price_a_cumulative_last` / `price_b_cumulative_last` 是 Q64.64 价格对时间的累加，
+//! 按 2^128 取模回绕（和 Uniswap V2 一样）。累加器的绝对值没有意义，只有两次观测的差值有意义：
+//!
+//! 1. 窗口开始时用 [`observe`] 记下一份 [`Observation`]（链下保存，或者存进自己程序的账户里）
+//! 2. 窗口结束时再 [`observe`] 一次
+//! 3. 用 [`twap`] 求窗口内的时间加权均价
+//!
+//! 差值按 `wrapping_sub` 计算，窗口跨过回绕点时结果仍然正确；
+//! 前提是窗口内累加器的增量小于 2^128，即 `平均价格 × 窗口秒数 < 2^64`。
+//!
+//! 另外还有一个 log2 价格累加器 `log_price_cumulative_last`，对它取平均再做 exp2 得到几何平均价格。
+//! 几何平均对短时间的价格尖刺更不敏感，而且 A 的均价和 B 的均价严格互为倒数，
+//! 用 [`geometric_twap`] 读取；池子里保存了最近 [`OBSERVATION_COUNT`] 条观测，
+//! 链上程序也可以直接调用 `get_geometric_twap` 指令按时间窗口读取，不需要自己保存起点。
+//!
+//! `PoolState` 是零拷贝账户，调用方用 `AccountLoader<PoolState>` 传入后 `load()` 读取：
+//!
+//! ```ignore
+//! let pool_state = ctx.accounts.pool_state.load()?;
+//! let start = oracle::observe(&pool_state, vault_a.amount, vault_b.amount, now);
+//! // ... 至少一个窗口之后 ...
+//! let end = oracle::observe(&pool_state, vault_a.amount, vault_b.amount, now);
+//! let price = oracle::twap(&start, &end).ok_or(MyError::WindowTooShort)?;
+//! ```
+
+use anchor_lang::prelude::*;
+
+use crate::math;
+use crate::state::{OracleObservation, PoolState, OBSERVATION_COUNT};
+
+/// 价格偏离以万分比（bps）表示
+pub const BPS_DENOMINATOR: u128 = 10_000;
+
+/// 某一时刻的累加器快照
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Observation {
+    pub timestamp: u64,
+    pub price_a_cumulative: u128,
+    pub price_b_cumulative: u128,
+    pub log_price_cumulative: i128,
+}
+
+/// 窗口内的时间加权均价，Q64.64 定点数
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Twap {
+    /// B 计价的 A 的均价
+    pub price_a: u128,
+    /// A 计价的 B 的均价
+    pub price_b: u128,
+}
+
+/// 池子最近一次更新时写入的累加器，不包含之后经过的时间
+impl From<&PoolState> for Observation {
+    fn from(pool_state: &PoolState) -> Self {
+        Self {
+            timestamp: pool_state.block_timestamp_last,
+            price_a_cumulative: pool_state.price_a_cumulative_last,
+            price_b_cumulative: pool_state.price_b_cumulative_last,
+            log_price_cumulative: pool_state.log_price_cumulative_last,
+        }
+    }
+}
+
+/// 观测池子在 `now` 时刻的累加器
+///
+/// 池子只在交易时更新累加器，这里用当前储备金补上最近一次更新到 `now` 之间的部分，
+/// 所以不需要等有人交易就能读到最新的值。
+///
+/// # Arguments
+/// * `reserve_a` / `reserve_b` - 两个金库当前的余额
+/// * `now` - 当前时间戳（`Clock::unix_timestamp`）
+pub fn observe(pool_state: &PoolState, reserve_a: u64, reserve_b: u64, now: u64) -> Observation {
+    let (price_a_cumulative, price_b_cumulative) =
+        math::current_cumulative_prices(pool_state, reserve_a, reserve_b, now);
+    Observation {
+        timestamp: now.max(pool_state.block_timestamp_last),
+        price_a_cumulative,
+        price_b_cumulative,
+        log_price_cumulative: math::current_log_price_cumulative(
+            pool_state, reserve_a, reserve_b, now,
+        ),
+    }
+}
+
+/// 计算两次观测之间的时间加权均价
+///
+/// # Returns
+/// * `None` - `end` 不晚于 `start`，窗口长度为 0
+pub fn twap(start: &Observation, end: &Observation) -> Option<Twap> {
+    let elapsed = end.timestamp.checked_sub(start.timestamp)?;
+    if elapsed == 0 {
+        return None;
+    }
+
+    Some(Twap {
+        price_a: end.price_a_cumulative.wrapping_sub(start.price_a_cumulative) / elapsed as u128,
+        price_b: end.price_b_cumulative.wrapping_sub(start.price_b_cumulative) / elapsed as u128,
+    })
+}
+
+/// `get_geometric_twap` 指令的返回值
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GeometricTwap {
+    /// 窗口实际的起止时间，起点是不晚于 `end - window` 的最近一条观测
+    pub start: u64,
+    pub end: u64,
+    /// B 计价的 A 的几何均价，Q64.64
+    pub price_a: u128,
+    /// A 计价的 B 的几何均价，Q64.64，在舍入误差内等于 price_a 的倒数
+    pub price_b: u128,
+}
+
+// 由 log2 价格累加器的增量求几何平均价格
+fn geometric_mean(
+    start: u64,
+    start_cumulative: i128,
+    end: u64,
+    end_cumulative: i128,
+) -> Option<GeometricTwap> {
+    let elapsed = end.checked_sub(start)?;
+    if elapsed == 0 {
+        return None;
+    }
+
+    let mean_log_price = end_cumulative.wrapping_sub(start_cumulative) / elapsed as i128;
+    Some(GeometricTwap {
+        start,
+        end,
+        price_a: math::exp2_q64(mean_log_price)?,
+        price_b: math::exp2_q64(-mean_log_price)?,
+    })
+}
+
+/// 计算两次观测之间的几何平均价格
+///
+/// # Returns
+/// * `None` - `end` 不晚于 `start`
+pub fn geometric_twap(start: &Observation, end: &Observation) -> Option<GeometricTwap> {
+    geometric_mean(
+        start.timestamp,
+        start.log_price_cumulative,
+        end.timestamp,
+        end.log_price_cumulative,
+    )
+}
+
+/// 池子观测缓冲区中不晚于 `target` 的最近一条观测
+pub fn observation_before(pool_state: &PoolState, target: u64) -> Option<OracleObservation> {
+    let count = (pool_state.observation_count as usize).min(OBSERVATION_COUNT);
+    (0..count)
+        .map(|i| {
+            let index = (pool_state.observation_index as usize + OBSERVATION_COUNT - i) % OBSERVATION_COUNT;
+            pool_state.observations[index]
+        })
+        .find(|observation| observation.timestamp <= target)
+}
+
+/// 用池子自带的观测缓冲区计算截至 `now`、长度至少为 `window` 秒的几何平均价格
+///
+/// # Returns
+/// * `None` - `window` 为 0，或者缓冲区里最早的观测也不够 `window` 秒
+pub fn geometric_twap_for_window(
+    pool_state: &PoolState,
+    reserve_a: u64,
+    reserve_b: u64,
+    now: u64,
+    window: u64,
+) -> Option<GeometricTwap> {
+    if window == 0 {
+        return None;
+    }
+    let end = observe(pool_state, reserve_a, reserve_b, now);
+    let start = observation_before(pool_state, end.timestamp.checked_sub(window)?)?;
+    geometric_mean(
+        start.timestamp,
+        start.log_price_cumulative,
+        end.timestamp,
+        end.log_price_cumulative,
+    )
+}
+
+/// 现货价格 reserve_b / reserve_a，Q64.64
+///
+/// # Returns
+/// * `None` - reserve_a 为 0
+pub fn spot_price(reserve_a: u64, reserve_b: u64) -> Option<u128> {
+    ((reserve_b as u128) << 64).checked_div(reserve_a as u128)
+}
+
+/// swap 限制价格偏离时使用的参考价：最近一条早于当前时间的观测到现在的几何均价（B 计价的 A）
+///
+/// 调用前必须已经用 `update_twap` 把累加器更新到当前时间。同一秒内的多笔交易还没有计入累加器，
+/// 所以它们都和同一个参考价比较，无法在一个 slot 内分多笔把价格推远。
+///
+/// # Returns
+/// * `None` - 池子还没有早于当前时间的观测（刚创建），此时不做限制
+pub fn reference_price(pool_state: &PoolState) -> Option<u128> {
+    let now = pool_state.block_timestamp_last;
+    let start = observation_before(pool_state, now.checked_sub(1)?)?;
+    geometric_mean(
+        start.timestamp,
+        start.log_price_cumulative,
+        now,
+        pool_state.log_price_cumulative_last,
+    )
+    .map(|twap| twap.price_a)
+}
+
+/// `price` 是否在 `reference` 的 ±`max_deviation_bps` 以内
+pub fn within_deviation(price: u128, reference: u128, max_deviation_bps: u16) -> bool {
+    // 先除后乘避免溢出，损失的精度小于 1e-4 bps
+    let limit = reference / BPS_DENOMINATOR * max_deviation_bps as u128;
+    price.abs_diff(reference) <= limit
+}
+
+/// swap 之后的储备金是否满足池子的价格偏离限制，未开启限制或还没有参考价时总是满足
+pub fn swap_within_deviation(pool_state: &PoolState, reserve_a_after: u64, reserve_b_after: u64) -> bool {
+    let max_deviation_bps = pool_state.max_price_deviation_bps;
+    if max_deviation_bps == 0 {
+        return true;
+    }
+    let Some(reference) = reference_price(pool_state) else {
+        return true;
+    };
+    spot_price(reserve_a_after, reserve_b_after)
+        .is_some_and(|spot| within_deviation(spot, reference, max_deviation_bps))
+}
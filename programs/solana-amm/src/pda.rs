@@ -0,0 +1,150 @@
+//! 程序账户地址的派生函数
+//!
+//! 给其他程序和链下客户端填写账户用，种子和 `contexts.rs` 中的约束一一对应。
+//! 其他程序通过 `cpi` feature 依赖本 crate 后，每条指令都有 Anchor 生成的
+//! `solana_amm::cpi::<指令名>` 和对应的 `solana_amm::cpi::accounts::<账户结构体>`：
+//!
+//! ```ignore
+//! let pool_state = pda::pool_address(&mint_a, &mint_b);
+//! solana_amm::cpi::swap(
+//!     CpiContext::new(amm_program, solana_amm::cpi::accounts::Swap { pool_state, .. }),
+//!     amount_in, is_a_to_b, min_amount_out, None, 0,
+//! )?;
+//! ```
+//!
+//! 完整的例子见工作空间中的 `programs/amm-consumer`。
+
+use anchor_lang::prelude::*;
+use anchor_spl::associated_token::get_associated_token_address;
+
+use crate::ID;
+
+fn find(seeds: &[&[u8]]) -> Pubkey {
+    Pubkey::find_program_address(seeds, &ID).0
+}
+
+/// 恒定乘积池子，`mint_a < mint_b`
+pub fn pool_address(mint_a: &Pubkey, mint_b: &Pubkey) -> Pubkey {
+    find(&[b"pool", mint_a.as_ref(), mint_b.as_ref()])
+}
+
+/// 所有金库和托管账户共用的签名 PDA
+pub fn pool_authority_address() -> Pubkey {
+    find(&[b"authority"])
+}
+
+/// 池子金库，pool_authority 的 ATA
+pub fn pool_vault_address(mint: &Pubkey) -> Pubkey {
+    get_associated_token_address(&pool_authority_address(), mint)
+}
+
+pub fn pool_registry_address() -> Pubkey {
+    find(&[b"pool_registry"])
+}
+
+pub fn registry_page_address(page_index: u64) -> Pubkey {
+    find(&[b"pool_registry_page", &page_index.to_le_bytes()])
+}
+
+pub fn fee_override_address(pool_state: &Pubkey, trader: &Pubkey) -> Pubkey {
+    find(&[b"fee_override", pool_state.as_ref(), trader.as_ref()])
+}
+
+pub fn lp_lock_address(pool_state: &Pubkey, owner: &Pubkey) -> Pubkey {
+    find(&[b"lp_lock", pool_state.as_ref(), owner.as_ref()])
+}
+
+pub fn lp_lock_vault_address(lp_lock: &Pubkey) -> Pubkey {
+    find(&[b"lp_lock_vault", lp_lock.as_ref()])
+}
+
+pub fn farm_address(pool_state: &Pubkey) -> Pubkey {
+    find(&[b"farm", pool_state.as_ref()])
+}
+
+pub fn farm_stake_vault_address(farm: &Pubkey) -> Pubkey {
+    find(&[b"farm_stake_vault", farm.as_ref()])
+}
+
+pub fn farm_reward_vault_address(farm: &Pubkey, reward_mint: &Pubkey) -> Pubkey {
+    find(&[b"farm_reward_vault", farm.as_ref(), reward_mint.as_ref()])
+}
+
+pub fn farm_stake_address(farm: &Pubkey, owner: &Pubkey) -> Pubkey {
+    find(&[b"farm_stake", farm.as_ref(), owner.as_ref()])
+}
+
+/// 集中流动性池子，`mint_a < mint_b`
+pub fn cl_pool_address(mint_a: &Pubkey, mint_b: &Pubkey, tick_spacing: u16) -> Pubkey {
+    find(&[b"cl_pool", mint_a.as_ref(), mint_b.as_ref(), &tick_spacing.to_le_bytes()])
+}
+
+pub fn cl_vault_address(cl_pool: &Pubkey, mint: &Pubkey) -> Pubkey {
+    find(&[b"cl_vault", cl_pool.as_ref(), mint.as_ref()])
+}
+
+pub fn tick_array_address(cl_pool: &Pubkey, start_tick_index: i32) -> Pubkey {
+    find(&[b"tick_array", cl_pool.as_ref(), &start_tick_index.to_le_bytes()])
+}
+
+pub fn position_address(cl_pool: &Pubkey, owner: &Pubkey, tick_lower: i32, tick_upper: i32) -> Pubkey {
+    find(&[
+        b"position",
+        cl_pool.as_ref(),
+        owner.as_ref(),
+        &tick_lower.to_le_bytes(),
+        &tick_upper.to_le_bytes(),
+    ])
+}
+
+pub fn limit_order_address(pool_state: &Pubkey, owner: &Pubkey, order_id: u64) -> Pubkey {
+    find(&[b"limit_order", pool_state.as_ref(), owner.as_ref(), &order_id.to_le_bytes()])
+}
+
+pub fn order_escrow_address(limit_order: &Pubkey) -> Pubkey {
+    find(&[b"order_escrow", limit_order.as_ref()])
+}
+
+pub fn twamm_address(pool_state: &Pubkey) -> Pubkey {
+    find(&[b"twamm", pool_state.as_ref()])
+}
+
+pub fn twamm_vault_address(twamm: &Pubkey, mint: &Pubkey) -> Pubkey {
+    find(&[b"twamm_vault", twamm.as_ref(), mint.as_ref()])
+}
+
+pub fn long_term_order_address(pool_state: &Pubkey, owner: &Pubkey, order_id: u64) -> Pubkey {
+    find(&[b"long_term_order", pool_state.as_ref(), owner.as_ref(), &order_id.to_le_bytes()])
+}
+
+pub fn dca_vault_address(pool_state: &Pubkey, owner: &Pubkey, dca_id: u64) -> Pubkey {
+    find(&[b"dca_vault", pool_state.as_ref(), owner.as_ref(), &dca_id.to_le_bytes()])
+}
+
+pub fn dca_input_address(dca_vault: &Pubkey) -> Pubkey {
+    find(&[b"dca_input", dca_vault.as_ref()])
+}
+
+pub fn dca_output_address(dca_vault: &Pubkey) -> Pubkey {
+    find(&[b"dca_output", dca_vault.as_ref()])
+}
+
+pub fn batch_auction_address(pool_state: &Pubkey) -> Pubkey {
+    find(&[b"batch_auction", pool_state.as_ref()])
+}
+
+pub fn batch_vault_address(batch_auction: &Pubkey, mint: &Pubkey) -> Pubkey {
+    find(&[b"batch_vault", batch_auction.as_ref(), mint.as_ref()])
+}
+
+pub fn batch_order_address(batch_auction: &Pubkey, owner: &Pubkey, batch_id: u64) -> Pubkey {
+    find(&[b"batch_order", batch_auction.as_ref(), owner.as_ref(), &batch_id.to_le_bytes()])
+}
+
+pub fn pool_gatekeeper_address(pool_state: &Pubkey) -> Pubkey {
+    find(&[b"pool_gatekeeper", pool_state.as_ref()])
+}
+
+pub fn access_pass_address(pool_state: &Pubkey, user: &Pubkey) -> Pubkey {
+    find(&[b"access_pass", pool_state.as_ref(), user.as_ref()])
+}
@@ -0,0 +1,38 @@
+//! 许可池子的准入检查
+//!
+//! 开启许可模式（`PoolState.permissioned`）的池子上，`swap`、`add_liquidity`、`remove_liquidity`、
+//! `claim_lp_lock_fees` 要求用户传入自己的 `AccessPass` 和池子的 `PoolGatekeeper`。凭证由池子管理员指定的
+//! 守门人发放，记录发放时的守门人，更换守门人之后旧凭证失效；撤销时直接关闭账户。两个账户的地址由
+//! Anchor 约束按种子校验。没有开启许可模式的池子忽略这两个可选账户，行为不变。
+//!
+//! 挂单类指令由 keeper 代为成交，无法检查成交时的用户，许可池子上直接拒绝。
+
+use anchor_lang::prelude::*;
+
+use crate::errors::AmmError;
+use crate::state::{AccessPass, PoolGatekeeper, PoolState};
+
+/// 许可池子要求用户持有当前守门人发放、在 now 时刻有效的 AccessPass
+pub fn check_access(
+    pool_state: &AccountLoader<PoolState>,
+    access_pass: Option<&Account<AccessPass>>,
+    pool_gatekeeper: Option<&Account<PoolGatekeeper>>,
+    now: i64,
+) -> Result<()> {
+    if !pool_state.load()?.permissioned() {
+        return Ok(());
+    }
+    // 没有 PoolGatekeeper 就无法确认凭证的发放者，按缺少凭证处理
+    let (Some(access_pass), Some(pool_gatekeeper)) = (access_pass, pool_gatekeeper) else {
+        return err!(AmmError::AccessPassRequired);
+    };
+    require_keys_eq!(access_pass.issuer, pool_gatekeeper.authority, AmmError::InvalidAccessPass);
+    require!(access_pass.is_valid(now), AmmError::AccessPassExpired);
+    Ok(())
+}
+
+/// 挂单类指令只能在没有开启许可模式的池子上使用
+pub fn require_permissionless(pool_state: &AccountLoader<PoolState>) -> Result<()> {
+    require!(!pool_state.load()?.permissioned(), AmmError::PermissionedPool);
+    Ok(())
+}
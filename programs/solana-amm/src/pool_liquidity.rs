@@ -0,0 +1,128 @@
+//! 存入和取出流动性的计算，`add_liquidity`、`remove_liquidity` 和链下模拟器共用
+//!
+//! 和 `pool_swap` 一样只计算和检查，不读取账户、不转账：handler 按返回的数量铸造、销毁 LP 和划转代币，
+//! 模拟器把同样的数量加减到余额上，两边的结果不会分叉。
+//!
+//! 两个函数的顺序和链上一致：先用变化前的储备更新 TWAP，再按增发前的 LP 供应量结算协议费，
+//! 然后按包含协议费的供应量计算用户的份额，最后把 `k_last` 更新为变化之后的储备乘积。
+
+use crate::errors::AmmError;
+use crate::math::{self, MINIMUM_LIQUIDITY};
+use crate::state::PoolState;
+
+/// 一笔存入的结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoolDeposit {
+    /// 铸造给 protocol_fee_recipient 的 LP
+    pub protocol_fee_lp: u64,
+    /// 首次存入时铸造到黑洞地址的 LP（MINIMUM_LIQUIDITY），之后为 0
+    pub locked_lp: u64,
+    /// 铸造给用户的 LP
+    pub liquidity: u64,
+    pub reserve_a_after: u64,
+    pub reserve_b_after: u64,
+}
+
+/// 一笔取出的结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoolWithdrawal {
+    /// 铸造给 protocol_fee_recipient 的 LP
+    pub protocol_fee_lp: u64,
+    pub amount_a: u64,
+    pub amount_b: u64,
+    pub reserve_a_after: u64,
+    pub reserve_b_after: u64,
+}
+
+/// 按储备 (reserve_a, reserve_b) 和 LP 供应量 `lp_supply` 计算存入 (amount_a, amount_b) 的结果
+///
+/// `lp_supply` 为 0 时是首次存入：按 sqrt(amount_a * amount_b) 计算流动性，其中 MINIMUM_LIQUIDITY 永久锁定；
+/// 之后按两侧比例中较小的一个计算，新用户不会分走已积累的手续费。
+///
+/// # Errors
+/// * `CapExceeded` - 单笔存入超过限额，或存入之后的储备超过上限
+/// * `InitialLiquidityTooLow` - 首次存入的流动性不超过 MINIMUM_LIQUIDITY
+pub fn execute_add_liquidity(
+    pool_state: &mut PoolState,
+    reserve_a: u64,
+    reserve_b: u64,
+    lp_supply: u64,
+    amount_a: u64,
+    amount_b: u64,
+    now: u64,
+) -> Result<PoolDeposit, AmmError> {
+    // 储备上限和单笔存入限额，0 表示不限制
+    if !pool_state.within_deposit_limit(true, amount_a) || !pool_state.within_deposit_limit(false, amount_b) {
+        return Err(AmmError::CapExceeded);
+    }
+    let reserve_a_after = reserve_a.checked_add(amount_a).ok_or(AmmError::MathOverflow)?;
+    let reserve_b_after = reserve_b.checked_add(amount_b).ok_or(AmmError::MathOverflow)?;
+    if !pool_state.within_reserve_cap(true, reserve_a_after) || !pool_state.within_reserve_cap(false, reserve_b_after) {
+        return Err(AmmError::CapExceeded);
+    }
+
+    math::update_twap(pool_state, reserve_a, reserve_b, now);
+    let protocol_fee_lp = protocol_fee_mint(pool_state, reserve_a, reserve_b, lp_supply)?;
+
+    let (locked_lp, liquidity) = if lp_supply == 0 {
+        // 学习 uniswap，把 MINIMUM_LIQUIDITY 铸造到黑洞地址，防止流动性归零攻击
+        let initial_liquidity = math::sqrt_product_u64(amount_a, amount_b).ok_or(AmmError::MathOverflow)?;
+        if initial_liquidity <= MINIMUM_LIQUIDITY {
+            return Err(AmmError::InitialLiquidityTooLow);
+        }
+        (MINIMUM_LIQUIDITY, initial_liquidity - MINIMUM_LIQUIDITY)
+    } else {
+        let total_lp_supply = lp_supply.checked_add(protocol_fee_lp).ok_or(AmmError::MathOverflow)?;
+        let liquidity = math::calculate_deposit_liquidity(amount_a, amount_b, reserve_a, reserve_b, total_lp_supply)
+            .ok_or(AmmError::MathOverflow)?;
+        (0, liquidity)
+    };
+
+    pool_state.k_last = (reserve_a_after as u128)
+        .checked_mul(reserve_b_after as u128)
+        .ok_or(AmmError::MathOverflow)?;
+
+    Ok(PoolDeposit { protocol_fee_lp, locked_lp, liquidity, reserve_a_after, reserve_b_after })
+}
+
+/// 按储备 (reserve_a, reserve_b) 和 LP 供应量 `lp_supply` 计算销毁 `amount_lp` 取回的代币
+///
+/// 调用者负责检查用户持有足够的 LP。
+///
+/// # Errors
+/// * `SlippageExceeded` - 取回的任意一侧低于 `min_amount_a` / `min_amount_b`
+#[allow(clippy::too_many_arguments)]
+pub fn execute_remove_liquidity(
+    pool_state: &mut PoolState,
+    reserve_a: u64,
+    reserve_b: u64,
+    lp_supply: u64,
+    amount_lp: u64,
+    min_amount_a: u64,
+    min_amount_b: u64,
+    now: u64,
+) -> Result<PoolWithdrawal, AmmError> {
+    math::update_twap(pool_state, reserve_a, reserve_b, now);
+    let protocol_fee_lp = protocol_fee_mint(pool_state, reserve_a, reserve_b, lp_supply)?;
+
+    let total_lp_supply = lp_supply.checked_add(protocol_fee_lp).ok_or(AmmError::MathOverflow)?;
+    let (amount_a, amount_b) = math::calculate_withdraw_amounts(amount_lp, reserve_a, reserve_b, total_lp_supply)
+        .ok_or(AmmError::MathOverflow)?;
+    if amount_a < min_amount_a || amount_b < min_amount_b {
+        return Err(AmmError::SlippageExceeded);
+    }
+
+    let reserve_a_after = reserve_a.checked_sub(amount_a).ok_or(AmmError::MathOverflow)?;
+    let reserve_b_after = reserve_b.checked_sub(amount_b).ok_or(AmmError::MathOverflow)?;
+    pool_state.k_last = (reserve_a_after as u128)
+        .checked_mul(reserve_b_after as u128)
+        .ok_or(AmmError::MathOverflow)?;
+
+    Ok(PoolWithdrawal { protocol_fee_lp, amount_a, amount_b, reserve_a_after, reserve_b_after })
+}
+
+// 上次结算以来手续费让 sqrt(k) 增长，按 protocol_fee_share 给协议方增发 LP
+fn protocol_fee_mint(pool_state: &PoolState, reserve_a: u64, reserve_b: u64, lp_supply: u64) -> Result<u64, AmmError> {
+    math::calculate_protocol_fee_mint(reserve_a, reserve_b, pool_state.k_last, lp_supply, pool_state.protocol_fee_share)
+        .ok_or(AmmError::MathOverflow)
+}
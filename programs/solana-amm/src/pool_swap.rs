@@ -0,0 +1,124 @@
+//! 所有和池子储备成交的路径共用的检查
+//!
+//! `swap`、`fill_orders`、`execute_dca` 立即和池子成交，`settle_batch` 按批次成交，TWAMM 按段成交。
+//! 这些路径的检查必须一致，任何一条少了一项就能绕过池子的保护：
+//!
+//! 1. [`begin_pool_swap`]：开启批量拍卖的池子拒绝立即成交，许可池子检查准入，再把 TWAMM 长期订单结算到现在
+//! 2. [`execute_pool_swap`]：用成交前的储备更新 TWAP，按池子费率计算输出，检查滑点，再用 [`check_pool_bounds`]
+//!    检查成交之后的价格偏离和储备上限
+//!
+//! 批量拍卖和 TWAMM 不是单笔兑换，自己计算成交之后的储备，再调用 [`check_pool_bounds`]。
+//! 后两个函数直接返回 `AmmError`，不依赖账户，链下模拟器复用同一份实现。
+
+use anchor_lang::prelude::*;
+
+use crate::errors::AmmError;
+use crate::math;
+use crate::oracle;
+use crate::permission;
+use crate::state::{AccessPass, PoolGatekeeper, PoolState};
+use crate::twamm::{self, TwammSettleAccounts};
+
+/// 谁在和池子成交，决定许可池子上的准入检查
+pub enum Trader<'a, 'info> {
+    /// 用户自己签名兑换，许可池子要求有效的 AccessPass
+    User {
+        access_pass: Option<&'a Account<'info, AccessPass>>,
+        pool_gatekeeper: Option<&'a Account<'info, PoolGatekeeper>>,
+    },
+    /// keeper 代为成交挂单，成交时无法检查订单所有者，许可池子直接拒绝
+    Keeper,
+}
+
+/// 和池子立即成交之前的检查，并把 TWAMM 长期订单结算到 `now`
+///
+/// 返回是否划转了代币，和 `twamm::settle_pool` 一样，返回 true 时调用者需要 reload 池子金库再读取储备。
+pub fn begin_pool_swap(accounts: TwammSettleAccounts, trader: Trader, now: i64) -> Result<bool> {
+    // 开启批量拍卖的池子不能立即成交，否则抢跑仍然有效
+    require!(!accounts.pool_state.load()?.batch_mode(), AmmError::BatchAuctionOnly);
+    match trader {
+        Trader::User { access_pass, pool_gatekeeper } => {
+            permission::check_access(accounts.pool_state, access_pass, pool_gatekeeper, now)?
+        }
+        Trader::Keeper => permission::require_permissionless(accounts.pool_state)?,
+    }
+    twamm::settle_pool(accounts, now as u64)
+}
+
+/// 储备从 (reserve_a, reserve_b) 变为 (reserve_a_after, reserve_b_after) 之后，
+/// 现货价格不能偏离 TWAP 太多，增加的一侧不能超过储备上限；储备没有变化时不检查
+pub fn check_pool_bounds(
+    pool_state: &PoolState,
+    reserve_a: u64,
+    reserve_b: u64,
+    reserve_a_after: u64,
+    reserve_b_after: u64,
+) -> std::result::Result<(), AmmError> {
+    if (reserve_a_after, reserve_b_after) == (reserve_a, reserve_b) {
+        return Ok(());
+    }
+    if !oracle::swap_within_deviation(pool_state, reserve_a_after, reserve_b_after) {
+        return Err(AmmError::PriceDeviationTooLarge);
+    }
+    if !pool_state.within_reserve_caps(reserve_a, reserve_b, reserve_a_after, reserve_b_after) {
+        return Err(AmmError::CapExceeded);
+    }
+    Ok(())
+}
+
+/// 一笔兑换的成交结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoolSwap {
+    /// 扣除手续费之后的有效输入
+    pub amount_in_effective: u64,
+    pub amount_out: u64,
+    pub reserve_a_after: u64,
+    pub reserve_b_after: u64,
+}
+
+/// 按恒定乘积曲线和池子成交一笔兑换，只计算和检查，不转账
+///
+/// 不读取任何账户，链下模拟器也直接调用这个函数，成交结果和链上一致。
+/// 先用成交前的储备更新 TWAP（同一秒内重复调用不会改变累加器），价格偏离以更新之后的 TWAP 为参考。
+/// `fee_numerator` 由调用者传入，折扣费率在这之前确定；分母总是池子的 `fee_denominator`。
+///
+/// # Errors
+/// * `SlippageExceeded` - 输出低于 `min_amount_out`
+/// * `PriceDeviationTooLarge` / `CapExceeded` - 见 [`check_pool_bounds`]
+#[allow(clippy::too_many_arguments)]
+pub fn execute_pool_swap(
+    pool_state: &mut PoolState,
+    reserve_a: u64,
+    reserve_b: u64,
+    amount_in: u64,
+    is_a_to_b: bool,
+    fee_numerator: u64,
+    min_amount_out: u64,
+    now: u64,
+) -> std::result::Result<PoolSwap, AmmError> {
+    math::update_twap(pool_state, reserve_a, reserve_b, now);
+
+    let (reserve_in, reserve_out) = if is_a_to_b { (reserve_a, reserve_b) } else { (reserve_b, reserve_a) };
+    let (amount_in_effective, amount_out) = math::calculate_swap_amount_out(
+        amount_in,
+        reserve_in,
+        reserve_out,
+        fee_numerator,
+        pool_state.fee_denominator,
+    )
+    .ok_or(AmmError::MathOverflow)?;
+    if amount_out < min_amount_out {
+        return Err(AmmError::SlippageExceeded);
+    }
+
+    let reserve_in_after = reserve_in.checked_add(amount_in).ok_or(AmmError::MathOverflow)?;
+    let reserve_out_after = reserve_out.checked_sub(amount_out).ok_or(AmmError::MathOverflow)?;
+    let (reserve_a_after, reserve_b_after) = if is_a_to_b {
+        (reserve_in_after, reserve_out_after)
+    } else {
+        (reserve_out_after, reserve_in_after)
+    };
+    check_pool_bounds(pool_state, reserve_a, reserve_b, reserve_a_after, reserve_b_after)?;
+
+    Ok(PoolSwap { amount_in_effective, amount_out, reserve_a_after, reserve_b_after })
+}
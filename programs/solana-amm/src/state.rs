@@ -1,64 +1,898 @@
 use anchor_lang::prelude::*;
 
+/// 预言机环形缓冲区的观测数量
+pub const OBSERVATION_COUNT: usize = 8;
+/// 两次写入观测之间的最小间隔（秒），缓冲区写满时至少能覆盖 OBSERVATION_COUNT * OBSERVATION_INTERVAL 秒
+pub const OBSERVATION_INTERVAL: u64 = 300;
+/// 推荐费的全局上限（万分比），管理员给单个池子设置的上限不能超过它
+pub const MAX_REFERRAL_FEE_BPS: u16 = 100;
+/// 一个池子的流动性挖矿最多同时发放的奖励代币种类
+pub const MAX_FARM_REWARDS: usize = 3;
+/// PoolState 的当前版本，`initialize` 写入，旧账户通过 `migrate_pool` 升级
+/// 版本 2 起改为零拷贝布局，版本 0、1 是 Borsh 布局（见 `LegacyPoolState`）；
+/// 版本 3 在末尾追加了新的 `reserved` 预留空间
+pub const CURRENT_POOL_VERSION: u8 = 3;
+/// 版本 2 的 PoolState 账户长度（没有末尾的 `reserved`），`migrate_pool` 据此区分零拷贝账户和 Borsh 账户
+pub const POOL_V2_LEN: usize = 696;
+/// `PoolState` 末尾预留的字节数
+pub const POOL_RESERVED_BYTES: usize = 64;
+/// `LegacyPoolState` 末尾预留的字节数
+pub const LEGACY_POOL_RESERVED_BYTES: usize = 64;
+/// ClPool 末尾预留的字节数
+pub const CL_POOL_RESERVED_BYTES: usize = 64;
+/// TWAMM 长期订单的到期时间都是 TWAMM_INTERVAL 的整数倍，同一时刻到期的订单共用一个槽位
+pub const TWAMM_INTERVAL: u64 = 14_400;
+/// Twamm 中到期槽位的数量，订单最长持续 TWAMM_SLOTS - 1 个间隔
+pub const TWAMM_SLOTS: usize = 64;
+/// 一批最多的订单数量，结算时每笔订单要传入 4 个账户，受交易账户数量限制
+pub const MAX_BATCH_ORDERS: u8 = 8;
+/// 每个注册表分页记录的池子数量，一页正好能放进 1024 字节的 return data
+pub const REGISTRY_PAGE_SIZE: usize = 8;
+/// 每个 TickArray 覆盖的可用 tick 数量，覆盖范围是 TICK_ARRAY_SIZE * tick_spacing
+pub const TICK_ARRAY_SIZE: usize = 32;
+
+/// 某一时刻的 log2 价格累加器快照，用于计算几何平均 TWAP
+///
+/// 作为 PoolState 的一部分零拷贝读取：i128 放在最前面，末尾显式补齐到 16 字节的整数倍
+#[zero_copy]
+#[derive(Default, Debug, PartialEq, Eq)]
+pub struct OracleObservation {
+    pub log_price_cumulative: i128,
+    pub timestamp: u64,
+    pub padding: [u8; 8],
+}
+
+impl OracleObservation {
+    pub fn new(timestamp: u64, log_price_cumulative: i128) -> Self {
+        Self { log_price_cumulative, timestamp, padding: [0; 8] }
+    }
+}
+
+/// 流动性挖矿的一种奖励代币，按秒线性释放到 end_ts
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub struct FarmReward {
+    pub mint: Pubkey,
+    pub vault: Pubkey,                  // 奖励金库，authority 是 pool_authority
+    pub emission_per_second: u64,
+    pub end_ts: i64,                    // 停止释放的时间（unix 时间戳）
+    pub last_update_ts: i64,            // reward_per_share 上次累加到的时间
+    pub reward_per_share: u128,         // 每个质押 LP 累计可得的奖励，放大 REWARD_PER_SHARE_PRECISION 倍
+}
+
 /// 池子状态结构体
 /// 存储 AMM 池的所有关键信息，包括代币地址、金库地址、手续费率等
-#[account]
+///
+/// 零拷贝布局：指令直接在账户数据上读写，不再每次反序列化、序列化整个结构体。
+/// `repr(C)` 下按对齐从大到小排列字段，u128/i128 都落在 16 字节边界上，
+/// 结构体总长是 16 的整数倍，主机和 SBF 上都没有隐式填充。
+///
+/// 布局是对外承诺的接口，其他程序可以用 `AccountLoader<PoolState>` 读取，
+/// 也可以不依赖本 crate 按偏移直接解析。下表是账户数据中的偏移（含 8 字节 discriminator），
+/// 整数都是小端序；已有字段的偏移不会再变。版本 2 末尾预留的 64 字节已经全部划给了
+/// `twamm` 和四个存入上限，版本 3 在末尾追加了新的 64 字节 `reserved`，之后新字段从这里划出：
+///
+/// | 偏移 | 长度 | 字段 |
+/// |-----:|-----:|------|
+/// | 0    | 8    | discriminator |
+/// | 8    | 16   | `price_a_cumulative_last` |
+/// | 24   | 16   | `price_b_cumulative_last` |
+/// | 40   | 16   | `k_last` |
+/// | 56   | 16   | `log_price_cumulative_last` |
+/// | 72   | 256  | `observations`（8 条，每条 `log_price_cumulative` i128 + `timestamp` u64 + 8 字节填充） |
+/// | 328  | 32   | `token_a` |
+/// | 360  | 32   | `token_b` |
+/// | 392  | 32   | `token_a_vault` |
+/// | 424  | 32   | `token_b_vault` |
+/// | 456  | 32   | `lp_mint` |
+/// | 488  | 32   | `admin` |
+/// | 520  | 32   | `pending_admin` |
+/// | 552  | 32   | `protocol_fee_recipient` |
+/// | 584  | 8    | `fee_numerator` |
+/// | 592  | 8    | `fee_denominator` |
+/// | 600  | 8    | `block_timestamp_last` |
+/// | 608  | 8    | `protocol_fee_share` |
+/// | 616  | 2    | `max_price_deviation_bps` |
+/// | 618  | 2    | `max_referral_fee_bps` |
+/// | 620  | 1    | `pool_bump` |
+/// | 621  | 1    | `auth_bump` |
+/// | 622  | 1    | `observation_index` |
+/// | 623  | 1    | `observation_count` |
+/// | 624  | 1    | `version` |
+/// | 625  | 1    | `batch_mode` |
+/// | 626  | 1    | `registered` |
+/// | 627  | 1    | `permissioned` |
+/// | 628  | 4    | `padding` |
+/// | 632  | 32   | `twamm` |
+/// | 664  | 8    | `max_total_reserve_a` |
+/// | 672  | 8    | `max_total_reserve_b` |
+/// | 680  | 8    | `max_deposit_a` |
+/// | 688  | 8    | `max_deposit_b` |
+/// | 696  | 64   | `reserved` |
+///
+/// 账户总长 `PoolState::LEN` = 760，`tests/state_layout.rs` 逐个字段检查上表。
+#[account(zero_copy)]
+#[derive(Debug)]
 pub struct PoolState {
+    // --- 16 字节对齐 ---
+    pub price_a_cumulative_last: u128,  // Token A 的累计价格
+    pub price_b_cumulative_last: u128,  // Token B 的累计价格
+    pub k_last: u128,                   // 上次结算时的储备金乘积 (reserve_a * reserve_b)
+    pub log_price_cumulative_last: i128, // log2(Token A 的价格) 的累计值，Q64.64，按 2^128 回绕
+    pub observations: [OracleObservation; OBSERVATION_COUNT], // 观测环形缓冲区
+
+    // --- 地址 ---
     pub token_a: Pubkey,
     pub token_b: Pubkey,
-
     pub token_a_vault: Pubkey,
     pub token_b_vault: Pubkey,
     pub lp_mint: Pubkey,
+    pub admin: Pubkey,                      // 管理员地址
+    pub pending_admin: Pubkey,              // 待定管理員，Pubkey::default() 表示没有，读写用 pending_admin() / set_pending_admin()
+    pub protocol_fee_recipient: Pubkey,     // 协议收入接收地址
+
+    // --- 8 字节对齐 ---
     pub fee_numerator: u64,
     pub fee_denominator: u64,
+    pub block_timestamp_last: u64,          // 记录上次更新的时间戳
+    pub protocol_fee_share: u64,            // 协议分成比例 (比如 1/6，建议设为分母，0表示关闭)
+
+    // --- 2 字节对齐 ---
+    pub max_price_deviation_bps: u16,       // swap 后现货价格相对 TWAP 的最大偏离（万分比），0 表示不限制
+    pub max_referral_fee_bps: u16,          // swap 时推荐人最多能收取的比例（万分比），0 表示不允许推荐费
 
+    // --- 1 字节 ---
     pub pool_bump: u8,
     pub auth_bump: u8,
+    pub observation_index: u8,              // 最近一次写入的观测下标
+    pub observation_count: u8,              // 已写入的观测数量，最多 OBSERVATION_COUNT
+    pub version: u8,                        // 账户布局版本，见 CURRENT_POOL_VERSION
+    pub batch_mode: u8,                     // 非 0 表示开启了批量拍卖（见 BatchAuction），从 padding 中划出
+    pub registered: u8,                     // 非 0 表示已经写入 PoolRegistry，从 padding 中划出
+    pub permissioned: u8,                   // 非 0 表示开启了许可模式（见 PoolGatekeeper），从 padding 中划出
+    pub padding: [u8; 4],                   // 补齐到 16 字节边界
+    pub twamm: Pubkey,                      // 长期订单账户（见 Twamm），Pubkey::default() 表示没有开启，从 reserved 中划出
+    // 以下四个上限从 reserved 中划出，0 表示不限制
+    pub max_total_reserve_a: u64,           // Token A 储备金的上限
+    pub max_total_reserve_b: u64,           // Token B 储备金的上限
+    pub max_deposit_a: u64,                 // add_liquidity 单笔最多存入的 Token A
+    pub max_deposit_b: u64,                 // add_liquidity 单笔最多存入的 Token B
+    pub reserved: [u8; POOL_RESERVED_BYTES], // 版本 3 追加的预留空间
+}
 
-    
-    // --- TWAP 新增字段 ---
-    pub block_timestamp_last: u64,       // 记录上次更新的时间戳
-    pub price_a_cumulative_last: u128,  // Token A 的累计价格
-    pub price_b_cumulative_last: u128,  // Token B 的累计价格
+impl PoolState {
+    pub const LEN: usize = 8 + std::mem::size_of::<PoolState>();
 
-    // --- 协议治理 ---
-    pub admin: Pubkey,                      // 管理员地址
-    pub pending_admin: Option<Pubkey>,      // 待定管理員
-    pub protocol_fee_recipient: Pubkey,     // 协议收入接收地址
-    pub protocol_fee_share: u64,    // 协议分成比例 (比如 1/6，建议设为分母，0表示关闭)
-    
-    // --- 营收结算核心 ---
-    pub k_last: u128,               // 上次结算时的储备金乘积 (reserve_a * reserve_b)
+    /// 待定管理员，没有发起转移时为 None
+    pub fn pending_admin(&self) -> Option<Pubkey> {
+        (self.pending_admin != Pubkey::default()).then_some(self.pending_admin)
+    }
+
+    pub fn set_pending_admin(&mut self, pending_admin: Option<Pubkey>) {
+        self.pending_admin = pending_admin.unwrap_or_default();
+    }
+
+    /// 开启了 TWAMM 时返回 Twamm 账户地址
+    pub fn twamm(&self) -> Option<Pubkey> {
+        (self.twamm != Pubkey::default()).then_some(self.twamm)
+    }
+
+    /// 开启了批量拍卖时，兑换只能通过 BatchAuction 按统一价格成交
+    pub fn batch_mode(&self) -> bool {
+        self.batch_mode != 0
+    }
+
+    /// 已经写入 PoolRegistry，`register_pool` 不会重复登记
+    pub fn registered(&self) -> bool {
+        self.registered != 0
+    }
+
+    /// 开启了许可模式时，swap、add_liquidity、remove_liquidity 要求用户持有有效的 AccessPass
+    pub fn permissioned(&self) -> bool {
+        self.permissioned != 0
+    }
+
+    /// Token A（is_token_a）或 Token B 的储备增加到 reserve_after 之后是否仍在上限以内
+    pub fn within_reserve_cap(&self, is_token_a: bool, reserve_after: u64) -> bool {
+        let cap = if is_token_a { self.max_total_reserve_a } else { self.max_total_reserve_b };
+        cap == 0 || reserve_after <= cap
+    }
+
+    /// 储备从 (reserve_a, reserve_b) 变为 (reserve_a_after, reserve_b_after) 之后，增加的一侧是否仍在上限以内
+    pub fn within_reserve_caps(&self, reserve_a: u64, reserve_b: u64, reserve_a_after: u64, reserve_b_after: u64) -> bool {
+        (reserve_a_after <= reserve_a || self.within_reserve_cap(true, reserve_a_after))
+            && (reserve_b_after <= reserve_b || self.within_reserve_cap(false, reserve_b_after))
+    }
+
+    /// 单笔存入的 Token A（is_token_a）或 Token B 是否在限额以内
+    pub fn within_deposit_limit(&self, is_token_a: bool, amount: u64) -> bool {
+        let limit = if is_token_a { self.max_deposit_a } else { self.max_deposit_b };
+        limit == 0 || amount <= limit
+    }
 }
 
-impl PoolState {
-    pub const LEN: usize = Self::calculate_len();
-    
-    const fn calculate_len() -> usize {
-        const DISCRIMINATOR: usize = 8;
-        const PUBKEY_SIZE: usize = 32;
-        const U64_SIZE: usize = 8;
-        const U128_SIZE: usize = 16;
-        const U8_SIZE: usize = 1;
-        
-        DISCRIMINATOR
-            .saturating_add(PUBKEY_SIZE) // token_a
-            .saturating_add(PUBKEY_SIZE) // token_b
-            .saturating_add(PUBKEY_SIZE) // token_a_vault
-            .saturating_add(PUBKEY_SIZE) // token_b_vault
-            .saturating_add(PUBKEY_SIZE) // lp_mint
-            .saturating_add(U64_SIZE)    // fee_numerator
-            .saturating_add(U64_SIZE)    // fee_denominator
-            .saturating_add(U8_SIZE)     // pool_bump
-            .saturating_add(U8_SIZE)     // auth_bump
-            .saturating_add(U128_SIZE)   // price_a_cumulative_last (u128)
-            .saturating_add(U128_SIZE)   // price_b_cumulative_last (u128)
-            .saturating_add(U64_SIZE)    // block_timestamp_last
-            .saturating_add(PUBKEY_SIZE) // admin
-            .saturating_add(1 + PUBKEY_SIZE) // pending_admin (Option<Pubkey>: 1 byte discriminator + 32 bytes)
-            .saturating_add(PUBKEY_SIZE) // protocol_fee_recipient
-            .saturating_add(U64_SIZE)    // protocol_fee_share
-            .saturating_add(U128_SIZE)   // k_last
+// 布局一旦上线就不能再变，长度写死在这里防止无意中改动
+const _: () = assert!(std::mem::size_of::<PoolState>() == 752);
+const _: () = assert!(POOL_V2_LEN == PoolState::LEN - POOL_RESERVED_BYTES);
+// migrate_pool 按长度区分 Borsh 账户和版本 2 的零拷贝账户
+const _: () = assert!(LegacyPoolState::LEN < POOL_V2_LEN);
+const _: () = assert!(std::mem::size_of::<PoolState>().is_multiple_of(16));
+
+/// 版本 0、1 使用的 Borsh 布局，只用于 `migrate_pool` 把旧账户转换成零拷贝布局
+///
+/// 字段是按时间顺序追加的，版本 0 的账户是这个布局的前缀，补零后即可解析
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct LegacyPoolState {
+    pub token_a: Pubkey,
+    pub token_b: Pubkey,
+    pub token_a_vault: Pubkey,
+    pub token_b_vault: Pubkey,
+    pub lp_mint: Pubkey,
+    pub fee_numerator: u64,
+    pub fee_denominator: u64,
+    pub pool_bump: u8,
+    pub auth_bump: u8,
+    pub block_timestamp_last: u64,
+    pub price_a_cumulative_last: u128,
+    pub price_b_cumulative_last: u128,
+    pub admin: Pubkey,
+    pub pending_admin: Option<Pubkey>,
+    pub protocol_fee_recipient: Pubkey,
+    pub protocol_fee_share: u64,
+    pub k_last: u128,
+    pub log_price_cumulative_last: i128,
+    pub observation_index: u8,
+    pub observation_count: u8,
+    pub observations: [LegacyOracleObservation; OBSERVATION_COUNT],
+    pub max_price_deviation_bps: u16,
+    pub max_referral_fee_bps: u16,
+    pub version: u8,
+    pub reserved: [u8; LEGACY_POOL_RESERVED_BYTES],
+}
+
+// migrate_pool 靠账户长度区分两种布局
+const _: () = assert!(LegacyPoolState::LEN < PoolState::LEN);
+
+/// `LegacyPoolState` 中观测的 Borsh 布局
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, Debug)]
+pub struct LegacyOracleObservation {
+    pub timestamp: u64,
+    pub log_price_cumulative: i128,
+}
+
+impl LegacyPoolState {
+    /// 版本 1 账户的长度（含 discriminator）
+    pub const LEN: usize = 8 // discriminator
+        + 32 * 5    // token_a, token_b, token_a_vault, token_b_vault, lp_mint
+        + 8 + 8     // fee_numerator, fee_denominator
+        + 1 + 1     // pool_bump, auth_bump
+        + 8         // block_timestamp_last
+        + 16 + 16   // price_a_cumulative_last, price_b_cumulative_last
+        + 32        // admin
+        + 1 + 32    // pending_admin (Option<Pubkey>)
+        + 32        // protocol_fee_recipient
+        + 8         // protocol_fee_share
+        + 16        // k_last
+        + 16        // log_price_cumulative_last
+        + 1 + 1     // observation_index, observation_count
+        + (8 + 16) * OBSERVATION_COUNT // observations
+        + 2 + 2     // max_price_deviation_bps, max_referral_fee_bps
+        + 1         // version
+        + LEGACY_POOL_RESERVED_BYTES; // reserved
+
+    /// 转换成零拷贝布局，字段一一对应，版本号保持不变
+    pub fn to_pool_state(&self) -> PoolState {
+        let mut observations = [OracleObservation::default(); OBSERVATION_COUNT];
+        for (observation, legacy) in observations.iter_mut().zip(self.observations.iter()) {
+            *observation = OracleObservation::new(legacy.timestamp, legacy.log_price_cumulative);
+        }
+        PoolState {
+            price_a_cumulative_last: self.price_a_cumulative_last,
+            price_b_cumulative_last: self.price_b_cumulative_last,
+            k_last: self.k_last,
+            log_price_cumulative_last: self.log_price_cumulative_last,
+            observations,
+            token_a: self.token_a,
+            token_b: self.token_b,
+            token_a_vault: self.token_a_vault,
+            token_b_vault: self.token_b_vault,
+            lp_mint: self.lp_mint,
+            admin: self.admin,
+            pending_admin: self.pending_admin.unwrap_or_default(),
+            protocol_fee_recipient: self.protocol_fee_recipient,
+            fee_numerator: self.fee_numerator,
+            fee_denominator: self.fee_denominator,
+            block_timestamp_last: self.block_timestamp_last,
+            protocol_fee_share: self.protocol_fee_share,
+            max_price_deviation_bps: self.max_price_deviation_bps,
+            max_referral_fee_bps: self.max_referral_fee_bps,
+            pool_bump: self.pool_bump,
+            auth_bump: self.auth_bump,
+            observation_index: self.observation_index,
+            observation_count: self.observation_count,
+            version: self.version,
+            batch_mode: 0,
+            registered: 0,
+            permissioned: 0,
+            padding: [0; 4],
+            // 旧布局的预留空间从未使用过，新划出的字段都取默认值
+            twamm: Pubkey::default(),
+            max_total_reserve_a: 0,
+            max_total_reserve_b: 0,
+            max_deposit_a: 0,
+            max_deposit_b: 0,
+            reserved: [0; POOL_RESERVED_BYTES],
+        }
+    }
+}
+
+/// 单个交易者在某个池子上的折扣费率，由池子管理员创建和关闭
+/// PDA 种子：["fee_override", pool_state, trader]
+#[account]
+pub struct FeeOverride {
+    pub pool: Pubkey,
+    pub trader: Pubkey,
+    pub fee_numerator: u64,     // 替代池子的 fee_numerator，分母仍然是池子的 fee_denominator
+    pub bump: u8,
+}
+
+impl FeeOverride {
+    pub const LEN: usize = 8 // discriminator
+        + 32    // pool
+        + 32    // trader
+        + 8     // fee_numerator
+        + 1;    // bump
+}
+
+/// LP 锁仓记录，LP 托管在 `lock_vault` 中，由 pool_authority 控制
+/// PDA 种子：["lp_lock", pool_state, owner]；lock_vault 的种子：["lp_lock_vault", lp_lock]
+#[account]
+pub struct LpLock {
+    pub pool: Pubkey,
+    pub owner: Pubkey,
+    pub lock_vault: Pubkey,
+    pub amount: u64,            // 托管中的 LP 数量
+    pub unlock_ts: i64,         // 到期时间（unix 时间戳），之后才能取回
+    pub principal: u128,        // 锁仓本金，按 sqrt(reserve_a * reserve_b) 计量，超出本金的 LP 视为手续费
+    pub bump: u8,
+}
+
+impl LpLock {
+    pub const LEN: usize = 8 // discriminator
+        + 32    // pool
+        + 32    // owner
+        + 32    // lock_vault
+        + 8     // amount
+        + 8     // unlock_ts
+        + 16    // principal
+        + 1;    // bump
+}
+
+/// 池子的流动性挖矿，质押的 LP 托管在 `stake_vault` 中，由 pool_authority 控制
+/// PDA 种子：["farm", pool_state]；stake_vault 的种子：["farm_stake_vault", farm]
+#[account]
+pub struct Farm {
+    pub pool: Pubkey,
+    pub stake_vault: Pubkey,
+    pub total_staked: u64,
+    pub reward_count: u8,       // rewards 中已使用的数量，按添加顺序排列
+    pub rewards: [FarmReward; MAX_FARM_REWARDS],
+    pub bump: u8,
+}
+
+impl Farm {
+    pub const LEN: usize = 8 // discriminator
+        + 32    // pool
+        + 32    // stake_vault
+        + 8     // total_staked
+        + 1     // reward_count
+        + (32 + 32 + 8 + 8 + 8 + 16) * MAX_FARM_REWARDS // rewards
+        + 1;    // bump
+}
+
+/// 用户在某个 Farm 中的质押
+/// PDA 种子：["farm_stake", farm, owner]
+#[account]
+pub struct FarmStake {
+    pub farm: Pubkey,
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub reward_debts: [u128; MAX_FARM_REWARDS],     // amount * reward_per_share，上次结算时已计入的部分
+    pub pending_rewards: [u64; MAX_FARM_REWARDS],   // 已结算但还没有领取的奖励
+    pub bump: u8,
+}
+
+impl FarmStake {
+    pub const LEN: usize = 8 // discriminator
+        + 32    // farm
+        + 32    // owner
+        + 8     // amount
+        + 16 * MAX_FARM_REWARDS // reward_debts
+        + 8 * MAX_FARM_REWARDS  // pending_rewards
+        + 1;    // bump
+}
+
+/// 集中流动性池子，和恒定乘积的 PoolState 相互独立
+/// PDA 种子：["cl_pool", mint_a, mint_b, tick_spacing (u16 LE)]；金库种子：["cl_vault", cl_pool, mint]
+///
+/// 和 PoolState 一样是零拷贝布局，价格都是 Q64.64 的 sqrt(B/A)
+#[account(zero_copy)]
+#[derive(Debug)]
+pub struct ClPool {
+    // --- 16 字节对齐 ---
+    pub sqrt_price_x64: u128,           // 当前价格的平方根
+    pub liquidity: u128,                // 当前价格所在区间内的有效流动性
+    pub fee_growth_global_a_x64: u128,  // 每单位流动性累计的 A 手续费，Q64.64，按 2^128 回绕
+    pub fee_growth_global_b_x64: u128,  // 每单位流动性累计的 B 手续费
+
+    // --- 地址 ---
+    pub token_a: Pubkey,
+    pub token_b: Pubkey,
+    pub token_a_vault: Pubkey,
+    pub token_b_vault: Pubkey,
+    pub admin: Pubkey,
+
+    // --- 8 字节对齐 ---
+    pub fee_numerator: u64,
+    pub fee_denominator: u64,
+
+    // --- 4 字节及以下 ---
+    pub tick_current: i32,              // 满足 sqrt_price_at_tick(tick_current) <= sqrt_price_x64 的最大 tick
+    pub tick_spacing: u16,              // 可用 tick 的间隔，决定价格区间的粒度
+    pub bump: u8,
+    pub auth_bump: u8,
+    pub padding: [u8; 8],               // 补齐到 16 字节边界
+    pub reserved: [u8; CL_POOL_RESERVED_BYTES], // 预留空间
+}
+
+impl ClPool {
+    pub const LEN: usize = 8 + std::mem::size_of::<ClPool>();
+}
+
+/// TickArray 中的一个 tick，liquidity_gross 为 0 表示没有任何头寸以它为边界
+#[zero_copy]
+#[derive(Default, Debug, PartialEq, Eq)]
+pub struct Tick {
+    pub liquidity_net: i128,                // 价格从下往上穿过时加到池子流动性上的量
+    pub liquidity_gross: u128,              // 以它为边界的所有头寸流动性之和
+    pub fee_growth_outside_a_x64: u128,     // 在这个 tick "另一侧" 累计的 A 手续费
+    pub fee_growth_outside_b_x64: u128,
+}
+
+impl Tick {
+    pub fn is_initialized(&self) -> bool {
+        self.liquidity_gross != 0
+    }
+}
+
+/// 连续 TICK_ARRAY_SIZE 个可用 tick 的状态，任何人都可以付租金创建
+/// PDA 种子：["tick_array", cl_pool, start_tick_index (i32 LE)]
+#[account(zero_copy)]
+#[derive(Debug)]
+pub struct TickArray {
+    pub ticks: [Tick; TICK_ARRAY_SIZE],
+    pub pool: Pubkey,
+    pub start_tick_index: i32,              // 第一个 tick，是 TICK_ARRAY_SIZE * tick_spacing 的整数倍
+    pub padding: [u8; 12],
+}
+
+impl TickArray {
+    pub const LEN: usize = 8 + std::mem::size_of::<TickArray>();
+
+    /// 包含 `tick` 的 TickArray 的起始 tick（向下取整）
+    pub fn start_index_for(tick: i32, tick_spacing: u16) -> i32 {
+        let span = TICK_ARRAY_SIZE as i32 * tick_spacing as i32;
+        tick.div_euclid(span) * span
+    }
+
+    /// `tick` 在 ticks 中的下标，tick 不在本数组范围内或者不是 tick_spacing 的整数倍时返回 None
+    pub fn offset(&self, tick: i32, tick_spacing: u16) -> Option<usize> {
+        let spacing = tick_spacing as i32;
+        let delta = tick.checked_sub(self.start_tick_index)?;
+        if tick % spacing != 0 || delta < 0 || delta >= TICK_ARRAY_SIZE as i32 * spacing {
+            return None;
+        }
+        Some((delta / spacing) as usize)
+    }
+
+    pub fn tick_mut(&mut self, tick: i32, tick_spacing: u16) -> Option<&mut Tick> {
+        let offset = self.offset(tick, tick_spacing)?;
+        Some(&mut self.ticks[offset])
+    }
+}
+
+// 同 PoolState，两种零拷贝布局的长度写死，防止无意中改动
+const _: () = assert!(std::mem::size_of::<ClPool>() == 320);
+const _: () = assert!(std::mem::size_of::<TickArray>() == 2096);
+
+/// 集中流动性头寸，只在 [tick_lower, tick_upper) 区间内提供流动性
+/// PDA 种子：["position", cl_pool, owner, tick_lower (i32 LE), tick_upper (i32 LE)]
+#[account]
+pub struct Position {
+    pub pool: Pubkey,
+    pub owner: Pubkey,
+    pub tick_lower: i32,
+    pub tick_upper: i32,
+    pub liquidity: u128,
+    pub fee_growth_inside_a_last_x64: u128, // 上次结算时区间内的 A 手续费增长
+    pub fee_growth_inside_b_last_x64: u128,
+    pub fees_owed_a: u64,                   // 已结算但还没有领取的手续费
+    pub fees_owed_b: u64,
+    pub bump: u8,
+}
+
+impl Position {
+    pub const LEN: usize = 8 // discriminator
+        + 32    // pool
+        + 32    // owner
+        + 4     // tick_lower
+        + 4     // tick_upper
+        + 16    // liquidity
+        + 16    // fee_growth_inside_a_last_x64
+        + 16    // fee_growth_inside_b_last_x64
+        + 8     // fees_owed_a
+        + 8     // fees_owed_b
+        + 1;    // bump
+}
+
+/// 挂在恒定乘积池子上的限价单，输入代币托管在 `escrow` 中，由 pool_authority 控制
+/// PDA 种子：["limit_order", pool_state, owner, order_id (u64 LE)]；escrow 的种子：["order_escrow", limit_order]
+///
+/// 限价用整笔订单的最少输出表示：池子按当前储备把 amount_in 换成不少于 min_amount_out 时才能成交
+#[account]
+pub struct LimitOrder {
+    pub pool: Pubkey,
+    pub owner: Pubkey,
+    pub escrow: Pubkey,
+    pub order_id: u64,          // 由用户选择，同一个用户在同一个池子上不能重复
+    pub is_a_to_b: bool,        // true 表示卖出 A 买入 B
+    pub amount_in: u64,         // 托管的输入数量，成交时全部卖出
+    pub min_amount_out: u64,
+    pub keeper_bounty: u64,     // 存在订单账户中的 lamports，成交时付给执行的 keeper
+    pub created_ts: i64,
+    pub bump: u8,
+}
+
+impl LimitOrder {
+    pub const LEN: usize = 8 // discriminator
+        + 32    // pool
+        + 32    // owner
+        + 32    // escrow
+        + 8     // order_id
+        + 1     // is_a_to_b
+        + 8     // amount_in
+        + 8     // min_amount_out
+        + 8     // keeper_bounty
+        + 8     // created_ts
+        + 1;    // bump
+}
+
+/// Twamm 中某个到期时刻的汇总，槽位下标是 (expiry_ts / TWAMM_INTERVAL) % TWAMM_SLOTS
+///
+/// open_orders 为 0 之前槽位不能给别的到期时刻复用，过期订单提取时要用到这里的快照
+#[zero_copy]
+#[derive(Default, Debug, PartialEq, Eq)]
+pub struct TwammSlot {
+    pub sell_rate_ending_a: u128,           // 在 expiry_ts 到期的 A 卖出速率之和
+    pub sell_rate_ending_b: u128,
+    pub proceeds_per_rate_a_x64: u128,      // 到期时刻 Twamm.proceeds_per_rate_a_x64 的快照
+    pub proceeds_per_rate_b_x64: u128,
+    pub expiry_ts: u64,
+    pub open_orders: u32,                   // 在这个时刻到期、还没有提取的订单数量
+    pub padding: [u8; 4],
+}
+
+/// 池子的 TWAMM 长期订单簿，订单被拆成连续的无穷小兑换，在池子被访问时惰性结算
+/// PDA 种子：["twamm", pool_state]；金库种子：["twamm_vault", twamm, mint]，authority 是 pool_authority
+///
+/// 卖出速率的单位是每秒 2^-TWAMM_RATE_SHIFT 个代币；proceeds_per_rate 是每单位卖出速率累计换得的
+/// 另一种代币，Q64.64，按 2^128 回绕，订单只用差值计算收益
+#[account(zero_copy)]
+#[derive(Debug)]
+pub struct Twamm {
+    // --- 16 字节对齐 ---
+    pub sell_rate_a: u128,                  // 当前所有卖出 A 的订单速率之和
+    pub sell_rate_b: u128,
+    pub proceeds_per_rate_a_x64: u128,      // A 的卖家累计换得的 B
+    pub proceeds_per_rate_b_x64: u128,      // B 的卖家累计换得的 A
+    pub slots: [TwammSlot; TWAMM_SLOTS],
+
+    // --- 地址 ---
+    pub pool: Pubkey,
+    pub vault_a: Pubkey,
+    pub vault_b: Pubkey,
+
+    // --- 8 字节及以下 ---
+    pub last_virtual_order_ts: u64,         // 虚拟订单已经结算到的时间
+    pub bump: u8,
+    pub padding: [u8; 7],
+}
+
+impl Twamm {
+    pub const LEN: usize = 8 + std::mem::size_of::<Twamm>();
+
+    pub fn slot_index(expiry_ts: u64) -> usize {
+        (expiry_ts / TWAMM_INTERVAL) as usize % TWAMM_SLOTS
+    }
+}
+
+// 同 PoolState，布局长度写死
+const _: () = assert!(std::mem::size_of::<TwammSlot>() == 80);
+const _: () = assert!(std::mem::size_of::<Twamm>() == 5296);
+
+/// TWAMM 长期订单，在 [start_ts, expiry_ts) 内按固定速率卖出，输入代币存在 Twamm 金库中
+/// PDA 种子：["long_term_order", pool_state, owner, order_id (u64 LE)]
+#[account]
+pub struct LongTermOrder {
+    pub pool: Pubkey,
+    pub owner: Pubkey,
+    pub order_id: u64,          // 由用户选择，同一个用户在同一个池子上不能重复
+    pub is_a_to_b: bool,        // true 表示卖出 A 买入 B
+    pub sell_rate: u128,        // 每秒卖出 2^-TWAMM_RATE_SHIFT 个输入代币
+    pub amount_in: u64,         // 存入的输入数量
+    pub start_ts: u64,
+    pub expiry_ts: u64,
+    pub proceeds_per_rate_start_x64: u128, // 下单时对应方向的 proceeds_per_rate 快照
+    pub bump: u8,
+}
+
+impl LongTermOrder {
+    pub const LEN: usize = 8 // discriminator
+        + 32    // pool
+        + 32    // owner
+        + 8     // order_id
+        + 1     // is_a_to_b
+        + 16    // sell_rate
+        + 8     // amount_in
+        + 8     // start_ts
+        + 8     // expiry_ts
+        + 16    // proceeds_per_rate_start_x64
+        + 1;    // bump
+}
+
+/// 一个池子的批量拍卖，PDA 种子：["batch_auction", pool_state]
+/// 两个托管账户的种子：["batch_vault", batch_auction, mint]，authority 是 pool_authority
+///
+/// 当前批次从第一笔订单所在的 slot 开始收集 batch_duration_slots 个 slot，
+/// 窗口结束后由任何人调用 `settle_batch` 按统一价格结算，结算之后开始下一批
+#[account]
+pub struct BatchAuction {
+    pub pool: Pubkey,
+    pub vault_a: Pubkey,
+    pub vault_b: Pubkey,
+    pub batch_duration_slots: u64,
+    pub batch_id: u64,              // 当前正在收集的批次
+    pub batch_start_slot: u64,      // 当前批次第一笔订单所在的 slot，order_count 为 0 时没有意义
+    pub order_count: u8,            // 当前批次的订单数量，最多 MAX_BATCH_ORDERS
+    pub amount_a_in: u64,           // 当前批次卖出 A 的总量
+    pub amount_b_in: u64,
+    pub min_order_amount_a: u64,    // 卖出 A 的订单最少数量，防止用零头订单占满批次
+    pub min_order_amount_b: u64,
+    pub bump: u8,
+}
+
+impl BatchAuction {
+    pub const LEN: usize = 8 // discriminator
+        + 32    // pool
+        + 32    // vault_a
+        + 32    // vault_b
+        + 8     // batch_duration_slots
+        + 8     // batch_id
+        + 8     // batch_start_slot
+        + 1     // order_count
+        + 8     // amount_a_in
+        + 8     // amount_b_in
+        + 8     // min_order_amount_a
+        + 8     // min_order_amount_b
+        + 1;    // bump
+
+    /// 当前批次的收集窗口是否已经结束
+    pub fn window_closed(&self, slot: u64) -> bool {
+        self.order_count > 0 && slot >= self.batch_start_slot.saturating_add(self.batch_duration_slots)
+    }
+}
+
+/// 批量拍卖中的一笔兑换，输入代币已经存入 BatchAuction 的托管账户
+/// PDA 种子：["batch_order", batch_auction, owner, batch_id (u64 LE)]，每个用户每一批只有一笔
+///
+/// 结算时只记下所有者可以取回的数量，代币留在托管账户中，由所有者调用 `claim_batch_order` 取回
+#[account]
+pub struct BatchOrder {
+    pub pool: Pubkey,
+    pub owner: Pubkey,
+    pub batch_id: u64,
+    pub is_a_to_b: bool,
+    pub amount_in: u64,
+    pub min_amount_out: u64,    // 统一价格下的输出低于这个数量时不成交，结算时退回输入
+    pub settled: bool,          // 所在批次已经结算
+    pub claim_amount_a: u64,    // 结算之后可以取回的 A：卖出 B 成交换得的 A，或者卖出 A 没有成交退回的输入
+    pub claim_amount_b: u64,
+    pub bump: u8,
+}
+
+impl BatchOrder {
+    pub const LEN: usize = 8 // discriminator
+        + 32    // pool
+        + 32    // owner
+        + 8     // batch_id
+        + 1     // is_a_to_b
+        + 8     // amount_in
+        + 8     // min_amount_out
+        + 1     // settled
+        + 8     // claim_amount_a
+        + 8     // claim_amount_b
+        + 1;    // bump
+}
+
+/// 定投（DCA）金库：存入的输入代币每隔 cycle_interval 秒通过池子卖出 amount_per_cycle
+/// PDA 种子：["dca_vault", pool_state, owner, dca_id (u64 LE)]
+/// 两个代币账户的种子：["dca_input", dca_vault] 和 ["dca_output", dca_vault]，authority 都是 pool_authority
+///
+/// 每一期由任何人调用 `execute_dca` 执行，换得的代币留在 output_vault 中，所有者随时提取
+#[account]
+pub struct DcaVault {
+    pub pool: Pubkey,
+    pub owner: Pubkey,
+    pub input_vault: Pubkey,
+    pub output_vault: Pubkey,
+    pub dca_id: u64,            // 由用户选择，同一个用户在同一个池子上不能重复
+    pub is_a_to_b: bool,        // true 表示定期卖出 A 买入 B
+    pub amount_per_cycle: u64,  // 每期卖出的数量，最后一期卖出剩下的全部
+    pub min_amount_out_per_cycle: u64, // 卖出 amount_per_cycle 时的最少输出，最后一期按比例缩小
+    pub cycle_interval: i64,    // 两期之间的秒数
+    pub next_cycle_ts: i64,     // 下一期最早的执行时间
+    pub amount_sold: u64,       // 累计卖出
+    pub amount_bought: u64,     // 累计换得（包括已经提取的部分）
+    pub bump: u8,
+}
+
+impl DcaVault {
+    pub const LEN: usize = 8 // discriminator
+        + 32    // pool
+        + 32    // owner
+        + 32    // input_vault
+        + 32    // output_vault
+        + 8     // dca_id
+        + 1     // is_a_to_b
+        + 8     // amount_per_cycle
+        + 8     // min_amount_out_per_cycle
+        + 8     // cycle_interval
+        + 8     // next_cycle_ts
+        + 8     // amount_sold
+        + 8     // amount_bought
+        + 1;    // bump
+
+    /// 这一期卖出的数量和对应的最少输出：最后一期不足 amount_per_cycle 时卖出剩下的全部，最少输出按比例缩小
+    pub fn cycle_amounts(&self, remaining: u64) -> Option<(u64, u64)> {
+        let amount_in = remaining.min(self.amount_per_cycle);
+        let min_amount_out = (self.min_amount_out_per_cycle as u128)
+            .checked_mul(amount_in as u128)?
+            .checked_div(self.amount_per_cycle as u128)?;
+        Some((amount_in, u64::try_from(min_amount_out).ok()?))
+    }
+
+    /// 在 now 执行一期之后的下一期时间：保持和 next_cycle_ts 对齐，错过的期数直接跳过，不会集中补卖
+    pub fn next_cycle_after(&self, now: i64) -> Option<i64> {
+        let missed = now.checked_sub(self.next_cycle_ts)?.checked_div(self.cycle_interval)?;
+        self.next_cycle_ts
+            .checked_add(missed.checked_add(1)?.checked_mul(self.cycle_interval)?)
+    }
+}
+
+/// 池子注册表的头部，记录已经登记的池子总数
+/// PDA 种子：["pool_registry"]，第一次创建池子时创建
+///
+/// 池子按登记顺序写入分页 `PoolRegistryPage`，第 i 个池子在第 i / REGISTRY_PAGE_SIZE 页
+#[account]
+pub struct PoolRegistry {
+    pub pool_count: u64,
+    pub bump: u8,
+}
+
+impl PoolRegistry {
+    pub const LEN: usize = 8 // discriminator
+        + 8     // pool_count
+        + 1;    // bump
+
+    /// 下一个池子所在的分页
+    pub fn next_page_index(&self) -> u64 {
+        self.pool_count / REGISTRY_PAGE_SIZE as u64
+    }
+
+    /// 把池子追加到当前分页，page 必须是 next_page_index 对应的分页
+    pub fn append(&mut self, page: &mut PoolRegistryPage, entry: RegistryEntry) -> Option<()> {
+        if page.entries.len() >= REGISTRY_PAGE_SIZE {
+            return None;
+        }
+        page.page_index = self.next_page_index();
+        page.entries.push(entry);
+        self.pool_count = self.pool_count.checked_add(1)?;
+        Some(())
+    }
+}
+
+/// 注册表中的一个池子
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub struct RegistryEntry {
+    pub pool: Pubkey,
+    pub mint_a: Pubkey,
+    pub mint_b: Pubkey,
+    pub fee_numerator: u64,     // 登记时的费率，之后管理员修改费率不会更新这里
+    pub fee_denominator: u64,
+}
+
+impl RegistryEntry {
+    pub const LEN: usize = 32 * 3 + 8 + 8;
+}
+
+/// 注册表的一页，最多 REGISTRY_PAGE_SIZE 个池子
+/// PDA 种子：["pool_registry_page", page_index (u64 LE)]，写入这一页的第一个池子时创建
+#[account]
+pub struct PoolRegistryPage {
+    pub page_index: u64,
+    pub entries: Vec<RegistryEntry>,
+}
+
+impl PoolRegistryPage {
+    pub const LEN: usize = 8 // discriminator
+        + 8     // page_index
+        + 4 + RegistryEntry::LEN * REGISTRY_PAGE_SIZE; // entries
+}
+
+/// `get_registry_page` 指令的返回值
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct RegistryPage {
+    /// 已经登记的池子总数，共有 pool_count.div_ceil(REGISTRY_PAGE_SIZE) 页
+    pub pool_count: u64,
+    pub page_index: u64,
+    pub entries: Vec<RegistryEntry>,
+}
+
+impl RegistryPage {
+    pub const MAX_LEN: usize = 8 + 8 + 4 + RegistryEntry::LEN * REGISTRY_PAGE_SIZE;
+}
+
+// return data 最多 1024 字节
+const _: () = assert!(RegistryPage::MAX_LEN <= 1024);
+
+/// 许可池子的守门人，由池子管理员指定，负责发放和撤销 AccessPass
+/// PDA 种子：["pool_gatekeeper", pool_state]，第一次开启许可模式时创建
+#[account]
+pub struct PoolGatekeeper {
+    pub pool: Pubkey,
+    pub authority: Pubkey,      // 守门人地址，可以是 KYC 服务商的多签
+    pub bump: u8,
+}
+
+impl PoolGatekeeper {
+    pub const LEN: usize = 8 // discriminator
+        + 32    // pool
+        + 32    // authority
+        + 1;    // bump
+}
+
+/// 用户在许可池子中交易和提供流动性的凭证，由守门人发放，撤销时直接关闭，更换守门人之后失效
+/// PDA 种子：["access_pass", pool_state, user]
+#[account]
+pub struct AccessPass {
+    pub pool: Pubkey,
+    pub user: Pubkey,
+    pub issuer: Pubkey,         // 发放时的守门人，更换守门人之后旧凭证失效
+    pub issued_ts: i64,
+    pub expires_ts: i64,        // 过期时间（unix 时间戳），0 表示不过期
+    pub bump: u8,
+}
+
+impl AccessPass {
+    pub const LEN: usize = 8 // discriminator
+        + 32    // pool
+        + 32    // user
+        + 32    // issuer
+        + 8     // issued_ts
+        + 8     // expires_ts
+        + 1;    // bump
+
+    /// 在 now 时刻是否还有效
+    pub fn is_valid(&self, now: i64) -> bool {
+        self.expires_ts == 0 || now < self.expires_ts
     }
 }
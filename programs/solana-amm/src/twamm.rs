@@ -0,0 +1,294 @@
+//! TWAMM 长期订单的虚拟成交
+//!
+//! 长期订单在 [start_ts, expiry_ts) 内以固定速率卖出，相当于无穷多笔无穷小的兑换。
+//! 链上不逐笔执行，而是在池子被访问（swap、add_liquidity、remove_liquidity、fill_orders、
+//! execute_dca、settle_batch、claim_lp_lock_fees、execute_twamm 以及长期订单自己的指令）时，把上次结算到现在的时间按 TWAMM_INTERVAL
+//! 的边界切成若干段，每段：
+//!
+//! 1. 用这段开始时的储备更新 TWAP，复用 `block_timestamp_last` 的记账；
+//! 2. 两个方向的卖单先按现货价格相互抵消，多出的一方按恒定乘积曲线和池子兑换，收取池子费率；
+//! 3. 换得的代币按卖出速率累加到 proceeds_per_rate，订单提取时用速率乘以差值得到收益。
+//!
+//! 订单都在段的边界上到期，到期的速率在边界上扣除并记下快照。所有订单都在
+//! TWAMM_SLOTS 个间隔内到期，一次结算最多 TWAMM_SLOTS + 1 段。
+//!
+//! 和 swap 一样，每段成交之后的价格不能偏离 TWAP 太多，卖进池子的一侧不能超过储备上限
+//!（`pool_swap::check_pool_bounds`）。超出时结算暂停在这一段的开始，池子的其他指令照常执行，之后价格回到范围内再继续；
+//! 订单的进度只计到 `last_virtual_order_ts`，暂停期间没有卖出的部分撤单时全额退回。
+//!
+//! 舍入方向对金库有利：每段实际卖出的数量向下取整，订单记账的卖出数量向上取整，
+//! 订单的收益向下取整，Twamm 金库里的代币总是够所有订单提取。
+
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, TokenAccount, Transfer};
+
+use crate::clmm::mul_div;
+use crate::errors::AmmError;
+use crate::math;
+use crate::pool_swap;
+use crate::state::{LongTermOrder, PoolState, Twamm, TwammSlot, TWAMM_INTERVAL};
+
+/// 卖出速率的小数位数，速率的单位是每秒 2^-TWAMM_RATE_SHIFT 个代币
+pub const TWAMM_RATE_SHIFT: u32 = 32;
+
+const Q64: u128 = 1 << 64;
+
+/// 一段时间内虚拟订单和池子成交的结果
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct VirtualTrade {
+    pub proceeds_a: u64,    // A 的卖家换得的 B
+    pub proceeds_b: u64,    // B 的卖家换得的 A
+    pub reserve_a: u64,     // 成交后的储备
+    pub reserve_b: u64,
+}
+
+/// 两个方向分别卖出 amount_a、amount_b，和储备为 (reserve_a, reserve_b) 的池子成交
+///
+/// 相反方向的卖单先按现货价格抵消，多出的一方按恒定乘积曲线和池子兑换。
+/// 池子还没有流动性时不成交，卖出的代币留在 Twamm 金库中。
+pub fn execute_virtual_trade(
+    amount_a: u64,
+    amount_b: u64,
+    reserve_a: u64,
+    reserve_b: u64,
+    fee_numerator: u64,
+    fee_denominator: u64,
+) -> Option<VirtualTrade> {
+    if reserve_a == 0 || reserve_b == 0 {
+        return Some(VirtualTrade { reserve_a, reserve_b, ..Default::default() });
+    }
+
+    // 两边都乘以 reserve_a * reserve_b 之后比较按现货价格折算的价值
+    let value_a = amount_a as u128 * reserve_b as u128;
+    let value_b = amount_b as u128 * reserve_a as u128;
+    if value_a >= value_b {
+        // A 多出：B 的卖单全部和 A 抵消，剩下的 A 卖给池子
+        let matched_a = (value_b / reserve_b as u128) as u64;
+        let net_a = amount_a - matched_a;
+        let (_, amount_out) =
+            math::calculate_swap_amount_out(net_a, reserve_a, reserve_b, fee_numerator, fee_denominator)?;
+        Some(VirtualTrade {
+            proceeds_a: amount_b.checked_add(amount_out)?,
+            proceeds_b: matched_a,
+            reserve_a: reserve_a.checked_add(net_a)?,
+            reserve_b: reserve_b - amount_out,
+        })
+    } else {
+        let matched_b = (value_a / reserve_a as u128) as u64;
+        let net_b = amount_b - matched_b;
+        let (_, amount_out) =
+            math::calculate_swap_amount_out(net_b, reserve_b, reserve_a, fee_numerator, fee_denominator)?;
+        Some(VirtualTrade {
+            proceeds_a: matched_b,
+            proceeds_b: amount_a.checked_add(amount_out)?,
+            reserve_a: reserve_a - amount_out,
+            reserve_b: reserve_b.checked_add(net_b)?,
+        })
+    }
+}
+
+// rate * dt 个代币，向下取整
+fn amount_sold(sell_rate: u128, dt: u64) -> Option<u64> {
+    u64::try_from(sell_rate.checked_mul(dt as u128)? >> TWAMM_RATE_SHIFT).ok()
+}
+
+// 把 proceeds 按卖出速率累加到 proceeds_per_rate 上，按 2^128 回绕
+fn accrue_proceeds(proceeds_per_rate: u128, proceeds: u64, sell_rate: u128) -> Option<u128> {
+    if sell_rate == 0 {
+        return Some(proceeds_per_rate);
+    }
+    Some(proceeds_per_rate.wrapping_add(mul_div(proceeds as u128, Q64, sell_rate, false)?))
+}
+
+/// 把虚拟订单结算到 `now`，返回结算后的储备
+///
+/// 两个方向都没有卖单时直接跳到 `now`：这时不会有还没到期的槽位。
+/// 某一段成交之后价格偏离 TWAP 太多或者储备超过上限时停在这一段的开始，`last_virtual_order_ts` 早于 `now`。
+pub fn execute_virtual_orders(
+    pool_state: &mut PoolState,
+    twamm: &mut Twamm,
+    mut reserve_a: u64,
+    mut reserve_b: u64,
+    now: u64,
+) -> Option<(u64, u64)> {
+    let mut t = twamm.last_virtual_order_ts;
+    while t < now {
+        let next = if twamm.sell_rate_a == 0 && twamm.sell_rate_b == 0 {
+            now
+        } else {
+            now.min((t / TWAMM_INTERVAL + 1) * TWAMM_INTERVAL)
+        };
+
+        // 这段时间内储备按段开始时计入 TWAP
+        if next > pool_state.block_timestamp_last {
+            math::update_twap(pool_state, reserve_a, reserve_b, next);
+        }
+
+        let trade = execute_virtual_trade(
+            amount_sold(twamm.sell_rate_a, next - t)?,
+            amount_sold(twamm.sell_rate_b, next - t)?,
+            reserve_a,
+            reserve_b,
+            pool_state.fee_numerator,
+            pool_state.fee_denominator,
+        )?;
+        if pool_swap::check_pool_bounds(pool_state, reserve_a, reserve_b, trade.reserve_a, trade.reserve_b).is_err() {
+            break;
+        }
+        twamm.proceeds_per_rate_a_x64 =
+            accrue_proceeds(twamm.proceeds_per_rate_a_x64, trade.proceeds_a, twamm.sell_rate_a)?;
+        twamm.proceeds_per_rate_b_x64 =
+            accrue_proceeds(twamm.proceeds_per_rate_b_x64, trade.proceeds_b, twamm.sell_rate_b)?;
+        reserve_a = trade.reserve_a;
+        reserve_b = trade.reserve_b;
+
+        // 在这个边界到期的订单停止卖出，记下快照供提取时使用
+        let slot = &mut twamm.slots[Twamm::slot_index(next)];
+        if slot.expiry_ts == next && slot.open_orders > 0 {
+            twamm.sell_rate_a = twamm.sell_rate_a.checked_sub(slot.sell_rate_ending_a)?;
+            twamm.sell_rate_b = twamm.sell_rate_b.checked_sub(slot.sell_rate_ending_b)?;
+            slot.sell_rate_ending_a = 0;
+            slot.sell_rate_ending_b = 0;
+            slot.proceeds_per_rate_a_x64 = twamm.proceeds_per_rate_a_x64;
+            slot.proceeds_per_rate_b_x64 = twamm.proceeds_per_rate_b_x64;
+        }
+        t = next;
+    }
+    twamm.last_virtual_order_ts = t;
+    Some((reserve_a, reserve_b))
+}
+
+/// 把卖出速率为 sell_rate、在 expiry_ts 到期的订单加入 Twamm，返回下单时对应方向的 proceeds_per_rate
+///
+/// 到期槽位上更早的到期时刻还有订单没有提取时不能复用，返回 None
+pub fn open_long_term_order(twamm: &mut Twamm, is_a_to_b: bool, sell_rate: u128, expiry_ts: u64) -> Option<u128> {
+    let slot = &mut twamm.slots[Twamm::slot_index(expiry_ts)];
+    if slot.expiry_ts != expiry_ts {
+        if slot.open_orders > 0 {
+            return None;
+        }
+        *slot = TwammSlot { expiry_ts, ..Default::default() };
+    }
+    slot.open_orders = slot.open_orders.checked_add(1)?;
+    if is_a_to_b {
+        slot.sell_rate_ending_a = slot.sell_rate_ending_a.checked_add(sell_rate)?;
+        twamm.sell_rate_a = twamm.sell_rate_a.checked_add(sell_rate)?;
+        Some(twamm.proceeds_per_rate_a_x64)
+    } else {
+        slot.sell_rate_ending_b = slot.sell_rate_ending_b.checked_add(sell_rate)?;
+        twamm.sell_rate_b = twamm.sell_rate_b.checked_add(sell_rate)?;
+        Some(twamm.proceeds_per_rate_b_x64)
+    }
+}
+
+/// 订单提取后从 Twamm 中移除，结算还没到到期时刻时同时扣除它的卖出速率
+pub fn close_long_term_order(twamm: &mut Twamm, order: &LongTermOrder, now: u64) -> Option<()> {
+    let settled_ts = now.min(twamm.last_virtual_order_ts);
+    let slot = &mut twamm.slots[Twamm::slot_index(order.expiry_ts)];
+    slot.open_orders = slot.open_orders.checked_sub(1)?;
+    if settled_ts < order.expiry_ts {
+        if order.is_a_to_b {
+            slot.sell_rate_ending_a = slot.sell_rate_ending_a.checked_sub(order.sell_rate)?;
+            twamm.sell_rate_a = twamm.sell_rate_a.checked_sub(order.sell_rate)?;
+        } else {
+            slot.sell_rate_ending_b = slot.sell_rate_ending_b.checked_sub(order.sell_rate)?;
+            twamm.sell_rate_b = twamm.sell_rate_b.checked_sub(order.sell_rate)?;
+        }
+    }
+    Some(())
+}
+
+/// 长期订单到 `now` 为止的进度：(已卖出的输入, 换得的输出)
+///
+/// 调用前先把 Twamm 结算到 `now`；结算因为价格偏离暂停时只计到 `last_virtual_order_ts`
+pub fn long_term_order_progress(order: &LongTermOrder, twamm: &Twamm, now: u64) -> Option<(u64, u64)> {
+    let now = now.min(twamm.last_virtual_order_ts);
+    let elapsed = now.min(order.expiry_ts).saturating_sub(order.start_ts);
+    let sold = mul_div(order.sell_rate, elapsed as u128, 1 << TWAMM_RATE_SHIFT, true)?.min(order.amount_in as u128);
+
+    let (current, at_expiry) = {
+        let slot = &twamm.slots[Twamm::slot_index(order.expiry_ts)];
+        if order.is_a_to_b {
+            (twamm.proceeds_per_rate_a_x64, slot.proceeds_per_rate_a_x64)
+        } else {
+            (twamm.proceeds_per_rate_b_x64, slot.proceeds_per_rate_b_x64)
+        }
+    };
+    let proceeds_per_rate = if now >= order.expiry_ts { at_expiry } else { current };
+    let delta = proceeds_per_rate.wrapping_sub(order.proceeds_per_rate_start_x64);
+    let amount_out = u64::try_from(mul_div(order.sell_rate, delta, Q64, false)?).ok()?;
+    Some((sold as u64, amount_out))
+}
+
+/// `settle_pool` 用到的账户；池子没有开启 TWAMM 时 Twamm 和它的金库可以不传
+pub struct TwammSettleAccounts<'a, 'info> {
+    pub pool_state: &'a AccountLoader<'info, PoolState>,
+    pub twamm: Option<&'a AccountLoader<'info, Twamm>>,
+    pub twamm_vault_a: Option<&'a Account<'info, TokenAccount>>,
+    pub twamm_vault_b: Option<&'a Account<'info, TokenAccount>>,
+    pub token_a_vault: &'a Account<'info, TokenAccount>,
+    pub token_b_vault: &'a Account<'info, TokenAccount>,
+    pub pool_authority: AccountInfo<'info>,
+    pub token_program: AccountInfo<'info>,
+}
+
+/// 把池子上的虚拟订单结算到 `now`，储备的净变化在 Twamm 金库和池子金库之间划转
+///
+/// 池子没有开启 TWAMM 时什么都不做。返回是否划转了代币：划转之后池子金库的缓存余额已经过期，
+/// 调用者需要先 reload 再读取储备。
+pub fn settle_pool(accounts: TwammSettleAccounts, now: u64) -> Result<bool> {
+    let mut pool_state = accounts.pool_state.load_mut()?;
+    let Some(twamm_key) = pool_state.twamm() else {
+        return Ok(false);
+    };
+    let (Some(twamm), Some(twamm_vault_a), Some(twamm_vault_b)) =
+        (accounts.twamm, accounts.twamm_vault_a, accounts.twamm_vault_b)
+    else {
+        return err!(AmmError::TwammAccountsMissing);
+    };
+    require_keys_eq!(twamm.key(), twamm_key, AmmError::InvalidTwamm);
+    let mut twamm = twamm.load_mut()?;
+    require!(
+        twamm.vault_a == twamm_vault_a.key() && twamm.vault_b == twamm_vault_b.key(),
+        AmmError::InvalidTwamm
+    );
+
+    let reserve_a = accounts.token_a_vault.amount;
+    let reserve_b = accounts.token_b_vault.amount;
+    let (reserve_a_after, reserve_b_after) =
+        execute_virtual_orders(&mut pool_state, &mut twamm, reserve_a, reserve_b, now)
+            .ok_or(AmmError::MathOverflow)?;
+
+    let auth_bump = pool_state.auth_bump;
+    let seeds: &[&[u8]] = &[
+        b"authority",
+        &[auth_bump],
+    ];
+    let signer_seeds = &[seeds];
+    let mut transferred = false;
+    for (twamm_vault, pool_vault, before, after) in [
+        (twamm_vault_a, accounts.token_a_vault, reserve_a, reserve_a_after),
+        (twamm_vault_b, accounts.token_b_vault, reserve_b, reserve_b_after),
+    ] {
+        let (from, to) = if after > before {
+            (twamm_vault.to_account_info(), pool_vault.to_account_info())
+        } else {
+            (pool_vault.to_account_info(), twamm_vault.to_account_info())
+        };
+        let amount = after.abs_diff(before);
+        if amount == 0 {
+            continue;
+        }
+        let cpi_accounts = Transfer {
+            from,
+            to,
+            authority: accounts.pool_authority.clone(),
+        };
+        token::transfer(
+            CpiContext::new_with_signer(accounts.token_program.clone(), cpi_accounts, signer_seeds),
+            amount,
+        )?;
+        transferred = true;
+    }
+    Ok(transferred)
+}
@@ -0,0 +1,101 @@
+mod common;
+
+use common::*;
+use solana_amm::math::{self, MINIMUM_LIQUIDITY};
+use solana_amm::AmmError;
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn first_deposit_locks_minimum_liquidity() {
+    let pool = TestPool::initialized();
+
+    let (amount_a, amount_b) = (1_000_000_000, 4_000_000_000);
+    assert_success(&pool.add_liquidity(amount_a, amount_b));
+
+    let initial = math::sqrt_product_u64(amount_a, amount_b).unwrap();
+    assert_eq!(pool.token_amount(&pool.user_lp), initial - MINIMUM_LIQUIDITY);
+    assert_eq!(pool.token_amount(&pool.black_hole_lp), MINIMUM_LIQUIDITY);
+    assert_eq!(pool.lp_supply(), initial);
+    assert_eq!(pool.reserves(), (amount_a, amount_b));
+    assert_eq!(pool.pool_state().k_last, amount_a as u128 * amount_b as u128);
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn subsequent_deposit_mints_by_smaller_ratio() {
+    let pool = TestPool::with_liquidity(1_000_000_000, 2_000_000_000);
+
+    let supply = pool.lp_supply();
+    let user_lp_before = pool.token_amount(&pool.user_lp);
+    // B 多给了一点，LP 按 A 的比例计算
+    let (amount_a, amount_b) = (100_000_000, 250_000_000);
+    let expected =
+        math::calculate_deposit_liquidity(amount_a, amount_b, 1_000_000_000, 2_000_000_000, supply).unwrap();
+    assert_eq!(expected, (amount_a as u128 * supply as u128 / 1_000_000_000) as u64);
+
+    assert_success(&pool.add_liquidity(amount_a, amount_b));
+
+    assert_eq!(pool.token_amount(&pool.user_lp), user_lp_before + expected);
+    assert_eq!(pool.lp_supply(), supply + expected);
+    assert_eq!(pool.reserves(), (1_100_000_000, 2_250_000_000));
+    assert_eq!(pool.pool_state().k_last, 1_100_000_000u128 * 2_250_000_000);
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn first_deposit_rejects_tiny_liquidity() {
+    let pool = TestPool::initialized();
+
+    assert_error(&pool.add_liquidity(1000, 1000), AmmError::InitialLiquidityTooLow);
+    assert_success(&pool.add_liquidity(1001, 1001));
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn add_liquidity_rejects_expired_deadline() {
+    let pool = TestPool::initialized();
+    let now = START_TIMESTAMP as i64;
+
+    assert_error(&pool.add_liquidity_before(now - 1, 1_000_000, 1_000_000), AmmError::Expired);
+    assert_eq!(pool.lp_supply(), 0);
+    assert_success(&pool.add_liquidity_before(now + 60, 1_000_000, 1_000_000));
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn add_liquidity_rejects_foreign_lp_mint() {
+    let mut pool = TestPool::initialized();
+
+    let fake_mint = anchor_lang::prelude::Pubkey::new_unique();
+    pool.set_account(&fake_mint, mint_account(9));
+    let mut accounts = pool.add_liquidity_accounts();
+    accounts.lp_mint = fake_mint;
+    accounts.user_lp_token_ATA =
+        anchor_spl::associated_token::get_associated_token_address(&pool.user, &fake_mint);
+
+    assert_error(&pool.add_liquidity_with(accounts, 1_000_000, 1_000_000), AmmError::InvalidLpMint);
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn add_liquidity_rejects_foreign_protocol_fee_recipient() {
+    let mut pool = TestPool::initialized();
+
+    let (lp_mint, stranger) = (pool.lp_mint, anchor_lang::prelude::Pubkey::new_unique());
+    let recipient = pool.create_token_account(&lp_mint, &stranger, 0);
+    let mut accounts = pool.add_liquidity_accounts();
+    accounts.protocol_fee_recipient = recipient;
+
+    assert_error(&pool.add_liquidity_with(accounts, 1_000_000, 1_000_000), AmmError::InvalidUserToken);
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn add_liquidity_rejects_user_token_with_wrong_mint() {
+    let pool = TestPool::initialized();
+
+    let mut accounts = pool.add_liquidity_accounts();
+    accounts.user_token_b = pool.user_token_a;
+
+    assert_error(&pool.add_liquidity_with(accounts, 1_000_000, 1_000_000), AmmError::InvalidUserToken);
+}
@@ -0,0 +1,222 @@
+mod common;
+
+use common::*;
+use solana_amm::{math, AmmError};
+
+const RESERVE_A: u64 = 1_000_000_000;
+const RESERVE_B: u64 = 2_000_000_000;
+const ORDER_IN: u64 = 1_000_000;
+const WINDOW: u64 = 4;
+const MIN_ORDER: u64 = 1_000;
+
+fn pool_with_batch_auction() -> TestPool {
+    let pool = TestPool::with_liquidity(RESERVE_A, RESERVE_B);
+    assert_success(&pool.init_batch_auction(&pool.admin, WINDOW, MIN_ORDER));
+    pool
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn batch_mode_disables_immediate_swaps() {
+    let pool = TestPool::with_liquidity(RESERVE_A, RESERVE_B);
+
+    assert_error(&pool.init_batch_auction(&pool.user, WINDOW, MIN_ORDER), AmmError::Unauthorized);
+    assert_error(&pool.init_batch_auction(&pool.admin, 0, MIN_ORDER), AmmError::InvalidBatchOrder);
+    assert_error(&pool.init_batch_auction(&pool.admin, WINDOW, 0), AmmError::InvalidBatchOrder);
+    assert_success(&pool.swap(ORDER_IN, true, 0));
+    assert_success(&pool.init_batch_auction(&pool.admin, WINDOW, MIN_ORDER));
+
+    assert!(pool.pool_state().batch_mode());
+    assert_error(&pool.swap(ORDER_IN, true, 0), AmmError::BatchAuctionOnly);
+    assert_success(&pool.place_order(1, ORDER_IN, true, 1, 0));
+    assert_error(&pool.fill_orders(&pool.admin, &[(1, true)]), AmmError::BatchAuctionOnly);
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn batch_settles_all_same_direction_orders_at_one_price() {
+    let mut pool = pool_with_batch_auction();
+    let (first, second) = (pool.trader(), pool.new_trader());
+    let before = (pool.token_amount(&first.2), pool.token_amount(&second.2));
+
+    // 同一批里先提交的没有任何优势
+    assert_success(&pool.submit_batch_swap(first, ORDER_IN, true, 0));
+    assert_success(&pool.submit_batch_swap(second, 3 * ORDER_IN, true, 0));
+    assert_eq!(pool.token_amount(&pool.batch_vault_address(&pool.mint_a)), 4 * ORDER_IN);
+    assert_error(&pool.settle_batch(&[first, second]), AmmError::BatchNotReady);
+
+    pool.advance_slot(WINDOW);
+    let (_, out) =
+        math::calculate_swap_amount_out(4 * ORDER_IN, RESERVE_A, RESERVE_B, FEE_NUMERATOR, FEE_DENOMINATOR).unwrap();
+    assert_error(&pool.claim_batch_order(first, 0), AmmError::BatchNotReady);
+    assert_success(&pool.settle_batch(&[first, second]));
+
+    // 结算只记下数量，输出留在托管账户中等所有者取回
+    assert_eq!(pool.reserves(), (RESERVE_A + 4 * ORDER_IN, RESERVE_B - out));
+    assert_eq!(pool.token_amount(&first.2), before.0);
+    let order = pool.batch_order(&first.0, 0).unwrap();
+    assert!(order.settled);
+    assert_eq!((order.claim_amount_a, order.claim_amount_b), (0, out / 4));
+    let batch_auction = pool.batch_auction();
+    assert_eq!((batch_auction.batch_id, batch_auction.order_count), (1, 0));
+
+    assert_error(&pool.claim_batch_order((second.0, first.1, first.2), 0), AmmError::InvalidUserToken);
+    assert_success(&pool.claim_batch_order(first, 0));
+    assert_success(&pool.claim_batch_order(second, 0));
+    assert_eq!(pool.token_amount(&first.2) - before.0, out / 4);
+    assert_eq!(pool.token_amount(&second.2) - before.1, out * 3 / 4);
+    assert!(pool.batch_order(&first.0, 0).is_none());
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn admin_can_turn_batch_mode_off_and_back_on() {
+    let mut pool = pool_with_batch_auction();
+    let (trader, late) = (pool.trader(), pool.new_trader());
+    assert_success(&pool.submit_batch_swap(trader, ORDER_IN, true, 0));
+
+    assert_error(&pool.set_batch_mode(&pool.user, false), AmmError::Unauthorized);
+    assert_success(&pool.set_batch_mode(&pool.admin, false));
+    assert!(!pool.pool_state().batch_mode());
+    assert_success(&pool.swap(ORDER_IN, true, 0));
+    assert_error(&pool.submit_batch_swap(late, ORDER_IN, true, 0), AmmError::BatchAuctionDisabled);
+
+    // 关闭之前提交的订单仍然可以结算
+    pool.advance_slot(WINDOW);
+    assert_success(&pool.settle_batch(&[trader]));
+    assert_success(&pool.claim_batch_order(trader, 0));
+    assert!(pool.batch_order(&trader.0, 0).is_none());
+
+    assert_success(&pool.set_batch_mode(&pool.admin, true));
+    assert_error(&pool.swap(ORDER_IN, true, 0), AmmError::BatchAuctionOnly);
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn settle_requires_every_order_of_the_batch() {
+    let mut pool = pool_with_batch_auction();
+    let (first, second) = (pool.trader(), pool.new_trader());
+    assert_success(&pool.submit_batch_swap(first, ORDER_IN, true, 0));
+    assert_success(&pool.submit_batch_swap(second, ORDER_IN, false, 0));
+    pool.advance_slot(WINDOW);
+
+    assert_error(&pool.settle_batch(&[first]), AmmError::InvalidBatchOrder);
+    assert_error(&pool.settle_batch(&[first, first]), AmmError::InvalidBatchOrder);
+    assert_success(&pool.settle_batch(&[second, first]));
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn orders_below_limit_are_refunded() {
+    let mut pool = pool_with_batch_auction();
+    let trader = pool.trader();
+    let before_a = pool.token_amount(&trader.1);
+
+    // 价格约 2 B/A，要求 2.5 B/A
+    assert_success(&pool.submit_batch_swap(trader, ORDER_IN, true, ORDER_IN * 5 / 2));
+    pool.advance_slot(WINDOW);
+    assert_success(&pool.settle_batch(&[trader]));
+    assert_success(&pool.claim_batch_order(trader, 0));
+
+    assert_eq!(pool.token_amount(&trader.1), before_a);
+    assert_eq!(pool.reserves(), (RESERVE_A, RESERVE_B));
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn orders_beyond_price_deviation_or_cap_are_refunded() {
+    let mut pool = pool_with_batch_auction();
+    let (small, large) = (pool.trader(), pool.new_trader());
+    let before_a = pool.token_amount(&large.1);
+
+    // 10% 的储备金会把价格推动约 19%，只有大单被退回
+    assert_success(&pool.set_max_price_deviation(&pool.admin, 100));
+    pool.advance_clock(60);
+    assert_success(&pool.submit_batch_swap(small, ORDER_IN, true, 0));
+    assert_success(&pool.submit_batch_swap(large, RESERVE_A / 10, true, 0));
+    pool.advance_slot(WINDOW);
+    assert_success(&pool.settle_batch(&[small, large]));
+    assert_success(&pool.claim_batch_order(large, 0));
+    assert_eq!(pool.token_amount(&large.1), before_a);
+    assert_eq!(pool.reserves().0, RESERVE_A + ORDER_IN);
+
+    // 储备上限同样适用于批量结算
+    assert_success(&pool.set_deposit_caps(&pool.admin, RESERVE_A + ORDER_IN, 0, 0, 0));
+    assert_success(&pool.submit_batch_swap(small, ORDER_IN, true, 0));
+    pool.advance_slot(WINDOW);
+    assert_success(&pool.settle_batch(&[small]));
+    assert_eq!(pool.reserves().0, RESERVE_A + ORDER_IN);
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn closed_window_waits_for_settlement() {
+    let mut pool = pool_with_batch_auction();
+    let (first, second) = (pool.trader(), pool.new_trader());
+    assert_error(&pool.submit_batch_swap(first, 0, true, 0), AmmError::InvalidBatchOrder);
+    // 零头订单不能占用批次的位置
+    assert_error(&pool.submit_batch_swap(first, MIN_ORDER - 1, false, 0), AmmError::InvalidBatchOrder);
+    assert_eq!(pool.batch_auction().min_order_amount_b, MIN_ORDER);
+    assert_success(&pool.submit_batch_swap(first, ORDER_IN, true, 0));
+
+    pool.advance_slot(WINDOW);
+    assert_error(&pool.submit_batch_swap(second, ORDER_IN, true, 0), AmmError::BatchClosed);
+    assert_success(&pool.settle_batch(&[first]));
+
+    // 下一批从新的第一笔订单开始计算窗口
+    assert_success(&pool.submit_batch_swap(second, ORDER_IN, true, 0));
+    let batch_auction = pool.batch_auction();
+    assert_eq!(batch_auction.batch_id, 1);
+    assert_eq!(batch_auction.amount_a_in, ORDER_IN);
+    assert!(pool.batch_order(&second.0, 1).is_some());
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn closed_owner_account_does_not_block_the_batch() {
+    let mut pool = pool_with_batch_auction();
+    let (gone, other) = (pool.new_trader(), pool.trader());
+    let before_b = pool.token_amount(&other.2);
+    assert_success(&pool.submit_batch_swap(gone, ORDER_IN, true, 0));
+    assert_success(&pool.submit_batch_swap(other, ORDER_IN, true, 0));
+
+    // 一个所有者关闭了自己的代币账户，批次照常结算，其他人照常取回
+    pool.remove_account(&gone.1);
+    pool.remove_account(&gone.2);
+    pool.advance_slot(WINDOW);
+    assert_success(&pool.settle_batch(&[gone, other]));
+    assert_success(&pool.claim_batch_order(other, 0));
+    assert!(pool.token_amount(&other.2) > before_b);
+
+    // 关闭账户的所有者之后换一个账户取回
+    let (mint_a, mint_b) = (pool.mint_a, pool.mint_b);
+    let token_a = pool.create_token_account(&mint_a, &gone.0, 0);
+    let token_b = pool.create_token_account(&mint_b, &gone.0, 0);
+    assert_success(&pool.claim_batch_order((gone.0, token_a, token_b), 0));
+    assert!(pool.token_amount(&token_b) > 0);
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn orders_can_be_cancelled_until_the_window_closes() {
+    let mut pool = pool_with_batch_auction();
+    let (first, second) = (pool.trader(), pool.new_trader());
+    let before_a = pool.token_amount(&first.1);
+    assert_success(&pool.submit_batch_swap(first, ORDER_IN, true, 0));
+    assert_success(&pool.submit_batch_swap(second, ORDER_IN, false, 0));
+
+    assert_error(&pool.cancel_batch_order((second.0, first.1, first.2), true), AmmError::InvalidBatchOrder);
+    assert_success(&pool.cancel_batch_order(first, true));
+    assert_eq!(pool.token_amount(&first.1), before_a);
+    assert!(pool.batch_order(&first.0, 0).is_none());
+    let batch_auction = pool.batch_auction();
+    assert_eq!((batch_auction.order_count, batch_auction.amount_a_in), (1, 0));
+
+    // 窗口结束之后订单已经确定，只能结算；关闭批量拍卖之后可以撤销
+    pool.advance_slot(WINDOW);
+    assert_error(&pool.cancel_batch_order(second, false), AmmError::BatchClosed);
+    assert_success(&pool.set_batch_mode(&pool.admin, false));
+    assert_success(&pool.cancel_batch_order(second, false));
+    let batch_auction = pool.batch_auction();
+    assert_eq!((batch_auction.order_count, batch_auction.amount_b_in), (0, 0));
+}
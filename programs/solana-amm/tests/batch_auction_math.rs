@@ -0,0 +1,106 @@
+// batch_auction 模块的单元测试和性质测试
+//
+// 和 twamm_math 一样只调用纯函数，不需要加载程序文件。
+
+use proptest::prelude::*;
+use solana_amm::batch_auction::{clear_batch, BatchFill};
+use solana_amm::math::calculate_swap_amount_out;
+
+const RESERVE_A: u64 = 1_000_000_000;
+const RESERVE_B: u64 = 2_000_000_000;
+
+fn fill(is_a_to_b: bool, amount_in: u64, min_amount_out: u64) -> BatchFill {
+    BatchFill { is_a_to_b, amount_in, min_amount_out }
+}
+
+#[test]
+fn same_direction_orders_share_one_price() {
+    let orders = [fill(true, 1_000_000, 0), fill(true, 3_000_000, 0)];
+    let clearing = clear_batch(&orders, RESERVE_A, RESERVE_B, 3, 1000, |_| true).unwrap();
+
+    // 整批和一笔 4_000_000 的兑换相同，两笔订单按卖出数量平分
+    let (_, out) = calculate_swap_amount_out(4_000_000, RESERVE_A, RESERVE_B, 3, 1000).unwrap();
+    assert_eq!(clearing.trade.proceeds_a, out);
+    assert_eq!(clearing.amounts_out, vec![Some(out / 4), Some(out * 3 / 4)]);
+    assert_eq!((clearing.trade.reserve_a, clearing.trade.reserve_b), (RESERVE_A + 4_000_000, RESERVE_B - out));
+}
+
+#[test]
+fn opposing_orders_net_before_touching_the_pool() {
+    // 价格 2 B/A，1000 A 和 2000 B 正好抵消
+    let orders = [fill(true, 1_000, 0), fill(false, 2_000, 0)];
+    let clearing = clear_batch(&orders, RESERVE_A, RESERVE_B, 3, 1000, |_| true).unwrap();
+    assert_eq!(clearing.amounts_out, vec![Some(2_000), Some(1_000)]);
+    assert_eq!((clearing.trade.reserve_a, clearing.trade.reserve_b), (RESERVE_A, RESERVE_B));
+}
+
+#[test]
+fn orders_below_limit_are_refunded_and_price_recomputed() {
+    // 第二笔要求 2.5 B/A，统一价格约 2 B/A 达不到
+    let orders = [fill(true, 1_000_000, 1_900_000), fill(true, 1_000_000, 2_500_000)];
+    let clearing = clear_batch(&orders, RESERVE_A, RESERVE_B, 3, 1000, |_| true).unwrap();
+
+    let (_, out) = calculate_swap_amount_out(1_000_000, RESERVE_A, RESERVE_B, 3, 1000).unwrap();
+    assert_eq!(clearing.amounts_out, vec![Some(out), None]);
+    assert_eq!(clearing.amount_a_in, 1_000_000);
+    assert_eq!(clearing.trade.reserve_a, RESERVE_A + 1_000_000);
+}
+
+#[test]
+fn largest_order_on_the_net_side_is_dropped_until_within_bounds() {
+    // 只能再卖进 2_000_000 个 A：先去掉最大的 A 订单，B 订单和剩下的 A 订单照常成交
+    let orders = [fill(true, 1_000_000, 0), fill(true, 3_000_000, 0), fill(false, 500_000, 0)];
+    let within_bounds = |trade: &solana_amm::twamm::VirtualTrade| trade.reserve_a <= RESERVE_A + 2_000_000;
+    let clearing = clear_batch(&orders, RESERVE_A, RESERVE_B, 3, 1000, within_bounds).unwrap();
+    assert!(clearing.amounts_out[0].is_some() && clearing.amounts_out[2].is_some());
+    assert_eq!(clearing.amounts_out[1], None);
+    assert_eq!(clearing.amount_a_in, 1_000_000);
+    assert!(clearing.trade.reserve_a <= RESERVE_A + 2_000_000);
+
+    // 怎么去掉都不满足时全部退回
+    let clearing = clear_batch(&orders, RESERVE_A, RESERVE_B, 3, 1000, |_| false).unwrap();
+    assert!(clearing.amounts_out.iter().all(Option::is_none));
+}
+
+#[test]
+fn empty_pool_refunds_everything() {
+    let orders = [fill(true, 1_000, 0), fill(false, 1_000, 0)];
+    let clearing = clear_batch(&orders, 0, 0, 3, 1000, |_| true).unwrap();
+    assert_eq!(clearing.amounts_out, vec![None, None]);
+    assert_eq!((clearing.amount_a_in, clearing.amount_b_in), (0, 0));
+}
+
+proptest! {
+    // 订单的提交顺序不影响结算结果，成交的订单都满足限价，付出的代币不超过换得的总量
+    #[test]
+    fn clearing_ignores_order_and_respects_limits(
+        orders in proptest::collection::vec(
+            (any::<bool>(), 1u64..1_000_000_000, 0u64..2_000_000_000),
+            1..8,
+        ),
+        reserve_a in 1_000_000u64..1_000_000_000_000,
+        reserve_b in 1_000_000u64..1_000_000_000_000,
+    ) {
+        let fills: Vec<BatchFill> =
+            orders.iter().map(|&(is_a_to_b, amount_in, min)| fill(is_a_to_b, amount_in, min)).collect();
+        let clearing = clear_batch(&fills, reserve_a, reserve_b, 3, 1000, |_| true).unwrap();
+
+        let reversed: Vec<BatchFill> = fills.iter().rev().copied().collect();
+        let mut reversed_out = clear_batch(&reversed, reserve_a, reserve_b, 3, 1000, |_| true).unwrap().amounts_out;
+        reversed_out.reverse();
+        prop_assert_eq!(&clearing.amounts_out, &reversed_out);
+
+        let (mut paid_a, mut paid_b) = (0u64, 0u64);
+        for (order, amount_out) in fills.iter().zip(&clearing.amounts_out) {
+            let Some(amount_out) = *amount_out else { continue };
+            prop_assert!(amount_out >= order.min_amount_out);
+            if order.is_a_to_b {
+                paid_b += amount_out;
+            } else {
+                paid_a += amount_out;
+            }
+        }
+        prop_assert!(paid_b <= clearing.trade.proceeds_a);
+        prop_assert!(paid_a <= clearing.trade.proceeds_b);
+    }
+}
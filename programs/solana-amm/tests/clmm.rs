@@ -0,0 +1,150 @@
+mod common;
+
+use common::*;
+use solana_amm::clmm::{amounts_for_liquidity, sqrt_price_at_tick};
+use solana_amm::{AmmError, Tick};
+
+const Q64: u128 = 1 << 64;
+const TICK_LOWER: i32 = -100;
+const TICK_UPPER: i32 = 100;
+const LIQUIDITY: u128 = 1_000_000_000_000;
+
+/// 价格 1.0 的池子，[-100, 100) 区间内有 LIQUIDITY 的流动性；TickArray 覆盖 [-320, 320)
+fn pool_with_position() -> TestPool {
+    let pool = TestPool::new();
+    assert_success(&pool.create_cl_pool(Q64));
+    assert_success(&pool.init_tick_array(-320));
+    assert_success(&pool.init_tick_array(0));
+    assert_success(&pool.open_position(TICK_LOWER, TICK_UPPER));
+    assert_success(&pool.increase_liquidity(TICK_LOWER, TICK_UPPER, LIQUIDITY));
+    pool
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn create_cl_pool_validates_price() {
+    let pool = TestPool::new();
+
+    assert_error(&pool.create_cl_pool(0), AmmError::InvalidSqrtPrice);
+    assert_success(&pool.create_cl_pool(sqrt_price_at_tick(-25).unwrap() + 1));
+
+    let cl_pool = pool.cl_pool();
+    assert_eq!(cl_pool.tick_current, -25);
+    assert_eq!(cl_pool.tick_spacing, CL_TICK_SPACING);
+    assert_eq!(cl_pool.liquidity, 0);
+    assert_eq!(cl_pool.token_a_vault, pool.cl_vault_address(&pool.mint_a));
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn tick_arrays_and_positions_must_align_with_spacing() {
+    let pool = TestPool::new();
+    assert_success(&pool.create_cl_pool(Q64));
+
+    assert_error(&pool.init_tick_array(10), AmmError::InvalidTickArray);
+    assert_success(&pool.init_tick_array(0));
+    assert_eq!(pool.tick_array(0).start_tick_index, 0);
+
+    assert_error(&pool.open_position(5, 100), AmmError::InvalidTickRange);
+    assert_error(&pool.open_position(100, 100), AmmError::InvalidTickRange);
+    assert_success(&pool.open_position(0, 100));
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn increase_liquidity_deposits_both_tokens_in_range() {
+    let pool = pool_with_position();
+
+    let (amount_a, amount_b) = amounts_for_liquidity(
+        Q64,
+        sqrt_price_at_tick(TICK_LOWER).unwrap(),
+        sqrt_price_at_tick(TICK_UPPER).unwrap(),
+        LIQUIDITY,
+        true,
+    )
+    .unwrap();
+    assert!(amount_a > 0 && amount_b > 0);
+    assert_eq!(pool.token_amount(&pool.cl_vault_address(&pool.mint_a)), amount_a);
+    assert_eq!(pool.token_amount(&pool.cl_vault_address(&pool.mint_b)), amount_b);
+    assert_eq!(pool.token_amount(&pool.user_token_a), USER_BALANCE - amount_a);
+
+    assert_eq!(pool.cl_pool().liquidity, LIQUIDITY);
+    assert_eq!(pool.position(TICK_LOWER, TICK_UPPER).unwrap().liquidity, LIQUIDITY);
+    let lower = pool.tick_array(-320).ticks[((TICK_LOWER + 320) / 10) as usize];
+    let upper = pool.tick_array(0).ticks[(TICK_UPPER / 10) as usize];
+    assert_eq!(lower.liquidity_net, LIQUIDITY as i128);
+    assert_eq!(upper.liquidity_net, -(LIQUIDITY as i128));
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn swap_within_range_moves_price_and_accrues_fees() {
+    let pool = pool_with_position();
+    let amount_in = 1_000_000;
+    let before_b = pool.token_amount(&pool.user_token_b);
+
+    assert_success(&pool.cl_swap(amount_in, true, None, &[0, -320]));
+
+    let cl_pool = pool.cl_pool();
+    assert!(cl_pool.sqrt_price_x64 < Q64);
+    assert_eq!(cl_pool.tick_current, -1);
+    assert_eq!(cl_pool.liquidity, LIQUIDITY);
+    assert!(cl_pool.fee_growth_global_a_x64 > 0);
+    let amount_out = pool.token_amount(&pool.user_token_b) - before_b;
+    assert!(amount_out > 0 && amount_out < amount_in);
+
+    // 手续费全部归这个头寸
+    let before_a = pool.token_amount(&pool.user_token_a);
+    assert_success(&pool.collect_fees(TICK_LOWER, TICK_UPPER));
+    let fees = pool.token_amount(&pool.user_token_a) - before_a;
+    assert!(fees > 0 && fees <= amount_in * FEE_NUMERATOR / FEE_DENOMINATOR);
+    assert_eq!(pool.position(TICK_LOWER, TICK_UPPER).unwrap().fees_owed_a, 0);
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn swap_crosses_position_boundary_and_stops_at_limit() {
+    let pool = pool_with_position();
+    let limit = sqrt_price_at_tick(-200).unwrap();
+
+    assert_success(&pool.cl_swap(u64::MAX / 4, true, Some(limit), &[0, -320]));
+
+    // 穿过 tick -100 之后没有流动性，价格停在限制上，只收取用掉的输入
+    let cl_pool = pool.cl_pool();
+    assert_eq!(cl_pool.sqrt_price_x64, limit);
+    assert_eq!(cl_pool.tick_current, -200);
+    assert_eq!(cl_pool.liquidity, 0);
+    assert!(pool.token_amount(&pool.user_token_a) > USER_BALANCE / 2);
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn swap_fails_without_required_tick_array() {
+    let pool = pool_with_position();
+
+    assert_error(&pool.cl_swap(1_000_000, true, None, &[0]), AmmError::TickArrayMissing);
+    assert_error(&pool.cl_swap(1_000_000, true, None, &[]), AmmError::InvalidTickArray);
+    assert_error(
+        &pool.cl_swap(1_000_000, true, Some(Q64 + 1), &[0, -320]),
+        AmmError::InvalidSqrtPrice,
+    );
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn decrease_liquidity_returns_tokens_and_clears_ticks() {
+    let pool = pool_with_position();
+
+    assert_error(
+        &pool.decrease_liquidity(TICK_LOWER, TICK_UPPER, LIQUIDITY + 1),
+        AmmError::InvalidLiquidityAmount,
+    );
+    assert_success(&pool.decrease_liquidity(TICK_LOWER, TICK_UPPER, LIQUIDITY));
+
+    assert_eq!(pool.position(TICK_LOWER, TICK_UPPER).unwrap().liquidity, 0);
+    assert_eq!(pool.cl_pool().liquidity, 0);
+    assert_eq!(pool.tick_array(-320).ticks[((TICK_LOWER + 320) / 10) as usize], Tick::default());
+    // 存入向上取整、取出向下取整，金库里最多剩下舍入的零头
+    assert!(pool.token_amount(&pool.cl_vault_address(&pool.mint_a)) <= 1);
+    assert!(pool.token_amount(&pool.cl_vault_address(&pool.mint_b)) <= 1);
+}
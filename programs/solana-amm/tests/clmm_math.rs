@@ -0,0 +1,226 @@
+// clmm 模块的单元测试和性质测试
+//
+// 和 math_props 一样只调用纯函数，不需要加载程序文件。
+
+use proptest::prelude::*;
+use solana_amm::clmm::{
+    add_liquidity_delta, amount_a_delta, amount_b_delta, amounts_for_liquidity, compute_swap_step,
+    cross_tick, fee_growth_inside, modify_position, sqrt_price_at_tick, tick_at_sqrt_price, MAX_TICK,
+    MIN_TICK,
+};
+use solana_amm::{ClPool, Position, Tick, TickArray};
+
+const Q64: u128 = 1 << 64;
+
+fn empty_pool(tick_current: i32) -> ClPool {
+    let mut pool: ClPool = bytemuck::Zeroable::zeroed();
+    pool.tick_current = tick_current;
+    pool.sqrt_price_x64 = sqrt_price_at_tick(tick_current).unwrap();
+    pool
+}
+
+fn empty_position(tick_lower: i32, tick_upper: i32) -> Position {
+    Position {
+        pool: Default::default(),
+        owner: Default::default(),
+        tick_lower,
+        tick_upper,
+        liquidity: 0,
+        fee_growth_inside_a_last_x64: 0,
+        fee_growth_inside_b_last_x64: 0,
+        fees_owed_a: 0,
+        fees_owed_b: 0,
+        bump: 0,
+    }
+}
+
+#[test]
+fn sqrt_price_at_known_ticks() {
+    assert_eq!(sqrt_price_at_tick(0), Some(Q64));
+    // 1.0001^(±10000 / 2) ≈ e^(±0.49998)
+    let up = sqrt_price_at_tick(10_000).unwrap() as f64 / Q64 as f64;
+    let down = sqrt_price_at_tick(-10_000).unwrap() as f64 / Q64 as f64;
+    assert!((up - 1.0001f64.powi(5_000)).abs() / up < 1e-9, "{up}");
+    assert!((down - 1.0001f64.powi(-5_000)).abs() / down < 1e-9, "{down}");
+
+    assert!(sqrt_price_at_tick(MIN_TICK).is_some());
+    assert!(sqrt_price_at_tick(MAX_TICK).is_some());
+    assert_eq!(sqrt_price_at_tick(MIN_TICK - 1), None);
+    assert_eq!(sqrt_price_at_tick(MAX_TICK + 1), None);
+}
+
+#[test]
+fn tick_at_sqrt_price_edges() {
+    let min = sqrt_price_at_tick(MIN_TICK).unwrap();
+    let max = sqrt_price_at_tick(MAX_TICK).unwrap();
+    assert_eq!(tick_at_sqrt_price(min), Some(MIN_TICK));
+    assert_eq!(tick_at_sqrt_price(min - 1), None);
+    assert_eq!(tick_at_sqrt_price(max - 1), Some(MAX_TICK - 1));
+    assert_eq!(tick_at_sqrt_price(max), None);
+    assert_eq!(tick_at_sqrt_price(Q64), Some(0));
+    assert_eq!(tick_at_sqrt_price(Q64 - 1), Some(-1));
+}
+
+#[test]
+fn amounts_for_liquidity_by_price_position() {
+    let (lower, upper) = (sqrt_price_at_tick(-100).unwrap(), sqrt_price_at_tick(100).unwrap());
+    let liquidity = 1_000_000_000u128;
+
+    // 低于区间全部是 A，高于区间全部是 B
+    let (a, b) = amounts_for_liquidity(lower - 1, lower, upper, liquidity, true).unwrap();
+    assert!(a > 0 && b == 0);
+    let (a, b) = amounts_for_liquidity(upper, lower, upper, liquidity, true).unwrap();
+    assert!(a == 0 && b > 0);
+
+    // 价格 1.0 在区间中点附近，两边数量接近
+    let (a, b) = amounts_for_liquidity(Q64, lower, upper, liquidity, true).unwrap();
+    assert!(a.abs_diff(b) <= 1, "{a} {b}");
+
+    // 存入向上取整，取出向下取整
+    let (a_down, b_down) = amounts_for_liquidity(Q64, lower, upper, liquidity, false).unwrap();
+    assert!(a_down <= a && b_down <= b);
+}
+
+#[test]
+fn swap_step_stops_at_target_and_charges_fee() {
+    let liquidity = 1_000_000_000_000u128;
+    let current = Q64;
+    let target = sqrt_price_at_tick(-10).unwrap();
+
+    // 输入足够多时停在目标价格，剩余的输入不会被消耗
+    let step = compute_swap_step(current, target, liquidity, u64::MAX / 2, 3, 1000).unwrap();
+    assert_eq!(step.sqrt_price_next, target);
+    assert_eq!(step.amount_in as u128, amount_a_delta(target, current, liquidity, true).unwrap());
+    assert!(step.fee_amount > 0);
+
+    // 输入不够时全部用完，价格停在中间
+    let step = compute_swap_step(current, target, liquidity, 1_000_000, 3, 1000).unwrap();
+    assert!(step.sqrt_price_next > target && step.sqrt_price_next < current);
+    assert_eq!(step.amount_in + step.fee_amount, 1_000_000);
+    assert_eq!(step.fee_amount, 1_000_000 - 997_000);
+
+    // 反方向
+    let target = sqrt_price_at_tick(10).unwrap();
+    let step = compute_swap_step(current, target, liquidity, 1_000_000, 3, 1000).unwrap();
+    assert!(step.sqrt_price_next > current && step.sqrt_price_next < target);
+    assert!(step.amount_out < 997_000);
+}
+
+#[test]
+fn swap_step_without_liquidity_jumps_to_target() {
+    let target = sqrt_price_at_tick(-600).unwrap();
+    let step = compute_swap_step(Q64, target, 0, 1_000, 3, 1000).unwrap();
+    assert_eq!(step.sqrt_price_next, target);
+    assert_eq!((step.amount_in, step.amount_out, step.fee_amount), (0, 0, 0));
+}
+
+#[test]
+fn position_accrues_fees_only_while_in_range() {
+    let mut pool = empty_pool(0);
+    let mut position = empty_position(-60, 60);
+    let (mut lower, mut upper) = (Tick::default(), Tick::default());
+
+    modify_position(&mut pool, &mut position, &mut lower, &mut upper, 1_000).unwrap();
+    assert_eq!(pool.liquidity, 1_000);
+    assert_eq!((lower.liquidity_net, upper.liquidity_net), (1_000, -1_000));
+
+    // 区间内产生了每单位流动性 2 个 A 的手续费
+    pool.fee_growth_global_a_x64 = 2 * Q64;
+    modify_position(&mut pool, &mut position, &mut lower, &mut upper, 0).unwrap();
+    assert_eq!(position.fees_owed_a, 2_000);
+
+    // 价格向上穿过 tick 60 之后产生的手续费不属于这个头寸
+    let net = cross_tick(&mut upper, pool.fee_growth_global_a_x64, pool.fee_growth_global_b_x64);
+    pool.liquidity = add_liquidity_delta(pool.liquidity, net).unwrap();
+    pool.tick_current = 60;
+    assert_eq!(pool.liquidity, 0);
+    pool.fee_growth_global_a_x64 = 5 * Q64;
+    let (inside_a, _) = fee_growth_inside(&pool, &lower, &upper, -60, 60);
+    assert_eq!(inside_a, 2 * Q64);
+
+    // 取出全部流动性后两个 tick 都被清空
+    modify_position(&mut pool, &mut position, &mut lower, &mut upper, -1_000).unwrap();
+    assert_eq!(position.liquidity, 0);
+    assert_eq!(position.fees_owed_a, 2_000);
+    assert_eq!(lower, Tick::default());
+    assert_eq!(upper, Tick::default());
+}
+
+#[test]
+fn tick_array_offsets() {
+    assert_eq!(TickArray::start_index_for(0, 10), 0);
+    assert_eq!(TickArray::start_index_for(319, 10), 0);
+    assert_eq!(TickArray::start_index_for(320, 10), 320);
+    assert_eq!(TickArray::start_index_for(-1, 10), -320);
+
+    let mut array: TickArray = bytemuck::Zeroable::zeroed();
+    array.start_tick_index = -320;
+    assert_eq!(array.offset(-320, 10), Some(0));
+    assert_eq!(array.offset(-10, 10), Some(31));
+    assert_eq!(array.offset(0, 10), None);
+    assert_eq!(array.offset(-315, 10), None);
+}
+
+proptest! {
+    #[test]
+    fn tick_roundtrip(tick in MIN_TICK..MAX_TICK) {
+        let sqrt_price = sqrt_price_at_tick(tick).unwrap();
+        prop_assert_eq!(tick_at_sqrt_price(sqrt_price), Some(tick));
+        prop_assert!(sqrt_price_at_tick(tick + 1).unwrap() > sqrt_price);
+    }
+
+    #[test]
+    fn tick_at_sqrt_price_is_floor(
+        sqrt_price in sqrt_price_at_tick(MIN_TICK).unwrap()..sqrt_price_at_tick(MAX_TICK).unwrap()
+    ) {
+        let tick = tick_at_sqrt_price(sqrt_price).unwrap();
+        prop_assert!(sqrt_price_at_tick(tick).unwrap() <= sqrt_price);
+        prop_assert!(sqrt_price_at_tick(tick + 1).unwrap() > sqrt_price);
+    }
+
+    // 同一段价格区间内，存入要付的不少于取出能拿到的
+    #[test]
+    fn amount_deltas_round_in_pool_favor(
+        tick_a in -200_000i32..200_000,
+        width in 1i32..50_000,
+        liquidity in 1u128..(1u128 << 80),
+    ) {
+        let lower = sqrt_price_at_tick(tick_a).unwrap();
+        let upper = sqrt_price_at_tick(tick_a + width).unwrap();
+        let a_up = amount_a_delta(lower, upper, liquidity, true).unwrap();
+        let a_down = amount_a_delta(lower, upper, liquidity, false).unwrap();
+        let b_up = amount_b_delta(lower, upper, liquidity, true).unwrap();
+        let b_down = amount_b_delta(lower, upper, liquidity, false).unwrap();
+        prop_assert!(a_up >= a_down && a_up - a_down <= 2);
+        prop_assert!(b_up >= b_down && b_up - b_down <= 1);
+    }
+
+    // 单步兑换：消耗的输入不超过给定数量，价格朝正确方向移动且不越过目标
+    #[test]
+    fn swap_step_is_bounded(
+        tick in -100_000i32..100_000,
+        distance in 1i32..5_000,
+        is_a_to_b in any::<bool>(),
+        liquidity in 1u128..(1u128 << 90),
+        amount in 0u64..u64::MAX / 2,
+        fee_numerator in 0u64..100,
+    ) {
+        let current = sqrt_price_at_tick(tick).unwrap();
+        let target = sqrt_price_at_tick(if is_a_to_b { tick - distance } else { tick + distance }).unwrap();
+        // 输出超出 u64 时返回 None，handler 中报 MathOverflow
+        let step = compute_swap_step(current, target, liquidity, amount, fee_numerator, 1000);
+        prop_assume!(step.is_some());
+        let step = step.unwrap();
+
+        prop_assert!(step.amount_in as u128 + step.fee_amount as u128 <= amount as u128);
+        if is_a_to_b {
+            prop_assert!(step.sqrt_price_next <= current && step.sqrt_price_next >= target);
+            let max_out = amount_b_delta(step.sqrt_price_next, current, liquidity, false).unwrap();
+            prop_assert!(step.amount_out as u128 <= max_out);
+        } else {
+            prop_assert!(step.sqrt_price_next >= current && step.sqrt_price_next <= target);
+            let max_out = amount_a_delta(current, step.sqrt_price_next, liquidity, false).unwrap();
+            prop_assert!(step.amount_out as u128 <= max_out);
+        }
+    }
+}
@@ -0,0 +1,188 @@
+//! 批量拍卖相关的账户地址和指令
+
+use super::*;
+
+impl TestPool {
+    pub fn batch_auction_address(&self) -> Pubkey {
+        Pubkey::find_program_address(&[b"batch_auction", self.pool_state.as_ref()], &solana_amm::ID).0
+    }
+
+    pub fn batch_vault_address(&self, mint: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(
+            &[b"batch_vault", self.batch_auction_address().as_ref(), mint.as_ref()],
+            &solana_amm::ID,
+        )
+        .0
+    }
+
+    pub fn batch_order_address(&self, owner: &Pubkey, batch_id: u64) -> Pubkey {
+        Pubkey::find_program_address(
+            &[b"batch_order", self.batch_auction_address().as_ref(), owner.as_ref(), &batch_id.to_le_bytes()],
+            &solana_amm::ID,
+        )
+        .0
+    }
+
+    pub fn batch_auction(&self) -> solana_amm::BatchAuction {
+        let account = self.account(&self.batch_auction_address()).expect("batch auction");
+        solana_amm::BatchAuction::try_deserialize(&mut account.data.as_slice()).unwrap()
+    }
+
+    pub fn batch_order(&self, owner: &Pubkey, batch_id: u64) -> Option<solana_amm::BatchOrder> {
+        let account = self.account(&self.batch_order_address(owner, batch_id))?;
+        solana_amm::BatchOrder::try_deserialize(&mut account.data.as_slice()).ok()
+    }
+
+    /// 两个方向使用同一个最少订单数量
+    pub fn init_batch_auction(&self, signer: &Pubkey, batch_duration_slots: u64, min_order_amount: u64) -> InstructionResult {
+        self.process(
+            solana_amm::instruction::InitBatchAuction {
+                batch_duration_slots,
+                min_order_amount_a: min_order_amount,
+                min_order_amount_b: min_order_amount,
+            },
+            solana_amm::accounts::InitBatchAuction {
+                pool_state: self.pool_state,
+                pool_authority: self.pool_authority,
+                batch_auction: self.batch_auction_address(),
+                token_a_mint: self.mint_a,
+                token_b_mint: self.mint_b,
+                batch_vault_a: self.batch_vault_address(&self.mint_a),
+                batch_vault_b: self.batch_vault_address(&self.mint_b),
+                admin: *signer,
+                system_program: anchor_lang::system_program::ID,
+                token_program: anchor_spl::token::ID,
+            },
+        )
+    }
+
+    pub fn set_batch_mode(&self, signer: &Pubkey, enabled: bool) -> InstructionResult {
+        self.process(
+            solana_amm::instruction::SetBatchMode { enabled },
+            solana_amm::accounts::SetBatchMode {
+                pool_state: self.pool_state,
+                batch_auction: self.batch_auction_address(),
+                admin: *signer,
+            },
+        )
+    }
+
+    /// 交易者和它的两个代币账户 (owner, token_a, token_b)
+    pub fn trader(&self) -> (Pubkey, Pubkey, Pubkey) {
+        (self.user, self.user_token_a, self.user_token_b)
+    }
+
+    /// 另一个有 lamports 和两种代币的交易者
+    pub fn new_trader(&mut self) -> (Pubkey, Pubkey, Pubkey) {
+        let owner = Pubkey::new_unique();
+        self.fund(&owner);
+        let (mint_a, mint_b) = (self.mint_a, self.mint_b);
+        let token_a = self.create_token_account(&mint_a, &owner, USER_BALANCE);
+        let token_b = self.create_token_account(&mint_b, &owner, USER_BALANCE);
+        (owner, token_a, token_b)
+    }
+
+    pub fn submit_batch_swap(
+        &self,
+        trader: (Pubkey, Pubkey, Pubkey),
+        amount_in: u64,
+        is_a_to_b: bool,
+        min_amount_out: u64,
+    ) -> InstructionResult {
+        let (owner, token_a, token_b) = trader;
+        let (batch_vault_in, user_token_in) = if is_a_to_b {
+            (self.batch_vault_address(&self.mint_a), token_a)
+        } else {
+            (self.batch_vault_address(&self.mint_b), token_b)
+        };
+        self.process(
+            solana_amm::instruction::SubmitBatchSwap {
+                amount_in,
+                is_a_to_b,
+                min_amount_out,
+            },
+            solana_amm::accounts::SubmitBatchSwap {
+                pool_state: self.pool_state,
+                batch_auction: self.batch_auction_address(),
+                batch_vault_in,
+                batch_order: self.batch_order_address(&owner, self.batch_auction().batch_id),
+                user_token_in,
+                owner,
+                system_program: anchor_lang::system_program::ID,
+                token_program: anchor_spl::token::ID,
+            },
+        )
+    }
+
+    /// 当前批次的订单按 `traders` 的顺序传入
+    pub fn settle_batch_instruction(&self, traders: &[(Pubkey, Pubkey, Pubkey)]) -> Instruction {
+        let twamm_accounts = self.twamm_accounts();
+        let mut instruction = instruction(
+            solana_amm::instruction::SettleBatch {},
+            solana_amm::accounts::SettleBatch {
+                pool_state: self.pool_state,
+                pool_authority: self.pool_authority,
+                token_a_vault: self.token_a_vault,
+                token_b_vault: self.token_b_vault,
+                batch_auction: self.batch_auction_address(),
+                batch_vault_a: self.batch_vault_address(&self.mint_a),
+                batch_vault_b: self.batch_vault_address(&self.mint_b),
+                token_program: anchor_spl::token::ID,
+                twamm: twamm_accounts.0,
+                twamm_vault_a: twamm_accounts.1,
+                twamm_vault_b: twamm_accounts.2,
+            },
+        );
+        let batch_id = self.batch_auction().batch_id;
+        for &(owner, _, _) in traders {
+            instruction.accounts.push(AccountMeta::new(address(&self.batch_order_address(&owner, batch_id)), false));
+        }
+        instruction
+    }
+
+    pub fn settle_batch(&self, traders: &[(Pubkey, Pubkey, Pubkey)]) -> InstructionResult {
+        self.context.process_instruction(&self.settle_batch_instruction(traders))
+    }
+
+    pub fn claim_batch_order(&self, trader: (Pubkey, Pubkey, Pubkey), batch_id: u64) -> InstructionResult {
+        let (owner, token_a, token_b) = trader;
+        self.process(
+            solana_amm::instruction::ClaimBatchOrder {},
+            solana_amm::accounts::ClaimBatchOrder {
+                pool_state: self.pool_state,
+                pool_authority: self.pool_authority,
+                batch_auction: self.batch_auction_address(),
+                batch_vault_a: self.batch_vault_address(&self.mint_a),
+                batch_vault_b: self.batch_vault_address(&self.mint_b),
+                batch_order: self.batch_order_address(&owner, batch_id),
+                owner_token_a: token_a,
+                owner_token_b: token_b,
+                owner,
+                token_program: anchor_spl::token::ID,
+            },
+        )
+    }
+
+    /// 撤销 trader 在当前批次中卖出 A（`is_a_to_b`）或者卖出 B 的订单
+    pub fn cancel_batch_order(&self, trader: (Pubkey, Pubkey, Pubkey), is_a_to_b: bool) -> InstructionResult {
+        let (owner, token_a, token_b) = trader;
+        let (batch_vault_in, user_token_in) = if is_a_to_b {
+            (self.batch_vault_address(&self.mint_a), token_a)
+        } else {
+            (self.batch_vault_address(&self.mint_b), token_b)
+        };
+        self.process(
+            solana_amm::instruction::CancelBatchOrder {},
+            solana_amm::accounts::CancelBatchOrder {
+                pool_state: self.pool_state,
+                pool_authority: self.pool_authority,
+                batch_auction: self.batch_auction_address(),
+                batch_order: self.batch_order_address(&owner, self.batch_auction().batch_id),
+                batch_vault_in,
+                user_token_in,
+                owner,
+                token_program: anchor_spl::token::ID,
+            },
+        )
+    }
+}
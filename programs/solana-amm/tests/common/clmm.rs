@@ -0,0 +1,219 @@
+//! 集中流动性池子相关的账户地址和指令
+
+use super::*;
+
+impl TestPool {
+    pub fn cl_pool_address(&self) -> Pubkey {
+        Pubkey::find_program_address(
+            &[b"cl_pool", self.mint_a.as_ref(), self.mint_b.as_ref(), &CL_TICK_SPACING.to_le_bytes()],
+            &solana_amm::ID,
+        )
+        .0
+    }
+
+    pub fn cl_vault_address(&self, mint: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(
+            &[b"cl_vault", self.cl_pool_address().as_ref(), mint.as_ref()],
+            &solana_amm::ID,
+        )
+        .0
+    }
+
+    pub fn tick_array_address(&self, start_tick_index: i32) -> Pubkey {
+        Pubkey::find_program_address(
+            &[b"tick_array", self.cl_pool_address().as_ref(), &start_tick_index.to_le_bytes()],
+            &solana_amm::ID,
+        )
+        .0
+    }
+
+    pub fn position_address(&self, tick_lower: i32, tick_upper: i32) -> Pubkey {
+        Pubkey::find_program_address(
+            &[
+                b"position",
+                self.cl_pool_address().as_ref(),
+                self.user.as_ref(),
+                &tick_lower.to_le_bytes(),
+                &tick_upper.to_le_bytes(),
+            ],
+            &solana_amm::ID,
+        )
+        .0
+    }
+
+    pub fn cl_pool(&self) -> solana_amm::ClPool {
+        let account = self.account(&self.cl_pool_address()).expect("cl pool");
+        bytemuck::pod_read_unaligned(&account.data[8..solana_amm::ClPool::LEN])
+    }
+
+    pub fn tick_array(&self, start_tick_index: i32) -> solana_amm::TickArray {
+        let account = self.account(&self.tick_array_address(start_tick_index)).expect("tick array");
+        bytemuck::pod_read_unaligned(&account.data[8..solana_amm::TickArray::LEN])
+    }
+
+    pub fn position(&self, tick_lower: i32, tick_upper: i32) -> Option<solana_amm::Position> {
+        let account = self.account(&self.position_address(tick_lower, tick_upper))?;
+        solana_amm::Position::try_deserialize(&mut account.data.as_slice()).ok()
+    }
+
+    /// 以 CL_TICK_SPACING 和 3/1000 的费率创建集中流动性池子
+    pub fn create_cl_pool(&self, initial_sqrt_price_x64: u128) -> InstructionResult {
+        self.process(
+            solana_amm::instruction::CreateClPool {
+                tick_spacing: CL_TICK_SPACING,
+                initial_sqrt_price_x64,
+                fee_numerator: FEE_NUMERATOR,
+                fee_denominator: FEE_DENOMINATOR,
+            },
+            solana_amm::accounts::CreateClPool {
+                cl_pool: self.cl_pool_address(),
+                pool_authority: self.pool_authority,
+                token_a: self.mint_a,
+                token_b: self.mint_b,
+                token_a_vault: self.cl_vault_address(&self.mint_a),
+                token_b_vault: self.cl_vault_address(&self.mint_b),
+                admin: self.admin,
+                system_program: anchor_lang::system_program::ID,
+                token_program: anchor_spl::token::ID,
+                rent: anchor_lang::solana_program::sysvar::rent::ID,
+            },
+        )
+    }
+
+    pub fn init_tick_array(&self, start_tick_index: i32) -> InstructionResult {
+        self.process(
+            solana_amm::instruction::InitTickArray { start_tick_index },
+            solana_amm::accounts::InitTickArray {
+                cl_pool: self.cl_pool_address(),
+                tick_array: self.tick_array_address(start_tick_index),
+                payer: self.user,
+                system_program: anchor_lang::system_program::ID,
+            },
+        )
+    }
+
+    pub fn open_position(&self, tick_lower: i32, tick_upper: i32) -> InstructionResult {
+        self.process(
+            solana_amm::instruction::OpenPosition { tick_lower, tick_upper },
+            solana_amm::accounts::OpenPosition {
+                cl_pool: self.cl_pool_address(),
+                position: self.position_address(tick_lower, tick_upper),
+                owner: self.user,
+                system_program: anchor_lang::system_program::ID,
+            },
+        )
+    }
+
+    pub fn increase_liquidity(&self, tick_lower: i32, tick_upper: i32, liquidity: u128) -> InstructionResult {
+        self.process(
+            solana_amm::instruction::IncreaseLiquidity {
+                liquidity,
+                max_amount_a: u64::MAX,
+                max_amount_b: u64::MAX,
+            },
+            solana_amm::accounts::IncreaseLiquidity {
+                cl_pool: self.cl_pool_address(),
+                position: self.position_address(tick_lower, tick_upper),
+                tick_array_lower: self.tick_array_address(TickArray::start_index_for(tick_lower, CL_TICK_SPACING)),
+                tick_array_upper: self.tick_array_address(TickArray::start_index_for(tick_upper, CL_TICK_SPACING)),
+                user_token_a: self.user_token_a,
+                user_token_b: self.user_token_b,
+                token_a_vault: self.cl_vault_address(&self.mint_a),
+                token_b_vault: self.cl_vault_address(&self.mint_b),
+                owner: self.user,
+                token_program: anchor_spl::token::ID,
+            },
+        )
+    }
+
+    pub fn decrease_liquidity(&self, tick_lower: i32, tick_upper: i32, liquidity: u128) -> InstructionResult {
+        self.process(
+            solana_amm::instruction::DecreaseLiquidity {
+                liquidity,
+                min_amount_a: 0,
+                min_amount_b: 0,
+            },
+            solana_amm::accounts::DecreaseLiquidity {
+                cl_pool: self.cl_pool_address(),
+                position: self.position_address(tick_lower, tick_upper),
+                tick_array_lower: self.tick_array_address(TickArray::start_index_for(tick_lower, CL_TICK_SPACING)),
+                tick_array_upper: self.tick_array_address(TickArray::start_index_for(tick_upper, CL_TICK_SPACING)),
+                user_token_a: self.user_token_a,
+                user_token_b: self.user_token_b,
+                token_a_vault: self.cl_vault_address(&self.mint_a),
+                token_b_vault: self.cl_vault_address(&self.mint_b),
+                pool_authority: self.pool_authority,
+                owner: self.user,
+                token_program: anchor_spl::token::ID,
+            },
+        )
+    }
+
+    pub fn collect_fees(&self, tick_lower: i32, tick_upper: i32) -> InstructionResult {
+        self.process(
+            solana_amm::instruction::CollectFees {},
+            solana_amm::accounts::CollectFees {
+                cl_pool: self.cl_pool_address(),
+                position: self.position_address(tick_lower, tick_upper),
+                tick_array_lower: self.tick_array_address(TickArray::start_index_for(tick_lower, CL_TICK_SPACING)),
+                tick_array_upper: self.tick_array_address(TickArray::start_index_for(tick_upper, CL_TICK_SPACING)),
+                user_token_a: self.user_token_a,
+                user_token_b: self.user_token_b,
+                token_a_vault: self.cl_vault_address(&self.mint_a),
+                token_b_vault: self.cl_vault_address(&self.mint_b),
+                pool_authority: self.pool_authority,
+                owner: self.user,
+                token_program: anchor_spl::token::ID,
+            },
+        )
+    }
+
+    /// `tick_array_starts` 按兑换方向排列，作为 remaining_accounts 传入
+    pub fn cl_swap_instruction(
+        &self,
+        amount_in: u64,
+        is_a_to_b: bool,
+        sqrt_price_limit_x64: Option<u128>,
+        tick_array_starts: &[i32],
+    ) -> Instruction {
+        let mut instruction = instruction(
+            solana_amm::instruction::ClSwap {
+                amount_in,
+                is_a_to_b,
+                min_amount_out: 0,
+                sqrt_price_limit_x64,
+            },
+            solana_amm::accounts::ClSwap {
+                cl_pool: self.cl_pool_address(),
+                user_token_a: self.user_token_a,
+                user_token_b: self.user_token_b,
+                token_a_vault: self.cl_vault_address(&self.mint_a),
+                token_b_vault: self.cl_vault_address(&self.mint_b),
+                user: self.user,
+                pool_authority: self.pool_authority,
+                token_program: anchor_spl::token::ID,
+            },
+        );
+        for &start in tick_array_starts {
+            instruction
+                .accounts
+                .push(AccountMeta::new(address(&self.tick_array_address(start)), false));
+        }
+        instruction
+    }
+
+    pub fn cl_swap(
+        &self,
+        amount_in: u64,
+        is_a_to_b: bool,
+        sqrt_price_limit_x64: Option<u128>,
+        tick_array_starts: &[i32],
+    ) -> InstructionResult {
+        self.context.process_instruction(&self.cl_swap_instruction(
+            amount_in,
+            is_a_to_b,
+            sqrt_price_limit_x64,
+            tick_array_starts,
+        ))
+    }
+}
@@ -0,0 +1,547 @@
+//! CU 基准场景
+//!
+//! `benches/compute_units.rs` 用它生成 markdown 报告，
+//! `tests/compute_units.rs` 用它检查 `benches/cu_budget.toml` 中的预算。
+//! 每个场景都是一个独立的池子，加上一条准备好但还没有执行的指令。
+
+use solana_amm::{LegacyPoolState, OBSERVATION_COUNT, OBSERVATION_INTERVAL};
+
+/// `PoolState` 还是 Borsh 布局时的程序文件名，和 `solana_amm.so` 放在同一个目录
+pub const BORSH_BASELINE_PROGRAM: &str = "solana_amm_borsh.so";
+
+use super::*;
+
+/// 不同量级的储备金，主要影响 sqrt 的迭代次数
+pub const RESERVE_SIZES: [(&str, u64, u64); 3] = [
+    ("small", 1_000_000, 2_000_000),
+    ("medium", 1_000_000_000, 2_000_000_000),
+    ("large", 1_000_000_000_000_000, 2_000_000_000_000_000),
+];
+
+/// 场景执行时距离上次更新 TWAP 的秒数，保证累加器分支被计入
+pub const ELAPSED: u64 = 60;
+
+pub struct Scenario {
+    pub name: String,
+    pub pool: TestPool,
+    pub instruction: Instruction,
+}
+
+impl Scenario {
+    fn new(name: impl Into<String>, mut pool: TestPool, instruction: Instruction) -> Self {
+        pool.advance_clock(ELAPSED);
+        Self {
+            name: name.into(),
+            pool,
+            instruction,
+        }
+    }
+
+    pub fn run(&self) -> InstructionResult {
+        self.pool.context.process_instruction(&self.instruction)
+    }
+
+    pub fn keyed_accounts(&self) -> Vec<(solana_pubkey::Pubkey, Account)> {
+        self.pool.keyed_accounts(&self.instruction)
+    }
+
+    pub fn unix_timestamp(&self) -> i64 {
+        self.pool.context.mollusk.sysvars.clock.unix_timestamp
+    }
+}
+
+// 来回交易产生手续费，使 sqrt(k) 增长，触发协议费增发
+fn generate_fees(pool: &TestPool, reserve_a: u64, reserve_b: u64) {
+    assert_success(&pool.swap(reserve_a / 20, true, 0));
+    assert_success(&pool.swap(reserve_b / 20, false, 0));
+}
+
+// 创建 Farm 并添加两种奖励，用户质押四分之一的 LP；返回池子和第一种奖励的 mint
+fn farm_pool(reserve_a: u64, reserve_b: u64) -> (TestPool, Pubkey) {
+    let mut pool = TestPool::with_liquidity(reserve_a, reserve_b);
+    assert_success(&pool.create_farm(&pool.admin));
+    let end_ts = pool.context.mollusk.sysvars.clock.unix_timestamp + 86_400;
+    let mut reward_mints = Vec::new();
+    for _ in 0..2 {
+        let (reward_mint, admin_reward) = pool.create_reward_mint(1_000 * 86_400);
+        assert_success(&pool.add_farm_reward(&reward_mint, &admin_reward, 1_000, end_ts));
+        reward_mints.push(reward_mint);
+    }
+    assert_success(&pool.stake_lp(pool.token_amount(&pool.user_lp) / 4));
+    (pool, reward_mints[0])
+}
+
+// 每个储备量级的场景之后的其他场景，按 `scenarios()` 中的顺序排列
+const FIXED_SCENARIOS: [&str; 18] = [
+    "swap_price_guard_medium",
+    "swap_referral_medium",
+    "swap_fee_override_medium",
+    "lock_lp",
+    "claim_lp_lock_fees",
+    "stake_lp",
+    "claim_rewards",
+    "get_geometric_twap",
+    "update_config",
+    "claim_admin",
+    "migrate_pool",
+    "cl_swap_in_range",
+    "cl_swap_cross_tick",
+    "fill_orders",
+    "execute_twamm",
+    "swap_twamm_medium",
+    "execute_dca",
+    "settle_batch",
+];
+
+/// 所有基准场景的名字，和 `scenarios()` 的顺序一致，不需要程序文件
+///
+/// 预算文件的检查用它确认每个场景都有预算；`scenarios()` 构建完之后会核对两者一致。
+pub fn scenario_names() -> Vec<String> {
+    let mut names = vec!["initialize".to_string()];
+    for (size, _, _) in RESERVE_SIZES {
+        names.extend(
+            [
+                "add_liquidity_first",
+                "add_liquidity",
+                "swap_a_to_b",
+                "swap_b_to_a",
+                "remove_liquidity",
+                "add_liquidity_protocol_fee",
+            ]
+            .map(|name| format!("{name}_{size}")),
+        );
+    }
+    names.extend(FIXED_SCENARIOS.map(String::from));
+    names
+}
+
+/// 所有基准场景，找不到程序文件时 panic
+pub fn scenarios() -> Vec<Scenario> {
+    let mut scenarios = Vec::new();
+
+    let pool = TestPool::new();
+    let ix = instruction(
+        solana_amm::instruction::Initialize {
+            mint_a: pool.mint_a,
+            mint_b: pool.mint_b,
+            fee_numerator: FEE_NUMERATOR,
+            fee_denominator: FEE_DENOMINATOR,
+        },
+        pool.initialize_accounts(),
+    );
+    scenarios.push(Scenario::new("initialize", pool, ix));
+
+    for (size, reserve_a, reserve_b) in RESERVE_SIZES {
+        let pool = TestPool::initialized();
+        let ix = instruction(
+            solana_amm::instruction::AddLiquidity {
+                amount_a: reserve_a,
+                amount_b: reserve_b,
+                deadline: None,
+            },
+            pool.add_liquidity_accounts(),
+        );
+        scenarios.push(Scenario::new(format!("add_liquidity_first_{size}"), pool, ix));
+
+        let pool = TestPool::with_liquidity(reserve_a, reserve_b);
+        let ix = instruction(
+            solana_amm::instruction::AddLiquidity {
+                amount_a: reserve_a / 10,
+                amount_b: reserve_b / 10,
+                deadline: None,
+            },
+            pool.add_liquidity_accounts(),
+        );
+        scenarios.push(Scenario::new(format!("add_liquidity_{size}"), pool, ix));
+
+        for (direction, is_a_to_b, amount_in) in
+            [("a_to_b", true, reserve_a / 100), ("b_to_a", false, reserve_b / 100)]
+        {
+            let pool = TestPool::with_liquidity(reserve_a, reserve_b);
+            let ix = instruction(
+                solana_amm::instruction::Swap {
+                    amount_in,
+                    is_a_to_b,
+                    min_amount_out: 0,
+                    deadline: None,
+                    referral_fee_bps: 0,
+                },
+                pool.swap_accounts(),
+            );
+            scenarios.push(Scenario::new(format!("swap_{direction}_{size}"), pool, ix));
+        }
+
+        let pool = TestPool::with_liquidity(reserve_a, reserve_b);
+        let amount_lp = pool.token_amount(&pool.user_lp) / 10;
+        let ix = instruction(
+            solana_amm::instruction::RemoveLiquidity {
+                amount_lp,
+                min_amount_a: 0,
+                min_amount_b: 0,
+                deadline: None,
+            },
+            pool.remove_liquidity_accounts(),
+        );
+        scenarios.push(Scenario::new(format!("remove_liquidity_{size}"), pool, ix));
+
+        // 开启协议费并产生手续费，覆盖 calculate_protocol_fee_mint 的两次 sqrt 和额外的 mint_to
+        let pool = TestPool::with_liquidity(reserve_a, reserve_b);
+        assert_success(&pool.update_config(&pool.admin, None, None, Some(5)));
+        generate_fees(&pool, reserve_a, reserve_b);
+        let ix = instruction(
+            solana_amm::instruction::AddLiquidity {
+                amount_a: reserve_a / 10,
+                amount_b: reserve_b / 10,
+                deadline: None,
+            },
+            pool.add_liquidity_accounts(),
+        );
+        scenarios.push(Scenario::new(format!("add_liquidity_protocol_fee_{size}"), pool, ix));
+    }
+
+    // 开启价格偏离保护的兑换，多出参考价的计算（观测查找 + exp2）
+    let (_, reserve_a, reserve_b) = RESERVE_SIZES[1];
+    let pool = TestPool::with_liquidity(reserve_a, reserve_b);
+    assert_success(&pool.set_max_price_deviation(&pool.admin, 500));
+    let ix = instruction(
+        solana_amm::instruction::Swap {
+            amount_in: reserve_a / 100,
+            is_a_to_b: true,
+            min_amount_out: 0,
+            deadline: None,
+            referral_fee_bps: 0,
+        },
+        pool.swap_accounts(),
+    );
+    scenarios.push(Scenario::new("swap_price_guard_medium", pool, ix));
+
+    // 带推荐人的兑换，多一次转账给推荐人
+    let mut pool = TestPool::with_liquidity(reserve_a, reserve_b);
+    assert_success(&pool.set_max_referral_fee(&pool.admin, 50));
+    let mint_a = pool.mint_a;
+    let referrer = pool.create_token_account(&mint_a, &Pubkey::new_unique(), 0);
+    let ix = instruction(
+        solana_amm::instruction::Swap {
+            amount_in: reserve_a / 100,
+            is_a_to_b: true,
+            min_amount_out: 0,
+            deadline: None,
+            referral_fee_bps: 30,
+        },
+        solana_amm::accounts::Swap {
+            referrer_token_account: Some(referrer),
+            ..pool.swap_accounts()
+        },
+    );
+    scenarios.push(Scenario::new("swap_referral_medium", pool, ix));
+
+    // 带折扣费率的兑换，多一次 FeeOverride 的读取和校验
+    let pool = TestPool::with_liquidity(reserve_a, reserve_b);
+    assert_success(&pool.set_fee_override(&pool.admin, &pool.user, 1));
+    let mut ix = instruction(
+        solana_amm::instruction::Swap {
+            amount_in: reserve_a / 100,
+            is_a_to_b: true,
+            min_amount_out: 0,
+            deadline: None,
+            referral_fee_bps: 0,
+        },
+        pool.swap_accounts(),
+    );
+    ix.accounts.push(AccountMeta::new_readonly(address(&pool.fee_override_address(&pool.user)), false));
+    scenarios.push(Scenario::new("swap_fee_override_medium", pool, ix));
+
+    // LP 锁仓：首次锁仓要创建锁仓记录和托管账户
+    let pool = TestPool::with_liquidity(reserve_a, reserve_b);
+    let amount = pool.token_amount(&pool.user_lp) / 2;
+    let unlock_ts = pool.context.mollusk.sysvars.clock.unix_timestamp + 86_400;
+    let ix = instruction(
+        solana_amm::instruction::LockLp { amount, unlock_ts },
+        solana_amm::accounts::LockLp {
+            pool_state: pool.pool_state,
+            pool_authority: pool.pool_authority,
+            token_a_vault: pool.token_a_vault,
+            token_b_vault: pool.token_b_vault,
+            lp_mint: pool.lp_mint,
+            lp_lock: pool.lp_lock_address(&pool.user),
+            lock_vault: pool.lp_lock_vault_address(&pool.user),
+            user_lp_token: pool.user_lp,
+            user: pool.user,
+            system_program: anchor_lang::system_program::ID,
+            token_program: anchor_spl::token::ID,
+            twamm: None,
+            twamm_vault_a: None,
+            twamm_vault_b: None,
+        },
+    );
+    scenarios.push(Scenario::new("lock_lp", pool, ix));
+
+    // 锁仓期间领取手续费，路径和 remove_liquidity 相近
+    let pool = TestPool::with_liquidity(reserve_a, reserve_b);
+    assert_success(&pool.lock_lp(amount, unlock_ts));
+    generate_fees(&pool, reserve_a, reserve_b);
+    let ix = instruction(
+        solana_amm::instruction::ClaimLpLockFees {},
+        solana_amm::accounts::ClaimLpLockFees {
+            pool_state: pool.pool_state,
+            pool_authority: pool.pool_authority,
+            lp_lock: pool.lp_lock_address(&pool.user),
+            lock_vault: pool.lp_lock_vault_address(&pool.user),
+            user_token_a: pool.user_token_a,
+            user_token_b: pool.user_token_b,
+            token_a_vault: pool.token_a_vault,
+            token_b_vault: pool.token_b_vault,
+            lp_mint: pool.lp_mint,
+            protocol_fee_recipient: pool.protocol_fee_recipient,
+            user: pool.user,
+            token_program: anchor_spl::token::ID,
+            twamm: None,
+            twamm_vault_a: None,
+            twamm_vault_b: None,
+            access_pass: None,
+            pool_gatekeeper: None,
+        },
+    );
+    scenarios.push(Scenario::new("claim_lp_lock_fees", pool, ix));
+
+    // 流动性挖矿：已有两种奖励时质押，需要逐个累加 reward_per_share 并结算
+    let (pool, _) = farm_pool(reserve_a, reserve_b);
+    let ix = instruction(
+        solana_amm::instruction::StakeLp { amount: amount / 2 },
+        solana_amm::accounts::StakeLp {
+            pool_state: pool.pool_state,
+            lp_mint: pool.lp_mint,
+            farm: pool.farm_address(),
+            stake_vault: pool.farm_stake_vault_address(),
+            farm_stake: pool.farm_stake_address(&pool.user),
+            user_lp_token: pool.user_lp,
+            user: pool.user,
+            system_program: anchor_lang::system_program::ID,
+            token_program: anchor_spl::token::ID,
+        },
+    );
+    scenarios.push(Scenario::new("stake_lp", pool, ix));
+
+    let (mut pool, reward_mint) = farm_pool(reserve_a, reserve_b);
+    let user = pool.user;
+    let user_reward = pool.create_token_account(&reward_mint, &user, 0);
+    let ix = instruction(
+        solana_amm::instruction::ClaimRewards { reward_index: 0 },
+        solana_amm::accounts::ClaimRewards {
+            pool_state: pool.pool_state,
+            pool_authority: pool.pool_authority,
+            farm: pool.farm_address(),
+            farm_stake: pool.farm_stake_address(&pool.user),
+            reward_vault: pool.farm_reward_vault_address(&reward_mint),
+            user_reward_token: user_reward,
+            user: pool.user,
+            token_program: anchor_spl::token::ID,
+        },
+    );
+    scenarios.push(Scenario::new("claim_rewards", pool, ix));
+
+    // 缓冲区写满后读取，覆盖 observation_before 的完整遍历和两次 exp2
+    let (_, reserve_a, reserve_b) = RESERVE_SIZES[1];
+    let mut pool = TestPool::with_liquidity(reserve_a, reserve_b);
+    for _ in 0..OBSERVATION_COUNT {
+        pool.advance_clock(OBSERVATION_INTERVAL);
+        assert_success(&pool.swap(reserve_a / 100, true, 0));
+    }
+    let window = OBSERVATION_INTERVAL * (OBSERVATION_COUNT as u64 - 1);
+    let ix = instruction(
+        solana_amm::instruction::GetGeometricTwap { window },
+        pool.get_geometric_twap_accounts(),
+    );
+    scenarios.push(Scenario::new("get_geometric_twap", pool, ix));
+
+    let pool = TestPool::initialized();
+    let ix = instruction(
+        solana_amm::instruction::UpdateConfig {
+            new_admin: Some(pool.user),
+            new_recipient: Some(pool.user),
+            new_share: Some(5),
+            new_max_price_deviation_bps: Some(500),
+            new_max_referral_fee_bps: Some(50),
+        },
+        solana_amm::accounts::UpdateConfig {
+            pool_state: pool.pool_state,
+            admin: pool.admin,
+        },
+    );
+    scenarios.push(Scenario::new("update_config", pool, ix));
+
+    let pool = TestPool::initialized();
+    assert_success(&pool.update_config(&pool.admin, Some(pool.user), None, None));
+    let ix = instruction(
+        solana_amm::instruction::ClaimAdmin {},
+        solana_amm::accounts::ClaimAdmin {
+            pool_state: pool.pool_state,
+            pending_admin: pool.user,
+        },
+    );
+    scenarios.push(Scenario::new("claim_admin", pool, ix));
+
+    // 零拷贝之前的 Borsh 布局转换成当前布局的一次性开销
+    let (_, reserve_a, reserve_b) = RESERVE_SIZES[1];
+    let mut pool = TestPool::with_liquidity(reserve_a, reserve_b);
+    pool.rewrite_as_legacy(1, LegacyPoolState::LEN);
+    let ix = instruction(
+        solana_amm::instruction::MigratePool {},
+        solana_amm::accounts::MigratePool {
+            pool_state: pool.pool_state,
+            payer: pool.user,
+            system_program: anchor_lang::system_program::ID,
+        },
+    );
+    scenarios.push(Scenario::new("migrate_pool", pool, ix));
+
+    // 集中流动性兑换：区间内的一段，以及穿过一个已初始化的 tick
+    for (name, amount_in) in [("cl_swap_in_range", 1_000_000), ("cl_swap_cross_tick", 100_000_000_000)] {
+        let pool = TestPool::new();
+        assert_success(&pool.create_cl_pool(1 << 64));
+        assert_success(&pool.init_tick_array(-320));
+        assert_success(&pool.init_tick_array(0));
+        for (lower, upper) in [(-100, 100), (-300, 300)] {
+            assert_success(&pool.open_position(lower, upper));
+            assert_success(&pool.increase_liquidity(lower, upper, 1_000_000_000_000));
+        }
+        let ix = pool.cl_swap_instruction(amount_in, true, None, &[0, -320]);
+        scenarios.push(Scenario::new(name, pool, ix));
+    }
+
+    // keeper 一次执行一笔限价单
+    let (_, reserve_a, reserve_b) = RESERVE_SIZES[1];
+    let pool = TestPool::with_liquidity(reserve_a, reserve_b);
+    assert_success(&pool.place_order(1, reserve_a / 100, true, 1, 5_000));
+    let ix = pool.fill_orders_instruction(&pool.admin, &[(1, true)]);
+    scenarios.push(Scenario::new("fill_orders", pool, ix));
+
+    // TWAMM：两个方向都有长期订单，结算跨过几个间隔边界
+    let mut pool = TestPool::with_liquidity(reserve_a, reserve_b);
+    assert_success(&pool.init_twamm(&pool.admin));
+    assert_success(&pool.place_long_term_order(1, reserve_a / 100, true, 8));
+    assert_success(&pool.place_long_term_order(2, reserve_b / 200, false, 4));
+    pool.advance_clock(6 * solana_amm::TWAMM_INTERVAL);
+    let ix = pool.execute_twamm_instruction();
+    scenarios.push(Scenario::new("execute_twamm", pool, ix));
+
+    // 开启了 TWAMM 的池子上的兑换，先结算一个间隔内的长期订单
+    let mut pool = TestPool::with_liquidity(reserve_a, reserve_b);
+    assert_success(&pool.init_twamm(&pool.admin));
+    assert_success(&pool.place_long_term_order(1, reserve_a / 100, true, 8));
+    pool.advance_clock(600);
+    let ix = instruction(
+        solana_amm::instruction::Swap {
+            amount_in: reserve_a / 100,
+            is_a_to_b: true,
+            min_amount_out: 0,
+            deadline: None,
+            referral_fee_bps: 0,
+        },
+        pool.swap_accounts(),
+    );
+    scenarios.push(Scenario::new("swap_twamm_medium", pool, ix));
+
+    // keeper 执行一期定投
+    let pool = TestPool::with_liquidity(reserve_a, reserve_b);
+    assert_success(&pool.open_dca(1, reserve_a / 100, true, reserve_a / 1000, 3_600, 0));
+    let ix = pool.execute_dca_instruction(&pool.admin, 1);
+    scenarios.push(Scenario::new("execute_dca", pool, ix));
+
+    // 批量拍卖：一批满额的订单，两个方向各一半
+    let mut pool = TestPool::with_liquidity(reserve_a, reserve_b);
+    assert_success(&pool.init_batch_auction(&pool.admin, 1, 1));
+    let mut traders = vec![pool.trader()];
+    while traders.len() < solana_amm::MAX_BATCH_ORDERS as usize {
+        traders.push(pool.new_trader());
+    }
+    for (i, &trader) in traders.iter().enumerate() {
+        let is_a_to_b = i % 2 == 0;
+        let amount_in = if is_a_to_b { reserve_a / 1000 } else { reserve_b / 1000 };
+        assert_success(&pool.submit_batch_swap(trader, amount_in, is_a_to_b, 0));
+    }
+    pool.advance_slot(1);
+    let ix = pool.settle_batch_instruction(&traders);
+    scenarios.push(Scenario::new("settle_batch", pool, ix));
+
+    let names: Vec<String> = scenarios.iter().map(|scenario| scenario.name.clone()).collect();
+    assert_eq!(names, scenario_names(), "scenario_names() is out of sync with scenarios()");
+    scenarios
+}
+
+/// 零拷贝之前的程序文件，找不到时 panic 并提示构建方法
+pub fn borsh_baseline_elf() -> Vec<u8> {
+    let path = deploy_dir().join(BORSH_BASELINE_PROGRAM);
+    std::fs::read(&path).unwrap_or_else(|err| {
+        panic!(
+            "cannot read {}: {err}; build the commit before `PoolState` switched to zero-copy and copy its \
+             solana_amm.so there (see README)",
+            path.display()
+        )
+    })
+}
+
+/// 对比 Borsh 布局和零拷贝布局的热路径场景：每个储备量级的兑换、添加和移除流动性
+///
+/// `borsh_elf` 为 `None` 时在当前程序上执行；否则池子先用当前程序建好，再把 `PoolState` 改写成版本 0 的
+/// Borsh 布局，换上旧程序执行同一条指令。这几条指令的参数没有变过，账户列表只在末尾多了可选账户，
+/// 旧程序把它们当作 remaining accounts 忽略。两次调用返回的场景一一对应，名字相同。
+pub fn layout_scenarios(borsh_elf: Option<&[u8]>) -> Vec<Scenario> {
+    let pool_with_layout = |reserve_a: u64, reserve_b: u64| {
+        let mut pool = TestPool::with_liquidity(reserve_a, reserve_b);
+        if let Some(elf) = borsh_elf {
+            pool.rewrite_as_legacy(0, LEGACY_POOL_V0_LEN);
+            pool.context.mollusk.add_program_with_loader_and_elf(
+                &address(&solana_amm::ID),
+                &loader_keys::LOADER_V3,
+                elf,
+            );
+        }
+        pool
+    };
+
+    let mut scenarios = Vec::new();
+    for (size, reserve_a, reserve_b) in RESERVE_SIZES {
+        for (direction, is_a_to_b, amount_in) in
+            [("a_to_b", true, reserve_a / 100), ("b_to_a", false, reserve_b / 100)]
+        {
+            let pool = pool_with_layout(reserve_a, reserve_b);
+            let ix = instruction(
+                solana_amm::instruction::Swap {
+                    amount_in,
+                    is_a_to_b,
+                    min_amount_out: 0,
+                    deadline: None,
+                    referral_fee_bps: 0,
+                },
+                pool.swap_accounts(),
+            );
+            scenarios.push(Scenario::new(format!("swap_{direction}_{size}"), pool, ix));
+        }
+
+        let pool = pool_with_layout(reserve_a, reserve_b);
+        let ix = instruction(
+            solana_amm::instruction::AddLiquidity {
+                amount_a: reserve_a / 10,
+                amount_b: reserve_b / 10,
+                deadline: None,
+            },
+            pool.add_liquidity_accounts(),
+        );
+        scenarios.push(Scenario::new(format!("add_liquidity_{size}"), pool, ix));
+
+        let pool = pool_with_layout(reserve_a, reserve_b);
+        let amount_lp = pool.token_amount(&pool.user_lp) / 10;
+        let ix = instruction(
+            solana_amm::instruction::RemoveLiquidity {
+                amount_lp,
+                min_amount_a: 0,
+                min_amount_b: 0,
+                deadline: None,
+            },
+            pool.remove_liquidity_accounts(),
+        );
+        scenarios.push(Scenario::new(format!("remove_liquidity_{size}"), pool, ix));
+    }
+    scenarios
+}
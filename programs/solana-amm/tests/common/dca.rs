@@ -0,0 +1,129 @@
+//! 定投（DCA）相关的账户地址和指令
+
+use super::*;
+
+impl TestPool {
+    pub fn dca_vault_address(&self, dca_id: u64) -> Pubkey {
+        Pubkey::find_program_address(
+            &[b"dca_vault", self.pool_state.as_ref(), self.user.as_ref(), &dca_id.to_le_bytes()],
+            &solana_amm::ID,
+        )
+        .0
+    }
+
+    pub fn dca_input_address(&self, dca_id: u64) -> Pubkey {
+        Pubkey::find_program_address(&[b"dca_input", self.dca_vault_address(dca_id).as_ref()], &solana_amm::ID).0
+    }
+
+    pub fn dca_output_address(&self, dca_id: u64) -> Pubkey {
+        Pubkey::find_program_address(&[b"dca_output", self.dca_vault_address(dca_id).as_ref()], &solana_amm::ID).0
+    }
+
+    pub fn dca_vault(&self, dca_id: u64) -> Option<solana_amm::DcaVault> {
+        let account = self.account(&self.dca_vault_address(dca_id))?;
+        solana_amm::DcaVault::try_deserialize(&mut account.data.as_slice()).ok()
+    }
+
+    // (token_in_mint, token_out_mint, user_token_in, user_token_out)
+    fn dca_direction(&self, is_a_to_b: bool) -> (Pubkey, Pubkey, Pubkey, Pubkey) {
+        if is_a_to_b {
+            (self.mint_a, self.mint_b, self.user_token_a, self.user_token_b)
+        } else {
+            (self.mint_b, self.mint_a, self.user_token_b, self.user_token_a)
+        }
+    }
+
+    pub fn open_dca(
+        &self,
+        dca_id: u64,
+        amount_in: u64,
+        is_a_to_b: bool,
+        amount_per_cycle: u64,
+        cycle_interval: i64,
+        min_amount_out_per_cycle: u64,
+    ) -> InstructionResult {
+        let (token_in_mint, token_out_mint, user_token_in, _) = self.dca_direction(is_a_to_b);
+        self.process(
+            solana_amm::instruction::OpenDca {
+                dca_id,
+                amount_in,
+                is_a_to_b,
+                amount_per_cycle,
+                cycle_interval,
+                min_amount_out_per_cycle,
+            },
+            solana_amm::accounts::OpenDca {
+                pool_state: self.pool_state,
+                pool_authority: self.pool_authority,
+                token_in_mint,
+                token_out_mint,
+                dca_vault: self.dca_vault_address(dca_id),
+                input_vault: self.dca_input_address(dca_id),
+                output_vault: self.dca_output_address(dca_id),
+                user_token_in,
+                owner: self.user,
+                system_program: anchor_lang::system_program::ID,
+                token_program: anchor_spl::token::ID,
+            },
+        )
+    }
+
+    pub fn execute_dca_instruction(&self, keeper: &Pubkey, dca_id: u64) -> Instruction {
+        let (twamm, twamm_vault_a, twamm_vault_b) = self.twamm_accounts();
+        instruction(
+            solana_amm::instruction::ExecuteDca {},
+            solana_amm::accounts::ExecuteDca {
+                pool_state: self.pool_state,
+                pool_authority: self.pool_authority,
+                token_a_vault: self.token_a_vault,
+                token_b_vault: self.token_b_vault,
+                dca_vault: self.dca_vault_address(dca_id),
+                input_vault: self.dca_input_address(dca_id),
+                output_vault: self.dca_output_address(dca_id),
+                keeper: *keeper,
+                token_program: anchor_spl::token::ID,
+                twamm,
+                twamm_vault_a,
+                twamm_vault_b,
+            },
+        )
+    }
+
+    pub fn execute_dca(&self, keeper: &Pubkey, dca_id: u64) -> InstructionResult {
+        self.context.process_instruction(&self.execute_dca_instruction(keeper, dca_id))
+    }
+
+    pub fn withdraw_dca(&self, dca_id: u64, is_a_to_b: bool) -> InstructionResult {
+        let (_, _, _, user_token_out) = self.dca_direction(is_a_to_b);
+        self.process(
+            solana_amm::instruction::WithdrawDca {},
+            solana_amm::accounts::WithdrawDca {
+                pool_state: self.pool_state,
+                pool_authority: self.pool_authority,
+                dca_vault: self.dca_vault_address(dca_id),
+                output_vault: self.dca_output_address(dca_id),
+                user_token_out,
+                owner: self.user,
+                token_program: anchor_spl::token::ID,
+            },
+        )
+    }
+
+    pub fn close_dca(&self, dca_id: u64, is_a_to_b: bool) -> InstructionResult {
+        let (_, _, user_token_in, user_token_out) = self.dca_direction(is_a_to_b);
+        self.process(
+            solana_amm::instruction::CloseDca {},
+            solana_amm::accounts::CloseDca {
+                pool_state: self.pool_state,
+                pool_authority: self.pool_authority,
+                dca_vault: self.dca_vault_address(dca_id),
+                input_vault: self.dca_input_address(dca_id),
+                output_vault: self.dca_output_address(dca_id),
+                user_token_in,
+                user_token_out,
+                owner: self.user,
+                token_program: anchor_spl::token::ID,
+            },
+        )
+    }
+}
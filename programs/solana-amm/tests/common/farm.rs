@@ -0,0 +1,140 @@
+//! 流动性挖矿相关的账户地址和指令
+
+use super::*;
+
+impl TestPool {
+    pub fn farm_address(&self) -> Pubkey {
+        Pubkey::find_program_address(&[b"farm", self.pool_state.as_ref()], &solana_amm::ID).0
+    }
+
+    pub fn farm_stake_vault_address(&self) -> Pubkey {
+        Pubkey::find_program_address(
+            &[b"farm_stake_vault", self.farm_address().as_ref()],
+            &solana_amm::ID,
+        )
+        .0
+    }
+
+    pub fn farm_reward_vault_address(&self, reward_mint: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(
+            &[b"farm_reward_vault", self.farm_address().as_ref(), reward_mint.as_ref()],
+            &solana_amm::ID,
+        )
+        .0
+    }
+
+    pub fn farm_stake_address(&self, owner: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(
+            &[b"farm_stake", self.farm_address().as_ref(), owner.as_ref()],
+            &solana_amm::ID,
+        )
+        .0
+    }
+
+    pub fn farm(&self) -> Option<solana_amm::Farm> {
+        let account = self.account(&self.farm_address())?;
+        solana_amm::Farm::try_deserialize(&mut account.data.as_slice()).ok()
+    }
+
+    pub fn farm_stake(&self, owner: &Pubkey) -> Option<solana_amm::FarmStake> {
+        let account = self.account(&self.farm_stake_address(owner))?;
+        solana_amm::FarmStake::try_deserialize(&mut account.data.as_slice()).ok()
+    }
+
+    /// 创建一个奖励代币，管理员持有 `amount` 个，返回 (mint, 管理员的代币账户)
+    pub fn create_reward_mint(&mut self, amount: u64) -> (Pubkey, Pubkey) {
+        let mint = Pubkey::new_unique();
+        self.set_account(&mint, mint_account(6));
+        let admin = self.admin;
+        (mint, self.create_token_account(&mint, &admin, amount))
+    }
+
+    pub fn create_farm(&self, signer: &Pubkey) -> InstructionResult {
+        self.process(
+            solana_amm::instruction::CreateFarm {},
+            solana_amm::accounts::CreateFarm {
+                pool_state: self.pool_state,
+                pool_authority: self.pool_authority,
+                lp_mint: self.lp_mint,
+                farm: self.farm_address(),
+                stake_vault: self.farm_stake_vault_address(),
+                admin: *signer,
+                system_program: anchor_lang::system_program::ID,
+                token_program: anchor_spl::token::ID,
+            },
+        )
+    }
+
+    pub fn add_farm_reward(
+        &self,
+        reward_mint: &Pubkey,
+        admin_reward_token: &Pubkey,
+        emission_per_second: u64,
+        end_ts: i64,
+    ) -> InstructionResult {
+        self.process(
+            solana_amm::instruction::AddFarmReward { emission_per_second, end_ts },
+            solana_amm::accounts::AddFarmReward {
+                pool_state: self.pool_state,
+                pool_authority: self.pool_authority,
+                farm: self.farm_address(),
+                reward_mint: *reward_mint,
+                reward_vault: self.farm_reward_vault_address(reward_mint),
+                admin_reward_token: *admin_reward_token,
+                admin: self.admin,
+                system_program: anchor_lang::system_program::ID,
+                token_program: anchor_spl::token::ID,
+            },
+        )
+    }
+
+    pub fn stake_lp(&self, amount: u64) -> InstructionResult {
+        self.process(
+            solana_amm::instruction::StakeLp { amount },
+            solana_amm::accounts::StakeLp {
+                pool_state: self.pool_state,
+                lp_mint: self.lp_mint,
+                farm: self.farm_address(),
+                stake_vault: self.farm_stake_vault_address(),
+                farm_stake: self.farm_stake_address(&self.user),
+                user_lp_token: self.user_lp,
+                user: self.user,
+                system_program: anchor_lang::system_program::ID,
+                token_program: anchor_spl::token::ID,
+            },
+        )
+    }
+
+    pub fn unstake_lp(&self, amount: u64) -> InstructionResult {
+        self.process(
+            solana_amm::instruction::UnstakeLp { amount },
+            solana_amm::accounts::UnstakeLp {
+                pool_state: self.pool_state,
+                pool_authority: self.pool_authority,
+                lp_mint: self.lp_mint,
+                farm: self.farm_address(),
+                stake_vault: self.farm_stake_vault_address(),
+                farm_stake: self.farm_stake_address(&self.user),
+                user_lp_token: self.user_lp,
+                user: self.user,
+                token_program: anchor_spl::token::ID,
+            },
+        )
+    }
+
+    pub fn claim_rewards(&self, reward_index: u8, reward_mint: &Pubkey, user_reward_token: &Pubkey) -> InstructionResult {
+        self.process(
+            solana_amm::instruction::ClaimRewards { reward_index },
+            solana_amm::accounts::ClaimRewards {
+                pool_state: self.pool_state,
+                pool_authority: self.pool_authority,
+                farm: self.farm_address(),
+                farm_stake: self.farm_stake_address(&self.user),
+                reward_vault: self.farm_reward_vault_address(reward_mint),
+                user_reward_token: *user_reward_token,
+                user: self.user,
+                token_program: anchor_spl::token::ID,
+            },
+        )
+    }
+}
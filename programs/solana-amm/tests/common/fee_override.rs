@@ -0,0 +1,53 @@
+//! 折扣费率（FeeOverride）相关的账户地址和指令
+
+use super::*;
+
+impl TestPool {
+    pub fn fee_override_address(&self, trader: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(
+            &[b"fee_override", self.pool_state.as_ref(), trader.as_ref()],
+            &solana_amm::ID,
+        )
+        .0
+    }
+
+    pub fn set_fee_override(&self, signer: &Pubkey, trader: &Pubkey, fee_numerator: u64) -> InstructionResult {
+        self.process(
+            solana_amm::instruction::SetFeeOverride { fee_numerator },
+            solana_amm::accounts::SetFeeOverride {
+                pool_state: self.pool_state,
+                trader: *trader,
+                fee_override: self.fee_override_address(trader),
+                admin: *signer,
+                system_program: anchor_lang::system_program::ID,
+            },
+        )
+    }
+
+    pub fn remove_fee_override(&self, signer: &Pubkey, trader: &Pubkey) -> InstructionResult {
+        self.process(
+            solana_amm::instruction::RemoveFeeOverride {},
+            solana_amm::accounts::RemoveFeeOverride {
+                pool_state: self.pool_state,
+                fee_override: self.fee_override_address(trader),
+                admin: *signer,
+            },
+        )
+    }
+
+    /// 把 `fee_override` 作为 remaining account 传入的 swap
+    pub fn swap_with_fee_override(&self, fee_override: &Pubkey, amount_in: u64, is_a_to_b: bool) -> InstructionResult {
+        let mut ix = instruction(
+            solana_amm::instruction::Swap {
+                amount_in,
+                is_a_to_b,
+                min_amount_out: 0,
+                deadline: None,
+                referral_fee_bps: 0,
+            },
+            self.swap_accounts(),
+        );
+        ix.accounts.push(AccountMeta::new_readonly(address(fee_override), false));
+        self.context.process_instruction(&ix)
+    }
+}
@@ -0,0 +1,111 @@
+//! 限价单相关的账户地址和指令
+
+use super::*;
+
+impl TestPool {
+    pub fn limit_order_address(&self, order_id: u64) -> Pubkey {
+        Pubkey::find_program_address(
+            &[b"limit_order", self.pool_state.as_ref(), self.user.as_ref(), &order_id.to_le_bytes()],
+            &solana_amm::ID,
+        )
+        .0
+    }
+
+    pub fn order_escrow_address(&self, order_id: u64) -> Pubkey {
+        Pubkey::find_program_address(
+            &[b"order_escrow", self.limit_order_address(order_id).as_ref()],
+            &solana_amm::ID,
+        )
+        .0
+    }
+
+    pub fn limit_order(&self, order_id: u64) -> Option<solana_amm::LimitOrder> {
+        let account = self.account(&self.limit_order_address(order_id))?;
+        solana_amm::LimitOrder::try_deserialize(&mut account.data.as_slice()).ok()
+    }
+
+    pub fn place_order(
+        &self,
+        order_id: u64,
+        amount_in: u64,
+        is_a_to_b: bool,
+        min_amount_out: u64,
+        keeper_bounty: u64,
+    ) -> InstructionResult {
+        let (token_in_mint, user_token_in) = if is_a_to_b {
+            (self.mint_a, self.user_token_a)
+        } else {
+            (self.mint_b, self.user_token_b)
+        };
+        self.process(
+            solana_amm::instruction::PlaceOrder {
+                order_id,
+                amount_in,
+                is_a_to_b,
+                min_amount_out,
+                keeper_bounty,
+            },
+            solana_amm::accounts::PlaceOrder {
+                pool_state: self.pool_state,
+                pool_authority: self.pool_authority,
+                token_in_mint,
+                limit_order: self.limit_order_address(order_id),
+                escrow: self.order_escrow_address(order_id),
+                user_token_in,
+                owner: self.user,
+                system_program: anchor_lang::system_program::ID,
+                token_program: anchor_spl::token::ID,
+            },
+        )
+    }
+
+    pub fn cancel_order(&self, order_id: u64, user_token_in: &Pubkey) -> InstructionResult {
+        self.process(
+            solana_amm::instruction::CancelOrder {},
+            solana_amm::accounts::CancelOrder {
+                pool_state: self.pool_state,
+                pool_authority: self.pool_authority,
+                limit_order: self.limit_order_address(order_id),
+                escrow: self.order_escrow_address(order_id),
+                user_token_in: *user_token_in,
+                owner: self.user,
+                token_program: anchor_spl::token::ID,
+            },
+        )
+    }
+
+    /// 由 `keeper` 执行用户的订单，A -> B 的订单输出到 user_token_b，反之到 user_token_a
+    pub fn fill_orders_instruction(&self, keeper: &Pubkey, orders: &[(u64, bool)]) -> Instruction {
+        let twamm_accounts = self.twamm_accounts();
+        let mut instruction = instruction(
+            solana_amm::instruction::FillOrders {},
+            solana_amm::accounts::FillOrders {
+                pool_state: self.pool_state,
+                pool_authority: self.pool_authority,
+                token_a_vault: self.token_a_vault,
+                token_b_vault: self.token_b_vault,
+                keeper: *keeper,
+                token_program: anchor_spl::token::ID,
+                twamm: twamm_accounts.0,
+                twamm_vault_a: twamm_accounts.1,
+                twamm_vault_b: twamm_accounts.2,
+            },
+        );
+        for &(order_id, is_a_to_b) in orders {
+            let owner_token_out = if is_a_to_b { self.user_token_b } else { self.user_token_a };
+            for key in [
+                self.limit_order_address(order_id),
+                self.order_escrow_address(order_id),
+                owner_token_out,
+                self.user,
+            ] {
+                instruction.accounts.push(AccountMeta::new(address(&key), false));
+            }
+        }
+        instruction
+    }
+
+    pub fn fill_orders(&self, keeper: &Pubkey, orders: &[(u64, bool)]) -> InstructionResult {
+        self.context.process_instruction(&self.fill_orders_instruction(keeper, orders))
+    }
+}
@@ -0,0 +1,91 @@
+//! LP 锁仓相关的账户地址和指令
+
+use super::*;
+
+impl TestPool {
+    pub fn lp_lock_address(&self, owner: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(
+            &[b"lp_lock", self.pool_state.as_ref(), owner.as_ref()],
+            &solana_amm::ID,
+        )
+        .0
+    }
+
+    pub fn lp_lock_vault_address(&self, owner: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(
+            &[b"lp_lock_vault", self.lp_lock_address(owner).as_ref()],
+            &solana_amm::ID,
+        )
+        .0
+    }
+
+    pub fn lp_lock(&self, owner: &Pubkey) -> Option<solana_amm::LpLock> {
+        let account = self.account(&self.lp_lock_address(owner))?;
+        solana_amm::LpLock::try_deserialize(&mut account.data.as_slice()).ok()
+    }
+
+    pub fn lock_lp(&self, amount: u64, unlock_ts: i64) -> InstructionResult {
+        let twamm_accounts = self.twamm_accounts();
+        self.process(
+            solana_amm::instruction::LockLp { amount, unlock_ts },
+            solana_amm::accounts::LockLp {
+                pool_state: self.pool_state,
+                pool_authority: self.pool_authority,
+                token_a_vault: self.token_a_vault,
+                token_b_vault: self.token_b_vault,
+                lp_mint: self.lp_mint,
+                lp_lock: self.lp_lock_address(&self.user),
+                lock_vault: self.lp_lock_vault_address(&self.user),
+                user_lp_token: self.user_lp,
+                user: self.user,
+                system_program: anchor_lang::system_program::ID,
+                token_program: anchor_spl::token::ID,
+                twamm: twamm_accounts.0,
+                twamm_vault_a: twamm_accounts.1,
+                twamm_vault_b: twamm_accounts.2,
+            },
+        )
+    }
+
+    pub fn unlock_lp(&self) -> InstructionResult {
+        self.process(
+            solana_amm::instruction::UnlockLp {},
+            solana_amm::accounts::UnlockLp {
+                pool_state: self.pool_state,
+                pool_authority: self.pool_authority,
+                lp_mint: self.lp_mint,
+                lp_lock: self.lp_lock_address(&self.user),
+                lock_vault: self.lp_lock_vault_address(&self.user),
+                user_lp_token: self.user_lp,
+                user: self.user,
+                token_program: anchor_spl::token::ID,
+            },
+        )
+    }
+
+    pub fn claim_lp_lock_fees(&self) -> InstructionResult {
+        let twamm_accounts = self.twamm_accounts();
+        self.process(
+            solana_amm::instruction::ClaimLpLockFees {},
+            solana_amm::accounts::ClaimLpLockFees {
+                pool_state: self.pool_state,
+                pool_authority: self.pool_authority,
+                lp_lock: self.lp_lock_address(&self.user),
+                lock_vault: self.lp_lock_vault_address(&self.user),
+                user_token_a: self.user_token_a,
+                user_token_b: self.user_token_b,
+                token_a_vault: self.token_a_vault,
+                token_b_vault: self.token_b_vault,
+                lp_mint: self.lp_mint,
+                protocol_fee_recipient: self.protocol_fee_recipient,
+                user: self.user,
+                token_program: anchor_spl::token::ID,
+                twamm: twamm_accounts.0,
+                twamm_vault_a: twamm_accounts.1,
+                twamm_vault_b: twamm_accounts.2,
+                access_pass: self.access_pass_account(&self.user),
+                pool_gatekeeper: self.pool_gatekeeper_account(),
+            },
+        )
+    }
+}
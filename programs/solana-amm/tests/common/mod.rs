@@ -0,0 +1,715 @@
+//! Mollusk 测试的公共部分
+//!
+//! 测试直接加载编译好的程序 `target/deploy/solana_amm.so`（或 `SBF_OUT_DIR` 下的同名文件），
+//! 需要先执行 `anchor build`。依赖程序文件的测试都标记为 `#[ignore]`，构建之后用
+//! `cargo test -p solana-amm -- --include-ignored` 运行；找不到程序文件时 `TestPool::new` 直接 panic，
+//! 不会悄悄跳过。
+//!
+//! Mollusk 使用 solana 3.x 的类型，Anchor 0.32 使用 2.x 的 `Pubkey`，
+//! 两者之间通过 `address` 按字节转换。
+#![allow(dead_code)]
+
+mod batch_auction;
+mod clmm;
+pub mod compute_units;
+mod dca;
+mod farm;
+mod fee_override;
+mod limit_order;
+mod lp_lock;
+mod permission;
+mod registry;
+mod twamm;
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use anchor_lang::error::ERROR_CODE_OFFSET;
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::{
+    AccountDeserialize, AnchorDeserialize, AnchorSerialize, Discriminator, InstructionData, ToAccountMetas,
+};
+use anchor_spl::associated_token::get_associated_token_address;
+use mollusk_svm::program::loader_keys;
+use mollusk_svm::result::{InstructionResult, ProgramResult};
+use mollusk_svm::{Mollusk, MolluskContext};
+use solana_account::Account;
+use solana_amm::oracle::GeometricTwap;
+use solana_amm::{
+    pda, AmmError, LegacyOracleObservation, LegacyPoolState, PoolRegistry, PoolRegistryPage, PoolState, RegistryPage,
+    TickArray, LEGACY_POOL_RESERVED_BYTES, POOL_V2_LEN,
+};
+use solana_instruction::{AccountMeta, Instruction};
+use solana_program_error::ProgramError;
+use solana_program_pack::Pack;
+use spl_token_interface::state::{Account as TokenAccount, AccountState, Mint};
+
+pub const FEE_NUMERATOR: u64 = 3;
+pub const FEE_DENOMINATOR: u64 = 1000;
+pub const START_TIMESTAMP: u64 = 1_700_000_000;
+pub const USER_BALANCE: u64 = 1_000_000_000_000_000;
+/// 集中流动性测试池子的 tick_spacing
+pub const CL_TICK_SPACING: u16 = 10;
+/// 版本 0 的 `PoolState` 布局长度：`LegacyPoolState` 去掉 version 和 reserved，也就是零拷贝之前程序写入的账户
+pub const LEGACY_POOL_V0_LEN: usize = LegacyPoolState::LEN - 1 - LEGACY_POOL_RESERVED_BYTES;
+
+pub fn address(key: &Pubkey) -> solana_pubkey::Pubkey {
+    solana_pubkey::Pubkey::new_from_array(key.to_bytes())
+}
+
+/// 编译好的程序文件所在目录：`SBF_OUT_DIR`，默认 `target/deploy`
+pub fn deploy_dir() -> PathBuf {
+    std::env::var("SBF_OUT_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../target/deploy"))
+}
+
+/// 编译好的程序文件，找不到时 panic 并提示先构建
+pub fn program_elf() -> Vec<u8> {
+    let path = deploy_dir().join("solana_amm.so");
+    std::fs::read(&path).unwrap_or_else(|err| {
+        panic!("cannot read {}: {err}; run `anchor build` (or `cargo build-sbf`) first", path.display())
+    })
+}
+
+/// 加载了本程序和 SPL Token / ATA 程序的 Mollusk
+pub fn mollusk(elf: &[u8]) -> Mollusk {
+    let mut mollusk = Mollusk::default();
+    mollusk.add_program_with_loader_and_elf(
+        &address(&solana_amm::ID),
+        &loader_keys::LOADER_V3,
+        elf,
+    );
+    mollusk_svm_programs_token::token::add_program(&mut mollusk);
+    mollusk_svm_programs_token::associated_token::add_program(&mut mollusk);
+    mollusk
+}
+
+pub fn instruction(data: impl InstructionData, accounts: impl ToAccountMetas) -> Instruction {
+    Instruction {
+        program_id: address(&solana_amm::ID),
+        accounts: accounts
+            .to_account_metas(None)
+            .into_iter()
+            .map(|meta| AccountMeta {
+                pubkey: address(&meta.pubkey),
+                is_signer: meta.is_signer,
+                is_writable: meta.is_writable,
+            })
+            .collect(),
+        data: data.data(),
+    }
+}
+
+pub fn mint_account(decimals: u8) -> Account {
+    mollusk_svm_programs_token::token::create_account_for_mint(Mint {
+        decimals,
+        is_initialized: true,
+        ..Mint::default()
+    })
+}
+
+pub fn token_account(mint: &Pubkey, owner: &Pubkey, amount: u64) -> Account {
+    mollusk_svm_programs_token::token::create_account_for_token_account(TokenAccount {
+        mint: address(mint),
+        owner: address(owner),
+        amount,
+        state: AccountState::Initialized,
+        ..TokenAccount::default()
+    })
+}
+
+pub fn assert_success(result: &InstructionResult) {
+    assert!(
+        result.program_result.is_ok(),
+        "instruction failed: {:?}",
+        result.program_result
+    );
+}
+
+pub fn assert_error(result: &InstructionResult, error: AmmError) {
+    assert_eq!(
+        result.program_result,
+        ProgramResult::Failure(ProgramError::Custom(ERROR_CODE_OFFSET + error as u32)),
+        "expected {error:?}"
+    );
+}
+
+/// 一个池子以及测试用到的全部账户
+pub struct TestPool {
+    pub context: MolluskContext<HashMap<solana_pubkey::Pubkey, Account>>,
+    pub admin: Pubkey,
+    pub user: Pubkey,
+    pub mint_a: Pubkey,
+    pub mint_b: Pubkey,
+    pub pool_state: Pubkey,
+    pub pool_authority: Pubkey,
+    pub lp_mint: Pubkey,
+    pub token_a_vault: Pubkey,
+    pub token_b_vault: Pubkey,
+    pub user_token_a: Pubkey,
+    pub user_token_b: Pubkey,
+    pub user_lp: Pubkey,
+    pub black_hole_lp: Pubkey,
+    pub protocol_fee_recipient: Pubkey,
+}
+
+impl TestPool {
+    /// 准备好两个 mint 和用户的代币账户，但还没有调用 `initialize`
+    pub fn new() -> Self {
+        let mollusk = mollusk(&program_elf());
+
+        let admin = Pubkey::new_unique();
+        let user = Pubkey::new_unique();
+        let (mint_a, mint_b) = {
+            let (x, y) = (Pubkey::new_unique(), Pubkey::new_unique());
+            if x < y { (x, y) } else { (y, x) }
+        };
+        let lp_mint = Pubkey::new_unique();
+        let (pool_state, _) = Pubkey::find_program_address(
+            &[b"pool", mint_a.as_ref(), mint_b.as_ref()],
+            &solana_amm::ID,
+        );
+        let (pool_authority, _) = Pubkey::find_program_address(&[b"authority"], &solana_amm::ID);
+
+        let mut pool = Self {
+            context: mollusk.with_context(HashMap::new()),
+            admin,
+            user,
+            mint_a,
+            mint_b,
+            pool_state,
+            pool_authority,
+            lp_mint,
+            token_a_vault: get_associated_token_address(&pool_authority, &mint_a),
+            token_b_vault: get_associated_token_address(&pool_authority, &mint_b),
+            user_token_a: Pubkey::new_unique(),
+            user_token_b: Pubkey::new_unique(),
+            user_lp: get_associated_token_address(&user, &lp_mint),
+            black_hole_lp: Pubkey::new_unique(),
+            protocol_fee_recipient: Pubkey::new_unique(),
+        };
+
+        let (user_token_a, user_token_b) = (pool.user_token_a, pool.user_token_b);
+        pool.fund(&admin);
+        pool.fund(&user);
+        pool.set_account(&mint_a, mint_account(6));
+        pool.set_account(&mint_b, mint_account(9));
+        pool.set_account(&user_token_a, token_account(&mint_a, &user, USER_BALANCE));
+        pool.set_account(&user_token_b, token_account(&mint_b, &user, USER_BALANCE));
+        pool.set_clock(START_TIMESTAMP);
+        pool
+    }
+
+    /// 已经以 3/1000 的费率初始化好的池子
+    pub fn initialized() -> Self {
+        let mut pool = Self::new();
+        let result = pool.initialize(FEE_NUMERATOR, FEE_DENOMINATOR);
+        assert_success(&result);
+        pool
+    }
+
+    /// 已经注入了 (amount_a, amount_b) 初始流动性的池子
+    pub fn with_liquidity(amount_a: u64, amount_b: u64) -> Self {
+        let pool = Self::initialized();
+        assert_success(&pool.add_liquidity(amount_a, amount_b));
+        pool
+    }
+
+    pub fn fund(&mut self, key: &Pubkey) {
+        self.set_account(key, Account::new(100_000_000_000, 0, &solana_pubkey::Pubkey::default()));
+    }
+
+    pub fn set_account(&mut self, key: &Pubkey, account: Account) {
+        self.context.account_store.borrow_mut().insert(address(key), account);
+    }
+
+    pub fn remove_account(&mut self, key: &Pubkey) {
+        self.context.account_store.borrow_mut().remove(&address(key));
+    }
+
+    pub fn account(&self, key: &Pubkey) -> Option<Account> {
+        self.context.account_store.borrow().get(&address(key)).cloned()
+    }
+
+    /// 当前账户状态下执行 `instruction` 需要的全部账户，用于无状态的 Mollusk（例如 bencher）
+    pub fn keyed_accounts(&self, instruction: &Instruction) -> Vec<(solana_pubkey::Pubkey, Account)> {
+        let store = self.context.account_store.borrow();
+        let sysvars = self.context.mollusk.sysvars.get_all_keyed_sysvar_accounts();
+        let mut accounts: Vec<(solana_pubkey::Pubkey, Account)> = Vec::new();
+        for meta in &instruction.accounts {
+            if accounts.iter().any(|(key, _)| *key == meta.pubkey) {
+                continue;
+            }
+            let account = store
+                .get(&meta.pubkey)
+                .cloned()
+                .or_else(|| sysvars.iter().find(|(key, _)| *key == meta.pubkey).map(|(_, a)| a.clone()))
+                .unwrap_or_default();
+            accounts.push((meta.pubkey, account));
+        }
+        accounts
+    }
+
+    /// 创建一个新的代币账户
+    pub fn create_token_account(&mut self, mint: &Pubkey, owner: &Pubkey, amount: u64) -> Pubkey {
+        let key = Pubkey::new_unique();
+        self.set_account(&key, token_account(mint, owner, amount));
+        key
+    }
+
+    pub fn set_clock(&mut self, timestamp: u64) {
+        self.context.mollusk.sysvars.clock.unix_timestamp = timestamp as i64;
+    }
+
+    pub fn advance_slot(&mut self, slots: u64) {
+        self.context.mollusk.sysvars.clock.slot += slots;
+    }
+
+    pub fn advance_clock(&mut self, seconds: u64) {
+        self.context.mollusk.sysvars.clock.unix_timestamp += seconds as i64;
+    }
+
+    pub fn token_amount(&self, key: &Pubkey) -> u64 {
+        TokenAccount::unpack(&self.account(key).expect("token account").data)
+            .unwrap()
+            .amount
+    }
+
+    pub fn lp_supply(&self) -> u64 {
+        Mint::unpack(&self.account(&self.lp_mint).expect("lp mint").data)
+            .unwrap()
+            .supply
+    }
+
+    pub fn pool_state(&self) -> PoolState {
+        let account = self.account(&self.pool_state).expect("pool state");
+        // 零拷贝布局，账户数据不一定按 16 字节对齐，复制出来读
+        bytemuck::pod_read_unaligned(&account.data[8..PoolState::LEN])
+    }
+
+    /// 把池子账户改写成 Borsh 布局并截断到 `len`，模拟切换到零拷贝布局之前创建的池子
+    pub fn rewrite_as_legacy(&mut self, version: u8, len: usize) {
+        let state = self.pool_state();
+        let legacy = LegacyPoolState {
+            token_a: state.token_a,
+            token_b: state.token_b,
+            token_a_vault: state.token_a_vault,
+            token_b_vault: state.token_b_vault,
+            lp_mint: state.lp_mint,
+            fee_numerator: state.fee_numerator,
+            fee_denominator: state.fee_denominator,
+            pool_bump: state.pool_bump,
+            auth_bump: state.auth_bump,
+            block_timestamp_last: state.block_timestamp_last,
+            price_a_cumulative_last: state.price_a_cumulative_last,
+            price_b_cumulative_last: state.price_b_cumulative_last,
+            admin: state.admin,
+            pending_admin: state.pending_admin(),
+            protocol_fee_recipient: state.protocol_fee_recipient,
+            protocol_fee_share: state.protocol_fee_share,
+            k_last: state.k_last,
+            log_price_cumulative_last: state.log_price_cumulative_last,
+            observation_index: state.observation_index,
+            observation_count: state.observation_count,
+            observations: state.observations.map(|observation| LegacyOracleObservation {
+                timestamp: observation.timestamp,
+                log_price_cumulative: observation.log_price_cumulative,
+            }),
+            max_price_deviation_bps: state.max_price_deviation_bps,
+            max_referral_fee_bps: state.max_referral_fee_bps,
+            version,
+            reserved: [0; LEGACY_POOL_RESERVED_BYTES],
+        };
+
+        let mut data = PoolState::DISCRIMINATOR.to_vec();
+        legacy.serialize(&mut data).unwrap();
+        assert_eq!(data.len(), LegacyPoolState::LEN);
+        data.truncate(len);
+
+        let mut account = self.account(&self.pool_state).unwrap();
+        account.data = data;
+        let key = self.pool_state;
+        self.set_account(&key, account);
+    }
+
+    /// 把池子账户截断到版本 2 的长度，模拟追加 `reserved` 之前创建的零拷贝池子
+    pub fn rewrite_as_v2(&mut self) {
+        let mut account = self.account(&self.pool_state).unwrap();
+        account.data[8 + std::mem::offset_of!(PoolState, version)] = 2;
+        account.data.truncate(POOL_V2_LEN);
+        let key = self.pool_state;
+        self.set_account(&key, account);
+    }
+
+    pub fn reserves(&self) -> (u64, u64) {
+        (
+            self.token_amount(&self.token_a_vault),
+            self.token_amount(&self.token_b_vault),
+        )
+    }
+
+    pub fn process(&self, data: impl InstructionData, accounts: impl ToAccountMetas) -> InstructionResult {
+        self.context.process_instruction(&instruction(data, accounts))
+    }
+
+    pub fn initialize_accounts(&self) -> solana_amm::accounts::Initialize {
+        solana_amm::accounts::Initialize {
+            pool_state: self.pool_state,
+            pool_authority: self.pool_authority,
+            token_a: self.mint_a,
+            token_b: self.mint_b,
+            token_a_vault: self.token_a_vault,
+            token_b_vault: self.token_b_vault,
+            lp_mint: self.lp_mint,
+            pool_registry: pda::pool_registry_address(),
+            registry_page: self.next_registry_page_address(),
+            system_program: anchor_lang::system_program::ID,
+            token_program: anchor_spl::token::ID,
+            rent: anchor_lang::solana_program::sysvar::rent::ID,
+            associated_token_program: anchor_spl::associated_token::ID,
+            admin: self.admin,
+            // Mollusk 没有加载 Metaplex 程序，不创建 LP 元数据
+            token_metadata_program: None,
+            lp_metadata: None,
+            token_a_metadata: None,
+            token_b_metadata: None,
+        }
+    }
+
+    /// 调用 `initialize`，成功后顺便创建黑洞账户和协议费接收账户
+    pub fn initialize(&mut self, fee_numerator: u64, fee_denominator: u64) -> InstructionResult {
+        let result = self.process(
+            solana_amm::instruction::Initialize {
+                mint_a: self.mint_a,
+                mint_b: self.mint_b,
+                fee_numerator,
+                fee_denominator,
+            },
+            self.initialize_accounts(),
+        );
+        if result.program_result.is_ok() {
+            let (lp_mint, admin) = (self.lp_mint, self.admin);
+            let (black_hole_lp, protocol_fee_recipient) = (self.black_hole_lp, self.protocol_fee_recipient);
+            self.set_account(&black_hole_lp, token_account(&lp_mint, &Pubkey::default(), 0));
+            self.set_account(&protocol_fee_recipient, token_account(&lp_mint, &admin, 0));
+        }
+        result
+    }
+
+    pub fn swap_accounts(&self) -> solana_amm::accounts::Swap {
+        let twamm_accounts = self.twamm_accounts();
+        solana_amm::accounts::Swap {
+            pool_state: self.pool_state,
+            user_token_a: self.user_token_a,
+            user_token_b: self.user_token_b,
+            token_a_vault: self.token_a_vault,
+            token_b_vault: self.token_b_vault,
+            user: self.user,
+            pool_authority: self.pool_authority,
+            token_program: anchor_spl::token::ID,
+            referrer_token_account: None,
+            twamm: twamm_accounts.0,
+            twamm_vault_a: twamm_accounts.1,
+            twamm_vault_b: twamm_accounts.2,
+            access_pass: self.access_pass_account(&self.user),
+            pool_gatekeeper: self.pool_gatekeeper_account(),
+        }
+    }
+
+    pub fn swap_with(
+        &self,
+        accounts: solana_amm::accounts::Swap,
+        amount_in: u64,
+        is_a_to_b: bool,
+        min_amount_out: u64,
+    ) -> InstructionResult {
+        self.process(
+            solana_amm::instruction::Swap {
+                amount_in,
+                is_a_to_b,
+                min_amount_out,
+                deadline: None,
+                referral_fee_bps: 0,
+            },
+            accounts,
+        )
+    }
+
+    pub fn swap(&self, amount_in: u64, is_a_to_b: bool, min_amount_out: u64) -> InstructionResult {
+        self.swap_with(self.swap_accounts(), amount_in, is_a_to_b, min_amount_out)
+    }
+
+    pub fn swap_before(&self, deadline: i64, amount_in: u64, is_a_to_b: bool) -> InstructionResult {
+        self.process(
+            solana_amm::instruction::Swap {
+                amount_in,
+                is_a_to_b,
+                min_amount_out: 0,
+                deadline: Some(deadline),
+                referral_fee_bps: 0,
+            },
+            self.swap_accounts(),
+        )
+    }
+
+    pub fn swap_with_referral(
+        &self,
+        referrer: Pubkey,
+        referral_fee_bps: u16,
+        amount_in: u64,
+        is_a_to_b: bool,
+    ) -> InstructionResult {
+        self.process(
+            solana_amm::instruction::Swap {
+                amount_in,
+                is_a_to_b,
+                min_amount_out: 0,
+                deadline: None,
+                referral_fee_bps,
+            },
+            solana_amm::accounts::Swap {
+                referrer_token_account: Some(referrer),
+                ..self.swap_accounts()
+            },
+        )
+    }
+
+    pub fn add_liquidity_accounts(&self) -> solana_amm::accounts::AddLiquidity {
+        let twamm_accounts = self.twamm_accounts();
+        solana_amm::accounts::AddLiquidity {
+            pool_state: self.pool_state,
+            pool_authority: self.pool_authority,
+            user_token_a: self.user_token_a,
+            user_token_b: self.user_token_b,
+            token_a_vault: self.token_a_vault,
+            token_b_vault: self.token_b_vault,
+            user: self.user,
+            lp_mint: self.lp_mint,
+            user_lp_token_ATA: self.user_lp,
+            black_hole_lp_ATA: self.black_hole_lp,
+            protocol_fee_recipient: self.protocol_fee_recipient,
+            associated_token_program: anchor_spl::associated_token::ID,
+            system_program: anchor_lang::system_program::ID,
+            token_program: anchor_spl::token::ID,
+            twamm: twamm_accounts.0,
+            twamm_vault_a: twamm_accounts.1,
+            twamm_vault_b: twamm_accounts.2,
+            access_pass: self.access_pass_account(&self.user),
+            pool_gatekeeper: self.pool_gatekeeper_account(),
+        }
+    }
+
+    pub fn add_liquidity_with(
+        &self,
+        accounts: solana_amm::accounts::AddLiquidity,
+        amount_a: u64,
+        amount_b: u64,
+    ) -> InstructionResult {
+        self.process(
+            solana_amm::instruction::AddLiquidity {
+                amount_a,
+                amount_b,
+                deadline: None,
+            },
+            accounts,
+        )
+    }
+
+    pub fn add_liquidity(&self, amount_a: u64, amount_b: u64) -> InstructionResult {
+        self.add_liquidity_with(self.add_liquidity_accounts(), amount_a, amount_b)
+    }
+
+    pub fn add_liquidity_before(&self, deadline: i64, amount_a: u64, amount_b: u64) -> InstructionResult {
+        self.process(
+            solana_amm::instruction::AddLiquidity {
+                amount_a,
+                amount_b,
+                deadline: Some(deadline),
+            },
+            self.add_liquidity_accounts(),
+        )
+    }
+
+    pub fn remove_liquidity_accounts(&self) -> solana_amm::accounts::RemoveLiquidity {
+        let twamm_accounts = self.twamm_accounts();
+        solana_amm::accounts::RemoveLiquidity {
+            pool_state: self.pool_state,
+            pool_authority: self.pool_authority,
+            user_token_a: self.user_token_a,
+            user_token_b: self.user_token_b,
+            token_a_vault: self.token_a_vault,
+            token_b_vault: self.token_b_vault,
+            user: self.user,
+            lp_mint: self.lp_mint,
+            user_lp_token_ATA: self.user_lp,
+            protocol_fee_recipient: self.protocol_fee_recipient,
+            associated_token_program: anchor_spl::associated_token::ID,
+            system_program: anchor_lang::system_program::ID,
+            token_program: anchor_spl::token::ID,
+            twamm: twamm_accounts.0,
+            twamm_vault_a: twamm_accounts.1,
+            twamm_vault_b: twamm_accounts.2,
+            access_pass: self.access_pass_account(&self.user),
+            pool_gatekeeper: self.pool_gatekeeper_account(),
+        }
+    }
+
+    pub fn remove_liquidity_with(
+        &self,
+        accounts: solana_amm::accounts::RemoveLiquidity,
+        amount_lp: u64,
+        min_amount_a: u64,
+        min_amount_b: u64,
+    ) -> InstructionResult {
+        self.process(
+            solana_amm::instruction::RemoveLiquidity {
+                amount_lp,
+                min_amount_a,
+                min_amount_b,
+                deadline: None,
+            },
+            accounts,
+        )
+    }
+
+    pub fn remove_liquidity(&self, amount_lp: u64, min_amount_a: u64, min_amount_b: u64) -> InstructionResult {
+        self.remove_liquidity_with(self.remove_liquidity_accounts(), amount_lp, min_amount_a, min_amount_b)
+    }
+
+    pub fn remove_liquidity_before(&self, deadline: i64, amount_lp: u64) -> InstructionResult {
+        self.process(
+            solana_amm::instruction::RemoveLiquidity {
+                amount_lp,
+                min_amount_a: 0,
+                min_amount_b: 0,
+                deadline: Some(deadline),
+            },
+            self.remove_liquidity_accounts(),
+        )
+    }
+
+    pub fn update_config(
+        &self,
+        signer: &Pubkey,
+        new_admin: Option<Pubkey>,
+        new_recipient: Option<Pubkey>,
+        new_share: Option<u64>,
+    ) -> InstructionResult {
+        self.process(
+            solana_amm::instruction::UpdateConfig {
+                new_admin,
+                new_recipient,
+                new_share,
+                new_max_price_deviation_bps: None,
+                new_max_referral_fee_bps: None,
+            },
+            solana_amm::accounts::UpdateConfig {
+                pool_state: self.pool_state,
+                admin: *signer,
+            },
+        )
+    }
+
+    /// 只修改 `max_price_deviation_bps` 的 update_config
+    pub fn set_max_price_deviation(&self, signer: &Pubkey, bps: u16) -> InstructionResult {
+        self.process(
+            solana_amm::instruction::UpdateConfig {
+                new_admin: None,
+                new_recipient: None,
+                new_share: None,
+                new_max_price_deviation_bps: Some(bps),
+                new_max_referral_fee_bps: None,
+            },
+            solana_amm::accounts::UpdateConfig {
+                pool_state: self.pool_state,
+                admin: *signer,
+            },
+        )
+    }
+
+    /// 只修改 `max_referral_fee_bps` 的 update_config
+    pub fn set_max_referral_fee(&self, signer: &Pubkey, bps: u16) -> InstructionResult {
+        self.process(
+            solana_amm::instruction::UpdateConfig {
+                new_admin: None,
+                new_recipient: None,
+                new_share: None,
+                new_max_price_deviation_bps: None,
+                new_max_referral_fee_bps: Some(bps),
+            },
+            solana_amm::accounts::UpdateConfig {
+                pool_state: self.pool_state,
+                admin: *signer,
+            },
+        )
+    }
+
+    pub fn set_deposit_caps(
+        &self,
+        signer: &Pubkey,
+        max_total_reserve_a: u64,
+        max_total_reserve_b: u64,
+        max_deposit_a: u64,
+        max_deposit_b: u64,
+    ) -> InstructionResult {
+        self.process(
+            solana_amm::instruction::SetDepositCaps {
+                max_total_reserve_a,
+                max_total_reserve_b,
+                max_deposit_a,
+                max_deposit_b,
+            },
+            solana_amm::accounts::SetDepositCaps {
+                pool_state: self.pool_state,
+                admin: *signer,
+            },
+        )
+    }
+
+    pub fn claim_admin(&self, signer: &Pubkey) -> InstructionResult {
+        self.process(
+            solana_amm::instruction::ClaimAdmin {},
+            solana_amm::accounts::ClaimAdmin {
+                pool_state: self.pool_state,
+                pending_admin: *signer,
+            },
+        )
+    }
+
+    pub fn migrate_pool(&self, payer: &Pubkey) -> InstructionResult {
+        self.process(
+            solana_amm::instruction::MigratePool {},
+            solana_amm::accounts::MigratePool {
+                pool_state: self.pool_state,
+                payer: *payer,
+                system_program: anchor_lang::system_program::ID,
+            },
+        )
+    }
+
+    pub fn get_geometric_twap_accounts(&self) -> solana_amm::accounts::GetGeometricTwap {
+        solana_amm::accounts::GetGeometricTwap {
+            pool_state: self.pool_state,
+            token_a_vault: self.token_a_vault,
+            token_b_vault: self.token_b_vault,
+        }
+    }
+
+    pub fn get_geometric_twap(&self, window: u64) -> InstructionResult {
+        self.process(
+            solana_amm::instruction::GetGeometricTwap { window },
+            self.get_geometric_twap_accounts(),
+        )
+    }
+}
+
+/// 解析 `get_geometric_twap` 的 return data
+pub fn geometric_twap(result: &InstructionResult) -> GeometricTwap {
+    GeometricTwap::try_from_slice(&result.return_data).expect("geometric twap return data")
+}
+
+/// 解析 `get_registry_page` 的 return data
+pub fn registry_page_data(result: &InstructionResult) -> RegistryPage {
+    RegistryPage::try_from_slice(&result.return_data).expect("registry page return data")
+}
@@ -0,0 +1,68 @@
+//! 许可池子（PoolGatekeeper / AccessPass）相关的账户地址和指令
+
+use super::*;
+
+impl TestPool {
+    pub fn pool_gatekeeper_address(&self) -> Pubkey {
+        pda::pool_gatekeeper_address(&self.pool_state)
+    }
+
+    pub fn access_pass_address(&self, user: &Pubkey) -> Pubkey {
+        pda::access_pass_address(&self.pool_state, user)
+    }
+
+    pub fn access_pass(&self, user: &Pubkey) -> Option<solana_amm::AccessPass> {
+        let account = self.account(&self.access_pass_address(user))?;
+        solana_amm::AccessPass::try_deserialize(&mut account.data.as_slice()).ok()
+    }
+
+    /// 用户持有 AccessPass 时返回它的地址，撤销（关闭）之后返回 None
+    pub fn access_pass_account(&self, user: &Pubkey) -> Option<Pubkey> {
+        let access_pass = self.access_pass_address(user);
+        self.account(&access_pass).filter(|account| account.lamports > 0).map(|_| access_pass)
+    }
+
+    /// 池子设置过守门人时返回 PoolGatekeeper 的地址
+    pub fn pool_gatekeeper_account(&self) -> Option<Pubkey> {
+        let pool_gatekeeper = self.pool_gatekeeper_address();
+        self.account(&pool_gatekeeper).map(|_| pool_gatekeeper)
+    }
+
+    pub fn set_gatekeeper(&self, signer: &Pubkey, gatekeeper: &Pubkey) -> InstructionResult {
+        self.process(
+            solana_amm::instruction::SetGatekeeper { gatekeeper: *gatekeeper },
+            solana_amm::accounts::SetGatekeeper {
+                pool_state: self.pool_state,
+                pool_gatekeeper: self.pool_gatekeeper_address(),
+                admin: *signer,
+                system_program: anchor_lang::system_program::ID,
+            },
+        )
+    }
+
+    pub fn issue_access_pass(&self, signer: &Pubkey, user: &Pubkey, expires_ts: i64) -> InstructionResult {
+        self.process(
+            solana_amm::instruction::IssueAccessPass { expires_ts },
+            solana_amm::accounts::IssueAccessPass {
+                pool_state: self.pool_state,
+                pool_gatekeeper: self.pool_gatekeeper_address(),
+                user: *user,
+                access_pass: self.access_pass_address(user),
+                gatekeeper: *signer,
+                system_program: anchor_lang::system_program::ID,
+            },
+        )
+    }
+
+    pub fn revoke_access_pass(&self, signer: &Pubkey, user: &Pubkey) -> InstructionResult {
+        self.process(
+            solana_amm::instruction::RevokeAccessPass {},
+            solana_amm::accounts::RevokeAccessPass {
+                pool_state: self.pool_state,
+                pool_gatekeeper: self.pool_gatekeeper_address(),
+                access_pass: self.access_pass_address(user),
+                gatekeeper: *signer,
+            },
+        )
+    }
+}
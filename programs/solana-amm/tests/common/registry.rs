@@ -0,0 +1,67 @@
+//! 池子注册表相关的账户地址和指令
+
+use super::*;
+
+impl TestPool {
+    /// 注册表头部，第一个池子创建之前不存在
+    pub fn pool_registry(&self) -> Option<PoolRegistry> {
+        let account = self.account(&pda::pool_registry_address())?;
+        PoolRegistry::try_deserialize(&mut account.data.as_slice()).ok()
+    }
+
+    pub fn registry_page(&self, page_index: u64) -> Option<PoolRegistryPage> {
+        let account = self.account(&pda::registry_page_address(page_index))?;
+        PoolRegistryPage::try_deserialize(&mut account.data.as_slice()).ok()
+    }
+
+    /// 下一个登记的池子所在的分页
+    pub fn next_registry_page_address(&self) -> Pubkey {
+        let page_index = self.pool_registry().map_or(0, |registry| registry.next_page_index());
+        pda::registry_page_address(page_index)
+    }
+
+    /// 用两个新的 mint 在同一个环境中再创建一个池子，返回池子地址
+    pub fn initialize_other_pool(&mut self, fee_numerator: u64, fee_denominator: u64) -> (Pubkey, InstructionResult) {
+        let (mint_a, mint_b) = {
+            let (x, y) = (Pubkey::new_unique(), Pubkey::new_unique());
+            if x < y { (x, y) } else { (y, x) }
+        };
+        self.set_account(&mint_a, mint_account(6));
+        self.set_account(&mint_b, mint_account(6));
+        let pool_state = pda::pool_address(&mint_a, &mint_b);
+        let accounts = solana_amm::accounts::Initialize {
+            pool_state,
+            token_a: mint_a,
+            token_b: mint_b,
+            token_a_vault: pda::pool_vault_address(&mint_a),
+            token_b_vault: pda::pool_vault_address(&mint_b),
+            lp_mint: Pubkey::new_unique(),
+            ..self.initialize_accounts()
+        };
+        let data = solana_amm::instruction::Initialize { mint_a, mint_b, fee_numerator, fee_denominator };
+        (pool_state, self.process(data, accounts))
+    }
+
+    pub fn register_pool(&self, payer: &Pubkey) -> InstructionResult {
+        self.process(
+            solana_amm::instruction::RegisterPool {},
+            solana_amm::accounts::RegisterPool {
+                pool_state: self.pool_state,
+                pool_registry: pda::pool_registry_address(),
+                registry_page: self.next_registry_page_address(),
+                payer: *payer,
+                system_program: anchor_lang::system_program::ID,
+            },
+        )
+    }
+
+    pub fn get_registry_page(&self, page_index: u64) -> InstructionResult {
+        self.process(
+            solana_amm::instruction::GetRegistryPage { page_index },
+            solana_amm::accounts::GetRegistryPage {
+                pool_registry: pda::pool_registry_address(),
+                registry_page: pda::registry_page_address(page_index),
+            },
+        )
+    }
+}
@@ -0,0 +1,138 @@
+//! TWAMM 长期订单相关的账户地址和指令
+
+use super::*;
+
+impl TestPool {
+    pub fn twamm_address(&self) -> Pubkey {
+        Pubkey::find_program_address(&[b"twamm", self.pool_state.as_ref()], &solana_amm::ID).0
+    }
+
+    pub fn twamm_vault_address(&self, mint: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(
+            &[b"twamm_vault", self.twamm_address().as_ref(), mint.as_ref()],
+            &solana_amm::ID,
+        )
+        .0
+    }
+
+    /// 池子开启了 TWAMM 之后 swap 等指令要附带的 (twamm, twamm_vault_a, twamm_vault_b)
+    pub fn twamm_accounts(&self) -> (Option<Pubkey>, Option<Pubkey>, Option<Pubkey>) {
+        let twamm = self.twamm_address();
+        if self.account(&twamm).is_none() {
+            return (None, None, None);
+        }
+        (
+            Some(twamm),
+            Some(self.twamm_vault_address(&self.mint_a)),
+            Some(self.twamm_vault_address(&self.mint_b)),
+        )
+    }
+
+    pub fn twamm(&self) -> solana_amm::Twamm {
+        let account = self.account(&self.twamm_address()).expect("twamm");
+        bytemuck::pod_read_unaligned(&account.data[8..solana_amm::Twamm::LEN])
+    }
+
+    pub fn long_term_order_address(&self, order_id: u64) -> Pubkey {
+        Pubkey::find_program_address(
+            &[b"long_term_order", self.pool_state.as_ref(), self.user.as_ref(), &order_id.to_le_bytes()],
+            &solana_amm::ID,
+        )
+        .0
+    }
+
+    pub fn long_term_order(&self, order_id: u64) -> Option<solana_amm::LongTermOrder> {
+        let account = self.account(&self.long_term_order_address(order_id))?;
+        solana_amm::LongTermOrder::try_deserialize(&mut account.data.as_slice()).ok()
+    }
+
+    pub fn init_twamm(&self, signer: &Pubkey) -> InstructionResult {
+        self.process(
+            solana_amm::instruction::InitTwamm {},
+            solana_amm::accounts::InitTwamm {
+                pool_state: self.pool_state,
+                pool_authority: self.pool_authority,
+                twamm: self.twamm_address(),
+                token_a_mint: self.mint_a,
+                token_b_mint: self.mint_b,
+                twamm_vault_a: self.twamm_vault_address(&self.mint_a),
+                twamm_vault_b: self.twamm_vault_address(&self.mint_b),
+                admin: *signer,
+                system_program: anchor_lang::system_program::ID,
+                token_program: anchor_spl::token::ID,
+            },
+        )
+    }
+
+    pub fn place_long_term_order(
+        &self,
+        order_id: u64,
+        amount_in: u64,
+        is_a_to_b: bool,
+        intervals: u16,
+    ) -> InstructionResult {
+        let user_token_in = if is_a_to_b { self.user_token_a } else { self.user_token_b };
+        self.process(
+            solana_amm::instruction::PlaceLongTermOrder {
+                order_id,
+                amount_in,
+                is_a_to_b,
+                intervals,
+            },
+            solana_amm::accounts::PlaceLongTermOrder {
+                pool_state: self.pool_state,
+                pool_authority: self.pool_authority,
+                token_a_vault: self.token_a_vault,
+                token_b_vault: self.token_b_vault,
+                twamm: self.twamm_address(),
+                twamm_vault_a: self.twamm_vault_address(&self.mint_a),
+                twamm_vault_b: self.twamm_vault_address(&self.mint_b),
+                long_term_order: self.long_term_order_address(order_id),
+                user_token_in,
+                owner: self.user,
+                system_program: anchor_lang::system_program::ID,
+                token_program: anchor_spl::token::ID,
+            },
+        )
+    }
+
+    pub fn withdraw_long_term_order(&self, order_id: u64) -> InstructionResult {
+        self.process(
+            solana_amm::instruction::WithdrawLongTermOrder {},
+            solana_amm::accounts::WithdrawLongTermOrder {
+                pool_state: self.pool_state,
+                pool_authority: self.pool_authority,
+                token_a_vault: self.token_a_vault,
+                token_b_vault: self.token_b_vault,
+                twamm: self.twamm_address(),
+                twamm_vault_a: self.twamm_vault_address(&self.mint_a),
+                twamm_vault_b: self.twamm_vault_address(&self.mint_b),
+                long_term_order: self.long_term_order_address(order_id),
+                user_token_a: self.user_token_a,
+                user_token_b: self.user_token_b,
+                owner: self.user,
+                token_program: anchor_spl::token::ID,
+            },
+        )
+    }
+
+    pub fn execute_twamm_instruction(&self) -> Instruction {
+        instruction(
+            solana_amm::instruction::ExecuteTwamm {},
+            solana_amm::accounts::ExecuteTwamm {
+                pool_state: self.pool_state,
+                pool_authority: self.pool_authority,
+                token_a_vault: self.token_a_vault,
+                token_b_vault: self.token_b_vault,
+                twamm: self.twamm_address(),
+                twamm_vault_a: self.twamm_vault_address(&self.mint_a),
+                twamm_vault_b: self.twamm_vault_address(&self.mint_b),
+                token_program: anchor_spl::token::ID,
+            },
+        )
+    }
+
+    pub fn execute_twamm(&self) -> InstructionResult {
+        self.context.process_instruction(&self.execute_twamm_instruction())
+    }
+}
@@ -0,0 +1,119 @@
+// CU 预算回归测试：每个基准场景的消耗不能超过 benches/cu_budget.toml 中的上限
+//
+// 预算文件由 CU_BUDGET_UPDATE=1 运行本测试根据实测值生成；
+// 不需要程序文件的 `budget_file_is_well_formed` 检查每个场景都有预算，缺少或者多出条目时失败
+
+mod common;
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use common::compute_units::{borsh_baseline_elf, layout_scenarios, scenario_names, scenarios};
+
+// 重写预算时在实测值上留的余量（百分比），并向上取整到 1000
+const HEADROOM_PERCENT: u64 = 10;
+const ROUND_TO: u64 = 1000;
+// 单条指令默认的 CU 上限，预算必须低于它才有意义
+const DEFAULT_COMPUTE_UNIT_LIMIT: u64 = 200_000;
+
+const HEADER: &str = "\
+# 每个基准场景允许消耗的 CU 上限，由 tests/compute_units.rs 检查
+# 场景定义见 tests/common/compute_units.rs
+#
+# 本文件由实测值（+10% 余量）生成，不要手工修改。改动程序后如果 CU 有意上涨，重新生成：
+#   anchor build && CU_BUDGET_UPDATE=1 cargo test -p solana-amm --test compute_units -- --include-ignored
+";
+
+fn budget_path() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("benches/cu_budget.toml")
+}
+
+fn read_budget() -> BTreeMap<String, u64> {
+    let text = std::fs::read_to_string(budget_path()).expect("read cu_budget.toml");
+    toml::from_str(&text).expect("parse cu_budget.toml")
+}
+
+fn write_budget(measured: &BTreeMap<String, u64>) {
+    let budget: BTreeMap<&String, u64> = measured
+        .iter()
+        .map(|(name, units)| {
+            let padded = units * (100 + HEADROOM_PERCENT) / 100;
+            (name, padded.div_ceil(ROUND_TO) * ROUND_TO)
+        })
+        .collect();
+    let body = toml::to_string(&budget).expect("serialize budget");
+    std::fs::write(budget_path(), format!("{HEADER}\n{body}")).expect("write cu_budget.toml");
+}
+
+#[test]
+fn budget_file_is_well_formed() {
+    let budget = read_budget();
+    let names = scenario_names();
+    let missing: Vec<&String> = names.iter().filter(|name| !budget.contains_key(*name)).collect();
+    assert!(missing.is_empty(), "no budget for {missing:?}; regenerate cu_budget.toml with CU_BUDGET_UPDATE=1");
+    let unknown: Vec<&String> = budget.keys().filter(|name| !names.contains(name)).collect();
+    assert!(unknown.is_empty(), "budget for unknown scenarios {unknown:?}");
+    for (name, &units) in &budget {
+        assert!(
+            units > 0 && units < DEFAULT_COMPUTE_UNIT_LIMIT,
+            "{name} = {units} is not below the default compute limit"
+        );
+    }
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn instructions_stay_within_cu_budget() {
+    let scenarios = scenarios();
+
+    let mut measured = BTreeMap::new();
+    for scenario in &scenarios {
+        let result = scenario.run();
+        assert!(
+            result.program_result.is_ok(),
+            "{} failed: {:?}",
+            scenario.name,
+            result.program_result
+        );
+        measured.insert(scenario.name.clone(), result.compute_units_consumed);
+    }
+
+    if std::env::var_os("CU_BUDGET_UPDATE").is_some() {
+        write_budget(&measured);
+        return;
+    }
+
+    let budget = read_budget();
+    let mut over = Vec::new();
+    for (name, units) in &measured {
+        let limit = budget.get(name).unwrap_or_else(|| {
+            panic!("{name} has no entry in cu_budget.toml; regenerate it with CU_BUDGET_UPDATE=1")
+        });
+        if units > limit {
+            over.push(format!("{name}: {units} > {limit}"));
+        }
+    }
+    assert!(over.is_empty(), "over CU budget:\n{}", over.join("\n"));
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so and solana_amm_borsh.so: see README"]
+fn zero_copy_layout_is_not_more_expensive_than_borsh() {
+    let borsh_elf = borsh_baseline_elf();
+    let borsh = layout_scenarios(Some(&borsh_elf));
+    let zero_copy = layout_scenarios(None);
+
+    let mut regressions = Vec::new();
+    for (borsh, zero_copy) in borsh.iter().zip(&zero_copy) {
+        let (before, after) = (borsh.run(), zero_copy.run());
+        assert!(before.program_result.is_ok(), "{} (Borsh) failed: {:?}", borsh.name, before.program_result);
+        assert!(after.program_result.is_ok(), "{} failed: {:?}", zero_copy.name, after.program_result);
+        if after.compute_units_consumed > before.compute_units_consumed {
+            regressions.push(format!(
+                "{}: {} > {}",
+                zero_copy.name, after.compute_units_consumed, before.compute_units_consumed
+            ));
+        }
+    }
+    assert!(regressions.is_empty(), "zero-copy costs more than Borsh:\n{}", regressions.join("\n"));
+}
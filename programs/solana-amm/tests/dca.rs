@@ -0,0 +1,126 @@
+mod common;
+
+use common::*;
+use solana_amm::{math, AmmError, DcaVault};
+
+const RESERVE_A: u64 = 1_000_000_000;
+const RESERVE_B: u64 = 2_000_000_000;
+const DEPOSIT: u64 = 2_500_000;
+const PER_CYCLE: u64 = 1_000_000;
+const INTERVAL: i64 = 3_600;
+
+fn schedule(next_cycle_ts: i64) -> DcaVault {
+    DcaVault {
+        pool: Default::default(),
+        owner: Default::default(),
+        input_vault: Default::default(),
+        output_vault: Default::default(),
+        dca_id: 0,
+        is_a_to_b: true,
+        amount_per_cycle: PER_CYCLE,
+        min_amount_out_per_cycle: 1_900_000,
+        cycle_interval: INTERVAL,
+        next_cycle_ts,
+        amount_sold: 0,
+        amount_bought: 0,
+        bump: 0,
+    }
+}
+
+#[test]
+fn cycle_amounts_scale_the_last_cycle() {
+    let dca = schedule(0);
+    assert_eq!(dca.cycle_amounts(DEPOSIT), Some((PER_CYCLE, 1_900_000)));
+    assert_eq!(dca.cycle_amounts(PER_CYCLE / 2), Some((PER_CYCLE / 2, 950_000)));
+}
+
+#[test]
+fn late_execution_skips_missed_cycles() {
+    let dca = schedule(1_000);
+    assert_eq!(dca.next_cycle_after(1_000), Some(1_000 + INTERVAL));
+    assert_eq!(dca.next_cycle_after(1_000 + INTERVAL - 1), Some(1_000 + INTERVAL));
+    // 晚了两期多才执行，下一期仍然和原来的时间表对齐
+    assert_eq!(dca.next_cycle_after(1_000 + 2 * INTERVAL + 5), Some(1_000 + 3 * INTERVAL));
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn open_dca_escrows_deposit() {
+    let pool = TestPool::with_liquidity(RESERVE_A, RESERVE_B);
+    let before = pool.token_amount(&pool.user_token_a);
+
+    assert_error(&pool.open_dca(1, DEPOSIT, true, 0, INTERVAL, 0), AmmError::InvalidDca);
+    assert_error(&pool.open_dca(1, DEPOSIT, true, DEPOSIT + 1, INTERVAL, 0), AmmError::InvalidDca);
+    assert_error(&pool.open_dca(1, DEPOSIT, true, PER_CYCLE, 0, 0), AmmError::InvalidDca);
+    assert_success(&pool.open_dca(1, DEPOSIT, true, PER_CYCLE, INTERVAL, 0));
+
+    assert_eq!(pool.token_amount(&pool.user_token_a), before - DEPOSIT);
+    assert_eq!(pool.token_amount(&pool.dca_input_address(1)), DEPOSIT);
+    let dca = pool.dca_vault(1).unwrap();
+    assert_eq!((dca.pool, dca.owner), (pool.pool_state, pool.user));
+    assert_eq!(dca.next_cycle_ts, START_TIMESTAMP as i64);
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn execute_dca_sells_one_cycle_per_interval() {
+    let mut pool = TestPool::with_liquidity(RESERVE_A, RESERVE_B);
+    let keeper = pool.admin;
+    assert_success(&pool.open_dca(1, DEPOSIT, true, PER_CYCLE, INTERVAL, 0));
+
+    let (reserve_a, reserve_b) = pool.reserves();
+    let (_, expected_out) =
+        math::calculate_swap_amount_out(PER_CYCLE, reserve_a, reserve_b, FEE_NUMERATOR, FEE_DENOMINATOR).unwrap();
+    assert_success(&pool.execute_dca(&keeper, 1));
+
+    assert_eq!(pool.token_amount(&pool.dca_input_address(1)), DEPOSIT - PER_CYCLE);
+    assert_eq!(pool.token_amount(&pool.dca_output_address(1)), expected_out);
+    assert_eq!(pool.reserves(), (reserve_a + PER_CYCLE, reserve_b - expected_out));
+    let dca = pool.dca_vault(1).unwrap();
+    assert_eq!(dca.next_cycle_ts, START_TIMESTAMP as i64 + INTERVAL);
+    assert_eq!((dca.amount_sold, dca.amount_bought), (PER_CYCLE, expected_out));
+
+    // 同一期不能执行两次
+    assert_error(&pool.execute_dca(&keeper, 1), AmmError::DcaNotDue);
+
+    // 最后一期只卖剩下的部分，之后没有可执行的期数
+    pool.advance_clock(INTERVAL as u64);
+    assert_success(&pool.execute_dca(&keeper, 1));
+    pool.advance_clock(INTERVAL as u64);
+    assert_success(&pool.execute_dca(&keeper, 1));
+    assert_eq!(pool.token_amount(&pool.dca_input_address(1)), 0);
+    assert_eq!(pool.dca_vault(1).unwrap().amount_sold, DEPOSIT);
+    pool.advance_clock(INTERVAL as u64);
+    assert_error(&pool.execute_dca(&keeper, 1), AmmError::DcaNotDue);
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn execute_dca_respects_min_out() {
+    let pool = TestPool::with_liquidity(RESERVE_A, RESERVE_B);
+    // 当前价格约 2 B/A，要求每期至少 2.5 B/A
+    assert_success(&pool.open_dca(1, DEPOSIT, true, PER_CYCLE, INTERVAL, PER_CYCLE * 5 / 2));
+
+    assert_error(&pool.execute_dca(&pool.admin, 1), AmmError::SlippageExceeded);
+    assert_eq!(pool.token_amount(&pool.dca_input_address(1)), DEPOSIT);
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn withdraw_and_close_return_balances() {
+    let pool = TestPool::with_liquidity(RESERVE_A, RESERVE_B);
+    let before_a = pool.token_amount(&pool.user_token_a);
+    let before_b = pool.token_amount(&pool.user_token_b);
+    assert_success(&pool.open_dca(1, DEPOSIT, true, PER_CYCLE, INTERVAL, 0));
+    assert_success(&pool.execute_dca(&pool.admin, 1));
+    let bought = pool.dca_vault(1).unwrap().amount_bought;
+
+    assert_success(&pool.withdraw_dca(1, true));
+    assert_eq!(pool.token_amount(&pool.user_token_b), before_b + bought);
+    assert_eq!(pool.token_amount(&pool.dca_output_address(1)), 0);
+
+    assert_success(&pool.close_dca(1, true));
+    assert_eq!(pool.token_amount(&pool.user_token_a), before_a - PER_CYCLE);
+    assert!(pool.dca_vault(1).is_none());
+    assert!(pool.account(&pool.dca_input_address(1)).is_none_or(|account| account.lamports == 0));
+}
@@ -0,0 +1,78 @@
+mod common;
+
+use common::*;
+use solana_amm::AmmError;
+
+const RESERVE_A: u64 = 1_000_000_000;
+const RESERVE_B: u64 = 2_000_000_000;
+const AMOUNT: u64 = 1_000_000;
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn only_admin_sets_caps() {
+    let pool = TestPool::initialized();
+
+    assert_error(&pool.set_deposit_caps(&pool.user, 1, 2, 3, 4), AmmError::Unauthorized);
+    assert_success(&pool.set_deposit_caps(&pool.admin, 1, 2, 3, 4));
+
+    let state = pool.pool_state();
+    assert_eq!(
+        (state.max_total_reserve_a, state.max_total_reserve_b, state.max_deposit_a, state.max_deposit_b),
+        (1, 2, 3, 4)
+    );
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn add_liquidity_respects_reserve_caps_and_deposit_limits() {
+    let pool = TestPool::with_liquidity(RESERVE_A, RESERVE_B);
+
+    // 单笔限额
+    assert_success(&pool.set_deposit_caps(&pool.admin, 0, 0, AMOUNT, 0));
+    assert_error(&pool.add_liquidity(AMOUNT + 1, 2 * (AMOUNT + 1)), AmmError::CapExceeded);
+    assert_success(&pool.add_liquidity(AMOUNT, 2 * AMOUNT));
+
+    // 储备上限：正好到达上限可以，超过 1 不行
+    let (reserve_a, reserve_b) = pool.reserves();
+    assert_success(&pool.set_deposit_caps(&pool.admin, reserve_a + AMOUNT, reserve_b + 2 * AMOUNT, 0, 0));
+    assert_error(&pool.add_liquidity(AMOUNT, 2 * AMOUNT + 1), AmmError::CapExceeded);
+    assert_success(&pool.add_liquidity(AMOUNT, 2 * AMOUNT));
+    assert_error(&pool.add_liquidity(1, 2), AmmError::CapExceeded);
+
+    // 去掉上限后恢复正常
+    assert_success(&pool.set_deposit_caps(&pool.admin, 0, 0, 0, 0));
+    assert_success(&pool.add_liquidity(AMOUNT, 2 * AMOUNT));
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn swap_fails_only_when_input_reserve_exceeds_cap() {
+    let pool = TestPool::with_liquidity(RESERVE_A, RESERVE_B);
+
+    // 两侧的上限都已经低于当前储备
+    assert_success(&pool.set_deposit_caps(&pool.admin, RESERVE_A - 1, RESERVE_B - 1, 0, 0));
+    assert_error(&pool.swap(AMOUNT, true, 0), AmmError::CapExceeded);
+    assert_error(&pool.swap(AMOUNT, false, 0), AmmError::CapExceeded);
+
+    // 只限制 A 时，B 换 A 让 A 的储备减少，不受影响
+    assert_success(&pool.set_deposit_caps(&pool.admin, RESERVE_A, 0, 0, 0));
+    assert_error(&pool.swap(AMOUNT, true, 0), AmmError::CapExceeded);
+    assert_success(&pool.swap(AMOUNT, false, 0));
+    let (reserve_a, _) = pool.reserves();
+    assert_success(&pool.swap(RESERVE_A - reserve_a, true, 0));
+    assert_eq!(pool.reserves().0, RESERVE_A);
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn fill_orders_skips_orders_over_the_cap() {
+    let pool = TestPool::with_liquidity(RESERVE_A, RESERVE_B);
+    assert_success(&pool.place_order(1, AMOUNT, true, 1, 0));
+
+    assert_success(&pool.set_deposit_caps(&pool.admin, RESERVE_A, 0, 0, 0));
+    assert_error(&pool.fill_orders(&pool.admin, &[(1, true)]), AmmError::OrderNotFillable);
+
+    assert_success(&pool.set_deposit_caps(&pool.admin, RESERVE_A + AMOUNT, 0, 0, 0));
+    assert_success(&pool.fill_orders(&pool.admin, &[(1, true)]));
+    assert_eq!(pool.reserves().0, RESERVE_A + AMOUNT);
+}
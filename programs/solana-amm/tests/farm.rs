@@ -0,0 +1,195 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use common::*;
+use solana_amm::{AmmError, MAX_FARM_REWARDS};
+
+const RESERVE_A: u64 = 1_000_000_000;
+const RESERVE_B: u64 = 2_000_000_000;
+const EMISSION: u64 = 1_000;
+const DURATION: u64 = 7 * 24 * 3600;
+
+fn end_ts() -> i64 {
+    (START_TIMESTAMP + DURATION) as i64
+}
+
+/// 创建好 Farm 并添加一种奖励代币，返回 (reward_mint, 用户的奖励代币账户)
+fn farm_with_reward(pool: &mut TestPool) -> (Pubkey, Pubkey) {
+    assert_success(&pool.create_farm(&pool.admin));
+    let (reward_mint, admin_reward) = pool.create_reward_mint(EMISSION * DURATION);
+    assert_success(&pool.add_farm_reward(&reward_mint, &admin_reward, EMISSION, end_ts()));
+    let user = pool.user;
+    let user_reward = pool.create_token_account(&reward_mint, &user, 0);
+    (reward_mint, user_reward)
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn only_admin_can_create_farm() {
+    let pool = TestPool::with_liquidity(RESERVE_A, RESERVE_B);
+
+    assert_error(&pool.create_farm(&pool.user), AmmError::Unauthorized);
+    assert_success(&pool.create_farm(&pool.admin));
+
+    let farm = pool.farm().unwrap();
+    assert_eq!(farm.pool, pool.pool_state);
+    assert_eq!(farm.stake_vault, pool.farm_stake_vault_address());
+    assert_eq!((farm.total_staked, farm.reward_count), (0, 0));
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn add_reward_prefunds_whole_emission() {
+    let mut pool = TestPool::with_liquidity(RESERVE_A, RESERVE_B);
+    assert_success(&pool.create_farm(&pool.admin));
+    let (reward_mint, admin_reward) = pool.create_reward_mint(EMISSION * DURATION);
+
+    assert_error(&pool.add_farm_reward(&reward_mint, &admin_reward, 0, end_ts()), AmmError::InvalidFarmReward);
+    assert_error(
+        &pool.add_farm_reward(&reward_mint, &admin_reward, EMISSION, START_TIMESTAMP as i64),
+        AmmError::InvalidFarmReward,
+    );
+    assert_success(&pool.add_farm_reward(&reward_mint, &admin_reward, EMISSION, end_ts()));
+
+    assert_eq!(pool.token_amount(&pool.farm_reward_vault_address(&reward_mint)), EMISSION * DURATION);
+    assert_eq!(pool.token_amount(&admin_reward), 0);
+    let farm = pool.farm().unwrap();
+    assert_eq!(farm.reward_count, 1);
+    assert_eq!(farm.rewards[0].mint, reward_mint);
+    assert_eq!(farm.rewards[0].end_ts, end_ts());
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn reward_tokens_are_capped() {
+    let mut pool = TestPool::with_liquidity(RESERVE_A, RESERVE_B);
+    assert_success(&pool.create_farm(&pool.admin));
+
+    for _ in 0..MAX_FARM_REWARDS {
+        let (reward_mint, admin_reward) = pool.create_reward_mint(EMISSION * DURATION);
+        assert_success(&pool.add_farm_reward(&reward_mint, &admin_reward, EMISSION, end_ts()));
+    }
+    let (reward_mint, admin_reward) = pool.create_reward_mint(EMISSION * DURATION);
+    assert_error(
+        &pool.add_farm_reward(&reward_mint, &admin_reward, EMISSION, end_ts()),
+        AmmError::FarmRewardsFull,
+    );
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn staker_earns_emission_per_second() {
+    let mut pool = TestPool::with_liquidity(RESERVE_A, RESERVE_B);
+    let (reward_mint, user_reward) = farm_with_reward(&mut pool);
+    let user_lp = pool.token_amount(&pool.user_lp);
+
+    assert_success(&pool.stake_lp(user_lp));
+    assert_eq!(pool.token_amount(&pool.farm_stake_vault_address()), user_lp);
+    assert_eq!(pool.farm().unwrap().total_staked, user_lp);
+
+    pool.advance_clock(100);
+    assert_success(&pool.claim_rewards(0, &reward_mint, &user_reward));
+    let claimed = pool.token_amount(&user_reward);
+    assert!(claimed <= EMISSION * 100 && claimed + 1 >= EMISSION * 100, "claimed {claimed}");
+
+    // 同一秒内再次领取没有新的奖励
+    assert_success(&pool.claim_rewards(0, &reward_mint, &user_reward));
+    assert_eq!(pool.token_amount(&user_reward), claimed);
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn unstake_keeps_pending_rewards_and_stops_accrual() {
+    let mut pool = TestPool::with_liquidity(RESERVE_A, RESERVE_B);
+    let (reward_mint, user_reward) = farm_with_reward(&mut pool);
+    let user_lp = pool.token_amount(&pool.user_lp);
+
+    assert_error(&pool.stake_lp(0), AmmError::InvalidStakeAmount);
+    assert_success(&pool.stake_lp(user_lp));
+    pool.advance_clock(50);
+    assert_error(&pool.unstake_lp(user_lp + 1), AmmError::InvalidStakeAmount);
+    assert_success(&pool.unstake_lp(user_lp));
+    assert_eq!(pool.token_amount(&pool.user_lp), user_lp);
+
+    // 取回之后不再产生奖励，之前的奖励仍然可以领取
+    pool.advance_clock(1_000);
+    assert_success(&pool.claim_rewards(0, &reward_mint, &user_reward));
+    let claimed = pool.token_amount(&user_reward);
+    assert!(claimed <= EMISSION * 50 && claimed + 1 >= EMISSION * 50, "claimed {claimed}");
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn rewards_stop_at_end_ts() {
+    let mut pool = TestPool::with_liquidity(RESERVE_A, RESERVE_B);
+    let (reward_mint, user_reward) = farm_with_reward(&mut pool);
+    assert_success(&pool.stake_lp(pool.token_amount(&pool.user_lp)));
+
+    pool.advance_clock(DURATION * 2);
+    assert_success(&pool.claim_rewards(0, &reward_mint, &user_reward));
+    let claimed = pool.token_amount(&user_reward);
+    assert!(claimed <= EMISSION * DURATION && claimed + 1 >= EMISSION * DURATION, "claimed {claimed}");
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn claim_checks_reward_index_and_vault() {
+    let mut pool = TestPool::with_liquidity(RESERVE_A, RESERVE_B);
+    let (reward_mint, user_reward) = farm_with_reward(&mut pool);
+    assert_success(&pool.stake_lp(1_000));
+
+    assert_error(&pool.claim_rewards(1, &reward_mint, &user_reward), AmmError::InvalidFarmReward);
+
+    // 第二种奖励的金库不能用来领取第一种奖励
+    let (other_mint, admin_other) = pool.create_reward_mint(EMISSION * DURATION);
+    assert_success(&pool.add_farm_reward(&other_mint, &admin_other, EMISSION, end_ts()));
+    let user = pool.user;
+    let user_other = pool.create_token_account(&other_mint, &user, 0);
+    assert_error(&pool.claim_rewards(0, &other_mint, &user_other), AmmError::InvalidFarmReward);
+    assert_success(&pool.claim_rewards(1, &other_mint, &user_other));
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn emission_pauses_while_nothing_is_staked() {
+    let mut pool = TestPool::with_liquidity(RESERVE_A, RESERVE_B);
+    let (reward_mint, user_reward) = farm_with_reward(&mut pool);
+
+    // 一半的释放时间里没有人质押，这段时间顺延到之后
+    pool.advance_clock(DURATION / 2);
+    assert_success(&pool.stake_lp(pool.token_amount(&pool.user_lp)));
+    assert_eq!(pool.farm().unwrap().rewards[0].end_ts, end_ts() + (DURATION / 2) as i64);
+
+    pool.advance_clock(DURATION * 2);
+    assert_success(&pool.claim_rewards(0, &reward_mint, &user_reward));
+    let claimed = pool.token_amount(&user_reward);
+    assert!(claimed <= EMISSION * DURATION && claimed + 1 >= EMISSION * DURATION, "claimed {claimed}");
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn ended_reward_can_be_added_again() {
+    let mut pool = TestPool::with_liquidity(RESERVE_A, RESERVE_B);
+    let (reward_mint, user_reward) = farm_with_reward(&mut pool);
+    assert_success(&pool.stake_lp(pool.token_amount(&pool.user_lp)));
+    let admin = pool.admin;
+    let admin_reward = pool.create_token_account(&reward_mint, &admin, EMISSION * DURATION);
+
+    // 还在释放时不能重新添加
+    assert_error(
+        &pool.add_farm_reward(&reward_mint, &admin_reward, EMISSION, end_ts() + 1),
+        AmmError::FarmRewardActive,
+    );
+
+    // 释放完之后沿用同一个槽位和金库，上一轮没有领取的奖励仍然可以领取
+    pool.advance_clock(DURATION);
+    let second_end = end_ts() + DURATION as i64;
+    assert_success(&pool.add_farm_reward(&reward_mint, &admin_reward, EMISSION, second_end));
+    let farm = pool.farm().unwrap();
+    assert_eq!((farm.reward_count, farm.rewards[0].end_ts), (1, second_end));
+
+    pool.advance_clock(DURATION);
+    assert_success(&pool.claim_rewards(0, &reward_mint, &user_reward));
+    let claimed = pool.token_amount(&user_reward);
+    assert!(claimed <= 2 * EMISSION * DURATION && claimed + 2 >= 2 * EMISSION * DURATION, "claimed {claimed}");
+}
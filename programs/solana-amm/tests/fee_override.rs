@@ -0,0 +1,82 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use common::*;
+use solana_amm::{math, AmmError};
+
+const RESERVE_A: u64 = 1_000_000_000;
+const RESERVE_B: u64 = 2_000_000_000;
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn partner_swaps_at_discounted_fee() {
+    let pool = TestPool::with_liquidity(RESERVE_A, RESERVE_B);
+    assert_success(&pool.set_fee_override(&pool.admin, &pool.user, 1));
+
+    let fee_override = pool.fee_override_address(&pool.user);
+    let state = pool.account(&fee_override).unwrap();
+    assert_eq!(state.data.len(), solana_amm::FeeOverride::LEN);
+
+    let amount_in = 10_000_000;
+    let (_, discounted_out) =
+        math::calculate_swap_amount_out(amount_in, RESERVE_A, RESERVE_B, 1, FEE_DENOMINATOR).unwrap();
+    assert_success(&pool.swap_with_fee_override(&fee_override, amount_in, true));
+    assert_eq!(pool.reserves(), (RESERVE_A + amount_in, RESERVE_B - discounted_out));
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn fee_override_is_admin_only_and_cannot_raise_fee() {
+    let pool = TestPool::initialized();
+
+    assert_error(
+        &pool.set_fee_override(&pool.admin, &pool.user, FEE_NUMERATOR + 1),
+        AmmError::InvalidFeeOverride,
+    );
+    assert_error(&pool.set_fee_override(&pool.user, &pool.user, 0), AmmError::Unauthorized);
+    assert_success(&pool.set_fee_override(&pool.admin, &pool.user, FEE_NUMERATOR));
+    // 已存在时覆盖
+    assert_success(&pool.set_fee_override(&pool.admin, &pool.user, 0));
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn another_traders_override_is_rejected() {
+    let pool = TestPool::with_liquidity(RESERVE_A, RESERVE_B);
+    let partner = Pubkey::new_unique();
+    assert_success(&pool.set_fee_override(&pool.admin, &partner, 0));
+
+    let fee_override = pool.fee_override_address(&partner);
+    assert_error(&pool.swap_with_fee_override(&fee_override, 1_000_000, true), AmmError::InvalidFeeOverride);
+    assert_eq!(pool.reserves(), (RESERVE_A, RESERVE_B));
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn account_not_owned_by_program_is_rejected() {
+    let pool = TestPool::with_liquidity(RESERVE_A, RESERVE_B);
+
+    // 代币账户的 owner 是 token program
+    assert_error(
+        &pool.swap_with_fee_override(&pool.user_token_a, 1_000_000, true),
+        AmmError::InvalidFeeOverride,
+    );
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn removed_override_restores_pool_fee() {
+    let pool = TestPool::with_liquidity(RESERVE_A, RESERVE_B);
+    assert_success(&pool.set_fee_override(&pool.admin, &pool.user, 0));
+    assert_error(&pool.remove_fee_override(&pool.user, &pool.user), AmmError::Unauthorized);
+    assert_success(&pool.remove_fee_override(&pool.admin, &pool.user));
+
+    let fee_override = pool.fee_override_address(&pool.user);
+    assert!(pool.account(&fee_override).is_none_or(|account| account.lamports == 0));
+    assert_error(&pool.swap_with_fee_override(&fee_override, 1_000_000, true), AmmError::InvalidFeeOverride);
+
+    let (_, expected_out) =
+        math::calculate_swap_amount_out(1_000_000, RESERVE_A, RESERVE_B, FEE_NUMERATOR, FEE_DENOMINATOR)
+            .unwrap();
+    assert_success(&pool.swap(1_000_000, true, expected_out));
+}
@@ -0,0 +1,94 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_spl::associated_token::get_associated_token_address;
+use common::*;
+use solana_amm::{math, AmmError};
+use solana_program_pack::Pack;
+use spl_token_interface::state::{Account as TokenAccount, Mint};
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn initialize_stores_pool_config() {
+    let pool = TestPool::initialized();
+
+    let state = pool.pool_state();
+    assert_eq!(state.token_a, pool.mint_a);
+    assert_eq!(state.token_b, pool.mint_b);
+    assert_eq!(state.token_a_vault, pool.token_a_vault);
+    assert_eq!(state.token_b_vault, pool.token_b_vault);
+    assert_eq!(state.lp_mint, pool.lp_mint);
+    assert_eq!(state.fee_numerator, FEE_NUMERATOR);
+    assert_eq!(state.fee_denominator, FEE_DENOMINATOR);
+    assert_eq!(state.admin, pool.admin);
+    assert_eq!(state.pending_admin(), None);
+    assert_eq!(state.protocol_fee_recipient, pool.admin);
+    assert_eq!(state.protocol_fee_share, 0);
+    assert_eq!(state.k_last, 0);
+    assert_eq!(state.block_timestamp_last, START_TIMESTAMP);
+    assert_eq!(state.price_a_cumulative_last, 0);
+    assert_eq!(state.price_b_cumulative_last, 0);
+
+    let lp_mint = Mint::unpack(&pool.account(&pool.lp_mint).unwrap().data).unwrap();
+    // 测试池子的 A 是 6 位精度、B 是 9 位精度
+    assert_eq!(lp_mint.decimals, 7);
+    assert_eq!(lp_mint.decimals, math::lp_decimals(6, 9));
+    assert_eq!(lp_mint.supply, 0);
+    assert_eq!(lp_mint.mint_authority, Some(address(&pool.pool_authority)).into());
+
+    for (vault, mint) in [(pool.token_a_vault, pool.mint_a), (pool.token_b_vault, pool.mint_b)] {
+        let vault = TokenAccount::unpack(&pool.account(&vault).unwrap().data).unwrap();
+        assert_eq!(vault.mint, address(&mint));
+        assert_eq!(vault.owner, address(&pool.pool_authority));
+        assert_eq!(vault.amount, 0);
+    }
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn initialize_rejects_unsorted_mints() {
+    let pool = TestPool::new();
+
+    // 用 (mint_b, mint_a) 的顺序建池
+    let (pool_state, _) = Pubkey::find_program_address(
+        &[b"pool", pool.mint_b.as_ref(), pool.mint_a.as_ref()],
+        &solana_amm::ID,
+    );
+    let mut accounts = pool.initialize_accounts();
+    accounts.pool_state = pool_state;
+    accounts.token_a = pool.mint_b;
+    accounts.token_b = pool.mint_a;
+    accounts.token_a_vault = get_associated_token_address(&pool.pool_authority, &pool.mint_b);
+    accounts.token_b_vault = get_associated_token_address(&pool.pool_authority, &pool.mint_a);
+
+    let result = pool.process(
+        solana_amm::instruction::Initialize {
+            mint_a: pool.mint_b,
+            mint_b: pool.mint_a,
+            fee_numerator: FEE_NUMERATOR,
+            fee_denominator: FEE_DENOMINATOR,
+        },
+        accounts,
+    );
+    assert_error(&result, AmmError::InvalidMint);
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn initialize_rejects_invalid_fee() {
+    let mut pool = TestPool::new();
+
+    assert_error(&pool.initialize(1000, 1000), AmmError::InvalidFee);
+    assert_error(&pool.initialize(0, 0), AmmError::InvalidFee);
+    assert_success(&pool.initialize(0, 1000));
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn initialize_same_pair_twice_fails() {
+    let mut pool = TestPool::initialized();
+
+    pool.lp_mint = Pubkey::new_unique();
+    let result = pool.initialize(FEE_NUMERATOR, FEE_DENOMINATOR);
+    assert!(result.program_result.is_err());
+}
@@ -0,0 +1,105 @@
+mod common;
+
+use common::*;
+use solana_amm::{math, AmmError};
+
+const RESERVE_A: u64 = 1_000_000_000;
+const RESERVE_B: u64 = 2_000_000_000;
+const ORDER_IN: u64 = 1_000_000;
+// 限价 2.5 B/A，高于初始价格 2.0
+const ORDER_MIN_OUT: u64 = 2_500_000;
+const BOUNTY: u64 = 5_000;
+
+fn lamports(pool: &TestPool, key: &anchor_lang::prelude::Pubkey) -> u64 {
+    pool.account(key).map_or(0, |account| account.lamports)
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn place_order_escrows_input_and_bounty() {
+    let pool = TestPool::with_liquidity(RESERVE_A, RESERVE_B);
+    let before = pool.token_amount(&pool.user_token_a);
+
+    assert_error(&pool.place_order(1, 0, true, ORDER_MIN_OUT, BOUNTY), AmmError::InvalidOrder);
+    assert_error(&pool.place_order(1, ORDER_IN, true, 0, BOUNTY), AmmError::InvalidOrder);
+    assert_success(&pool.place_order(1, ORDER_IN, true, ORDER_MIN_OUT, BOUNTY));
+
+    assert_eq!(pool.token_amount(&pool.user_token_a), before - ORDER_IN);
+    assert_eq!(pool.token_amount(&pool.order_escrow_address(1)), ORDER_IN);
+    let order = pool.limit_order(1).unwrap();
+    assert_eq!(order.pool, pool.pool_state);
+    assert_eq!(order.owner, pool.user);
+    assert!(order.is_a_to_b);
+    assert_eq!((order.amount_in, order.min_amount_out, order.keeper_bounty), (ORDER_IN, ORDER_MIN_OUT, BOUNTY));
+    assert!(lamports(&pool, &pool.limit_order_address(1)) > BOUNTY);
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn cancel_returns_escrow_and_closes_accounts() {
+    let pool = TestPool::with_liquidity(RESERVE_A, RESERVE_B);
+    let before = pool.token_amount(&pool.user_token_a);
+    assert_success(&pool.place_order(1, ORDER_IN, true, ORDER_MIN_OUT, BOUNTY));
+
+    assert_success(&pool.cancel_order(1, &pool.user_token_a));
+
+    assert_eq!(pool.token_amount(&pool.user_token_a), before);
+    assert_eq!(lamports(&pool, &pool.limit_order_address(1)), 0);
+    assert_eq!(lamports(&pool, &pool.order_escrow_address(1)), 0);
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn fill_waits_until_price_crosses_limit() {
+    let pool = TestPool::with_liquidity(RESERVE_A, RESERVE_B);
+    let keeper = pool.admin;
+    assert_success(&pool.place_order(1, ORDER_IN, true, ORDER_MIN_OUT, BOUNTY));
+
+    // 当前价格 2.0，达不到限价
+    assert_error(&pool.fill_orders(&keeper, &[(1, true)]), AmmError::OrderNotFillable);
+    assert!(pool.limit_order(1).is_some());
+
+    // 有人用 B 买入 A，A 的价格上涨到限价之上
+    assert_success(&pool.swap(RESERVE_B, false, 0));
+    let (reserve_a, reserve_b) = pool.reserves();
+    let (_, expected_out) =
+        math::calculate_swap_amount_out(ORDER_IN, reserve_a, reserve_b, FEE_NUMERATOR, FEE_DENOMINATOR).unwrap();
+    assert!(expected_out >= ORDER_MIN_OUT);
+
+    let user_b = pool.token_amount(&pool.user_token_b);
+    let keeper_lamports = lamports(&pool, &keeper);
+    assert_success(&pool.fill_orders(&keeper, &[(1, true)]));
+
+    assert_eq!(pool.token_amount(&pool.user_token_b), user_b + expected_out);
+    assert_eq!(pool.reserves(), (reserve_a + ORDER_IN, reserve_b - expected_out));
+    assert_eq!(lamports(&pool, &keeper), keeper_lamports + BOUNTY);
+    assert_eq!(lamports(&pool, &pool.limit_order_address(1)), 0);
+    assert_eq!(lamports(&pool, &pool.order_escrow_address(1)), 0);
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn fill_skips_orders_below_limit() {
+    let pool = TestPool::with_liquidity(RESERVE_A, RESERVE_B);
+    let keeper = pool.admin;
+    // 订单 1 的限价 1.5 已经满足，订单 2 的限价 2.5 还没有
+    assert_success(&pool.place_order(1, ORDER_IN, true, ORDER_IN * 3 / 2, BOUNTY));
+    assert_success(&pool.place_order(2, ORDER_IN, true, ORDER_MIN_OUT, BOUNTY));
+
+    assert_success(&pool.fill_orders(&keeper, &[(1, true), (2, true)]));
+
+    assert!(pool.limit_order(1).is_none());
+    assert_eq!(pool.limit_order(2).unwrap().amount_in, ORDER_IN);
+    assert_eq!(pool.token_amount(&pool.order_escrow_address(2)), ORDER_IN);
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn fill_rejects_malformed_accounts() {
+    let pool = TestPool::with_liquidity(RESERVE_A, RESERVE_B);
+    assert_success(&pool.place_order(1, ORDER_IN, true, 1, BOUNTY));
+
+    assert_error(&pool.fill_orders(&pool.admin, &[]), AmmError::InvalidOrder);
+    // 方向写反时输出账户的 mint 不对
+    assert_error(&pool.fill_orders(&pool.admin, &[(1, false)]), AmmError::InvalidUserToken);
+}
@@ -0,0 +1,96 @@
+mod common;
+
+use common::*;
+use solana_amm::{math, AmmError};
+
+const RESERVE_A: u64 = 1_000_000_000;
+const RESERVE_B: u64 = 2_000_000_000;
+const LOCK_SECONDS: u64 = 30 * 24 * 3600;
+
+fn unlock_ts() -> i64 {
+    (START_TIMESTAMP + LOCK_SECONDS) as i64
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn lock_moves_lp_into_escrow() {
+    let pool = TestPool::with_liquidity(RESERVE_A, RESERVE_B);
+    let user_lp = pool.token_amount(&pool.user_lp);
+    let amount = user_lp / 2;
+
+    assert_success(&pool.lock_lp(amount, unlock_ts()));
+
+    assert_eq!(pool.token_amount(&pool.user_lp), user_lp - amount);
+    assert_eq!(pool.token_amount(&pool.lp_lock_vault_address(&pool.user)), amount);
+    let lock = pool.lp_lock(&pool.user).unwrap();
+    assert_eq!(lock.amount, amount);
+    assert_eq!(lock.unlock_ts, unlock_ts());
+    assert_eq!(
+        lock.principal,
+        math::calculate_lp_value(amount, RESERVE_A, RESERVE_B, pool.lp_supply()).unwrap()
+    );
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn lock_validates_amount_and_unlock_time() {
+    let pool = TestPool::with_liquidity(RESERVE_A, RESERVE_B);
+
+    assert_error(&pool.lock_lp(0, unlock_ts()), AmmError::InvalidLockAmount);
+    assert_error(&pool.lock_lp(1_000, START_TIMESTAMP as i64), AmmError::InvalidUnlockTime);
+
+    // 追加锁仓可以延后，不能提前
+    assert_success(&pool.lock_lp(1_000, unlock_ts()));
+    assert_error(&pool.lock_lp(1_000, unlock_ts() - 1), AmmError::InvalidUnlockTime);
+    assert_success(&pool.lock_lp(1_000, unlock_ts() + 1));
+    let lock = pool.lp_lock(&pool.user).unwrap();
+    assert_eq!((lock.amount, lock.unlock_ts), (2_000, unlock_ts() + 1));
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn unlock_only_after_unlock_ts() {
+    let mut pool = TestPool::with_liquidity(RESERVE_A, RESERVE_B);
+    let user_lp = pool.token_amount(&pool.user_lp);
+    assert_success(&pool.lock_lp(user_lp, unlock_ts()));
+
+    pool.advance_clock(LOCK_SECONDS - 1);
+    assert_error(&pool.unlock_lp(), AmmError::LpStillLocked);
+
+    pool.advance_clock(1);
+    assert_success(&pool.unlock_lp());
+    assert_eq!(pool.token_amount(&pool.user_lp), user_lp);
+    assert!(pool.lp_lock(&pool.user).is_none());
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn locked_fees_can_be_claimed_without_touching_principal() {
+    let mut pool = TestPool::with_liquidity(RESERVE_A, RESERVE_B);
+    let amount = pool.token_amount(&pool.user_lp);
+    assert_success(&pool.lock_lp(amount, unlock_ts()));
+    let principal = pool.lp_lock(&pool.user).unwrap().principal;
+
+    // 还没有手续费时领取不改变任何状态
+    assert_success(&pool.claim_lp_lock_fees());
+    assert_eq!(pool.lp_lock(&pool.user).unwrap().amount, amount);
+
+    for _ in 0..5 {
+        pool.advance_clock(60);
+        assert_success(&pool.swap(RESERVE_A / 10, true, 0));
+        assert_success(&pool.swap(RESERVE_B / 10, false, 0));
+    }
+    let (a_before, b_before) = (pool.token_amount(&pool.user_token_a), pool.token_amount(&pool.user_token_b));
+    assert_success(&pool.claim_lp_lock_fees());
+
+    let lock = pool.lp_lock(&pool.user).unwrap();
+    assert!(lock.amount < amount);
+    assert_eq!(pool.token_amount(&pool.lp_lock_vault_address(&pool.user)), lock.amount);
+    assert!(pool.token_amount(&pool.user_token_a) > a_before);
+    assert!(pool.token_amount(&pool.user_token_b) > b_before);
+
+    // 剩下的 LP 仍然覆盖本金
+    let (reserve_a, reserve_b) = pool.reserves();
+    let value = math::calculate_lp_value(lock.amount, reserve_a, reserve_b, pool.lp_supply()).unwrap();
+    assert!(value >= principal);
+}
@@ -0,0 +1,51 @@
+use anchor_lang::prelude::Pubkey;
+use solana_amm::lp_metadata::{
+    fallback_symbol, is_valid, lp_name_and_symbol, FALLBACK_SYMBOL_LENGTH, MAX_NAME_LENGTH,
+    MAX_SYMBOL_LENGTH, MAX_URI_LENGTH,
+};
+
+// 创建元数据需要 Metaplex 程序，Mollusk 中没有加载，这里只测试名称和符号的生成
+
+#[test]
+fn name_and_symbol_from_underlying_symbols() {
+    let (name, symbol) = lp_name_and_symbol("USDC", "SOL");
+    assert_eq!(name, "AMM LP USDC-SOL");
+    assert_eq!(symbol, "USDC-SOL");
+    assert!(is_valid(&name, &symbol, ""));
+}
+
+#[test]
+fn long_symbols_are_truncated_to_metaplex_limits() {
+    let (name, symbol) = lp_name_and_symbol("VERYLONGSYMBOL", "ANOTHERLONGSYMBOL");
+    assert_eq!(name.len(), MAX_NAME_LENGTH);
+    assert_eq!(symbol.len(), MAX_SYMBOL_LENGTH);
+    assert!(name.starts_with("AMM LP VERYLONGSYMBOL-"));
+    assert_eq!(symbol, "VERYLONGSY");
+
+    // 多字节字符不会被切开
+    let (name, symbol) = lp_name_and_symbol("狗狗币", "以太坊");
+    assert!(name.len() <= MAX_NAME_LENGTH && symbol.len() <= MAX_SYMBOL_LENGTH);
+    assert_eq!(symbol, "狗狗币-");
+}
+
+#[test]
+fn fallback_symbol_uses_mint_prefix() {
+    let mint = Pubkey::new_unique();
+    let symbol = fallback_symbol(&mint);
+    assert_eq!(symbol.len(), FALLBACK_SYMBOL_LENGTH);
+    assert!(mint.to_string().starts_with(&symbol));
+
+    let (name, symbol) = lp_name_and_symbol(&symbol, &fallback_symbol(&Pubkey::new_unique()));
+    assert!(is_valid(&name, &symbol, ""));
+}
+
+#[test]
+fn metadata_length_limits() {
+    let name = "n".repeat(MAX_NAME_LENGTH);
+    let symbol = "s".repeat(MAX_SYMBOL_LENGTH);
+    let uri = "u".repeat(MAX_URI_LENGTH);
+    assert!(is_valid(&name, &symbol, &uri));
+    assert!(!is_valid(&format!("{name}n"), &symbol, &uri));
+    assert!(!is_valid(&name, &format!("{symbol}s"), &uri));
+    assert!(!is_valid(&name, &symbol, &format!("{uri}u")));
+}
@@ -0,0 +1,440 @@
+// math 模块的性质测试
+//
+// 这些函数同时被链上程序和链下模拟器使用，不需要加载程序文件，直接调用即可。
+// 更长时间的随机测试见 fuzz/ 下的 cargo-fuzz 目标。
+
+use anchor_lang::AccountDeserialize;
+use proptest::prelude::*;
+use solana_amm::math::{
+    calculate_deposit_liquidity, calculate_locked_fee_lp, calculate_lp_value,
+    calculate_protocol_fee_mint, calculate_referral_fee, calculate_swap_amount_out,
+    calculate_withdraw_amounts, exp2_q64, log2_q64, lp_decimals, reset_farm_reward_debts, settle_farm_stake,
+    sqrt_product_u64, sqrt_u128, update_farm_rewards, update_twap,
+};
+use solana_amm::oracle::{self, Observation};
+use solana_amm::{Farm, FarmStake, PoolState, OBSERVATION_COUNT, OBSERVATION_INTERVAL};
+
+// 全零的空池子，避免在测试里逐个列出字段
+fn empty_pool() -> PoolState {
+    bytemuck::Zeroable::zeroed()
+}
+
+const Q64: f64 = 18_446_744_073_709_551_616.0;
+
+fn price_fixed(numerator: u64, denominator: u64) -> u128 {
+    ((numerator as u128) << 64) / denominator as u128
+}
+
+// (r + 1)^2 超出 u128 时一定大于 n
+fn is_floor_sqrt(n: u128, r: u64) -> bool {
+    let r = r as u128;
+    let next = r + 1;
+    r * r <= n && next.checked_mul(next).is_none_or(|sq| sq > n)
+}
+
+#[test]
+fn sqrt_edge_cases() {
+    for n in [0, 1, 2, 3, 4, 15, 16, 17, u64::MAX as u128, u128::MAX - 1, u128::MAX] {
+        let r = sqrt_u128(n).unwrap();
+        assert!(is_floor_sqrt(n, r), "sqrt({n}) = {r}");
+    }
+    assert_eq!(sqrt_u128(u128::MAX), Some(u64::MAX));
+    assert_eq!(sqrt_product_u64(u64::MAX, u64::MAX), Some(u64::MAX));
+}
+
+#[test]
+fn twap_accumulators_wrap_instead_of_failing() {
+    let mut pool = empty_pool();
+    pool.block_timestamp_last = 100;
+    pool.price_a_cumulative_last = u128::MAX - 1;
+    pool.price_b_cumulative_last = 7;
+
+    // 价格 1.0 持续 1 秒，累加 2^64
+    update_twap(&mut pool, 1, 1, 101);
+    assert_eq!(pool.block_timestamp_last, 101);
+    assert_eq!(pool.price_a_cumulative_last, (1u128 << 64) - 2);
+    assert_eq!(pool.price_b_cumulative_last, 7 + (1u128 << 64));
+}
+
+#[test]
+fn oracle_twap_across_wraparound() {
+    let mut pool = empty_pool();
+    pool.block_timestamp_last = 1_000;
+    pool.price_a_cumulative_last = u128::MAX - 5;
+    let start = Observation::from(&pool);
+
+    // A 的价格是 2.0，维持 30 秒后再维持 10 秒 4.0
+    update_twap(&mut pool, 100, 200, 1_030);
+    let end = oracle::observe(&pool, 100, 400, 1_040);
+    assert!(end.price_a_cumulative < start.price_a_cumulative);
+
+    let twap = oracle::twap(&start, &end).unwrap();
+    assert_eq!(twap.price_a, (2u128 * 30 + 4 * 10) * (1 << 64) / 40);
+
+    assert_eq!(oracle::twap(&end, &end), None);
+    assert_eq!(oracle::twap(&end, &start), None);
+}
+
+#[test]
+fn oracle_observe_does_not_move_backwards() {
+    let mut pool = empty_pool();
+    pool.block_timestamp_last = 500;
+    pool.price_a_cumulative_last = 42;
+
+    let observation = oracle::observe(&pool, 1, 1, 400);
+    assert_eq!(observation, Observation::from(&pool));
+}
+
+#[test]
+fn twap_does_not_accumulate_without_liquidity_or_time() {
+    let mut pool = empty_pool();
+    pool.block_timestamp_last = 100;
+
+    update_twap(&mut pool, 0, 5, 200);
+    assert_eq!(pool.price_a_cumulative_last, 0);
+    assert_eq!(pool.block_timestamp_last, 200);
+
+    update_twap(&mut pool, 5, 5, 200);
+    assert_eq!(pool.price_a_cumulative_last, 0);
+}
+
+#[test]
+fn log2_and_exp2_edge_cases() {
+    assert_eq!(log2_q64(0), None);
+    assert_eq!(log2_q64(1), Some(0));
+    assert_eq!(log2_q64(1 << 100), Some(100 << 64));
+    assert_eq!(exp2_q64(0), Some(1 << 64));
+    assert_eq!(exp2_q64(-64 << 64), Some(1));
+    assert_eq!(exp2_q64(63 << 64), Some(1 << 127));
+    assert_eq!(exp2_q64(64 << 64), None);
+    assert_eq!(exp2_q64(-200 << 64), Some(0));
+    assert_eq!(exp2_q64(i128::MIN), Some(0));
+}
+
+#[test]
+fn observations_respect_interval_and_capacity() {
+    let mut pool = empty_pool();
+    pool.block_timestamp_last = 1_000;
+    solana_amm::math::record_observation(&mut pool, 1_000);
+
+    // 间隔不足时只更新累加器，不写观测
+    update_twap(&mut pool, 1, 2, 1_000 + OBSERVATION_INTERVAL - 1);
+    assert_eq!(pool.observation_count, 1);
+
+    let mut now = 1_000;
+    for _ in 0..OBSERVATION_COUNT * 2 {
+        now += OBSERVATION_INTERVAL;
+        update_twap(&mut pool, 1, 2, now);
+    }
+    assert_eq!(pool.observation_count as usize, OBSERVATION_COUNT);
+    assert_eq!(pool.observations[pool.observation_index as usize].timestamp, now);
+
+    let oldest = now - (OBSERVATION_COUNT as u64 - 1) * OBSERVATION_INTERVAL;
+    assert_eq!(oracle::observation_before(&pool, now - 1).unwrap().timestamp, now - OBSERVATION_INTERVAL);
+    assert_eq!(oracle::observation_before(&pool, oldest).unwrap().timestamp, oldest);
+    assert_eq!(oracle::observation_before(&pool, oldest - 1), None);
+}
+
+proptest! {
+    #[test]
+    fn sqrt_is_floor(n in any::<u128>()) {
+        let r = sqrt_u128(n).unwrap();
+        prop_assert!(is_floor_sqrt(n, r));
+    }
+
+    #[test]
+    fn sqrt_product_matches_sqrt(a in any::<u64>(), b in any::<u64>()) {
+        prop_assert_eq!(sqrt_product_u64(a, b), sqrt_u128(a as u128 * b as u128));
+    }
+
+    #[test]
+    fn twap_accumulators_advance_by_price_times_time(
+        start in any::<u64>(),
+        initial in any::<(u128, u128)>(),
+        steps in prop::collection::vec((0u64..100_000, 1u64..=u64::MAX, 1u64..=u64::MAX), 1..32),
+    ) {
+        let mut pool = empty_pool();
+        pool.block_timestamp_last = start;
+        pool.price_a_cumulative_last = initial.0;
+        pool.price_b_cumulative_last = initial.1;
+        let mut now = start;
+
+        for (dt, reserve_a, reserve_b) in steps {
+            let before = Observation::from(&pool);
+            now = now.saturating_add(dt);
+            let dt = now - before.timestamp;
+            update_twap(&mut pool, reserve_a, reserve_b, now);
+
+            // 增量按 2^128 取模等于 price * dt
+            prop_assert_eq!(pool.block_timestamp_last, now);
+            prop_assert_eq!(
+                pool.price_a_cumulative_last.wrapping_sub(before.price_a_cumulative),
+                price_fixed(reserve_b, reserve_a).wrapping_mul(dt as u128)
+            );
+            prop_assert_eq!(
+                pool.price_b_cumulative_last.wrapping_sub(before.price_b_cumulative),
+                price_fixed(reserve_a, reserve_b).wrapping_mul(dt as u128)
+            );
+        }
+    }
+
+    #[test]
+    fn oracle_twap_matches_constant_price(
+        initial in any::<(u128, u128)>(),
+        reserve_a in 1u64..1 << 40,
+        reserve_b in 1u64..1 << 40,
+        elapsed in 1u64..1 << 20,
+    ) {
+        // 价格不变时，不论累加器从哪里开始、是否回绕，TWAP 都等于当前价格
+        let mut pool = empty_pool();
+        pool.price_a_cumulative_last = initial.0;
+        pool.price_b_cumulative_last = initial.1;
+        let start = Observation::from(&pool);
+        let end = oracle::observe(&pool, reserve_a, reserve_b, elapsed);
+
+        let twap = oracle::twap(&start, &end).unwrap();
+        prop_assert_eq!(twap.price_a, price_fixed(reserve_b, reserve_a));
+        prop_assert_eq!(twap.price_b, price_fixed(reserve_a, reserve_b));
+    }
+
+    #[test]
+    fn log2_matches_float(x in 1u128..) {
+        let expected = (x as f64).log2();
+        let actual = log2_q64(x).unwrap() as f64 / Q64;
+        // 只截断 32 位小数，再加上 f64 本身的舍入
+        prop_assert!((actual - expected).abs() < 1e-9 + expected * f64::EPSILON * 4.0);
+        prop_assert!(actual <= expected + expected * f64::EPSILON * 4.0);
+    }
+
+    #[test]
+    fn exp2_inverts_log2(x in 1u64..) {
+        let back = exp2_q64(log2_q64(x as u128).unwrap()).unwrap();
+        let expected = (x as u128) << 64;
+        prop_assert!(back <= expected);
+        prop_assert!(expected - back <= expected / 1_000_000_000);
+    }
+
+    #[test]
+    fn geometric_twap_is_symmetric(
+        reserve_a in 1u64..,
+        reserve_b in 1u64..,
+        elapsed in 1u64..1 << 32,
+    ) {
+        let pool = empty_pool();
+        let start = Observation::from(&pool);
+        let end = oracle::observe(&pool, reserve_a, reserve_b, elapsed);
+        let twap = oracle::geometric_twap(&start, &end).unwrap();
+
+        // 价格不变时几何均价等于现货价格，且 A、B 两个方向的 log2 均价互为相反数
+        let spot = reserve_b as f64 / reserve_a as f64;
+        let price_a = twap.price_a as f64 / Q64;
+        let price_b = twap.price_b as f64 / Q64;
+        // 价格很小时 Q64.64 只剩下整数部分的精度
+        let tolerance = |price: f64| price * 1e-8 + 2.0 / Q64;
+        prop_assert!((price_a - spot).abs() <= tolerance(spot));
+        prop_assert!((price_b - 1.0 / spot).abs() <= tolerance(1.0 / spot));
+    }
+
+    #[test]
+    fn deviation_bound_is_symmetric_and_monotonic(
+        reference in 1u128 << 40..1u128 << 100,
+        offset_bps in 0u64..20_000,
+        max_bps in 0u16..=10_000,
+    ) {
+        let offset = reference / 10_000 * offset_bps as u128;
+        let above = reference + offset;
+        let below = reference.saturating_sub(offset);
+
+        let inside = offset_bps <= max_bps as u64;
+        prop_assert_eq!(oracle::within_deviation(above, reference, max_bps), inside);
+        prop_assert_eq!(oracle::within_deviation(below, reference, max_bps), inside);
+        prop_assert!(oracle::within_deviation(reference, reference, max_bps));
+    }
+
+    #[test]
+    fn swap_never_decreases_k(
+        amount_in in 1u64..1 << 62,
+        reserve_in in 1u64..1 << 62,
+        reserve_out in 1u64..1 << 62,
+        fee_denominator in 1u64..=10_000,
+        fee_seed in any::<u64>(),
+    ) {
+        let fee_numerator = fee_seed % fee_denominator;
+        let (effective, out) = calculate_swap_amount_out(
+            amount_in, reserve_in, reserve_out, fee_numerator, fee_denominator,
+        ).unwrap();
+
+        prop_assert!(effective <= amount_in);
+        prop_assert!(out < reserve_out);
+        let k_before = reserve_in as u128 * reserve_out as u128;
+        // 只算有效输入时 k 也不减少，手续费部分让 k 严格增长
+        prop_assert!((reserve_in + effective) as u128 * (reserve_out - out) as u128 >= k_before);
+        prop_assert!((reserve_in + amount_in) as u128 * (reserve_out - out) as u128 >= k_before);
+    }
+
+    #[test]
+    fn referral_fee_splits_amount_in(amount_in in any::<u64>(), bps in 0u16..=10_000) {
+        let (referral, to_pool) = calculate_referral_fee(amount_in, bps).unwrap();
+
+        prop_assert_eq!(referral + to_pool, amount_in);
+        prop_assert_eq!(referral as u128, amount_in as u128 * bps as u128 / 10_000);
+    }
+
+    #[test]
+    fn locked_fee_lp_never_touches_principal(
+        reserve_a in 1_000u64..1 << 40,
+        reserve_b in 1_000u64..1 << 40,
+        supply_seed in any::<u64>(),
+        amount_seed in any::<u64>(),
+        fee_a in 0u64..1 << 30,
+        fee_b in 0u64..1 << 30,
+    ) {
+        let lp_supply = sqrt_product_u64(reserve_a, reserve_b).unwrap().max(1_000);
+        let lp_supply = lp_supply / 2 + supply_seed % lp_supply;
+        let amount = 1 + amount_seed % lp_supply;
+        let principal = calculate_lp_value(amount, reserve_a, reserve_b, lp_supply).unwrap();
+
+        // 储备金不变时没有手续费
+        prop_assert_eq!(calculate_locked_fee_lp(amount, principal, reserve_a, reserve_b, lp_supply), Some(0));
+
+        // 手续费让储备金增长，领取之后剩下的 LP 仍然覆盖本金
+        let (grown_a, grown_b) = (reserve_a + fee_a, reserve_b + fee_b);
+        let fee_lp = calculate_locked_fee_lp(amount, principal, grown_a, grown_b, lp_supply).unwrap();
+        prop_assert!(fee_lp <= amount);
+        prop_assert!(calculate_lp_value(amount - fee_lp, grown_a, grown_b, lp_supply).unwrap() >= principal);
+    }
+
+    #[test]
+    fn farm_rewards_never_exceed_emission(
+        emission in 1u64..1 << 32,
+        duration in 1i64..1 << 24,
+        joined_at in 0i64..1 << 24,
+        settled_at in 0i64..1 << 25,
+        amount_a in 1u64..1 << 39,
+        amount_b in 1u64..1 << 39,
+    ) {
+        let mut farm = Farm::try_deserialize_unchecked(&mut vec![0u8; Farm::LEN].as_slice()).unwrap();
+        farm.reward_count = 1;
+        farm.rewards[0].emission_per_second = emission;
+        farm.rewards[0].end_ts = duration;
+        let empty_stake = || FarmStake::try_deserialize_unchecked(&mut vec![0u8; FarmStake::LEN].as_slice()).unwrap();
+
+        // A 从 0 开始质押，B 在 joined_at 加入，最后在 settled_at 一起结算
+        let mut stake_a = empty_stake();
+        stake_a.amount = amount_a;
+        farm.total_staked = amount_a;
+
+        let joined_at = joined_at.min(settled_at);
+        let mut stake_b = empty_stake();
+        update_farm_rewards(&mut farm, joined_at).unwrap();
+        settle_farm_stake(&farm, &mut stake_b).unwrap();
+        stake_b.amount = amount_b;
+        farm.total_staked += amount_b;
+        reset_farm_reward_debts(&farm, &mut stake_b).unwrap();
+
+        update_farm_rewards(&mut farm, settled_at).unwrap();
+        settle_farm_stake(&farm, &mut stake_a).unwrap();
+        settle_farm_stake(&farm, &mut stake_b).unwrap();
+
+        // 向下取整只会少发：两次累加和两个质押者各自最多少 1
+        let emitted = emission as u128 * settled_at.min(duration) as u128;
+        let paid = stake_a.pending_rewards[0] as u128 + stake_b.pending_rewards[0] as u128;
+        prop_assert!(paid <= emitted);
+        prop_assert!(paid + 4 >= emitted);
+    }
+
+    #[test]
+    fn farm_emission_pauses_without_stake(
+        emission in 1u64..1 << 32,
+        duration in 1i64..1 << 24,
+        idle in 0i64..1 << 25,
+        amount in 1u64..1 << 39,
+    ) {
+        let mut farm = Farm::try_deserialize_unchecked(&mut vec![0u8; Farm::LEN].as_slice()).unwrap();
+        farm.reward_count = 1;
+        farm.rewards[0].emission_per_second = emission;
+        farm.rewards[0].end_ts = duration;
+
+        // 前 idle 秒没有人质押，释放顺延，之后质押的人拿到全部奖励
+        update_farm_rewards(&mut farm, idle).unwrap();
+        prop_assert_eq!(farm.rewards[0].end_ts, idle + duration);
+        let mut stake = FarmStake::try_deserialize_unchecked(&mut vec![0u8; FarmStake::LEN].as_slice()).unwrap();
+        stake.amount = amount;
+        farm.total_staked = amount;
+        reset_farm_reward_debts(&farm, &mut stake).unwrap();
+
+        update_farm_rewards(&mut farm, idle + 2 * duration).unwrap();
+        settle_farm_stake(&farm, &mut stake).unwrap();
+        let emitted = emission as u128 * duration as u128;
+        prop_assert!(stake.pending_rewards[0] as u128 <= emitted);
+        prop_assert!(stake.pending_rewards[0] as u128 + 1 >= emitted);
+    }
+
+    #[test]
+    fn lp_decimals_is_between_token_decimals(decimals_a in any::<u8>(), decimals_b in any::<u8>()) {
+        let decimals = lp_decimals(decimals_a, decimals_b);
+
+        prop_assert!(decimals >= decimals_a.min(decimals_b));
+        prop_assert!(decimals <= decimals_a.max(decimals_b));
+        prop_assert_eq!(decimals, lp_decimals(decimals_b, decimals_a));
+    }
+
+    #[test]
+    fn deposit_then_withdraw_never_returns_more(
+        reserve_a in 1u64..1 << 40,
+        reserve_b in 1u64..1 << 40,
+        lp_supply in 1u64..1 << 40,
+        amount_a in 0u64..1 << 40,
+        amount_b in 0u64..1 << 40,
+    ) {
+        let minted = calculate_deposit_liquidity(amount_a, amount_b, reserve_a, reserve_b, lp_supply).unwrap();
+        let (out_a, out_b) = calculate_withdraw_amounts(
+            minted, reserve_a + amount_a, reserve_b + amount_b, lp_supply + minted,
+        ).unwrap();
+
+        prop_assert!(out_a <= amount_a);
+        prop_assert!(out_b <= amount_b);
+    }
+
+    #[test]
+    fn protocol_fee_stays_within_share(
+        reserve_a in 1u64..1 << 40,
+        reserve_b in 1u64..1 << 40,
+        growth_a in 0u64..1 << 30,
+        growth_b in 0u64..1 << 30,
+        lp_supply in 1u64..1 << 40,
+        fee_share in 1u64..=500,
+    ) {
+        let k_last = reserve_a as u128 * reserve_b as u128;
+        let minted = calculate_protocol_fee_mint(
+            reserve_a + growth_a, reserve_b + growth_b, k_last, lp_supply, fee_share,
+        ).unwrap() as u128;
+
+        let root_k = sqrt_product_u64(reserve_a + growth_a, reserve_b + growth_b).unwrap() as u128;
+        let root_k_last = sqrt_u128(k_last).unwrap() as u128;
+        let growth = root_k - root_k_last;
+        let supply = lp_supply as u128;
+
+        // 协议方持有的份额不超过增长部分的 1 / (fee_share + 1)
+        prop_assert!(minted * (fee_share as u128 + 1) * root_k <= (supply + minted) * growth);
+        // 向下取整最多少算 1
+        prop_assert!((minted + 1) * (fee_share as u128 * root_k + root_k_last) > supply * growth);
+    }
+
+    #[test]
+    fn protocol_fee_is_zero_when_disabled_or_k_shrinks(
+        reserve_a in 1u64..1 << 40,
+        reserve_b in 1u64..1 << 40,
+        shrink in 0u64..1 << 20,
+        lp_supply in any::<u64>(),
+        fee_share in 0u64..=500,
+    ) {
+        let k = reserve_a as u128 * reserve_b as u128;
+        prop_assert_eq!(calculate_protocol_fee_mint(reserve_a, reserve_b, k, lp_supply, 0), Some(0));
+        prop_assert_eq!(calculate_protocol_fee_mint(reserve_a, reserve_b, 0, lp_supply, fee_share), Some(0));
+        prop_assert_eq!(
+            calculate_protocol_fee_mint(reserve_a, reserve_b, k + shrink as u128, lp_supply, fee_share),
+            Some(0)
+        );
+    }
+}
@@ -0,0 +1,117 @@
+mod common;
+
+use anchor_lang::error::ErrorCode;
+use common::*;
+use mollusk_svm::result::ProgramResult;
+use solana_amm::{AmmError, LegacyPoolState, PoolState, CURRENT_POOL_VERSION, POOL_RESERVED_BYTES, POOL_V2_LEN};
+use solana_program_error::ProgramError;
+
+const RESERVE_A: u64 = 1_000_000_000;
+const RESERVE_B: u64 = 2_000_000_000;
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn initialize_writes_current_version() {
+    let pool = TestPool::initialized();
+
+    let state = pool.pool_state();
+    assert_eq!(state.version, CURRENT_POOL_VERSION);
+    assert_eq!((state.max_total_reserve_a, state.max_deposit_a), (0, 0));
+    assert_error(&pool.migrate_pool(&pool.admin), AmmError::PoolAlreadyMigrated);
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn borsh_pool_is_converted_and_keeps_its_fields() {
+    let mut pool = TestPool::with_liquidity(RESERVE_A, RESERVE_B);
+    assert_success(&pool.set_max_referral_fee(&pool.admin, 30));
+    let new_admin = pool.user;
+    assert_success(&pool.update_config(&pool.admin, Some(new_admin), None, None));
+    let before = pool.pool_state();
+    pool.rewrite_as_legacy(1, LegacyPoolState::LEN);
+
+    // 旧账户长度不够，其他指令无法按零拷贝布局读取
+    assert!(pool.swap(1_000, true, 0).program_result.is_err());
+
+    assert_success(&pool.migrate_pool(&pool.user));
+    let account = pool.account(&pool.pool_state).unwrap();
+    assert_eq!(account.data.len(), PoolState::LEN);
+
+    let after = pool.pool_state();
+    assert_eq!(after.version, CURRENT_POOL_VERSION);
+    assert_eq!(after.admin, before.admin);
+    assert_eq!(after.fee_numerator, before.fee_numerator);
+    assert_eq!(after.k_last, before.k_last);
+    assert_eq!(after.price_a_cumulative_last, before.price_a_cumulative_last);
+    assert_eq!(after.log_price_cumulative_last, before.log_price_cumulative_last);
+    assert_eq!(after.observations, before.observations);
+    assert_eq!(after.pending_admin(), Some(new_admin));
+    assert_eq!(after.max_referral_fee_bps, 30);
+
+    // 迁移之后可以正常交易，再次迁移会被拒绝
+    pool.advance_clock(60);
+    assert_success(&pool.swap(1_000, true, 0));
+    assert_error(&pool.migrate_pool(&pool.user), AmmError::PoolAlreadyMigrated);
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn version_2_pool_is_extended_with_reserved_space() {
+    let mut pool = TestPool::with_liquidity(RESERVE_A, RESERVE_B);
+    assert_success(&pool.set_deposit_caps(&pool.admin, 0, 0, RESERVE_A, 0));
+    let before = pool.pool_state();
+    pool.rewrite_as_v2();
+    assert_eq!(pool.account(&pool.pool_state).unwrap().data.len(), POOL_V2_LEN);
+
+    // 账户长度不够，其他指令无法按当前布局读取
+    assert!(pool.swap(1_000, true, 0).program_result.is_err());
+
+    assert_success(&pool.migrate_pool(&pool.user));
+    assert_eq!(pool.account(&pool.pool_state).unwrap().data.len(), PoolState::LEN);
+
+    // 零拷贝字段原样保留，只追加全零的预留空间
+    let after = pool.pool_state();
+    assert_eq!(after.version, CURRENT_POOL_VERSION);
+    assert_eq!(after.reserved, [0; POOL_RESERVED_BYTES]);
+    assert_eq!(after.admin, before.admin);
+    assert_eq!(after.k_last, before.k_last);
+    assert_eq!(after.block_timestamp_last, before.block_timestamp_last);
+    assert_eq!(after.observations, before.observations);
+    assert_eq!(after.max_deposit_a, RESERVE_A);
+
+    pool.advance_clock(60);
+    assert_success(&pool.swap(1_000, true, 0));
+    assert_error(&pool.migrate_pool(&pool.user), AmmError::PoolAlreadyMigrated);
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn fields_missing_from_older_layouts_default_to_zero() {
+    let mut pool = TestPool::with_liquidity(RESERVE_A, RESERVE_B);
+    assert_success(&pool.set_max_price_deviation(&pool.admin, 500));
+    assert_success(&pool.set_max_referral_fee(&pool.admin, 30));
+    // 再去掉 max_price_deviation_bps 和 max_referral_fee_bps
+    pool.rewrite_as_legacy(0, LEGACY_POOL_V0_LEN - 4);
+
+    assert_success(&pool.migrate_pool(&pool.admin));
+    let state = pool.pool_state();
+    assert_eq!(state.version, CURRENT_POOL_VERSION);
+    assert_eq!(state.max_price_deviation_bps, 0);
+    assert_eq!(state.max_referral_fee_bps, 0);
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn non_pool_accounts_are_rejected() {
+    let mut pool = TestPool::initialized();
+    let fee_override = pool.fee_override_address(&pool.user);
+    let user = pool.user;
+    assert_success(&pool.set_fee_override(&pool.admin, &user, 1));
+
+    // 把 FeeOverride 当作池子传入
+    pool.pool_state = fee_override;
+    assert_eq!(
+        pool.migrate_pool(&pool.admin).program_result,
+        ProgramResult::Failure(ProgramError::Custom(ErrorCode::AccountDiscriminatorMismatch as u32))
+    );
+}
@@ -0,0 +1,152 @@
+mod common;
+
+use common::*;
+use solana_amm::{math, AmmError, OBSERVATION_COUNT, OBSERVATION_INTERVAL};
+
+const RESERVE_A: u64 = 1_000_000_000;
+const RESERVE_B: u64 = 4_000_000_000;
+
+// Q64.64 的 4.0 和 0.25，允许 log2 截断带来的 1e-9 相对误差
+fn assert_close(actual: u128, expected: u128) {
+    let diff = actual.abs_diff(expected);
+    assert!(diff <= expected / 1_000_000_000, "{actual} != {expected}");
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn initialize_records_first_observation() {
+    let pool = TestPool::initialized();
+
+    let state = pool.pool_state();
+    assert_eq!(state.observation_count, 1);
+    assert_eq!(state.observations[0].timestamp, START_TIMESTAMP);
+    assert_eq!(state.log_price_cumulative_last, 0);
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn geometric_twap_at_constant_price_equals_spot() {
+    let mut pool = TestPool::with_liquidity(RESERVE_A, RESERVE_B);
+
+    pool.advance_clock(OBSERVATION_INTERVAL * 2);
+    let result = pool.get_geometric_twap(OBSERVATION_INTERVAL);
+    assert_success(&result);
+
+    let twap = geometric_twap(&result);
+    assert_eq!(twap.end, START_TIMESTAMP + OBSERVATION_INTERVAL * 2);
+    assert!(twap.end - twap.start >= OBSERVATION_INTERVAL);
+    assert_close(twap.price_a, 4 << 64);
+    assert_close(twap.price_b, 1 << 62);
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn geometric_twap_is_symmetric_after_price_moves() {
+    let mut pool = TestPool::with_liquidity(RESERVE_A, RESERVE_B);
+
+    for _ in 0..4 {
+        pool.advance_clock(OBSERVATION_INTERVAL);
+        assert_success(&pool.swap(RESERVE_A / 10, true, 0));
+    }
+    let result = pool.get_geometric_twap(OBSERVATION_INTERVAL * 3);
+    assert_success(&result);
+
+    // price_a * price_b = 1，即 Q64.64 下乘积为 2^128，这里右移 64 位后和 2^64 比较
+    let twap = geometric_twap(&result);
+    let product = (twap.price_a >> 32) * (twap.price_b >> 32);
+    assert_close(product, 1 << 64);
+
+    // 几何均价落在窗口内的最低价和最高价之间
+    let (a, b) = pool.reserves();
+    let spot_now = math::exp2_q64(
+        math::log2_q64(b as u128).unwrap() - math::log2_q64(a as u128).unwrap(),
+    )
+    .unwrap();
+    assert!(twap.price_a < 4 << 64);
+    assert!(twap.price_a > spot_now);
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn observation_buffer_wraps_around() {
+    let mut pool = TestPool::with_liquidity(RESERVE_A, RESERVE_B);
+
+    for _ in 0..OBSERVATION_COUNT + 3 {
+        pool.advance_clock(OBSERVATION_INTERVAL);
+        assert_success(&pool.swap(1_000, true, 0));
+    }
+
+    let state = pool.pool_state();
+    assert_eq!(state.observation_count as usize, OBSERVATION_COUNT);
+    let newest = state.observations[state.observation_index as usize];
+    assert_eq!(newest.timestamp, state.block_timestamp_last);
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn window_longer_than_history_is_rejected() {
+    let mut pool = TestPool::with_liquidity(RESERVE_A, RESERVE_B);
+    pool.advance_clock(60);
+
+    assert_error(&pool.get_geometric_twap(3_600), AmmError::OracleWindowUnavailable);
+    assert_error(&pool.get_geometric_twap(0), AmmError::OracleWindowUnavailable);
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn price_deviation_config_is_validated() {
+    let pool = TestPool::initialized();
+
+    assert_error(&pool.set_max_price_deviation(&pool.admin, 10_001), AmmError::InvalidPriceDeviationConfig);
+    assert_error(&pool.set_max_price_deviation(&pool.user, 100), AmmError::Unauthorized);
+    assert_success(&pool.set_max_price_deviation(&pool.admin, 10_000));
+    assert_eq!(pool.pool_state().max_price_deviation_bps, 10_000);
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn swap_beyond_price_deviation_is_rejected() {
+    let mut pool = TestPool::with_liquidity(RESERVE_A, RESERVE_B);
+    assert_success(&pool.set_max_price_deviation(&pool.admin, 100));
+    pool.advance_clock(60);
+
+    // 10% 的储备金会把价格推动约 19%
+    assert_error(&pool.swap(RESERVE_A / 10, true, 0), AmmError::PriceDeviationTooLarge);
+    assert_error(&pool.swap(RESERVE_B / 10, false, 0), AmmError::PriceDeviationTooLarge);
+    // 0.1% 只推动约 0.2%
+    assert_success(&pool.swap(RESERVE_A / 1_000, true, 0));
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn same_slot_swaps_cannot_compound_past_the_bound() {
+    let mut pool = TestPool::with_liquidity(RESERVE_A, RESERVE_B);
+    assert_success(&pool.set_max_price_deviation(&pool.admin, 100));
+    pool.advance_clock(60);
+
+    // 每笔约 0.3%，同一秒内都和同一个参考价比较，累计到第四笔就会超过 1%
+    let mut accepted = 0;
+    while accepted < 10 {
+        let result = pool.swap(RESERVE_A * 15 / 10_000, true, 0);
+        if result.program_result.is_err() {
+            assert_error(&result, AmmError::PriceDeviationTooLarge);
+            break;
+        }
+        accepted += 1;
+    }
+    assert!((1..4).contains(&accepted), "accepted {accepted} swaps");
+
+    // 时间过去之后 TWAP 跟上新价格，可以继续交易
+    pool.advance_clock(OBSERVATION_INTERVAL * 2);
+    assert_success(&pool.swap(RESERVE_A * 15 / 10_000, true, 0));
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn price_guard_is_off_by_default() {
+    let mut pool = TestPool::with_liquidity(RESERVE_A, RESERVE_B);
+    pool.advance_clock(60);
+
+    assert_eq!(pool.pool_state().max_price_deviation_bps, 0);
+    assert_success(&pool.swap(RESERVE_A / 2, true, 0));
+}
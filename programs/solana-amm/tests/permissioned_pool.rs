@@ -0,0 +1,126 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use common::*;
+use solana_amm::AmmError;
+
+const RESERVE_A: u64 = 1_000_000_000;
+const RESERVE_B: u64 = 2_000_000_000;
+const AMOUNT: u64 = 1_000_000;
+const DAY: u64 = 86_400;
+
+fn permissioned_pool() -> (TestPool, Pubkey) {
+    let mut pool = TestPool::with_liquidity(RESERVE_A, RESERVE_B);
+    let gatekeeper = Pubkey::new_unique();
+    pool.fund(&gatekeeper);
+    assert_success(&pool.set_gatekeeper(&pool.admin, &gatekeeper));
+    (pool, gatekeeper)
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn only_admin_enables_permission_mode() {
+    let pool = TestPool::with_liquidity(RESERVE_A, RESERVE_B);
+    let gatekeeper = Pubkey::new_unique();
+
+    assert_error(&pool.set_gatekeeper(&pool.user, &gatekeeper), AmmError::Unauthorized);
+    assert!(!pool.pool_state().permissioned());
+    // 没有开启许可模式的池子不需要 AccessPass
+    assert_success(&pool.swap(AMOUNT, true, 0));
+
+    assert_success(&pool.set_gatekeeper(&pool.admin, &gatekeeper));
+    assert!(pool.pool_state().permissioned());
+
+    // 传入默认地址关闭
+    assert_success(&pool.set_gatekeeper(&pool.admin, &Pubkey::default()));
+    assert!(!pool.pool_state().permissioned());
+    assert_success(&pool.swap(AMOUNT, true, 0));
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn trading_requires_an_access_pass() {
+    let (pool, gatekeeper) = permissioned_pool();
+
+    assert_error(&pool.swap(AMOUNT, true, 0), AmmError::AccessPassRequired);
+    assert_error(&pool.add_liquidity(AMOUNT, 2 * AMOUNT), AmmError::AccessPassRequired);
+    assert_error(&pool.remove_liquidity(AMOUNT, 0, 0), AmmError::AccessPassRequired);
+
+    assert_error(&pool.issue_access_pass(&pool.admin, &pool.user, 0), AmmError::Unauthorized);
+    assert_success(&pool.issue_access_pass(&gatekeeper, &pool.user, 0));
+    let access_pass = pool.access_pass(&pool.user).unwrap();
+    assert_eq!((access_pass.pool, access_pass.user), (pool.pool_state, pool.user));
+    assert_eq!(access_pass.issued_ts, START_TIMESTAMP as i64);
+
+    assert_success(&pool.swap(AMOUNT, true, 0));
+    assert_success(&pool.add_liquidity(AMOUNT, 2 * AMOUNT));
+    assert_success(&pool.remove_liquidity(AMOUNT, 0, 0));
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn revoked_or_expired_pass_is_rejected() {
+    let (mut pool, gatekeeper) = permissioned_pool();
+    let expires_ts = (START_TIMESTAMP + DAY) as i64;
+
+    assert_error(
+        &pool.issue_access_pass(&gatekeeper, &pool.user, START_TIMESTAMP as i64),
+        AmmError::InvalidAccessPass
+    );
+    assert_success(&pool.issue_access_pass(&gatekeeper, &pool.user, expires_ts));
+    assert_success(&pool.swap(AMOUNT, true, 0));
+
+    pool.advance_clock(DAY);
+    assert_error(&pool.swap(AMOUNT, true, 0), AmmError::AccessPassExpired);
+
+    // 重新发放时续期
+    assert_success(&pool.issue_access_pass(&gatekeeper, &pool.user, 0));
+    assert_success(&pool.swap(AMOUNT, true, 0));
+
+    assert_error(&pool.revoke_access_pass(&pool.admin, &pool.user), AmmError::Unauthorized);
+    assert_success(&pool.revoke_access_pass(&gatekeeper, &pool.user));
+    assert!(pool.access_pass_account(&pool.user).is_none());
+    assert_error(&pool.swap(AMOUNT, true, 0), AmmError::AccessPassRequired);
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn changing_gatekeeper_invalidates_issued_passes() {
+    let (mut pool, gatekeeper) = permissioned_pool();
+    assert_success(&pool.issue_access_pass(&gatekeeper, &pool.user, 0));
+    assert_eq!(pool.access_pass(&pool.user).unwrap().issuer, gatekeeper);
+
+    let new_gatekeeper = Pubkey::new_unique();
+    pool.fund(&new_gatekeeper);
+    assert_success(&pool.set_gatekeeper(&pool.admin, &new_gatekeeper));
+    assert_error(&pool.swap(AMOUNT, true, 0), AmmError::InvalidAccessPass);
+
+    // 新守门人重新发放之后恢复
+    assert_success(&pool.issue_access_pass(&new_gatekeeper, &pool.user, 0));
+    assert_success(&pool.swap(AMOUNT, true, 0));
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn claiming_locked_fees_requires_an_access_pass() {
+    let mut pool = TestPool::with_liquidity(RESERVE_A, RESERVE_B);
+    let lp = pool.token_amount(&pool.user_lp);
+    assert_success(&pool.lock_lp(lp / 2, (START_TIMESTAMP + DAY) as i64));
+    let gatekeeper = Pubkey::new_unique();
+    pool.fund(&gatekeeper);
+    assert_success(&pool.set_gatekeeper(&pool.admin, &gatekeeper));
+
+    assert_error(&pool.claim_lp_lock_fees(), AmmError::AccessPassRequired);
+    assert_success(&pool.issue_access_pass(&gatekeeper, &pool.user, 0));
+    assert_success(&pool.claim_lp_lock_fees());
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn order_entry_points_are_disabled() {
+    let (pool, gatekeeper) = permissioned_pool();
+    assert_success(&pool.issue_access_pass(&gatekeeper, &pool.user, 0));
+
+    // 挂单之后由 keeper 代为成交，无法检查 AccessPass
+    assert_error(&pool.place_order(1, AMOUNT, true, 1, 0), AmmError::PermissionedPool);
+}
@@ -0,0 +1,102 @@
+// pool_liquidity 模块的单元测试
+//
+// 和 pool_swap_math 一样只调用纯函数，不需要加载程序文件。
+
+use solana_amm::math::{calculate_deposit_liquidity, calculate_protocol_fee_mint, MINIMUM_LIQUIDITY};
+use solana_amm::pool_liquidity::{execute_add_liquidity, execute_remove_liquidity, PoolDeposit, PoolWithdrawal};
+use solana_amm::{AmmError, PoolState};
+
+const START: u64 = 1_000_000;
+const RESERVE: u64 = 1_000_000_000;
+
+fn pool() -> PoolState {
+    let mut pool: PoolState = bytemuck::Zeroable::zeroed();
+    pool.fee_numerator = 3;
+    pool.fee_denominator = 1000;
+    pool.block_timestamp_last = START;
+    pool
+}
+
+#[test]
+fn first_deposit_locks_minimum_liquidity() {
+    let mut pool = pool();
+    let deposit = execute_add_liquidity(&mut pool, 0, 0, 0, RESERVE, 4 * RESERVE, START).unwrap();
+    assert_eq!(
+        deposit,
+        PoolDeposit {
+            protocol_fee_lp: 0,
+            locked_lp: MINIMUM_LIQUIDITY,
+            liquidity: 2 * RESERVE - MINIMUM_LIQUIDITY,
+            reserve_a_after: RESERVE,
+            reserve_b_after: 4 * RESERVE,
+        }
+    );
+    assert_eq!(pool.k_last, RESERVE as u128 * 4 * RESERVE as u128);
+
+    let mut pool = self::pool();
+    assert!(matches!(
+        execute_add_liquidity(&mut pool, 0, 0, 0, MINIMUM_LIQUIDITY, MINIMUM_LIQUIDITY, START),
+        Err(AmmError::InitialLiquidityTooLow)
+    ));
+}
+
+#[test]
+fn later_deposits_include_the_protocol_fee_in_the_supply() {
+    let mut pool = pool();
+    pool.protocol_fee_share = 5;
+    // 手续费让 k 从 RESERVE^2 增长到 (1.1 * RESERVE)^2
+    pool.k_last = RESERVE as u128 * RESERVE as u128;
+    let reserve = RESERVE + RESERVE / 10;
+    let lp_supply = RESERVE;
+
+    let deposit = execute_add_liquidity(&mut pool, reserve, reserve, lp_supply, 1_000_000, 1_000_000, START).unwrap();
+    let protocol_fee_lp = calculate_protocol_fee_mint(reserve, reserve, RESERVE as u128 * RESERVE as u128, lp_supply, 5).unwrap();
+    assert!(protocol_fee_lp > 0);
+    assert_eq!(deposit.protocol_fee_lp, protocol_fee_lp);
+    assert_eq!(deposit.locked_lp, 0);
+    assert_eq!(
+        deposit.liquidity,
+        calculate_deposit_liquidity(1_000_000, 1_000_000, reserve, reserve, lp_supply + protocol_fee_lp).unwrap()
+    );
+    assert_eq!(pool.k_last, (reserve + 1_000_000) as u128 * (reserve + 1_000_000) as u128);
+}
+
+#[test]
+fn deposits_respect_the_caps() {
+    let mut pool = pool();
+    pool.max_deposit_a = 1_000;
+    assert!(matches!(
+        execute_add_liquidity(&mut pool, RESERVE, RESERVE, RESERVE, 1_001, 1_001, START),
+        Err(AmmError::CapExceeded)
+    ));
+
+    let mut pool = self::pool();
+    pool.max_total_reserve_b = RESERVE + 1_000;
+    assert!(matches!(
+        execute_add_liquidity(&mut pool, RESERVE, RESERVE, RESERVE, 1_001, 1_001, START),
+        Err(AmmError::CapExceeded)
+    ));
+    assert!(execute_add_liquidity(&mut pool, RESERVE, RESERVE, RESERVE, 1_000, 1_000, START).is_ok());
+}
+
+#[test]
+fn withdrawal_is_proportional_and_updates_k_last() {
+    let mut pool = pool();
+    let withdrawal = execute_remove_liquidity(&mut pool, RESERVE, 2 * RESERVE, RESERVE, RESERVE / 4, 0, 0, START).unwrap();
+    assert_eq!(
+        withdrawal,
+        PoolWithdrawal {
+            protocol_fee_lp: 0,
+            amount_a: RESERVE / 4,
+            amount_b: RESERVE / 2,
+            reserve_a_after: RESERVE - RESERVE / 4,
+            reserve_b_after: 2 * RESERVE - RESERVE / 2,
+        }
+    );
+    assert_eq!(pool.k_last, (RESERVE - RESERVE / 4) as u128 * (2 * RESERVE - RESERVE / 2) as u128);
+
+    assert!(matches!(
+        execute_remove_liquidity(&mut pool, RESERVE, 2 * RESERVE, RESERVE, RESERVE / 4, 0, RESERVE / 2 + 1, START),
+        Err(AmmError::SlippageExceeded)
+    ));
+}
@@ -0,0 +1,82 @@
+mod common;
+
+use common::*;
+use solana_amm::{pda, AmmError, LegacyPoolState, RegistryEntry, REGISTRY_PAGE_SIZE};
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn initialize_appends_pool_to_registry() {
+    let pool = TestPool::initialized();
+
+    let registry = pool.pool_registry().unwrap();
+    assert_eq!(registry.pool_count, 1);
+    let expected = RegistryEntry {
+        pool: pool.pool_state,
+        mint_a: pool.mint_a,
+        mint_b: pool.mint_b,
+        fee_numerator: FEE_NUMERATOR,
+        fee_denominator: FEE_DENOMINATOR,
+    };
+    assert_eq!(pool.registry_page(0).unwrap().entries, vec![expected]);
+    assert!(pool.pool_state().registered());
+
+    let result = pool.get_registry_page(0);
+    assert_success(&result);
+    let page = registry_page_data(&result);
+    assert_eq!((page.pool_count, page.page_index), (1, 0));
+    assert_eq!(page.entries, vec![expected]);
+
+    // 已经登记的池子不能重复登记
+    assert_error(&pool.register_pool(&pool.user), AmmError::PoolAlreadyRegistered);
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn full_page_rolls_over_to_next_page() {
+    let mut pool = TestPool::initialized();
+
+    let mut pools = vec![pool.pool_state];
+    for _ in 0..REGISTRY_PAGE_SIZE {
+        let (pool_state, result) = pool.initialize_other_pool(1, 100);
+        assert_success(&result);
+        pools.push(pool_state);
+    }
+
+    assert_eq!(pool.pool_registry().unwrap().pool_count, REGISTRY_PAGE_SIZE as u64 + 1);
+    let first = pool.registry_page(0).unwrap();
+    assert_eq!(first.entries.len(), REGISTRY_PAGE_SIZE);
+    assert_eq!(first.entries.iter().map(|entry| entry.pool).collect::<Vec<_>>(), pools[..REGISTRY_PAGE_SIZE]);
+
+    let result = pool.get_registry_page(1);
+    assert_success(&result);
+    let page = registry_page_data(&result);
+    assert_eq!((page.pool_count, page.page_index), (REGISTRY_PAGE_SIZE as u64 + 1, 1));
+    assert_eq!(page.entries.len(), 1);
+    assert_eq!(page.entries[0].pool, pools[REGISTRY_PAGE_SIZE]);
+    assert_eq!((page.entries[0].fee_numerator, page.entries[0].fee_denominator), (1, 100));
+
+    // 还没有创建的分页读不到
+    assert!(pool.get_registry_page(2).program_result.is_err());
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn register_pool_backfills_pools_created_before_registry() {
+    let mut pool = TestPool::initialized();
+
+    // 模拟注册表上线之前创建、还没有迁移的池子
+    pool.rewrite_as_legacy(1, LegacyPoolState::LEN);
+    pool.remove_account(&pda::pool_registry_address());
+    pool.remove_account(&pda::registry_page_address(0));
+    assert_success(&pool.migrate_pool(&pool.user));
+    assert!(!pool.pool_state().registered());
+
+    assert_success(&pool.register_pool(&pool.user));
+    assert!(pool.pool_state().registered());
+    assert_eq!(pool.pool_registry().unwrap().pool_count, 1);
+    let entry = pool.registry_page(0).unwrap().entries[0];
+    assert_eq!((entry.pool, entry.mint_a, entry.mint_b), (pool.pool_state, pool.mint_a, pool.mint_b));
+
+    assert_error(&pool.register_pool(&pool.user), AmmError::PoolAlreadyRegistered);
+    assert_eq!(pool.pool_registry().unwrap().pool_count, 1);
+}
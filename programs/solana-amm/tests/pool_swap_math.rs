@@ -0,0 +1,93 @@
+// pool_swap 模块的单元测试
+//
+// 和 twamm_math 一样只调用纯函数，不需要加载程序文件。
+
+use solana_amm::math::{self, calculate_swap_amount_out};
+use solana_amm::pool_swap::{check_pool_bounds, execute_pool_swap, PoolSwap};
+use solana_amm::{AmmError, PoolState};
+
+const START: u64 = 1_000_000;
+const RESERVE: u64 = 1_000_000_000;
+
+fn pool() -> PoolState {
+    let mut pool: PoolState = bytemuck::Zeroable::zeroed();
+    pool.fee_numerator = 3;
+    pool.fee_denominator = 1000;
+    pool.block_timestamp_last = START;
+    pool
+}
+
+#[test]
+fn swap_matches_constant_product_math() {
+    let mut pool = pool();
+    let swap = execute_pool_swap(&mut pool, RESERVE, 2 * RESERVE, 10_000, true, 3, 0, START).unwrap();
+    let (amount_in_effective, amount_out) = calculate_swap_amount_out(10_000, RESERVE, 2 * RESERVE, 3, 1000).unwrap();
+    assert_eq!(
+        swap,
+        PoolSwap {
+            amount_in_effective,
+            amount_out,
+            reserve_a_after: RESERVE + 10_000,
+            reserve_b_after: 2 * RESERVE - amount_out,
+        }
+    );
+
+    // 折扣费率由调用者传入
+    let discounted = execute_pool_swap(&mut pool, RESERVE, 2 * RESERVE, 10_000, true, 0, 0, START).unwrap();
+    assert!(discounted.amount_out > amount_out);
+
+    let swap = execute_pool_swap(&mut pool, RESERVE, 2 * RESERVE, 10_000, false, 3, 0, START).unwrap();
+    assert_eq!((swap.reserve_a_after, swap.reserve_b_after), (RESERVE - swap.amount_out, 2 * RESERVE + 10_000));
+}
+
+#[test]
+fn swap_rejects_slippage() {
+    let mut pool = pool();
+    let (_, amount_out) = calculate_swap_amount_out(10_000, RESERVE, RESERVE, 3, 1000).unwrap();
+    assert!(matches!(
+        execute_pool_swap(&mut pool, RESERVE, RESERVE, 10_000, true, 3, amount_out + 1, START),
+        Err(AmmError::SlippageExceeded)
+    ));
+    assert!(execute_pool_swap(&mut pool, RESERVE, RESERVE, 10_000, true, 3, amount_out, START).is_ok());
+}
+
+#[test]
+fn swap_rejects_price_deviation() {
+    let mut pool = pool();
+    // 先记下一条观测，之后才有参考价
+    pool.max_price_deviation_bps = 100;
+    pool.block_timestamp_last = START - 100;
+    math::update_twap(&mut pool, RESERVE, RESERVE, START);
+
+    let now = START + 1;
+    assert!(matches!(
+        execute_pool_swap(&mut pool, RESERVE, RESERVE, RESERVE / 10, true, 3, 0, now),
+        Err(AmmError::PriceDeviationTooLarge)
+    ));
+    assert!(execute_pool_swap(&mut pool, RESERVE, RESERVE, RESERVE / 1000, true, 3, 0, now).is_ok());
+}
+
+#[test]
+fn only_the_increasing_side_is_capped() {
+    let mut pool = pool();
+    pool.max_total_reserve_a = RESERVE + 5_000;
+    pool.max_total_reserve_b = RESERVE;
+    assert!(matches!(
+        execute_pool_swap(&mut pool, RESERVE, RESERVE, 10_000, true, 3, 0, START),
+        Err(AmmError::CapExceeded)
+    ));
+    // 输出一侧减少，已经达到上限也不影响
+    assert!(execute_pool_swap(&mut pool, RESERVE, RESERVE, 5_000, true, 3, 0, START).is_ok());
+    assert!(matches!(
+        check_pool_bounds(&pool, RESERVE, RESERVE, RESERVE - 10, RESERVE + 10),
+        Err(AmmError::CapExceeded)
+    ));
+}
+
+#[test]
+fn unchanged_reserves_skip_the_bounds() {
+    let mut pool = pool();
+    // 储备已经超过上限，没有变化时不检查，否则池子的其他路径都会被卡住
+    pool.max_total_reserve_a = RESERVE / 2;
+    assert!(check_pool_bounds(&pool, RESERVE, RESERVE, RESERVE, RESERVE).is_ok());
+}
@@ -0,0 +1,87 @@
+mod common;
+
+use common::*;
+use solana_amm::math;
+
+const RESERVE_A: u64 = 1_000_000_000;
+const RESERVE_B: u64 = 2_000_000_000;
+const SHARE: u64 = 5;
+
+// 在池子里来回交易，让手续费累积到储备金中
+fn generate_fees(pool: &TestPool) {
+    for _ in 0..5 {
+        assert_success(&pool.swap(50_000_000, true, 0));
+        assert_success(&pool.swap(100_000_000, false, 0));
+    }
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn protocol_fee_is_off_by_default() {
+    let pool = TestPool::with_liquidity(RESERVE_A, RESERVE_B);
+
+    generate_fees(&pool);
+    assert_success(&pool.add_liquidity(1_000_000, 2_000_000));
+
+    assert_eq!(pool.token_amount(&pool.protocol_fee_recipient), 0);
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn add_liquidity_mints_protocol_fee() {
+    let pool = TestPool::with_liquidity(RESERVE_A, RESERVE_B);
+    assert_success(&pool.update_config(&pool.admin, None, None, Some(SHARE)));
+
+    generate_fees(&pool);
+    let (reserve_a, reserve_b) = pool.reserves();
+    let supply = pool.lp_supply();
+    let expected = math::calculate_protocol_fee_mint(
+        reserve_a,
+        reserve_b,
+        pool.pool_state().k_last,
+        supply,
+        SHARE,
+    )
+    .unwrap();
+    assert!(expected > 0);
+
+    assert_success(&pool.add_liquidity(1_000_000, 2_000_000));
+
+    assert_eq!(pool.token_amount(&pool.protocol_fee_recipient), expected);
+    let (a, b) = pool.reserves();
+    assert_eq!(pool.pool_state().k_last, a as u128 * b as u128);
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn remove_liquidity_mints_protocol_fee() {
+    let pool = TestPool::with_liquidity(RESERVE_A, RESERVE_B);
+    assert_success(&pool.update_config(&pool.admin, None, None, Some(SHARE)));
+
+    generate_fees(&pool);
+    let (reserve_a, reserve_b) = pool.reserves();
+    let supply = pool.lp_supply();
+    let expected =
+        math::calculate_protocol_fee_mint(reserve_a, reserve_b, pool.pool_state().k_last, supply, SHARE)
+            .unwrap();
+
+    // 协议费先增发，用户按增发后的总量计算份额
+    let amount_lp = pool.token_amount(&pool.user_lp) / 10;
+    let (expected_a, expected_b) =
+        math::calculate_withdraw_amounts(amount_lp, reserve_a, reserve_b, supply + expected).unwrap();
+    assert_success(&pool.remove_liquidity(amount_lp, expected_a, expected_b));
+
+    assert_eq!(pool.token_amount(&pool.protocol_fee_recipient), expected);
+    assert_eq!(pool.lp_supply(), supply + expected - amount_lp);
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn no_protocol_fee_without_growth() {
+    let pool = TestPool::with_liquidity(RESERVE_A, RESERVE_B);
+    assert_success(&pool.update_config(&pool.admin, None, None, Some(SHARE)));
+
+    // 没有交易，sqrt(k) 没有增长
+    assert_success(&pool.add_liquidity(1_000_000, 2_000_000));
+    assert_eq!(pool.token_amount(&pool.protocol_fee_recipient), 0);
+}
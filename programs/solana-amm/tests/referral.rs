@@ -0,0 +1,91 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use common::*;
+use solana_amm::{math, AmmError, MAX_REFERRAL_FEE_BPS};
+
+const RESERVE_A: u64 = 1_000_000_000;
+const RESERVE_B: u64 = 2_000_000_000;
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn referral_fee_is_carved_out_of_amount_in() {
+    let mut pool = TestPool::with_liquidity(RESERVE_A, RESERVE_B);
+    assert_success(&pool.set_max_referral_fee(&pool.admin, 50));
+    let mint_a = pool.mint_a;
+    let referrer = pool.create_token_account(&mint_a, &Pubkey::new_unique(), 0);
+
+    let amount_in = 10_000_000;
+    let (_, expected_out) =
+        math::calculate_swap_amount_out(9_970_000, RESERVE_A, RESERVE_B, FEE_NUMERATOR, FEE_DENOMINATOR)
+            .unwrap();
+    let user_a_before = pool.token_amount(&pool.user_token_a);
+
+    assert_success(&pool.swap_with_referral(referrer, 30, amount_in, true));
+
+    assert_eq!(pool.token_amount(&referrer), 30_000);
+    assert_eq!(pool.token_amount(&pool.user_token_a), user_a_before - amount_in);
+    assert_eq!(pool.reserves(), (RESERVE_A + 9_970_000, RESERVE_B - expected_out));
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn referrer_must_hold_the_input_token() {
+    let mut pool = TestPool::with_liquidity(RESERVE_A, RESERVE_B);
+    assert_success(&pool.set_max_referral_fee(&pool.admin, 50));
+    let (mint_a, mint_b) = (pool.mint_a, pool.mint_b);
+    let referrer_a = pool.create_token_account(&mint_a, &Pubkey::new_unique(), 0);
+    let referrer_b = pool.create_token_account(&mint_b, &Pubkey::new_unique(), 0);
+
+    assert_error(&pool.swap_with_referral(referrer_a, 30, 1_000_000, false), AmmError::InvalidReferrer);
+    assert_success(&pool.swap_with_referral(referrer_b, 30, 1_000_000, false));
+    assert_eq!(pool.token_amount(&referrer_b), 3_000);
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn referral_fee_requires_referrer_account() {
+    let pool = TestPool::with_liquidity(RESERVE_A, RESERVE_B);
+    assert_success(&pool.set_max_referral_fee(&pool.admin, 50));
+
+    let result = pool.process(
+        solana_amm::instruction::Swap {
+            amount_in: 1_000_000,
+            is_a_to_b: true,
+            min_amount_out: 0,
+            deadline: None,
+            referral_fee_bps: 30,
+        },
+        pool.swap_accounts(),
+    );
+    assert_error(&result, AmmError::InvalidReferrer);
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn referral_fee_is_capped_by_pool() {
+    let mut pool = TestPool::with_liquidity(RESERVE_A, RESERVE_B);
+    let mint_a = pool.mint_a;
+    let referrer = pool.create_token_account(&mint_a, &Pubkey::new_unique(), 0);
+
+    // 默认上限为 0，不允许推荐费
+    assert_error(&pool.swap_with_referral(referrer, 1, 1_000_000, true), AmmError::ReferralFeeTooHigh);
+
+    assert_success(&pool.set_max_referral_fee(&pool.admin, 50));
+    assert_error(&pool.swap_with_referral(referrer, 51, 1_000_000, true), AmmError::ReferralFeeTooHigh);
+    assert_success(&pool.swap_with_referral(referrer, 50, 1_000_000, true));
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn referral_fee_config_is_validated() {
+    let pool = TestPool::initialized();
+
+    assert_error(
+        &pool.set_max_referral_fee(&pool.admin, MAX_REFERRAL_FEE_BPS + 1),
+        AmmError::InvalidReferralFeeConfig,
+    );
+    assert_error(&pool.set_max_referral_fee(&pool.user, 10), AmmError::Unauthorized);
+    assert_success(&pool.set_max_referral_fee(&pool.admin, MAX_REFERRAL_FEE_BPS));
+    assert_eq!(pool.pool_state().max_referral_fee_bps, MAX_REFERRAL_FEE_BPS);
+}
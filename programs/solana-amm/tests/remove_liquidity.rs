@@ -0,0 +1,81 @@
+mod common;
+
+use common::*;
+use solana_amm::{math, AmmError};
+
+const RESERVE_A: u64 = 1_000_000_000;
+const RESERVE_B: u64 = 2_000_000_000;
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn remove_liquidity_returns_pro_rata_share() {
+    let pool = TestPool::with_liquidity(RESERVE_A, RESERVE_B);
+
+    let supply = pool.lp_supply();
+    let user_lp = pool.token_amount(&pool.user_lp);
+    let amount_lp = user_lp / 4;
+    let (expected_a, expected_b) =
+        math::calculate_withdraw_amounts(amount_lp, RESERVE_A, RESERVE_B, supply).unwrap();
+    let user_a_before = pool.token_amount(&pool.user_token_a);
+    let user_b_before = pool.token_amount(&pool.user_token_b);
+
+    assert_success(&pool.remove_liquidity(amount_lp, expected_a, expected_b));
+
+    assert_eq!(pool.token_amount(&pool.user_lp), user_lp - amount_lp);
+    assert_eq!(pool.lp_supply(), supply - amount_lp);
+    assert_eq!(pool.token_amount(&pool.user_token_a), user_a_before + expected_a);
+    assert_eq!(pool.token_amount(&pool.user_token_b), user_b_before + expected_b);
+    assert_eq!(pool.reserves(), (RESERVE_A - expected_a, RESERVE_B - expected_b));
+    assert_eq!(
+        pool.pool_state().k_last,
+        (RESERVE_A - expected_a) as u128 * (RESERVE_B - expected_b) as u128
+    );
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn remove_liquidity_rejects_more_than_balance() {
+    let pool = TestPool::with_liquidity(RESERVE_A, RESERVE_B);
+
+    let user_lp = pool.token_amount(&pool.user_lp);
+    assert_error(&pool.remove_liquidity(user_lp + 1, 0, 0), AmmError::InvalidLpMint);
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn remove_liquidity_rejects_insufficient_output() {
+    let pool = TestPool::with_liquidity(RESERVE_A, RESERVE_B);
+
+    let amount_lp = pool.token_amount(&pool.user_lp) / 2;
+    let (expected_a, expected_b) =
+        math::calculate_withdraw_amounts(amount_lp, RESERVE_A, RESERVE_B, pool.lp_supply()).unwrap();
+
+    assert_error(&pool.remove_liquidity(amount_lp, expected_a + 1, 0), AmmError::SlippageExceeded);
+    assert_error(&pool.remove_liquidity(amount_lp, 0, expected_b + 1), AmmError::SlippageExceeded);
+    assert_eq!(pool.reserves(), (RESERVE_A, RESERVE_B));
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn remove_liquidity_rejects_expired_deadline() {
+    let mut pool = TestPool::with_liquidity(RESERVE_A, RESERVE_B);
+    let deadline = START_TIMESTAMP as i64 + 60;
+    let amount_lp = pool.token_amount(&pool.user_lp) / 2;
+
+    pool.advance_clock(61);
+    assert_error(&pool.remove_liquidity_before(deadline, amount_lp), AmmError::Expired);
+    assert_eq!(pool.reserves(), (RESERVE_A, RESERVE_B));
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn remove_liquidity_rejects_foreign_vault() {
+    let mut pool = TestPool::with_liquidity(RESERVE_A, RESERVE_B);
+
+    let (mint_b, authority) = (pool.mint_b, pool.pool_authority);
+    let fake_vault = pool.create_token_account(&mint_b, &authority, RESERVE_B);
+    let mut accounts = pool.remove_liquidity_accounts();
+    accounts.token_b_vault = fake_vault;
+
+    assert_error(&pool.remove_liquidity_with(accounts, 1_000, 0, 0), AmmError::InvalidVault);
+}
@@ -0,0 +1,322 @@
+// 各账户手工累加的 LEN 必须和 Borsh 序列化后的实际长度一致，
+// 新增字段时忘记更新 LEN 会让 init 分配的空间不够（或者浪费租金）。
+// PoolState、ClPool、TickArray 和 Twamm 是零拷贝布局，检查的是没有隐式填充
+
+use std::mem::{offset_of, size_of};
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::{AccountSerialize, AnchorSerialize};
+use solana_amm::{
+    AccessPass, BatchAuction, BatchOrder, ClPool, DcaVault, Farm, FarmReward, FarmStake, FeeOverride,
+    LegacyOracleObservation, LegacyPoolState, LimitOrder, LongTermOrder, LpLock, OracleObservation, PoolGatekeeper,
+    PoolRegistry, PoolRegistryPage, PoolState, Position, RegistryEntry, RegistryPage, Tick, TickArray, Twamm, TwammSlot,
+    LEGACY_POOL_RESERVED_BYTES, MAX_FARM_REWARDS, OBSERVATION_COUNT, POOL_RESERVED_BYTES, POOL_V2_LEN, REGISTRY_PAGE_SIZE,
+};
+
+fn serialized_len(account: &impl AccountSerialize) -> usize {
+    let mut data = Vec::new();
+    account.try_serialize(&mut data).unwrap();
+    data.len()
+}
+
+fn key() -> Pubkey {
+    Pubkey::new_unique()
+}
+
+#[test]
+fn pool_state_is_zero_copy_without_implicit_padding() {
+    assert_eq!(PoolState::LEN, 8 + size_of::<PoolState>());
+    assert!(size_of::<PoolState>().is_multiple_of(16));
+    assert_eq!(size_of::<OracleObservation>(), 32);
+
+    // 16 字节的字段必须落在 16 字节边界上，SBF 和主机的布局才一致
+    for offset in [
+        offset_of!(PoolState, price_a_cumulative_last),
+        offset_of!(PoolState, price_b_cumulative_last),
+        offset_of!(PoolState, k_last),
+        offset_of!(PoolState, log_price_cumulative_last),
+        offset_of!(PoolState, observations),
+        offset_of!(PoolState, max_total_reserve_a),
+    ] {
+        assert!(offset.is_multiple_of(16));
+    }
+    assert_eq!(offset_of!(OracleObservation, log_price_cumulative), 0);
+}
+
+#[test]
+fn pool_state_offsets_match_documented_layout() {
+    // 和 PoolState 文档中的表格一致（账户数据偏移 = 结构体偏移 + 8），其他程序按这些偏移解析
+    let documented = [
+        (offset_of!(PoolState, price_a_cumulative_last), 8),
+        (offset_of!(PoolState, price_b_cumulative_last), 24),
+        (offset_of!(PoolState, k_last), 40),
+        (offset_of!(PoolState, log_price_cumulative_last), 56),
+        (offset_of!(PoolState, observations), 72),
+        (offset_of!(PoolState, token_a), 328),
+        (offset_of!(PoolState, token_b), 360),
+        (offset_of!(PoolState, token_a_vault), 392),
+        (offset_of!(PoolState, token_b_vault), 424),
+        (offset_of!(PoolState, lp_mint), 456),
+        (offset_of!(PoolState, admin), 488),
+        (offset_of!(PoolState, pending_admin), 520),
+        (offset_of!(PoolState, protocol_fee_recipient), 552),
+        (offset_of!(PoolState, fee_numerator), 584),
+        (offset_of!(PoolState, fee_denominator), 592),
+        (offset_of!(PoolState, block_timestamp_last), 600),
+        (offset_of!(PoolState, protocol_fee_share), 608),
+        (offset_of!(PoolState, max_price_deviation_bps), 616),
+        (offset_of!(PoolState, max_referral_fee_bps), 618),
+        (offset_of!(PoolState, pool_bump), 620),
+        (offset_of!(PoolState, auth_bump), 621),
+        (offset_of!(PoolState, observation_index), 622),
+        (offset_of!(PoolState, observation_count), 623),
+        (offset_of!(PoolState, version), 624),
+        (offset_of!(PoolState, batch_mode), 625),
+        (offset_of!(PoolState, registered), 626),
+        (offset_of!(PoolState, permissioned), 627),
+        (offset_of!(PoolState, padding), 628),
+        (offset_of!(PoolState, twamm), 632),
+        (offset_of!(PoolState, max_total_reserve_a), 664),
+        (offset_of!(PoolState, max_total_reserve_b), 672),
+        (offset_of!(PoolState, max_deposit_a), 680),
+        (offset_of!(PoolState, max_deposit_b), 688),
+        (offset_of!(PoolState, reserved), 696),
+    ];
+    for (offset, account_offset) in documented {
+        assert_eq!(offset + 8, account_offset);
+    }
+    assert_eq!(PoolState::LEN, 760);
+    assert_eq!(PoolState::LEN - POOL_RESERVED_BYTES, POOL_V2_LEN);
+    assert_eq!(size_of::<OracleObservation>() * OBSERVATION_COUNT, 256);
+}
+
+#[test]
+fn clmm_accounts_are_zero_copy_without_implicit_padding() {
+    assert_eq!(ClPool::LEN, 8 + size_of::<ClPool>());
+    assert_eq!(TickArray::LEN, 8 + size_of::<TickArray>());
+    assert!(size_of::<ClPool>().is_multiple_of(16));
+    assert!(size_of::<TickArray>().is_multiple_of(16));
+    assert_eq!(size_of::<Tick>(), 64);
+
+    for offset in [
+        offset_of!(ClPool, sqrt_price_x64),
+        offset_of!(ClPool, liquidity),
+        offset_of!(ClPool, fee_growth_global_a_x64),
+        offset_of!(ClPool, fee_growth_global_b_x64),
+        offset_of!(ClPool, reserved),
+        offset_of!(TickArray, ticks),
+    ] {
+        assert!(offset.is_multiple_of(16));
+    }
+}
+
+#[test]
+fn twamm_is_zero_copy_without_implicit_padding() {
+    assert_eq!(Twamm::LEN, 8 + size_of::<Twamm>());
+    assert!(size_of::<Twamm>().is_multiple_of(16));
+    assert!(size_of::<TwammSlot>().is_multiple_of(16));
+
+    for offset in [
+        offset_of!(Twamm, sell_rate_a),
+        offset_of!(Twamm, sell_rate_b),
+        offset_of!(Twamm, proceeds_per_rate_a_x64),
+        offset_of!(Twamm, proceeds_per_rate_b_x64),
+        offset_of!(Twamm, slots),
+    ] {
+        assert!(offset.is_multiple_of(16));
+    }
+    // twamm 从原来的预留空间中划出，前面字段的偏移不变
+    assert_eq!(offset_of!(PoolState, max_total_reserve_a) - offset_of!(PoolState, twamm), 32);
+}
+
+#[test]
+fn legacy_pool_state_len_matches_serialized_size() {
+    // 所有 Option 都取 Some，得到最大长度
+    let legacy = LegacyPoolState {
+        token_a: key(),
+        token_b: key(),
+        token_a_vault: key(),
+        token_b_vault: key(),
+        lp_mint: key(),
+        fee_numerator: 3,
+        fee_denominator: 1000,
+        pool_bump: 255,
+        auth_bump: 254,
+        block_timestamp_last: u64::MAX,
+        price_a_cumulative_last: u128::MAX,
+        price_b_cumulative_last: u128::MAX,
+        admin: key(),
+        pending_admin: Some(key()),
+        protocol_fee_recipient: key(),
+        protocol_fee_share: 5,
+        k_last: u128::MAX,
+        log_price_cumulative_last: i128::MIN,
+        observation_index: 7,
+        observation_count: 8,
+        observations: [LegacyOracleObservation { timestamp: u64::MAX, log_price_cumulative: i128::MAX }; OBSERVATION_COUNT],
+        max_price_deviation_bps: 10_000,
+        max_referral_fee_bps: 100,
+        version: 1,
+        reserved: [0xff; LEGACY_POOL_RESERVED_BYTES],
+    };
+    let mut data = Vec::new();
+    legacy.serialize(&mut data).unwrap();
+    assert_eq!(8 + data.len(), LegacyPoolState::LEN);
+}
+
+#[test]
+fn other_accounts_len_matches_serialized_size() {
+    let fee_override = FeeOverride { pool: key(), trader: key(), fee_numerator: 1, bump: 255 };
+    assert_eq!(serialized_len(&fee_override), FeeOverride::LEN);
+
+    let lp_lock = LpLock {
+        pool: key(),
+        owner: key(),
+        lock_vault: key(),
+        amount: u64::MAX,
+        unlock_ts: i64::MAX,
+        principal: u128::MAX,
+        bump: 255,
+    };
+    assert_eq!(serialized_len(&lp_lock), LpLock::LEN);
+
+    let reward = FarmReward {
+        mint: key(),
+        vault: key(),
+        emission_per_second: u64::MAX,
+        end_ts: i64::MAX,
+        last_update_ts: i64::MAX,
+        reward_per_share: u128::MAX,
+    };
+    let farm = Farm {
+        pool: key(),
+        stake_vault: key(),
+        total_staked: u64::MAX,
+        reward_count: MAX_FARM_REWARDS as u8,
+        rewards: [reward; MAX_FARM_REWARDS],
+        bump: 255,
+    };
+    assert_eq!(serialized_len(&farm), Farm::LEN);
+
+    let farm_stake = FarmStake {
+        farm: key(),
+        owner: key(),
+        amount: u64::MAX,
+        reward_debts: [u128::MAX; MAX_FARM_REWARDS],
+        pending_rewards: [u64::MAX; MAX_FARM_REWARDS],
+        bump: 255,
+    };
+    assert_eq!(serialized_len(&farm_stake), FarmStake::LEN);
+
+    let position = Position {
+        pool: key(),
+        owner: key(),
+        tick_lower: i32::MIN,
+        tick_upper: i32::MAX,
+        liquidity: u128::MAX,
+        fee_growth_inside_a_last_x64: u128::MAX,
+        fee_growth_inside_b_last_x64: u128::MAX,
+        fees_owed_a: u64::MAX,
+        fees_owed_b: u64::MAX,
+        bump: 255,
+    };
+    assert_eq!(serialized_len(&position), Position::LEN);
+
+    let limit_order = LimitOrder {
+        pool: key(),
+        owner: key(),
+        escrow: key(),
+        order_id: u64::MAX,
+        is_a_to_b: true,
+        amount_in: u64::MAX,
+        min_amount_out: u64::MAX,
+        keeper_bounty: u64::MAX,
+        created_ts: i64::MAX,
+        bump: 255,
+    };
+    assert_eq!(serialized_len(&limit_order), LimitOrder::LEN);
+
+    let long_term_order = LongTermOrder {
+        pool: key(),
+        owner: key(),
+        order_id: u64::MAX,
+        is_a_to_b: true,
+        sell_rate: u128::MAX,
+        amount_in: u64::MAX,
+        start_ts: u64::MAX,
+        expiry_ts: u64::MAX,
+        proceeds_per_rate_start_x64: u128::MAX,
+        bump: 255,
+    };
+    assert_eq!(serialized_len(&long_term_order), LongTermOrder::LEN);
+
+    let dca_vault = DcaVault {
+        pool: key(),
+        owner: key(),
+        input_vault: key(),
+        output_vault: key(),
+        dca_id: u64::MAX,
+        is_a_to_b: true,
+        amount_per_cycle: u64::MAX,
+        min_amount_out_per_cycle: u64::MAX,
+        cycle_interval: i64::MAX,
+        next_cycle_ts: i64::MAX,
+        amount_sold: u64::MAX,
+        amount_bought: u64::MAX,
+        bump: 255,
+    };
+    assert_eq!(serialized_len(&dca_vault), DcaVault::LEN);
+
+    let batch_auction = BatchAuction {
+        pool: key(),
+        vault_a: key(),
+        vault_b: key(),
+        batch_duration_slots: u64::MAX,
+        batch_id: u64::MAX,
+        batch_start_slot: u64::MAX,
+        order_count: u8::MAX,
+        amount_a_in: u64::MAX,
+        amount_b_in: u64::MAX,
+        min_order_amount_a: u64::MAX,
+        min_order_amount_b: u64::MAX,
+        bump: 255,
+    };
+    assert_eq!(serialized_len(&batch_auction), BatchAuction::LEN);
+
+    let batch_order = BatchOrder {
+        pool: key(),
+        owner: key(),
+        batch_id: u64::MAX,
+        is_a_to_b: true,
+        amount_in: u64::MAX,
+        min_amount_out: u64::MAX,
+        settled: true,
+        claim_amount_a: u64::MAX,
+        claim_amount_b: u64::MAX,
+        bump: 255,
+    };
+    assert_eq!(serialized_len(&batch_order), BatchOrder::LEN);
+
+    let pool_gatekeeper = PoolGatekeeper { pool: key(), authority: key(), bump: 255 };
+    assert_eq!(serialized_len(&pool_gatekeeper), PoolGatekeeper::LEN);
+
+    let access_pass = AccessPass { pool: key(), user: key(), issuer: key(), issued_ts: i64::MAX, expires_ts: i64::MAX, bump: 255 };
+    assert_eq!(serialized_len(&access_pass), AccessPass::LEN);
+
+    let pool_registry = PoolRegistry { pool_count: u64::MAX, bump: 255 };
+    assert_eq!(serialized_len(&pool_registry), PoolRegistry::LEN);
+
+    // 写满一页时的长度
+    let entry = RegistryEntry {
+        pool: key(),
+        mint_a: key(),
+        mint_b: key(),
+        fee_numerator: u64::MAX,
+        fee_denominator: u64::MAX,
+    };
+    let registry_page = PoolRegistryPage { page_index: u64::MAX, entries: vec![entry; REGISTRY_PAGE_SIZE] };
+    assert_eq!(serialized_len(&registry_page), PoolRegistryPage::LEN);
+
+    let page = RegistryPage { pool_count: u64::MAX, page_index: u64::MAX, entries: vec![entry; REGISTRY_PAGE_SIZE] };
+    assert_eq!(page.try_to_vec().unwrap().len(), RegistryPage::MAX_LEN);
+}
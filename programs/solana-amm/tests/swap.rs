@@ -0,0 +1,115 @@
+mod common;
+
+use common::*;
+use solana_amm::{math, AmmError};
+
+const RESERVE_A: u64 = 1_000_000_000;
+const RESERVE_B: u64 = 2_000_000_000;
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn swap_a_to_b_follows_constant_product() {
+    let pool = TestPool::with_liquidity(RESERVE_A, RESERVE_B);
+
+    let amount_in = 10_000_000;
+    let (_, expected_out) =
+        math::calculate_swap_amount_out(amount_in, RESERVE_A, RESERVE_B, FEE_NUMERATOR, FEE_DENOMINATOR)
+            .unwrap();
+    let user_b_before = pool.token_amount(&pool.user_token_b);
+
+    assert_success(&pool.swap(amount_in, true, expected_out));
+
+    assert_eq!(pool.reserves(), (RESERVE_A + amount_in, RESERVE_B - expected_out));
+    assert_eq!(pool.token_amount(&pool.user_token_b), user_b_before + expected_out);
+    let (a, b) = pool.reserves();
+    assert!(a as u128 * b as u128 > RESERVE_A as u128 * RESERVE_B as u128);
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn swap_b_to_a_follows_constant_product() {
+    let pool = TestPool::with_liquidity(RESERVE_A, RESERVE_B);
+
+    let amount_in = 30_000_000;
+    let (_, expected_out) =
+        math::calculate_swap_amount_out(amount_in, RESERVE_B, RESERVE_A, FEE_NUMERATOR, FEE_DENOMINATOR)
+            .unwrap();
+    let user_a_before = pool.token_amount(&pool.user_token_a);
+
+    assert_success(&pool.swap(amount_in, false, expected_out));
+
+    assert_eq!(pool.reserves(), (RESERVE_A - expected_out, RESERVE_B + amount_in));
+    assert_eq!(pool.token_amount(&pool.user_token_a), user_a_before + expected_out);
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn swap_updates_twap_accumulators() {
+    let mut pool = TestPool::with_liquidity(RESERVE_A, RESERVE_B);
+
+    pool.advance_clock(60);
+    assert_success(&pool.swap(1_000_000, true, 0));
+
+    let state = pool.pool_state();
+    let price_a = ((RESERVE_B as u128) << 64) / RESERVE_A as u128;
+    let price_b = ((RESERVE_A as u128) << 64) / RESERVE_B as u128;
+    assert_eq!(state.price_a_cumulative_last, price_a * 60);
+    assert_eq!(state.price_b_cumulative_last, price_b * 60);
+    assert_eq!(state.block_timestamp_last, START_TIMESTAMP + 60);
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn swap_rejects_insufficient_output() {
+    let pool = TestPool::with_liquidity(RESERVE_A, RESERVE_B);
+
+    let (_, expected_out) =
+        math::calculate_swap_amount_out(1_000_000, RESERVE_A, RESERVE_B, FEE_NUMERATOR, FEE_DENOMINATOR)
+            .unwrap();
+    assert_error(&pool.swap(1_000_000, true, expected_out + 1), AmmError::SlippageExceeded);
+    assert_eq!(pool.reserves(), (RESERVE_A, RESERVE_B));
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn swap_rejects_expired_deadline() {
+    let pool = TestPool::with_liquidity(RESERVE_A, RESERVE_B);
+    let now = START_TIMESTAMP as i64;
+
+    assert_error(&pool.swap_before(now - 1, 1_000_000, true), AmmError::Expired);
+    assert_eq!(pool.reserves(), (RESERVE_A, RESERVE_B));
+    // deadline 当秒仍然有效
+    assert_success(&pool.swap_before(now, 1_000_000, true));
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn swap_rejects_foreign_vault() {
+    let mut pool = TestPool::with_liquidity(RESERVE_A, RESERVE_B);
+
+    let (mint_a, authority) = (pool.mint_a, pool.pool_authority);
+    let fake_vault = pool.create_token_account(&mint_a, &authority, RESERVE_A);
+    let mut accounts = pool.swap_accounts();
+    accounts.token_a_vault = fake_vault;
+
+    assert_error(&pool.swap_with(accounts, 1_000_000, true, 0), AmmError::InvalidVault);
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn swap_rejects_user_token_with_wrong_mint() {
+    let pool = TestPool::with_liquidity(RESERVE_A, RESERVE_B);
+
+    let mut accounts = pool.swap_accounts();
+    accounts.user_token_a = pool.user_token_b;
+
+    assert_error(&pool.swap_with(accounts, 1_000_000, true, 0), AmmError::InvalidUserToken);
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn swap_rejects_overflowing_input() {
+    let pool = TestPool::with_liquidity(RESERVE_A, RESERVE_B);
+
+    assert_error(&pool.swap(u64::MAX, true, 0), AmmError::MathOverflow);
+}
@@ -0,0 +1,167 @@
+mod common;
+
+use common::*;
+use solana_amm::math::{calculate_lp_value, calculate_protocol_fee_mint};
+use solana_amm::{AmmError, Twamm, TWAMM_INTERVAL};
+
+const RESERVE_A: u64 = 1_000_000_000;
+const RESERVE_B: u64 = 2_000_000_000;
+const ORDER_IN: u64 = 10_000_000;
+
+fn pool_with_twamm() -> TestPool {
+    let pool = TestPool::with_liquidity(RESERVE_A, RESERVE_B);
+    assert_success(&pool.init_twamm(&pool.admin));
+    pool
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn init_twamm_is_admin_only() {
+    let pool = TestPool::with_liquidity(RESERVE_A, RESERVE_B);
+
+    assert_error(&pool.init_twamm(&pool.user), AmmError::Unauthorized);
+    assert_success(&pool.init_twamm(&pool.admin));
+
+    assert_eq!(pool.pool_state().twamm, pool.twamm_address());
+    let twamm = pool.twamm();
+    assert_eq!(twamm.vault_a, pool.twamm_vault_address(&pool.mint_a));
+    assert_eq!(twamm.last_virtual_order_ts, START_TIMESTAMP);
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn swap_requires_twamm_accounts_once_enabled() {
+    let pool = pool_with_twamm();
+
+    let accounts = solana_amm::accounts::Swap {
+        twamm: None,
+        twamm_vault_a: None,
+        twamm_vault_b: None,
+        ..pool.swap_accounts()
+    };
+    assert_error(&pool.swap_with(accounts, 1_000, true, 0), AmmError::TwammAccountsMissing);
+    assert_success(&pool.swap(1_000, true, 0));
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn place_order_validates_duration() {
+    let pool = pool_with_twamm();
+
+    assert_error(&pool.place_long_term_order(1, ORDER_IN, true, 0), AmmError::InvalidLongTermOrder);
+    assert_error(&pool.place_long_term_order(1, 0, true, 2), AmmError::InvalidLongTermOrder);
+    assert_success(&pool.place_long_term_order(1, ORDER_IN, true, 2));
+
+    let order = pool.long_term_order(1).unwrap();
+    assert_eq!(order.expiry_ts, (START_TIMESTAMP / TWAMM_INTERVAL + 2) * TWAMM_INTERVAL);
+    assert_eq!(pool.token_amount(&pool.twamm_vault_address(&pool.mint_a)), ORDER_IN);
+    assert_eq!(pool.twamm().sell_rate_a, order.sell_rate);
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn swap_settles_virtual_orders_first() {
+    let mut pool = pool_with_twamm();
+    assert_success(&pool.place_long_term_order(1, ORDER_IN, true, 2));
+    let order = pool.long_term_order(1).unwrap();
+
+    // 过了到期时间之后的第一笔 swap 先把整笔长期订单卖进池子
+    pool.set_clock(order.expiry_ts + 60);
+    assert_success(&pool.swap(1_000, false, 0));
+
+    let twamm = pool.twamm();
+    assert_eq!(twamm.last_virtual_order_ts, order.expiry_ts + 60);
+    assert_eq!(twamm.sell_rate_a, 0);
+    let (reserve_a, reserve_b) = pool.reserves();
+    assert!(reserve_a > RESERVE_A + ORDER_IN - ORDER_IN / 100);
+    assert!(reserve_b < RESERVE_B);
+    assert_eq!(pool.pool_state().block_timestamp_last, order.expiry_ts + 60);
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn claim_lp_lock_fees_settles_virtual_orders_first() {
+    let mut pool = pool_with_twamm();
+    let lp = pool.token_amount(&pool.user_lp);
+    assert_success(&pool.lock_lp(lp / 2, (START_TIMESTAMP + 8 * TWAMM_INTERVAL) as i64));
+    assert_success(&pool.place_long_term_order(1, ORDER_IN, true, 2));
+    let expiry_ts = pool.long_term_order(1).unwrap().expiry_ts;
+
+    // 长期订单卖进池子的手续费也计入锁仓份额
+    pool.set_clock(expiry_ts);
+    assert_success(&pool.claim_lp_lock_fees());
+
+    let twamm = pool.twamm();
+    assert_eq!(twamm.last_virtual_order_ts, expiry_ts);
+    assert_eq!(twamm.sell_rate_a, 0);
+    assert!(pool.reserves().0 > RESERVE_A);
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn lock_lp_records_principal_after_settling_virtual_orders() {
+    let mut pool = pool_with_twamm();
+    assert_success(&pool.place_long_term_order(1, ORDER_IN, true, 2));
+    let expiry_ts = pool.long_term_order(1).unwrap().expiry_ts;
+
+    pool.set_clock(expiry_ts);
+    let amount = pool.token_amount(&pool.user_lp) / 2;
+    assert_success(&pool.lock_lp(amount, (expiry_ts + TWAMM_INTERVAL) as i64));
+
+    // 本金按结算后的储备计算，而不是订单开始前的储备
+    let twamm = pool.twamm();
+    assert_eq!(twamm.last_virtual_order_ts, expiry_ts);
+    assert_eq!(twamm.sell_rate_a, 0);
+    let (reserve_a, reserve_b) = pool.reserves();
+    assert!(reserve_a > RESERVE_A);
+    let state = pool.pool_state();
+    let lp_supply = pool.lp_supply()
+        + calculate_protocol_fee_mint(reserve_a, reserve_b, state.k_last, pool.lp_supply(), state.protocol_fee_share).unwrap();
+    assert_eq!(
+        pool.lp_lock(&pool.user).unwrap().principal,
+        calculate_lp_value(amount, reserve_a, reserve_b, lp_supply).unwrap()
+    );
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn withdraw_after_expiry_pays_proceeds() {
+    let mut pool = pool_with_twamm();
+    assert_success(&pool.place_long_term_order(1, ORDER_IN, true, 2));
+    let expiry_ts = pool.long_term_order(1).unwrap().expiry_ts;
+    let before_a = pool.token_amount(&pool.user_token_a);
+    let before_b = pool.token_amount(&pool.user_token_b);
+
+    pool.set_clock(expiry_ts);
+    assert_success(&pool.execute_twamm());
+    assert_success(&pool.withdraw_long_term_order(1));
+
+    // 价格约 2 B/A，卖出 ORDER_IN 个 A 换得接近 2 * ORDER_IN 个 B
+    let out = pool.token_amount(&pool.user_token_b) - before_b;
+    assert!(out > ORDER_IN * 2 * 95 / 100 && out < ORDER_IN * 2, "{out}");
+    assert!(pool.token_amount(&pool.user_token_a) - before_a <= 1);
+    assert!(pool.long_term_order(1).is_none());
+    assert_eq!(pool.twamm().slots[Twamm::slot_index(expiry_ts)].open_orders, 0);
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn withdraw_before_expiry_cancels_remaining_sale() {
+    let mut pool = pool_with_twamm();
+    assert_success(&pool.place_long_term_order(1, ORDER_IN, true, 4));
+    let order = pool.long_term_order(1).unwrap();
+    let before_a = pool.token_amount(&pool.user_token_a);
+
+    pool.set_clock((order.start_ts + order.expiry_ts) / 2);
+    assert_success(&pool.withdraw_long_term_order(1));
+
+    let refunded = pool.token_amount(&pool.user_token_a) - before_a;
+    assert!(refunded.abs_diff(ORDER_IN / 2) <= 1, "{refunded}");
+    assert_eq!(pool.twamm().sell_rate_a, 0);
+
+    // 撤单之后时间继续推进，池子储备不再变化
+    let reserves = pool.reserves();
+    pool.set_clock(order.expiry_ts);
+    assert_success(&pool.execute_twamm());
+    assert_eq!(pool.reserves(), reserves);
+}
@@ -0,0 +1,246 @@
+// twamm 模块的单元测试和性质测试
+//
+// 和 clmm_math 一样只调用纯函数，不需要加载程序文件。
+
+use proptest::prelude::*;
+use solana_amm::math::{self, calculate_swap_amount_out};
+use solana_amm::twamm::{
+    close_long_term_order, execute_virtual_orders, execute_virtual_trade, long_term_order_progress,
+    open_long_term_order, TWAMM_RATE_SHIFT,
+};
+use solana_amm::{LongTermOrder, PoolState, Twamm, TWAMM_INTERVAL, TWAMM_SLOTS};
+
+const START: u64 = 100 * TWAMM_INTERVAL;
+
+fn pool() -> PoolState {
+    let mut pool: PoolState = bytemuck::Zeroable::zeroed();
+    pool.fee_numerator = 3;
+    pool.fee_denominator = 1000;
+    pool.block_timestamp_last = START;
+    pool
+}
+
+fn twamm() -> Twamm {
+    let mut twamm: Twamm = bytemuck::Zeroable::zeroed();
+    twamm.last_virtual_order_ts = START;
+    twamm
+}
+
+// 和 place_long_term_order 相同的记账
+fn place(twamm: &mut Twamm, now: u64, amount_in: u64, is_a_to_b: bool, intervals: u64) -> Option<LongTermOrder> {
+    let expiry_ts = (now / TWAMM_INTERVAL + intervals) * TWAMM_INTERVAL;
+    let sell_rate = ((amount_in as u128) << TWAMM_RATE_SHIFT) / (expiry_ts - now) as u128;
+    let start = open_long_term_order(twamm, is_a_to_b, sell_rate, expiry_ts)?;
+    Some(LongTermOrder {
+        pool: Default::default(),
+        owner: Default::default(),
+        order_id: 0,
+        is_a_to_b,
+        sell_rate,
+        amount_in,
+        start_ts: now,
+        expiry_ts,
+        proceeds_per_rate_start_x64: start,
+        bump: 0,
+    })
+}
+
+#[test]
+fn opposing_flows_net_at_spot_price() {
+    // 价格 2 B/A，1000 A 和 2000 B 正好抵消，不和池子成交
+    let trade = execute_virtual_trade(1_000, 2_000, 1_000_000, 2_000_000, 3, 1000).unwrap();
+    assert_eq!((trade.proceeds_a, trade.proceeds_b), (2_000, 1_000));
+    assert_eq!((trade.reserve_a, trade.reserve_b), (1_000_000, 2_000_000));
+
+    // 只有一个方向时和一笔普通兑换相同
+    let trade = execute_virtual_trade(10_000, 0, 1_000_000, 2_000_000, 3, 1000).unwrap();
+    let (_, out) = calculate_swap_amount_out(10_000, 1_000_000, 2_000_000, 3, 1000).unwrap();
+    assert_eq!((trade.proceeds_a, trade.proceeds_b), (out, 0));
+    assert_eq!((trade.reserve_a, trade.reserve_b), (1_010_000, 2_000_000 - out));
+
+    // B 多出时只有净额进入池子
+    let trade = execute_virtual_trade(1_000, 12_000, 1_000_000, 2_000_000, 3, 1000).unwrap();
+    let (_, out) = calculate_swap_amount_out(10_000, 2_000_000, 1_000_000, 3, 1000).unwrap();
+    assert_eq!((trade.proceeds_a, trade.proceeds_b), (2_000, 1_000 + out));
+    assert_eq!((trade.reserve_a, trade.reserve_b), (1_000_000 - out, 2_010_000));
+}
+
+#[test]
+fn empty_pool_does_not_trade() {
+    let trade = execute_virtual_trade(1_000, 0, 0, 0, 3, 1000).unwrap();
+    assert_eq!((trade.proceeds_a, trade.proceeds_b, trade.reserve_a, trade.reserve_b), (0, 0, 0, 0));
+}
+
+#[test]
+fn orders_stop_selling_at_expiry_and_update_twap() {
+    let (mut pool, mut twamm) = (pool(), twamm());
+    let now = START + 100;
+    let order = place(&mut twamm, now, 1_000_000, true, 2).unwrap();
+    assert_eq!(order.expiry_ts, START + 2 * TWAMM_INTERVAL);
+    let order_b = place(&mut twamm, now, 1_000_000, false, 2).unwrap();
+    twamm.last_virtual_order_ts = now;
+
+    // 过了到期时间很久才结算，到期之后不再卖出
+    let later = order.expiry_ts + 10 * TWAMM_INTERVAL;
+    let (reserve_a, reserve_b) =
+        execute_virtual_orders(&mut pool, &mut twamm, 1_000_000_000, 1_000_000_000, later).unwrap();
+    assert_eq!((twamm.sell_rate_a, twamm.sell_rate_b), (0, 0));
+    assert_eq!(twamm.last_virtual_order_ts, later);
+    assert_eq!(pool.block_timestamp_last, later);
+    assert!(pool.price_a_cumulative_last > 0);
+    // 两个方向数量相同，价格 1 附近几乎完全抵消
+    assert!(reserve_a.abs_diff(1_000_000_000) < 10 && reserve_b.abs_diff(1_000_000_000) < 10);
+
+    let (sold, out) = long_term_order_progress(&order, &twamm, later).unwrap();
+    assert_eq!(sold, order.amount_in);
+    assert!(out <= 1_000_000 && out > 999_000, "{out}");
+    let (sold_b, _) = long_term_order_progress(&order_b, &twamm, later).unwrap();
+    assert_eq!(sold_b, order_b.amount_in);
+
+    // 到期快照之后累加器继续变化也不影响已到期的订单
+    let slot = twamm.slots[Twamm::slot_index(order.expiry_ts)];
+    assert_eq!(slot.open_orders, 2);
+    close_long_term_order(&mut twamm, &order, later).unwrap();
+    assert_eq!(twamm.slots[Twamm::slot_index(order.expiry_ts)].open_orders, 1);
+}
+
+#[test]
+fn cancelled_order_stops_selling() {
+    let (mut pool, mut twamm) = (pool(), twamm());
+    let order = place(&mut twamm, START, 1_000_000, true, 4).unwrap();
+    let half = START + 2 * TWAMM_INTERVAL;
+    execute_virtual_orders(&mut pool, &mut twamm, 1_000_000_000, 1_000_000_000, half).unwrap();
+
+    let (sold, out) = long_term_order_progress(&order, &twamm, half).unwrap();
+    assert!(sold.abs_diff(500_000) <= 1, "{sold}");
+    assert!(out > 0 && out < sold);
+
+    close_long_term_order(&mut twamm, &order, half).unwrap();
+    assert_eq!(twamm.sell_rate_a, 0);
+    assert_eq!(twamm.slots[Twamm::slot_index(order.expiry_ts)].sell_rate_ending_a, 0);
+}
+
+#[test]
+fn settlement_pauses_beyond_price_deviation() {
+    let (mut pool, mut twamm) = (pool(), twamm());
+    const RESERVE: u64 = 1_000_000_000;
+    // 先在 START 记下一条观测，之后的段才有参考价
+    pool.max_price_deviation_bps = 100;
+    pool.block_timestamp_last = START - 100;
+    math::update_twap(&mut pool, RESERVE, RESERVE, START);
+
+    // 一个间隔卖出储备的 10%，价格偏离远超 1%，停在第一段的开始
+    let order = place(&mut twamm, START, RESERVE / 5, true, 2).unwrap();
+    let later = START + 2 * TWAMM_INTERVAL;
+    let reserves = execute_virtual_orders(&mut pool, &mut twamm, RESERVE, RESERVE, later).unwrap();
+    assert_eq!(reserves, (RESERVE, RESERVE));
+    assert_eq!(twamm.last_virtual_order_ts, START);
+
+    // 暂停期间没有卖出，撤单时全额退回，卖出速率立即扣除
+    assert_eq!(long_term_order_progress(&order, &twamm, later).unwrap(), (0, 0));
+    close_long_term_order(&mut twamm, &order, later).unwrap();
+    assert_eq!(twamm.sell_rate_a, 0);
+
+    // 没有卖单之后结算直接跳到现在
+    execute_virtual_orders(&mut pool, &mut twamm, RESERVE, RESERVE, later).unwrap();
+    assert_eq!(twamm.last_virtual_order_ts, later);
+
+    // 小订单每段的价格变化在范围内，正常结算
+    let small = place(&mut twamm, later, RESERVE / 1000, true, 2).unwrap();
+    let end = small.expiry_ts;
+    let (reserve_a, _) = execute_virtual_orders(&mut pool, &mut twamm, RESERVE, RESERVE, end).unwrap();
+    assert_eq!(twamm.last_virtual_order_ts, end);
+    // 每段卖出的数量向下取整
+    assert!(reserve_a.abs_diff(RESERVE + small.amount_in) <= 2, "{reserve_a}");
+}
+
+#[test]
+fn settlement_pauses_at_the_reserve_cap() {
+    let (mut pool, mut twamm) = (pool(), twamm());
+    const RESERVE: u64 = 1_000_000_000;
+    // 只够卖进第一段
+    let order = place(&mut twamm, START, 2_000_000, true, 2).unwrap();
+    pool.max_total_reserve_a = RESERVE + 1_500_000;
+
+    let later = order.expiry_ts;
+    let (reserve_a, _) = execute_virtual_orders(&mut pool, &mut twamm, RESERVE, RESERVE, later).unwrap();
+    assert_eq!(twamm.last_virtual_order_ts, START + TWAMM_INTERVAL);
+    assert!(reserve_a <= pool.max_total_reserve_a);
+    let (sold, _) = long_term_order_progress(&order, &twamm, later).unwrap();
+    assert!(sold.abs_diff(1_000_000) <= 1, "{sold}");
+
+    // 提高上限之后继续结算
+    pool.max_total_reserve_a = 0;
+    execute_virtual_orders(&mut pool, &mut twamm, reserve_a, RESERVE, later).unwrap();
+    assert_eq!(twamm.last_virtual_order_ts, later);
+    assert_eq!(long_term_order_progress(&order, &twamm, later).unwrap().0, order.amount_in);
+}
+
+#[test]
+fn expiry_slot_is_not_reused_until_withdrawn() {
+    let (mut pool, mut twamm) = (pool(), twamm());
+    let order = place(&mut twamm, START, 1_000, true, 1).unwrap();
+    let now = START + (TWAMM_SLOTS as u64 - 1) * TWAMM_INTERVAL;
+    execute_virtual_orders(&mut pool, &mut twamm, 1_000_000, 1_000_000, now).unwrap();
+
+    // 新订单的到期时间落在同一个槽位上，旧订单还没有提取
+    assert!(place(&mut twamm, now, 1_000, true, 2).is_none());
+    close_long_term_order(&mut twamm, &order, now).unwrap();
+    assert!(place(&mut twamm, now, 1_000, true, 2).is_some());
+}
+
+proptest! {
+    // 不管什么时候结算，Twamm 金库里的代币都够所有订单提取
+    #[test]
+    fn twamm_vault_stays_solvent(
+        amount_a in 1u64..1_000_000_000_000,
+        amount_b in 0u64..1_000_000_000_000,
+        intervals_a in 1u64..TWAMM_SLOTS as u64,
+        intervals_b in 1u64..TWAMM_SLOTS as u64,
+        offset in 0u64..TWAMM_INTERVAL,
+        settle_points in proptest::collection::vec(1u64..(TWAMM_SLOTS as u64 + 2) * TWAMM_INTERVAL, 1..6),
+        reserve_a in 1_000_000u64..1_000_000_000_000_000,
+        reserve_b in 1_000_000u64..1_000_000_000_000_000,
+    ) {
+        let (mut pool, mut twamm) = (pool(), twamm());
+        let now = START + offset;
+        twamm.last_virtual_order_ts = now;
+        let mut orders = vec![place(&mut twamm, now, amount_a, true, intervals_a).unwrap()];
+        if amount_b > 0 {
+            orders.push(place(&mut twamm, now, amount_b, false, intervals_b).unwrap());
+        }
+        let (mut vault_a, mut vault_b) = (amount_a as i128, amount_b as i128);
+
+        let (mut ra, mut rb) = (reserve_a, reserve_b);
+        let mut points = settle_points;
+        points.sort_unstable();
+        let mut last = now;
+        for point in points {
+            let t = now + point;
+            let result = execute_virtual_orders(&mut pool, &mut twamm, ra, rb, t);
+            prop_assume!(result.is_some());
+            let (ra_after, rb_after) = result.unwrap();
+            vault_a -= ra_after as i128 - ra as i128;
+            vault_b -= rb_after as i128 - rb as i128;
+            (ra, rb) = (ra_after, rb_after);
+            prop_assert!(vault_a >= 0 && vault_b >= 0);
+            last = t;
+        }
+
+        let (mut owed_a, mut owed_b) = (0i128, 0i128);
+        for order in &orders {
+            let (sold, out) = long_term_order_progress(order, &twamm, last).unwrap();
+            prop_assert!(sold <= order.amount_in);
+            let refund = (order.amount_in - sold) as i128;
+            if order.is_a_to_b {
+                owed_a += refund;
+                owed_b += out as i128;
+            } else {
+                owed_b += refund;
+                owed_a += out as i128;
+            }
+        }
+        prop_assert!(owed_a <= vault_a, "{} > {}", owed_a, vault_a);
+        prop_assert!(owed_b <= vault_b, "{} > {}", owed_b, vault_b);
+    }
+}
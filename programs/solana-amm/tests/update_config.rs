@@ -0,0 +1,71 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use common::*;
+use solana_amm::AmmError;
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn admin_updates_recipient_and_share() {
+    let pool = TestPool::initialized();
+
+    let recipient = Pubkey::new_unique();
+    assert_success(&pool.update_config(&pool.admin, None, Some(recipient), Some(500)));
+
+    let state = pool.pool_state();
+    assert_eq!(state.protocol_fee_recipient, recipient);
+    assert_eq!(state.protocol_fee_share, 500);
+    assert_eq!(state.admin, pool.admin);
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn update_config_rejects_non_admin() {
+    let pool = TestPool::initialized();
+
+    let result = pool.update_config(&pool.user, None, None, Some(5));
+    assert_error(&result, AmmError::Unauthorized);
+    assert_eq!(pool.pool_state().protocol_fee_share, 0);
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn update_config_rejects_share_above_limit() {
+    let pool = TestPool::initialized();
+
+    assert_error(&pool.update_config(&pool.admin, None, None, Some(501)), AmmError::InvalidFeeConfig);
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn admin_transfer_takes_two_steps() {
+    let pool = TestPool::initialized();
+
+    let new_admin = Pubkey::new_unique();
+    assert_success(&pool.update_config(&pool.admin, Some(new_admin), None, None));
+
+    // 只是提名，管理员还没有变
+    let state = pool.pool_state();
+    assert_eq!(state.admin, pool.admin);
+    assert_eq!(state.pending_admin(), Some(new_admin));
+
+    assert_success(&pool.claim_admin(&new_admin));
+    let state = pool.pool_state();
+    assert_eq!(state.admin, new_admin);
+    assert_eq!(state.pending_admin(), None);
+
+    // 旧管理员失去权限
+    assert_error(&pool.update_config(&pool.admin, None, None, Some(5)), AmmError::Unauthorized);
+    assert_success(&pool.update_config(&new_admin, None, None, Some(5)));
+}
+
+#[test]
+#[ignore = "needs target/deploy/solana_amm.so: run anchor build"]
+fn claim_admin_rejects_other_signers() {
+    let pool = TestPool::initialized();
+
+    assert_error(&pool.claim_admin(&pool.user), AmmError::Unauthorized);
+
+    assert_success(&pool.update_config(&pool.admin, Some(Pubkey::new_unique()), None, None));
+    assert_error(&pool.claim_admin(&pool.user), AmmError::Unauthorized);
+}
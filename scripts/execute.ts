@@ -9,11 +9,48 @@
  *   initialize <mintA> <mintB> <feeNum> <feeDenom>
  *   deposit    <mintA> <mintB> <amountA> <amountB>
  *   withdraw   <mintA> <mintB> <amountLp> <minA> <minB>
- *   swap       <mintA> <mintB> <amountIn> <isAtoB> [minAmountOut]
- *   update_config <mintA> <mintB> [newAdmin|-] [newRecipient|-] [newShare|-]
+ *   swap       <mintA> <mintB> <amountIn> <isAtoB> [minAmountOut] [referrerTokenAccount referralBps]
+ *   update_config <mintA> <mintB> [newAdmin|-] [newRecipient|-] [newShare|-] [maxDeviationBps|-] [maxReferralBps|-]
  *   claim_admin  <mintA> <mintB>
+ *   set_deposit_caps <mintA> <mintB> <maxReserveA> <maxReserveB> [maxDepositA] [maxDepositB]   # 0 表示不限制
+ *   set_gatekeeper <mintA> <mintB> <gatekeeper|->   # 开启许可模式，- 表示关闭
+ *   issue_access_pass  <mintA> <mintB> <user> [expiresTs]   # 由守门人执行，expiresTs 省略或为 0 表示不过期
+ *   revoke_access_pass <mintA> <mintB> <user>
+ *   set_fee_override    <mintA> <mintB> <trader> <feeNum>
+ *   remove_fee_override <mintA> <mintB> <trader>
+ *   lock_lp    <mintA> <mintB> <amountLp> <lockDays>
+ *   unlock_lp  <mintA> <mintB>
+ *   claim_lp_lock_fees <mintA> <mintB>
+ *   update_lp_metadata <mintA> <mintB> <name> <symbol> <uri>
+ *   migrate_pool <mintA> <mintB>   # 把旧版本的池子账户升级到当前布局
+ *   create_farm     <mintA> <mintB>
+ *   add_farm_reward <mintA> <mintB> <rewardMint> <emissionPerSecond> <durationDays>
+ *   stake_lp        <mintA> <mintB> <amountLp>
+ *   unstake_lp      <mintA> <mintB> <amountLp>
+ *   claim_rewards   <mintA> <mintB> <rewardIndex>
+ *   place_order  <mintA> <mintB> <orderId> <amountIn> <isAtoB> <minAmountOut> [bountyLamports]
+ *   cancel_order <mintA> <mintB> <orderId>
+ *   fill_orders  <mintA> <mintB> <owner> <orderId> [orderId...]
+ *   init_twamm   <mintA> <mintB>
+ *   place_long_term_order    <mintA> <mintB> <orderId> <amountIn> <isAtoB> <intervals>
+ *   withdraw_long_term_order <mintA> <mintB> <orderId>
+ *   execute_twamm <mintA> <mintB>
+ *   open_dca     <mintA> <mintB> <dcaId> <amountIn> <isAtoB> <amountPerCycle> <intervalSeconds> [minOutPerCycle]
+ *   execute_dca  <mintA> <mintB> <owner> <dcaId>
+ *   withdraw_dca <mintA> <mintB> <dcaId>
+ *   close_dca    <mintA> <mintB> <dcaId>
+ *   init_batch_auction <mintA> <mintB> <batchDurationSlots> <minOrderA> <minOrderB>
+ *   submit_batch_swap  <mintA> <mintB> <amountIn> <isAtoB> [minAmountOut]
+ *   settle_batch       <mintA> <mintB>
+ *   claim_batch_order  <mintA> <mintB> <batchId>
+ *   cancel_batch_order <mintA> <mintB>
+ *   set_batch_mode     <mintA> <mintB> <on|off>
+ *   list_pools   # 按页列出注册表中的所有池子
+ *   register_pool <mintA> <mintB>   # 把注册表上线之前创建的池子登记进去
  *   state      <mintA> <mintB>   # 仅查询池状态
  *   examples   # 打印可运行的示例命令
+ *
+ * deposit / withdraw / swap 默认 120 秒后过期，可用环境变量 TX_DEADLINE_SECONDS 修改
  */
 
 import * as anchor from "@coral-xyz/anchor";
@@ -39,8 +76,15 @@ import * as fs from "fs";
 const idl = require(path.join(__dirname, "../target/idl/solana_amm.json"));
 
 const PROGRAM_ID = new PublicKey("3urPFjzfHCS8K37dh2yqvavsQPdmEa5H6pLuv8xWpQXP");
+const TOKEN_METADATA_PROGRAM_ID = new PublicKey("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
 const DEVNET_RPC = process.env.SOLANA_RPC_URL || clusterApiUrl("devnet");
 const BLACK_HOLE_OWNER = new PublicKey("11111111111111111111111111111111");
+// 交易有效期（秒），超过之后链上会以 Expired 拒绝，避免网络拥堵时延迟成交
+const TX_DEADLINE_SECONDS = Number(process.env.TX_DEADLINE_SECONDS || 120);
+
+function deadlineFromNow(): anchor.BN {
+  return new anchor.BN(Math.floor(Date.now() / 1000) + TX_DEADLINE_SECONDS);
+}
 
 function loadWalletKeypair(): Keypair {
   const keypairPath =
@@ -79,6 +123,221 @@ function derivePoolPdas(
   return { poolState, poolAuthority };
 }
 
+function deriveFeeOverride(programId: PublicKey, poolState: PublicKey, trader: PublicKey): PublicKey {
+  const [feeOverride] = PublicKey.findProgramAddressSync(
+    [Buffer.from("fee_override"), poolState.toBuffer(), trader.toBuffer()],
+    programId
+  );
+  return feeOverride;
+}
+
+function deriveLpLock(
+  programId: PublicKey,
+  poolState: PublicKey,
+  owner: PublicKey
+): { lpLock: PublicKey; lockVault: PublicKey } {
+  const [lpLock] = PublicKey.findProgramAddressSync(
+    [Buffer.from("lp_lock"), poolState.toBuffer(), owner.toBuffer()],
+    programId
+  );
+  const [lockVault] = PublicKey.findProgramAddressSync(
+    [Buffer.from("lp_lock_vault"), lpLock.toBuffer()],
+    programId
+  );
+  return { lpLock, lockVault };
+}
+
+function deriveFarm(
+  programId: PublicKey,
+  poolState: PublicKey
+): { farm: PublicKey; stakeVault: PublicKey } {
+  const [farm] = PublicKey.findProgramAddressSync(
+    [Buffer.from("farm"), poolState.toBuffer()],
+    programId
+  );
+  const [stakeVault] = PublicKey.findProgramAddressSync(
+    [Buffer.from("farm_stake_vault"), farm.toBuffer()],
+    programId
+  );
+  return { farm, stakeVault };
+}
+
+function deriveLimitOrder(
+  programId: PublicKey,
+  poolState: PublicKey,
+  owner: PublicKey,
+  orderId: anchor.BN
+): { limitOrder: PublicKey; escrow: PublicKey } {
+  const [limitOrder] = PublicKey.findProgramAddressSync(
+    [Buffer.from("limit_order"), poolState.toBuffer(), owner.toBuffer(), orderId.toArrayLike(Buffer, "le", 8)],
+    programId
+  );
+  const [escrow] = PublicKey.findProgramAddressSync(
+    [Buffer.from("order_escrow"), limitOrder.toBuffer()],
+    programId
+  );
+  return { limitOrder, escrow };
+}
+
+function deriveTwamm(
+  programId: PublicKey,
+  poolState: PublicKey,
+  mintA: PublicKey,
+  mintB: PublicKey
+): { twamm: PublicKey; twammVaultA: PublicKey; twammVaultB: PublicKey } {
+  const [twamm] = PublicKey.findProgramAddressSync([Buffer.from("twamm"), poolState.toBuffer()], programId);
+  const [twammVaultA] = PublicKey.findProgramAddressSync(
+    [Buffer.from("twamm_vault"), twamm.toBuffer(), mintA.toBuffer()],
+    programId
+  );
+  const [twammVaultB] = PublicKey.findProgramAddressSync(
+    [Buffer.from("twamm_vault"), twamm.toBuffer(), mintB.toBuffer()],
+    programId
+  );
+  return { twamm, twammVaultA, twammVaultB };
+}
+
+// 池子开启 TWAMM 之后 deposit / withdraw / swap / fill_orders 必须带上这三个可选账户，否则传 null
+async function optionalTwammAccounts(
+  connection: Connection,
+  programId: PublicKey,
+  poolState: PublicKey,
+  mintA: PublicKey,
+  mintB: PublicKey
+): Promise<{ twamm: PublicKey | null; twammVaultA: PublicKey | null; twammVaultB: PublicKey | null }> {
+  const accounts = deriveTwamm(programId, poolState, mintA, mintB);
+  if (!(await connection.getAccountInfo(accounts.twamm))) {
+    return { twamm: null, twammVaultA: null, twammVaultB: null };
+  }
+  return accounts;
+}
+
+function deriveAccessPass(programId: PublicKey, poolState: PublicKey, user: PublicKey): PublicKey {
+  const [accessPass] = PublicKey.findProgramAddressSync(
+    [Buffer.from("access_pass"), poolState.toBuffer(), user.toBuffer()],
+    programId
+  );
+  return accessPass;
+}
+
+function derivePoolGatekeeper(programId: PublicKey, poolState: PublicKey): PublicKey {
+  const [poolGatekeeper] = PublicKey.findProgramAddressSync(
+    [Buffer.from("pool_gatekeeper"), poolState.toBuffer()],
+    programId
+  );
+  return poolGatekeeper;
+}
+
+// 许可池子的 deposit / withdraw / swap / claim_lp_lock_fees 必须带上用户的 AccessPass 和池子的 PoolGatekeeper，
+// 账户不存在时传 null（许可池子上会失败）
+async function optionalAccessPass(
+  connection: Connection,
+  programId: PublicKey,
+  poolState: PublicKey,
+  user: PublicKey
+): Promise<{ accessPass: PublicKey | null; poolGatekeeper: PublicKey | null }> {
+  const accessPass = deriveAccessPass(programId, poolState, user);
+  const poolGatekeeper = derivePoolGatekeeper(programId, poolState);
+  const [accessPassInfo, poolGatekeeperInfo] = await connection.getMultipleAccountsInfo([accessPass, poolGatekeeper]);
+  return {
+    accessPass: accessPassInfo ? accessPass : null,
+    poolGatekeeper: poolGatekeeperInfo ? poolGatekeeper : null,
+  };
+}
+
+function deriveLongTermOrder(
+  programId: PublicKey,
+  poolState: PublicKey,
+  owner: PublicKey,
+  orderId: anchor.BN
+): PublicKey {
+  const [longTermOrder] = PublicKey.findProgramAddressSync(
+    [Buffer.from("long_term_order"), poolState.toBuffer(), owner.toBuffer(), orderId.toArrayLike(Buffer, "le", 8)],
+    programId
+  );
+  return longTermOrder;
+}
+
+function deriveBatchAuction(
+  programId: PublicKey,
+  poolState: PublicKey,
+  mintA: PublicKey,
+  mintB: PublicKey
+): { batchAuction: PublicKey; batchVaultA: PublicKey; batchVaultB: PublicKey } {
+  const [batchAuction] = PublicKey.findProgramAddressSync(
+    [Buffer.from("batch_auction"), poolState.toBuffer()],
+    programId
+  );
+  const [batchVaultA] = PublicKey.findProgramAddressSync(
+    [Buffer.from("batch_vault"), batchAuction.toBuffer(), mintA.toBuffer()],
+    programId
+  );
+  const [batchVaultB] = PublicKey.findProgramAddressSync(
+    [Buffer.from("batch_vault"), batchAuction.toBuffer(), mintB.toBuffer()],
+    programId
+  );
+  return { batchAuction, batchVaultA, batchVaultB };
+}
+
+function deriveBatchOrder(
+  programId: PublicKey,
+  batchAuction: PublicKey,
+  owner: PublicKey,
+  batchId: anchor.BN
+): PublicKey {
+  const [batchOrder] = PublicKey.findProgramAddressSync(
+    [Buffer.from("batch_order"), batchAuction.toBuffer(), owner.toBuffer(), batchId.toArrayLike(Buffer, "le", 8)],
+    programId
+  );
+  return batchOrder;
+}
+
+function deriveDca(
+  programId: PublicKey,
+  poolState: PublicKey,
+  owner: PublicKey,
+  dcaId: anchor.BN
+): { dcaVault: PublicKey; inputVault: PublicKey; outputVault: PublicKey } {
+  const [dcaVault] = PublicKey.findProgramAddressSync(
+    [Buffer.from("dca_vault"), poolState.toBuffer(), owner.toBuffer(), dcaId.toArrayLike(Buffer, "le", 8)],
+    programId
+  );
+  const [inputVault] = PublicKey.findProgramAddressSync([Buffer.from("dca_input"), dcaVault.toBuffer()], programId);
+  const [outputVault] = PublicKey.findProgramAddressSync([Buffer.from("dca_output"), dcaVault.toBuffer()], programId);
+  return { dcaVault, inputVault, outputVault };
+}
+
+// 与程序中的 REGISTRY_PAGE_SIZE 一致
+const REGISTRY_PAGE_SIZE = 8;
+
+function deriveRegistryPage(programId: PublicKey, pageIndex: number): PublicKey {
+  const [registryPage] = PublicKey.findProgramAddressSync(
+    [Buffer.from("pool_registry_page"), new anchor.BN(pageIndex).toArrayLike(Buffer, "le", 8)],
+    programId
+  );
+  return registryPage;
+}
+
+// initialize / register_pool 追加条目的注册表账户：新池子写入 pool_count 所在的页
+async function registryAccounts(
+  program: Program<anchor.Idl>,
+  programId: PublicKey
+): Promise<{ poolRegistry: PublicKey; registryPage: PublicKey; poolCount: number }> {
+  const [poolRegistry] = PublicKey.findProgramAddressSync([Buffer.from("pool_registry")], programId);
+  const registry = await (program.account as any).poolRegistry.fetchNullable(poolRegistry);
+  const poolCount = registry ? Number(registry.poolCount) : 0;
+  const registryPage = deriveRegistryPage(programId, Math.floor(poolCount / REGISTRY_PAGE_SIZE));
+  return { poolRegistry, registryPage, poolCount };
+}
+
+function deriveMetadata(mint: PublicKey): PublicKey {
+  const [metadata] = PublicKey.findProgramAddressSync(
+    [Buffer.from("metadata"), TOKEN_METADATA_PROGRAM_ID.toBuffer(), mint.toBuffer()],
+    TOKEN_METADATA_PROGRAM_ID
+  );
+  return metadata;
+}
+
 function solscanTxUrl(signature: string, cluster: string = "devnet"): string {
   return `https://solscan.io/tx/${signature}?cluster=${cluster}`;
 }
@@ -118,9 +377,10 @@ async function fetchPoolStateSummary(
     console.log("  admin:", (state as any).admin?.toBase58?.() ?? state.admin);
     console.log(
       "  pending_admin:",
-      (state as any).pendingAdmin != null
-        ? (state as any).pendingAdmin.toBase58()
-        : "null"
+      // 零拷贝布局中没有待定管理员时为全零地址
+      (state as any).pendingAdmin.equals(PublicKey.default)
+        ? "null"
+        : (state as any).pendingAdmin.toBase58()
     );
     console.log("  k_last:", (state as any).kLast?.toString?.() ?? (state as any).k_last);
     console.log("  reserve_a:", reserveA.toString());
@@ -128,6 +388,12 @@ async function fetchPoolStateSummary(
     console.log("  fee:", (state as any).feeNumerator + "/" + (state as any).feeDenominator);
     console.log("  protocol_fee_share:", (state as any).protocolFeeShare?.toString?.() ?? (state as any).protocol_fee_share);
     console.log("  protocol_fee_recipient:", (state as any).protocolFeeRecipient?.toBase58?.() ?? (state as any).protocol_fee_recipient);
+    // 储备上限 / 单笔存入限额，0 表示不限制
+    const s = state as any;
+    console.log(
+      "  caps (reserve A/B, deposit A/B):",
+      [s.maxTotalReserveA, s.maxTotalReserveB, s.maxDepositA, s.maxDepositB].map((v) => v?.toString?.() ?? "0").join(" / ")
+    );
     console.log("---------------");
   } catch (e: any) {
     console.log("(无法读取池状态:", e.message ?? e, ")");
@@ -142,9 +408,44 @@ Solana AMM execute.ts 用法:
   yarn ts-node scripts/execute.ts initialize <mintA> <mintB> <feeNum> <feeDenom>
   yarn ts-node scripts/execute.ts deposit <mintA> <mintB> <amountA> <amountB>
   yarn ts-node scripts/execute.ts withdraw <mintA> <mintB> <amountLp> <minA> <minB>
-  yarn ts-node scripts/execute.ts swap <mintA> <mintB> <amountIn> <isAtoB> [minAmountOut]
-  yarn ts-node scripts/execute.ts update_config <mintA> <mintB> [newAdmin|-] [newRecipient|-] [newShare|-]
+  yarn ts-node scripts/execute.ts swap <mintA> <mintB> <amountIn> <isAtoB> [minAmountOut] [referrerTokenAccount referralBps]
+  yarn ts-node scripts/execute.ts update_config <mintA> <mintB> [newAdmin|-] [newRecipient|-] [newShare|-] [maxDeviationBps|-] [maxReferralBps|-]
   yarn ts-node scripts/execute.ts claim_admin <mintA> <mintB>
+  yarn ts-node scripts/execute.ts set_deposit_caps <mintA> <mintB> <maxReserveA> <maxReserveB> [maxDepositA] [maxDepositB]
+  yarn ts-node scripts/execute.ts set_gatekeeper <mintA> <mintB> <gatekeeper|->
+  yarn ts-node scripts/execute.ts issue_access_pass <mintA> <mintB> <user> [expiresTs]
+  yarn ts-node scripts/execute.ts revoke_access_pass <mintA> <mintB> <user>
+  yarn ts-node scripts/execute.ts set_fee_override <mintA> <mintB> <trader> <feeNum>
+  yarn ts-node scripts/execute.ts remove_fee_override <mintA> <mintB> <trader>
+  yarn ts-node scripts/execute.ts lock_lp <mintA> <mintB> <amountLp> <lockDays>
+  yarn ts-node scripts/execute.ts unlock_lp <mintA> <mintB>
+  yarn ts-node scripts/execute.ts claim_lp_lock_fees <mintA> <mintB>
+  yarn ts-node scripts/execute.ts update_lp_metadata <mintA> <mintB> <name> <symbol> <uri>
+  yarn ts-node scripts/execute.ts migrate_pool <mintA> <mintB>
+  yarn ts-node scripts/execute.ts create_farm <mintA> <mintB>
+  yarn ts-node scripts/execute.ts add_farm_reward <mintA> <mintB> <rewardMint> <emissionPerSecond> <durationDays>
+  yarn ts-node scripts/execute.ts stake_lp <mintA> <mintB> <amountLp>
+  yarn ts-node scripts/execute.ts unstake_lp <mintA> <mintB> <amountLp>
+  yarn ts-node scripts/execute.ts claim_rewards <mintA> <mintB> <rewardIndex>
+  yarn ts-node scripts/execute.ts place_order <mintA> <mintB> <orderId> <amountIn> <isAtoB> <minAmountOut> [bountyLamports]
+  yarn ts-node scripts/execute.ts cancel_order <mintA> <mintB> <orderId>
+  yarn ts-node scripts/execute.ts fill_orders <mintA> <mintB> <owner> <orderId> [orderId...]
+  yarn ts-node scripts/execute.ts init_twamm <mintA> <mintB>
+  yarn ts-node scripts/execute.ts place_long_term_order <mintA> <mintB> <orderId> <amountIn> <isAtoB> <intervals>
+  yarn ts-node scripts/execute.ts withdraw_long_term_order <mintA> <mintB> <orderId>
+  yarn ts-node scripts/execute.ts execute_twamm <mintA> <mintB>
+  yarn ts-node scripts/execute.ts open_dca <mintA> <mintB> <dcaId> <amountIn> <isAtoB> <amountPerCycle> <intervalSeconds> [minOutPerCycle]
+  yarn ts-node scripts/execute.ts execute_dca <mintA> <mintB> <owner> <dcaId>
+  yarn ts-node scripts/execute.ts withdraw_dca <mintA> <mintB> <dcaId>
+  yarn ts-node scripts/execute.ts close_dca <mintA> <mintB> <dcaId>
+  yarn ts-node scripts/execute.ts init_batch_auction <mintA> <mintB> <batchDurationSlots> <minOrderA> <minOrderB>
+  yarn ts-node scripts/execute.ts submit_batch_swap <mintA> <mintB> <amountIn> <isAtoB> [minAmountOut]
+  yarn ts-node scripts/execute.ts settle_batch <mintA> <mintB>
+  yarn ts-node scripts/execute.ts claim_batch_order <mintA> <mintB> <batchId>
+  yarn ts-node scripts/execute.ts cancel_batch_order <mintA> <mintB>
+  yarn ts-node scripts/execute.ts set_batch_mode <mintA> <mintB> <on|off>
+  yarn ts-node scripts/execute.ts list_pools
+  yarn ts-node scripts/execute.ts register_pool <mintA> <mintB>
   yarn ts-node scripts/execute.ts examples
 `);
 }
@@ -191,6 +492,70 @@ function printRunnableExamples(): void {
 10) 认领 admin (当前钱包需已是 pending_admin):
     yarn ts-node scripts/execute.ts claim_admin $MINT_A $MINT_B
 
+11) 开启价格偏离保护 (swap 后价格偏离 TWAP 超过 5% 时拒绝):
+    yarn ts-node scripts/execute.ts update_config $MINT_A $MINT_B - - - 500
+
+12) 允许推荐费 (最多 0.3%)，然后带推荐人交换 (推荐人账户必须是输入代币 A 的代币账户):
+    yarn ts-node scripts/execute.ts update_config $MINT_A $MINT_B - - - - 30
+    yarn ts-node scripts/execute.ts swap $MINT_A $MINT_B 1000000 true 1 $REFERRER_TOKEN_A 30
+
+13) 给合作做市商设置折扣费率 (分子 1，分母沿用池子的 fee_denominator)；对方 swap 时脚本会自动带上:
+    yarn ts-node scripts/execute.ts set_fee_override $MINT_A $MINT_B $TRADER 1
+
+14) 锁仓 1000 LP 90 天，期间可以领取手续费，到期后取回:
+    yarn ts-node scripts/execute.ts lock_lp $MINT_A $MINT_B 1000 90
+    yarn ts-node scripts/execute.ts claim_lp_lock_fees $MINT_A $MINT_B
+    yarn ts-node scripts/execute.ts unlock_lp $MINT_A $MINT_B
+
+15) 开启流动性挖矿：每秒释放 1000 个奖励代币，持续 30 天 (管理员钱包需持有全部奖励)；LP 持有者质押后领取:
+    yarn ts-node scripts/execute.ts create_farm $MINT_A $MINT_B
+    yarn ts-node scripts/execute.ts add_farm_reward $MINT_A $MINT_B $REWARD_MINT 1000 30
+    yarn ts-node scripts/execute.ts stake_lp $MINT_A $MINT_B 1000
+    yarn ts-node scripts/execute.ts claim_rewards $MINT_A $MINT_B 0
+
+16) 给 LP 代币补上图标和描述 (initialize 时已创建元数据，URI 为空):
+    yarn ts-node scripts/execute.ts update_lp_metadata $MINT_A $MINT_B "AMM LP USDC-SOL" "USDC-SOL" https://example.com/lp.json
+
+17) 挂限价单：价格涨到 2.5 B/A 时卖出 1 A (最少换到 2.5 B)，赏金 10000 lamports；任何人都可以执行到价的订单:
+    yarn ts-node scripts/execute.ts place_order $MINT_A $MINT_B 1 1000000 true 2500000 10000
+    yarn ts-node scripts/execute.ts fill_orders $MINT_A $MINT_B $OWNER 1
+    yarn ts-node scripts/execute.ts cancel_order $MINT_A $MINT_B 1
+
+18) 开启 TWAMM，挂一个在 6 个间隔 (每个 4 小时) 内匀速卖出 100 A 的长期订单；到期后 (或提前撤单) 提取:
+    yarn ts-node scripts/execute.ts init_twamm $MINT_A $MINT_B
+    yarn ts-node scripts/execute.ts place_long_term_order $MINT_A $MINT_B 1 100000000 true 6
+    yarn ts-node scripts/execute.ts execute_twamm $MINT_A $MINT_B
+    yarn ts-node scripts/execute.ts withdraw_long_term_order $MINT_A $MINT_B 1
+
+19) 定投：存入 100 A，每天卖出 10 A 买入 B；keeper 每天执行一期，随时提取买到的 B 或者取消:
+    yarn ts-node scripts/execute.ts open_dca $MINT_A $MINT_B 1 100000000 true 10000000 86400
+    yarn ts-node scripts/execute.ts execute_dca $MINT_A $MINT_B $OWNER 1
+    yarn ts-node scripts/execute.ts withdraw_dca $MINT_A $MINT_B 1
+    yarn ts-node scripts/execute.ts close_dca $MINT_A $MINT_B 1
+
+20) 开启批量拍卖 (每批收集 10 个 slot)，之后只能提交到批次里，窗口结束后任何人都可以结算:
+    yarn ts-node scripts/execute.ts init_batch_auction $MINT_A $MINT_B 10 1000000 1000000
+    yarn ts-node scripts/execute.ts submit_batch_swap $MINT_A $MINT_B 1000000 true 1900000
+    yarn ts-node scripts/execute.ts settle_batch $MINT_A $MINT_B
+    # 结算之后自己取回第 0 批订单的输出（没有成交时取回输入）；窗口结束之前可以撤销当前批次的订单
+    yarn ts-node scripts/execute.ts claim_batch_order $MINT_A $MINT_B 0
+    yarn ts-node scripts/execute.ts cancel_batch_order $MINT_A $MINT_B
+    # 关闭批量拍卖，恢复立即成交；已经提交的订单仍然可以结算或者撤销
+    yarn ts-node scripts/execute.ts set_batch_mode $MINT_A $MINT_B off
+
+21) 列出注册表中的所有池子；注册表上线之前创建的池子先迁移再登记:
+    yarn ts-node scripts/execute.ts list_pools
+    yarn ts-node scripts/execute.ts migrate_pool $MINT_A $MINT_B
+    yarn ts-node scripts/execute.ts register_pool $MINT_A $MINT_B
+
+22) 新币上线初期限制池子规模：A、B 的储备最多 10000 / 20000 个，单笔最多存入 1000 A；0 表示不限制:
+    yarn ts-node scripts/execute.ts set_deposit_caps $MINT_A $MINT_B 10000000000 20000000000 1000000000 0
+
+23) 许可池子：管理员指定守门人，守门人 (用自己的钱包运行) 给通过 KYC 的用户发放 AccessPass，之后可以撤销:
+    yarn ts-node scripts/execute.ts set_gatekeeper $MINT_A $MINT_B $GATEKEEPER
+    yarn ts-node scripts/execute.ts issue_access_pass $MINT_A $MINT_B $USER
+    yarn ts-node scripts/execute.ts revoke_access_pass $MINT_A $MINT_B $USER
+
 ====================================
 `);
 }
@@ -260,6 +625,7 @@ async function main(): Promise<void> {
       const vaultA = getAssociatedTokenAddressSync(ma, poolAuthority, true);
       const vaultB = getAssociatedTokenAddressSync(mb, poolAuthority, true);
 
+      const { poolRegistry, registryPage } = await registryAccounts(program, programId);
       const lpMintKeypair = Keypair.generate();
       console.log("执行前池不存在，跳过 state 打印");
       const sig = await program.methods
@@ -272,12 +638,19 @@ async function main(): Promise<void> {
           tokenAVault: vaultA,
           tokenBVault: vaultB,
           lpMint: lpMintKeypair.publicKey,
+          poolRegistry,
+          registryPage,
           admin: wallet.publicKey,
           systemProgram: anchor.web3.SystemProgram.programId,
           tokenProgram: TOKEN_PROGRAM_ID,
           rent: anchor.web3.SYSVAR_RENT_PUBKEY,
           associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
-        })
+          // 同时为 LP 创建 Metaplex 元数据，名称和符号取自两个代币的元数据
+          tokenMetadataProgram: TOKEN_METADATA_PROGRAM_ID,
+          lpMetadata: deriveMetadata(lpMintKeypair.publicKey),
+          tokenAMetadata: deriveMetadata(ma),
+          tokenBMetadata: deriveMetadata(mb),
+        } as any)
         .signers([lpMintKeypair])
         .rpc();
 
@@ -300,6 +673,49 @@ async function main(): Promise<void> {
       return;
     }
 
+    // 旧版本的池子账户长度不够，按当前 IDL 无法 fetch，需要在解析池状态之前处理
+    if (command === "migrate_pool") {
+      const [ma, mb] = ensureMintOrder(parsePubkey(args[1]), parsePubkey(args[2]));
+      const { poolState } = derivePoolPdas(programId, ma, mb);
+      const before = await connection.getAccountInfo(poolState);
+      console.log("迁移前账户长度:", before?.data.length);
+
+      const sig = await program.methods
+        .migratePool()
+        .accounts({
+          poolState,
+          payer: wallet.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        } as any)
+        .rpc();
+
+      console.log("Tx:", sig);
+      console.log("Solscan (devnet):", solscanTxUrl(sig, "devnet"));
+      await confirmTx(connection, sig);
+      const after = await connection.getAccountInfo(poolState);
+      console.log("迁移后账户长度:", after?.data.length);
+      return;
+    }
+
+    if (command === "list_pools") {
+      const { poolRegistry, poolCount } = await registryAccounts(program, programId);
+      console.log("注册表中的池子数:", poolCount);
+      const pageCount = Math.ceil(poolCount / REGISTRY_PAGE_SIZE);
+      for (let i = 0; i < pageCount; i++) {
+        const page = await program.methods
+          .getRegistryPage(new anchor.BN(i))
+          .accounts({ poolRegistry, registryPage: deriveRegistryPage(programId, i) } as any)
+          .view();
+        for (const entry of page.entries) {
+          console.log(
+            `  ${entry.pool.toBase58()}  A=${entry.mintA.toBase58()}  B=${entry.mintB.toBase58()}  ` +
+              `fee=${entry.feeNumerator.toString()}/${entry.feeDenominator.toString()}`
+          );
+        }
+      }
+      return;
+    }
+
     // 以下命令均需要已存在的池：先解析 mint 并派生 PDA
     const mintA = parsePubkey(args[1]);
     const mintB = parsePubkey(args[2]);
@@ -342,7 +758,7 @@ async function main(): Promise<void> {
       await fetchPoolStateSummary(program, connection, poolState, vaultA, vaultB);
 
       const sig = await program.methods
-        .addLiquidity(amountA, amountB)
+        .addLiquidity(amountA, amountB, deadlineFromNow())
         .accounts({
           poolState,
           poolAuthority,
@@ -358,6 +774,8 @@ async function main(): Promise<void> {
           associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
           systemProgram: anchor.web3.SystemProgram.programId,
           tokenProgram: TOKEN_PROGRAM_ID,
+          ...(await optionalTwammAccounts(connection, programId, poolState, ma, mb)),
+          ...(await optionalAccessPass(connection, programId, poolState, wallet.publicKey)),
         })
         .rpc();
 
@@ -393,7 +811,7 @@ async function main(): Promise<void> {
       await fetchPoolStateSummary(program, connection, poolState, vaultA, vaultB);
 
       const sig = await program.methods
-        .removeLiquidity(amountLp, minA, minB)
+        .removeLiquidity(amountLp, minA, minB, deadlineFromNow())
         .accounts({
           poolState,
           poolAuthority,
@@ -408,6 +826,8 @@ async function main(): Promise<void> {
           associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
           systemProgram: anchor.web3.SystemProgram.programId,
           tokenProgram: TOKEN_PROGRAM_ID,
+          ...(await optionalTwammAccounts(connection, programId, poolState, ma, mb)),
+          ...(await optionalAccessPass(connection, programId, poolState, wallet.publicKey)),
         })
         .rpc();
 
@@ -422,6 +842,8 @@ async function main(): Promise<void> {
       const amountIn = parseNum(args[3]);
       const isAtoB = args[4] === "true" || args[4] === "1";
       const minAmountOut = args[5] != null ? parseNum(args[5]) : new anchor.BN(1);
+      const referrerTokenAccount = args[6] != null ? parsePubkey(args[6]) : null;
+      const referralBps = args[7] != null ? parseInt(args[7], 10) : 0;
       if (!args[3] || !args[4]) {
         console.error("swap 需要 mintA mintB amountIn isAtoB [minAmountOut]");
         process.exit(1);
@@ -429,11 +851,17 @@ async function main(): Promise<void> {
       const userTokenA = getAssociatedTokenAddressSync(ma, wallet.publicKey);
       const userTokenB = getAssociatedTokenAddressSync(mb, wallet.publicKey);
 
+      // 钱包有折扣费率时作为 remaining account 传入
+      const feeOverride = deriveFeeOverride(programId, poolState, wallet.publicKey);
+      const remainingAccounts = (await connection.getAccountInfo(feeOverride))
+        ? [{ pubkey: feeOverride, isWritable: false, isSigner: false }]
+        : [];
+
       console.log("--- 执行前 ---");
       await fetchPoolStateSummary(program, connection, poolState, vaultA, vaultB);
 
       const sig = await program.methods
-        .swap(amountIn, isAtoB, minAmountOut)
+        .swap(amountIn, isAtoB, minAmountOut, deadlineFromNow(), referralBps)
         .accounts({
           poolState,
           userTokenA,
@@ -443,7 +871,11 @@ async function main(): Promise<void> {
           user: wallet.publicKey,
           poolAuthority,
           tokenProgram: TOKEN_PROGRAM_ID,
+          referrerTokenAccount,
+          ...(await optionalTwammAccounts(connection, programId, poolState, ma, mb)),
+          ...(await optionalAccessPass(connection, programId, poolState, wallet.publicKey)),
         })
+        .remainingAccounts(remainingAccounts)
         .rpc();
 
       console.log("Tx:", sig);
@@ -453,10 +885,95 @@ async function main(): Promise<void> {
       return;
     }
 
+    if (command === "set_deposit_caps") {
+      if (!args[3] || !args[4]) {
+        console.error("set_deposit_caps 需要 mintA mintB maxReserveA maxReserveB [maxDepositA] [maxDepositB]");
+        process.exit(1);
+      }
+      const sig = await program.methods
+        .setDepositCaps(parseNum(args[3]), parseNum(args[4]), parseNum(args[5] ?? "0"), parseNum(args[6] ?? "0"))
+        .accounts({
+          poolState,
+          admin: wallet.publicKey,
+        } as any)
+        .rpc();
+
+      console.log("Tx:", sig);
+      console.log("Solscan (devnet):", solscanTxUrl(sig, "devnet"));
+      await fetchPoolStateSummary(program, connection, poolState, vaultA, vaultB);
+      return;
+    }
+
+    if (command === "set_gatekeeper") {
+      if (!args[3]) {
+        console.error("set_gatekeeper 需要 mintA mintB gatekeeper (- 表示关闭许可模式)");
+        process.exit(1);
+      }
+      const gatekeeper = args[3] === "-" ? PublicKey.default : parsePubkey(args[3]);
+      const poolGatekeeper = derivePoolGatekeeper(programId, poolState);
+      const sig = await program.methods
+        .setGatekeeper(gatekeeper)
+        .accounts({
+          poolState,
+          poolGatekeeper,
+          admin: wallet.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        } as any)
+        .rpc();
+
+      console.log("Tx:", sig);
+      console.log("Solscan (devnet):", solscanTxUrl(sig, "devnet"));
+      console.log(gatekeeper.equals(PublicKey.default) ? "许可模式已关闭" : "守门人: " + gatekeeper.toBase58());
+      return;
+    }
+
+    if (command === "issue_access_pass" || command === "revoke_access_pass") {
+      if (!args[3]) {
+        console.error(command + " 需要 mintA mintB user");
+        process.exit(1);
+      }
+      const user = parsePubkey(args[3]);
+      const poolGatekeeper = derivePoolGatekeeper(programId, poolState);
+      const accessPass = deriveAccessPass(programId, poolState, user);
+      const sig =
+        command === "issue_access_pass"
+          ? await program.methods
+              .issueAccessPass(parseNum(args[4] ?? "0"))
+              .accounts({
+                poolState,
+                poolGatekeeper,
+                user,
+                accessPass,
+                gatekeeper: wallet.publicKey,
+                systemProgram: anchor.web3.SystemProgram.programId,
+              } as any)
+              .rpc()
+          : await program.methods
+              .revokeAccessPass()
+              .accounts({
+                poolState,
+                poolGatekeeper,
+                accessPass,
+                gatekeeper: wallet.publicKey,
+              } as any)
+              .rpc();
+
+      console.log("Tx:", sig);
+      console.log("Solscan (devnet):", solscanTxUrl(sig, "devnet"));
+      console.log("AccessPass:", accessPass.toBase58());
+      if (command === "issue_access_pass") {
+        const pass = await (program.account as any).accessPass.fetch(accessPass);
+        console.log("发放者:", pass.issuer.toBase58(), "过期时间:", pass.expiresTs.toString());
+      }
+      return;
+    }
+
     if (command === "update_config") {
       const newAdminRaw = args[3];
       const newRecipientRaw = args[4];
       const newShareRaw = args[5];
+      const maxDeviationRaw = args[6];
+      const maxReferralRaw = args[7];
       const newAdmin =
         newAdminRaw == null || newAdminRaw === "-"
           ? null
@@ -469,12 +986,21 @@ async function main(): Promise<void> {
         newShareRaw == null || newShareRaw === "-"
           ? null
           : parseNum(newShareRaw);
+      // u16 参数在客户端是 number，不是 BN
+      const maxDeviationBps =
+        maxDeviationRaw == null || maxDeviationRaw === "-"
+          ? null
+          : parseInt(maxDeviationRaw, 10);
+      const maxReferralBps =
+        maxReferralRaw == null || maxReferralRaw === "-"
+          ? null
+          : parseInt(maxReferralRaw, 10);
 
       console.log("--- 执行前 ---");
       await fetchPoolStateSummary(program, connection, poolState, vaultA, vaultB);
 
       const sig = await program.methods
-        .updateConfig(newAdmin, newRecipient, newShare)
+        .updateConfig(newAdmin, newRecipient, newShare, maxDeviationBps, maxReferralBps)
         .accounts({
           poolState,
           admin: wallet.publicKey,
@@ -507,6 +1033,730 @@ async function main(): Promise<void> {
       return;
     }
 
+    if (command === "set_fee_override" || command === "remove_fee_override") {
+      if (!args[3] || (command === "set_fee_override" && !args[4])) {
+        console.error("set_fee_override 需要 mintA mintB trader feeNum，remove_fee_override 需要 mintA mintB trader");
+        process.exit(1);
+      }
+      const trader = parsePubkey(args[3]);
+      const feeOverride = deriveFeeOverride(programId, poolState, trader);
+
+      const sig =
+        command === "set_fee_override"
+          ? await program.methods
+              .setFeeOverride(parseNum(args[4]))
+              .accounts({
+                poolState,
+                trader,
+                feeOverride,
+                admin: wallet.publicKey,
+              } as any)
+              .rpc()
+          : await program.methods
+              .removeFeeOverride()
+              .accounts({
+                poolState,
+                feeOverride,
+                admin: wallet.publicKey,
+              } as any)
+              .rpc();
+
+      console.log("FeeOverride:", feeOverride.toBase58());
+      console.log("Tx:", sig);
+      console.log("Solscan (devnet):", solscanTxUrl(sig, "devnet"));
+      return;
+    }
+
+    if (command === "lock_lp" || command === "unlock_lp" || command === "claim_lp_lock_fees") {
+      const { lpLock, lockVault } = deriveLpLock(programId, poolState, wallet.publicKey);
+      const userLpAta = getAssociatedTokenAddressSync(lpMint, wallet.publicKey);
+
+      console.log("--- 执行前 ---");
+      await fetchPoolStateSummary(program, connection, poolState, vaultA, vaultB);
+
+      let sig: string;
+      if (command === "lock_lp") {
+        if (!args[3] || !args[4]) {
+          console.error("lock_lp 需要 mintA mintB amountLp lockDays");
+          process.exit(1);
+        }
+        const unlockTs = new anchor.BN(Math.floor(Date.now() / 1000) + Number(args[4]) * 86400);
+        sig = await program.methods
+          .lockLp(parseNum(args[3]), unlockTs)
+          .accounts({
+            poolState,
+            poolAuthority,
+            tokenAVault: vaultA,
+            tokenBVault: vaultB,
+            lpMint,
+            lpLock,
+            lockVault,
+            userLpToken: userLpAta,
+            user: wallet.publicKey,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            ...(await optionalTwammAccounts(connection, programId, poolState, ma, mb)),
+          } as any)
+          .rpc();
+        console.log("解锁时间:", new Date(unlockTs.toNumber() * 1000).toISOString());
+      } else if (command === "unlock_lp") {
+        sig = await program.methods
+          .unlockLp()
+          .accounts({
+            poolState,
+            poolAuthority,
+            lpMint,
+            lpLock,
+            lockVault,
+            userLpToken: userLpAta,
+            user: wallet.publicKey,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          } as any)
+          .rpc();
+      } else {
+        const protocolFeeRecipient = (poolStateAccount as any).protocolFeeRecipient ?? (poolStateAccount as any).protocol_fee_recipient;
+        const protocolFeeRecipientAta = (
+          await getOrCreateAssociatedTokenAccount(connection, wallet, lpMint, protocolFeeRecipient, true)
+        ).address;
+        sig = await program.methods
+          .claimLpLockFees()
+          .accounts({
+            poolState,
+            poolAuthority,
+            lpLock,
+            lockVault,
+            userTokenA: getAssociatedTokenAddressSync(ma, wallet.publicKey),
+            userTokenB: getAssociatedTokenAddressSync(mb, wallet.publicKey),
+            tokenAVault: vaultA,
+            tokenBVault: vaultB,
+            lpMint,
+            protocolFeeRecipient: protocolFeeRecipientAta,
+            user: wallet.publicKey,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            ...(await optionalTwammAccounts(connection, programId, poolState, ma, mb)),
+            ...(await optionalAccessPass(connection, programId, poolState, wallet.publicKey)),
+          } as any)
+          .rpc();
+      }
+
+      console.log("LpLock:", lpLock.toBase58());
+      console.log("Tx:", sig);
+      console.log("Solscan (devnet):", solscanTxUrl(sig, "devnet"));
+      console.log("--- 执行后 ---");
+      await fetchPoolStateSummary(program, connection, poolState, vaultA, vaultB);
+      return;
+    }
+
+    if (command === "update_lp_metadata") {
+      if (!args[3] || !args[4] || args[5] == null) {
+        console.error("update_lp_metadata 需要 mintA mintB name symbol uri");
+        process.exit(1);
+      }
+      const lpMetadata = deriveMetadata(lpMint);
+      const sig = await program.methods
+        .updateLpMetadata(args[3], args[4], args[5])
+        .accounts({
+          poolState,
+          poolAuthority,
+          lpMetadata,
+          admin: wallet.publicKey,
+          tokenMetadataProgram: TOKEN_METADATA_PROGRAM_ID,
+        } as any)
+        .rpc();
+
+      console.log("LP Metadata:", lpMetadata.toBase58());
+      console.log("Tx:", sig);
+      console.log("Solscan (devnet):", solscanTxUrl(sig, "devnet"));
+      return;
+    }
+
+    if (
+      command === "create_farm" ||
+      command === "add_farm_reward" ||
+      command === "stake_lp" ||
+      command === "unstake_lp" ||
+      command === "claim_rewards"
+    ) {
+      const { farm, stakeVault } = deriveFarm(programId, poolState);
+      const userLpAta = getAssociatedTokenAddressSync(lpMint, wallet.publicKey);
+      const [farmStake] = PublicKey.findProgramAddressSync(
+        [Buffer.from("farm_stake"), farm.toBuffer(), wallet.publicKey.toBuffer()],
+        programId
+      );
+
+      let sig: string;
+      if (command === "create_farm") {
+        sig = await program.methods
+          .createFarm()
+          .accounts({
+            poolState,
+            poolAuthority,
+            lpMint,
+            farm,
+            stakeVault,
+            admin: wallet.publicKey,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          } as any)
+          .rpc();
+      } else if (command === "add_farm_reward") {
+        if (!args[3] || !args[4] || !args[5]) {
+          console.error("add_farm_reward 需要 mintA mintB rewardMint emissionPerSecond durationDays");
+          process.exit(1);
+        }
+        const rewardMint = parsePubkey(args[3]);
+        const [rewardVault] = PublicKey.findProgramAddressSync(
+          [Buffer.from("farm_reward_vault"), farm.toBuffer(), rewardMint.toBuffer()],
+          programId
+        );
+        const endTs = new anchor.BN(Math.floor(Date.now() / 1000) + Number(args[5]) * 86400);
+        sig = await program.methods
+          .addFarmReward(parseNum(args[4]), endTs)
+          .accounts({
+            poolState,
+            poolAuthority,
+            farm,
+            rewardMint,
+            rewardVault,
+            adminRewardToken: getAssociatedTokenAddressSync(rewardMint, wallet.publicKey),
+            admin: wallet.publicKey,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          } as any)
+          .rpc();
+        console.log("RewardVault:", rewardVault.toBase58());
+      } else if (command === "stake_lp" || command === "unstake_lp") {
+        if (!args[3]) {
+          console.error(`${command} 需要 mintA mintB amountLp`);
+          process.exit(1);
+        }
+        const amount = parseNum(args[3]);
+        const method = command === "stake_lp" ? program.methods.stakeLp(amount) : program.methods.unstakeLp(amount);
+        sig = await method
+          .accounts({
+            poolState,
+            poolAuthority,
+            lpMint,
+            farm,
+            stakeVault,
+            farmStake,
+            userLpToken: userLpAta,
+            user: wallet.publicKey,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          } as any)
+          .rpc();
+      } else {
+        const rewardIndex = Number(args[3] ?? 0);
+        const farmAccount = await (program.account as any).farm.fetch(farm);
+        const reward = farmAccount.rewards[rewardIndex];
+        if (rewardIndex >= farmAccount.rewardCount) {
+          console.error("奖励下标超出范围，当前奖励数量:", farmAccount.rewardCount);
+          process.exit(1);
+        }
+        const userRewardToken = (
+          await getOrCreateAssociatedTokenAccount(connection, wallet, reward.mint, wallet.publicKey)
+        ).address;
+        sig = await program.methods
+          .claimRewards(rewardIndex)
+          .accounts({
+            poolState,
+            poolAuthority,
+            farm,
+            farmStake,
+            rewardVault: reward.vault,
+            userRewardToken,
+            user: wallet.publicKey,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          } as any)
+          .rpc();
+      }
+
+      console.log("Farm:", farm.toBase58());
+      console.log("Tx:", sig);
+      console.log("Solscan (devnet):", solscanTxUrl(sig, "devnet"));
+      return;
+    }
+
+    if (command === "place_order" || command === "cancel_order") {
+      if (!args[3] || (command === "place_order" && (!args[4] || !args[5] || !args[6]))) {
+        console.error(
+          command === "place_order"
+            ? "place_order 需要 mintA mintB orderId amountIn isAtoB minAmountOut [bountyLamports]"
+            : "cancel_order 需要 mintA mintB orderId"
+        );
+        process.exit(1);
+      }
+      const orderId = parseNum(args[3]);
+      const { limitOrder, escrow } = deriveLimitOrder(programId, poolState, wallet.publicKey, orderId);
+
+      let sig: string;
+      if (command === "place_order") {
+        const isAtoB = args[5] === "true" || args[5] === "1";
+        const tokenInMint = isAtoB ? ma : mb;
+        sig = await program.methods
+          .placeOrder(orderId, parseNum(args[4]), isAtoB, parseNum(args[6]), parseNum(args[7] ?? "0"))
+          .accounts({
+            poolState,
+            poolAuthority,
+            tokenInMint,
+            limitOrder,
+            escrow,
+            userTokenIn: getAssociatedTokenAddressSync(tokenInMint, wallet.publicKey),
+            owner: wallet.publicKey,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          } as any)
+          .rpc();
+      } else {
+        const order = await (program.account as any).limitOrder.fetch(limitOrder);
+        const tokenInMint = (order.isAToB ?? order.is_a_to_b) ? ma : mb;
+        sig = await program.methods
+          .cancelOrder()
+          .accounts({
+            poolState,
+            poolAuthority,
+            limitOrder,
+            escrow,
+            userTokenIn: getAssociatedTokenAddressSync(tokenInMint, wallet.publicKey),
+            owner: wallet.publicKey,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          } as any)
+          .rpc();
+      }
+
+      console.log("LimitOrder:", limitOrder.toBase58());
+      console.log("Tx:", sig);
+      console.log("Solscan (devnet):", solscanTxUrl(sig, "devnet"));
+      return;
+    }
+
+    if (command === "fill_orders") {
+      if (!args[3] || !args[4]) {
+        console.error("fill_orders 需要 mintA mintB owner orderId [orderId...]");
+        process.exit(1);
+      }
+      const owner = parsePubkey(args[3]);
+      // 每个订单依次传入 [limit_order, escrow, owner 接收输出的代币账户, owner]
+      const remainingAccounts = [];
+      for (const id of args.slice(4)) {
+        const { limitOrder, escrow } = deriveLimitOrder(programId, poolState, owner, parseNum(id));
+        const order = await (program.account as any).limitOrder.fetch(limitOrder);
+        const mintOut = (order.isAToB ?? order.is_a_to_b) ? mb : ma;
+        remainingAccounts.push(
+          { pubkey: limitOrder, isSigner: false, isWritable: true },
+          { pubkey: escrow, isSigner: false, isWritable: true },
+          { pubkey: getAssociatedTokenAddressSync(mintOut, owner), isSigner: false, isWritable: true },
+          { pubkey: owner, isSigner: false, isWritable: true }
+        );
+      }
+
+      console.log("--- 执行前 ---");
+      await fetchPoolStateSummary(program, connection, poolState, vaultA, vaultB);
+      const sig = await program.methods
+        .fillOrders()
+        .accounts({
+          poolState,
+          poolAuthority,
+          tokenAVault: vaultA,
+          tokenBVault: vaultB,
+          keeper: wallet.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          ...(await optionalTwammAccounts(connection, programId, poolState, ma, mb)),
+        } as any)
+        .remainingAccounts(remainingAccounts)
+        .rpc();
+
+      console.log("Tx:", sig);
+      console.log("Solscan (devnet):", solscanTxUrl(sig, "devnet"));
+      console.log("--- 执行后 ---");
+      await fetchPoolStateSummary(program, connection, poolState, vaultA, vaultB);
+      return;
+    }
+
+    if (command === "init_twamm") {
+      const { twamm, twammVaultA, twammVaultB } = deriveTwamm(programId, poolState, ma, mb);
+      const sig = await program.methods
+        .initTwamm()
+        .accounts({
+          poolState,
+          poolAuthority,
+          twamm,
+          tokenAMint: ma,
+          tokenBMint: mb,
+          twammVaultA,
+          twammVaultB,
+          admin: wallet.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        } as any)
+        .rpc();
+
+      console.log("Twamm:", twamm.toBase58());
+      console.log("Tx:", sig);
+      console.log("Solscan (devnet):", solscanTxUrl(sig, "devnet"));
+      return;
+    }
+
+    if (
+      command === "place_long_term_order" ||
+      command === "withdraw_long_term_order" ||
+      command === "execute_twamm"
+    ) {
+      if (command === "place_long_term_order" && (!args[3] || !args[4] || !args[5] || !args[6])) {
+        console.error("place_long_term_order 需要 mintA mintB orderId amountIn isAtoB intervals");
+        process.exit(1);
+      }
+      if (command === "withdraw_long_term_order" && !args[3]) {
+        console.error("withdraw_long_term_order 需要 mintA mintB orderId");
+        process.exit(1);
+      }
+      // 三条指令都会先把长期订单结算到当前时间
+      const settleAccounts = {
+        poolState,
+        poolAuthority,
+        tokenAVault: vaultA,
+        tokenBVault: vaultB,
+        ...deriveTwamm(programId, poolState, ma, mb),
+        tokenProgram: TOKEN_PROGRAM_ID,
+      };
+
+      console.log("--- 执行前 ---");
+      await fetchPoolStateSummary(program, connection, poolState, vaultA, vaultB);
+
+      let sig: string;
+      if (command === "execute_twamm") {
+        sig = await program.methods.executeTwamm().accounts(settleAccounts as any).rpc();
+      } else {
+        const orderId = parseNum(args[3]);
+        const longTermOrder = deriveLongTermOrder(programId, poolState, wallet.publicKey, orderId);
+        console.log("LongTermOrder:", longTermOrder.toBase58());
+        if (command === "place_long_term_order") {
+          const isAtoB = args[5] === "true" || args[5] === "1";
+          sig = await program.methods
+            .placeLongTermOrder(orderId, parseNum(args[4]), isAtoB, parseInt(args[6], 10))
+            .accounts({
+              ...settleAccounts,
+              longTermOrder,
+              userTokenIn: getAssociatedTokenAddressSync(isAtoB ? ma : mb, wallet.publicKey),
+              owner: wallet.publicKey,
+              systemProgram: anchor.web3.SystemProgram.programId,
+            } as any)
+            .rpc();
+        } else {
+          sig = await program.methods
+            .withdrawLongTermOrder()
+            .accounts({
+              ...settleAccounts,
+              longTermOrder,
+              userTokenA: getAssociatedTokenAddressSync(ma, wallet.publicKey),
+              userTokenB: getAssociatedTokenAddressSync(mb, wallet.publicKey),
+              owner: wallet.publicKey,
+            } as any)
+            .rpc();
+        }
+      }
+
+      console.log("Tx:", sig);
+      console.log("Solscan (devnet):", solscanTxUrl(sig, "devnet"));
+      console.log("--- 执行后 ---");
+      await fetchPoolStateSummary(program, connection, poolState, vaultA, vaultB);
+      return;
+    }
+
+    if (command === "open_dca" || command === "withdraw_dca" || command === "close_dca") {
+      if (!args[3] || (command === "open_dca" && (!args[4] || !args[5] || !args[6] || !args[7]))) {
+        console.error(
+          command === "open_dca"
+            ? "open_dca 需要 mintA mintB dcaId amountIn isAtoB amountPerCycle intervalSeconds [minOutPerCycle]"
+            : `${command} 需要 mintA mintB dcaId`
+        );
+        process.exit(1);
+      }
+      const dcaId = parseNum(args[3]);
+      const { dcaVault, inputVault, outputVault } = deriveDca(programId, poolState, wallet.publicKey, dcaId);
+
+      let sig: string;
+      if (command === "open_dca") {
+        const isAtoB = args[5] === "true" || args[5] === "1";
+        const tokenInMint = isAtoB ? ma : mb;
+        sig = await program.methods
+          .openDca(dcaId, parseNum(args[4]), isAtoB, parseNum(args[6]), parseNum(args[7]), parseNum(args[8] ?? "0"))
+          .accounts({
+            poolState,
+            poolAuthority,
+            tokenInMint,
+            tokenOutMint: isAtoB ? mb : ma,
+            dcaVault,
+            inputVault,
+            outputVault,
+            userTokenIn: getAssociatedTokenAddressSync(tokenInMint, wallet.publicKey),
+            owner: wallet.publicKey,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          } as any)
+          .rpc();
+      } else {
+        const dca = await (program.account as any).dcaVault.fetch(dcaVault);
+        const isAtoB = dca.isAToB ?? dca.is_a_to_b;
+        const userTokenOut = getAssociatedTokenAddressSync(isAtoB ? mb : ma, wallet.publicKey);
+        if (command === "withdraw_dca") {
+          sig = await program.methods
+            .withdrawDca()
+            .accounts({
+              poolState,
+              poolAuthority,
+              dcaVault,
+              outputVault,
+              userTokenOut,
+              owner: wallet.publicKey,
+              tokenProgram: TOKEN_PROGRAM_ID,
+            } as any)
+            .rpc();
+        } else {
+          sig = await program.methods
+            .closeDca()
+            .accounts({
+              poolState,
+              poolAuthority,
+              dcaVault,
+              inputVault,
+              outputVault,
+              userTokenIn: getAssociatedTokenAddressSync(isAtoB ? ma : mb, wallet.publicKey),
+              userTokenOut,
+              owner: wallet.publicKey,
+              tokenProgram: TOKEN_PROGRAM_ID,
+            } as any)
+            .rpc();
+        }
+      }
+
+      console.log("DcaVault:", dcaVault.toBase58());
+      console.log("Tx:", sig);
+      console.log("Solscan (devnet):", solscanTxUrl(sig, "devnet"));
+      return;
+    }
+
+    if (command === "execute_dca") {
+      if (!args[3] || !args[4]) {
+        console.error("execute_dca 需要 mintA mintB owner dcaId");
+        process.exit(1);
+      }
+      const { dcaVault, inputVault, outputVault } = deriveDca(programId, poolState, parsePubkey(args[3]), parseNum(args[4]));
+
+      console.log("--- 执行前 ---");
+      await fetchPoolStateSummary(program, connection, poolState, vaultA, vaultB);
+      const sig = await program.methods
+        .executeDca()
+        .accounts({
+          poolState,
+          poolAuthority,
+          tokenAVault: vaultA,
+          tokenBVault: vaultB,
+          dcaVault,
+          inputVault,
+          outputVault,
+          keeper: wallet.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          ...(await optionalTwammAccounts(connection, programId, poolState, ma, mb)),
+        } as any)
+        .rpc();
+
+      console.log("Tx:", sig);
+      console.log("Solscan (devnet):", solscanTxUrl(sig, "devnet"));
+      console.log("--- 执行后 ---");
+      await fetchPoolStateSummary(program, connection, poolState, vaultA, vaultB);
+      return;
+    }
+
+    if (command === "init_batch_auction") {
+      if (!args[3] || !args[4] || !args[5]) {
+        console.error("init_batch_auction 需要 mintA mintB batchDurationSlots minOrderA minOrderB");
+        process.exit(1);
+      }
+      const { batchAuction, batchVaultA, batchVaultB } = deriveBatchAuction(programId, poolState, ma, mb);
+      const sig = await program.methods
+        .initBatchAuction(parseNum(args[3]), parseNum(args[4]), parseNum(args[5]))
+        .accounts({
+          poolState,
+          poolAuthority,
+          batchAuction,
+          tokenAMint: ma,
+          tokenBMint: mb,
+          batchVaultA,
+          batchVaultB,
+          admin: wallet.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        } as any)
+        .rpc();
+
+      console.log("BatchAuction:", batchAuction.toBase58());
+      console.log("Tx:", sig);
+      console.log("Solscan (devnet):", solscanTxUrl(sig, "devnet"));
+      return;
+    }
+
+    if (command === "submit_batch_swap") {
+      if (!args[3] || !args[4]) {
+        console.error("submit_batch_swap 需要 mintA mintB amountIn isAtoB [minAmountOut]");
+        process.exit(1);
+      }
+      const isAtoB = args[4] === "true" || args[4] === "1";
+      const { batchAuction, batchVaultA, batchVaultB } = deriveBatchAuction(programId, poolState, ma, mb);
+      const auction = await (program.account as any).batchAuction.fetch(batchAuction);
+      const batchOrder = deriveBatchOrder(programId, batchAuction, wallet.publicKey, auction.batchId);
+      const sig = await program.methods
+        .submitBatchSwap(parseNum(args[3]), isAtoB, parseNum(args[5] ?? "0"))
+        .accounts({
+          poolState,
+          batchAuction,
+          batchVaultIn: isAtoB ? batchVaultA : batchVaultB,
+          batchOrder,
+          userTokenIn: getAssociatedTokenAddressSync(isAtoB ? ma : mb, wallet.publicKey),
+          owner: wallet.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        } as any)
+        .rpc();
+
+      console.log("Batch:", auction.batchId.toString(), "BatchOrder:", batchOrder.toBase58());
+      console.log("Tx:", sig);
+      console.log("Solscan (devnet):", solscanTxUrl(sig, "devnet"));
+      return;
+    }
+
+    if (command === "set_batch_mode") {
+      if (args[3] !== "on" && args[3] !== "off") {
+        console.error("set_batch_mode 需要 mintA mintB on|off");
+        process.exit(1);
+      }
+      const { batchAuction } = deriveBatchAuction(programId, poolState, ma, mb);
+      const sig = await program.methods
+        .setBatchMode(args[3] === "on")
+        .accounts({
+          poolState,
+          batchAuction,
+          admin: wallet.publicKey,
+        } as any)
+        .rpc();
+
+      console.log("Tx:", sig);
+      console.log("Solscan (devnet):", solscanTxUrl(sig, "devnet"));
+      console.log("批量拍卖:", args[3] === "on" ? "开启" : "关闭");
+      return;
+    }
+
+    if (command === "settle_batch") {
+      const { batchAuction, batchVaultA, batchVaultB } = deriveBatchAuction(programId, poolState, ma, mb);
+      const auction = await (program.account as any).batchAuction.fetch(batchAuction);
+      // BatchOrder 的第一个字段是 pool，按池子过滤后只留下当前批次
+      const orders = (
+        await (program.account as any).batchOrder.all([{ memcmp: { offset: 8, bytes: poolState.toBase58() } }])
+      ).filter((order: any) => order.account.batchId.eq(auction.batchId) && !order.account.settled);
+      // 每个订单只传入可写的 batch_order，结算结果由所有者之后用 claim_batch_order 取回
+      const remainingAccounts = orders.map((order: any) => ({
+        pubkey: order.publicKey,
+        isSigner: false,
+        isWritable: true,
+      }));
+
+      console.log(`--- 结算第 ${auction.batchId.toString()} 批，${orders.length} 笔订单 ---`);
+      await fetchPoolStateSummary(program, connection, poolState, vaultA, vaultB);
+      const sig = await program.methods
+        .settleBatch()
+        .accounts({
+          poolState,
+          poolAuthority,
+          tokenAVault: vaultA,
+          tokenBVault: vaultB,
+          batchAuction,
+          batchVaultA,
+          batchVaultB,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          ...(await optionalTwammAccounts(connection, programId, poolState, ma, mb)),
+        } as any)
+        .remainingAccounts(remainingAccounts)
+        .rpc();
+
+      console.log("Tx:", sig);
+      console.log("Solscan (devnet):", solscanTxUrl(sig, "devnet"));
+      console.log("--- 执行后 ---");
+      await fetchPoolStateSummary(program, connection, poolState, vaultA, vaultB);
+      return;
+    }
+
+    if (command === "claim_batch_order") {
+      if (!args[3]) {
+        console.error("claim_batch_order 需要 mintA mintB batchId");
+        process.exit(1);
+      }
+      const { batchAuction, batchVaultA, batchVaultB } = deriveBatchAuction(programId, poolState, ma, mb);
+      const batchOrder = deriveBatchOrder(programId, batchAuction, wallet.publicKey, new anchor.BN(args[3]));
+      const sig = await program.methods
+        .claimBatchOrder()
+        .accounts({
+          poolState,
+          poolAuthority,
+          batchAuction,
+          batchVaultA,
+          batchVaultB,
+          batchOrder,
+          ownerTokenA: getAssociatedTokenAddressSync(ma, wallet.publicKey),
+          ownerTokenB: getAssociatedTokenAddressSync(mb, wallet.publicKey),
+          owner: wallet.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        } as any)
+        .rpc();
+
+      console.log("Tx:", sig);
+      console.log("Solscan (devnet):", solscanTxUrl(sig, "devnet"));
+      return;
+    }
+
+    if (command === "cancel_batch_order") {
+      const { batchAuction, batchVaultA, batchVaultB } = deriveBatchAuction(programId, poolState, ma, mb);
+      const auction = await (program.account as any).batchAuction.fetch(batchAuction);
+      const batchOrder = deriveBatchOrder(programId, batchAuction, wallet.publicKey, auction.batchId);
+      const order = await (program.account as any).batchOrder.fetch(batchOrder);
+      const sig = await program.methods
+        .cancelBatchOrder()
+        .accounts({
+          poolState,
+          poolAuthority,
+          batchAuction,
+          batchOrder,
+          batchVaultIn: order.isAToB ? batchVaultA : batchVaultB,
+          userTokenIn: getAssociatedTokenAddressSync(order.isAToB ? ma : mb, wallet.publicKey),
+          owner: wallet.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        } as any)
+        .rpc();
+
+      console.log("Tx:", sig);
+      console.log("Solscan (devnet):", solscanTxUrl(sig, "devnet"));
+      return;
+    }
+
+    if (command === "register_pool") {
+      const { poolRegistry, registryPage, poolCount } = await registryAccounts(program, programId);
+      const sig = await program.methods
+        .registerPool()
+        .accounts({
+          poolState,
+          poolRegistry,
+          registryPage,
+          payer: wallet.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        } as any)
+        .rpc();
+
+      console.log("Tx:", sig);
+      console.log("Solscan (devnet):", solscanTxUrl(sig, "devnet"));
+      console.log("已登记为注册表中的第", poolCount, "个池子");
+      return;
+    }
+
     console.error("未知命令:", command);
     printUsage();
     process.exit(1);
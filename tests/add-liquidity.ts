@@ -12,6 +12,7 @@ import {
   getMint,
 } from "@solana/spl-token";
 import { assert } from "chai";
+import { registryAccounts } from "./utils";
 
 describe("add-liquidity", () => {
   // 1. 配置 Provider
@@ -78,6 +79,9 @@ describe("add-liquidity", () => {
     const lpMintKeypair = anchor.web3.Keypair.generate();
     lpMint = lpMintKeypair.publicKey;
     
+    // 新池子追加到注册表中 pool_count 所在的页
+    const { poolRegistry, registryPage } = await registryAccounts(program);
+
     await program.methods
       .initialize(mintA, mintB, feeNumerator, feeDenominator)
       .accounts({
@@ -88,6 +92,8 @@ describe("add-liquidity", () => {
         tokenAVault: vaultA,
         tokenBVault: vaultB,
         lpMint: lpMint,
+        poolRegistry,
+        registryPage,
         admin: user.publicKey,
         systemProgram: anchor.web3.SystemProgram.programId,
         tokenProgram: TOKEN_PROGRAM_ID,
@@ -145,7 +151,7 @@ describe("add-liquidity", () => {
     assert.equal(preLpMint.supply, 0n, "首次加池前 lp mint supply 应为 0");
 
     await program.methods
-      .addLiquidity(new anchor.BN(depositA), new anchor.BN(depositB))
+      .addLiquidity(new anchor.BN(depositA), new anchor.BN(depositB), null)
       .accounts({
         poolState,
         poolAuthority,
@@ -236,7 +242,7 @@ describe("add-liquidity", () => {
     const preUserB = await getAccount(provider.connection, userTokenB);
 
     await program.methods
-      .addLiquidity(new anchor.BN(depositA.toString()), new anchor.BN(depositB.toString()))
+      .addLiquidity(new anchor.BN(depositA.toString()), new anchor.BN(depositB.toString()), null)
       .accounts({
         poolState,
         poolAuthority,
@@ -12,6 +12,7 @@ import {
   getAssociatedTokenAddressSync,
 } from "@solana/spl-token";
 import { assert } from "chai";
+import { registryAccounts } from "./utils";
 
 /**
  * Demo 脚本
@@ -222,6 +223,9 @@ describe("Demo - Solana AMM 完整演示", () => {
     const lpMintKeypair = anchor.web3.Keypair.generate();
     lpMint = lpMintKeypair.publicKey;
 
+    // 新池子追加到注册表中 pool_count 所在的页
+    const { poolRegistry, registryPage } = await registryAccounts(program);
+
     await program.methods
       .initialize(mintA, mintB, feeNumerator, feeDenominator)
       .accounts({
@@ -232,6 +236,8 @@ describe("Demo - Solana AMM 完整演示", () => {
         tokenAVault: vaultA,
         tokenBVault: vaultB,
         lpMint: lpMint,
+        poolRegistry,
+        registryPage,
         admin: user.publicKey,
         systemProgram: anchor.web3.SystemProgram.programId,
         tokenProgram: TOKEN_PROGRAM_ID,
@@ -292,7 +298,7 @@ describe("Demo - Solana AMM 完整演示", () => {
     console.log(`   黑洞地址 LP 余额: ${preBlackHole.amount.toString()}`);
 
     await program.methods
-      .addLiquidity(new anchor.BN(depositA), new anchor.BN(depositB))
+      .addLiquidity(new anchor.BN(depositA), new anchor.BN(depositB), null)
       .accounts({
         poolState,
         poolAuthority,
@@ -381,7 +387,7 @@ describe("Demo - Solana AMM 完整演示", () => {
     const preUserB = await getAccount(provider.connection, userTokenB);
 
     await program.methods
-      .swap(amountIn, true, minAmountOut)
+      .swap(amountIn, true, minAmountOut, null, 0)
       .accounts({
         poolState: poolState,
         userTokenA: userTokenA,
@@ -441,7 +447,7 @@ describe("Demo - Solana AMM 完整演示", () => {
     const preUserB = await getAccount(provider.connection, userTokenB);
 
     await program.methods
-      .swap(amountIn, true, minAmountOut)
+      .swap(amountIn, true, minAmountOut, null, 0)
       .accounts({
         poolState: poolState,
         userTokenA: userTokenA,
@@ -486,7 +492,7 @@ describe("Demo - Solana AMM 完整演示", () => {
     const preUserA = await getAccount(provider.connection, userTokenA);
 
     await program.methods
-      .swap(amountIn, false, minAmountOut)
+      .swap(amountIn, false, minAmountOut, null, 0)
       .accounts({
         poolState: poolState,
         userTokenA: userTokenA,
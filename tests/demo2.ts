@@ -12,6 +12,7 @@ import {
   getAssociatedTokenAddressSync,
 } from "@solana/spl-token";
 import { assert } from "chai";
+import { registryAccounts } from "./utils";
 
 /**
  * 🚀 Demo 脚本 (Devnet 终极版)
@@ -134,6 +135,9 @@ describe("Demo - Solana AMM 完整演示", () => {
     const lpMintKeypair = anchor.web3.Keypair.generate();
     lpMint = lpMintKeypair.publicKey;
 
+    // 新池子追加到注册表中 pool_count 所在的页
+    const { poolRegistry, registryPage } = await registryAccounts(program);
+
     await program.methods
       .initialize(mintA, mintB, feeNumerator, feeDenominator)
       .accounts({
@@ -144,6 +148,8 @@ describe("Demo - Solana AMM 完整演示", () => {
         tokenAVault: vaultA,
         tokenBVault: vaultB,
         lpMint,
+        poolRegistry,
+        registryPage,
         admin: user.publicKey,
         systemProgram: anchor.web3.SystemProgram.programId,
         tokenProgram: TOKEN_PROGRAM_ID,
@@ -166,7 +172,7 @@ describe("Demo - Solana AMM 完整演示", () => {
     ).address;
 
     await program.methods
-      .addLiquidity(new anchor.BN(100_000_000), new anchor.BN(100_000_000))
+      .addLiquidity(new anchor.BN(100_000_000), new anchor.BN(100_000_000), null)
       .accounts({
         poolState,
         poolAuthority,
@@ -188,12 +194,12 @@ describe("Demo - Solana AMM 完整演示", () => {
 
   it("步骤 3~6: 执行多次 Swap 积累手续费", async () => {
     console.log("\n🔄 正在执行 Swap，为池子积累手续费...");
-    await program.methods.swap(new anchor.BN(10_000_000), true, new anchor.BN(0))
+    await program.methods.swap(new anchor.BN(10_000_000), true, new anchor.BN(0), null, 0)
       .accounts({ poolState, userTokenA, userTokenB, tokenAVault: vaultA, tokenBVault: vaultB, user: user.publicKey, poolAuthority, tokenProgram: TOKEN_PROGRAM_ID } as any).rpc();
     
     await advanceTime(5);
 
-    await program.methods.swap(new anchor.BN(8_000_000), false, new anchor.BN(0))
+    await program.methods.swap(new anchor.BN(8_000_000), false, new anchor.BN(0), null, 0)
       .accounts({ poolState, userTokenA, userTokenB, tokenAVault: vaultA, tokenBVault: vaultB, user: user.publicKey, poolAuthority, tokenProgram: TOKEN_PROGRAM_ID } as any).rpc();
     
     await displayPoolState("Swap 积累手续费后的状态");
@@ -222,7 +228,9 @@ describe("Demo - Solana AMM 完整演示", () => {
       .updateConfig(
         newAdmin.publicKey,              // new_admin (Option<Pubkey>)
         protocolFeeRecipient.publicKey,  // new_recipient (Option<Pubkey>) - 更新为独立账户
-        new anchor.BN(166)               // new_share (Option<u64>)
+        new anchor.BN(166),              // new_share (Option<u64>)
+        null,                            // new_max_price_deviation_bps (Option<u16>)
+        null                             // new_max_referral_fee_bps (Option<u16>)
       )
       .accounts({
         poolState,
@@ -247,7 +255,7 @@ describe("Demo - Solana AMM 完整演示", () => {
 
     state = await program.account.poolState.fetch(poolState);
     assert.isTrue(state.admin.equals(newAdmin.publicKey), "管理员未成功移交");
-    assert.isNull(state.pendingAdmin, "Pending Admin 未清空");
+    assert.isTrue(state.pendingAdmin.equals(anchor.web3.PublicKey.default), "Pending Admin 未清空");
     assert.isTrue(state.protocolFeeRecipient.equals(protocolFeeRecipient.publicKey), "协议费接收者未正确设置");
     assert.equal(state.protocolFeeShare.toNumber(), 166, "协议费比例未正确设置");
     console.log(`✅ 移交成功! 当前 Admin: ${state.admin.toString()}`);
@@ -280,7 +288,7 @@ describe("Demo - Solana AMM 完整演示", () => {
     const amountLpToRemove = new anchor.BN(1000);
 
     await program.methods
-      .removeLiquidity(amountLpToRemove, new anchor.BN(0), new anchor.BN(0))
+      .removeLiquidity(amountLpToRemove, new anchor.BN(0), new anchor.BN(0), null)
       .accounts({
         poolState,
         poolAuthority,
@@ -12,6 +12,7 @@ import {
   getMint,
 } from "@solana/spl-token";
 import { assert } from "chai";
+import { registryAccounts } from "./utils";
 
 describe("protocol-fee", () => {
   // 1. 配置 Provider
@@ -85,6 +86,9 @@ describe("protocol-fee", () => {
     const lpMintKeypair = anchor.web3.Keypair.generate();
     lpMint = lpMintKeypair.publicKey;
     
+    // 新池子追加到注册表中 pool_count 所在的页
+    const { poolRegistry, registryPage } = await registryAccounts(program);
+
     await program.methods
       .initialize(mintA, mintB, feeNumerator, feeDenominator)
       .accounts({
@@ -95,6 +99,8 @@ describe("protocol-fee", () => {
         tokenAVault: vaultA,
         tokenBVault: vaultB,
         lpMint: lpMint,
+        poolRegistry,
+        registryPage,
         admin: user.publicKey,
         systemProgram: anchor.web3.SystemProgram.programId,
         tokenProgram: TOKEN_PROGRAM_ID,
@@ -111,7 +117,9 @@ describe("protocol-fee", () => {
       .updateConfig(
         null, // new_admin (不更新)
         protocolFeeRecipient.publicKey, // new_recipient
-        null  // new_share (稍后设置)
+        null, // new_share (稍后设置)
+        null, // new_max_price_deviation_bps (不更新)
+        null  // new_max_referral_fee_bps (不更新)
       )
       .accounts({
         poolState: poolState,
@@ -124,7 +132,9 @@ describe("protocol-fee", () => {
       .updateConfig(
         null, // new_admin (不更新)
         null, // new_recipient (不更新)
-        new anchor.BN(6) // new_share = 6 (表示 1/6)
+        new anchor.BN(6), // new_share = 6 (表示 1/6)
+        null, // new_max_price_deviation_bps (不更新)
+        null // new_max_referral_fee_bps (不更新)
       )
       .accounts({
         poolState: poolState,
@@ -175,7 +185,7 @@ describe("protocol-fee", () => {
     const preLpMint = await getMint(provider.connection, lpMint);
 
     await program.methods
-      .addLiquidity(new anchor.BN(depositA), new anchor.BN(depositB))
+      .addLiquidity(new anchor.BN(depositA), new anchor.BN(depositB), null)
       .accounts({
         poolState,
         poolAuthority,
@@ -223,7 +233,7 @@ describe("protocol-fee", () => {
     const preVaultB = await getAccount(provider.connection, vaultB);
 
     await program.methods
-      .swap(amountIn, true, minAmountOut) // A -> B
+      .swap(amountIn, true, minAmountOut, null, 0) // A -> B
       .accounts({
         poolState: poolState,
         userTokenA: userTokenA,
@@ -270,7 +280,7 @@ describe("protocol-fee", () => {
     const preState = await program.account.poolState.fetch(poolState);
 
     await program.methods
-      .addLiquidity(new anchor.BN(depositA), new anchor.BN(depositB))
+      .addLiquidity(new anchor.BN(depositA), new anchor.BN(depositB), null)
       .accounts({
         poolState,
         poolAuthority,
@@ -324,7 +334,7 @@ describe("protocol-fee", () => {
 
     // 先执行一次 swap 来产生手续费
     await program.methods
-      .swap(new anchor.BN(5_000_000), true, new anchor.BN(0))
+      .swap(new anchor.BN(5_000_000), true, new anchor.BN(0), null, 0)
       .accounts({
         poolState: poolState,
         userTokenA: userTokenA,
@@ -351,7 +361,8 @@ describe("protocol-fee", () => {
         .removeLiquidity(
           new anchor.BN(amountLpToRemove.toString()),
           minAmountA,
-          minAmountB
+          minAmountB,
+          null
         )
         .accounts({
           poolState,
@@ -419,7 +430,7 @@ describe("protocol-fee", () => {
     const depositB = 10_000_000; // 10 B
 
     await program.methods
-      .addLiquidity(new anchor.BN(depositA), new anchor.BN(depositB))
+      .addLiquidity(new anchor.BN(depositA), new anchor.BN(depositB), null)
       .accounts({
         poolState,
         poolAuthority,
@@ -12,6 +12,7 @@ import {
   getMint,
 } from "@solana/spl-token";
 import { assert } from "chai";
+import { registryAccounts } from "./utils";
 
 describe("remove-liquidity", () => {
   // 1. 配置 Provider
@@ -78,6 +79,9 @@ describe("remove-liquidity", () => {
     const lpMintKeypair = anchor.web3.Keypair.generate();
     lpMint = lpMintKeypair.publicKey;
     
+    // 新池子追加到注册表中 pool_count 所在的页
+    const { poolRegistry, registryPage } = await registryAccounts(program);
+
     await program.methods
       .initialize(mintA, mintB, feeNumerator, feeDenominator)
       .accounts({
@@ -88,6 +92,8 @@ describe("remove-liquidity", () => {
         tokenAVault: vaultA,
         tokenBVault: vaultB,
         lpMint: lpMint,
+        poolRegistry,
+        registryPage,
         admin: user.publicKey,
         systemProgram: anchor.web3.SystemProgram.programId,
         tokenProgram: TOKEN_PROGRAM_ID,
@@ -135,7 +141,7 @@ describe("remove-liquidity", () => {
     const depositB = 10_000_000; // 10 B (mintB decimals=6)
 
     await program.methods
-      .addLiquidity(new anchor.BN(depositA), new anchor.BN(depositB))
+      .addLiquidity(new anchor.BN(depositA), new anchor.BN(depositB), null)
       .accounts({
         poolState,
         poolAuthority,
@@ -187,7 +193,8 @@ describe("remove-liquidity", () => {
       .removeLiquidity(
         new anchor.BN(amountLpToRemoveU64),
         new anchor.BN(Number(minAmountA)),
-        new anchor.BN(Number(minAmountB))
+        new anchor.BN(Number(minAmountB)),
+        null
       )
       .accounts({
         poolState,
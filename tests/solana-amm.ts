@@ -11,6 +11,7 @@ import {
   getAssociatedTokenAddressSync,
 } from "@solana/spl-token";
 import { assert } from "chai";
+import { registryAccounts } from "./utils";
 
 describe("solana-amm", () => {
   // 1. 配置 Provider
@@ -72,6 +73,9 @@ describe("solana-amm", () => {
     // 生成 lpMint 的 keypair（Anchor 会自动创建）
     const lpMintKeypair = anchor.web3.Keypair.generate();
     
+    // 新池子追加到注册表中 pool_count 所在的页
+    const { poolRegistry, registryPage } = await registryAccounts(program);
+
     await program.methods
       .initialize(mintA, mintB, feeNumerator, feeDenominator)
       .accounts({
@@ -82,6 +86,8 @@ describe("solana-amm", () => {
         tokenAVault: vaultA,
         tokenBVault: vaultB,
         lpMint: lpMintKeypair.publicKey,
+        poolRegistry,
+        registryPage,
         admin: user.publicKey,
         systemProgram: anchor.web3.SystemProgram.programId,
         tokenProgram: TOKEN_PROGRAM_ID,
@@ -106,7 +112,7 @@ describe("solana-amm", () => {
     const preUserB = await getAccount(provider.connection, userTokenB);
 
     await program.methods
-      .swap(amountIn, true, minAmountOut) // is_a_to_b = true
+      .swap(amountIn, true, minAmountOut, null, 0) // is_a_to_b = true
       .accounts({
         poolState: poolState,
         userTokenA: userTokenA,
@@ -130,7 +136,7 @@ describe("solana-amm", () => {
 
     try {
       await program.methods
-        .swap(amountIn, true, greedyMinAmountOut)
+        .swap(amountIn, true, greedyMinAmountOut, null, 0)
         .accounts({
             poolState: poolState,
             userTokenA: userTokenA,
@@ -148,4 +154,28 @@ describe("solana-amm", () => {
       assert.include(err.toString(), "SlippageExceeded");
     }
   });
+
+  it("过期的交易被拒绝", async () => {
+    // deadline 设为一小时前
+    const deadline = new anchor.BN(Math.floor(Date.now() / 1000) - 3600);
+
+    try {
+      await program.methods
+        .swap(new anchor.BN(1_000_000), true, new anchor.BN(1), deadline, 0)
+        .accounts({
+            poolState: poolState,
+            userTokenA: userTokenA,
+            userTokenB: userTokenB,
+            tokenAVault: vaultA,
+            tokenBVault: vaultB,
+            user: user.publicKey,
+            poolAuthority: poolAuthority,
+            tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .rpc();
+      assert.fail("deadline 未生效");
+    } catch (err: any) {
+      assert.include(err.toString(), "Expired");
+    }
+  });
 });
\ No newline at end of file
@@ -11,6 +11,7 @@ import {
   getAssociatedTokenAddressSync,
 } from "@solana/spl-token";
 import { assert } from "chai";
+import { registryAccounts } from "./utils";
 
 describe("twap", () => {
   // 1. 配置 Provider
@@ -73,6 +74,9 @@ describe("twap", () => {
     const lpMintKeypair = anchor.web3.Keypair.generate();
     lpMint = lpMintKeypair.publicKey;
     
+    // 新池子追加到注册表中 pool_count 所在的页
+    const { poolRegistry, registryPage } = await registryAccounts(program);
+
     await program.methods
       .initialize(mintA, mintB, feeNumerator, feeDenominator)
       .accounts({
@@ -83,6 +87,8 @@ describe("twap", () => {
         tokenAVault: vaultA,
         tokenBVault: vaultB,
         lpMint: lpMint,
+        poolRegistry,
+        registryPage,
         admin: user.publicKey,
         systemProgram: anchor.web3.SystemProgram.programId,
         tokenProgram: TOKEN_PROGRAM_ID,
@@ -131,7 +137,7 @@ describe("twap", () => {
     // 注意：在测试环境中，时间戳可能不会变化，但我们应该验证逻辑
 
     await program.methods
-      .swap(amountIn, true, minAmountOut) // is_a_to_b = true
+      .swap(amountIn, true, minAmountOut, null, 0) // is_a_to_b = true
       .accounts({
         poolState: poolState,
         userTokenA: userTokenA,
@@ -181,7 +187,7 @@ describe("twap", () => {
     const preVaultB1 = await getAccount(provider.connection, vaultB);
 
     await program.methods
-      .swap(amountIn1, true, minAmountOut1)
+      .swap(amountIn1, true, minAmountOut1, null, 0)
       .accounts({
         poolState: poolState,
         userTokenA: userTokenA,
@@ -207,7 +213,7 @@ describe("twap", () => {
     const minAmountOut2 = new anchor.BN(0);
 
     await program.methods
-      .swap(amountIn2, true, minAmountOut2)
+      .swap(amountIn2, true, minAmountOut2, null, 0)
       .accounts({
         poolState: poolState,
         userTokenA: userTokenA,
@@ -262,7 +268,7 @@ describe("twap", () => {
     const minAmountOut = new anchor.BN(0);
 
     await program.methods
-      .swap(amountIn, true, minAmountOut)
+      .swap(amountIn, true, minAmountOut, null, 0)
       .accounts({
         poolState: poolState,
         userTokenA: userTokenA,
@@ -340,7 +346,7 @@ describe("twap", () => {
     const minAmountOut = new anchor.BN(0);
 
     await program.methods
-      .swap(amountIn, false, minAmountOut) // is_a_to_b = false，即 B -> A
+      .swap(amountIn, false, minAmountOut, null, 0) // is_a_to_b = false，即 B -> A
       .accounts({
         poolState: poolState,
         userTokenA: userTokenA,
@@ -406,7 +412,7 @@ describe("twap", () => {
     const minAmountOut = new anchor.BN(0);
 
     await program.methods
-      .swap(amountIn, true, minAmountOut)
+      .swap(amountIn, true, minAmountOut, null, 0)
       .accounts({
         poolState: poolState,
         userTokenA: userTokenA,
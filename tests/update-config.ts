@@ -10,6 +10,7 @@ import {
   getAssociatedTokenAddressSync,
 } from "@solana/spl-token";
 import { assert } from "chai";
+import { registryAccounts } from "./utils";
 
 describe("update_config - 更新池子配置", () => {
   const provider = anchor.AnchorProvider.env();
@@ -62,6 +63,9 @@ describe("update_config - 更新池子配置", () => {
     const lpMintKeypair = anchor.web3.Keypair.generate();
     lpMint = lpMintKeypair.publicKey;
 
+    // 新池子追加到注册表中 pool_count 所在的页
+    const { poolRegistry, registryPage } = await registryAccounts(program);
+
     await program.methods
       .initialize(mintA, mintB, feeNumerator, feeDenominator)
       .accounts({
@@ -72,6 +76,8 @@ describe("update_config - 更新池子配置", () => {
         tokenAVault: vaultA,
         tokenBVault: vaultB,
         lpMint: lpMint,
+        poolRegistry,
+        registryPage,
         admin: admin.publicKey,
         systemProgram: anchor.web3.SystemProgram.programId,
         tokenProgram: TOKEN_PROGRAM_ID,
@@ -117,7 +123,7 @@ describe("update_config - 更新池子配置", () => {
 
     // 第一步：设置 pending_admin
     await program.methods
-      .updateConfig(newAdmin.publicKey, null, null)
+      .updateConfig(newAdmin.publicKey, null, null, null, null)
       .accounts({
         poolState: poolState,
         admin: admin.publicKey,
@@ -127,11 +133,11 @@ describe("update_config - 更新池子配置", () => {
     // 验证 pending_admin 已设置
     const stateAfterPending = await program.account.poolState.fetch(poolState);
     assert.ok(
-      stateAfterPending.pendingAdmin !== null,
+      !stateAfterPending.pendingAdmin.equals(anchor.web3.PublicKey.default),
       "pending_admin 应该已设置"
     );
     assert.ok(
-      stateAfterPending.pendingAdmin?.equals(newAdmin.publicKey),
+      stateAfterPending.pendingAdmin.equals(newAdmin.publicKey),
       "pending_admin 应该是 newAdmin"
     );
     assert.ok(
@@ -162,7 +168,7 @@ describe("update_config - 更新池子配置", () => {
       "admin 应该与旧地址不同"
     );
     assert.ok(
-      stateAfter.pendingAdmin === null,
+      stateAfter.pendingAdmin.equals(anchor.web3.PublicKey.default),
       "pending_admin 应该已被清空"
     );
 
@@ -183,7 +189,7 @@ describe("update_config - 更新池子配置", () => {
     // 如果 currentAdmin 是 newAdmin，需要使用 newAdmin 作为签名者
     const isNewAdmin = currentAdmin.equals(newAdmin.publicKey);
     await program.methods
-      .updateConfig(null, newRecipient.publicKey, null)
+      .updateConfig(null, newRecipient.publicKey, null, null, null)
       .accounts({
         poolState: poolState,
         admin: currentAdmin,
@@ -216,7 +222,7 @@ describe("update_config - 更新池子配置", () => {
     const newShare = new anchor.BN(100);
     const isNewAdmin = currentAdmin.equals(newAdmin.publicKey);
     await program.methods
-      .updateConfig(null, null, newShare)
+      .updateConfig(null, null, newShare, null, null)
       .accounts({
         poolState: poolState,
         admin: currentAdmin,
@@ -251,7 +257,7 @@ describe("update_config - 更新池子配置", () => {
 
     try {
       await program.methods
-        .updateConfig(null, null, invalidShare)
+        .updateConfig(null, null, invalidShare, null, null)
         .accounts({
           poolState: poolState,
           admin: currentAdmin,
@@ -298,7 +304,7 @@ describe("update_config - 更新池子配置", () => {
     // 尝试用非 admin 用户更新配置（应该失败）
     try {
       await program.methods
-        .updateConfig(admin.publicKey, null, null)
+        .updateConfig(admin.publicKey, null, null, null, null)
         .accounts({
           poolState: poolState,
           admin: unauthorizedUser.publicKey,
@@ -358,7 +364,7 @@ describe("update_config - 更新池子配置", () => {
     const newShare = new anchor.BN(200);
     const isNewAdmin = currentAdmin.equals(newAdmin.publicKey);
     await program.methods
-      .updateConfig(anotherAdmin.publicKey, anotherRecipient.publicKey, newShare)
+      .updateConfig(anotherAdmin.publicKey, anotherRecipient.publicKey, newShare, null, null)
       .accounts({
         poolState: poolState,
         admin: currentAdmin,
@@ -378,7 +384,7 @@ describe("update_config - 更新池子配置", () => {
       "protocol_fee_share 应该已更新"
     );
     assert.ok(
-      stateAfterUpdate.pendingAdmin?.equals(anotherAdmin.publicKey),
+      stateAfterUpdate.pendingAdmin.equals(anotherAdmin.publicKey),
       "pending_admin 应该已设置"
     );
     assert.ok(
@@ -416,7 +422,7 @@ describe("update_config - 更新池子配置", () => {
       "protocol_fee_share 应该已更新"
     );
     assert.ok(
-      stateAfter.pendingAdmin === null,
+      stateAfter.pendingAdmin.equals(anchor.web3.PublicKey.default),
       "pending_admin 应该已被清空"
     );
 
@@ -0,0 +1,21 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { SolanaAmm } from "../target/types/solana_amm";
+
+// 注册表每页登记的池子数量，和程序中的 REGISTRY_PAGE_SIZE 一致
+export const REGISTRY_PAGE_SIZE = 8;
+
+// initialize 需要的注册表账户：新池子追加到 pool_count 所在的页
+export async function registryAccounts(program: Program<SolanaAmm>): Promise<{
+  poolRegistry: anchor.web3.PublicKey;
+  registryPage: anchor.web3.PublicKey;
+}> {
+  const [poolRegistry] = anchor.web3.PublicKey.findProgramAddressSync([Buffer.from("pool_registry")], program.programId);
+  const registry = await program.account.poolRegistry.fetchNullable(poolRegistry);
+  const pageIndex = registry ? registry.poolCount.divn(REGISTRY_PAGE_SIZE) : new anchor.BN(0);
+  const [registryPage] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("pool_registry_page"), pageIndex.toArrayLike(Buffer, "le", 8)],
+    program.programId
+  );
+  return { poolRegistry, registryPage };
+}